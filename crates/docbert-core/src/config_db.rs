@@ -13,6 +13,7 @@ use crate::{
     incremental::DocumentMetadata,
     merkle::Snapshot,
     redb_migration::{self, CONFIG_MAX_DBS},
+    saved_search::SavedSearch,
     storage_codec::{decode_bytes, encode_bytes},
     stored_json::StoredJsonValue,
 };
@@ -23,6 +24,7 @@ const DOCUMENT_METADATA_DB: &str = "document_metadata";
 const CONVERSATIONS_DB: &str = "conversations";
 const COLLECTION_MERKLE_SNAPSHOTS_DB: &str = "collection_merkle_snapshots";
 const SETTINGS_DB: &str = "settings";
+const SAVED_SEARCHES_DB: &str = "saved_searches";
 /// Per-document chunk manifest.
 ///
 /// Keyed by `doc_num_id` and storing the ordered list of chunks the
@@ -52,7 +54,7 @@ const KEY_LLM_API_KEY: &str = "llm_api_key";
 
 /// Local store for collections, settings, and document metadata.
 ///
-/// It keeps eight named LMDB databases inside one
+/// It keeps nine named LMDB databases inside one
 /// [`heed::Env`](https://docs.rs/heed):
 ///
 /// - **collections**: collection names to filesystem paths
@@ -65,6 +67,8 @@ const KEY_LLM_API_KEY: &str = "llm_api_key";
 ///   (each entry pairs a content-derived chunk id with its byte range)
 /// - **chunk_owners**: chunk id to the set of documents that contain
 ///   it — populated atomically alongside `doc_chunks`
+/// - **saved_searches**: saved search name to its query and tracked
+///   results (see [`crate::saved_search`])
 ///
 /// LMDB gives us proper cross-process readers and writers, so several
 /// `docbert mcp` / `docbert web` / CLI processes can share the same
@@ -97,6 +101,7 @@ pub struct ConfigDb {
     settings: Database<Str, Bytes>,
     doc_chunks: Database<U64<BigEndian>, Bytes>,
     chunk_owners: Database<U64<BigEndian>, Bytes>,
+    saved_searches: Database<Str, Bytes>,
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
@@ -170,6 +175,8 @@ impl ConfigDb {
         let doc_chunks = env.create_database(&mut wtxn, Some(DOC_CHUNKS_DB))?;
         let chunk_owners =
             env.create_database(&mut wtxn, Some(CHUNK_OWNERS_DB))?;
+        let saved_searches =
+            env.create_database(&mut wtxn, Some(SAVED_SEARCHES_DB))?;
         wtxn.commit()?;
         Ok(Self {
             env,
@@ -181,6 +188,7 @@ impl ConfigDb {
             settings,
             doc_chunks,
             chunk_owners,
+            saved_searches,
         })
     }
 
@@ -401,6 +409,112 @@ impl ConfigDb {
         Ok(result)
     }
 
+    // -- Saved searches --
+
+    /// Store a saved search keyed by its name, replacing any search
+    /// saved under the same name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let tmp = tempfile::tempdir().unwrap();
+    /// # let db = docbert_core::ConfigDb::open(&tmp.path().join("config.db")).unwrap();
+    /// use docbert_core::saved_search::SavedSearch;
+    /// use docbert_core::search::SearchMode;
+    ///
+    /// let saved =
+    ///     SavedSearch::new("plaid", "plaid", SearchMode::Bm25, None, 10)
+    ///         .unwrap();
+    /// db.set_saved_search(&saved).unwrap();
+    /// assert_eq!(db.get_saved_search("plaid").unwrap(), Some(saved));
+    /// ```
+    pub fn set_saved_search(&self, saved: &SavedSearch) -> Result<()> {
+        let encoded = encode_bytes(saved)?;
+        let mut wtxn = self.env.write_txn()?;
+        self.saved_searches.put(
+            &mut wtxn,
+            saved.name.as_str(),
+            encoded.as_slice(),
+        )?;
+        wtxn.commit()?;
+        Ok(())
+    }
+
+    /// Apply `update` to the saved search named `name` and store the
+    /// result within one write transaction, so a concurrent update of
+    /// the same search can't be lost in between. Returns `None` without
+    /// writing if no search has that name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let tmp = tempfile::tempdir().unwrap();
+    /// # let db = docbert_core::ConfigDb::open(&tmp.path().join("config.db")).unwrap();
+    /// use docbert_core::saved_search::SavedSearch;
+    /// use docbert_core::search::SearchMode;
+    ///
+    /// let saved =
+    ///     SavedSearch::new("plaid", "plaid", SearchMode::Bm25, None, 10)
+    ///         .unwrap();
+    /// db.set_saved_search(&saved).unwrap();
+    /// let count = db.update_saved_search("plaid", |s| {
+    ///     s.count = 20;
+    ///     s.count
+    /// });
+    /// assert_eq!(count.unwrap(), Some(20));
+    /// assert_eq!(db.get_saved_search("plaid").unwrap().unwrap().count, 20);
+    /// assert_eq!(db.update_saved_search("nope", |_| ()).unwrap(), None);
+    /// ```
+    pub fn update_saved_search<T>(
+        &self,
+        name: &str,
+        update: impl FnOnce(&mut SavedSearch) -> T,
+    ) -> Result<Option<T>> {
+        let mut wtxn = self.env.write_txn()?;
+        let Some(mut saved) = self
+            .saved_searches
+            .get(&wtxn, name)?
+            .map(decode_aligned::<SavedSearch>)
+            .transpose()?
+        else {
+            return Ok(None);
+        };
+        let updated = update(&mut saved);
+        let encoded = encode_bytes(&saved)?;
+        self.saved_searches
+            .put(&mut wtxn, name, encoded.as_slice())?;
+        wtxn.commit()?;
+        Ok(Some(updated))
+    }
+
+    /// Retrieve a saved search by name. Returns `None` if not found.
+    pub fn get_saved_search(&self, name: &str) -> Result<Option<SavedSearch>> {
+        let rtxn = self.env.read_txn()?;
+        self.saved_searches
+            .get(&rtxn, name)?
+            .map(decode_aligned)
+            .transpose()
+    }
+
+    /// Remove a saved search by name. Returns `true` if it existed.
+    pub fn remove_saved_search(&self, name: &str) -> Result<bool> {
+        let mut wtxn = self.env.write_txn()?;
+        let removed = self.saved_searches.delete(&mut wtxn, name)?;
+        wtxn.commit()?;
+        Ok(removed)
+    }
+
+    /// List all saved searches, ordered by name.
+    pub fn list_saved_searches(&self) -> Result<Vec<SavedSearch>> {
+        let rtxn = self.env.read_txn()?;
+        let mut result = Vec::new();
+        for entry in self.saved_searches.iter(&rtxn)? {
+            let (_name, bytes) = entry?;
+            result.push(decode_aligned(bytes)?);
+        }
+        Ok(result)
+    }
+
     // -- Document Metadata --
 
     /// Remove a document's metadata. Returns `true` if it existed.
@@ -1,6 +1,12 @@
-use std::path::{Path, PathBuf};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
-use crate::error::{Error, Result};
+use crate::{
+    error::{Error, Result},
+    resident_plaid::ResidentPlaid,
+};
 
 /// Root directory for docbert's on-disk state.
 ///
@@ -11,7 +17,9 @@ use crate::error::{Error, Result};
 ///
 /// `DataDir` is a thin wrapper around a [`PathBuf`]. It does not resolve
 /// default locations or create directories on its own — use
-/// [`DataDir::new`] with an already-resolved path.
+/// [`DataDir::new`] with an already-resolved path. Long-lived runtimes
+/// can attach a [`ResidentPlaid`] with [`DataDir::with_resident_plaid`]
+/// so searches through this `DataDir` share one in-memory PLAID index.
 ///
 /// # Examples
 ///
@@ -25,6 +33,7 @@ use crate::error::{Error, Result};
 #[derive(Debug, Clone)]
 pub struct DataDir {
     root: PathBuf,
+    resident_plaid: Option<Arc<ResidentPlaid>>,
 }
 
 impl DataDir {
@@ -43,7 +52,36 @@ impl DataDir {
     /// assert_eq!(dir.root(), tmp.path());
     /// ```
     pub fn new(root: impl Into<PathBuf>) -> Self {
-        Self { root: root.into() }
+        Self {
+            root: root.into(),
+            resident_plaid: None,
+        }
+    }
+
+    /// Serve PLAID loads through `resident` instead of reading
+    /// `plaid.idx` on every search.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let tmp = tempfile::tempdir().unwrap();
+    /// use std::sync::Arc;
+    /// use docbert_core::DataDir;
+    /// use docbert_core::resident_plaid::ResidentPlaid;
+    ///
+    /// let resident = Arc::new(ResidentPlaid::new());
+    /// let dir = DataDir::new(tmp.path()).with_resident_plaid(resident.clone());
+    /// assert!(Arc::ptr_eq(dir.resident_plaid().unwrap(), &resident));
+    /// ```
+    pub fn with_resident_plaid(mut self, resident: Arc<ResidentPlaid>) -> Self {
+        self.resident_plaid = Some(resident);
+        self
+    }
+
+    /// The in-memory PLAID index attached with
+    /// [`with_resident_plaid`](Self::with_resident_plaid), if any.
+    pub fn resident_plaid(&self) -> Option<&Arc<ResidentPlaid>> {
+        self.resident_plaid.as_ref()
     }
 
     /// Returns the root path of the data directory.
@@ -73,4 +73,18 @@ pub enum Error {
         "PLAID semantic index is not built yet; run `docbert sync` or `docbert rebuild` to build it"
     )]
     PlaidIndexMissing,
+
+    #[error(
+        "search index at {} predates the current {} field(s); \
+         run `docbert sync` to rebuild it",
+        path.display(),
+        missing.join(", ")
+    )]
+    SearchIndexOutdated { path: PathBuf, missing: Vec<String> },
+
+    #[error("invalid search cursor: {0}")]
+    InvalidCursor(String),
+
+    #[error("invalid pattern: {0}")]
+    InvalidPattern(#[from] regex::Error),
 }
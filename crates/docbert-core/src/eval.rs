@@ -0,0 +1,639 @@
+//! Retrieval evaluation against relevance judgments.
+//!
+//! [`evaluate`] runs every judged query through a search function and
+//! scores the ranking with nDCG@k, reciprocal rank and recall@k, timing
+//! each query along the way. Queries come from JSONL ([`parse_queries`])
+//! and judgments from a qrels file ([`parse_qrels`]), in the formats the
+//! BEIR and TREC collections ship with.
+//!
+//! [`recall_loss`] needs no judgments: it ranks each query with PLAID
+//! and with [`crate::exact`]'s full-precision scan, and measures how
+//! much of the exact ranking PLAID's approximations lose.
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Error, Result},
+    search::FinalResult,
+};
+
+/// One query to evaluate.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct EvalQuery {
+    /// Identifier the qrels refer to. BEIR files call it `_id`.
+    #[serde(alias = "_id")]
+    pub id: String,
+    /// Query text. BEIR files call it `text`.
+    #[serde(alias = "text")]
+    pub query: String,
+}
+
+/// Parse queries from JSONL, one `{"id": ..., "query": ...}` object per
+/// line. BEIR's `{"_id": ..., "text": ...}` works too; other fields are
+/// ignored and blank lines skipped.
+///
+/// # Examples
+///
+/// ```
+/// use docbert_core::eval::parse_queries;
+///
+/// let queries = parse_queries(
+///     "{\"id\": \"q1\", \"query\": \"rust ownership\"}\n\
+///      {\"_id\": \"q2\", \"text\": \"borrow checker\"}\n",
+/// )
+/// .unwrap();
+/// assert_eq!(queries[1].id, "q2");
+/// assert_eq!(queries[1].query, "borrow checker");
+/// ```
+pub fn parse_queries(jsonl: &str) -> Result<Vec<EvalQuery>> {
+    jsonl
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line).map_err(|e| {
+                Error::Config(format!("invalid query on line {}: {e}", i + 1))
+            })
+        })
+        .collect()
+}
+
+/// Graded relevance judgments: query id to judged document to grade.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Qrels {
+    judgments: HashMap<String, HashMap<String, u32>>,
+}
+
+impl Qrels {
+    /// Judgments for `query_id`, by document.
+    pub fn for_query(&self, query_id: &str) -> Option<&HashMap<String, u32>> {
+        self.judgments.get(query_id)
+    }
+
+    /// Number of queries with at least one judgment.
+    pub fn len(&self) -> usize {
+        self.judgments.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.judgments.is_empty()
+    }
+}
+
+/// Parse a qrels file.
+///
+/// Columns are separated by tabs or spaces. Three columns are read as
+/// BEIR's `query-id corpus-id score`, four as TREC's
+/// `query-id iteration doc-id relevance`. A first line whose grade is
+/// not a number is taken as a header. Grades of zero are kept as
+/// judged-irrelevant.
+///
+/// Document ids are matched against results by `collection:path`,
+/// `path`, short doc id, or file stem (see [`evaluate`]).
+///
+/// # Examples
+///
+/// ```
+/// use docbert_core::eval::parse_qrels;
+///
+/// let qrels =
+///     parse_qrels("query-id\tcorpus-id\tscore\nq1\tnotes:rust.md\t2\n")
+///         .unwrap();
+/// assert_eq!(qrels.for_query("q1").unwrap()["notes:rust.md"], 2);
+/// ```
+pub fn parse_qrels(tsv: &str) -> Result<Qrels> {
+    let mut qrels = Qrels::default();
+    for (i, line) in tsv.lines().enumerate() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let (query_id, doc_id, grade) = match fields.as_slice() {
+            [] => continue,
+            [query_id, doc_id, grade] => (query_id, doc_id, grade),
+            [query_id, _, doc_id, grade] => (query_id, doc_id, grade),
+            _ => {
+                return Err(Error::Config(format!(
+                    "invalid qrels line {}: expected 3 or 4 columns",
+                    i + 1
+                )));
+            }
+        };
+        let grade = match grade.parse::<u32>() {
+            Ok(grade) => grade,
+            Err(_) if i == 0 => continue,
+            Err(_) => {
+                return Err(Error::Config(format!(
+                    "invalid qrels line {}: grade {grade:?} is not a non-negative integer",
+                    i + 1
+                )));
+            }
+        };
+        qrels
+            .judgments
+            .entry(query_id.to_string())
+            .or_default()
+            .insert(doc_id.to_string(), grade);
+    }
+    Ok(qrels)
+}
+
+/// Normalized discounted cumulative gain of the first `k` grades, with
+/// linear gain and a `log2(rank + 1)` discount.
+///
+/// `grades` are the retrieved documents' grades in rank order (zero for
+/// unjudged), `judged` every grade judged for the query. Returns `0.0`
+/// when nothing is relevant.
+///
+/// # Examples
+///
+/// ```
+/// use docbert_core::eval::ndcg_at_k;
+///
+/// assert_eq!(ndcg_at_k(&[1, 0], &[1], 10), 1.0);
+/// assert!(ndcg_at_k(&[0, 1], &[1], 10) < 1.0);
+/// ```
+pub fn ndcg_at_k(grades: &[u32], judged: &[u32], k: usize) -> f64 {
+    let dcg = |grades: &mut dyn Iterator<Item = u32>| -> f64 {
+        grades
+            .take(k)
+            .enumerate()
+            .map(|(i, grade)| f64::from(grade) / ((i + 2) as f64).log2())
+            .sum()
+    };
+    let mut ideal = judged.to_vec();
+    ideal.sort_unstable_by(|a, b| b.cmp(a));
+    let ideal = dcg(&mut ideal.into_iter());
+    if ideal == 0.0 {
+        return 0.0;
+    }
+    dcg(&mut grades.iter().copied()) / ideal
+}
+
+/// `1 / rank` of the first relevant document within the first `k`, or
+/// `0.0` when none is.
+pub fn reciprocal_rank(grades: &[u32], k: usize) -> f64 {
+    grades
+        .iter()
+        .take(k)
+        .position(|&grade| grade > 0)
+        .map_or(0.0, |i| 1.0 / (i + 1) as f64)
+}
+
+/// Share of the `relevant` documents found within the first `k`
+/// grades, or `0.0` when there are none to find.
+pub fn recall_at_k(grades: &[u32], relevant: usize, k: usize) -> f64 {
+    if relevant == 0 {
+        return 0.0;
+    }
+    let found = grades.iter().take(k).filter(|&&grade| grade > 0).count();
+    found as f64 / relevant as f64
+}
+
+/// Scores of one evaluated query.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QueryEval {
+    pub id: String,
+    pub query: String,
+    pub ndcg: f64,
+    pub reciprocal_rank: f64,
+    pub recall: f64,
+    /// Judged-relevant documents for the query.
+    pub relevant: usize,
+    /// Of those, how many the first `k` results found.
+    pub found: usize,
+    pub latency_ms: f64,
+}
+
+/// Search latency over the evaluated queries, in milliseconds.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct LatencySummary {
+    pub mean_ms: f64,
+    pub p50_ms: f64,
+    pub p90_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+impl LatencySummary {
+    /// Summarize `latencies` with nearest-rank percentiles.
+    pub fn from_latencies(latencies: &[Duration]) -> Self {
+        if latencies.is_empty() {
+            return Self::default();
+        }
+        let mut ms: Vec<f64> =
+            latencies.iter().map(|d| d.as_secs_f64() * 1000.0).collect();
+        ms.sort_by(f64::total_cmp);
+        let percentile = |p: f64| {
+            let rank = (p / 100.0 * ms.len() as f64).ceil() as usize;
+            ms[rank.clamp(1, ms.len()) - 1]
+        };
+        Self {
+            mean_ms: ms.iter().sum::<f64>() / ms.len() as f64,
+            p50_ms: percentile(50.0),
+            p90_ms: percentile(90.0),
+            p95_ms: percentile(95.0),
+            p99_ms: percentile(99.0),
+            max_ms: ms[ms.len() - 1],
+        }
+    }
+}
+
+/// Aggregate scores of an evaluation run.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EvalReport {
+    /// Rank cutoff of every metric.
+    pub k: usize,
+    /// Queries with at least one relevant judgment, all searched.
+    pub evaluated: usize,
+    /// Ids of queries skipped for having no relevant judgment.
+    pub skipped: Vec<String>,
+    /// Mean nDCG@k.
+    pub ndcg: f64,
+    /// Mean reciprocal rank within the first `k`.
+    pub mrr: f64,
+    /// Mean recall@k.
+    pub recall: f64,
+    pub latency: LatencySummary,
+    pub queries: Vec<QueryEval>,
+    /// PLAID's recall against exact search, when it was measured.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plaid_recall_loss: Option<RecallLoss>,
+}
+
+/// How much of an exact search's ranking an approximate one misses.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RecallLoss {
+    /// Queries compared. Those the exact search finds nothing for are
+    /// left out.
+    pub queries: usize,
+    /// Mean share of the exact top `k` missing from the approximate
+    /// top `k`.
+    pub mean: f64,
+    /// The largest share missed by a single query.
+    pub max: f64,
+}
+
+/// The ways a qrels file may name `result`: `collection:path`, `path`,
+/// the short doc id (with or without `#`), and the path's file stem.
+fn result_keys(result: &FinalResult) -> Vec<String> {
+    let mut keys = vec![
+        format!("{}:{}", result.collection, result.path),
+        result.path.clone(),
+        result.doc_id.clone(),
+        result.doc_id.trim_start_matches('#').to_string(),
+    ];
+    if let Some(stem) = Path::new(&result.path)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+    {
+        keys.push(stem.to_string());
+    }
+    keys
+}
+
+/// Grade each result against `judged`, in rank order.
+///
+/// A judged document counts once: later results that resolve to the
+/// same judgment (say, two files sharing a stem) grade as unjudged.
+fn grade_results(
+    results: &[FinalResult],
+    judged: &HashMap<String, u32>,
+) -> Vec<u32> {
+    let mut used = HashSet::new();
+    results
+        .iter()
+        .map(|result| {
+            result_keys(result)
+                .into_iter()
+                .find(|key| judged.contains_key(key))
+                .filter(|key| used.insert(key.clone()))
+                .map_or(0, |key| judged[&key])
+        })
+        .collect()
+}
+
+/// Run every query with a relevant judgment through `search` and score
+/// its results at cutoff `k`.
+///
+/// `search` should return at least `k` results when it can. Each call
+/// is timed for the latency summary. Queries without a relevant
+/// judgment are listed in [`EvalReport::skipped`] and never searched.
+///
+/// # Examples
+///
+/// ```
+/// use docbert_core::eval::{evaluate, parse_qrels, EvalQuery};
+///
+/// let queries = vec![EvalQuery {
+///     id: "q1".to_string(),
+///     query: "rust".to_string(),
+/// }];
+/// let qrels = parse_qrels("q1 notes:rust.md 1\n").unwrap();
+///
+/// // A search that finds nothing scores zero everywhere.
+/// let report = evaluate(&queries, &qrels, 10, |_| Ok(Vec::new())).unwrap();
+/// assert_eq!(report.evaluated, 1);
+/// assert_eq!(report.ndcg, 0.0);
+/// ```
+pub fn evaluate(
+    queries: &[EvalQuery],
+    qrels: &Qrels,
+    k: usize,
+    mut search: impl FnMut(&EvalQuery) -> Result<Vec<FinalResult>>,
+) -> Result<EvalReport> {
+    let mut skipped = Vec::new();
+    let mut evaluated = Vec::new();
+    let mut latencies = Vec::new();
+
+    for query in queries {
+        let Some(judged) = qrels
+            .for_query(&query.id)
+            .filter(|judged| judged.values().any(|&grade| grade > 0))
+        else {
+            skipped.push(query.id.clone());
+            continue;
+        };
+
+        let started = Instant::now();
+        let results = search(query)?;
+        let latency = started.elapsed();
+        latencies.push(latency);
+
+        let grades = grade_results(&results, judged);
+        let judged_grades: Vec<u32> = judged.values().copied().collect();
+        let relevant = judged_grades.iter().filter(|&&g| g > 0).count();
+        evaluated.push(QueryEval {
+            id: query.id.clone(),
+            query: query.query.clone(),
+            ndcg: ndcg_at_k(&grades, &judged_grades, k),
+            reciprocal_rank: reciprocal_rank(&grades, k),
+            recall: recall_at_k(&grades, relevant, k),
+            relevant,
+            found: grades.iter().take(k).filter(|&&g| g > 0).count(),
+            latency_ms: latency.as_secs_f64() * 1000.0,
+        });
+    }
+
+    let mean = |metric: fn(&QueryEval) -> f64| {
+        if evaluated.is_empty() {
+            0.0
+        } else {
+            evaluated.iter().map(metric).sum::<f64>() / evaluated.len() as f64
+        }
+    };
+    Ok(EvalReport {
+        k,
+        evaluated: evaluated.len(),
+        skipped,
+        ndcg: mean(|q| q.ndcg),
+        mrr: mean(|q| q.reciprocal_rank),
+        recall: mean(|q| q.recall),
+        latency: LatencySummary::from_latencies(&latencies),
+        queries: evaluated,
+        plaid_recall_loss: None,
+    })
+}
+
+/// Share of the documents in `exact`'s first `k` results that are
+/// missing from `approximate`'s first `k`, or `None` when `exact` is
+/// empty.
+pub fn missed_at_k(
+    approximate: &[FinalResult],
+    exact: &[FinalResult],
+    k: usize,
+) -> Option<f64> {
+    let expected: HashSet<u64> =
+        exact.iter().take(k).map(|r| r.doc_num_id).collect();
+    if expected.is_empty() {
+        return None;
+    }
+    let found = approximate
+        .iter()
+        .take(k)
+        .filter(|r| expected.contains(&r.doc_num_id))
+        .map(|r| r.doc_num_id)
+        .collect::<HashSet<u64>>()
+        .len();
+    Some(1.0 - found as f64 / expected.len() as f64)
+}
+
+/// Rank every query twice with `search`, once approximately
+/// (`exact == false`) and once exactly, and summarize the share of the
+/// exact top `k` the approximate ranking misses.
+///
+/// # Examples
+///
+/// ```
+/// use docbert_core::eval::{EvalQuery, recall_loss};
+///
+/// let queries = vec![EvalQuery {
+///     id: "q1".to_string(),
+///     query: "rust".to_string(),
+/// }];
+/// // Nothing found exactly means nothing to compare against.
+/// let loss = recall_loss(&queries, 10, |_, _| Ok(Vec::new())).unwrap();
+/// assert_eq!(loss.queries, 0);
+/// ```
+pub fn recall_loss(
+    queries: &[EvalQuery],
+    k: usize,
+    mut search: impl FnMut(&EvalQuery, bool) -> Result<Vec<FinalResult>>,
+) -> Result<RecallLoss> {
+    let mut missed = Vec::new();
+    for query in queries {
+        let exact = search(query, true)?;
+        if exact.is_empty() {
+            continue;
+        }
+        let approximate = search(query, false)?;
+        missed.extend(missed_at_k(&approximate, &exact, k));
+    }
+    if missed.is_empty() {
+        return Ok(RecallLoss::default());
+    }
+    Ok(RecallLoss {
+        queries: missed.len(),
+        mean: missed.iter().sum::<f64>() / missed.len() as f64,
+        max: missed.iter().copied().fold(0.0, f64::max),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hit(collection: &str, path: &str) -> FinalResult {
+        FinalResult {
+            rank: 0,
+            score: 1.0,
+            doc_id: "#abc123".to_string(),
+            doc_num_id: 1,
+            collection: collection.to_string(),
+            path: path.to_string(),
+            title: String::new(),
+            best_chunk_doc_id: None,
+            rerank_score: None,
+            passages: Vec::new(),
+            token_matches: Vec::new(),
+            explain: None,
+        }
+    }
+
+    #[test]
+    fn ndcg_matches_hand_computed_values() {
+        // DCG = 2/log2(2) + 0 + 1/log2(4) = 2.5
+        // IDCG = 2/log2(2) + 1/log2(3) = 2.6309...
+        let ndcg = ndcg_at_k(&[2, 0, 1], &[2, 1, 0], 10);
+        assert!((ndcg - 2.5 / (2.0 + 1.0 / 3f64.log2())).abs() < 1e-9);
+        // Cut off before the second relevant document.
+        let cut = ndcg_at_k(&[2, 0, 1], &[2, 1], 2);
+        assert!((cut - 2.0 / (2.0 + 1.0 / 3f64.log2())).abs() < 1e-9);
+        assert_eq!(ndcg_at_k(&[0, 0], &[0], 10), 0.0);
+    }
+
+    #[test]
+    fn reciprocal_rank_and_recall_respect_the_cutoff() {
+        assert_eq!(reciprocal_rank(&[0, 0, 3], 10), 1.0 / 3.0);
+        assert_eq!(reciprocal_rank(&[0, 0, 3], 2), 0.0);
+        assert_eq!(recall_at_k(&[1, 0, 1], 4, 10), 0.5);
+        assert_eq!(recall_at_k(&[1, 0, 1], 4, 1), 0.25);
+        assert_eq!(recall_at_k(&[1], 0, 10), 0.0);
+    }
+
+    #[test]
+    fn latency_percentiles_use_nearest_rank() {
+        let latencies: Vec<Duration> =
+            (1..=10).map(Duration::from_millis).collect();
+        let summary = LatencySummary::from_latencies(&latencies);
+        assert_eq!(summary.p50_ms, 5.0);
+        assert_eq!(summary.p90_ms, 9.0);
+        assert_eq!(summary.p99_ms, 10.0);
+        assert_eq!(summary.max_ms, 10.0);
+        assert!((summary.mean_ms - 5.5).abs() < 1e-9);
+        assert_eq!(
+            LatencySummary::from_latencies(&[]),
+            LatencySummary::default()
+        );
+    }
+
+    #[test]
+    fn qrels_parse_beir_and_trec_layouts() {
+        let beir =
+            parse_qrels("query-id\tcorpus-id\tscore\nq1\ta\t1\n").unwrap();
+        let trec = parse_qrels("q1 0 a 1\nq1 0 b 0\n\n").unwrap();
+        assert_eq!(beir.for_query("q1").unwrap()["a"], 1);
+        assert_eq!(trec.for_query("q1").unwrap()["b"], 0);
+        assert_eq!(trec.len(), 1);
+
+        assert!(parse_qrels("q1 a\n").is_err());
+        assert!(parse_qrels("q1 a 1\nq2 b high\n").is_err());
+        assert!(parse_queries("{\"id\": \"q1\"}\n").is_err());
+    }
+
+    #[test]
+    fn results_match_judgments_by_any_document_name_once() {
+        let judged: HashMap<String, u32> = [
+            ("notes:rust.md".to_string(), 2),
+            ("guide".to_string(), 1),
+            ("abc123".to_string(), 3),
+        ]
+        .into();
+        let mut by_id = hit("docs", "other.md");
+        by_id.doc_id = "#abc123".to_string();
+        let mut stem_clash = hit("notes", "guide.txt");
+        stem_clash.doc_id = "#fff000".to_string();
+        let mut rust = hit("notes", "rust.md");
+        rust.doc_id = "#ddd000".to_string();
+        let mut guide = hit("docs", "guide.md");
+        guide.doc_id = "#eee000".to_string();
+
+        let grades = grade_results(&[rust, guide, stem_clash, by_id], &judged);
+        assert_eq!(grades, [2, 1, 0, 3]);
+    }
+
+    #[test]
+    fn evaluate_skips_unjudged_queries_and_averages_the_rest() {
+        let queries = vec![
+            EvalQuery {
+                id: "q1".to_string(),
+                query: "rust".to_string(),
+            },
+            EvalQuery {
+                id: "q2".to_string(),
+                query: "pasta".to_string(),
+            },
+            EvalQuery {
+                id: "q3".to_string(),
+                query: "nothing judged".to_string(),
+            },
+        ];
+        let qrels =
+            parse_qrels("q1 notes:rust.md 1\nq2 notes:pasta.md 1\nq3 x 0\n")
+                .unwrap();
+
+        let mut searched = Vec::new();
+        let report = evaluate(&queries, &qrels, 10, |query| {
+            searched.push(query.id.clone());
+            Ok(vec![hit("notes", "rust.md"), hit("notes", "pasta.md")])
+        })
+        .unwrap();
+
+        assert_eq!(searched, ["q1", "q2"]);
+        assert_eq!(report.skipped, ["q3"]);
+        assert_eq!(report.evaluated, 2);
+        assert!((report.mrr - 0.75).abs() < 1e-9);
+        assert_eq!(report.recall, 1.0);
+        assert_eq!(report.queries[1].found, 1);
+    }
+
+    #[test]
+    fn recall_loss_compares_the_approximate_top_k_with_the_exact_one() {
+        let ranked = |ids: &[u64]| -> Vec<FinalResult> {
+            ids.iter()
+                .map(|&id| FinalResult {
+                    doc_num_id: id,
+                    ..hit("notes", &format!("{id}.md"))
+                })
+                .collect()
+        };
+        assert_eq!(
+            missed_at_k(&ranked(&[1, 2]), &ranked(&[2, 1]), 2),
+            Some(0.0)
+        );
+        assert_eq!(
+            missed_at_k(&ranked(&[1, 3]), &ranked(&[1, 2]), 2),
+            Some(0.5)
+        );
+        // Only the first `k` of each side count.
+        assert_eq!(
+            missed_at_k(&ranked(&[3, 1]), &ranked(&[1, 2]), 1),
+            Some(1.0)
+        );
+        assert_eq!(missed_at_k(&ranked(&[1]), &[], 10), None);
+
+        let queries: Vec<EvalQuery> = ["a", "b", "c"]
+            .iter()
+            .map(|id| EvalQuery {
+                id: id.to_string(),
+                query: id.to_string(),
+            })
+            .collect();
+        let loss = recall_loss(&queries, 2, |query, exact| {
+            Ok(match (query.id.as_str(), exact) {
+                ("a", _) => ranked(&[1, 2]),
+                ("b", true) => ranked(&[1, 2]),
+                ("b", false) => ranked(&[1, 3]),
+                _ => Vec::new(),
+            })
+        })
+        .unwrap();
+        assert_eq!(loss.queries, 2);
+        assert!((loss.mean - 0.25).abs() < 1e-9);
+        assert_eq!(loss.max, 0.5);
+    }
+}
@@ -0,0 +1,295 @@
+//! Exact semantic search: full-precision MaxSim against every stored
+//! embedding matrix.
+//!
+//! PLAID scores compressed tokens (a centroid plus a quantized residual)
+//! and prunes candidates by centroid score, so it can miss chunks that
+//! an exact scan ranks highly. On a corpus of a few thousand chunks
+//! that buys no real latency, so [`use_exact`] sends small indexes to
+//! [`search`], which scores every matrix in the [`EmbeddingDb`]
+//! instead. The same scan is the ground truth `docbert eval` measures
+//! PLAID's recall loss against.
+//!
+//! Matrices are loaded [`EXACT_BATCH_CHUNKS`] at a time and the chunks
+//! of a batch are scored in parallel with the runtime-selected SIMD
+//! kernel of [`MaxSim::dense`], the scorer PLAID uses for its final
+//! stage. Only the best results survive from one batch to the next, so
+//! a scan of a large corpus holds one batch plus `top_k` results.
+
+use std::{cmp::Ordering, collections::HashSet};
+
+use docbert_plaid::simd::{Kernel, MaxSim};
+use rayon::prelude::*;
+
+use crate::{
+    config_db::ConfigDb,
+    embedding_db::{EmbeddingDb, EmbeddingMatrix},
+    error::{Error, Result},
+    plaid::{PlaidQueryParams, PlaidResult},
+};
+
+/// Indexes with at most this many chunks are searched exactly unless
+/// [`PlaidQueryParams::exact`] says otherwise.
+pub const EXACT_SEARCH_CHUNK_LIMIT: usize = 4096;
+
+/// Chunks whose matrices are loaded and scored together.
+const EXACT_BATCH_CHUNKS: usize = 256;
+
+/// Whether a semantic search over an index of `num_chunks` chunks
+/// should use [`search`] rather than PLAID.
+///
+/// `params.exact` decides when set; otherwise indexes up to
+/// [`EXACT_SEARCH_CHUNK_LIMIT`] chunks are searched exactly.
+///
+/// # Examples
+///
+/// ```
+/// use docbert_core::exact::{EXACT_SEARCH_CHUNK_LIMIT, use_exact};
+/// use docbert_core::plaid::PlaidQueryParams;
+///
+/// let auto = PlaidQueryParams::default();
+/// assert!(use_exact(&auto, 100));
+/// assert!(!use_exact(&auto, EXACT_SEARCH_CHUNK_LIMIT + 1));
+///
+/// let plaid_only = PlaidQueryParams {
+///     exact: Some(false),
+///     ..Default::default()
+/// };
+/// assert!(!use_exact(&plaid_only, 100));
+/// ```
+pub fn use_exact(params: &PlaidQueryParams, num_chunks: usize) -> bool {
+    params
+        .exact
+        .unwrap_or(num_chunks <= EXACT_SEARCH_CHUNK_LIMIT)
+}
+
+/// The chunk ids of the documents in `doc_num_ids`, from their chunk
+/// manifests. Documents without one contribute nothing.
+///
+/// # Errors
+///
+/// Returns an error if reading `config_db` fails.
+pub fn document_chunks(
+    config_db: &ConfigDb,
+    doc_num_ids: impl IntoIterator<Item = u64>,
+) -> Result<Vec<u64>> {
+    let mut chunks = HashSet::new();
+    for doc_num_id in doc_num_ids {
+        if let Some(manifest) = config_db.get_doc_chunks(doc_num_id)? {
+            chunks.extend(manifest.iter().map(|entry| entry.chunk_doc_id));
+        }
+    }
+    let mut chunks: Vec<u64> = chunks.into_iter().collect();
+    chunks.sort_unstable();
+    Ok(chunks)
+}
+
+/// Score a flat, row-major `[n_tokens * dim]` query against the stored
+/// matrices and return the `top_k` best chunks, best first.
+///
+/// Only the matrices of `chunk_ids` are scored when it is given, every
+/// stored matrix otherwise. Chunks without a stored matrix are skipped.
+/// Also returns how many matrices were scored.
+///
+/// # Errors
+///
+/// Returns [`Error::Config`] if the query isn't a whole number of
+/// `dim`-wide tokens or a stored matrix has another dimension, and an
+/// error if reading `embedding_db` fails.
+///
+/// # Examples
+///
+/// ```
+/// # let tmp = tempfile::tempdir().unwrap();
+/// use docbert_core::{EmbeddingDb, exact};
+///
+/// let db = EmbeddingDb::open(&tmp.path().join("emb.db")).unwrap();
+/// db.store(1, 2, 2, &[1.0, 0.0, 0.0, 1.0]).unwrap();
+/// db.store(2, 1, 2, &[0.5, 0.5]).unwrap();
+///
+/// let (results, scored) =
+///     exact::search(&db, &[1.0, 0.0, 0.0, 1.0], 2, 10, None).unwrap();
+/// assert_eq!(scored, 2);
+/// assert_eq!(results[0].doc_id, 1);
+/// assert_eq!(results[0].score, 2.0);
+/// ```
+pub fn search(
+    embedding_db: &EmbeddingDb,
+    query_flat: &[f32],
+    dim: usize,
+    top_k: usize,
+    chunk_ids: Option<&[u64]>,
+) -> Result<(Vec<PlaidResult>, usize)> {
+    if query_flat.is_empty() || top_k == 0 {
+        return Ok((Vec::new(), 0));
+    }
+    if dim == 0 || !query_flat.len().is_multiple_of(dim) {
+        return Err(Error::Config(format!(
+            "exact query of {} values is not a whole number of {dim}-dim tokens",
+            query_flat.len(),
+        )));
+    }
+    let kernel = Kernel::detect();
+
+    let all_ids;
+    let chunk_ids = match chunk_ids {
+        Some(ids) => ids,
+        None => {
+            all_ids = embedding_db.list_ids()?;
+            &all_ids
+        }
+    };
+
+    let mut results = Vec::new();
+    let mut scored = 0;
+    for ids in chunk_ids.chunks(EXACT_BATCH_CHUNKS) {
+        let batch: Vec<(u64, EmbeddingMatrix)> = embedding_db
+            .batch_load(ids)?
+            .into_iter()
+            .filter_map(|(id, matrix)| Some((id, matrix?)))
+            .filter(|(_, matrix)| matrix.num_tokens > 0)
+            .collect();
+        scored += batch.len();
+        results.extend(score_batch(kernel, query_flat, &batch, dim)?);
+        if results.len() > top_k {
+            results.select_nth_unstable_by(top_k, best_first);
+            results.truncate(top_k);
+        }
+    }
+
+    results.sort_by(best_first);
+    Ok((results, scored))
+}
+
+/// Higher score first, then lower chunk id.
+fn best_first(a: &PlaidResult, b: &PlaidResult) -> Ordering {
+    b.score.total_cmp(&a.score).then(a.doc_id.cmp(&b.doc_id))
+}
+
+/// MaxSim of the flat `[n_query_tokens * dim]` `query` against each
+/// matrix in `batch`, one scorer per rayon worker.
+fn score_batch(
+    kernel: Kernel,
+    query: &[f32],
+    batch: &[(u64, EmbeddingMatrix)],
+    dim: usize,
+) -> Result<Vec<PlaidResult>> {
+    if let Some((chunk_id, matrix)) = batch
+        .iter()
+        .find(|(_, matrix)| matrix.dimension as usize != dim)
+    {
+        return Err(Error::Config(format!(
+            "query has dim {dim} but chunk {chunk_id} has dim {}",
+            matrix.dimension,
+        )));
+    }
+    Ok(batch
+        .par_iter()
+        .map_init(
+            || MaxSim::dense(kernel, query, dim),
+            |maxsim, (chunk_id, matrix)| PlaidResult {
+                doc_id: *chunk_id,
+                score: maxsim.score_dense(&matrix.data),
+            },
+        )
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_db() -> (tempfile::TempDir, EmbeddingDb) {
+        let tmp = tempfile::tempdir().unwrap();
+        let db = EmbeddingDb::open(&tmp.path().join("embeddings.db")).unwrap();
+        (tmp, db)
+    }
+
+    /// Scalar MaxSim, the reference the SIMD scorer must agree with.
+    fn reference_maxsim(query: &[f32], document: &[f32], dim: usize) -> f32 {
+        query
+            .chunks_exact(dim)
+            .map(|q| {
+                document
+                    .chunks_exact(dim)
+                    .map(|d| q.iter().zip(d).map(|(a, b)| a * b).sum::<f32>())
+                    .fold(f32::NEG_INFINITY, f32::max)
+            })
+            .sum()
+    }
+
+    #[test]
+    fn search_matches_scalar_maxsim_across_batches() {
+        let (_tmp, db) = test_db();
+        let dim = 4;
+        let mut documents = Vec::new();
+        // More chunks than one batch, with varying token counts.
+        for id in 0..(EXACT_BATCH_CHUNKS as u64 + 40) {
+            let n_tokens = 1 + (id % 5) as usize;
+            let data: Vec<f32> = (0..n_tokens * dim)
+                .map(|i| ((id as usize * 31 + i * 7) % 13) as f32 / 13.0 - 0.5)
+                .collect();
+            db.store(id, n_tokens as u32, dim as u32, &data).unwrap();
+            documents.push((id, data));
+        }
+        let query = [0.3, -0.2, 0.9, 0.1, -0.4, 0.8, 0.0, 0.5];
+
+        let (results, scored) =
+            search(&db, &query, dim, documents.len(), None).unwrap();
+        assert_eq!(scored, documents.len());
+        assert_eq!(results.len(), documents.len());
+        for result in &results {
+            let (_, data) = &documents[result.doc_id as usize];
+            let expected = reference_maxsim(&query, data, dim);
+            assert!(
+                (result.score - expected).abs() < 1e-5,
+                "chunk {}: {} != {expected}",
+                result.doc_id,
+                result.score,
+            );
+        }
+        assert!(results.windows(2).all(|w| w[0].score >= w[1].score));
+    }
+
+    #[test]
+    fn search_keeps_the_best_chunks_across_batches() {
+        let (_tmp, db) = test_db();
+        let n_chunks = 3 * EXACT_BATCH_CHUNKS as u64;
+        // Scores rise towards the middle batch, and ties break by id.
+        for id in 0..n_chunks {
+            let score = (id.min(n_chunks - id) / 2) as f32;
+            db.store(id, 1, 2, &[score, 0.0]).unwrap();
+        }
+
+        let (all, _) =
+            search(&db, &[1.0, 0.0], 2, n_chunks as usize, None).unwrap();
+        let (top, scored) = search(&db, &[1.0, 0.0], 2, 5, None).unwrap();
+        assert_eq!(scored, n_chunks as usize);
+        assert_eq!(top, all[..5]);
+    }
+
+    #[test]
+    fn search_scores_only_the_given_chunks_and_truncates() {
+        let (_tmp, db) = test_db();
+        db.store(1, 1, 2, &[1.0, 0.0]).unwrap();
+        db.store(2, 1, 2, &[0.9, 0.0]).unwrap();
+        db.store(3, 1, 2, &[0.1, 0.0]).unwrap();
+
+        let (results, scored) =
+            search(&db, &[1.0, 0.0], 2, 1, Some(&[2, 3, 99])).unwrap();
+        assert_eq!(scored, 2);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].doc_id, 2);
+    }
+
+    #[test]
+    fn search_rejects_mismatched_dimensions() {
+        let (_tmp, db) = test_db();
+        db.store(1, 1, 3, &[1.0, 0.0, 0.0]).unwrap();
+
+        assert!(search(&db, &[1.0, 0.0, 0.0], 2, 10, None).is_err());
+        assert!(search(&db, &[1.0, 0.0], 2, 10, None).is_err());
+        let (results, scored) = search(&db, &[], 2, 10, None).unwrap();
+        assert!(results.is_empty());
+        assert_eq!(scored, 0);
+    }
+}
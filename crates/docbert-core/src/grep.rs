@@ -0,0 +1,479 @@
+//! Exact line-level search over the registered collections.
+//!
+//! Unlike [`search`](crate::search), [`run`] ranks nothing: it walks every
+//! collection with [`walker`], reads each file the way `docbert get` does
+//! (so PDFs are searched through their extracted text), and returns every
+//! line the pattern matches in collection, path and line order. Patterns
+//! are matched one line at a time.
+
+use std::{collections::BTreeMap, path::Path};
+
+use regex::{Regex, RegexBuilder};
+use serde::Serialize;
+
+use crate::{
+    ConfigDb,
+    DocumentId,
+    SearchIndex,
+    error::{Error, Result},
+    preparation,
+    walker,
+};
+
+/// Lines of context [`GrepParams::context`] defaults to on the CLI, web
+/// API and MCP server.
+pub const DEFAULT_CONTEXT_LINES: usize = 2;
+
+/// Matches [`GrepParams::max_matches`] defaults to on the CLI, web API
+/// and MCP server.
+pub const DEFAULT_MAX_MATCHES: usize = 200;
+
+/// What [`run`] looks for and where.
+#[derive(Debug, Clone)]
+pub struct GrepParams {
+    /// Regular expression, or a literal string with `fixed_strings`.
+    pub pattern: String,
+    /// Match `pattern` literally instead of as a regular expression.
+    pub fixed_strings: bool,
+    /// Match regardless of case.
+    pub ignore_case: bool,
+    /// Only search this collection.
+    pub collection: Option<String>,
+    /// Lines of context kept before and after each matching line.
+    pub context: usize,
+    /// Stop after this many matching lines.
+    pub max_matches: usize,
+    /// Skip indexed files the Tantivy index says cannot contain a literal
+    /// pattern. Ignored for patterns that aren't literal.
+    pub prefilter: bool,
+}
+
+/// One matching line.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GrepMatch {
+    /// Collection the file belongs to.
+    pub collection: String,
+    /// Path relative to the collection root.
+    pub path: String,
+    /// 1-based line number, as `docbert get` numbers lines.
+    pub line_number: usize,
+    /// 1-based byte column where the first match on the line starts.
+    pub column: usize,
+    /// The matching line, without its line ending.
+    pub line: String,
+    /// Up to [`GrepParams::context`] lines before the match, in order.
+    pub before: Vec<String>,
+    /// Up to [`GrepParams::context`] lines after the match, in order.
+    pub after: Vec<String>,
+}
+
+/// Every match [`run`] found, with what it took to find them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GrepOutcome {
+    /// Matching lines in collection, path and line order.
+    pub matches: Vec<GrepMatch>,
+    /// Files that were read and searched.
+    pub files_searched: usize,
+    /// Unchanged indexed files the prefilter ruled out without reading.
+    pub files_skipped: usize,
+    /// Whether more matches followed the last one returned.
+    pub truncated: bool,
+}
+
+/// Compile the pattern `params` describe.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidPattern`] when `pattern` is not a valid
+/// regular expression.
+///
+/// # Examples
+///
+/// ```
+/// use docbert_core::grep::{GrepParams, compile};
+///
+/// let params = GrepParams {
+///     pattern: "a.b".to_string(),
+///     fixed_strings: true,
+///     ignore_case: true,
+///     collection: None,
+///     context: 0,
+///     max_matches: 10,
+///     prefilter: false,
+/// };
+/// let regex = compile(&params).unwrap();
+/// assert!(regex.is_match("see A.B"));
+/// assert!(!regex.is_match("axb"));
+/// ```
+pub fn compile(params: &GrepParams) -> Result<Regex> {
+    let pattern = if params.fixed_strings {
+        regex::escape(&params.pattern)
+    } else {
+        params.pattern.clone()
+    };
+    Ok(RegexBuilder::new(&pattern)
+        .case_insensitive(params.ignore_case)
+        .build()?)
+}
+
+/// The literal text every match contains, when the pattern has one.
+fn literal(params: &GrepParams) -> Option<&str> {
+    (params.fixed_strings || regex::escape(&params.pattern) == params.pattern)
+        .then_some(params.pattern.as_str())
+}
+
+/// Search the files of every registered collection, or of
+/// `params.collection`, for lines matching the pattern.
+///
+/// Files that can't be read are skipped. With `params.prefilter` and a
+/// literal pattern, files the index holds at their current modification
+/// time are only read when their indexed text could contain the
+/// literal; matches only the index doesn't see, such as ones inside YAML
+/// frontmatter, are then missed.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidPattern`] for an invalid regular expression
+/// and [`Error::NotFound`] when `params.collection` isn't registered.
+pub fn run(
+    params: &GrepParams,
+    config_db: &ConfigDb,
+    search_index: &SearchIndex,
+) -> Result<GrepOutcome> {
+    let regex = compile(params)?;
+    let mut collections = config_db.list_collections()?;
+    if let Some(name) = &params.collection {
+        collections.retain(|(collection, _)| collection == name);
+        if collections.is_empty() {
+            return Err(Error::NotFound {
+                kind: "collection",
+                name: name.clone(),
+            });
+        }
+    }
+    collections.sort();
+
+    let excluded = match literal(params).filter(|_| params.prefilter) {
+        Some(literal) => search_index.literal_exclusions(literal)?,
+        None => None,
+    };
+
+    let mut outcome = GrepOutcome::default();
+    for (collection, root) in collections {
+        let files = match walker::discover_files(Path::new(&root)) {
+            Ok(files) => files,
+            Err(err) => {
+                tracing::warn!(%collection, %err, "grep skipped collection");
+                continue;
+            }
+        };
+        for file in files {
+            let path = file.relative_path.to_string_lossy().to_string();
+            if let Some(excluded) = &excluded {
+                let did = DocumentId::new(&collection, &path);
+                if excluded.get(&did.numeric) == Some(&file.mtime) {
+                    outcome.files_skipped += 1;
+                    continue;
+                }
+            }
+            let content = match preparation::load_preview_content(
+                &file.relative_path,
+                &file.absolute_path,
+            ) {
+                Ok(content) => content,
+                Err(err) => {
+                    tracing::debug!(
+                        %collection, %path, %err, "grep skipped file"
+                    );
+                    continue;
+                }
+            };
+            outcome.files_searched += 1;
+            if grep_content(
+                &regex,
+                &content,
+                &collection,
+                &path,
+                params,
+                &mut outcome.matches,
+            ) {
+                outcome.truncated = true;
+                return Ok(outcome);
+            }
+        }
+    }
+    Ok(outcome)
+}
+
+/// Append the lines of `content` matching `regex` to `matches`. Returns
+/// `true` when a match was left out because `matches` is full.
+fn grep_content(
+    regex: &Regex,
+    content: &str,
+    collection: &str,
+    path: &str,
+    params: &GrepParams,
+    matches: &mut Vec<GrepMatch>,
+) -> bool {
+    let lines: Vec<&str> = content.lines().collect();
+    for (i, line) in lines.iter().enumerate() {
+        let Some(found) = regex.find(line) else {
+            continue;
+        };
+        if matches.len() >= params.max_matches {
+            return true;
+        }
+        let after_end = (i + 1 + params.context).min(lines.len());
+        matches.push(GrepMatch {
+            collection: collection.to_string(),
+            path: path.to_string(),
+            line_number: i + 1,
+            column: found.start() + 1,
+            line: line.to_string(),
+            before: lines[i.saturating_sub(params.context)..i]
+                .iter()
+                .map(|line| line.to_string())
+                .collect(),
+            after: lines[i + 1..after_end]
+                .iter()
+                .map(|line| line.to_string())
+                .collect(),
+        });
+    }
+    false
+}
+
+/// Lay matches out the way `grep -n` does: `collection:path:line:text`
+/// for matching lines, `collection:path-line-text` for context, and `--`
+/// between runs of lines that aren't adjacent. Context shared by nearby
+/// matches is printed once.
+///
+/// # Examples
+///
+/// ```
+/// use docbert_core::grep::{GrepMatch, format_lines};
+///
+/// let found = GrepMatch {
+///     collection: "notes".to_string(),
+///     path: "a.md".to_string(),
+///     line_number: 2,
+///     column: 1,
+///     line: "TODO: fix".to_string(),
+///     before: vec!["# Title".to_string()],
+///     after: Vec::new(),
+/// };
+/// assert_eq!(format_lines(&[found]), ["notes:a.md-1-# Title", "notes:a.md:2:TODO: fix"]);
+/// ```
+pub fn format_lines(matches: &[GrepMatch]) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut rest = matches;
+    while let Some(first) = rest.first() {
+        let same_file = rest
+            .iter()
+            .take_while(|found| {
+                found.collection == first.collection && found.path == first.path
+            })
+            .count();
+        let (file, next) = rest.split_at(same_file);
+        rest = next;
+
+        // Line number -> (is a match, text).
+        let mut lines: BTreeMap<usize, (bool, &str)> = BTreeMap::new();
+        for found in file {
+            let start = found.line_number - found.before.len();
+            for (i, text) in found.before.iter().enumerate() {
+                lines.entry(start + i).or_insert((false, text));
+            }
+            lines.insert(found.line_number, (true, &found.line));
+            for (i, text) in found.after.iter().enumerate() {
+                lines
+                    .entry(found.line_number + 1 + i)
+                    .or_insert((false, text));
+            }
+        }
+
+        let mut previous = None;
+        for (number, (is_match, text)) in lines {
+            let adjacent = previous.is_some_and(|line| line + 1 == number);
+            if !out.is_empty() && !adjacent {
+                out.push("--".to_string());
+            }
+            let separator = if is_match { ':' } else { '-' };
+            out.push(format!(
+                "{}:{}{separator}{number}{separator}{text}",
+                first.collection, first.path
+            ));
+            previous = Some(number);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(pattern: &str) -> GrepParams {
+        GrepParams {
+            pattern: pattern.to_string(),
+            fixed_strings: false,
+            ignore_case: false,
+            collection: None,
+            context: 1,
+            max_matches: DEFAULT_MAX_MATCHES,
+            prefilter: false,
+        }
+    }
+
+    fn grep_match(line_number: usize, line: &str) -> GrepMatch {
+        GrepMatch {
+            collection: "notes".to_string(),
+            path: "a.md".to_string(),
+            line_number,
+            column: 1,
+            line: line.to_string(),
+            before: Vec::new(),
+            after: Vec::new(),
+        }
+    }
+
+    fn setup(files: &[(&str, &str)]) -> (tempfile::TempDir, ConfigDb) {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().join("notes");
+        for (path, content) in files {
+            let full = root.join(path);
+            std::fs::create_dir_all(full.parent().unwrap()).unwrap();
+            std::fs::write(full, content).unwrap();
+        }
+        let config_db = ConfigDb::open(&tmp.path().join("config.db")).unwrap();
+        config_db
+            .set_collection("notes", root.to_str().unwrap())
+            .unwrap();
+        (tmp, config_db)
+    }
+
+    #[test]
+    fn grep_returns_numbered_lines_with_context() {
+        let (_tmp, config_db) = setup(&[
+            ("a.md", "# Title\nfirst TODO here\nmiddle\nlast todo\n"),
+            ("sub/b.txt", "nothing to see\n"),
+        ]);
+        let index = SearchIndex::open_in_ram().unwrap();
+
+        let outcome = run(&params("TODO"), &config_db, &index).unwrap();
+        assert_eq!(outcome.files_searched, 2);
+        assert!(!outcome.truncated);
+        assert_eq!(
+            outcome.matches,
+            vec![GrepMatch {
+                collection: "notes".to_string(),
+                path: "a.md".to_string(),
+                line_number: 2,
+                column: 7,
+                line: "first TODO here".to_string(),
+                before: vec!["# Title".to_string()],
+                after: vec!["middle".to_string()],
+            }]
+        );
+
+        let mut insensitive = params(r"t\w+o\b");
+        insensitive.ignore_case = true;
+        insensitive.max_matches = 1;
+        let outcome = run(&insensitive, &config_db, &index).unwrap();
+        assert_eq!(outcome.matches.len(), 1);
+        assert!(outcome.truncated);
+
+        let mut fixed = params("a.md");
+        fixed.fixed_strings = true;
+        assert!(run(&fixed, &config_db, &index).unwrap().matches.is_empty());
+
+        assert!(matches!(
+            run(&params("("), &config_db, &index),
+            Err(Error::InvalidPattern(_))
+        ));
+        let mut missing = params("x");
+        missing.collection = Some("nope".to_string());
+        assert!(matches!(
+            run(&missing, &config_db, &index),
+            Err(Error::NotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn prefilter_skips_unchanged_indexed_files_without_the_literal() {
+        let (_tmp, config_db) = setup(&[
+            ("a.md", "borrow checker rules\n"),
+            ("b.md", "nothing relevant\n"),
+            ("c.md", "borrow checker, again\n"),
+        ]);
+        let root = config_db.get_collection("notes").unwrap().unwrap();
+        let files = walker::discover_files(Path::new(&root)).unwrap();
+        let index = SearchIndex::open_in_ram().unwrap();
+        let mut writer = index.writer(15_000_000).unwrap();
+        // c.md is left unindexed, as if it was added after the last sync.
+        for file in files
+            .iter()
+            .filter(|file| !file.relative_path.ends_with("c.md"))
+        {
+            let path = file.relative_path.to_string_lossy();
+            let did = DocumentId::new("notes", &path);
+            let body = std::fs::read_to_string(&file.absolute_path).unwrap();
+            index
+                .add_document(
+                    &writer,
+                    &did.full_hex(),
+                    did.numeric,
+                    "notes",
+                    &path,
+                    "",
+                    &body,
+                    file.mtime,
+                )
+                .unwrap();
+        }
+        writer.commit().unwrap();
+
+        let mut literal = params("orrow check");
+        literal.prefilter = true;
+        let outcome = run(&literal, &config_db, &index).unwrap();
+        assert_eq!(outcome.files_skipped, 1);
+        assert_eq!(outcome.files_searched, 2);
+        let paths: Vec<&str> =
+            outcome.matches.iter().map(|m| m.path.as_str()).collect();
+        assert_eq!(paths, ["a.md", "c.md"]);
+
+        // A real regular expression can't be prefiltered.
+        let mut regex = params("orrow ch.ck");
+        regex.prefilter = true;
+        let outcome = run(&regex, &config_db, &index).unwrap();
+        assert_eq!(outcome.files_skipped, 0);
+        assert_eq!(outcome.matches.len(), 2);
+    }
+
+    #[test]
+    fn format_lines_merges_shared_context() {
+        let mut first = grep_match(2, "one TODO");
+        first.before = vec!["title".to_string()];
+        first.after = vec!["two TODO".to_string()];
+        let mut second = grep_match(3, "two TODO");
+        second.before = vec!["one TODO".to_string()];
+        second.after = vec!["tail".to_string()];
+        let far = grep_match(9, "far TODO");
+        let mut other = grep_match(1, "TODO elsewhere");
+        other.path = "b.md".to_string();
+
+        assert_eq!(
+            format_lines(&[first, second, far, other]),
+            [
+                "notes:a.md-1-title",
+                "notes:a.md:2:one TODO",
+                "notes:a.md:3:two TODO",
+                "notes:a.md-4-tail",
+                "--",
+                "notes:a.md:9:far TODO",
+                "--",
+                "notes:b.md:1:TODO elsewhere",
+            ]
+        );
+    }
+}
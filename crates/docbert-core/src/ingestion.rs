@@ -117,7 +117,7 @@ pub fn ingest_prepared_documents(
     documents: &[SearchDocument],
 ) -> Result<usize> {
     for doc in documents {
-        index.add_document(
+        index.add_tagged_document(
             writer,
             &doc.did.full_hex(),
             doc.did.numeric,
@@ -126,6 +126,7 @@ pub fn ingest_prepared_documents(
             &doc.title,
             &doc.searchable_body,
             doc.mtime,
+            &doc.tags,
         )?;
     }
 
@@ -473,6 +474,7 @@ mod tests {
             raw_content: None,
             metadata: None,
             mtime: 1000,
+            tags: Vec::new(),
         }];
 
         let count =
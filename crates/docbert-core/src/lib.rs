@@ -49,6 +49,14 @@
 //!     bm25_only: true,
 //!     no_fuzzy: false,
 //!     all: false,
+//!     passages: 0,
+//!     explain: false,
+//!     prf: None,
+//!     facets: false,
+//!     offset: 0,
+//!     cursor: None,
+//!     autocorrect: false,
+//!     plaid: Default::default(),
 //! };
 //!
 //! let results = search::run(
@@ -93,20 +101,28 @@ pub mod doc_id;
 pub mod embedding;
 pub mod embedding_db;
 pub mod error;
+pub mod eval;
+pub mod exact;
+pub mod grep;
 pub mod incremental;
 pub mod ingestion;
 pub mod merkle;
 pub mod model_manager;
 pub mod path_safety;
 pub mod plaid;
+pub mod plaid_drift;
 pub mod preparation;
 pub mod redb_migration;
 pub mod reranker;
+pub mod resident_plaid;
 pub mod results;
+pub mod saved_search;
 pub mod search;
+pub mod search_cache;
 pub mod storage_codec;
 pub mod stored_json;
 pub mod tantivy_index;
+pub mod tantivy_migration;
 pub mod text;
 pub mod token_pool;
 pub mod walker;
@@ -1,5 +1,5 @@
 use candle_core::{Device, Tensor};
-use docbert_pylate::{ColBERT, Similarities};
+use docbert_pylate::{ColBERT, CrossEncoder, MatchExplanation, Similarities};
 
 use crate::error::{Error, Result};
 
@@ -9,6 +9,10 @@ pub const DEFAULT_MODEL_ID: &str = "lightonai/GTE-ModernColBERT-v1";
 /// Environment variable checked for a model ID override (`DOCBERT_MODEL`).
 pub const MODEL_ENV_VAR: &str = "DOCBERT_MODEL";
 
+/// `config.db` setting holding the local cross-encoder checkpoint used
+/// for the optional final rerank stage.
+pub const RERANKER_MODEL_SETTING: &str = "reranker_model";
+
 /// Fallback document length when the model config does not specify one.
 ///
 /// Only used as a last resort. The model's own
@@ -318,6 +322,10 @@ pub struct ModelManager {
     document_length: Option<usize>,
     embedding_batch_size: Option<usize>,
     runtime_config: Option<ModelRuntimeConfig>,
+    /// The loaded cross-encoder, or why it failed to load, so a broken
+    /// checkpoint is read once rather than on every query.
+    reranker: Option<std::result::Result<CrossEncoder, String>>,
+    reranker_model: Option<String>,
 }
 
 impl Default for ModelManager {
@@ -334,6 +342,8 @@ impl ModelManager {
             document_length: None,
             embedding_batch_size: None,
             runtime_config: None,
+            reranker: None,
+            reranker_model: None,
         }
     }
 
@@ -377,11 +387,34 @@ impl ModelManager {
         self
     }
 
+    /// Sets the local cross-encoder checkpoint used by [`rerank`](Self::rerank).
+    ///
+    /// `None` disables the rerank stage. The checkpoint is not loaded
+    /// until the first `rerank` call.
+    pub fn with_reranker_model(
+        mut self,
+        reranker_model: Option<String>,
+    ) -> Self {
+        self.reranker_model = reranker_model;
+        self.reranker = None;
+        self
+    }
+
     /// Returns the model ID that will be (or has been) loaded.
     pub fn model_id(&self) -> &str {
         &self.model_id
     }
 
+    /// Returns the configured cross-encoder checkpoint, if any.
+    pub fn reranker_model(&self) -> Option<&str> {
+        self.reranker_model.as_deref()
+    }
+
+    /// Returns `true` when a cross-encoder checkpoint is configured.
+    pub fn has_reranker(&self) -> bool {
+        self.reranker_model.is_some()
+    }
+
     /// Returns `true` if the model has already been loaded into memory.
     pub fn is_loaded(&self) -> bool {
         self.model.is_some()
@@ -501,6 +534,31 @@ impl ModelManager {
         Ok(embeddings.squeeze(0)?)
     }
 
+    /// Encodes several queries in one batched forward pass.
+    ///
+    /// Returns one `[Q, D]` tensor per query, in input order, each the
+    /// same as [`encode_query`](Self::encode_query) would return for it:
+    /// queries are expanded to the model's fixed query length, so batching
+    /// adds no padding of its own. Honors
+    /// `DOCBERT_WEB_TEST_FAKE_EMBEDDINGS` like `encode_query`.
+    pub fn encode_queries(
+        &mut self,
+        queries: &[String],
+    ) -> Result<Vec<Tensor>> {
+        if queries.is_empty() {
+            return Ok(Vec::new());
+        }
+        if std::env::var_os("DOCBERT_WEB_TEST_FAKE_EMBEDDINGS").is_some() {
+            return queries
+                .iter()
+                .map(|query| self.encode_query(query))
+                .collect();
+        }
+        let model = self.ensure_loaded()?;
+        let embeddings = model.encode(queries, true)?;
+        (0..queries.len()).map(|i| Ok(embeddings.get(i)?)).collect()
+    }
+
     /// Computes MaxSim similarity scores between query and document embeddings.
     ///
     /// Both tensors must be 3D: `[batch, tokens, dimension]`. Returns a
@@ -532,6 +590,102 @@ impl ModelManager {
         })?;
         Ok(model.similarity(query_embeddings, document_embeddings)?)
     }
+
+    /// Breaks the MaxSim score of `query` against `text` down by query
+    /// token.
+    ///
+    /// Every query token is aligned to the document token it scored
+    /// highest against, with that token's byte span in `text`. See
+    /// [`ColBERT::explain`]. Downloads the model on first call if not
+    /// already cached.
+    ///
+    /// When the `DOCBERT_WEB_TEST_FAKE_EMBEDDINGS` environment variable is
+    /// set, returns an empty explanation without loading the model.
+    pub fn explain_match(
+        &mut self,
+        query: &str,
+        text: &str,
+    ) -> Result<MatchExplanation> {
+        if std::env::var_os("DOCBERT_WEB_TEST_FAKE_EMBEDDINGS").is_some() {
+            return Ok(MatchExplanation {
+                score: 0.0,
+                alignments: Vec::new(),
+            });
+        }
+        let model = self.ensure_loaded()?;
+        Ok(model.explain(query, text)?)
+    }
+
+    /// Scores `(query, passage)` pairs with the configured cross-encoder.
+    ///
+    /// Returns one score per passage, in input order; higher is more
+    /// relevant. The checkpoint is loaded from the local directory set
+    /// through [`with_reranker_model`](Self::with_reranker_model) on
+    /// first call; a checkpoint that fails to load is not retried, and
+    /// every later call returns the same `Config` error. Also fails with
+    /// a `Config` error when no reranker is configured, so callers should
+    /// check [`has_reranker`](Self::has_reranker) first.
+    pub fn rerank(
+        &mut self,
+        query: &str,
+        passages: &[String],
+    ) -> Result<Vec<f32>> {
+        let reranker = match &self.reranker {
+            Some(loaded) => loaded,
+            None => {
+                let path = self.reranker_model.as_deref().ok_or_else(|| {
+                    Error::Config("no reranker model configured".to_string())
+                })?;
+                let device = default_device().device;
+                let loaded =
+                    CrossEncoder::from_dir(std::path::Path::new(path), &device)
+                        .map_err(|e| {
+                            format!("could not load reranker {path}: {e}")
+                        });
+                self.reranker.insert(loaded)
+            }
+        };
+        match reranker {
+            Ok(reranker) => Ok(reranker.score(query, passages)?),
+            Err(message) => Err(Error::Config(message.clone())),
+        }
+    }
+}
+
+/// Resolve the cross-encoder checkpoint for the rerank stage.
+///
+/// An explicit `--rerank-model` value wins over the
+/// [`RERANKER_MODEL_SETTING`] stored in `config.db`. Returns `None` when
+/// neither is set, which leaves the rerank stage disabled.
+///
+/// # Examples
+///
+/// ```
+/// # let tmp = tempfile::tempdir().unwrap();
+/// use docbert_core::ConfigDb;
+/// use docbert_core::model_manager::{RERANKER_MODEL_SETTING, resolve_reranker_model};
+///
+/// let db = ConfigDb::open(&tmp.path().join("config.db")).unwrap();
+/// assert_eq!(resolve_reranker_model(&db, None).unwrap(), None);
+///
+/// db.set_setting(RERANKER_MODEL_SETTING, "/models/reranker").unwrap();
+/// assert_eq!(
+///     resolve_reranker_model(&db, None).unwrap().as_deref(),
+///     Some("/models/reranker")
+/// );
+/// assert_eq!(
+///     resolve_reranker_model(&db, Some("/tmp/other")).unwrap().as_deref(),
+///     Some("/tmp/other")
+/// );
+/// ```
+pub fn resolve_reranker_model(
+    config_db: &crate::config_db::ConfigDb,
+    cli_reranker: Option<&str>,
+) -> Result<Option<String>> {
+    if let Some(cli) = cli_reranker {
+        return Ok(Some(cli.to_string()));
+    }
+    config_db.get_setting(RERANKER_MODEL_SETTING)
 }
 
 /// Where the chosen model ID came from.
@@ -666,6 +820,25 @@ mod tests {
         assert_eq!(manager.embedding_batch_size, Some(96));
     }
 
+    #[test]
+    fn with_reranker_model_is_stored_and_not_loaded() {
+        let manager = ModelManager::new()
+            .with_reranker_model(Some("/models/reranker".to_string()));
+        assert!(manager.has_reranker());
+        assert_eq!(manager.reranker_model(), Some("/models/reranker"));
+        assert!(manager.reranker.is_none());
+    }
+
+    #[test]
+    fn rerank_without_configured_model_is_a_config_error() {
+        let mut manager = ModelManager::new();
+        assert!(!manager.has_reranker());
+        let err = manager
+            .rerank("query", &["passage".to_string()])
+            .unwrap_err();
+        assert!(matches!(err, Error::Config(_)));
+    }
+
     #[test]
     fn default_impl_matches_new() {
         let from_default = ModelManager::default();
@@ -680,6 +853,28 @@ mod tests {
         assert_eq!(manager.document_length, None);
     }
 
+    #[test]
+    fn rerank_caches_a_checkpoint_that_fails_to_load() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut manager = ModelManager::new().with_reranker_model(Some(
+            tmp.path().to_string_lossy().into_owned(),
+        ));
+        let query = "query";
+        let passages = ["passage".to_string()];
+
+        let first = manager.rerank(query, &passages).unwrap_err().to_string();
+        assert!(matches!(manager.reranker, Some(Err(_))));
+        // A checkpoint appearing later is not picked up: the failure is
+        // cached until the reranker model is set again.
+        std::fs::write(tmp.path().join("config.json"), "{}").unwrap();
+        let second = manager.rerank(query, &passages).unwrap_err().to_string();
+        assert_eq!(first, second);
+
+        let mut manager = manager.with_reranker_model(None);
+        assert!(manager.reranker.is_none());
+        assert!(manager.rerank(query, &passages).is_err());
+    }
+
     #[test]
     fn compute_device_kind_labels_are_stable() {
         assert_eq!(ComputeDeviceKind::Cpu.as_str(), "cpu");
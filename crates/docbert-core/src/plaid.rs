@@ -12,8 +12,12 @@
 //! - [`build_index_from_embedding_db`] scans every stored embedding,
 //!   flattens it into a `DocumentTokens` row, and hands the whole corpus
 //!   to `docbert_plaid::index::build_index`.
+//! - [`update_index_with_chunks`] / [`update_index_from_embedding_db`]
+//!   add a small delta segment for a sync's changes instead of
+//!   rebuilding (see `docbert_plaid::segment`).
 //! - [`save_index`] / [`load_index`] persist the result under
-//!   [`crate::DataDir::plaid_index`].
+//!   [`crate::DataDir::plaid_index`]; [`merge_segments`] compacts the
+//!   segments that updates leave behind.
 //! - [`search`] runs a query tensor through the stored index and returns
 //!   ranked `(doc_id, score)` pairs.
 //!
@@ -21,20 +25,25 @@
 //! one is present is a separate, follow-up change; today this module
 //! only exposes the pieces that change needs.
 
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex, Weak},
+    thread::JoinHandle,
+};
+
 use candle_core::Tensor;
 use docbert_plaid::{
-    index::{
-        self as plaid_index,
-        DocumentTokens,
-        Index as PlaidIndex,
-        IndexParams,
-    },
-    persistence,
-    search::{self as plaid_search, SearchParams},
-    update::{self as plaid_update, IndexUpdate},
+    codec::DecodeTable,
+    index::{self as plaid_index, DocumentTokens, IndexParams},
+    kmeans,
+    search::SearchParams,
+    segment::{self, IndexLock},
+    update::IndexUpdate,
 };
+use serde::{Deserialize, Serialize};
 
 use crate::{
+    config_db::ConfigDb,
     data_dir::DataDir,
     embedding_db::EmbeddingDb,
     error::{Error, Result},
@@ -46,7 +55,9 @@ use crate::{
 /// The defaults (via [`PlaidBuildParams::default`]) are tuned for a
 /// small personal-notes corpus: 256 coarse centroids with 2-bit residual
 /// quantization and a generous k-means iteration cap. Larger corpora
-/// typically want proportionally more centroids.
+/// typically want proportionally more centroids, which
+/// [`PlaidBuildParams::for_corpus`] picks from the token count;
+/// [`PlaidBuildOverrides`] pins individual fields instead.
 #[derive(Debug, Clone, Copy)]
 pub struct PlaidBuildParams {
     /// Number of coarse centroids trained by k-means.
@@ -55,6 +66,10 @@ pub struct PlaidBuildParams {
     pub nbits: u32,
     /// Upper bound on k-means iterations during training.
     pub max_kmeans_iters: usize,
+    /// k-means seeding, mini-batch training, early stopping and
+    /// training-sample size. The default is Lloyd's algorithm on
+    /// `256 · k_centroids` tokens seeded by a random pick.
+    pub kmeans: kmeans::KMeansOptions,
 }
 
 impl Default for PlaidBuildParams {
@@ -63,10 +78,267 @@ impl Default for PlaidBuildParams {
             k_centroids: 256,
             nbits: 2,
             max_kmeans_iters: 20,
+            kmeans: kmeans::KMeansOptions::default(),
+        }
+    }
+}
+
+impl PlaidBuildParams {
+    /// The default bit-width and iteration cap, with `k_centroids`
+    /// scaled to a corpus of `total_tokens` token embeddings (see
+    /// `docbert_plaid::index::auto_k_centroids`).
+    pub fn for_corpus(total_tokens: usize) -> Self {
+        Self {
+            k_centroids: plaid_index::auto_k_centroids(total_tokens),
+            ..Self::default()
+        }
+    }
+}
+
+/// Settings key the [`PlaidBuildOverrides`] are stored under.
+pub const PLAID_BUILD_SETTING: &str = "plaid_build_params";
+
+/// Build parameters the user pinned, e.g. with `docbert reindex
+/// --centroids`. Fields left `None` are chosen per build by
+/// [`PlaidBuildParams::for_corpus`].
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+pub struct PlaidBuildOverrides {
+    pub k_centroids: Option<usize>,
+    pub nbits: Option<u32>,
+    pub max_kmeans_iters: Option<usize>,
+}
+
+impl PlaidBuildOverrides {
+    /// Whether no field is pinned.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// `self` with every field `other` pins replaced by `other`'s value.
+    pub fn with(self, other: PlaidBuildOverrides) -> Self {
+        Self {
+            k_centroids: other.k_centroids.or(self.k_centroids),
+            nbits: other.nbits.or(self.nbits),
+            max_kmeans_iters: other.max_kmeans_iters.or(self.max_kmeans_iters),
+        }
+    }
+
+    /// The parameters to build a corpus of `total_tokens` token
+    /// embeddings with. A pinned `k_centroids` larger than the corpus is
+    /// capped at `total_tokens`, since k-means needs a point per
+    /// centroid.
+    pub fn resolve(&self, total_tokens: usize) -> PlaidBuildParams {
+        let auto = PlaidBuildParams::for_corpus(total_tokens);
+        PlaidBuildParams {
+            k_centroids: self
+                .k_centroids
+                .map_or(auto.k_centroids, |k| k.min(total_tokens).max(1)),
+            nbits: self.nbits.unwrap_or(auto.nbits),
+            max_kmeans_iters: self
+                .max_kmeans_iters
+                .unwrap_or(auto.max_kmeans_iters),
+            kmeans: auto.kmeans,
         }
     }
 }
 
+/// The stored [`PlaidBuildOverrides`]. Nothing stored, or a record that
+/// no longer parses, means nothing is pinned.
+///
+/// # Errors
+///
+/// Returns an error if reading `config_db` fails.
+pub fn load_build_overrides(
+    config_db: &ConfigDb,
+) -> Result<PlaidBuildOverrides> {
+    Ok(config_db
+        .get_json_setting(PLAID_BUILD_SETTING)?
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default())
+}
+
+/// Store `overrides`, removing the setting when nothing is pinned.
+///
+/// # Errors
+///
+/// Returns an error if writing `config_db` fails.
+pub fn save_build_overrides(
+    config_db: &ConfigDb,
+    overrides: &PlaidBuildOverrides,
+) -> Result<()> {
+    if overrides.is_empty() {
+        config_db.remove_setting(PLAID_BUILD_SETTING)?;
+        return Ok(());
+    }
+    let value = serde_json::to_value(overrides).map_err(|e| {
+        Error::Config(format!(
+            "failed to serialize PLAID build parameters: {e}"
+        ))
+    })?;
+    config_db.set_json_setting(PLAID_BUILD_SETTING, &value)
+}
+
+/// Total number of token embeddings stored in `embedding_db`, read from
+/// the entry headers only.
+///
+/// # Errors
+///
+/// Returns an error if reading `embedding_db` fails.
+pub fn corpus_token_count(embedding_db: &EmbeddingDb) -> Result<usize> {
+    Ok(embedding_db
+        .list_shapes()?
+        .iter()
+        .map(|&(_, n_tokens, _)| n_tokens as usize)
+        .sum())
+}
+
+/// Settings key the default [`PlaidQueryParams`] are stored under.
+pub const PLAID_QUERY_SETTING: &str = "plaid_search_params";
+
+/// Query-time PLAID parameters overriding
+/// [`SearchParams::paper_defaults`], which were tuned on MS MARCO and
+/// prune harder than a small corpus needs.
+///
+/// Fields left `None` keep the paper default for the requested `top_k`.
+/// `exhaustive` skips pruning altogether: every centroid is probed and
+/// every candidate is decoded and scored, trading latency for recall.
+/// `exact` bypasses PLAID for [`crate::exact::search`]'s full-precision
+/// scan; left `None`, small indexes are scanned exactly (see
+/// [`crate::exact::use_exact`]).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct PlaidQueryParams {
+    /// Centroids each query token probes.
+    pub n_probe: Option<usize>,
+    /// Candidates kept by centroid interaction before the exact MaxSim.
+    pub n_candidate_docs: Option<usize>,
+    /// Minimum centroid score for a centroid to produce candidates.
+    pub centroid_score_threshold: Option<f32>,
+    #[serde(default)]
+    pub exhaustive: bool,
+    /// Score every stored embedding exactly (`Some(true)`) or always use
+    /// PLAID (`Some(false)`); `None` decides by index size.
+    pub exact: Option<bool>,
+}
+
+impl PlaidQueryParams {
+    /// Whether nothing overrides the paper defaults.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// `self` with every field `other` sets replaced by `other`'s value.
+    /// Either side asking for an exhaustive search makes it exhaustive.
+    pub fn with(self, other: PlaidQueryParams) -> Self {
+        Self {
+            n_probe: other.n_probe.or(self.n_probe),
+            n_candidate_docs: other.n_candidate_docs.or(self.n_candidate_docs),
+            centroid_score_threshold: other
+                .centroid_score_threshold
+                .or(self.centroid_score_threshold),
+            exhaustive: self.exhaustive || other.exhaustive,
+            exact: other.exact.or(self.exact),
+        }
+    }
+
+    /// Reject values PLAID can't search with.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Config`] for a zero `n_probe` or
+    /// `n_candidate_docs` and for a threshold that isn't a finite number.
+    pub fn validate(&self) -> Result<()> {
+        if self.n_probe == Some(0) {
+            return Err(Error::Config(
+                "PLAID n_probe must be positive".to_string(),
+            ));
+        }
+        if self.n_candidate_docs == Some(0) {
+            return Err(Error::Config(
+                "PLAID n_candidate_docs must be positive".to_string(),
+            ));
+        }
+        if self
+            .centroid_score_threshold
+            .is_some_and(|threshold| !threshold.is_finite())
+        {
+            return Err(Error::Config(
+                "PLAID centroid_score_threshold must be a finite number"
+                    .to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// The parameters to search for `top_k` results with.
+    ///
+    /// A `n_candidate_docs` below `4 * top_k` is raised to it, since
+    /// the last centroid-interaction stage keeps a quarter of the
+    /// candidates. A zero `n_probe` is raised to one.
+    pub fn resolve(&self, top_k: usize) -> SearchParams {
+        let defaults = SearchParams::paper_defaults(top_k);
+        if self.exhaustive {
+            return SearchParams {
+                top_k,
+                // Clamped to the index's centroid count by the search.
+                n_probe: usize::MAX,
+                n_candidate_docs: None,
+                centroid_score_threshold: None,
+            };
+        }
+        SearchParams {
+            top_k,
+            n_probe: self.n_probe.unwrap_or(defaults.n_probe).max(1),
+            n_candidate_docs: self
+                .n_candidate_docs
+                .map(|n| n.max(top_k.saturating_mul(4)))
+                .or(defaults.n_candidate_docs),
+            centroid_score_threshold: self
+                .centroid_score_threshold
+                .or(defaults.centroid_score_threshold),
+        }
+    }
+}
+
+/// The stored default [`PlaidQueryParams`]. Nothing stored, or a record
+/// that no longer parses, means the paper defaults.
+///
+/// # Errors
+///
+/// Returns an error if reading `config_db` fails.
+pub fn load_query_params(config_db: &ConfigDb) -> Result<PlaidQueryParams> {
+    Ok(config_db
+        .get_json_setting(PLAID_QUERY_SETTING)?
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default())
+}
+
+/// Store `params` as the default for every search, removing the setting
+/// when nothing is overridden.
+///
+/// # Errors
+///
+/// Returns [`Error::Config`] if `params` fails
+/// [`PlaidQueryParams::validate`], or an error if writing `config_db`
+/// fails.
+pub fn save_query_params(
+    config_db: &ConfigDb,
+    params: &PlaidQueryParams,
+) -> Result<()> {
+    params.validate()?;
+    if params.is_empty() {
+        config_db.remove_setting(PLAID_QUERY_SETTING)?;
+        return Ok(());
+    }
+    let value = serde_json::to_value(params).map_err(|e| {
+        Error::Config(format!(
+            "failed to serialize PLAID search parameters: {e}"
+        ))
+    })?;
+    config_db.set_json_setting(PLAID_QUERY_SETTING, &value)
+}
+
 /// A scored document returned by a PLAID search over docbert's corpus.
 ///
 /// This mirrors `docbert_plaid::search::SearchResult` but is re-exported
@@ -77,6 +349,98 @@ pub struct PlaidResult {
     pub score: f32,
 }
 
+/// Per-stage counters and timings of one PLAID search.
+///
+/// Re-exported from `docbert_plaid::search::SearchStats`.
+pub use docbert_plaid::search::SearchStats as PlaidSearchStats;
+/// Which chunks of one [`PlaidIndex`] a filtered search may return.
+///
+/// Re-exported from `docbert_plaid::segment::IndexFilter`.
+pub use docbert_plaid::segment::IndexFilter as PlaidFilter;
+/// When [`merge_segments`] compacts the index's segments.
+///
+/// Re-exported from `docbert_plaid::segment::MergePolicy`.
+pub use docbert_plaid::segment::MergePolicy as PlaidMergePolicy;
+/// The PLAID index docbert builds, updates and searches: immutable
+/// segments sharing one codec.
+///
+/// Re-exported from `docbert_plaid::segment::SegmentedIndex`.
+pub use docbert_plaid::segment::SegmentedIndex as PlaidIndex;
+
+/// [`PlaidFilter`]s built for one loaded index, by key (for instance
+/// one per collection).
+///
+/// A filter is only valid for the index it was built from, so the cache
+/// remembers that index and starts over when it is asked about another
+/// one. [`crate::resident_plaid::ResidentPlaid`] keeps one next to the
+/// resident index.
+#[derive(Debug, Default)]
+pub struct PlaidFilterCache {
+    entries: Mutex<FilterEntries>,
+}
+
+#[derive(Debug, Default)]
+struct FilterEntries {
+    index: Weak<PlaidIndex>,
+    filters: HashMap<String, Arc<PlaidFilter>>,
+}
+
+impl PlaidFilterCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The filter cached under `key` for `index`, built with `build` and
+    /// cached on a miss.
+    ///
+    /// # Errors
+    ///
+    /// Propagates the error of `build`; nothing is cached then.
+    pub fn get_or_build(
+        &self,
+        index: &Arc<PlaidIndex>,
+        key: &str,
+        build: impl FnOnce() -> Result<PlaidFilter>,
+    ) -> Result<Arc<PlaidFilter>> {
+        let mut entries = self
+            .entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        // The `Weak` keeps the old allocation alive, so a new index can
+        // never share its address.
+        if !std::ptr::eq(entries.index.as_ptr(), Arc::as_ptr(index)) {
+            entries.index = Arc::downgrade(index);
+            entries.filters.clear();
+        }
+        if let Some(filter) = entries.filters.get(key) {
+            return Ok(Arc::clone(filter));
+        }
+        let filter = Arc::new(build()?);
+        entries.filters.insert(key.to_string(), Arc::clone(&filter));
+        Ok(filter)
+    }
+}
+
+/// A [`PlaidFilter`] allowing every chunk of the documents in
+/// `doc_num_ids`, found through their chunk manifests.
+///
+/// # Errors
+///
+/// Returns an error if reading a manifest from `config_db` fails.
+pub fn documents_filter(
+    config_db: &ConfigDb,
+    index: &PlaidIndex,
+    doc_num_ids: impl IntoIterator<Item = u64>,
+) -> Result<PlaidFilter> {
+    let mut chunks: HashSet<u64> = HashSet::new();
+    for doc_num_id in doc_num_ids {
+        if let Some(manifest) = config_db.get_doc_chunks(doc_num_id)? {
+            chunks.extend(manifest.iter().map(|entry| entry.chunk_doc_id));
+        }
+    }
+    Ok(index.filter(|chunk_doc_id| chunks.contains(&chunk_doc_id)))
+}
+
 /// Build a PLAID index over every embedding currently stored in
 /// `embedding_db`.
 ///
@@ -111,6 +475,14 @@ pub fn build_index_from_embedding_db(
         total_tokens += n_tokens as usize;
     }
 
+    if params.k_centroids == 0 || !matches!(params.nbits, 1 | 2 | 4 | 8) {
+        return Err(Error::Config(format!(
+            "cannot build PLAID index: need a positive centroid count and \
+             1, 2, 4 or 8 residual bits, got {} centroids and {} bits",
+            params.k_centroids, params.nbits,
+        )));
+    }
+
     if total_tokens < params.k_centroids {
         return Err(Error::Config(format!(
             "cannot build PLAID index: need at least {} tokens for \
@@ -119,6 +491,18 @@ pub fn build_index_from_embedding_db(
         )));
     }
 
+    let options = params.kmeans;
+    if options.mini_batch == Some(0)
+        || options.sample_tokens == Some(0)
+        || options.tolerance.is_some_and(|t| !t.is_finite() || t < 0.0)
+    {
+        return Err(Error::Config(format!(
+            "cannot build PLAID index: k-means needs positive mini-batch \
+             and sample sizes and a finite, non-negative tolerance, got \
+             {options:?}",
+        )));
+    }
+
     // Second pass: load matrices one at a time, copy their tokens into
     // the pool, and drop each matrix before the next load. Peak memory
     // is now `pool + one matrix`, not `pool + every matrix`.
@@ -137,13 +521,12 @@ pub fn build_index_from_embedding_db(
         nbits: params.nbits,
         k_centroids: params.k_centroids,
         max_kmeans_iters: params.max_kmeans_iters,
+        kmeans: params.kmeans,
     };
 
-    Ok(plaid_index::build_index_from_pool(
-        pool,
-        doc_meta,
-        index_params,
-    )?)
+    let index =
+        plaid_index::build_index_from_pool(pool, doc_meta, index_params)?;
+    Ok(PlaidIndex::from_index(index))
 }
 
 /// Apply the given sync deltas to `existing`, reusing its codec.
@@ -162,10 +545,10 @@ pub fn build_index_from_embedding_db(
 /// whose distribution hasn't drifted much, this is strictly cheaper
 /// than [`build_index_from_embedding_db`] since k-means and quantizer
 /// training are skipped; the heavy work reduces to encoding just the
-/// `changed_ids` tokens.
+/// `changed_ids` tokens into one new segment.
 pub fn update_index_from_embedding_db(
     embedding_db: &EmbeddingDb,
-    existing: PlaidIndex,
+    mut existing: PlaidIndex,
     changed_ids: &[u64],
     deleted_ids: &[u64],
 ) -> Result<PlaidIndex> {
@@ -193,13 +576,11 @@ pub fn update_index_from_embedding_db(
         });
     }
 
-    Ok(plaid_update::apply_update(
-        existing,
-        IndexUpdate {
-            deletions: deleted_ids,
-            upserts: &upserts,
-        },
-    )?)
+    existing.apply_update(IndexUpdate {
+        deletions: deleted_ids,
+        upserts: &upserts,
+    })?;
+    Ok(existing)
 }
 
 /// Incrementally sync `existing` against `embedding_db` for an explicit
@@ -218,10 +599,12 @@ pub fn update_index_from_embedding_db(
 ///
 /// Returns [`Error::Config`] when a chunk in `upsert_chunk_ids` is
 /// missing from `embedding_db` or has a dimensionality that doesn't
-/// match the index. Returns a fresh `PlaidIndex` with the same codec.
+/// match the index. Returns `existing` with the upserts in one new
+/// segment and the replaced or deleted chunks marked deleted in the
+/// older ones.
 pub fn update_index_with_chunks(
     embedding_db: &EmbeddingDb,
-    existing: PlaidIndex,
+    mut existing: PlaidIndex,
     upsert_chunk_ids: &[u64],
     deleted_chunk_ids: &[u64],
 ) -> Result<PlaidIndex> {
@@ -262,7 +645,7 @@ pub fn update_index_with_chunks(
     // Deletions are the caller-supplied list, restricted to chunks
     // the old index actually carries — silently ignoring stragglers
     // keeps the bridge safe to call on disjoint inputs.
-    let known: HashSet<u64> = existing.doc_ids.iter().copied().collect();
+    let known: HashSet<u64> = existing.live_doc_ids().collect();
     let deletions: Vec<u64> = deleted_chunk_ids
         .iter()
         .copied()
@@ -270,21 +653,80 @@ pub fn update_index_with_chunks(
         .filter(|id| !seen.contains(id))
         .collect();
 
-    Ok(plaid_update::apply_update(
-        existing,
-        IndexUpdate {
-            upserts: &upserts,
-            deletions: &deletions,
-        },
-    )?)
+    existing.apply_update(IndexUpdate {
+        upserts: &upserts,
+        deletions: &deletions,
+    })?;
+    Ok(existing)
 }
 
 /// Write `index` to the canonical PLAID index path under `data_dir`.
+///
+/// Only segments that aren't on disk yet are written, so saving after
+/// a small sync writes one delta segment and the `plaid.idx` manifest.
+/// The manifest is replaced atomically, so a process reading the index
+/// concurrently (a
+/// [`ResidentPlaid`](crate::resident_plaid::ResidentPlaid) reloading in
+/// a server) sees either the old index or the new one, never a partial
+/// write, and an index still memory-mapped from old segments keeps
+/// working.
+///
+/// Writers should hold [`lock_index`] from loading the index they
+/// modify until this returns.
 pub fn save_index(index: &PlaidIndex, data_dir: &DataDir) -> Result<()> {
-    persistence::save(index, &data_dir.plaid_index())?;
+    index.save(&data_dir.plaid_index())?;
     Ok(())
 }
 
+/// Take the exclusive write lock on the PLAID index under `data_dir`,
+/// waiting for any other writer (another sync, a background merge).
+///
+/// Hold the returned guard from [`load_index`] through [`save_index`]
+/// so two writers can't build on the same index and drop each other's
+/// changes. Searching never needs it.
+pub fn lock_index(data_dir: &DataDir) -> Result<IndexLock> {
+    Ok(segment::lock(&data_dir.plaid_index())?)
+}
+
+/// Compact the PLAID index under `data_dir` according to `policy`.
+///
+/// The merge itself runs against a snapshot without holding the write
+/// lock, so a sync can keep updating the index meanwhile; only
+/// installing the merged segment and saving take the lock. Returns
+/// whether a merge was installed: `false` when there was nothing to do
+/// or another writer replaced the merged segments first.
+pub fn merge_segments(
+    data_dir: &DataDir,
+    policy: PlaidMergePolicy,
+) -> Result<bool> {
+    let Some(snapshot) = load_index(data_dir)? else {
+        return Ok(false);
+    };
+    let Some(plan) = snapshot.plan_merge(policy) else {
+        return Ok(false);
+    };
+    let merged = snapshot.merge(&plan)?;
+    drop(snapshot);
+
+    let _lock = lock_index(data_dir)?;
+    let Some(mut index) = load_index(data_dir)? else {
+        return Ok(false);
+    };
+    if !index.install_merge(merged) {
+        return Ok(false);
+    }
+    save_index(&index, data_dir)?;
+    Ok(true)
+}
+
+/// Run [`merge_segments`] on a background thread.
+pub fn spawn_background_merge(
+    data_dir: DataDir,
+    policy: PlaidMergePolicy,
+) -> JoinHandle<Result<bool>> {
+    std::thread::spawn(move || merge_segments(&data_dir, policy))
+}
+
 /// Load the PLAID index from `data_dir` if one has been built.
 ///
 /// Returns `Ok(None)` when the file does not exist — callers typically
@@ -294,7 +736,20 @@ pub fn load_index(data_dir: &DataDir) -> Result<Option<PlaidIndex>> {
     if !path.exists() {
         return Ok(None);
     }
-    Ok(Some(persistence::load(&path)?))
+    Ok(Some(PlaidIndex::load(&path)?))
+}
+
+/// The PLAID index searches should rank against.
+///
+/// Uses the [`ResidentPlaid`](crate::resident_plaid::ResidentPlaid)
+/// attached to `data_dir` when there is one, so long-lived runtimes
+/// load the file once per change; otherwise reads it like
+/// [`load_index`]. Returns `Ok(None)` when no index has been built.
+pub fn open_index(data_dir: &DataDir) -> Result<Option<Arc<PlaidIndex>>> {
+    match data_dir.resident_plaid() {
+        Some(resident) => resident.get(data_dir),
+        None => Ok(load_index(data_dir)?.map(Arc::new)),
+    }
 }
 
 /// Release CUDA's async memory pool back to the driver.
@@ -328,6 +783,33 @@ pub fn search(
     query_embedding: &Tensor,
     top_k: usize,
 ) -> Result<Vec<PlaidResult>> {
+    search_with_stats(index, query_embedding, top_k).map(|(results, _)| results)
+}
+
+/// [`search`], also returning the PLAID stage counters and timings used
+/// by search explanations.
+pub fn search_with_stats(
+    index: &PlaidIndex,
+    query_embedding: &Tensor,
+    top_k: usize,
+) -> Result<(Vec<PlaidResult>, PlaidSearchStats)> {
+    let query_flat = query_tokens_flat(index, query_embedding)?;
+    search_flat(
+        index,
+        &query_flat,
+        top_k,
+        &PlaidQueryParams::default(),
+        None,
+    )
+}
+
+/// Copy an encoded `[n_tokens, dim]` query tensor into the flat,
+/// row-major layout taken by [`search_tokens`], checking its dimension
+/// against `index`.
+pub fn query_tokens_flat(
+    index: &PlaidIndex,
+    query_embedding: &Tensor,
+) -> Result<Vec<f32>> {
     let (query_tokens, query_dim) = query_embedding.dims2()?;
     if query_dim != index.params.dim {
         return Err(Error::Config(format!(
@@ -341,16 +823,174 @@ pub fn search(
         .flatten_all()?
         .to_vec1::<f32>()?;
     debug_assert_eq!(query_flat.len(), query_tokens * query_dim);
+    Ok(query_flat)
+}
+
+/// Search `index` with a flat, row-major `[n_tokens * dim]` query
+/// matrix instead of an encoded query tensor.
+///
+/// Used by similar-document search, which queries with a stored
+/// document's token embeddings. An empty query returns no results.
+pub fn search_tokens(
+    index: &PlaidIndex,
+    query_flat: &[f32],
+    top_k: usize,
+) -> Result<Vec<PlaidResult>> {
+    search_tokens_with_stats(
+        index,
+        query_flat,
+        top_k,
+        &PlaidQueryParams::default(),
+    )
+    .map(|(results, _)| results)
+}
+
+/// [`search_tokens`] with `params` overriding the paper defaults, also
+/// returning the PLAID stage counters and timings.
+pub fn search_tokens_with_stats(
+    index: &PlaidIndex,
+    query_flat: &[f32],
+    top_k: usize,
+    params: &PlaidQueryParams,
+) -> Result<(Vec<PlaidResult>, PlaidSearchStats)> {
+    if query_flat.is_empty() {
+        return Ok((Vec::new(), PlaidSearchStats::default()));
+    }
+    check_query_dim(index, query_flat)?;
+    search_flat(index, query_flat, top_k, params, None)
+}
+
+/// [`search_tokens_with_stats`], returning only chunks `filter` allows.
+///
+/// The filter is applied while PLAID gathers candidates, so a small
+/// collection inside a large index still fills its `top_k`.
+///
+/// # Panics
+///
+/// Panics if `filter` was built for another index.
+pub fn search_tokens_filtered(
+    index: &PlaidIndex,
+    query_flat: &[f32],
+    top_k: usize,
+    params: &PlaidQueryParams,
+    filter: &PlaidFilter,
+) -> Result<(Vec<PlaidResult>, PlaidSearchStats)> {
+    if query_flat.is_empty() {
+        return Ok((Vec::new(), PlaidSearchStats::default()));
+    }
+    check_query_dim(index, query_flat)?;
+    search_flat(index, query_flat, top_k, params, Some(filter))
+}
+
+fn check_query_dim(index: &PlaidIndex, query_flat: &[f32]) -> Result<()> {
+    let dim = index.params.dim;
+    if dim == 0 || !query_flat.len().is_multiple_of(dim) {
+        return Err(Error::Config(format!(
+            "PLAID query of {} values is not a whole number of {dim}-dim tokens",
+            query_flat.len(),
+        )));
+    }
+    Ok(())
+}
 
-    let params = SearchParams::paper_defaults(top_k);
-    let out = plaid_search::search(index, &query_flat, params)?;
-    Ok(out
+fn search_flat(
+    index: &PlaidIndex,
+    query_flat: &[f32],
+    top_k: usize,
+    params: &PlaidQueryParams,
+    filter: Option<&PlaidFilter>,
+) -> Result<(Vec<PlaidResult>, PlaidSearchStats)> {
+    params.validate()?;
+    let params = params.resolve(top_k);
+    let (out, stats) = match filter {
+        Some(filter) => index.search_filtered(query_flat, params, filter)?,
+        None => index.search_with_stats(query_flat, params)?,
+    };
+    let results = out
         .into_iter()
         .map(|r| PlaidResult {
             doc_id: r.doc_id,
             score: r.score,
         })
-        .collect())
+        .collect();
+    Ok((results, stats))
+}
+
+/// Decode the tokens the index stores for `doc_id` back into a flat,
+/// row-major `[n_tokens * dim]` matrix.
+///
+/// The residual codec is lossy, so these are approximations of the
+/// original embeddings. Returns `None` when `doc_id` isn't indexed.
+pub fn decode_document_tokens(
+    index: &PlaidIndex,
+    doc_id: u64,
+) -> Result<Option<Vec<f32>>> {
+    let Some((segment, position)) = index.locate(doc_id) else {
+        return Ok(None);
+    };
+    let index = segment.index();
+    let table = DecodeTable::new(&index.codec);
+    let mut flat =
+        Vec::with_capacity(index.doc_token_count(position) * index.params.dim);
+    for token in index.doc_tokens_vec(position) {
+        flat.extend(index.codec.decode_vector_with_table(&token, &table)?);
+    }
+    Ok(Some(flat))
+}
+
+/// Pool the tokens of pseudo-relevance feedback documents into at most
+/// `count` expansion embeddings, ColBERT-PRF style.
+///
+/// `feedback_flat` holds the feedback documents' tokens as one flat,
+/// row-major `[n_tokens * dim]` matrix. The tokens are clustered with
+/// k-means into up to `2 * count` groups; each group is scored by the
+/// IDF of the index centroid nearest to it, so groups sitting in rare
+/// regions of the corpus win over ones close to common tokens. The best
+/// `count` group centroids are L2-normalized and scaled by `weight`,
+/// ready to be appended to a query matrix for [`search_tokens`].
+pub fn prf_expansion_embeddings(
+    index: &PlaidIndex,
+    feedback_flat: &[f32],
+    count: usize,
+    weight: f32,
+) -> Result<Vec<f32>> {
+    let dim = index.params.dim;
+    if count == 0 || dim == 0 || feedback_flat.is_empty() {
+        return Ok(Vec::new());
+    }
+    if !feedback_flat.len().is_multiple_of(dim) {
+        return Err(Error::Config(format!(
+            "PRF feedback of {} values is not a whole number of {dim}-dim tokens",
+            feedback_flat.len(),
+        )));
+    }
+    let n_tokens = feedback_flat.len() / dim;
+    let k = (2 * count).min(n_tokens);
+    let centroids = kmeans::fit(feedback_flat, k, dim, 10)?;
+
+    let n_docs = index.num_documents().max(1) as f32;
+    let mut scored: Vec<(f32, &[f32])> = centroids
+        .chunks_exact(dim)
+        .map(|centroid| {
+            let nearest =
+                kmeans::nearest_centroid(centroid, &index.codec.centroids, dim);
+            let df = index.doc_frequency(nearest).max(1) as f32;
+            ((n_docs / df).ln(), centroid)
+        })
+        .collect();
+    scored.sort_by(|a, b| {
+        b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    let mut expansion = Vec::with_capacity(count.min(k) * dim);
+    for (_, centroid) in scored.into_iter().take(count) {
+        let norm = centroid.iter().map(|v| v * v).sum::<f32>().sqrt();
+        if norm == 0.0 {
+            continue;
+        }
+        expansion.extend(centroid.iter().map(|v| v / norm * weight));
+    }
+    Ok(expansion)
 }
 
 #[cfg(test)]
@@ -368,11 +1008,29 @@ mod tests {
         db.store(3, 1, 2, &[0.05, -0.05]).unwrap();
     }
 
+    /// Sorted ids of the documents `index` still serves.
+    fn live_ids(index: &PlaidIndex) -> Vec<u64> {
+        let mut ids: Vec<u64> = index.live_doc_ids().collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// The stored encoding of `doc_id`, from whichever segment holds it.
+    fn encoded(
+        index: &PlaidIndex,
+        doc_id: u64,
+    ) -> Vec<docbert_plaid::codec::EncodedVector> {
+        let (segment, position) =
+            index.locate(doc_id).expect("document is indexed");
+        segment.index().doc_tokens_vec(position)
+    }
+
     fn small_build_params() -> PlaidBuildParams {
         PlaidBuildParams {
             k_centroids: 2,
             nbits: 2,
             max_kmeans_iters: 50,
+            ..PlaidBuildParams::default()
         }
     }
 
@@ -395,9 +1053,7 @@ mod tests {
         let index =
             build_index_from_embedding_db(&db, small_build_params()).unwrap();
         assert_eq!(index.num_documents(), 3);
-        let mut ids = index.doc_ids.clone();
-        ids.sort();
-        assert_eq!(ids, vec![1, 2, 3]);
+        assert_eq!(live_ids(&index), vec![1, 2, 3]);
     }
 
     #[test]
@@ -432,6 +1088,289 @@ mod tests {
         }
     }
 
+    #[test]
+    fn build_rejects_unsupported_residual_bits() {
+        let tmp = tempfile::tempdir().unwrap();
+        let db = EmbeddingDb::open(&tmp.path().join("emb.db")).unwrap();
+        seed_small_db(&db);
+
+        let mut params = small_build_params();
+        params.nbits = 3;
+        let err = build_index_from_embedding_db(&db, params).unwrap_err();
+        match err {
+            Error::Config(msg) => assert!(msg.contains("3 bits"), "{msg}"),
+            other => panic!("expected Config error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn build_rejects_invalid_kmeans_options() {
+        let tmp = tempfile::tempdir().unwrap();
+        let db = EmbeddingDb::open(&tmp.path().join("emb.db")).unwrap();
+        seed_small_db(&db);
+
+        let invalid = [
+            kmeans::KMeansOptions {
+                mini_batch: Some(0),
+                ..kmeans::KMeansOptions::default()
+            },
+            kmeans::KMeansOptions {
+                sample_tokens: Some(0),
+                ..kmeans::KMeansOptions::default()
+            },
+            kmeans::KMeansOptions {
+                tolerance: Some(-1.0),
+                ..kmeans::KMeansOptions::default()
+            },
+            kmeans::KMeansOptions {
+                tolerance: Some(f32::NAN),
+                ..kmeans::KMeansOptions::default()
+            },
+        ];
+        for options in invalid {
+            let mut params = small_build_params();
+            params.kmeans = options;
+            let err = build_index_from_embedding_db(&db, params).unwrap_err();
+            match err {
+                Error::Config(msg) => assert!(msg.contains("k-means"), "{msg}"),
+                other => panic!("expected Config error, got {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn build_index_accepts_every_kmeans_training_mode() {
+        let tmp = tempfile::tempdir().unwrap();
+        let db = EmbeddingDb::open(&tmp.path().join("emb.db")).unwrap();
+        seed_small_db(&db);
+
+        for init in [
+            kmeans::KMeansInit::Random,
+            kmeans::KMeansInit::PlusPlus,
+            kmeans::KMeansInit::Parallel,
+        ] {
+            let mut params = small_build_params();
+            params.kmeans = kmeans::KMeansOptions {
+                init,
+                mini_batch: Some(2),
+                tolerance: Some(1e-4),
+                sample_tokens: Some(3),
+            };
+            let index = build_index_from_embedding_db(&db, params).unwrap();
+            assert_eq!(live_ids(&index), vec![1, 2, 3], "{init:?}");
+        }
+    }
+
+    #[test]
+    fn overrides_pin_fields_and_pick_the_rest_from_the_corpus() {
+        let auto = PlaidBuildOverrides::default().resolve(10_000);
+        assert_eq!(auto.k_centroids, 1_024);
+        assert_eq!(auto.nbits, PlaidBuildParams::default().nbits);
+
+        let pinned = PlaidBuildOverrides {
+            nbits: Some(4),
+            ..PlaidBuildOverrides::default()
+        }
+        .with(PlaidBuildOverrides {
+            k_centroids: Some(64),
+            ..PlaidBuildOverrides::default()
+        });
+        let params = pinned.resolve(10_000);
+        assert_eq!((params.k_centroids, params.nbits), (64, 4));
+        assert_eq!(pinned.resolve(10).k_centroids, 10);
+    }
+
+    #[test]
+    fn build_overrides_round_trip_through_config_db() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config_db = ConfigDb::open(&tmp.path().join("config.db")).unwrap();
+        assert!(load_build_overrides(&config_db).unwrap().is_empty());
+
+        let overrides = PlaidBuildOverrides {
+            k_centroids: Some(512),
+            nbits: None,
+            max_kmeans_iters: Some(5),
+        };
+        save_build_overrides(&config_db, &overrides).unwrap();
+        assert_eq!(load_build_overrides(&config_db).unwrap(), overrides);
+
+        save_build_overrides(&config_db, &PlaidBuildOverrides::default())
+            .unwrap();
+        assert!(
+            config_db
+                .get_json_setting(PLAID_BUILD_SETTING)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn query_params_override_the_paper_defaults() {
+        let defaults = SearchParams::paper_defaults(10);
+        let resolved = PlaidQueryParams::default().resolve(10);
+        assert_eq!(resolved.n_probe, defaults.n_probe);
+        assert_eq!(resolved.n_candidate_docs, defaults.n_candidate_docs);
+
+        let stored = PlaidQueryParams {
+            n_probe: Some(4),
+            centroid_score_threshold: Some(0.2),
+            ..PlaidQueryParams::default()
+        };
+        let resolved = stored
+            .with(PlaidQueryParams {
+                n_probe: Some(8),
+                n_candidate_docs: Some(16),
+                ..PlaidQueryParams::default()
+            })
+            .resolve(10);
+        assert_eq!(resolved.n_probe, 8);
+        assert_eq!(resolved.centroid_score_threshold, Some(0.2));
+        // Raised so the last interaction stage still keeps `top_k`.
+        assert_eq!(resolved.n_candidate_docs, Some(40));
+
+        let exhaustive = stored
+            .with(PlaidQueryParams {
+                exhaustive: true,
+                ..PlaidQueryParams::default()
+            })
+            .resolve(10);
+        assert_eq!(exhaustive.n_probe, usize::MAX);
+        assert_eq!(exhaustive.n_candidate_docs, None);
+        assert_eq!(exhaustive.centroid_score_threshold, None);
+
+        let exact = PlaidQueryParams {
+            exact: Some(false),
+            ..stored
+        };
+        assert_eq!(exact.with(stored).exact, Some(false));
+        let forced = PlaidQueryParams {
+            exact: Some(true),
+            ..PlaidQueryParams::default()
+        };
+        assert_eq!(exact.with(forced).exact, Some(true));
+    }
+
+    #[test]
+    fn query_params_round_trip_and_reject_invalid_values() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config_db = ConfigDb::open(&tmp.path().join("config.db")).unwrap();
+        assert!(load_query_params(&config_db).unwrap().is_empty());
+
+        let params = PlaidQueryParams {
+            n_probe: Some(4),
+            exhaustive: false,
+            exact: Some(false),
+            ..PlaidQueryParams::default()
+        };
+        save_query_params(&config_db, &params).unwrap();
+        assert_eq!(load_query_params(&config_db).unwrap(), params);
+
+        for invalid in [
+            PlaidQueryParams {
+                n_probe: Some(0),
+                ..PlaidQueryParams::default()
+            },
+            PlaidQueryParams {
+                centroid_score_threshold: Some(f32::NAN),
+                ..PlaidQueryParams::default()
+            },
+        ] {
+            let err = save_query_params(&config_db, &invalid).unwrap_err();
+            assert!(matches!(err, Error::Config(_)), "{err:?}");
+        }
+        assert_eq!(load_query_params(&config_db).unwrap(), params);
+
+        save_query_params(&config_db, &PlaidQueryParams::default()).unwrap();
+        assert!(
+            config_db
+                .get_json_setting(PLAID_QUERY_SETTING)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn exhaustive_search_probes_every_centroid() {
+        let tmp = tempfile::tempdir().unwrap();
+        let db = EmbeddingDb::open(&tmp.path().join("emb.db")).unwrap();
+        seed_small_db(&db);
+        let index =
+            build_index_from_embedding_db(&db, small_build_params()).unwrap();
+        let query = [10.0, 10.0];
+
+        let (_, pruned) = search_tokens_with_stats(
+            &index,
+            &query,
+            3,
+            &PlaidQueryParams::default(),
+        )
+        .unwrap();
+        let (hits, exhaustive) = search_tokens_with_stats(
+            &index,
+            &query,
+            3,
+            &PlaidQueryParams {
+                exhaustive: true,
+                ..PlaidQueryParams::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(pruned.probed_docs, 1);
+        assert_eq!(exhaustive.probed_docs, 3);
+        assert_eq!(hits.len(), 3);
+        assert_eq!(hits[0].doc_id, 2);
+    }
+
+    #[test]
+    fn filter_cache_reuses_filters_until_the_index_changes() {
+        use crate::config_db::DocChunkEntry;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let db = EmbeddingDb::open(&tmp.path().join("emb.db")).unwrap();
+        let config_db = ConfigDb::open(&tmp.path().join("config.db")).unwrap();
+        seed_small_db(&db);
+        // Document 7 is made of chunks 1 and 3.
+        let chunk = |chunk_doc_id| DocChunkEntry {
+            chunk_doc_id,
+            start_byte: 0,
+            byte_len: 1,
+        };
+        config_db.set_doc_chunks(7, &[chunk(1), chunk(3)]).unwrap();
+        let index = Arc::new(
+            build_index_from_embedding_db(&db, small_build_params()).unwrap(),
+        );
+
+        let cache = PlaidFilterCache::new();
+        let build = || documents_filter(&config_db, &index, [7]);
+        let first = cache.get_or_build(&index, "notes", build).unwrap();
+        assert_eq!(first.count(), 2);
+        let again = cache
+            .get_or_build(&index, "notes", || panic!("should be cached"))
+            .unwrap();
+        assert!(Arc::ptr_eq(&first, &again));
+
+        let rebuilt = Arc::new(
+            build_index_from_embedding_db(&db, small_build_params()).unwrap(),
+        );
+        let fresh = cache
+            .get_or_build(&rebuilt, "notes", || {
+                documents_filter(&config_db, &rebuilt, [7])
+            })
+            .unwrap();
+        assert!(!Arc::ptr_eq(&first, &fresh));
+
+        let (hits, _) = search_tokens_filtered(
+            &rebuilt,
+            &[10.0, 10.0],
+            3,
+            &PlaidQueryParams::default(),
+            &fresh,
+        )
+        .unwrap();
+        assert!(hits.iter().all(|hit| hit.doc_id != 2), "{hits:?}");
+    }
+
     #[test]
     fn save_and_load_round_trip() {
         let tmp = tempfile::tempdir().unwrap();
@@ -444,7 +1383,7 @@ mod tests {
         save_index(&index, &data_dir).unwrap();
 
         let loaded = load_index(&data_dir).unwrap().expect("present");
-        assert_eq!(loaded.doc_ids, index.doc_ids);
+        assert_eq!(live_ids(&loaded), live_ids(&index));
         assert_eq!(loaded.codec.centroids, index.codec.centroids);
     }
 
@@ -492,13 +1431,14 @@ mod tests {
         seed_small_db(&db);
         let index =
             build_index_from_embedding_db(&db, small_build_params()).unwrap();
-        let before_ids = index.doc_ids.clone();
+        let before_ids = live_ids(&index);
         let before_codec = index.codec.clone();
 
         let updated =
             update_index_from_embedding_db(&db, index, &[], &[]).unwrap();
 
-        assert_eq!(updated.doc_ids, before_ids);
+        assert_eq!(live_ids(&updated), before_ids);
+        assert_eq!(updated.segments().len(), 1);
         assert_eq!(updated.codec.centroids, before_codec.centroids);
         assert_eq!(updated.codec.bucket_cutoffs, before_codec.bucket_cutoffs);
         assert_eq!(updated.codec.bucket_weights, before_codec.bucket_weights);
@@ -519,7 +1459,7 @@ mod tests {
         let updated =
             update_index_from_embedding_db(&db, index, &[4], &[]).unwrap();
 
-        assert!(updated.doc_ids.contains(&4));
+        assert!(updated.contains(4));
         assert_eq!(updated.codec.centroids, before_codec.centroids);
         assert_eq!(updated.codec.bucket_cutoffs, before_codec.bucket_cutoffs);
         assert_eq!(updated.codec.bucket_weights, before_codec.bucket_weights);
@@ -536,9 +1476,9 @@ mod tests {
         let updated =
             update_index_from_embedding_db(&db, index, &[], &[2]).unwrap();
 
-        assert!(!updated.doc_ids.contains(&2));
-        assert!(updated.doc_ids.contains(&1));
-        assert!(updated.doc_ids.contains(&3));
+        assert!(!updated.contains(2));
+        assert!(updated.contains(1));
+        assert!(updated.contains(3));
     }
 
     #[test]
@@ -548,7 +1488,7 @@ mod tests {
         seed_small_db(&db);
         let index =
             build_index_from_embedding_db(&db, small_build_params()).unwrap();
-        let old_encoded = index.doc_tokens_vec(index.position_of(1).unwrap());
+        let old_encoded = encoded(&index, 1);
 
         // Overwrite doc 1's embedding with tokens from the far cluster.
         // After update, the encoded centroid_id should flip — proving
@@ -559,8 +1499,7 @@ mod tests {
         let updated =
             update_index_from_embedding_db(&db, index, &[1], &[]).unwrap();
 
-        let pos = updated.position_of(1).expect("doc 1 must survive update");
-        let new_encoded = &updated.doc_tokens_vec(pos);
+        let new_encoded = &encoded(&updated, 1);
         assert_eq!(new_encoded.len(), 2);
         assert_ne!(
             *new_encoded, old_encoded,
@@ -622,15 +1561,14 @@ mod tests {
         seed_small_db(&db);
         let index =
             build_index_from_embedding_db(&db, small_build_params()).unwrap();
-        let old_encoded = index.doc_tokens_vec(index.position_of(1).unwrap());
+        let old_encoded = encoded(&index, 1);
 
         db.store(1, 2, 2, &[10.0, 10.0, 10.1, 9.9]).unwrap();
 
         let updated = update_index_with_chunks(&db, index, &[1], &[]).unwrap();
 
-        let pos = updated.position_of(1).expect("chunk 1 survives upsert");
         assert_ne!(
-            updated.doc_tokens_vec(pos),
+            encoded(&updated, 1),
             old_encoded,
             "the touched chunk must be re-encoded with the new tokens",
         );
@@ -647,7 +1585,7 @@ mod tests {
 
         let updated = update_index_with_chunks(&db, index, &[], &[2]).unwrap();
 
-        assert!(!updated.doc_ids.contains(&2));
+        assert!(!updated.contains(2));
         assert_eq!(updated.codec.centroids, before_codec.centroids);
     }
 
@@ -681,21 +1619,77 @@ mod tests {
         seed_small_db(&db);
         let index =
             build_index_from_embedding_db(&db, small_build_params()).unwrap();
-        let untouched_2 = index.doc_tokens_vec(index.position_of(2).unwrap());
-        let untouched_3 = index.doc_tokens_vec(index.position_of(3).unwrap());
+        let untouched_2 = encoded(&index, 2);
+        let untouched_3 = encoded(&index, 3);
 
         db.store(1, 1, 2, &[0.15, -0.15]).unwrap();
 
         let updated = update_index_with_chunks(&db, index, &[1], &[]).unwrap();
 
-        assert_eq!(
-            updated.doc_tokens_vec(updated.position_of(2).unwrap()),
-            untouched_2,
-        );
-        assert_eq!(
-            updated.doc_tokens_vec(updated.position_of(3).unwrap()),
-            untouched_3,
-        );
+        assert_eq!(encoded(&updated, 2), untouched_2,);
+        assert_eq!(encoded(&updated, 3), untouched_3,);
+    }
+
+    #[test]
+    fn saving_an_update_writes_only_the_delta_segment() {
+        let tmp = tempfile::tempdir().unwrap();
+        let data_dir = DataDir::new(tmp.path());
+        let db = EmbeddingDb::open(&data_dir.embeddings_db()).unwrap();
+        seed_small_db(&db);
+        let index =
+            build_index_from_embedding_db(&db, small_build_params()).unwrap();
+        save_index(&index, &data_dir).unwrap();
+        let segments_dir = segment::segments_dir(&data_dir.plaid_index());
+        let files = |dir: &std::path::Path| {
+            let mut names: Vec<_> = std::fs::read_dir(dir)
+                .unwrap()
+                .map(|e| e.unwrap().file_name())
+                .collect();
+            names.sort();
+            names
+        };
+        let before = files(&segments_dir);
+
+        db.store(1, 1, 2, &[0.15, -0.15]).unwrap();
+        let index = load_index(&data_dir).unwrap().unwrap();
+        let updated = update_index_with_chunks(&db, index, &[1], &[]).unwrap();
+        save_index(&updated, &data_dir).unwrap();
+
+        let after = files(&segments_dir);
+        assert_eq!(after.len(), before.len() + 1);
+        assert!(before.iter().all(|name| after.contains(name)));
+        let loaded = load_index(&data_dir).unwrap().unwrap();
+        assert_eq!(loaded.segments().len(), 2);
+        assert_eq!(live_ids(&loaded), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn merge_segments_compacts_the_saved_index() {
+        let tmp = tempfile::tempdir().unwrap();
+        let data_dir = DataDir::new(tmp.path());
+        let db = EmbeddingDb::open(&data_dir.embeddings_db()).unwrap();
+        seed_small_db(&db);
+        let mut index =
+            build_index_from_embedding_db(&db, small_build_params()).unwrap();
+        for id in 4..7 {
+            db.store(id, 1, 2, &[id as f32, 0.0]).unwrap();
+            index = update_index_with_chunks(&db, index, &[id], &[]).unwrap();
+        }
+        save_index(&index, &data_dir).unwrap();
+
+        let policy = PlaidMergePolicy {
+            max_segments: 1,
+            ..PlaidMergePolicy::default()
+        };
+        let merged = spawn_background_merge(data_dir.clone(), policy)
+            .join()
+            .unwrap()
+            .unwrap();
+        assert!(merged);
+        let loaded = load_index(&data_dir).unwrap().unwrap();
+        assert_eq!(loaded.segments().len(), 1);
+        assert_eq!(live_ids(&loaded), vec![1, 2, 3, 4, 5, 6]);
+        assert!(!merge_segments(&data_dir, policy).unwrap());
     }
 
     #[test]
@@ -720,4 +1714,30 @@ mod tests {
             other => panic!("expected Config error, got {other:?}"),
         }
     }
+
+    #[test]
+    fn prf_expansion_embeddings_are_scaled_unit_vectors() {
+        let tmp = tempfile::tempdir().unwrap();
+        let db = EmbeddingDb::open(&tmp.path().join("emb.db")).unwrap();
+        seed_small_db(&db);
+        let index =
+            build_index_from_embedding_db(&db, small_build_params()).unwrap();
+
+        let feedback = [0.0, 1.0, 0.1, 0.9, 3.0, 0.0, 2.9, 0.1];
+        let expansion =
+            prf_expansion_embeddings(&index, &feedback, 1, 0.5).unwrap();
+        assert_eq!(expansion.len(), 2);
+        let norm = expansion.iter().map(|v| v * v).sum::<f32>().sqrt();
+        assert!((norm - 0.5).abs() < 1e-4, "norm {norm}");
+
+        assert!(
+            prf_expansion_embeddings(&index, &feedback, 0, 0.5)
+                .unwrap()
+                .is_empty()
+        );
+        assert!(matches!(
+            prf_expansion_embeddings(&index, &feedback[..3], 1, 0.5),
+            Err(Error::Config(_))
+        ));
+    }
 }
@@ -0,0 +1,323 @@
+//! Tracking how far the PLAID codec has drifted from the corpus.
+//!
+//! `sync` never retrains the PLAID codec: new and changed chunks are
+//! encoded against the centroids and residual buckets learned at the
+//! last full build (see [`crate::plaid::update_index_with_chunks`]). As
+//! the corpus moves away from what the codec was trained on, compression
+//! gets lossier and a few centroids collect most of the tokens.
+//!
+//! [`PlaidDrift`] records two measurements from `docbert_plaid::drift`
+//! for the current codec:
+//!
+//! - the mean residual reconstruction error of a sample of the corpus at
+//!   build time (the baseline) and of a sample of every chunk encoded by
+//!   an update since;
+//! - the centroid-assignment imbalance of the index at build time and
+//!   after the latest update.
+//!
+//! The record lives in `config.db` under [`PLAID_DRIFT_SETTING`]. Once
+//! either ratio crosses its [`DriftThresholds`] limit, callers should run
+//! a full build ([`crate::plaid::build_index_from_embedding_db`]).
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use docbert_plaid::drift::{self, DEFAULT_SAMPLE_TOKENS, ErrorSample};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config_db::ConfigDb,
+    embedding_db::EmbeddingDb,
+    error::{Error, Result},
+    plaid::PlaidIndex,
+};
+
+/// Settings key the latest [`PlaidDrift`] is stored under.
+pub const PLAID_DRIFT_SETTING: &str = "plaid_drift";
+
+/// Most embedding entries read to take one error sample.
+const MAX_SAMPLED_ENTRIES: usize = 64;
+
+/// Drift measurements for one trained PLAID codec.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PlaidDrift {
+    /// `codec_id` of the index the measurements belong to.
+    pub codec_id: u64,
+    /// Mean reconstruction error of corpus tokens when the codec was
+    /// measured first, normally right after the build.
+    pub baseline_error: f32,
+    /// Centroid-assignment imbalance at the same point.
+    pub baseline_imbalance: f32,
+    /// Mean reconstruction error over the tokens sampled from every
+    /// update since, or `None` before the first update.
+    pub update_error: Option<f32>,
+    /// Number of tokens `update_error` was averaged over.
+    pub update_sampled_tokens: usize,
+    /// Centroid-assignment imbalance after the latest update.
+    pub imbalance: f32,
+    /// Unix timestamp (seconds) of the latest measurement.
+    pub measured_at: u64,
+}
+
+/// Drift ratios past which the PLAID index should be retrained.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DriftThresholds {
+    /// Largest acceptable [`PlaidDrift::error_ratio`].
+    pub max_error_ratio: f32,
+    /// Largest acceptable [`PlaidDrift::imbalance_ratio`].
+    pub max_imbalance_ratio: f32,
+}
+
+impl Default for DriftThresholds {
+    fn default() -> Self {
+        Self {
+            max_error_ratio: 1.5,
+            max_imbalance_ratio: 2.0,
+        }
+    }
+}
+
+impl PlaidDrift {
+    /// How many times worse updated tokens reconstruct than the
+    /// baseline did, or `None` when no update has been measured.
+    pub fn error_ratio(&self) -> Option<f32> {
+        let update = self.update_error?;
+        Some(ratio(update, self.baseline_error))
+    }
+
+    /// How many times more imbalanced the centroid assignment is than
+    /// at the baseline.
+    pub fn imbalance_ratio(&self) -> f32 {
+        ratio(self.imbalance, self.baseline_imbalance)
+    }
+
+    /// Whether either ratio is past its limit in `thresholds`.
+    pub fn exceeds(&self, thresholds: DriftThresholds) -> bool {
+        self.error_ratio()
+            .is_some_and(|r| r > thresholds.max_error_ratio)
+            || self.imbalance_ratio() > thresholds.max_imbalance_ratio
+    }
+}
+
+/// `value / baseline`, treating a zero baseline as "no change" unless
+/// the value moved off zero too.
+fn ratio(value: f32, baseline: f32) -> f32 {
+    match (value, baseline) {
+        (v, b) if b > 0.0 => v / b,
+        (v, _) if v > 0.0 => f32::INFINITY,
+        _ => 1.0,
+    }
+}
+
+/// Take the baseline measurements for a freshly built `index`.
+///
+/// The error is sampled from the corpus in `embedding_db`, which is what
+/// the codec was just trained on.
+///
+/// # Errors
+///
+/// Returns an error if reading `embedding_db` fails.
+pub fn measure_after_build(
+    embedding_db: &EmbeddingDb,
+    index: &PlaidIndex,
+) -> Result<PlaidDrift> {
+    let ids: Vec<u64> = embedding_db
+        .list_shapes()?
+        .into_iter()
+        .map(|(id, _, _)| id)
+        .collect();
+    let baseline = sample_error(embedding_db, index, &ids)?;
+    let imbalance = drift::assignment_imbalance(&index.centroid_token_counts());
+    Ok(PlaidDrift {
+        codec_id: index.codec_id(),
+        baseline_error: baseline.map_or(0.0, |s| s.mean_error),
+        baseline_imbalance: imbalance,
+        update_error: None,
+        update_sampled_tokens: 0,
+        imbalance,
+        measured_at: now(),
+    })
+}
+
+/// Fold an update that encoded `upserted_ids` into `index` into the
+/// measurements of `previous`.
+///
+/// When `previous` is missing or belongs to another codec (the index
+/// was built by something that doesn't record drift), a baseline is
+/// taken first from the whole corpus.
+///
+/// # Errors
+///
+/// Returns an error if reading `embedding_db` fails.
+pub fn measure_after_update(
+    embedding_db: &EmbeddingDb,
+    index: &PlaidIndex,
+    previous: Option<PlaidDrift>,
+    upserted_ids: &[u64],
+) -> Result<PlaidDrift> {
+    let mut drift = match previous {
+        Some(drift) if drift.codec_id == index.codec_id() => drift,
+        _ => measure_after_build(embedding_db, index)?,
+    };
+    if let Some(sample) = sample_error(embedding_db, index, upserted_ids)? {
+        let before = drift.update_error.map(|mean_error| ErrorSample {
+            tokens: drift.update_sampled_tokens,
+            mean_error,
+        });
+        let merged = before.map_or(sample, |before| before.merge(sample));
+        drift.update_error = Some(merged.mean_error);
+        drift.update_sampled_tokens = merged.tokens;
+    }
+    drift.imbalance =
+        drift::assignment_imbalance(&index.centroid_token_counts());
+    drift.measured_at = now();
+    Ok(drift)
+}
+
+/// Mean reconstruction error under `index`'s codec of tokens read from
+/// up to [`MAX_SAMPLED_ENTRIES`] of the embeddings in `ids`, spread
+/// evenly across the list.
+fn sample_error(
+    embedding_db: &EmbeddingDb,
+    index: &PlaidIndex,
+    ids: &[u64],
+) -> Result<Option<ErrorSample>> {
+    let dim = index.params.dim;
+    let take = ids.len().min(MAX_SAMPLED_ENTRIES);
+    let mut tokens = Vec::new();
+    for i in 0..take {
+        let id = ids[i * ids.len() / take];
+        // Entries of another dimension can't be encoded by this codec;
+        // the update itself rejects them.
+        if let Some(matrix) = embedding_db.load(id)?
+            && matrix.dimension as usize == dim
+        {
+            tokens.extend_from_slice(&matrix.data);
+        }
+    }
+    Ok(drift::sample_reconstruction_error(
+        &index.codec,
+        &tokens,
+        DEFAULT_SAMPLE_TOKENS,
+    )?)
+}
+
+/// The stored measurements for `index`'s codec, if there are any.
+///
+/// # Errors
+///
+/// Returns an error if reading `config_db` fails.
+pub fn load(
+    config_db: &ConfigDb,
+    index: &PlaidIndex,
+) -> Result<Option<PlaidDrift>> {
+    Ok(load_latest(config_db)?.filter(|d| d.codec_id == index.codec_id()))
+}
+
+/// The most recently stored measurements, whichever codec they belong
+/// to. A record that no longer parses counts as missing.
+///
+/// # Errors
+///
+/// Returns an error if reading `config_db` fails.
+pub fn load_latest(config_db: &ConfigDb) -> Result<Option<PlaidDrift>> {
+    Ok(config_db
+        .get_json_setting(PLAID_DRIFT_SETTING)?
+        .and_then(|value| serde_json::from_value(value).ok()))
+}
+
+/// Store `drift` as the latest measurements.
+///
+/// # Errors
+///
+/// Returns an error if writing `config_db` fails.
+pub fn save(config_db: &ConfigDb, drift: &PlaidDrift) -> Result<()> {
+    let value = serde_json::to_value(drift).map_err(|e| {
+        Error::Config(format!("failed to serialize PLAID drift: {e}"))
+    })?;
+    config_db.set_json_setting(PLAID_DRIFT_SETTING, &value)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plaid::{self, PlaidBuildParams};
+
+    fn build(embedding_db: &EmbeddingDb) -> PlaidIndex {
+        embedding_db.store(1, 2, 2, &[0.0, 0.0, 0.1, 0.1]).unwrap();
+        embedding_db
+            .store(2, 2, 2, &[10.0, 10.0, 10.1, 9.9])
+            .unwrap();
+        embedding_db.store(3, 1, 2, &[0.05, -0.05]).unwrap();
+        plaid::build_index_from_embedding_db(
+            embedding_db,
+            PlaidBuildParams {
+                k_centroids: 2,
+                nbits: 2,
+                max_kmeans_iters: 50,
+                ..PlaidBuildParams::default()
+            },
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn updates_far_from_the_codec_raise_the_error_ratio() {
+        let tmp = tempfile::tempdir().unwrap();
+        let db = EmbeddingDb::open(&tmp.path().join("emb.db")).unwrap();
+        let index = build(&db);
+        let baseline = measure_after_build(&db, &index).unwrap();
+        assert_eq!(baseline.codec_id, index.codec_id());
+        assert!(baseline.error_ratio().is_none());
+        assert!(!baseline.exceeds(DriftThresholds::default()));
+
+        db.store(4, 2, 2, &[-40.0, 55.0, 60.0, -35.0]).unwrap();
+        let index =
+            plaid::update_index_with_chunks(&db, index, &[4], &[]).unwrap();
+        let drift =
+            measure_after_update(&db, &index, Some(baseline), &[4]).unwrap();
+        assert_eq!(drift.baseline_error, baseline.baseline_error);
+        assert_eq!(drift.update_sampled_tokens, 2);
+        assert!(drift.error_ratio().unwrap() > 10.0, "{drift:?}");
+        assert!(drift.exceeds(DriftThresholds::default()));
+    }
+
+    #[test]
+    fn update_without_a_baseline_for_the_codec_takes_one() {
+        let tmp = tempfile::tempdir().unwrap();
+        let db = EmbeddingDb::open(&tmp.path().join("emb.db")).unwrap();
+        let index = build(&db);
+        let stale = PlaidDrift {
+            codec_id: index.codec_id() ^ 1,
+            ..measure_after_build(&db, &index).unwrap()
+        };
+
+        let drift =
+            measure_after_update(&db, &index, Some(stale), &[]).unwrap();
+        assert_eq!(drift.codec_id, index.codec_id());
+        assert!(drift.update_error.is_none());
+        assert_eq!(drift.imbalance_ratio(), 1.0);
+    }
+
+    #[test]
+    fn save_and_load_round_trip_per_codec() {
+        let tmp = tempfile::tempdir().unwrap();
+        let db = EmbeddingDb::open(&tmp.path().join("emb.db")).unwrap();
+        let config_db = ConfigDb::open(&tmp.path().join("config.db")).unwrap();
+        let index = build(&db);
+        assert!(load(&config_db, &index).unwrap().is_none());
+
+        let drift = measure_after_build(&db, &index).unwrap();
+        save(&config_db, &drift).unwrap();
+        assert_eq!(load(&config_db, &index).unwrap(), Some(drift));
+
+        let rebuilt = build(&db);
+        assert!(load(&config_db, &rebuilt).unwrap().is_none());
+        assert_eq!(load_latest(&config_db).unwrap(), Some(drift));
+    }
+}
@@ -14,6 +14,8 @@ use crate::{
 pub struct MarkdownBody {
     pub title: String,
     pub searchable_body: String,
+    /// Tags from the frontmatter's `tags` key.
+    pub tags: Vec<String>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -25,6 +27,8 @@ pub struct SearchDocument {
     pub raw_content: Option<String>,
     pub metadata: Option<serde_json::Value>,
     pub mtime: u64,
+    /// Tags from the frontmatter's `tags` key, indexed for facets.
+    pub tags: Vec<String>,
 }
 
 pub fn markdown(relative_path: &Path, raw_markdown: &str) -> MarkdownBody {
@@ -35,6 +39,7 @@ pub fn markdown(relative_path: &Path, raw_markdown: &str) -> MarkdownBody {
     MarkdownBody {
         title,
         searchable_body,
+        tags: text::yaml_frontmatter_tags(raw_markdown),
     }
 }
 
@@ -56,6 +61,7 @@ pub fn uploaded(
         raw_content: Some(raw_markdown.to_string()),
         metadata,
         mtime,
+        tags: prepared.tags,
     }
 }
 
@@ -77,6 +83,7 @@ pub fn filesystem(
         raw_content: None,
         metadata: None,
         mtime,
+        tags: prepared.tags,
     }
 }
 
@@ -50,7 +50,7 @@ const MIGRATION_EMBEDDING_MAP_SIZE: usize = 64 * 1024 * 1024 * 1024; // 64 GiB
 
 /// Number of named heed databases the config env can hold.
 /// Keep in sync with [`crate::config_db`].
-pub(crate) const CONFIG_MAX_DBS: u32 = 8;
+pub(crate) const CONFIG_MAX_DBS: u32 = 9;
 
 // NOTE: the legacy `chunk_offsets` redb table is intentionally not
 // migrated. The current schema uses content-derived chunk ids and a
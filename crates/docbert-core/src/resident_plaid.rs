@@ -0,0 +1,295 @@
+//! A PLAID index kept in memory by long-lived runtimes.
+//!
+//! Opening the index memory-maps its segments, which is cheap, but a fresh
+//! mapping starts cold: each query would copy the codec again and fault
+//! in the pages it touches. The web and MCP servers instead attach one [`ResidentPlaid`]
+//! to their [`DataDir`] with [`DataDir::with_resident_plaid`]; searches
+//! then load through [`crate::plaid::open_index`], which hands out the
+//! resident copy.
+//!
+//! The handle notices a new index by comparing the `plaid.idx`
+//! manifest's modification time, length and inode with the ones it
+//! loaded; every save, including a merge's, replaces the manifest. A `sync` in another process
+//! (or any other writer going through [`crate::plaid::save_index`],
+//! which replaces the file atomically) is picked up by the next search,
+//! and the swap bumps [`ResidentPlaid::generation`]. Searches already
+//! holding the previous index finish on it.
+
+use std::{
+    fmt,
+    path::Path,
+    sync::{
+        Arc,
+        RwLock,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::SystemTime,
+};
+
+use crate::{
+    data_dir::DataDir,
+    error::Result,
+    plaid::{PlaidFilterCache, PlaidIndex},
+};
+
+/// What identifies one version of `plaid.idx` on disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileStamp {
+    modified: Option<SystemTime>,
+    len: u64,
+    /// Inode number where available. `save_index` renames a fresh file
+    /// into place, so a rewrite changes it even within one mtime tick.
+    inode: u64,
+}
+
+impl FileStamp {
+    /// Stamp of the file at `path`, or `None` when it doesn't exist.
+    fn of(path: &Path) -> Result<Option<Self>> {
+        match std::fs::metadata(path) {
+            Ok(meta) => Ok(Some(Self {
+                modified: meta.modified().ok(),
+                len: meta.len(),
+                inode: inode(&meta),
+            })),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn inode(meta: &std::fs::Metadata) -> u64 {
+    std::os::unix::fs::MetadataExt::ino(meta)
+}
+
+#[cfg(not(unix))]
+fn inode(_meta: &std::fs::Metadata) -> u64 {
+    0
+}
+
+struct Loaded {
+    index: Arc<PlaidIndex>,
+    stamp: FileStamp,
+}
+
+/// Shared, hot-reloadable in-memory PLAID index.
+///
+/// Safe to share between threads. Readers take a cheap `Arc` clone of
+/// the current index; a reload swaps the whole index at once.
+///
+/// # Examples
+///
+/// ```
+/// # let tmp = tempfile::tempdir().unwrap();
+/// use docbert_core::DataDir;
+/// use docbert_core::resident_plaid::ResidentPlaid;
+///
+/// let data_dir = DataDir::new(tmp.path());
+/// let resident = ResidentPlaid::new();
+/// // No index has been built yet.
+/// assert!(resident.get(&data_dir).unwrap().is_none());
+/// assert_eq!(resident.generation(), 0);
+/// ```
+#[derive(Default)]
+pub struct ResidentPlaid {
+    loaded: RwLock<Option<Loaded>>,
+    generation: AtomicU64,
+    filters: PlaidFilterCache,
+}
+
+impl fmt::Debug for ResidentPlaid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResidentPlaid")
+            .field("generation", &self.generation())
+            .field("loaded", &self.is_loaded())
+            .finish()
+    }
+}
+
+impl ResidentPlaid {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The current index under `data_dir`, loading it when the file is
+    /// new or has changed since the last load.
+    ///
+    /// Returns `Ok(None)` when no index has been built. If a changed
+    /// file fails to load while an older index is resident, the older
+    /// index keeps serving and the load is retried on the next call.
+    pub fn get(&self, data_dir: &DataDir) -> Result<Option<Arc<PlaidIndex>>> {
+        let path = data_dir.plaid_index();
+        let Some(stamp) = FileStamp::of(&path)? else {
+            self.evict();
+            return Ok(None);
+        };
+
+        if let Some(loaded) = self.read().as_ref()
+            && loaded.stamp == stamp
+        {
+            return Ok(Some(Arc::clone(&loaded.index)));
+        }
+
+        let mut slot = self.write();
+        // Another thread may have reloaded while this one waited.
+        if let Some(loaded) = slot.as_ref()
+            && loaded.stamp == stamp
+        {
+            return Ok(Some(Arc::clone(&loaded.index)));
+        }
+        match PlaidIndex::load(&path) {
+            Ok(index) => {
+                let index = Arc::new(index);
+                *slot = Some(Loaded {
+                    index: Arc::clone(&index),
+                    stamp,
+                });
+                let generation =
+                    self.generation.fetch_add(1, Ordering::AcqRel) + 1;
+                tracing::info!(
+                    generation,
+                    documents = index.num_documents(),
+                    "loaded resident PLAID index"
+                );
+                Ok(Some(index))
+            }
+            Err(err) => match slot.as_ref() {
+                Some(loaded) => {
+                    tracing::warn!(
+                        %err,
+                        "could not reload PLAID index; serving the previous one"
+                    );
+                    Ok(Some(Arc::clone(&loaded.index)))
+                }
+                None => Err(err.into()),
+            },
+        }
+    }
+
+    /// Per-collection search filters for the resident index. Entries
+    /// built for an index that has since been swapped out are dropped
+    /// the next time a filter is asked for.
+    pub fn filters(&self) -> &PlaidFilterCache {
+        &self.filters
+    }
+
+    /// Number of times an index has been loaded; changes whenever a new
+    /// index is swapped in.
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// Whether an index is currently held in memory.
+    pub fn is_loaded(&self) -> bool {
+        self.read().is_some()
+    }
+
+    /// Drop the resident index so the next [`get`](Self::get) reads the
+    /// file again, even if it looks unchanged.
+    pub fn invalidate(&self) {
+        self.evict();
+    }
+
+    fn evict(&self) {
+        if self.read().is_some() {
+            *self.write() = None;
+        }
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, Option<Loaded>> {
+        self.loaded
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, Option<Loaded>> {
+        self.loaded
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        embedding_db::EmbeddingDb,
+        plaid::{self, PlaidBuildParams},
+    };
+
+    fn build(data_dir: &DataDir, doc_ids: &[u64]) -> PlaidIndex {
+        let db = EmbeddingDb::open(&data_dir.embeddings_db()).unwrap();
+        for &id in doc_ids {
+            let base = id as f32;
+            db.store(id, 2, 2, &[base, base, base + 0.1, base - 0.1])
+                .unwrap();
+        }
+        plaid::build_index_from_embedding_db(
+            &db,
+            PlaidBuildParams {
+                k_centroids: 2,
+                nbits: 2,
+                max_kmeans_iters: 20,
+                ..PlaidBuildParams::default()
+            },
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn get_reuses_the_loaded_index_until_the_file_changes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let data_dir = DataDir::new(tmp.path());
+        plaid::save_index(&build(&data_dir, &[1, 2]), &data_dir).unwrap();
+        let resident = ResidentPlaid::new();
+
+        let first = resident.get(&data_dir).unwrap().unwrap();
+        let again = resident.get(&data_dir).unwrap().unwrap();
+        assert!(Arc::ptr_eq(&first, &again));
+        assert_eq!(resident.generation(), 1);
+
+        plaid::save_index(&build(&data_dir, &[1, 2, 3]), &data_dir).unwrap();
+        let swapped = resident.get(&data_dir).unwrap().unwrap();
+        assert_eq!(swapped.num_documents(), 3);
+        assert_eq!(resident.generation(), 2);
+        // A search holding the old index keeps it intact.
+        assert_eq!(first.num_documents(), 2);
+
+        resident.invalidate();
+        assert!(!resident.is_loaded());
+        resident.get(&data_dir).unwrap().unwrap();
+        assert_eq!(resident.generation(), 3);
+    }
+
+    #[test]
+    fn get_keeps_serving_the_old_index_when_a_reload_fails() {
+        let tmp = tempfile::tempdir().unwrap();
+        let data_dir = DataDir::new(tmp.path());
+        plaid::save_index(&build(&data_dir, &[1, 2]), &data_dir).unwrap();
+        let resident = ResidentPlaid::new();
+        resident.get(&data_dir).unwrap().unwrap();
+
+        std::fs::write(data_dir.plaid_index(), b"not an index").unwrap();
+        let served = resident.get(&data_dir).unwrap().unwrap();
+        assert_eq!(served.num_documents(), 2);
+        assert_eq!(resident.generation(), 1);
+
+        // Without a resident copy the broken file is an error.
+        assert!(ResidentPlaid::new().get(&data_dir).is_err());
+    }
+
+    #[test]
+    fn get_drops_the_index_once_the_file_is_removed() {
+        let tmp = tempfile::tempdir().unwrap();
+        let data_dir = DataDir::new(tmp.path());
+        plaid::save_index(&build(&data_dir, &[1, 2]), &data_dir).unwrap();
+        let resident = Arc::new(ResidentPlaid::new());
+        let data_dir = data_dir.with_resident_plaid(Arc::clone(&resident));
+        assert!(plaid::open_index(&data_dir).unwrap().is_some());
+        assert!(resident.is_loaded());
+
+        std::fs::remove_file(data_dir.plaid_index()).unwrap();
+        assert!(plaid::open_index(&data_dir).unwrap().is_none());
+        assert!(!resident.is_loaded());
+    }
+}
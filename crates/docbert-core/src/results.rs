@@ -47,6 +47,10 @@ mod tests {
             path: format!("{doc_num_id}.md"),
             title: format!("Doc {doc_num_id}"),
             best_chunk_doc_id: None,
+            rerank_score: None,
+            passages: Vec::new(),
+            token_matches: Vec::new(),
+            explain: None,
         }
     }
 
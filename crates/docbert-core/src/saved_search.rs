@@ -0,0 +1,523 @@
+//! Saved searches and the documents that newly reach their results.
+//!
+//! A [`SavedSearch`] is a named query stored in [`ConfigDb`]. [`refresh`]
+//! runs every saved search again and compares its top `count` documents
+//! with the ones the previous run returned. Documents that weren't there
+//! before are kept on the search as [`SavedSearchHit`]s until
+//! [`clear_new_hits`] drops them, so a caller can ask "what showed up
+//! since I last looked?" after any number of syncs.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use crate::{
+    config_db::ConfigDb,
+    data_dir::DataDir,
+    error::{Error, Result},
+    model_manager::ModelManager,
+    search::{self, FinalResult, SearchMode, SearchQuery},
+    tantivy_index::SearchIndex,
+};
+
+/// Default number of top results a saved search watches.
+pub const DEFAULT_SAVED_SEARCH_COUNT: usize = 10;
+
+/// A named query whose top results are tracked across syncs.
+///
+/// # Examples
+///
+/// ```
+/// use docbert_core::saved_search::SavedSearch;
+/// use docbert_core::search::SearchMode;
+///
+/// let saved = SavedSearch::new(
+///     "plaid",
+///     "plaid index compression",
+///     SearchMode::Hybrid,
+///     None,
+///     10,
+/// )
+/// .unwrap();
+/// assert_eq!(saved.mode(), SearchMode::Hybrid);
+/// assert!(saved.last_run_at.is_none());
+/// ```
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Serialize,
+    rkyv::Archive,
+    rkyv::Serialize,
+    rkyv::Deserialize,
+)]
+pub struct SavedSearch {
+    /// Unique name the search is stored and managed under.
+    pub name: String,
+    /// The search query.
+    pub query: String,
+    /// Retrieval mode, as [`SearchMode::as_str`] spells it.
+    pub mode: String,
+    /// Only search this collection.
+    pub collection: Option<String>,
+    /// Number of top results watched for newcomers.
+    pub count: usize,
+    /// Unix seconds when the search was saved.
+    pub created_at: u64,
+    /// Unix seconds of the last recorded run. `None` until the first
+    /// run, which only takes a baseline.
+    pub last_run_at: Option<u64>,
+    /// Numeric ids of the documents the last run returned, best first.
+    pub top: Vec<u64>,
+    /// Documents that entered the results since hits were last cleared,
+    /// oldest first.
+    pub new_hits: Vec<SavedSearchHit>,
+}
+
+/// A document that entered a saved search's top results.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Serialize,
+    rkyv::Archive,
+    rkyv::Serialize,
+    rkyv::Deserialize,
+)]
+pub struct SavedSearchHit {
+    /// Name of the saved search the document entered.
+    pub search: String,
+    /// Numeric document identifier.
+    pub doc_num_id: u64,
+    pub collection: String,
+    pub path: String,
+    pub title: String,
+    /// Rank the document entered at.
+    pub rank: usize,
+    /// Score the document entered with.
+    pub score: f32,
+    /// Unix seconds of the run that first returned the document.
+    pub found_at: u64,
+}
+
+impl SavedSearch {
+    /// Create a saved search that hasn't run yet.
+    ///
+    /// Fails with [`Error::Config`] for an empty name or query, or a
+    /// `count` of zero.
+    pub fn new(
+        name: &str,
+        query: &str,
+        mode: SearchMode,
+        collection: Option<String>,
+        count: usize,
+    ) -> Result<Self> {
+        if name.trim().is_empty() {
+            return Err(Error::Config(
+                "saved search name must not be empty".to_string(),
+            ));
+        }
+        if query.trim().is_empty() {
+            return Err(Error::Config(
+                "saved search query must not be empty".to_string(),
+            ));
+        }
+        if count == 0 {
+            return Err(Error::Config(
+                "saved search count must be at least 1".to_string(),
+            ));
+        }
+        Ok(Self {
+            name: name.to_string(),
+            query: query.to_string(),
+            mode: mode.as_str().to_string(),
+            collection,
+            count,
+            created_at: unix_now(),
+            last_run_at: None,
+            top: Vec::new(),
+            new_hits: Vec::new(),
+        })
+    }
+
+    /// The retrieval mode, falling back to [`SearchMode::Hybrid`] for an
+    /// unknown stored value.
+    pub fn mode(&self) -> SearchMode {
+        SearchMode::parse(&self.mode).unwrap_or(SearchMode::Hybrid)
+    }
+
+    /// The request [`run`] searches with.
+    pub fn search_query(&self) -> SearchQuery {
+        SearchQuery {
+            query: self.query.clone(),
+            collection: self.collection.clone(),
+            count: self.count,
+            min_score: 0.0,
+            passages: 0,
+            explain: false,
+            prf: None,
+            facets: false,
+            offset: 0,
+            cursor: None,
+            autocorrect: false,
+            plaid: Default::default(),
+        }
+    }
+}
+
+/// Run a saved search and return its current top results, without
+/// recording anything.
+pub fn run(
+    saved: &SavedSearch,
+    search_index: &SearchIndex,
+    config_db: &ConfigDb,
+    data_dir: &DataDir,
+    model: &mut ModelManager,
+) -> Result<Vec<FinalResult>> {
+    search::by_mode(
+        saved.mode(),
+        &saved.search_query(),
+        search_index,
+        config_db,
+        data_dir,
+        model,
+    )
+}
+
+/// Record a run's results on `saved` and return the documents that
+/// weren't in the previous run's results.
+///
+/// The first run only takes a baseline and returns nothing. A document
+/// already waiting in [`SavedSearch::new_hits`] isn't added twice.
+///
+/// # Examples
+///
+/// ```
+/// use docbert_core::saved_search::{SavedSearch, record};
+/// use docbert_core::search::SearchMode;
+///
+/// let mut saved =
+///     SavedSearch::new("plaid", "plaid", SearchMode::Bm25, None, 10).unwrap();
+/// assert!(record(&mut saved, &[], 1_700_000_000).is_empty());
+/// assert_eq!(saved.last_run_at, Some(1_700_000_000));
+/// ```
+pub fn record(
+    saved: &mut SavedSearch,
+    results: &[FinalResult],
+    now: u64,
+) -> Vec<SavedSearchHit> {
+    let baseline = saved.last_run_at.is_none();
+    let mut entered = Vec::new();
+    for result in results.iter().take(saved.count) {
+        if baseline
+            || saved.top.contains(&result.doc_num_id)
+            || saved
+                .new_hits
+                .iter()
+                .any(|hit| hit.doc_num_id == result.doc_num_id)
+        {
+            continue;
+        }
+        entered.push(SavedSearchHit {
+            search: saved.name.clone(),
+            doc_num_id: result.doc_num_id,
+            collection: result.collection.clone(),
+            path: result.path.clone(),
+            title: result.title.clone(),
+            rank: result.rank,
+            score: result.score,
+            found_at: now,
+        });
+    }
+
+    saved.top = results
+        .iter()
+        .take(saved.count)
+        .map(|result| result.doc_num_id)
+        .collect();
+    saved.last_run_at = Some(now);
+    saved.new_hits.extend(entered.iter().cloned());
+    entered
+}
+
+/// Run every saved search, record what entered its results, and store
+/// it back.
+///
+/// Each search runs without holding a transaction; its results are then
+/// recorded on the stored search within one
+/// [`ConfigDb::update_saved_search`] transaction, so hits cleared or a
+/// search redefined or removed while it ran are not overwritten.
+/// A search that fails (for instance a semantic search before the PLAID
+/// index is built) is logged and left unchanged; the others still run.
+/// Returns the hits recorded by this refresh across all searches.
+///
+/// # Examples
+///
+/// ```no_run
+/// use docbert_core::{ConfigDb, DataDir, ModelManager, SearchIndex};
+/// use docbert_core::saved_search;
+///
+/// # let tmp = tempfile::tempdir().unwrap();
+/// let data_dir = DataDir::new(tmp.path());
+/// let config_db = ConfigDb::open(&data_dir.config_db()).unwrap();
+/// let index = SearchIndex::open(&data_dir.tantivy_dir().unwrap()).unwrap();
+/// let mut model = ModelManager::new();
+///
+/// for hit in
+///     saved_search::refresh(&index, &config_db, &data_dir, &mut model).unwrap()
+/// {
+///     println!("{}: {}:{}", hit.search, hit.collection, hit.path);
+/// }
+/// ```
+pub fn refresh(
+    search_index: &SearchIndex,
+    config_db: &ConfigDb,
+    data_dir: &DataDir,
+    model: &mut ModelManager,
+) -> Result<Vec<SavedSearchHit>> {
+    let now = unix_now();
+    let mut recorded = Vec::new();
+    for saved in config_db.list_saved_searches()? {
+        let results =
+            match run(&saved, search_index, config_db, data_dir, model) {
+                Ok(results) => results,
+                Err(err) => {
+                    tracing::warn!(
+                        search = %saved.name,
+                        %err,
+                        "saved search skipped"
+                    );
+                    continue;
+                }
+            };
+        let entered =
+            config_db.update_saved_search(&saved.name, |current| {
+                if same_definition(current, &saved) {
+                    record(current, &results, now)
+                } else {
+                    Vec::new()
+                }
+            })?;
+        recorded.extend(entered.unwrap_or_default());
+    }
+    Ok(recorded)
+}
+
+/// Whether `a` and `b` search for the same thing, so results of one
+/// can be recorded on the other.
+fn same_definition(a: &SavedSearch, b: &SavedSearch) -> bool {
+    a.query == b.query
+        && a.mode == b.mode
+        && a.collection == b.collection
+        && a.count == b.count
+}
+
+/// Drop the waiting new hits of one saved search, or of all of them
+/// when `name` is `None`, and return how many were dropped.
+///
+/// Fails with [`Error::NotFound`] when `name` isn't a saved search.
+pub fn clear_new_hits(
+    config_db: &ConfigDb,
+    name: Option<&str>,
+) -> Result<usize> {
+    let clear =
+        |saved: &mut SavedSearch| std::mem::take(&mut saved.new_hits).len();
+    match name {
+        Some(name) => {
+            config_db.update_saved_search(name, clear)?.ok_or_else(|| {
+                Error::NotFound {
+                    kind: "saved search",
+                    name: name.to_string(),
+                }
+            })
+        }
+        None => {
+            let mut cleared = 0;
+            for saved in config_db.list_saved_searches()? {
+                if saved.new_hits.is_empty() {
+                    continue;
+                }
+                cleared += config_db
+                    .update_saved_search(&saved.name, clear)?
+                    .unwrap_or(0);
+            }
+            Ok(cleared)
+        }
+    }
+}
+
+/// The waiting new hits of one saved search, or of all of them when
+/// `name` is `None`, in search-name order.
+///
+/// Fails with [`Error::NotFound`] when `name` isn't a saved search.
+pub fn new_hits(
+    config_db: &ConfigDb,
+    name: Option<&str>,
+) -> Result<Vec<SavedSearchHit>> {
+    match name {
+        Some(name) => config_db
+            .get_saved_search(name)?
+            .map(|saved| saved.new_hits)
+            .ok_or_else(|| Error::NotFound {
+                kind: "saved search",
+                name: name.to_string(),
+            }),
+        None => Ok(config_db
+            .list_saved_searches()?
+            .into_iter()
+            .flat_map(|saved| saved.new_hits)
+            .collect()),
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::doc_id::DocumentId;
+
+    fn setup() -> (SearchIndex, DataDir, ConfigDb, tempfile::TempDir) {
+        let tmp = tempfile::tempdir().unwrap();
+        let data_dir = DataDir::new(tmp.path());
+        let config_db = ConfigDb::open(&data_dir.config_db()).unwrap();
+        config_db
+            .set_collection("notes", tmp.path().to_str().unwrap())
+            .unwrap();
+        let idx = SearchIndex::open_in_ram().unwrap();
+        add(&idx, "ownership.md", "Ownership and the borrow checker.");
+        add(&idx, "pasta.md", "Simmer the pasta sauce slowly.");
+        (idx, data_dir, config_db, tmp)
+    }
+
+    fn add(idx: &SearchIndex, path: &str, body: &str) {
+        let mut writer = idx.writer(15_000_000).unwrap();
+        let did = DocumentId::new("notes", path);
+        idx.add_document(
+            &writer,
+            &did.full_hex(),
+            did.numeric,
+            "notes",
+            path,
+            path,
+            body,
+            1,
+        )
+        .unwrap();
+        writer.commit().unwrap();
+    }
+
+    fn save(config_db: &ConfigDb, name: &str, query: &str) {
+        let saved =
+            SavedSearch::new(name, query, SearchMode::Bm25, None, 10).unwrap();
+        config_db.set_saved_search(&saved).unwrap();
+    }
+
+    #[test]
+    fn new_rejects_empty_names_queries_and_counts() {
+        for (name, query, count) in
+            [("", "q", 10), ("name", "  ", 10), ("name", "q", 0)]
+        {
+            let err =
+                SavedSearch::new(name, query, SearchMode::Bm25, None, count)
+                    .unwrap_err();
+            assert!(matches!(err, Error::Config(_)), "{err:?}");
+        }
+    }
+
+    #[test]
+    fn refresh_records_only_documents_that_enter_after_the_baseline() {
+        let (idx, data_dir, config_db, _tmp) = setup();
+        let mut model = ModelManager::new();
+        save(&config_db, "borrow", "borrow checker");
+
+        let recorded =
+            refresh(&idx, &config_db, &data_dir, &mut model).unwrap();
+        assert!(recorded.is_empty(), "first run is the baseline");
+        let saved = config_db.get_saved_search("borrow").unwrap().unwrap();
+        assert_eq!(
+            saved.top,
+            vec![DocumentId::new("notes", "ownership.md").numeric]
+        );
+        assert!(saved.last_run_at.is_some());
+
+        add(
+            &idx,
+            "lifetimes.md",
+            "Lifetimes keep the borrow checker happy.",
+        );
+        let recorded =
+            refresh(&idx, &config_db, &data_dir, &mut model).unwrap();
+        assert_eq!(recorded.len(), 1);
+        assert_eq!(recorded[0].search, "borrow");
+        assert_eq!(recorded[0].path, "lifetimes.md");
+
+        // Running again with nothing new records nothing and keeps the
+        // waiting hit exactly once.
+        let recorded =
+            refresh(&idx, &config_db, &data_dir, &mut model).unwrap();
+        assert!(recorded.is_empty());
+        let hits = new_hits(&config_db, Some("borrow")).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].path, "lifetimes.md");
+    }
+
+    #[test]
+    fn record_does_not_repeat_a_hit_that_left_and_came_back() {
+        let mut saved =
+            SavedSearch::new("s", "q", SearchMode::Bm25, None, 2).unwrap();
+        let result = |doc_num_id: u64, rank: usize| FinalResult {
+            rank,
+            score: 1.0,
+            doc_id: String::new(),
+            doc_num_id,
+            collection: "notes".to_string(),
+            path: format!("{doc_num_id}.md"),
+            title: String::new(),
+            best_chunk_doc_id: None,
+            rerank_score: None,
+            passages: Vec::new(),
+            token_matches: Vec::new(),
+            explain: None,
+        };
+
+        record(&mut saved, &[result(1, 1)], 10);
+        assert_eq!(
+            record(&mut saved, &[result(2, 1), result(1, 2)], 20).len(),
+            1
+        );
+        record(&mut saved, &[result(1, 1)], 30);
+        assert!(record(&mut saved, &[result(2, 1)], 40).is_empty());
+        assert_eq!(saved.new_hits.len(), 1);
+        assert_eq!(saved.new_hits[0].found_at, 20);
+        assert_eq!(saved.top, vec![2]);
+    }
+
+    #[test]
+    fn clear_new_hits_empties_one_or_all_searches() {
+        let (idx, data_dir, config_db, _tmp) = setup();
+        let mut model = ModelManager::new();
+        save(&config_db, "borrow", "borrow");
+        save(&config_db, "pasta", "pasta");
+        refresh(&idx, &config_db, &data_dir, &mut model).unwrap();
+        add(&idx, "more-borrow.md", "More on borrow rules.");
+        add(&idx, "more-pasta.md", "More pasta shapes.");
+        refresh(&idx, &config_db, &data_dir, &mut model).unwrap();
+        assert_eq!(new_hits(&config_db, None).unwrap().len(), 2);
+
+        assert_eq!(clear_new_hits(&config_db, Some("pasta")).unwrap(), 1);
+        let left = new_hits(&config_db, None).unwrap();
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].search, "borrow");
+        assert_eq!(clear_new_hits(&config_db, None).unwrap(), 1);
+        assert!(new_hits(&config_db, None).unwrap().is_empty());
+
+        let err = clear_new_hits(&config_db, Some("missing")).unwrap_err();
+        assert!(matches!(err, Error::NotFound { .. }), "{err:?}");
+    }
+}
@@ -1,15 +1,25 @@
-use std::{collections::HashMap, path::Path};
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use docbert_plaid::simd::{Kernel, MaxSim};
 
 use crate::{
-    config_db::ConfigDb,
+    config_db::{ChunkByteOffset, ConfigDb},
     data_dir::DataDir,
     doc_id::{format_document_ref, strip_document_ref_prefix},
+    embedding_db::EmbeddingDb,
     error::{Error, Result},
+    exact,
     incremental::DocumentMetadata,
     ingestion,
     model_manager::ModelManager,
     plaid,
     reranker::RankedDocument,
+    search_cache::{self, CacheKey, SearchCache, SearchCursor},
     tantivy_index::{SearchIndex, SearchResult},
 };
 
@@ -24,6 +34,20 @@ pub const RRF_K: usize = 60;
 /// [`run`].
 pub const RRF_CANDIDATE_LIMIT: usize = 100;
 
+/// Number of top fused results rescored by the optional cross-encoder
+/// stage. Everything past this cut keeps its fused order.
+pub const RERANK_CANDIDATE_LIMIT: usize = 20;
+
+/// Top BM25 score under which a lexical or hybrid search counts as a
+/// weak match and offers a spelling suggestion. Around what a single
+/// moderately rare query term scores; a query whose known terms match
+/// well is left alone even if another of its terms is misspelled.
+pub const SUGGESTION_BM25_SCORE: f32 = 5.0;
+
+/// Upper bound on the passage handed to the cross-encoder when a result
+/// has no chunk range to point at (BM25-only hits).
+const RERANK_FALLBACK_PASSAGE_BYTES: u64 = 2048;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SearchMode {
     Semantic,
@@ -50,12 +74,93 @@ impl SearchMode {
     }
 }
 
+/// How passage-mode results are laid out.
+///
+/// `Document` keeps one result per document with its top passages
+/// attached; `Passage` flattens them into one result per passage,
+/// ordered by passage score. See [`group_passages`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PassageGrouping {
+    #[default]
+    Document,
+    Passage,
+}
+
+impl PassageGrouping {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PassageGrouping::Document => "document",
+            PassageGrouping::Passage => "passage",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "document" => Some(PassageGrouping::Document),
+            "passage" => Some(PassageGrouping::Passage),
+            _ => None,
+        }
+    }
+}
+
+/// Pseudo-relevance feedback settings for [`run`].
+///
+/// With PRF, the top `feedback_docs` results of a first search pass are
+/// assumed relevant. Their `expansion_terms` most discriminative BM25
+/// terms are added to the lexical query, and up to as many pooled token
+/// embeddings (ColBERT-PRF style) are appended to the PLAID query
+/// matrix. Both legs are then searched again and re-fused. `weight`
+/// scales the expansion against the original query: `0.0` keeps the
+/// expansion from moving any score, `1.0` counts it as much as the
+/// query itself.
+///
+/// # Examples
+///
+/// ```
+/// use docbert_core::search::PrfParams;
+///
+/// let prf = PrfParams {
+///     feedback_docs: 5,
+///     ..PrfParams::default()
+/// };
+/// assert_eq!(prf.expansion_terms, 10);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PrfParams {
+    /// Number of first-pass results treated as relevant.
+    pub feedback_docs: usize,
+    /// Number of expansion terms, and of expansion embeddings.
+    pub expansion_terms: usize,
+    /// Weight of the expansion relative to the original query.
+    pub weight: f32,
+}
+
+impl Default for PrfParams {
+    fn default() -> Self {
+        Self {
+            feedback_docs: 3,
+            expansion_terms: 10,
+            weight: 0.5,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct SearchQuery {
     pub query: String,
     pub collection: Option<String>,
     pub count: usize,
     pub min_score: f32,
+    pub passages: usize,
+    pub explain: bool,
+    pub prf: Option<PrfParams>,
+    pub facets: bool,
+    pub offset: usize,
+    pub cursor: Option<SearchCursor>,
+    /// Ignored in [`SearchMode::Semantic`], which offers no suggestions.
+    pub autocorrect: bool,
+    /// Ignored in [`SearchMode::Bm25`], which never queries PLAID.
+    pub plaid: plaid::PlaidQueryParams,
 }
 
 /// Options for hybrid search.
@@ -77,6 +182,14 @@ pub struct SearchQuery {
 ///     bm25_only: false,
 ///     no_fuzzy: false,
 ///     all: false,
+///     passages: 0,
+///     explain: false,
+///     prf: None,
+///     facets: false,
+///     offset: 0,
+///     cursor: None,
+///     autocorrect: false,
+///     plaid: Default::default(),
 /// };
 /// ```
 #[derive(Debug, Clone)]
@@ -96,6 +209,28 @@ pub struct SearchParams {
     pub no_fuzzy: bool,
     /// Return all results instead of capping at `count`.
     pub all: bool,
+    /// Attach up to this many scored chunks to each result as
+    /// [`FinalResult::passages`]. `0` disables passage mode.
+    pub passages: usize,
+    /// Attach a [`ResultExplain`] to every result and collect a
+    /// [`SearchExplain`] for the query (see [`run_explained`]).
+    pub explain: bool,
+    /// Expand the query from the first pass's top results and search
+    /// again. `None` disables pseudo-relevance feedback.
+    pub prf: Option<PrfParams>,
+    /// Count the candidates into [`Facets`] (see [`run_explained`]).
+    pub facets: bool,
+    /// Skip this many ranked results before the page starts.
+    pub offset: usize,
+    /// Continue from [`SearchOutcome::next_cursor`] of an earlier page;
+    /// overrides `offset` (see [`run_cached`]).
+    pub cursor: Option<SearchCursor>,
+    /// When the query has a [`QuerySuggestion`], search with the
+    /// corrected query instead.
+    pub autocorrect: bool,
+    /// PLAID query-time parameters for the semantic leg, layered over
+    /// the stored [`plaid::PLAID_QUERY_SETTING`] default.
+    pub plaid: plaid::PlaidQueryParams,
 }
 
 /// Options for semantic-only search.
@@ -111,6 +246,12 @@ pub struct SearchParams {
 ///     count: 5,
 ///     min_score: 0.0,
 ///     all: false,
+///     passages: 0,
+///     explain: false,
+///     facets: false,
+///     offset: 0,
+///     cursor: None,
+///     plaid: Default::default(),
 /// };
 /// ```
 #[derive(Debug, Clone)]
@@ -125,6 +266,22 @@ pub struct SemanticSearchParams {
     pub min_score: f32,
     /// Return all results above the score threshold.
     pub all: bool,
+    /// Attach up to this many scored chunks to each result as
+    /// [`FinalResult::passages`]. `0` disables passage mode.
+    pub passages: usize,
+    /// Attach a [`ResultExplain`] to every result and collect a
+    /// [`SearchExplain`] for the query (see [`semantic_explained`]).
+    pub explain: bool,
+    /// Count the candidates into [`Facets`] (see [`semantic_explained`]).
+    pub facets: bool,
+    /// Skip this many ranked results before the page starts.
+    pub offset: usize,
+    /// Continue from [`SearchOutcome::next_cursor`] of an earlier page;
+    /// overrides `offset` (see [`semantic_cached`]).
+    pub cursor: Option<SearchCursor>,
+    /// PLAID query-time parameters, layered over the stored
+    /// [`plaid::PLAID_QUERY_SETTING`] default.
+    pub plaid: plaid::PlaidQueryParams,
 }
 
 /// Search result returned by [`run`] or [`semantic`].
@@ -153,8 +310,487 @@ pub struct FinalResult {
     /// semantic leg never surfaced. Equal to `doc_num_id` when chunk 0
     /// (the base chunk) was the best match.
     pub best_chunk_doc_id: Option<u64>,
+    /// Cross-encoder relevance score, when a reranker model is configured
+    /// and this result was among the top [`RERANK_CANDIDATE_LIMIT`]
+    /// candidates. `score` keeps the fused (or MaxSim) value alongside it.
+    pub rerank_score: Option<f32>,
+    /// Top-scoring chunks of this document, best first, when passage
+    /// mode was requested. Empty otherwise, and for documents the
+    /// semantic leg never surfaced.
+    pub passages: Vec<Passage>,
+    /// Query tokens aligned to their best-matching token in the result's
+    /// best chunk, when explanations were requested through
+    /// [`explain_matches`]. Empty otherwise.
+    pub token_matches: Vec<TokenMatch>,
+    /// Where this result sat in each retrieval leg and what each leg
+    /// contributed to its fused score, when the request set `explain`.
+    pub explain: Option<ResultExplain>,
+}
+
+/// One scored chunk of a search hit, located by its byte range in the
+/// source document.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Passage {
+    /// Content-derived chunk identifier.
+    pub chunk_doc_id: u64,
+    /// ColBERT MaxSim score of this chunk against the query.
+    pub score: f32,
+    /// Byte offset where the chunk begins in the source document.
+    pub start_byte: u64,
+    /// Byte length of the chunk in the source document.
+    pub byte_len: u64,
+}
+
+impl Passage {
+    /// Convert a passage into its byte range.
+    pub fn byte_offset(&self) -> ChunkByteOffset {
+        ChunkByteOffset {
+            start_byte: self.start_byte,
+            byte_len: self.byte_len,
+        }
+    }
+}
+
+/// One query token's MaxSim alignment inside a search hit.
+///
+/// `score` is the token's contribution to the chunk's MaxSim score; the
+/// byte range locates the matched document token in the source document,
+/// ready for highlighting.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenMatch {
+    /// Query token as produced by the model's tokenizer.
+    pub query_token: String,
+    /// Document token the query token aligned to.
+    pub document_token: String,
+    /// Dot product between the two token embeddings.
+    pub score: f32,
+    /// Byte offset where the document token begins in the source document.
+    pub start_byte: u64,
+    /// Byte length of the document token in the source document.
+    pub byte_len: u64,
+}
+
+impl TokenMatch {
+    /// Convert a token match into its byte range.
+    pub fn byte_offset(&self) -> ChunkByteOffset {
+        ChunkByteOffset {
+            start_byte: self.start_byte,
+            byte_len: self.byte_len,
+        }
+    }
+}
+
+/// Rank and score of a result inside one retrieval leg.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LegHit {
+    /// 1-indexed position in the leg's candidate list.
+    pub rank: usize,
+    /// The leg's own score: BM25 for the BM25 leg, ColBERT MaxSim for
+    /// the semantic leg.
+    pub score: f32,
+    /// What this leg added to the fused score, `1 / (RRF_K + rank)`.
+    /// `0.0` when the search did not fuse legs.
+    pub rrf: f32,
+}
+
+/// Per-result breakdown of how a search scored one document.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResultExplain {
+    /// Position in the BM25 leg, when the document surfaced there.
+    pub bm25: Option<LegHit>,
+    /// Position in the semantic (PLAID) leg, when the document surfaced
+    /// there.
+    pub semantic: Option<LegHit>,
+    /// 1-indexed rank after fusion, before the optional cross-encoder
+    /// stage and the result limit.
+    pub fused_rank: usize,
+}
+
+/// Why a scored candidate is missing from the returned results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DropReason {
+    /// Scored below the request's `min_score`.
+    MinScore,
+    /// Ranked past the request's `count`.
+    Limit,
+}
+
+impl DropReason {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::MinScore => "min_score",
+            Self::Limit => "limit",
+        }
+    }
+}
+
+/// A candidate that was scored but filtered out of the results.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DroppedCandidate {
+    /// Numeric document identifier.
+    pub doc_num_id: u64,
+    /// Collection the document belongs to.
+    pub collection: String,
+    /// Relative file path within the collection.
+    pub path: String,
+    /// Score the candidate had when it was dropped.
+    pub score: f32,
+    /// The filter or threshold that removed it.
+    pub reason: DropReason,
+    /// Where the candidate sat in each leg.
+    pub explain: ResultExplain,
+}
+
+/// Wall-clock time spent in each stage of one search.
+///
+/// Stages a search did not run stay at zero.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct StageTimings {
+    /// Encoding the query with ColBERT.
+    pub query_encoding: Duration,
+    /// PLAID centroid probing and centroid interaction.
+    pub plaid_probe: Duration,
+    /// PLAID residual decoding and exact MaxSim.
+    pub plaid_decode: Duration,
+    /// The full-precision scan that replaces PLAID on small indexes.
+    pub exact: Duration,
+    /// The Tantivy BM25 query.
+    pub tantivy: Duration,
+    /// The optional cross-encoder stage.
+    pub rerank: Duration,
+    /// The whole call, end to end.
+    pub total: Duration,
+}
+
+/// Candidates listed in [`SearchExplain::dropped`]; the rest are only
+/// counted in [`SearchExplain::dropped_count`].
+pub const EXPLAIN_DROPPED_LIMIT: usize = 50;
+
+/// Query-level breakdown returned by [`run_explained`],
+/// [`semantic_explained`] and [`by_mode_explained`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchExplain {
+    /// Time spent in each stage.
+    pub timings: StageTimings,
+    /// Candidates returned by the BM25 leg.
+    pub bm25_candidates: usize,
+    /// Documents returned by the semantic leg after chunk collapse.
+    pub semantic_candidates: usize,
+    /// Documents reachable through PLAID's probed centroids.
+    pub plaid_probed_docs: usize,
+    /// Documents PLAID decoded for exact MaxSim after centroid
+    /// interaction.
+    pub plaid_decoded_docs: usize,
+    /// Chunks scored by the full-precision scan instead of PLAID.
+    pub exact_scored_docs: usize,
+    /// `true` when the request set a `min_score` that this search mode
+    /// ignores (RRF fusion).
+    pub min_score_ignored: bool,
+    /// Why the cross-encoder failed, when it did; the results then keep
+    /// their order from before the rerank stage.
+    pub rerank_error: Option<String>,
+    /// Total number of scored candidates missing from the results.
+    pub dropped_count: usize,
+    /// The best [`EXPLAIN_DROPPED_LIMIT`] of those, by fused rank.
+    pub dropped: Vec<DroppedCandidate>,
+}
+
+/// Values kept per facet in [`Facets`], most frequent first.
+pub const FACET_VALUE_LIMIT: usize = 20;
+
+/// One facet value and the number of candidates that carry it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FacetCount {
+    pub value: String,
+    pub count: usize,
+}
+
+/// Candidate counts grouped by collection, file extension,
+/// modification year and frontmatter tag.
+///
+/// Counted over every candidate that survived `min_score`, before the
+/// result limit, so they describe what narrowing the query would keep.
+/// Each list holds at most [`FACET_VALUE_LIMIT`] values, by descending
+/// count and then value. Documents without an extension, a recorded
+/// mtime or tags are left out of that facet.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Facets {
+    /// Number of candidates counted.
+    pub total: usize,
+    pub collections: Vec<FacetCount>,
+    /// Lowercased, without the leading dot.
+    pub extensions: Vec<FacetCount>,
+    /// UTC year of the document's mtime.
+    pub years: Vec<FacetCount>,
+    pub tags: Vec<FacetCount>,
+}
+
+/// Results of an explained search.
+#[derive(Debug, Clone)]
+pub struct SearchOutcome {
+    /// The same results the non-explained entry point returns.
+    pub results: Vec<FinalResult>,
+    /// Query-level breakdown, present when the request set `explain`.
+    pub explain: Option<SearchExplain>,
+    /// Candidate counts, present when the request set `facets`.
+    pub facets: Option<Facets>,
+    /// Number of ranked results the pages are cut from.
+    pub total: usize,
+    /// Where the next page starts, `None` on the last page.
+    pub next_cursor: Option<SearchCursor>,
+    /// Spelling correction of a query with terms the index has never
+    /// seen. Only lexical and hybrid searches offer one.
+    pub suggestion: Option<QuerySuggestion>,
+}
+
+/// A corrected spelling of a search's query, from
+/// [`SearchIndex::suggest`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuerySuggestion {
+    /// The corrected query.
+    pub query: String,
+    /// Whether the results are for the corrected query rather than the
+    /// original one, because the request set `autocorrect`.
+    pub applied: bool,
+}
+
+/// Every result a search ranked, best first, before a page is cut from
+/// it. [`SearchCache`] keeps these between pages.
+#[derive(Debug, Default)]
+pub(crate) struct RankedSearch {
+    /// Ranked results; `rank` is assigned per page.
+    results: Vec<FinalResult>,
+    chunk_hits: ChunkHits,
+    /// Per-candidate breakdowns in ranked order, when explained.
+    explained: Vec<ExplainCandidate>,
+    /// Threshold dropped candidates are attributed to; `None` under RRF.
+    min_score: Option<f32>,
+    /// Stage timings and candidate counts of the ranking pass.
+    explain: SearchExplain,
+    facets: Option<Facets>,
+    suggestion: Option<QuerySuggestion>,
+    /// Best score of the BM25 leg, `None` when it matched nothing.
+    bm25_top_score: Option<f32>,
+    /// [`search_cache::fingerprint`] of the ranked document order.
+    pub(crate) fingerprint: u64,
+}
+
+/// Which slice of a [`RankedSearch`] to return, and how to name it in
+/// the next page's cursor.
+struct Page {
+    key: u64,
+    depth: usize,
+    offset: usize,
+    count: usize,
+    all: bool,
+    passages: usize,
+    explain: bool,
+}
+
+/// The PLAID index, document metadata and query encodings shared by
+/// every search of a batch, so each is loaded or computed once.
+struct QueryBatch {
+    plaid_index: Arc<plaid::PlaidIndex>,
+    metadata: Vec<(u64, DocumentMetadata)>,
+    /// Flat query token matrices, by query text.
+    tokens: HashMap<String, Vec<f32>>,
+    /// The batch's single encoding pass, split evenly across its
+    /// queries for [`StageTimings::query_encoding`].
+    encoding_share: Duration,
+    /// Collection filters shared by the batch's queries.
+    filters: plaid::PlaidFilterCache,
+}
+
+impl QueryBatch {
+    /// Load the PLAID index and document metadata, and encode the
+    /// distinct `queries` in one forward pass.
+    fn prepare<'a>(
+        queries: impl IntoIterator<Item = &'a str>,
+        config_db: &ConfigDb,
+        data_dir: &DataDir,
+        model: &mut ModelManager,
+    ) -> Result<Self> {
+        let (plaid_index, metadata) =
+            semantic_corpus(None, config_db, data_dir)?;
+        let mut seen = HashSet::new();
+        let distinct: Vec<String> = queries
+            .into_iter()
+            .filter(|query| seen.insert(*query))
+            .map(str::to_string)
+            .collect();
+
+        let mut tokens = HashMap::with_capacity(distinct.len());
+        let mut encoding_share = Duration::ZERO;
+        // Without documents no semantic leg gets as far as encoding.
+        if !metadata.is_empty() && !distinct.is_empty() {
+            let started = Instant::now();
+            let encoded = model.encode_queries(&distinct)?;
+            for (query, embedding) in distinct.iter().zip(&encoded) {
+                tokens.insert(
+                    query.clone(),
+                    plaid::query_tokens_flat(&plaid_index, embedding)?,
+                );
+            }
+            encoding_share = started.elapsed() / distinct.len() as u32;
+        }
+
+        Ok(Self {
+            plaid_index,
+            metadata,
+            tokens,
+            encoding_share,
+            filters: plaid::PlaidFilterCache::new(),
+        })
+    }
+}
+
+/// The PLAID filter allowing the chunks of the documents in
+/// `metadata`, which all belong to `collection`.
+///
+/// Cached per collection in the batch, or else on the resident index,
+/// when there is one; built for this search only otherwise.
+fn collection_filter(
+    batch: Option<&QueryBatch>,
+    config_db: &ConfigDb,
+    data_dir: &DataDir,
+    plaid_index: &Arc<plaid::PlaidIndex>,
+    collection: &str,
+    metadata: &HashMap<u64, DocumentMetadata>,
+) -> Result<Arc<plaid::PlaidFilter>> {
+    let build = || {
+        plaid::documents_filter(
+            config_db,
+            plaid_index,
+            metadata.keys().copied(),
+        )
+    };
+    let cache = batch
+        .map(|batch| &batch.filters)
+        .or_else(|| data_dir.resident_plaid().map(|r| r.filters()));
+    match cache {
+        Some(cache) => cache.get_or_build(plaid_index, collection, build),
+        None => build().map(Arc::new),
+    }
+}
+
+/// How a semantic query scores chunks.
+enum SemanticBackend {
+    /// PLAID, restricted to the filter's chunks when there is one.
+    Plaid(Option<Arc<plaid::PlaidFilter>>),
+    /// [`exact::search`] over the stored embeddings, of `chunks` only
+    /// when it is set.
+    Exact {
+        embeddings: EmbeddingDb,
+        chunks: Option<Vec<u64>>,
+    },
+}
+
+/// The backend a semantic query over `plaid_index` runs on, chosen by
+/// [`exact::use_exact`] and restricted to the documents in `metadata`
+/// when `collection` is set.
+#[allow(clippy::too_many_arguments)]
+fn semantic_backend(
+    batch: Option<&QueryBatch>,
+    config_db: &ConfigDb,
+    data_dir: &DataDir,
+    plaid_index: &Arc<plaid::PlaidIndex>,
+    collection: Option<&str>,
+    metadata: &HashMap<u64, DocumentMetadata>,
+    params: &plaid::PlaidQueryParams,
+) -> Result<SemanticBackend> {
+    if exact::use_exact(params, plaid_index.num_documents()) {
+        let chunks = collection
+            .map(|_| {
+                exact::document_chunks(config_db, metadata.keys().copied())
+            })
+            .transpose()?;
+        return Ok(SemanticBackend::Exact {
+            embeddings: EmbeddingDb::open(&data_dir.embeddings_db())?,
+            chunks,
+        });
+    }
+    let filter = collection
+        .map(|c| {
+            collection_filter(
+                batch,
+                config_db,
+                data_dir,
+                plaid_index,
+                c,
+                metadata,
+            )
+        })
+        .transpose()?;
+    Ok(SemanticBackend::Plaid(filter))
+}
+
+/// A PLAID index and the metadata of every indexed document.
+type SemanticCorpus = (Arc<plaid::PlaidIndex>, Vec<(u64, DocumentMetadata)>);
+
+/// The PLAID index and document metadata a semantic search ranks
+/// against: the batch's when there is one, otherwise from
+/// [`plaid::open_index`].
+fn semantic_corpus(
+    batch: Option<&QueryBatch>,
+    config_db: &ConfigDb,
+    data_dir: &DataDir,
+) -> Result<SemanticCorpus> {
+    if let Some(batch) = batch {
+        return Ok((Arc::clone(&batch.plaid_index), batch.metadata.clone()));
+    }
+    // Require a prebuilt PLAID index. The caller surfaces the error as
+    // an actionable "run `docbert sync`" message.
+    let plaid_index =
+        plaid::open_index(data_dir)?.ok_or(Error::PlaidIndexMissing)?;
+    let metadata = config_db.list_all_document_metadata_typed()?;
+    Ok((plaid_index, metadata))
+}
+
+/// A scored candidate tracked while explaining a search.
+#[derive(Debug, Clone)]
+struct ExplainCandidate {
+    doc_num_id: u64,
+    collection: String,
+    path: String,
+    score: f32,
+    explain: ResultExplain,
+}
+
+/// Passages attached per document when a caller asks for the flat
+/// [`PassageGrouping::Passage`] layout without giving a count.
+pub const DEFAULT_PASSAGE_COUNT: usize = 3;
+
+/// Resolve the per-document passage count for a request that carries an
+/// optional count and a grouping.
+///
+/// An explicit count always wins. Without one, passage mode stays off
+/// for [`PassageGrouping::Document`] and falls back to
+/// [`DEFAULT_PASSAGE_COUNT`] for [`PassageGrouping::Passage`], which has
+/// nothing to list otherwise.
+///
+/// # Examples
+///
+/// ```
+/// use docbert_core::search::{passage_count, PassageGrouping};
+///
+/// assert_eq!(passage_count(None, PassageGrouping::Document), 0);
+/// assert_eq!(passage_count(None, PassageGrouping::Passage), 3);
+/// assert_eq!(passage_count(Some(5), PassageGrouping::Document), 5);
+/// ```
+pub fn passage_count(
+    requested: Option<usize>,
+    grouping: PassageGrouping,
+) -> usize {
+    match (requested, grouping) {
+        (Some(count), _) => count,
+        (None, PassageGrouping::Document) => 0,
+        (None, PassageGrouping::Passage) => DEFAULT_PASSAGE_COUNT,
+    }
 }
 
+/// Every PLAID chunk hit per owning document, as `(chunk_doc_id, score)`.
+type ChunkHits = HashMap<u64, Vec<(u64, f32)>>;
+
 /// Run the hybrid search pipeline.
 ///
 /// When `bm25_only` is `false` (the default), BM25 and semantic retrieval
@@ -169,8 +805,14 @@ pub struct FinalResult {
 ///    so the caller can surface a clear "run `docbert sync`" message.
 /// 3. **RRF fusion** — ranks from both lists are combined as
 ///    `sum(1 / (k + rank))` with `k = `[`RRF_K`].
-/// 4. **Limit** — the top `count` fused results are returned, unless
-///    `all` is set.
+/// 4. **Limit** — `count` fused results starting at `offset` are
+///    returned, unless `all` is set.
+///
+/// When the [`ModelManager`] carries a cross-encoder (see
+/// [`ModelManager::with_reranker_model`]), the top
+/// [`RERANK_CANDIDATE_LIMIT`] fused results are rescored on their best
+/// chunk and reordered before the limit is applied. Without one, this
+/// stage is skipped.
 ///
 /// When `bm25_only` is `true`, the semantic leg is skipped entirely and
 /// the PLAID index is not touched. `min_score` is ignored in the RRF
@@ -196,6 +838,14 @@ pub struct FinalResult {
 ///     bm25_only: true,
 ///     no_fuzzy: false,
 ///     all: false,
+///     passages: 0,
+///     explain: false,
+///     prf: None,
+///     facets: false,
+///     offset: 0,
+///     cursor: None,
+///     autocorrect: false,
+///     plaid: Default::default(),
 /// };
 ///
 /// // bm25_only skips the semantic leg; no PLAID index is required.
@@ -211,97 +861,705 @@ pub fn run(
     data_dir: &DataDir,
     model: &mut ModelManager,
 ) -> Result<Vec<FinalResult>> {
-    if args.bm25_only {
-        return execute_bm25_only(args, search_index);
-    }
-
-    rrf(args, search_index, config_db, data_dir, model)
+    run_explained(args, search_index, config_db, data_dir, model)
+        .map(|outcome| outcome.results)
 }
 
-fn execute_bm25_only(
+/// [`run`], also returning a [`SearchExplain`] when `args.explain` is
+/// set.
+///
+/// With `explain`, every result carries a [`ResultExplain`] with its
+/// BM25 and PLAID rank and score and each leg's RRF contribution, and
+/// the query-level breakdown lists stage timings, candidate counts, and
+/// the candidates the result limit or `min_score` dropped.
+///
+/// # Examples
+///
+/// ```no_run
+/// use docbert_core::{ConfigDb, DataDir, SearchIndex, ModelManager};
+/// use docbert_core::search::{run_explained, SearchParams};
+///
+/// # let tmp = tempfile::tempdir().unwrap();
+/// let data_dir = DataDir::new(tmp.path());
+/// let index = SearchIndex::open_in_ram().unwrap();
+/// let config_db = ConfigDb::open(&tmp.path().join("config.db")).unwrap();
+/// let mut model = ModelManager::new();
+///
+/// let params = SearchParams {
+///     query: "rust programming".to_string(),
+///     count: 10,
+///     collection: None,
+///     min_score: 0.0,
+///     bm25_only: false,
+///     no_fuzzy: false,
+///     all: false,
+///     passages: 0,
+///     explain: true,
+///     prf: None,
+///     facets: false,
+///     offset: 0,
+///     cursor: None,
+///     autocorrect: false,
+///     plaid: Default::default(),
+/// };
+///
+/// let outcome =
+///     run_explained(&params, &index, &config_db, &data_dir, &mut model)
+///         .unwrap();
+/// let explain = outcome.explain.unwrap();
+/// println!("tantivy took {:?}", explain.timings.tantivy);
+/// for r in &outcome.results {
+///     println!("{} {:?}", r.rank, r.explain);
+/// }
+/// ```
+pub fn run_explained(
     args: &SearchParams,
     search_index: &SearchIndex,
-) -> Result<Vec<FinalResult>> {
-    let bm25_limit = 1000;
-    let bm25_results = run_bm25_leg(
-        search_index,
-        &args.query,
-        args.collection.as_deref(),
-        args.no_fuzzy,
-        bm25_limit,
-    )?;
-
-    if bm25_results.is_empty() {
-        return Ok(vec![]);
-    }
-
-    let filtered: Vec<FinalResult> = bm25_to_final(&bm25_results)
-        .into_iter()
-        .filter(|r| r.score >= args.min_score)
-        .collect();
-
-    let limit = if args.all { filtered.len() } else { args.count };
-    Ok(filtered
-        .into_iter()
-        .take(limit)
-        .enumerate()
-        .map(|(i, mut r)| {
-            r.rank = i + 1;
-            r
-        })
-        .collect())
+    config_db: &ConfigDb,
+    data_dir: &DataDir,
+    model: &mut ModelManager,
+) -> Result<SearchOutcome> {
+    run_paged(args, None, None, search_index, config_db, data_dir, model)
 }
 
-fn rrf(
+/// [`run_explained`], keeping the ranking in `cache` so later pages are
+/// cut from it.
+///
+/// A request with `offset == 0` and no cursor always ranks afresh and
+/// replaces the cached ranking. Later pages, asked for by `offset` or by
+/// [`SearchOutcome::next_cursor`], reuse it without running either leg
+/// again. A cursor whose ranking has expired reruns the search and fails
+/// with [`Error::InvalidCursor`] if the order changed in the meantime,
+/// or if it was issued for different parameters.
+///
+/// # Examples
+///
+/// ```no_run
+/// use docbert_core::{ConfigDb, DataDir, SearchIndex, ModelManager};
+/// use docbert_core::search::{run_cached, SearchParams};
+/// use docbert_core::search_cache::SearchCache;
+///
+/// # let tmp = tempfile::tempdir().unwrap();
+/// let data_dir = DataDir::new(tmp.path());
+/// let index = SearchIndex::open_in_ram().unwrap();
+/// let config_db = ConfigDb::open(&tmp.path().join("config.db")).unwrap();
+/// let mut model = ModelManager::new();
+/// let cache = SearchCache::default();
+///
+/// let mut params = SearchParams {
+///     query: "rust programming".to_string(),
+///     count: 10,
+///     collection: None,
+///     min_score: 0.0,
+///     bm25_only: false,
+///     no_fuzzy: false,
+///     all: false,
+///     passages: 0,
+///     explain: false,
+///     prf: None,
+///     facets: false,
+///     offset: 0,
+///     cursor: None,
+///     autocorrect: false,
+///     plaid: Default::default(),
+/// };
+///
+/// let first = run_cached(
+///     &params, &cache, &index, &config_db, &data_dir, &mut model,
+/// )
+/// .unwrap();
+/// params.cursor = first.next_cursor;
+/// let second = run_cached(
+///     &params, &cache, &index, &config_db, &data_dir, &mut model,
+/// )
+/// .unwrap();
+/// println!("{} of {}", second.results.len(), second.total);
+/// ```
+pub fn run_cached(
     args: &SearchParams,
+    cache: &SearchCache,
     search_index: &SearchIndex,
     config_db: &ConfigDb,
     data_dir: &DataDir,
     model: &mut ModelManager,
-) -> Result<Vec<FinalResult>> {
-    // BM25 leg
-    let bm25_results = run_bm25_leg(
+) -> Result<SearchOutcome> {
+    run_paged(
+        args,
+        Some(cache),
+        None,
         search_index,
-        &args.query,
-        args.collection.as_deref(),
-        args.no_fuzzy,
-        RRF_CANDIDATE_LIMIT,
-    )?;
-
-    // Semantic leg — requires a prebuilt PLAID index.
-    let (sem_metadata, sem_ranked) = run_semantic_leg(
         config_db,
         data_dir,
         model,
-        &args.query,
-        args.collection.as_deref(),
-        RRF_CANDIDATE_LIMIT,
-    )?;
-
-    if bm25_results.is_empty() && sem_ranked.is_empty() {
-        return Ok(vec![]);
-    }
-
-    // RRF fusion on ranked doc_num_id lists
-    let bm25_ids: Vec<u64> =
-        bm25_results.iter().map(|r| r.doc_num_id).collect();
-    let sem_ids: Vec<u64> = sem_ranked.iter().map(|r| r.doc_num_id).collect();
-    let fused = rrf_fuse(&[&bm25_ids, &sem_ids], RRF_K);
+    )
+}
 
-    // Build FinalResults, preferring BM25-side metadata (which already carries
-    // title) and falling back to semantic-side metadata for docs that only
-    // surfaced semantically. The semantic-side carries the winning chunk
-    // id (when known) so search consumers can surface that chunk's byte
-    // range; a doc that only surfaced via BM25 has no chunk-level score
-    // and gets `None`.
-    let bm25_lookup: HashMap<u64, &SearchResult> =
-        bm25_results.iter().map(|r| (r.doc_num_id, r)).collect();
+/// [`run_explained`] for many queries at once, returning one outcome
+/// per entry of `args`, in order.
+///
+/// The PLAID index and document metadata are loaded once, and every
+/// query with a semantic leg is encoded in a single batched forward
+/// pass, so a batch costs far less than the same searches one by one.
+/// Results are the same as searching each entry alone. Entries may mix
+/// `bm25_only` and fused searches; an all-BM25 batch never touches
+/// PLAID or the model. Nothing is cached, so cursors in the outcomes
+/// page through [`run_cached`] like any other.
+///
+/// # Examples
+///
+/// ```no_run
+/// use docbert_core::{ConfigDb, DataDir, SearchIndex, ModelManager};
+/// use docbert_core::search::{run_batch, SearchParams};
+///
+/// # let tmp = tempfile::tempdir().unwrap();
+/// let data_dir = DataDir::new(tmp.path());
+/// let index = SearchIndex::open_in_ram().unwrap();
+/// let config_db = ConfigDb::open(&tmp.path().join("config.db")).unwrap();
+/// let mut model = ModelManager::new();
+///
+/// let params: Vec<SearchParams> = ["ownership", "lifetimes"]
+///     .into_iter()
+///     .map(|query| SearchParams {
+///         query: query.to_string(),
+///         count: 10,
+///         collection: None,
+///         min_score: 0.0,
+///         bm25_only: false,
+///         no_fuzzy: false,
+///         all: false,
+///         passages: 0,
+///         explain: false,
+///         prf: None,
+///         facets: false,
+///         offset: 0,
+///         cursor: None,
+///         autocorrect: false,
+///         plaid: Default::default(),
+///     })
+///     .collect();
+///
+/// let outcomes =
+///     run_batch(&params, &index, &config_db, &data_dir, &mut model)
+///         .unwrap();
+/// for (params, outcome) in params.iter().zip(&outcomes) {
+///     println!("{}: {} result(s)", params.query, outcome.results.len());
+/// }
+/// ```
+pub fn run_batch(
+    args: &[SearchParams],
+    search_index: &SearchIndex,
+    config_db: &ConfigDb,
+    data_dir: &DataDir,
+    model: &mut ModelManager,
+) -> Result<Vec<SearchOutcome>> {
+    let semantic: Vec<&str> = args
+        .iter()
+        .filter(|a| !a.bm25_only)
+        .map(|a| a.query.as_str())
+        .collect();
+    let batch = if semantic.is_empty() {
+        None
+    } else {
+        Some(QueryBatch::prepare(semantic, config_db, data_dir, model)?)
+    };
+    args.iter()
+        .map(|a| {
+            run_paged(
+                a,
+                None,
+                batch.as_ref(),
+                search_index,
+                config_db,
+                data_dir,
+                model,
+            )
+        })
+        .collect()
+}
+
+fn run_paged(
+    args: &SearchParams,
+    cache: Option<&SearchCache>,
+    batch: Option<&QueryBatch>,
+    search_index: &SearchIndex,
+    config_db: &ConfigDb,
+    data_dir: &DataDir,
+    model: &mut ModelManager,
+) -> Result<SearchOutcome> {
+    let started = Instant::now();
+    let plaid_params = query_params(config_db, &args.plaid)?;
+    let args = &SearchParams {
+        plaid: plaid_params,
+        ..args.clone()
+    };
+    // Both legs rank a fixed number of candidates, so the ranking does
+    // not depend on how deep the caller pages.
+    let depth = 0;
+    let mut key = CacheKey::new("hybrid");
+    key.str(&data_dir.root().to_string_lossy())
+        .str(&args.query)
+        .opt_str(args.collection.as_deref())
+        .u64(u64::from(args.min_score.to_bits()))
+        .flag(args.bm25_only)
+        .flag(args.no_fuzzy)
+        .flag(args.explain)
+        .flag(args.facets)
+        .flag(args.autocorrect)
+        .opt_str(model.reranker_model());
+    hash_query_params(&mut key, &args.plaid);
+    if let Some(prf) = args.prf {
+        key.u64(prf.feedback_docs as u64)
+            .u64(prf.expansion_terms as u64)
+            .u64(u64::from(prf.weight.to_bits()));
+    }
+    let key = key.finish();
+
+    let (ranked, fresh) =
+        ranked_for_page(cache, key, args.offset, args.cursor.as_ref(), || {
+            rank_lexical(args, batch, search_index, config_db, data_dir, model)
+        })?;
+    let page = Page {
+        key,
+        depth,
+        offset: args.cursor.map_or(args.offset, |c| c.offset),
+        count: args.count,
+        all: args.all,
+        passages: args.passages,
+        explain: args.explain,
+    };
+    let mut outcome = cut_page(&ranked, &page, fresh, config_db);
+    if let Some(explain) = &mut outcome.explain {
+        explain.timings.total = started.elapsed();
+    }
+    Ok(outcome)
+}
+
+/// The stored PLAID query parameters with `request`'s overrides on
+/// top, checked before anything is searched.
+fn query_params(
+    config_db: &ConfigDb,
+    request: &plaid::PlaidQueryParams,
+) -> Result<plaid::PlaidQueryParams> {
+    let params = plaid::load_query_params(config_db)?.with(*request);
+    params.validate()?;
+    Ok(params)
+}
+
+fn hash_query_params(key: &mut CacheKey, params: &plaid::PlaidQueryParams) {
+    key.opt_u64(params.n_probe.map(|n| n as u64))
+        .opt_u64(params.n_candidate_docs.map(|n| n as u64))
+        .opt_u64(
+            params
+                .centroid_score_threshold
+                .map(|t| u64::from(t.to_bits())),
+        )
+        .flag(params.exhaustive)
+        .opt_u64(params.exact.map(u64::from));
+}
+
+/// Return the ranking a page should be cut from, and whether it was
+/// computed by this call.
+///
+/// First pages (`offset == 0`, no cursor) always run `rank` so new
+/// queries see the current index. Later pages read `cache` first; a
+/// cursor additionally requires the ranking to match the one it was
+/// issued for.
+fn ranked_for_page(
+    cache: Option<&SearchCache>,
+    key: u64,
+    offset: usize,
+    cursor: Option<&SearchCursor>,
+    rank: impl FnOnce() -> Result<RankedSearch>,
+) -> Result<(Arc<RankedSearch>, bool)> {
+    if cursor.is_some_and(|c| c.key != key) {
+        return Err(Error::InvalidCursor(
+            "the cursor was issued for a different search".to_string(),
+        ));
+    }
+    let wanted = cursor.map(|c| c.fingerprint);
+    if offset > 0 || cursor.is_some() {
+        let hit = cache.and_then(|cache| cache.get(key)).filter(|ranked| {
+            wanted.is_none_or(|fingerprint| fingerprint == ranked.fingerprint)
+        });
+        if let Some(ranked) = hit {
+            return Ok((ranked, false));
+        }
+    }
+
+    let mut ranked = rank()?;
+    ranked.fingerprint =
+        search_cache::fingerprint(ranked.results.iter().map(|r| r.doc_num_id));
+    if wanted.is_some_and(|fingerprint| fingerprint != ranked.fingerprint) {
+        return Err(Error::InvalidCursor(
+            "the results changed since the cursor was issued; search again from the first page"
+                .to_string(),
+        ));
+    }
+    let ranked = Arc::new(ranked);
+    if let Some(cache) = cache {
+        cache.insert(key, Arc::clone(&ranked));
+    }
+    Ok((ranked, true))
+}
+
+/// Cut `page` out of `ranked`: assign ranks from `offset + 1`, attach
+/// explanations, passages and titles, and point a cursor at the next
+/// page.
+///
+/// Stage timings are only reported for a `fresh` ranking; a page read
+/// from the cache did none of that work.
+fn cut_page(
+    ranked: &RankedSearch,
+    page: &Page,
+    fresh: bool,
+    config_db: &ConfigDb,
+) -> SearchOutcome {
+    let total = ranked.results.len();
+    let start = page.offset.min(total);
+    let end = if page.all {
+        total
+    } else {
+        start.saturating_add(page.count).min(total)
+    };
+    let mut results = ranked.results[start..end].to_vec();
+    for (i, r) in results.iter_mut().enumerate() {
+        r.rank = start + i + 1;
+    }
+
+    let mut explain = ranked.explain.clone();
+    if !fresh {
+        explain.timings = StageTimings::default();
+    }
+    // Results on earlier pages were returned, not dropped.
+    let earlier: HashSet<u64> = ranked.results[..start]
+        .iter()
+        .map(|r| r.doc_num_id)
+        .collect();
+    let candidates = ranked
+        .explained
+        .iter()
+        .filter(|c| !earlier.contains(&c.doc_num_id))
+        .cloned()
+        .collect();
+    attach_explanations(
+        &mut results,
+        candidates,
+        ranked.min_score,
+        &mut explain,
+    );
+
+    attach_passages(&mut results, &ranked.chunk_hits, config_db, page.passages);
+    // Titles for semantic-only entries (BM25 entries already have them).
+    populate_titles(&mut results, config_db);
+
+    let next_cursor = (end < total).then_some(SearchCursor {
+        key: page.key,
+        fingerprint: ranked.fingerprint,
+        depth: page.depth,
+        offset: end,
+    });
+    SearchOutcome {
+        results,
+        explain: page.explain.then_some(explain),
+        facets: ranked.facets.clone(),
+        total,
+        next_cursor,
+        suggestion: ranked.suggestion.clone(),
+    }
+}
+
+/// Rank `args` with BM25 alone or fused with the semantic leg, and
+/// offer a spelling suggestion when the lexical match is weak: no hits,
+/// or a top BM25 score under [`SUGGESTION_BM25_SCORE`]. Only then is
+/// the index asked for one, and under `autocorrect` the corrected query
+/// is ranked in place of the original.
+fn rank_lexical(
+    args: &SearchParams,
+    batch: Option<&QueryBatch>,
+    search_index: &SearchIndex,
+    config_db: &ConfigDb,
+    data_dir: &DataDir,
+    model: &mut ModelManager,
+) -> Result<RankedSearch> {
+    let mut rank = |args: &SearchParams| {
+        if args.bm25_only {
+            rank_bm25_only(args, search_index, config_db)
+        } else {
+            rank_rrf(args, batch, search_index, config_db, data_dir, model)
+        }
+    };
+    let mut ranked = rank(args)?;
+    let weak = ranked.results.is_empty()
+        || ranked
+            .bm25_top_score
+            .is_none_or(|score| score < SUGGESTION_BM25_SCORE);
+    if !weak {
+        return Ok(ranked);
+    }
+    let Some(query) = search_index.suggest(&args.query)? else {
+        return Ok(ranked);
+    };
+    if args.autocorrect {
+        ranked = rank(&SearchParams {
+            query: query.clone(),
+            ..args.clone()
+        })?;
+    }
+    ranked.suggestion = Some(QuerySuggestion {
+        query,
+        applied: args.autocorrect,
+    });
+    Ok(ranked)
+}
+
+fn rank_bm25_only(
+    args: &SearchParams,
+    search_index: &SearchIndex,
+    config_db: &ConfigDb,
+) -> Result<RankedSearch> {
+    let mut explain = SearchExplain::default();
+    let bm25_limit = 1000;
+    let tantivy_started = Instant::now();
+    let mut bm25_results = run_bm25_leg(
+        search_index,
+        &args.query,
+        args.collection.as_deref(),
+        args.no_fuzzy,
+        bm25_limit,
+    )?;
+    if let Some(prf) = args.prf {
+        let feedback = feedback_texts(
+            config_db,
+            bm25_results
+                .iter()
+                .map(|r| (r.collection.as_str(), r.path.as_str())),
+            prf.feedback_docs,
+        );
+        if let Some(expanded) =
+            prf_bm25_leg(search_index, args, &prf, &feedback, bm25_limit)?
+        {
+            bm25_results = expanded;
+        }
+    }
+    explain.timings.tantivy = tantivy_started.elapsed();
+    explain.bm25_candidates = bm25_results.len();
+
+    if bm25_results.is_empty() {
+        return Ok(RankedSearch {
+            explain,
+            ..RankedSearch::default()
+        });
+    }
+
+    let candidates = bm25_to_final(&bm25_results);
+    let explained: Vec<ExplainCandidate> = if args.explain {
+        candidates
+            .iter()
+            .map(|r| ExplainCandidate {
+                doc_num_id: r.doc_num_id,
+                collection: r.collection.clone(),
+                path: r.path.clone(),
+                score: r.score,
+                explain: ResultExplain {
+                    bm25: Some(LegHit {
+                        rank: r.rank,
+                        score: r.score,
+                        rrf: 0.0,
+                    }),
+                    semantic: None,
+                    fused_rank: r.rank,
+                },
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let filtered: Vec<FinalResult> = candidates
+        .into_iter()
+        .filter(|r| r.score >= args.min_score)
+        .collect();
+    let facets = if args.facets {
+        Some(count_facets(
+            search_index,
+            filtered.iter().map(|r| (r.doc_num_id, r.path.as_str())),
+        )?)
+    } else {
+        None
+    };
+
+    Ok(RankedSearch {
+        results: filtered,
+        chunk_hits: ChunkHits::new(),
+        explained,
+        min_score: Some(args.min_score),
+        explain,
+        facets,
+        fingerprint: 0,
+        suggestion: None,
+        bm25_top_score: bm25_results.first().map(|r| r.score),
+    })
+}
+
+fn rank_rrf(
+    args: &SearchParams,
+    batch: Option<&QueryBatch>,
+    search_index: &SearchIndex,
+    config_db: &ConfigDb,
+    data_dir: &DataDir,
+    model: &mut ModelManager,
+) -> Result<RankedSearch> {
+    let mut explain = SearchExplain {
+        min_score_ignored: args.min_score > 0.0,
+        ..SearchExplain::default()
+    };
+
+    // BM25 leg
+    let tantivy_started = Instant::now();
+    let mut bm25_results = run_bm25_leg(
+        search_index,
+        &args.query,
+        args.collection.as_deref(),
+        args.no_fuzzy,
+        RRF_CANDIDATE_LIMIT,
+    )?;
+    explain.timings.tantivy = tantivy_started.elapsed();
+
+    // Semantic leg — requires a prebuilt PLAID index.
+    let SemanticLeg {
+        metadata: sem_metadata,
+        ranked: mut sem_ranked,
+        mut chunk_hits,
+        query: sem_query,
+    } = run_semantic_leg(
+        config_db,
+        data_dir,
+        model,
+        batch,
+        &args.query,
+        args.collection.as_deref(),
+        RRF_CANDIDATE_LIMIT,
+        &args.plaid,
+        &mut explain,
+    )?;
+
+    if bm25_results.is_empty() && sem_ranked.is_empty() {
+        return Ok(RankedSearch {
+            explain,
+            ..RankedSearch::default()
+        });
+    }
+
+    let mut results = fuse_legs(&bm25_results, &sem_metadata, &sem_ranked);
+
+    if let Some(prf) = args.prf {
+        let feedback = feedback_texts(
+            config_db,
+            results
+                .iter()
+                .map(|r| (r.collection.as_str(), r.path.as_str())),
+            prf.feedback_docs,
+        );
+        let tantivy_started = Instant::now();
+        if let Some(expanded) = prf_bm25_leg(
+            search_index,
+            args,
+            &prf,
+            &feedback,
+            RRF_CANDIDATE_LIMIT,
+        )? {
+            bm25_results = expanded;
+        }
+        explain.timings.tantivy += tantivy_started.elapsed();
+
+        if let Some(sem_query) = &sem_query {
+            let feedback_ids: Vec<u64> = results
+                .iter()
+                .take(prf.feedback_docs)
+                .map(|r| r.doc_num_id)
+                .collect();
+            if let Some((ranked, hits)) = prf_semantic_leg(
+                config_db,
+                data_dir,
+                sem_query,
+                &sem_metadata,
+                &prf,
+                &feedback_ids,
+                &mut explain,
+            )? {
+                sem_ranked = ranked;
+                chunk_hits = hits;
+            }
+        }
+        results = fuse_legs(&bm25_results, &sem_metadata, &sem_ranked);
+    }
+    explain.bm25_candidates = bm25_results.len();
+    explain.semantic_candidates = sem_ranked.len();
+    let facets = if args.facets {
+        Some(count_facets(
+            search_index,
+            results.iter().map(|r| (r.doc_num_id, r.path.as_str())),
+        )?)
+    } else {
+        None
+    };
+
+    let explained = if args.explain {
+        fused_candidates(&results, &bm25_results, &sem_ranked)
+    } else {
+        Vec::new()
+    };
+
+    let rerank_started = Instant::now();
+    rerank_or_keep_order(
+        &mut results,
+        &args.query,
+        config_db,
+        model,
+        &mut explain,
+    );
+    explain.timings.rerank = rerank_started.elapsed();
+
+    Ok(RankedSearch {
+        results,
+        chunk_hits,
+        explained,
+        min_score: None,
+        explain,
+        facets,
+        fingerprint: 0,
+        suggestion: None,
+        bm25_top_score: bm25_results.first().map(|r| r.score),
+    })
+}
+
+/// Fuse a BM25 and a semantic ranking with RRF and turn the fused list
+/// into [`FinalResult`]s, unranked and untruncated.
+fn fuse_legs(
+    bm25_results: &[SearchResult],
+    sem_metadata: &HashMap<u64, DocumentMetadata>,
+    sem_ranked: &[RankedDocument],
+) -> Vec<FinalResult> {
+    // RRF fusion on ranked doc_num_id lists
+    let bm25_ids: Vec<u64> =
+        bm25_results.iter().map(|r| r.doc_num_id).collect();
+    let sem_ids: Vec<u64> = sem_ranked.iter().map(|r| r.doc_num_id).collect();
+    let fused = rrf_fuse(&[&bm25_ids, &sem_ids], RRF_K);
+
+    // Build FinalResults, preferring BM25-side metadata (which already carries
+    // title) and falling back to semantic-side metadata for docs that only
+    // surfaced semantically. The semantic-side carries the winning chunk
+    // id (when known) so search consumers can surface that chunk's byte
+    // range; a doc that only surfaced via BM25 has no chunk-level score
+    // and gets `None`.
+    let bm25_lookup: HashMap<u64, &SearchResult> =
+        bm25_results.iter().map(|r| (r.doc_num_id, r)).collect();
     let sem_best_chunk: HashMap<u64, Option<u64>> = sem_ranked
         .iter()
         .map(|r| (r.doc_num_id, r.best_chunk_doc_id))
         .collect();
 
-    let mut results: Vec<FinalResult> = fused
+    fused
         .into_iter()
         .filter_map(|(doc_num_id, score)| {
             let best_chunk_doc_id =
@@ -316,6 +1574,10 @@ fn rrf(
                     path: bm25.path.clone(),
                     title: bm25.title.clone(),
                     best_chunk_doc_id,
+                    rerank_score: None,
+                    passages: Vec::new(),
+                    token_matches: Vec::new(),
+                    explain: None,
                 });
             }
             let meta = sem_metadata.get(&doc_num_id)?;
@@ -330,23 +1592,176 @@ fn rrf(
                 path: meta.relative_path.clone(),
                 title: String::new(),
                 best_chunk_doc_id,
+                rerank_score: None,
+                passages: Vec::new(),
+                token_matches: Vec::new(),
+                explain: None,
             })
         })
-        .collect();
+        .collect()
+}
 
-    let limit = if args.all { results.len() } else { args.count };
-    results.truncate(limit);
+/// Current text of the first `count` of `documents`, skipping the ones
+/// that can't be read.
+fn feedback_texts<'a>(
+    config_db: &ConfigDb,
+    documents: impl Iterator<Item = (&'a str, &'a str)>,
+    count: usize,
+) -> Vec<String> {
+    documents
+        .take(count)
+        .filter_map(|(collection, path)| {
+            source_text(config_db, collection, path)
+        })
+        .collect()
+}
 
-    for (i, r) in results.iter_mut().enumerate() {
-        r.rank = i + 1;
+/// BM25 leg of a pseudo-relevance feedback pass: the query plus the
+/// most discriminative terms of `feedback`.
+///
+/// Returns `None` when `feedback` yields no expansion terms, leaving
+/// the first pass's results in place.
+fn prf_bm25_leg(
+    search_index: &SearchIndex,
+    args: &SearchParams,
+    prf: &PrfParams,
+    feedback: &[String],
+    limit: usize,
+) -> Result<Option<Vec<SearchResult>>> {
+    let texts: Vec<&str> = feedback.iter().map(String::as_str).collect();
+    let terms = search_index.expansion_terms(
+        &texts,
+        &args.query,
+        prf.expansion_terms,
+    )?;
+    if terms.is_empty() {
+        return Ok(None);
     }
+    search_index
+        .search_expanded(
+            &args.query,
+            &terms,
+            prf.weight,
+            args.collection.as_deref(),
+            !args.no_fuzzy,
+            limit,
+        )
+        .map(Some)
+}
 
-    // Titles for semantic-only entries (BM25 entries already have them).
-    if results.iter().any(|r| r.title.is_empty()) {
-        populate_titles(&mut results, config_db);
+/// Semantic leg of a pseudo-relevance feedback pass: the encoded query
+/// plus pooled token embeddings of the `feedback` documents, searched
+/// again over the same index.
+///
+/// Returns `None` when the feedback documents have no tokens to pool.
+fn prf_semantic_leg(
+    config_db: &ConfigDb,
+    data_dir: &DataDir,
+    query: &SemanticQuery,
+    metadata: &HashMap<u64, DocumentMetadata>,
+    prf: &PrfParams,
+    feedback: &[u64],
+    explain: &mut SearchExplain,
+) -> Result<Option<(Vec<RankedDocument>, ChunkHits)>> {
+    let mut feedback_tokens = Vec::new();
+    for &doc_num_id in feedback {
+        feedback_tokens.extend(similar_query_tokens(
+            config_db,
+            data_dir,
+            &query.plaid_index,
+            doc_num_id,
+        )?);
+    }
+    let expansion = plaid::prf_expansion_embeddings(
+        &query.plaid_index,
+        &feedback_tokens,
+        prf.expansion_terms,
+        prf.weight,
+    )?;
+    if expansion.is_empty() {
+        return Ok(None);
     }
 
-    Ok(results)
+    let mut tokens = query.tokens.clone();
+    tokens.extend(expansion);
+    let raw_results = search_semantic_tokens(
+        &query.plaid_index,
+        &query.backend,
+        &tokens,
+        query.oversample,
+        &query.params,
+        explain,
+    )?;
+    rank_documents(config_db, metadata, &raw_results, RRF_CANDIDATE_LIMIT)
+        .map(Some)
+}
+
+/// Count `candidates` into [`Facets`]: collection, mtime and tags come
+/// from Tantivy's FAST columns, the extension from the path.
+fn count_facets<'a>(
+    search_index: &SearchIndex,
+    candidates: impl Iterator<Item = (u64, &'a str)>,
+) -> Result<Facets> {
+    let candidates: Vec<(u64, &str)> = candidates.collect();
+    let ids = candidates.iter().map(|&(id, _)| id).collect();
+    let indexed = search_index.document_facets(&ids)?;
+
+    let mut collections: HashMap<String, usize> = HashMap::new();
+    let mut extensions: HashMap<String, usize> = HashMap::new();
+    let mut years: HashMap<String, usize> = HashMap::new();
+    let mut tags: HashMap<String, usize> = HashMap::new();
+    for (doc_num_id, path) in &candidates {
+        if let Some(extension) =
+            Path::new(path).extension().and_then(|e| e.to_str())
+        {
+            *extensions.entry(extension.to_lowercase()).or_default() += 1;
+        }
+        let Some(document) = indexed.get(doc_num_id) else {
+            continue;
+        };
+        *collections.entry(document.collection.clone()).or_default() += 1;
+        if document.mtime > 0 {
+            let year = utc_year(document.mtime).to_string();
+            *years.entry(year).or_default() += 1;
+        }
+        for tag in &document.tags {
+            *tags.entry(tag.clone()).or_default() += 1;
+        }
+    }
+
+    Ok(Facets {
+        total: candidates.len(),
+        collections: top_facet_counts(collections),
+        extensions: top_facet_counts(extensions),
+        years: top_facet_counts(years),
+        tags: top_facet_counts(tags),
+    })
+}
+
+fn top_facet_counts(counts: HashMap<String, usize>) -> Vec<FacetCount> {
+    let mut counts: Vec<FacetCount> = counts
+        .into_iter()
+        .map(|(value, count)| FacetCount { value, count })
+        .collect();
+    counts.sort_by(|a, b| b.count.cmp(&a.count).then(a.value.cmp(&b.value)));
+    counts.truncate(FACET_VALUE_LIMIT);
+    counts
+}
+
+/// Proleptic Gregorian UTC year of a Unix timestamp.
+fn utc_year(unix_secs: u64) -> i64 {
+    // Days-to-civil conversion from Howard Hinnant's date algorithms.
+    let days = (unix_secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524
+        - day_of_era / 146_096)
+        / 365;
+    let day_of_year =
+        day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let year = year_of_era + era * 400;
+    if month_index >= 10 { year + 1 } else { year }
 }
 
 fn run_bm25_leg(
@@ -367,25 +1782,228 @@ fn run_bm25_leg(
     }
 }
 
-fn run_semantic_leg(
-    config_db: &ConfigDb,
-    data_dir: &DataDir,
-    model: &mut ModelManager,
-    query: &str,
-    collection: Option<&str>,
-    limit: usize,
-) -> Result<(HashMap<u64, DocumentMetadata>, Vec<RankedDocument>)> {
-    // Require a prebuilt PLAID index. The caller surfaces the error as
-    // an actionable "run `docbert sync`" message.
-    let plaid_index =
-        plaid::load_index(data_dir)?.ok_or(Error::PlaidIndexMissing)?;
+/// Per-result leg breakdown for every fused candidate, in fused order.
+fn fused_candidates(
+    fused: &[FinalResult],
+    bm25_results: &[SearchResult],
+    sem_ranked: &[RankedDocument],
+) -> Vec<ExplainCandidate> {
+    let leg_hit = |i: usize, score: f32| LegHit {
+        rank: i + 1,
+        score,
+        rrf: 1.0 / (RRF_K + i + 1) as f32,
+    };
+    let bm25_hits: HashMap<u64, LegHit> = bm25_results
+        .iter()
+        .enumerate()
+        .map(|(i, r)| (r.doc_num_id, leg_hit(i, r.score)))
+        .collect();
+    let sem_hits: HashMap<u64, LegHit> = sem_ranked
+        .iter()
+        .enumerate()
+        .map(|(i, r)| (r.doc_num_id, leg_hit(i, r.score)))
+        .collect();
 
-    // Collect metadata up front so we can (a) filter results by
-    // collection and (b) hand a HashMap to the fusion caller for
+    fused
+        .iter()
+        .enumerate()
+        .map(|(i, r)| ExplainCandidate {
+            doc_num_id: r.doc_num_id,
+            collection: r.collection.clone(),
+            path: r.path.clone(),
+            score: r.score,
+            explain: ResultExplain {
+                bm25: bm25_hits.get(&r.doc_num_id).copied(),
+                semantic: sem_hits.get(&r.doc_num_id).copied(),
+                fused_rank: i + 1,
+            },
+        })
+        .collect()
+}
+
+/// Hand every kept candidate's breakdown to its result and record the
+/// rest in `explain` as dropped.
+///
+/// A dropped candidate is attributed to `min_score` when one applies and
+/// it scored below it, and to the result limit otherwise. A no-op when
+/// `candidates` is empty, which is how callers skip explanations.
+fn attach_explanations(
+    results: &mut [FinalResult],
+    candidates: Vec<ExplainCandidate>,
+    min_score: Option<f32>,
+    explain: &mut SearchExplain,
+) {
+    if candidates.is_empty() {
+        return;
+    }
+    let mut kept: HashMap<u64, usize> = results
+        .iter()
+        .enumerate()
+        .map(|(i, r)| (r.doc_num_id, i))
+        .collect();
+
+    for candidate in candidates {
+        if let Some(i) = kept.remove(&candidate.doc_num_id) {
+            results[i].explain = Some(candidate.explain);
+            continue;
+        }
+        let reason = match min_score {
+            Some(min) if candidate.score < min => DropReason::MinScore,
+            _ => DropReason::Limit,
+        };
+        explain.dropped_count += 1;
+        if explain.dropped.len() < EXPLAIN_DROPPED_LIMIT {
+            explain.dropped.push(DroppedCandidate {
+                doc_num_id: candidate.doc_num_id,
+                collection: candidate.collection,
+                path: candidate.path,
+                score: candidate.score,
+                reason,
+                explain: candidate.explain,
+            });
+        }
+    }
+}
+
+/// Encode `query` and run it on `backend`, recording the encoding and
+/// search stage timings in `explain`.
+///
+/// Also returns the encoded query as a flat token matrix, which a
+/// pseudo-relevance feedback pass extends and searches again. A query
+/// `batch` already encoded is not encoded again.
+#[allow(clippy::too_many_arguments)]
+fn encode_and_search_semantic(
+    plaid_index: &plaid::PlaidIndex,
+    backend: &SemanticBackend,
+    model: &mut ModelManager,
+    query: &str,
+    batch: Option<&QueryBatch>,
+    top_k: usize,
+    params: &plaid::PlaidQueryParams,
+    explain: &mut SearchExplain,
+) -> Result<(Vec<plaid::PlaidResult>, Vec<f32>)> {
+    if let Some(batch) = batch
+        && let Some(tokens) = batch.tokens.get(query)
+    {
+        explain.timings.query_encoding = batch.encoding_share;
+        let raw_results = search_semantic_tokens(
+            plaid_index,
+            backend,
+            tokens,
+            top_k,
+            params,
+            explain,
+        )?;
+        return Ok((raw_results, tokens.clone()));
+    }
+
+    let encode_started = Instant::now();
+    let query_embedding = model.encode_query(query)?;
+    let query_tokens = plaid::query_tokens_flat(plaid_index, &query_embedding)?;
+    explain.timings.query_encoding = encode_started.elapsed();
+
+    let raw_results = search_semantic_tokens(
+        plaid_index,
+        backend,
+        &query_tokens,
+        top_k,
+        params,
+        explain,
+    )?;
+    Ok((raw_results, query_tokens))
+}
+
+/// Run a flat query matrix on `backend`, adding the stage timings and
+/// counters to `explain`.
+fn search_semantic_tokens(
+    plaid_index: &plaid::PlaidIndex,
+    backend: &SemanticBackend,
+    query_tokens: &[f32],
+    top_k: usize,
+    params: &plaid::PlaidQueryParams,
+    explain: &mut SearchExplain,
+) -> Result<Vec<plaid::PlaidResult>> {
+    let filter = match backend {
+        SemanticBackend::Plaid(filter) => filter.as_deref(),
+        SemanticBackend::Exact { embeddings, chunks } => {
+            let started = Instant::now();
+            let (raw_results, scored) = exact::search(
+                embeddings,
+                query_tokens,
+                plaid_index.params.dim,
+                top_k,
+                chunks.as_deref(),
+            )?;
+            explain.timings.exact += started.elapsed();
+            explain.exact_scored_docs += scored;
+            return Ok(raw_results);
+        }
+    };
+    let (raw_results, stats) = match filter {
+        Some(filter) => plaid::search_tokens_filtered(
+            plaid_index,
+            query_tokens,
+            top_k,
+            params,
+            filter,
+        )?,
+        None => plaid::search_tokens_with_stats(
+            plaid_index,
+            query_tokens,
+            top_k,
+            params,
+        )?,
+    };
+    explain.timings.plaid_probe += stats.probe;
+    explain.timings.plaid_decode += stats.decode;
+    explain.plaid_probed_docs += stats.probed_docs;
+    explain.plaid_decoded_docs += stats.decoded_docs;
+    Ok(raw_results)
+}
+
+/// What [`run_semantic_leg`] ranked, plus the index and encoded query
+/// it ranked them with.
+struct SemanticLeg {
+    metadata: HashMap<u64, DocumentMetadata>,
+    ranked: Vec<RankedDocument>,
+    chunk_hits: ChunkHits,
+    /// `None` when the leg had no documents to search.
+    query: Option<SemanticQuery>,
+}
+
+struct SemanticQuery {
+    plaid_index: Arc<plaid::PlaidIndex>,
+    /// The backend and collection scope the query ran with.
+    backend: SemanticBackend,
+    tokens: Vec<f32>,
+    oversample: usize,
+    params: plaid::PlaidQueryParams,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_semantic_leg(
+    config_db: &ConfigDb,
+    data_dir: &DataDir,
+    model: &mut ModelManager,
+    batch: Option<&QueryBatch>,
+    query: &str,
+    collection: Option<&str>,
+    limit: usize,
+    params: &plaid::PlaidQueryParams,
+    explain: &mut SearchExplain,
+) -> Result<SemanticLeg> {
+    // Collect metadata up front so we can (a) filter results by
+    // collection and (b) hand a HashMap to the fusion caller for
     // final-result enrichment.
-    let metadata_entries = config_db.list_all_document_metadata_typed()?;
+    let (plaid_index, metadata_entries) =
+        semantic_corpus(batch, config_db, data_dir)?;
     if metadata_entries.is_empty() {
-        return Ok((HashMap::new(), Vec::new()));
+        return Ok(SemanticLeg {
+            metadata: HashMap::new(),
+            ranked: Vec::new(),
+            chunk_hits: ChunkHits::new(),
+            query: None,
+        });
     }
 
     let metadata: HashMap<u64, DocumentMetadata> = metadata_entries
@@ -395,31 +2013,72 @@ fn run_semantic_leg(
         })
         .collect();
     if metadata.is_empty() {
-        return Ok((metadata, Vec::new()));
+        return Ok(SemanticLeg {
+            metadata,
+            ranked: Vec::new(),
+            chunk_hits: ChunkHits::new(),
+            query: None,
+        });
     }
 
-    let query_embedding = model.encode_query(query)?;
-
-    // Oversample PLAID so a collection filter + chunk-family collapse
-    // don't starve the fused result set of candidates.
-    let oversample = limit.saturating_mul(8).max(limit).max(64);
-    let raw_results =
-        plaid::search(&plaid_index, &query_embedding, oversample)?;
-
-    let mut ranked = collapse_chunks_to_documents(
+    // A collection scope is applied inside the backend, so the
+    // candidates all come from the collection. Still oversample so the
+    // chunk-family collapse doesn't starve the fused result set.
+    let backend = semantic_backend(
+        batch,
         config_db,
+        data_dir,
+        &plaid_index,
+        collection,
         &metadata,
-        &raw_results,
-        limit,
+        params,
     )?;
+    let oversample = limit.saturating_mul(8).max(limit).max(64);
+    let (raw_results, tokens) = encode_and_search_semantic(
+        &plaid_index,
+        &backend,
+        model,
+        query,
+        batch,
+        oversample,
+        params,
+        explain,
+    )?;
+
+    let (ranked, chunk_hits) =
+        rank_documents(config_db, &metadata, &raw_results, limit)?;
+
+    Ok(SemanticLeg {
+        metadata,
+        ranked,
+        chunk_hits,
+        query: Some(SemanticQuery {
+            plaid_index,
+            backend,
+            tokens,
+            oversample,
+            params: *params,
+        }),
+    })
+}
+
+/// [`collapse_chunks_to_documents`], then keep the `limit` best
+/// documents, best first.
+fn rank_documents(
+    config_db: &ConfigDb,
+    metadata: &HashMap<u64, DocumentMetadata>,
+    raw_results: &[plaid::PlaidResult],
+    limit: usize,
+) -> Result<(Vec<RankedDocument>, ChunkHits)> {
+    let (mut ranked, chunk_hits) =
+        collapse_chunks_to_documents(config_db, metadata, raw_results, limit)?;
     ranked.sort_by(|a, b| {
         b.score
             .partial_cmp(&a.score)
             .unwrap_or(std::cmp::Ordering::Equal)
     });
     ranked.truncate(limit);
-
-    Ok((metadata, ranked))
+    Ok((ranked, chunk_hits))
 }
 
 /// Fan a list of chunk-level PLAID hits out to their owning documents
@@ -429,20 +2088,28 @@ fn run_semantic_leg(
 /// documents; the lookup against `chunk_owners` returns every document
 /// that contains the chunk. Owners absent from `metadata` (typically
 /// filtered out by a collection scope) are dropped silently.
+///
+/// Every surviving hit is also kept per document so passage mode can
+/// report more than the single best chunk.
 fn collapse_chunks_to_documents(
     config_db: &ConfigDb,
     metadata: &HashMap<u64, DocumentMetadata>,
     raw_results: &[plaid::PlaidResult],
     limit: usize,
-) -> Result<Vec<RankedDocument>> {
+) -> Result<(Vec<RankedDocument>, ChunkHits)> {
     let mut best_per_doc: HashMap<u64, (f32, u64)> =
         HashMap::with_capacity(limit);
+    let mut chunk_hits = ChunkHits::with_capacity(limit);
     for result in raw_results {
         let owners = config_db.get_chunk_owners(result.doc_id)?;
         for owner in owners {
             if !metadata.contains_key(&owner) {
                 continue;
             }
+            chunk_hits
+                .entry(owner)
+                .or_default()
+                .push((result.doc_id, result.score));
             best_per_doc
                 .entry(owner)
                 .and_modify(|(best_score, best_chunk_id)| {
@@ -455,14 +2122,114 @@ fn collapse_chunks_to_documents(
         }
     }
 
-    Ok(best_per_doc
+    let ranked = best_per_doc
         .into_iter()
         .map(|(doc_num_id, (score, best_chunk_doc_id))| RankedDocument {
             doc_num_id,
             score,
             best_chunk_doc_id: Some(best_chunk_doc_id),
         })
-        .collect())
+        .collect();
+    Ok((ranked, chunk_hits))
+}
+
+/// Attach up to `per_doc` of each result's best-scoring chunks as
+/// [`Passage`]s, resolving byte ranges from the document's chunk
+/// manifest.
+///
+/// Chunks missing from the manifest (documents indexed before offsets
+/// were tracked) are skipped. A no-op when `per_doc` is `0`.
+fn attach_passages(
+    results: &mut [FinalResult],
+    chunk_hits: &ChunkHits,
+    config_db: &ConfigDb,
+    per_doc: usize,
+) {
+    if per_doc == 0 {
+        return;
+    }
+
+    for result in results {
+        let Some(hits) = chunk_hits.get(&result.doc_num_id) else {
+            continue;
+        };
+        let Some(manifest) =
+            config_db.get_doc_chunks(result.doc_num_id).ok().flatten()
+        else {
+            continue;
+        };
+
+        let mut hits = hits.clone();
+        hits.sort_by(|a, b| {
+            b.1.partial_cmp(&a.1)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.0.cmp(&b.0))
+        });
+        hits.dedup_by_key(|(chunk_doc_id, _)| *chunk_doc_id);
+
+        result.passages = hits
+            .into_iter()
+            .filter_map(|(chunk_doc_id, score)| {
+                let entry = manifest
+                    .iter()
+                    .find(|entry| entry.chunk_doc_id == chunk_doc_id)?;
+                Some(Passage {
+                    chunk_doc_id,
+                    score,
+                    start_byte: entry.start_byte,
+                    byte_len: entry.byte_len,
+                })
+            })
+            .take(per_doc)
+            .collect();
+    }
+}
+
+/// Lay out passage-mode results according to `grouping`.
+///
+/// [`PassageGrouping::Document`] returns `results` unchanged.
+/// [`PassageGrouping::Passage`] emits one result per attached passage,
+/// carrying that passage alone, its score as `score`, and its chunk as
+/// `best_chunk_doc_id`. The flat list is ordered by passage score and
+/// re-ranked from 1. Results without passages are dropped from the flat
+/// list, since there is no location to report for them.
+///
+/// # Examples
+///
+/// ```
+/// use docbert_core::search::{group_passages, PassageGrouping};
+///
+/// assert!(group_passages(Vec::new(), PassageGrouping::Passage).is_empty());
+/// ```
+pub fn group_passages(
+    results: Vec<FinalResult>,
+    grouping: PassageGrouping,
+) -> Vec<FinalResult> {
+    if grouping == PassageGrouping::Document {
+        return results;
+    }
+
+    let mut flat: Vec<FinalResult> = results
+        .into_iter()
+        .flat_map(|result| {
+            let passages = result.passages.clone();
+            passages.into_iter().map(move |passage| FinalResult {
+                score: passage.score,
+                best_chunk_doc_id: Some(passage.chunk_doc_id),
+                passages: vec![passage],
+                ..result.clone()
+            })
+        })
+        .collect();
+    flat.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    for (i, result) in flat.iter_mut().enumerate() {
+        result.rank = i + 1;
+    }
+    flat
 }
 
 /// Fuse any number of ranked document-id lists using Reciprocal Rank Fusion.
@@ -531,6 +2298,10 @@ fn semantic_final_results_from_ranked(
                     path: meta.relative_path.clone(),
                     title: String::new(),
                     best_chunk_doc_id,
+                    rerank_score: None,
+                    passages: Vec::new(),
+                    token_matches: Vec::new(),
+                    explain: None,
                 })
             },
         )
@@ -539,123 +2310,1139 @@ fn semantic_final_results_from_ranked(
     let limit = if all { results.len() } else { count };
     results.truncate(limit);
 
-    for (i, result) in results.iter_mut().enumerate() {
-        result.rank = i + 1;
+    for (i, result) in results.iter_mut().enumerate() {
+        result.rank = i + 1;
+    }
+
+    results
+}
+
+/// Run semantic-only search across all indexed documents.
+///
+/// Unlike [`run`], this skips BM25 entirely and returns
+/// results purely from the PLAID index. If the index has not been built
+/// yet, the call fails with [`Error::PlaidIndexMissing`]; the caller
+/// should surface this as a clear "run `docbert sync`" message.
+///
+/// The ColBERT model is loaded on first use.
+pub fn semantic(
+    args: &SemanticSearchParams,
+    config_db: &ConfigDb,
+    data_dir: &DataDir,
+    model: &mut ModelManager,
+) -> Result<Vec<FinalResult>> {
+    semantic_explained(args, config_db, data_dir, model)
+        .map(|outcome| outcome.results)
+}
+
+/// [`semantic`], also returning a [`SearchExplain`] when `args.explain`
+/// is set.
+///
+/// Results carry their PLAID rank and MaxSim score as the semantic
+/// [`LegHit`]; there is no BM25 leg and no RRF contribution.
+pub fn semantic_explained(
+    args: &SemanticSearchParams,
+    config_db: &ConfigDb,
+    data_dir: &DataDir,
+    model: &mut ModelManager,
+) -> Result<SearchOutcome> {
+    semantic_paged(args, None, None, config_db, data_dir, model)
+}
+
+/// [`semantic_explained`], keeping the ranking in `cache` so later pages
+/// are cut from it. Paging works as in [`run_cached`].
+///
+/// How many documents PLAID ranks depends on how deep the first request
+/// reaches (`offset + count`). A cursor keeps that depth, so following
+/// [`SearchOutcome::next_cursor`] pages through one ranking until it
+/// runs out; jumping ahead with a larger `offset` ranks deeper instead.
+pub fn semantic_cached(
+    args: &SemanticSearchParams,
+    cache: &SearchCache,
+    config_db: &ConfigDb,
+    data_dir: &DataDir,
+    model: &mut ModelManager,
+) -> Result<SearchOutcome> {
+    semantic_paged(args, Some(cache), None, config_db, data_dir, model)
+}
+
+/// [`semantic_explained`] for many queries at once, loading the PLAID
+/// index once and encoding every query in one batched forward pass
+/// (see [`run_batch`]).
+pub fn semantic_batch(
+    args: &[SemanticSearchParams],
+    config_db: &ConfigDb,
+    data_dir: &DataDir,
+    model: &mut ModelManager,
+) -> Result<Vec<SearchOutcome>> {
+    if args.is_empty() {
+        return Ok(Vec::new());
+    }
+    let batch = QueryBatch::prepare(
+        args.iter().map(|a| a.query.as_str()),
+        config_db,
+        data_dir,
+        model,
+    )?;
+    args.iter()
+        .map(|a| {
+            semantic_paged(a, None, Some(&batch), config_db, data_dir, model)
+        })
+        .collect()
+}
+
+fn semantic_paged(
+    args: &SemanticSearchParams,
+    cache: Option<&SearchCache>,
+    batch: Option<&QueryBatch>,
+    config_db: &ConfigDb,
+    data_dir: &DataDir,
+    model: &mut ModelManager,
+) -> Result<SearchOutcome> {
+    let started = Instant::now();
+    let plaid_params = query_params(config_db, &args.plaid)?;
+    let args = &SemanticSearchParams {
+        plaid: plaid_params,
+        ..args.clone()
+    };
+    let depth = args
+        .cursor
+        .map_or(args.offset.saturating_add(args.count), |c| c.depth);
+    let mut key = CacheKey::new("semantic");
+    key.str(&data_dir.root().to_string_lossy())
+        .str(&args.query)
+        .opt_str(args.collection.as_deref())
+        .u64(u64::from(args.min_score.to_bits()))
+        .flag(args.explain)
+        .flag(args.facets)
+        .opt_str(model.reranker_model())
+        .u64(depth as u64);
+    hash_query_params(&mut key, &args.plaid);
+    let key = key.finish();
+
+    let (ranked, fresh) =
+        ranked_for_page(cache, key, args.offset, args.cursor.as_ref(), || {
+            rank_semantic(args, depth, batch, config_db, data_dir, model)
+        })?;
+    let page = Page {
+        key,
+        depth,
+        offset: args.cursor.map_or(args.offset, |c| c.offset),
+        count: args.count,
+        all: args.all,
+        passages: args.passages,
+        explain: args.explain,
+    };
+    let mut outcome = cut_page(&ranked, &page, fresh, config_db);
+    if let Some(explain) = &mut outcome.explain {
+        explain.timings.total = started.elapsed();
+    }
+    Ok(outcome)
+}
+
+/// Rank documents for a semantic-only search that pages down to
+/// `depth` results.
+fn rank_semantic(
+    args: &SemanticSearchParams,
+    depth: usize,
+    batch: Option<&QueryBatch>,
+    config_db: &ConfigDb,
+    data_dir: &DataDir,
+    model: &mut ModelManager,
+) -> Result<RankedSearch> {
+    let mut explain = SearchExplain::default();
+    let (plaid_index, metadata_entries) =
+        semantic_corpus(batch, config_db, data_dir)?;
+    if metadata_entries.is_empty() {
+        return Ok(RankedSearch::default());
+    }
+
+    let metadata: HashMap<u64, DocumentMetadata> = metadata_entries
+        .into_iter()
+        .filter(|(_, meta)| {
+            args.collection.is_none()
+                || args.collection.as_deref() == Some(meta.collection.as_str())
+        })
+        .collect();
+    if metadata.is_empty() {
+        return Ok(RankedSearch::default());
+    }
+
+    let backend = semantic_backend(
+        batch,
+        config_db,
+        data_dir,
+        &plaid_index,
+        args.collection.as_deref(),
+        &metadata,
+        &args.plaid,
+    )?;
+    let oversample = depth.saturating_mul(8).max(depth).max(64);
+    let (raw_results, _) = encode_and_search_semantic(
+        &plaid_index,
+        &backend,
+        model,
+        &args.query,
+        batch,
+        oversample,
+        &args.plaid,
+        &mut explain,
+    )?;
+
+    let (mut ranked, chunk_hits) = collapse_chunks_to_documents(
+        config_db,
+        &metadata,
+        &raw_results,
+        depth,
+    )?;
+    ranked.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    explain.semantic_candidates = ranked.len();
+
+    let facets = if args.facets {
+        // The semantic path has no index handle of its own; facets read
+        // Tantivy's FAST columns, so open it just for them.
+        let search_index = SearchIndex::open(&data_dir.tantivy_dir()?)?;
+        Some(count_facets(
+            &search_index,
+            ranked
+                .iter()
+                .filter(|r| r.score >= args.min_score)
+                .filter_map(|r| {
+                    let meta = metadata.get(&r.doc_num_id)?;
+                    Some((r.doc_num_id, meta.relative_path.as_str()))
+                }),
+        )?)
+    } else {
+        None
+    };
+
+    let explained: Vec<ExplainCandidate> = if args.explain {
+        ranked
+            .iter()
+            .enumerate()
+            .filter_map(|(i, r)| {
+                let meta = metadata.get(&r.doc_num_id)?;
+                Some(ExplainCandidate {
+                    doc_num_id: r.doc_num_id,
+                    collection: meta.collection.clone(),
+                    path: meta.relative_path.clone(),
+                    score: r.score,
+                    explain: ResultExplain {
+                        bm25: None,
+                        semantic: Some(LegHit {
+                            rank: i + 1,
+                            score: r.score,
+                            rrf: 0.0,
+                        }),
+                        fused_rank: i + 1,
+                    },
+                })
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let mut results = semantic_final_results_from_ranked(
+        &metadata,
+        ranked,
+        args.min_score,
+        0,
+        true,
+    );
+
+    if model.has_reranker() {
+        let rerank_started = Instant::now();
+        rerank_or_keep_order(
+            &mut results,
+            &args.query,
+            config_db,
+            model,
+            &mut explain,
+        );
+        explain.timings.rerank = rerank_started.elapsed();
+    }
+
+    Ok(RankedSearch {
+        results,
+        chunk_hits,
+        explained,
+        min_score: Some(args.min_score),
+        explain,
+        facets,
+        fingerprint: 0,
+        suggestion: None,
+        bm25_top_score: None,
+    })
+}
+
+/// Maximum number of source-document tokens used as the PLAID query in
+/// [`similar`]. Longer documents are subsampled evenly, so the query
+/// still covers the whole document.
+pub const SIMILAR_QUERY_TOKEN_LIMIT: usize = 256;
+
+/// Options for similar-document search.
+///
+/// # Examples
+///
+/// ```
+/// use docbert_core::search::SimilarParams;
+///
+/// let params = SimilarParams {
+///     reference: "notes:rust.md".to_string(),
+///     count: 10,
+///     collection: None,
+///     passages: 0,
+/// };
+/// ```
+#[derive(Debug, Clone)]
+pub struct SimilarParams {
+    /// The source document: `#short_id`, `collection:path`, or a bare
+    /// relative path (see [`resolve_reference`]).
+    pub reference: String,
+    /// Number of results to return.
+    pub count: usize,
+    /// Only return documents from this collection.
+    pub collection: Option<String>,
+    /// Attach up to this many scored chunks to each result as
+    /// [`FinalResult::passages`]. `0` disables passage mode.
+    pub passages: usize,
+}
+
+/// Find the indexed documents most similar to another indexed document.
+///
+/// The reference is resolved with [`resolve_reference`], and two legs are
+/// fused with Reciprocal Rank Fusion, as in [`run`]:
+///
+/// 1. **Semantic leg** — the source document's stored token embeddings
+///    query the PLAID index directly, capped at
+///    [`SIMILAR_QUERY_TOKEN_LIMIT`] tokens. Chunks missing from the
+///    embedding database are decoded from the PLAID index instead.
+/// 2. **MoreLikeThis leg** — Tantivy's MoreLikeThis query over the
+///    source's title and its current text on disk.
+///
+/// The source document never appears in the results. Nothing is
+/// encoded, so the ColBERT model is not loaded and no cross-encoder
+/// runs; scores are fused RRF scores.
+///
+/// Fails with [`Error::NotFound`] when the reference doesn't name an
+/// indexed document and with [`Error::PlaidIndexMissing`] when the
+/// PLAID index hasn't been built.
+///
+/// # Examples
+///
+/// ```no_run
+/// use docbert_core::{ConfigDb, DataDir, SearchIndex};
+/// use docbert_core::search::{similar, SimilarParams};
+///
+/// # let tmp = tempfile::tempdir().unwrap();
+/// let data_dir = DataDir::new(tmp.path());
+/// let index = SearchIndex::open(&data_dir.tantivy_dir().unwrap()).unwrap();
+/// let config_db = ConfigDb::open(&data_dir.config_db()).unwrap();
+///
+/// let params = SimilarParams {
+///     reference: "notes:rust.md".to_string(),
+///     count: 5,
+///     collection: None,
+///     passages: 0,
+/// };
+/// for r in similar(&params, &index, &config_db, &data_dir).unwrap() {
+///     println!("{}: {}:{}", r.rank, r.collection, r.path);
+/// }
+/// ```
+pub fn similar(
+    args: &SimilarParams,
+    search_index: &SearchIndex,
+    config_db: &ConfigDb,
+    data_dir: &DataDir,
+) -> Result<Vec<FinalResult>> {
+    let not_found = || Error::NotFound {
+        kind: "document",
+        name: args.reference.clone(),
+    };
+    let (collection, path) =
+        resolve_reference(config_db, &args.reference).ok_or_else(not_found)?;
+    let source = crate::DocumentId::new(&collection, &path);
+    if config_db
+        .get_document_metadata_typed(source.numeric)?
+        .is_none()
+    {
+        return Err(not_found());
+    }
+
+    let plaid_index =
+        plaid::open_index(data_dir)?.ok_or(Error::PlaidIndexMissing)?;
+
+    let mut metadata: HashMap<u64, DocumentMetadata> = config_db
+        .list_all_document_metadata_typed()?
+        .into_iter()
+        .filter(|(_, meta)| {
+            args.collection
+                .as_deref()
+                .is_none_or(|c| c == meta.collection.as_str())
+        })
+        .collect();
+    // The scope covers the whole collection, source included, so a
+    // PLAID filter is the same one collection searches cache.
+    let params = plaid::load_query_params(config_db)?;
+    let backend = semantic_backend(
+        None,
+        config_db,
+        data_dir,
+        &plaid_index,
+        args.collection.as_deref(),
+        &metadata,
+        &params,
+    )?;
+    metadata.remove(&source.numeric);
+
+    // Semantic leg. The source's own chunks score highest against its
+    // tokens, so oversample past them before collapsing to documents.
+    let query_tokens = similar_query_tokens(
+        config_db,
+        data_dir,
+        &plaid_index,
+        source.numeric,
+    )?;
+    let top_k = RRF_CANDIDATE_LIMIT.saturating_mul(8);
+    let raw_results = search_semantic_tokens(
+        &plaid_index,
+        &backend,
+        &query_tokens,
+        top_k,
+        &params,
+        &mut SearchExplain::default(),
+    )?;
+    let (sem_ranked, chunk_hits) = rank_documents(
+        config_db,
+        &metadata,
+        &raw_results,
+        RRF_CANDIDATE_LIMIT,
+    )?;
+
+    // MoreLikeThis leg over the text the source holds today.
+    let mlt_results = match source_text(config_db, &collection, &path) {
+        Some(body) => {
+            let title = search_index
+                .find_by_collection_path(&collection, &path)?
+                .map(|r| r.title)
+                .unwrap_or_default();
+            search_index.more_like_this(
+                &title,
+                &body,
+                &source.full_hex(),
+                args.collection.as_deref(),
+                RRF_CANDIDATE_LIMIT,
+            )?
+        }
+        None => Vec::new(),
+    };
+
+    let mut results = fuse_legs(&mlt_results, &metadata, &sem_ranked);
+    results.truncate(args.count);
+    for (i, r) in results.iter_mut().enumerate() {
+        r.rank = i + 1;
+    }
+
+    attach_passages(&mut results, &chunk_hits, config_db, args.passages);
+    if results.iter().any(|r| r.title.is_empty()) {
+        populate_titles(&mut results, config_db);
+    }
+
+    Ok(results)
+}
+
+/// Token embeddings of `doc_num_id` as a flat PLAID query, subsampled
+/// evenly down to [`SIMILAR_QUERY_TOKEN_LIMIT`] tokens.
+///
+/// Each chunk in the document's manifest is read from the embedding
+/// database and, failing that, decoded from the PLAID index. Documents
+/// without a manifest yield an empty query.
+fn similar_query_tokens(
+    config_db: &ConfigDb,
+    data_dir: &DataDir,
+    plaid_index: &plaid::PlaidIndex,
+    doc_num_id: u64,
+) -> Result<Vec<f32>> {
+    let Some(manifest) = config_db.get_doc_chunks(doc_num_id)? else {
+        return Ok(Vec::new());
+    };
+    let mut chunk_ids: Vec<u64> =
+        manifest.iter().map(|c| c.chunk_doc_id).collect();
+    chunk_ids.sort_unstable();
+    chunk_ids.dedup();
+
+    let embedding_db = crate::EmbeddingDb::open(&data_dir.embeddings_db())?;
+    let mut flat = Vec::new();
+    for (chunk_id, matrix) in embedding_db.batch_load(&chunk_ids)? {
+        match matrix {
+            Some(matrix) => flat.extend(matrix.data),
+            None => {
+                if let Some(decoded) =
+                    plaid::decode_document_tokens(plaid_index, chunk_id)?
+                {
+                    flat.extend(decoded);
+                }
+            }
+        }
+    }
+
+    Ok(subsample_tokens(
+        flat,
+        plaid_index.params.dim,
+        SIMILAR_QUERY_TOKEN_LIMIT,
+    ))
+}
+
+/// Keep at most `limit` evenly spaced rows of a flat `[n * dim]` matrix.
+fn subsample_tokens(flat: Vec<f32>, dim: usize, limit: usize) -> Vec<f32> {
+    let n_tokens = flat.len().checked_div(dim).unwrap_or(0);
+    if n_tokens <= limit {
+        return flat;
+    }
+    (0..limit)
+        .flat_map(|i| {
+            let row = i * n_tokens / limit;
+            flat[row * dim..(row + 1) * dim].iter().copied()
+        })
+        .collect()
+}
+
+/// Default number of passages [`within`] returns.
+pub const DEFAULT_WITHIN_COUNT: usize = 5;
+
+/// Options for searching inside one document.
+///
+/// # Examples
+///
+/// ```
+/// use docbert_core::search::{DEFAULT_WITHIN_COUNT, WithinParams};
+///
+/// let params = WithinParams {
+///     reference: "specs:http.pdf".to_string(),
+///     query: "cache invalidation".to_string(),
+///     count: DEFAULT_WITHIN_COUNT,
+///     min_score: 0.0,
+/// };
+/// ```
+#[derive(Debug, Clone)]
+pub struct WithinParams {
+    /// The document to search: `#short_id`, `collection:path`, or a bare
+    /// relative path (see [`resolve_reference`]).
+    pub reference: String,
+    /// The search query.
+    pub query: String,
+    /// Number of passages to return.
+    pub count: usize,
+    /// Drop passages scoring below this.
+    pub min_score: f32,
+}
+
+/// One chunk of the searched document, ranked by [`within`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocumentPassage {
+    /// 1-based position in the ranking.
+    pub rank: usize,
+    /// Exact ColBERT MaxSim score of this chunk against the query.
+    pub score: f32,
+    /// Content-derived chunk identifier.
+    pub chunk_doc_id: u64,
+    /// Byte offset where the chunk begins in the document.
+    pub start_byte: u64,
+    /// Byte length of the chunk in the document.
+    pub byte_len: u64,
+    /// 1-based line the chunk begins on.
+    pub start_line: usize,
+    /// 1-based line the chunk ends on, inclusive.
+    pub end_line: usize,
+    /// The chunk's text.
+    pub text: String,
+}
+
+impl DocumentPassage {
+    /// Convert a passage into its byte range.
+    pub fn byte_offset(&self) -> ChunkByteOffset {
+        ChunkByteOffset {
+            start_byte: self.start_byte,
+            byte_len: self.byte_len,
+        }
+    }
+}
+
+/// What [`within`] found in one document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WithinOutcome {
+    /// Collection of the searched document.
+    pub collection: String,
+    /// Path of the searched document inside its collection.
+    pub path: String,
+    /// Number of chunks scored against the query.
+    pub chunks_scored: usize,
+    /// The best chunks, highest score first.
+    pub passages: Vec<DocumentPassage>,
+}
+
+/// Find the parts of one indexed document that best match a query.
+///
+/// The reference is resolved with [`resolve_reference`]. Every chunk in
+/// the document's manifest is scored with exact ColBERT MaxSim between
+/// the encoded query and the chunk's stored token embeddings; chunks
+/// missing from the embedding database are decoded from the PLAID index
+/// instead, and skipped when that isn't built either. No candidate
+/// generation runs, so no chunk of the document can be missed.
+///
+/// Byte and line ranges locate each passage in the document's current
+/// text, as read by [`preparation::load_preview_content`]; ties keep
+/// document order. Documents indexed before chunk offsets were tracked
+/// have no manifest and return no passages.
+///
+/// Fails with [`Error::NotFound`] when the reference doesn't name an
+/// indexed document or its file can't be read.
+///
+/// [`preparation::load_preview_content`]: crate::preparation::load_preview_content
+///
+/// # Examples
+///
+/// ```no_run
+/// use docbert_core::{ConfigDb, DataDir, ModelManager};
+/// use docbert_core::search::{within, WithinParams};
+///
+/// # let tmp = tempfile::tempdir().unwrap();
+/// let data_dir = DataDir::new(tmp.path());
+/// let config_db = ConfigDb::open(&data_dir.config_db()).unwrap();
+/// let mut model = ModelManager::new();
+///
+/// let params = WithinParams {
+///     reference: "specs:http.pdf".to_string(),
+///     query: "cache invalidation".to_string(),
+///     count: 3,
+///     min_score: 0.0,
+/// };
+/// let outcome = within(&params, &config_db, &data_dir, &mut model).unwrap();
+/// for p in &outcome.passages {
+///     println!("{}: lines {}-{}", p.rank, p.start_line, p.end_line);
+/// }
+/// ```
+pub fn within(
+    args: &WithinParams,
+    config_db: &ConfigDb,
+    data_dir: &DataDir,
+    model: &mut ModelManager,
+) -> Result<WithinOutcome> {
+    within_scored(args, config_db, data_dir, |query| {
+        let embedding = model.encode_query(query)?;
+        let (_, dim) = embedding.dims2()?;
+        let flat = embedding
+            .contiguous()?
+            .to_device(&candle_core::Device::Cpu)?
+            .flatten_all()?
+            .to_vec1::<f32>()?;
+        Ok((flat, dim))
+    })
+}
+
+/// [`within`] with the query encoder supplied by the caller, which
+/// returns a flat, row-major `[n_tokens * dim]` matrix and `dim`.
+///
+/// The encoder only runs when the document has chunks to score.
+fn within_scored(
+    args: &WithinParams,
+    config_db: &ConfigDb,
+    data_dir: &DataDir,
+    encode: impl FnOnce(&str) -> Result<(Vec<f32>, usize)>,
+) -> Result<WithinOutcome> {
+    let not_found = || Error::NotFound {
+        kind: "document",
+        name: args.reference.clone(),
+    };
+    let (collection, path) =
+        resolve_reference(config_db, &args.reference).ok_or_else(not_found)?;
+    let source = crate::DocumentId::new(&collection, &path);
+    if config_db
+        .get_document_metadata_typed(source.numeric)?
+        .is_none()
+    {
+        return Err(not_found());
+    }
+    let content =
+        source_text(config_db, &collection, &path).ok_or_else(not_found)?;
+    let manifest = config_db
+        .get_doc_chunks(source.numeric)?
+        .unwrap_or_default();
+
+    let mut outcome = WithinOutcome {
+        collection,
+        path,
+        chunks_scored: 0,
+        passages: Vec::new(),
+    };
+    if manifest.is_empty() || args.count == 0 {
+        return Ok(outcome);
+    }
+
+    let (query, dim) = encode(&args.query)?;
+    let mut chunk_ids: Vec<u64> =
+        manifest.iter().map(|c| c.chunk_doc_id).collect();
+    chunk_ids.sort_unstable();
+    chunk_ids.dedup();
+    let scores = chunk_maxsim_scores(data_dir, &chunk_ids, &query, dim)?;
+    outcome.chunks_scored = scores.len();
+
+    let mut scored: Vec<(f32, &crate::config_db::DocChunkEntry)> = manifest
+        .iter()
+        .filter_map(|entry| {
+            let score = *scores.get(&entry.chunk_doc_id)?;
+            (score >= args.min_score).then_some((score, entry))
+        })
+        .collect();
+    scored.sort_by(|a, b| {
+        b.0.partial_cmp(&a.0)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.1.start_byte.cmp(&b.1.start_byte))
+    });
+
+    for (score, entry) in scored {
+        if outcome.passages.len() == args.count {
+            break;
+        }
+        let range =
+            crate::text::byte_range(&content, entry.start_byte, entry.byte_len);
+        if range.is_empty() {
+            continue;
+        }
+        let text = &content[range.clone()];
+        let start_line = content[..range.start].matches('\n').count() + 1;
+        let end_line = start_line
+            + text
+                .strip_suffix('\n')
+                .unwrap_or(text)
+                .matches('\n')
+                .count();
+        outcome.passages.push(DocumentPassage {
+            rank: outcome.passages.len() + 1,
+            score,
+            chunk_doc_id: entry.chunk_doc_id,
+            start_byte: range.start as u64,
+            byte_len: range.len() as u64,
+            start_line,
+            end_line,
+            text: text.to_string(),
+        });
+    }
+    Ok(outcome)
+}
+
+/// Exact MaxSim of a flat `[n_tokens * dim]` query against each chunk's
+/// token embeddings.
+///
+/// Embeddings come from the embedding database and, failing that, from
+/// the PLAID index, which is only loaded when a chunk is missing.
+/// Chunks found in neither are left out of the returned map.
+fn chunk_maxsim_scores(
+    data_dir: &DataDir,
+    chunk_ids: &[u64],
+    query: &[f32],
+    dim: usize,
+) -> Result<HashMap<u64, f32>> {
+    if dim == 0 || query.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let embedding_db = crate::EmbeddingDb::open(&data_dir.embeddings_db())?;
+    let mut plaid_index = None;
+    let mut maxsim = MaxSim::dense(Kernel::detect(), query, dim);
+    let mut scores = HashMap::with_capacity(chunk_ids.len());
+    for (chunk_id, matrix) in embedding_db.batch_load(chunk_ids)? {
+        let tokens = match matrix {
+            Some(matrix) => {
+                if matrix.dimension as usize != dim {
+                    return Err(Error::Config(format!(
+                        "query has dim {dim} but chunk {chunk_id} has dim {}",
+                        matrix.dimension,
+                    )));
+                }
+                matrix.data
+            }
+            None => {
+                if plaid_index.is_none() {
+                    plaid_index = Some(plaid::open_index(data_dir)?);
+                }
+                let decoded =
+                    match plaid_index.as_ref().and_then(Option::as_ref) {
+                        Some(index) if index.params.dim == dim => {
+                            plaid::decode_document_tokens(index, chunk_id)?
+                        }
+                        _ => None,
+                    };
+                let Some(decoded) = decoded else {
+                    continue;
+                };
+                decoded
+            }
+        };
+        if !tokens.is_empty() {
+            scores.insert(chunk_id, maxsim.score_dense(&tokens));
+        }
+    }
+    Ok(scores)
+}
+
+/// Current text of an indexed document, or `None` when its collection
+/// or file can't be read.
+fn source_text(
+    config_db: &ConfigDb,
+    collection: &str,
+    path: &str,
+) -> Option<String> {
+    let root = config_db.get_collection(collection).ok().flatten()?;
+    let full_path =
+        crate::path_safety::resolve_document_path(Path::new(&root), path)
+            .ok()?;
+    crate::preparation::load_preview_content(Path::new(path), &full_path).ok()
+}
+
+/// Rescore the head of a ranked result list with the configured
+/// cross-encoder and reorder it by that score.
+///
+/// Only the first [`RERANK_CANDIDATE_LIMIT`] results are scored; the
+/// tail keeps its incoming order behind them. Each result is scored on
+/// its best-matching chunk when one is known, falling back to the
+/// document's first chunk and then to the head of the document. This
+/// is a no-op when no reranker is configured.
+fn rerank_with_cross_encoder(
+    results: &mut [FinalResult],
+    query: &str,
+    config_db: &ConfigDb,
+    model: &mut ModelManager,
+) -> Result<()> {
+    if !model.has_reranker() || results.is_empty() {
+        return Ok(());
+    }
+
+    let head_len = results.len().min(RERANK_CANDIDATE_LIMIT);
+    let head = &mut results[..head_len];
+    let mut collection_paths: HashMap<String, Option<String>> = HashMap::new();
+    let passages: Vec<String> = head
+        .iter()
+        .map(|r| rerank_passage(r, config_db, &mut collection_paths))
+        .collect();
+
+    let scores = model.rerank(query, &passages)?;
+    for (result, score) in head.iter_mut().zip(scores) {
+        result.rerank_score = Some(score);
+    }
+    head.sort_by(|a, b| {
+        b.rerank_score
+            .partial_cmp(&a.rerank_score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    Ok(())
+}
+
+/// [`rerank_with_cross_encoder`], keeping the incoming order when the
+/// cross-encoder fails. The reranker is an optional refinement, so a
+/// checkpoint that can't be loaded or run degrades the search instead
+/// of failing it; the error is logged and kept in
+/// [`SearchExplain::rerank_error`].
+fn rerank_or_keep_order(
+    results: &mut [FinalResult],
+    query: &str,
+    config_db: &ConfigDb,
+    model: &mut ModelManager,
+    explain: &mut SearchExplain,
+) {
+    if let Err(err) =
+        rerank_with_cross_encoder(results, query, config_db, model)
+    {
+        tracing::warn!(
+            %err,
+            reranker = model.reranker_model().unwrap_or_default(),
+            "cross-encoder rerank failed, keeping the fused order"
+        );
+        explain.rerank_error = Some(err.to_string());
     }
+}
 
-    results
+/// Text the cross-encoder reads for one result.
+fn rerank_passage(
+    result: &FinalResult,
+    config_db: &ConfigDb,
+    collection_paths: &mut HashMap<String, Option<String>>,
+) -> String {
+    best_chunk_text(result, config_db, collection_paths)
+        .map(|(_, text)| text)
+        .unwrap_or_else(|| result.title.clone())
 }
 
-/// Run semantic-only search across all indexed documents.
+/// Load the text of a result's best-matching chunk together with the
+/// byte offset it starts at in the source document.
 ///
-/// Unlike [`run`], this skips BM25 entirely and returns
-/// results purely from the PLAID index. If the index has not been built
-/// yet, the call fails with [`Error::PlaidIndexMissing`]; the caller
-/// should surface this as a clear "run `docbert sync`" message.
+/// Falls back to the document's first chunk and then to the head of the
+/// document when no chunk is known. Returns `None` when the source file
+/// can't be read.
+fn best_chunk_text(
+    result: &FinalResult,
+    config_db: &ConfigDb,
+    collection_paths: &mut HashMap<String, Option<String>>,
+) -> Option<(u64, String)> {
+    let collection_path = collection_paths
+        .entry(result.collection.clone())
+        .or_insert_with(|| {
+            config_db.get_collection(&result.collection).ok().flatten()
+        });
+    let content = collection_path
+        .as_deref()
+        .filter(|root| !root.is_empty())
+        .and_then(|root| {
+            let relative = Path::new(&result.path);
+            crate::preparation::load_preview_content(
+                relative,
+                &Path::new(root).join(relative),
+            )
+            .ok()
+        });
+    let content = content?;
+
+    let chunk_offset = match result.best_chunk_doc_id {
+        Some(chunk_id) => config_db
+            .get_chunk_offset_for_doc(result.doc_num_id, chunk_id)
+            .ok()
+            .flatten(),
+        None => config_db
+            .get_doc_chunks(result.doc_num_id)
+            .ok()
+            .flatten()
+            .and_then(|chunks| chunks.first().map(|c| c.byte_offset())),
+    };
+    let (start, len) = match chunk_offset {
+        Some(offset) => (offset.start_byte, offset.byte_len),
+        None => (0, RERANK_FALLBACK_PASSAGE_BYTES),
+    };
+    let range = crate::text::byte_range(&content, start, len);
+    Some((range.start as u64, content[range].to_string()))
+}
+
+/// Attach [`TokenMatch`]es to every result, explaining how the query's
+/// tokens aligned with the result's best chunk under MaxSim.
 ///
-/// The ColBERT model is loaded on first use.
-pub fn semantic(
-    args: &SemanticSearchParams,
+/// Each result is explained against the same passage the cross-encoder
+/// stage would read, and the spans are shifted to source-document byte
+/// offsets. Special and prefix tokens with no source text are left out.
+/// Results whose source file can't be read are left without matches.
+///
+/// # Examples
+///
+/// ```no_run
+/// use docbert_core::{ConfigDb, ModelManager};
+/// use docbert_core::search::explain_matches;
+///
+/// # let tmp = tempfile::tempdir().unwrap();
+/// let config_db = ConfigDb::open(&tmp.path().join("config.db")).unwrap();
+/// let mut model = ModelManager::new();
+/// let mut results = Vec::new();
+/// explain_matches(&mut results, "rust ownership", &config_db, &mut model)
+///     .unwrap();
+/// ```
+pub fn explain_matches(
+    results: &mut [FinalResult],
+    query: &str,
     config_db: &ConfigDb,
-    data_dir: &DataDir,
     model: &mut ModelManager,
-) -> Result<Vec<FinalResult>> {
-    let plaid_index =
-        plaid::load_index(data_dir)?.ok_or(Error::PlaidIndexMissing)?;
-
-    let metadata_entries = config_db.list_all_document_metadata_typed()?;
-    if metadata_entries.is_empty() {
-        return Ok(vec![]);
+) -> Result<()> {
+    let mut collection_paths: HashMap<String, Option<String>> = HashMap::new();
+    for result in results {
+        let Some((chunk_start, text)) =
+            best_chunk_text(result, config_db, &mut collection_paths)
+        else {
+            continue;
+        };
+        let explanation = model.explain_match(query, &text)?;
+        result.token_matches =
+            token_matches_from_alignments(&explanation.alignments, chunk_start);
     }
+    Ok(())
+}
 
-    let metadata: HashMap<u64, DocumentMetadata> = metadata_entries
-        .into_iter()
-        .filter(|(_, meta)| {
-            args.collection.is_none()
-                || args.collection.as_deref() == Some(meta.collection.as_str())
+/// Keep the alignments that point at source text and shift their spans
+/// from chunk-relative to document byte offsets.
+fn token_matches_from_alignments(
+    alignments: &[docbert_pylate::TokenAlignment],
+    chunk_start: u64,
+) -> Vec<TokenMatch> {
+    alignments
+        .iter()
+        .filter_map(|alignment| {
+            let (start, end) = alignment.span?;
+            Some(TokenMatch {
+                query_token: alignment.query_token.clone(),
+                document_token: alignment.document_token.clone(),
+                score: alignment.score,
+                start_byte: chunk_start + start as u64,
+                byte_len: (end - start) as u64,
+            })
         })
-        .collect();
-    if metadata.is_empty() {
-        return Ok(vec![]);
-    }
-
-    let query_embedding = model.encode_query(&args.query)?;
+        .collect()
+}
 
-    let oversample = args.count.saturating_mul(8).max(args.count).max(64);
-    let raw_results =
-        plaid::search(&plaid_index, &query_embedding, oversample)?;
+pub fn by_mode(
+    mode: SearchMode,
+    request: &SearchQuery,
+    search_index: &SearchIndex,
+    config_db: &ConfigDb,
+    data_dir: &DataDir,
+    model: &mut ModelManager,
+) -> Result<Vec<FinalResult>> {
+    by_mode_explained(mode, request, search_index, config_db, data_dir, model)
+        .map(|outcome| outcome.results)
+}
 
-    let mut ranked = collapse_chunks_to_documents(
+/// [`by_mode`], also returning a [`SearchExplain`] when
+/// `request.explain` is set.
+pub fn by_mode_explained(
+    mode: SearchMode,
+    request: &SearchQuery,
+    search_index: &SearchIndex,
+    config_db: &ConfigDb,
+    data_dir: &DataDir,
+    model: &mut ModelManager,
+) -> Result<SearchOutcome> {
+    by_mode_paged(
+        mode,
+        request,
+        None,
+        None,
+        search_index,
         config_db,
-        &metadata,
-        &raw_results,
-        args.count,
-    )?;
-    ranked.sort_by(|a, b| {
-        b.score
-            .partial_cmp(&a.score)
-            .unwrap_or(std::cmp::Ordering::Equal)
-    });
-
-    let mut results = semantic_final_results_from_ranked(
-        &metadata,
-        ranked,
-        args.min_score,
-        args.count,
-        args.all,
-    );
+        data_dir,
+        model,
+    )
+}
 
-    populate_titles(&mut results, config_db);
+/// [`by_mode_explained`], keeping the ranking in `cache` so later pages
+/// are cut from it (see [`run_cached`] and [`semantic_cached`]).
+pub fn by_mode_cached(
+    mode: SearchMode,
+    request: &SearchQuery,
+    cache: &SearchCache,
+    search_index: &SearchIndex,
+    config_db: &ConfigDb,
+    data_dir: &DataDir,
+    model: &mut ModelManager,
+) -> Result<SearchOutcome> {
+    by_mode_paged(
+        mode,
+        request,
+        Some(cache),
+        None,
+        search_index,
+        config_db,
+        data_dir,
+        model,
+    )
+}
 
-    Ok(results)
+/// [`by_mode_explained`] for many requests in the same mode, sharing
+/// one PLAID load and one query encoding pass (see [`run_batch`]).
+pub fn by_mode_batch(
+    mode: SearchMode,
+    requests: &[SearchQuery],
+    search_index: &SearchIndex,
+    config_db: &ConfigDb,
+    data_dir: &DataDir,
+    model: &mut ModelManager,
+) -> Result<Vec<SearchOutcome>> {
+    let batch = if mode == SearchMode::Bm25 || requests.is_empty() {
+        None
+    } else {
+        Some(QueryBatch::prepare(
+            requests.iter().map(|r| r.query.as_str()),
+            config_db,
+            data_dir,
+            model,
+        )?)
+    };
+    requests
+        .iter()
+        .map(|request| {
+            by_mode_paged(
+                mode,
+                request,
+                None,
+                batch.as_ref(),
+                search_index,
+                config_db,
+                data_dir,
+                model,
+            )
+        })
+        .collect()
 }
 
-pub fn by_mode(
+#[allow(clippy::too_many_arguments)]
+fn by_mode_paged(
     mode: SearchMode,
     request: &SearchQuery,
+    cache: Option<&SearchCache>,
+    batch: Option<&QueryBatch>,
     search_index: &SearchIndex,
     config_db: &ConfigDb,
     data_dir: &DataDir,
     model: &mut ModelManager,
-) -> Result<Vec<FinalResult>> {
+) -> Result<SearchOutcome> {
+    let hybrid = |bm25_only| SearchParams {
+        query: request.query.clone(),
+        count: request.count,
+        collection: request.collection.clone(),
+        min_score: request.min_score,
+        bm25_only,
+        no_fuzzy: false,
+        all: false,
+        passages: request.passages,
+        explain: request.explain,
+        prf: request.prf,
+        facets: request.facets,
+        offset: request.offset,
+        cursor: request.cursor,
+        autocorrect: request.autocorrect,
+        plaid: request.plaid,
+    };
     match mode {
-        SearchMode::Semantic => semantic(
+        SearchMode::Semantic => semantic_paged(
             &SemanticSearchParams {
                 query: request.query.clone(),
                 collection: request.collection.clone(),
                 count: request.count,
                 min_score: request.min_score,
                 all: false,
+                passages: request.passages,
+                explain: request.explain,
+                facets: request.facets,
+                offset: request.offset,
+                cursor: request.cursor,
+                plaid: request.plaid,
             },
+            cache,
+            batch,
             config_db,
             data_dir,
             model,
         ),
-        SearchMode::Hybrid => run(
-            &SearchParams {
-                query: request.query.clone(),
-                count: request.count,
-                collection: request.collection.clone(),
-                min_score: request.min_score,
-                bm25_only: false,
-                no_fuzzy: false,
-                all: false,
-            },
+        SearchMode::Hybrid => run_paged(
+            &hybrid(false),
+            cache,
+            batch,
             search_index,
             config_db,
             data_dir,
             model,
         ),
-        SearchMode::Bm25 => run(
-            &SearchParams {
-                query: request.query.clone(),
-                count: request.count,
-                collection: request.collection.clone(),
-                min_score: request.min_score,
-                bm25_only: true,
-                no_fuzzy: false,
-                all: false,
-            },
+        SearchMode::Bm25 => run_paged(
+            &hybrid(true),
+            cache,
+            None,
             search_index,
             config_db,
             data_dir,
@@ -785,6 +3572,10 @@ fn bm25_to_final(results: &[SearchResult]) -> Vec<FinalResult> {
             // BM25 indexes whole documents — there is no per-chunk
             // score to point at, so leave the chunk offset hint empty.
             best_chunk_doc_id: None,
+            rerank_score: None,
+            passages: Vec::new(),
+            token_matches: Vec::new(),
+            explain: None,
         })
         .collect()
 }
@@ -878,7 +3669,9 @@ pub fn disambiguate_doc_ids(results: &mut [FinalResult], config_db: &ConfigDb) {
 /// Print results in the default terminal format.
 ///
 /// Each result is shown as `rank. [score] collection:path #doc_id`, with the
-/// title on the next line when one is available. A total count is printed last.
+/// title on the next line when one is available, one `@ bytes` line per
+/// passage in passage mode and one `~` line per token match when
+/// explanations were attached. A total count is printed last.
 pub fn format_human(results: &[FinalResult]) {
     if results.is_empty() {
         println!("No results found.");
@@ -886,20 +3679,274 @@ pub fn format_human(results: &[FinalResult]) {
     }
 
     for r in results {
+        let score = match r.rerank_score {
+            Some(rerank) => format!("{:.3} | rerank {rerank:.3}", r.score),
+            None => format!("{:.3}", r.score),
+        };
         println!(
-            "{:>3}. [{:.3}] {}:{} {}",
-            r.rank, r.score, r.collection, r.path, r.doc_id
+            "{:>3}. [{score}] {}:{} {}",
+            r.rank, r.collection, r.path, r.doc_id
         );
         if !r.title.is_empty() {
             println!("     {}", r.title);
         }
+        if let Some(explain) = &r.explain {
+            println!("     {}", result_explain_human(explain));
+        }
+        for passage in &r.passages {
+            let start = passage.start_byte;
+            let end = passage.byte_offset().inclusive_end().unwrap_or(start);
+            println!("     @ bytes {start}-{end} [{:.3}]", passage.score);
+        }
+        for token in &r.token_matches {
+            let start = token.start_byte;
+            let end = token.byte_offset().inclusive_end().unwrap_or(start);
+            println!(
+                "     ~ {} -> {} @ bytes {start}-{end} [{:.3}]",
+                token.query_token, token.document_token, token.score
+            );
+        }
     }
     println!("\n{} result(s)", results.len());
 }
 
+fn leg_hit_human(name: &str, hit: Option<LegHit>) -> String {
+    match hit {
+        Some(hit) if hit.rrf > 0.0 => format!(
+            "{name} #{} {:.3} (rrf {:.4})",
+            hit.rank, hit.score, hit.rrf
+        ),
+        Some(hit) => format!("{name} #{} {:.3}", hit.rank, hit.score),
+        None => format!("{name} -"),
+    }
+}
+
+fn result_explain_human(explain: &ResultExplain) -> String {
+    format!(
+        "{} | {} | fused #{}",
+        leg_hit_human("bm25", explain.bm25),
+        leg_hit_human("semantic", explain.semantic),
+        explain.fused_rank
+    )
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Print the query-level part of an explained search.
+///
+/// Shows stage timings in milliseconds, candidate counts per leg, and
+/// how many candidates each filter dropped. Per-result breakdowns are
+/// printed by [`format_human`].
+pub fn format_explain_human(explain: &SearchExplain) {
+    let t = &explain.timings;
+    println!(
+        "timings: encode {:.1}ms | plaid probe {:.1}ms | plaid decode {:.1}ms | exact {:.1}ms | tantivy {:.1}ms | rerank {:.1}ms | total {:.1}ms",
+        millis(t.query_encoding),
+        millis(t.plaid_probe),
+        millis(t.plaid_decode),
+        millis(t.exact),
+        millis(t.tantivy),
+        millis(t.rerank),
+        millis(t.total),
+    );
+    println!(
+        "candidates: bm25 {} | semantic {} (plaid probed {}, decoded {}; exact scored {})",
+        explain.bm25_candidates,
+        explain.semantic_candidates,
+        explain.plaid_probed_docs,
+        explain.plaid_decoded_docs,
+        explain.exact_scored_docs,
+    );
+    let by_reason = |reason: DropReason| {
+        explain
+            .dropped
+            .iter()
+            .filter(|d| d.reason == reason)
+            .count()
+    };
+    println!(
+        "dropped: {} (limit {}, min_score {} among the first {})",
+        explain.dropped_count,
+        by_reason(DropReason::Limit),
+        by_reason(DropReason::MinScore),
+        explain.dropped.len(),
+    );
+    if explain.min_score_ignored {
+        println!("note: min_score is ignored under RRF fusion");
+    }
+    if let Some(err) = &explain.rerank_error {
+        println!("note: rerank failed, fused order kept: {err}");
+    }
+}
+
+/// Print a spelling suggestion: `Did you mean: ...?`, or
+/// `Showing results for: ...` when the results are for it.
+pub fn format_suggestion_human(suggestion: &QuerySuggestion) {
+    if suggestion.applied {
+        println!("Showing results for: {}", suggestion.query);
+    } else {
+        println!("Did you mean: {}?", suggestion.query);
+    }
+}
+
+/// Print facet counts as one `name: value (count), ...` line per
+/// non-empty facet, after a header with the number of candidates.
+pub fn format_facets_human(facets: &Facets) {
+    println!("facets over {} candidates:", facets.total);
+    let facet_lists = [
+        ("collection", &facets.collections),
+        ("extension", &facets.extensions),
+        ("year", &facets.years),
+        ("tag", &facets.tags),
+    ];
+    for (name, counts) in facet_lists {
+        if counts.is_empty() {
+            continue;
+        }
+        let values: Vec<String> = counts
+            .iter()
+            .map(|c| format!("{} ({})", c.value, c.count))
+            .collect();
+        println!("  {name}: {}", values.join(", "));
+    }
+}
+
+fn leg_hit_json_string(hit: Option<LegHit>) -> String {
+    match hit {
+        Some(hit) => format!(
+            "{{\"rank\":{},\"score\":{:.6},\"rrf\":{:.6}}}",
+            hit.rank, hit.score, hit.rrf
+        ),
+        None => "null".to_string(),
+    }
+}
+
+fn result_explain_json_string(explain: &ResultExplain) -> String {
+    format!(
+        "{{\"bm25\":{},\"semantic\":{},\"fused_rank\":{}}}",
+        leg_hit_json_string(explain.bm25),
+        leg_hit_json_string(explain.semantic),
+        explain.fused_rank
+    )
+}
+
+fn search_explain_json_string(explain: &SearchExplain) -> String {
+    let t = &explain.timings;
+    let dropped: Vec<String> = explain
+        .dropped
+        .iter()
+        .map(|d| {
+            format!(
+                "{{\"collection\":{},\"path\":{},\"score\":{:.6},\"reason\":{},\"explain\":{}}}",
+                json_escape(&d.collection),
+                json_escape(&d.path),
+                d.score,
+                json_escape(d.reason.as_str()),
+                result_explain_json_string(&d.explain)
+            )
+        })
+        .collect();
+    format!(
+        "{{\"timings_ms\":{{\"query_encoding\":{:.3},\"plaid_probe\":{:.3},\"plaid_decode\":{:.3},\"exact\":{:.3},\"tantivy\":{:.3},\"rerank\":{:.3},\"total\":{:.3}}},\"bm25_candidates\":{},\"semantic_candidates\":{},\"plaid_probed_docs\":{},\"plaid_decoded_docs\":{},\"exact_scored_docs\":{},\"min_score_ignored\":{},\"rerank_error\":{},\"dropped_count\":{},\"dropped\":[{}]}}",
+        millis(t.query_encoding),
+        millis(t.plaid_probe),
+        millis(t.plaid_decode),
+        millis(t.exact),
+        millis(t.tantivy),
+        millis(t.rerank),
+        millis(t.total),
+        explain.bm25_candidates,
+        explain.semantic_candidates,
+        explain.plaid_probed_docs,
+        explain.plaid_decoded_docs,
+        explain.exact_scored_docs,
+        explain.min_score_ignored,
+        explain
+            .rerank_error
+            .as_deref()
+            .map_or_else(|| "null".to_string(), json_escape),
+        explain.dropped_count,
+        dropped.join(","),
+    )
+}
+
+fn facets_json_string(facets: &Facets) -> String {
+    let list = |counts: &[FacetCount]| {
+        let items: Vec<String> = counts
+            .iter()
+            .map(|c| {
+                format!(
+                    "{{\"value\":{},\"count\":{}}}",
+                    json_escape(&c.value),
+                    c.count
+                )
+            })
+            .collect();
+        format!("[{}]", items.join(","))
+    };
+    format!(
+        "{{\"total\":{},\"collections\":{},\"extensions\":{},\"years\":{},\"tags\":{}}}",
+        facets.total,
+        list(&facets.collections),
+        list(&facets.extensions),
+        list(&facets.years),
+        list(&facets.tags),
+    )
+}
+
+fn passage_json_string(passage: &Passage) -> String {
+    let start = passage.start_byte;
+    let end = passage.byte_offset().inclusive_end().unwrap_or(start);
+    format!(
+        "{{\"score\":{:.6},\"start_byte\":{start},\"end_byte\":{end}}}",
+        passage.score
+    )
+}
+
+fn token_match_json_string(token: &TokenMatch) -> String {
+    let start = token.start_byte;
+    let end = token.byte_offset().inclusive_end().unwrap_or(start);
+    format!(
+        "{{\"query_token\":{},\"document_token\":{},\"score\":{:.6},\"start_byte\":{start},\"end_byte\":{end}}}",
+        json_escape(&token.query_token),
+        json_escape(&token.document_token),
+        token.score
+    )
+}
+
 fn search_result_json_string(result: &FinalResult) -> String {
+    let rerank = result
+        .rerank_score
+        .map(|score| format!(",\"rerank_score\":{score:.6}"))
+        .unwrap_or_default();
+    let passages = if result.passages.is_empty() {
+        String::new()
+    } else {
+        let items: Vec<String> =
+            result.passages.iter().map(passage_json_string).collect();
+        format!(",\"passages\":[{}]", items.join(","))
+    };
+    let token_matches = if result.token_matches.is_empty() {
+        String::new()
+    } else {
+        let items: Vec<String> = result
+            .token_matches
+            .iter()
+            .map(token_match_json_string)
+            .collect();
+        format!(",\"token_matches\":[{}]", items.join(","))
+    };
+    let explain = result
+        .explain
+        .as_ref()
+        .map(|explain| {
+            format!(",\"explain\":{}", result_explain_json_string(explain))
+        })
+        .unwrap_or_default();
     format!(
-        "{{\"rank\":{},\"score\":{:.6},\"doc_id\":{},\"collection\":{},\"path\":{},\"title\":{}}}",
+        "{{\"rank\":{},\"score\":{:.6},\"doc_id\":{},\"collection\":{},\"path\":{},\"title\":{}{rerank}{passages}{token_matches}{explain}}}",
         result.rank,
         result.score,
         json_escape(&result.doc_id),
@@ -909,7 +3956,13 @@ fn search_result_json_string(result: &FinalResult) -> String {
     )
 }
 
-fn format_json_string(results: &[FinalResult], query: &str) -> String {
+fn format_json_string(
+    results: &[FinalResult],
+    query: &str,
+    explain: Option<&SearchExplain>,
+    facets: Option<&Facets>,
+    suggestion: Option<&QuerySuggestion>,
+) -> String {
     let mut output = format!(
         "{{\"query\":{},\"result_count\":{},\"results\":[",
         json_escape(query),
@@ -923,16 +3976,78 @@ fn format_json_string(results: &[FinalResult], query: &str) -> String {
         output.push_str(&search_result_json_string(result));
     }
 
-    output.push_str("]}");
+    output.push(']');
+    if let Some(explain) = explain {
+        output.push_str(",\"explain\":");
+        output.push_str(&search_explain_json_string(explain));
+    }
+    if let Some(facets) = facets {
+        output.push_str(",\"facets\":");
+        output.push_str(&facets_json_string(facets));
+    }
+    if let Some(suggestion) = suggestion {
+        output.push_str(&format!(
+            ",\"suggestion\":{{\"query\":{},\"applied\":{}}}",
+            json_escape(&suggestion.query),
+            suggestion.applied
+        ));
+    }
+    output.push('}');
     output
 }
 
 /// Print results as JSON.
 ///
 /// The output object contains `query`, `result_count`, and a `results` array
-/// with `rank`, `score`, `doc_id`, `collection`, `path`, and `title`.
+/// with `rank`, `score`, `doc_id`, `collection`, `path`, and `title`, plus
+/// `rerank_score` for results the cross-encoder stage rescored and
+/// `passages` (`score`, inclusive `start_byte`/`end_byte`) in passage mode
+/// and `token_matches` (`query_token`, `document_token`, `score`,
+/// inclusive `start_byte`/`end_byte`) when explanations were attached.
 pub fn format_json(results: &[FinalResult], query: &str) {
-    println!("{}", format_json_string(results, query));
+    println!("{}", format_json_string(results, query, None, None, None));
+}
+
+/// Print results as JSON together with the query-level breakdown of an
+/// explained search.
+///
+/// Same shape as [`format_json`], plus a top-level `explain` object with
+/// `timings_ms`, candidate counts, `min_score_ignored`, `dropped_count`
+/// and the `dropped` candidates (`collection`, `path`, `score`,
+/// `reason`, `explain`). Each result's own `explain` object holds its
+/// `bm25` and `semantic` leg (`rank`, `score`, `rrf`, or `null`) and
+/// its `fused_rank`.
+pub fn format_json_explained(
+    results: &[FinalResult],
+    query: &str,
+    explain: &SearchExplain,
+) {
+    println!(
+        "{}",
+        format_json_string(results, query, Some(explain), None, None)
+    );
+}
+
+/// Print results as JSON with whichever of the query-level breakdown,
+/// the facets and the spelling suggestion the search produced.
+///
+/// Same shape as [`format_json_explained`] when `explain` is set, plus
+/// a top-level `facets` object when `facets` is: `total` and the
+/// `collections`, `extensions`, `years` and `tags` lists of
+/// `{"value", "count"}` objects. A `suggestion` adds a top-level
+/// `suggestion` object with the corrected `query` and whether the
+/// results are for it (`applied`).
+pub fn format_json_outcome(
+    results: &[FinalResult],
+    query: &str,
+    explain: Option<&SearchExplain>,
+    facets: Option<&Facets>,
+    suggestion: Option<&QuerySuggestion>,
+) {
+    println!(
+        "{}",
+        format_json_string(results, query, explain, facets, suggestion)
+    );
 }
 
 /// Print matching files as absolute paths, one per line.
@@ -1012,6 +4127,12 @@ mod tests {
             count: 10,
             all: false,
             min_score: 0.0,
+            passages: 0,
+            explain: false,
+            facets: false,
+            offset: 0,
+            cursor: None,
+            plaid: Default::default(),
         }
     }
 
@@ -1024,6 +4145,14 @@ mod tests {
             min_score: 0.0,
             bm25_only: true,
             no_fuzzy: false,
+            passages: 0,
+            explain: false,
+            prf: None,
+            facets: false,
+            offset: 0,
+            cursor: None,
+            autocorrect: false,
+            plaid: Default::default(),
         }
     }
 
@@ -1241,25 +4370,208 @@ mod tests {
             .unwrap();
         }
 
-        writer.commit().unwrap();
-        (idx, data_dir, config_db, tmp)
+        writer.commit().unwrap();
+        (idx, data_dir, config_db, tmp)
+    }
+
+    #[test]
+    fn bm25_only_returns_relevant_results() {
+        let (idx, data_dir, config_db, _tmp) = setup_index_with_docs();
+        let mut model = ModelManager::new();
+        let args = make_search_args("rust programming");
+
+        let results =
+            run(&args, &idx, &config_db, &data_dir, &mut model).unwrap();
+
+        assert!(!results.is_empty(), "search should return results");
+        // The Rust guide should be the top result
+        assert_eq!(
+            results[0].path, "rust-guide.md",
+            "Rust guide should rank first for 'rust programming'"
+        );
+    }
+
+    #[test]
+    fn bm25_only_explain_reports_leg_ranks_and_dropped_candidates() {
+        let (idx, data_dir, config_db, _tmp) = setup_index_with_docs();
+        let mut model = ModelManager::new();
+        let mut args = make_search_args("programming");
+        args.count = 1;
+        args.explain = true;
+
+        let outcome =
+            run_explained(&args, &idx, &config_db, &data_dir, &mut model)
+                .unwrap();
+
+        assert_eq!(outcome.results.len(), 1);
+        let top = outcome.results[0].explain.expect("per-result explain");
+        let bm25 = top.bm25.expect("bm25 leg hit");
+        assert_eq!(bm25.rank, 1);
+        assert_eq!(bm25.score, outcome.results[0].score);
+        assert!(top.semantic.is_none());
+
+        let explain = outcome.explain.expect("query-level explain");
+        assert!(explain.bm25_candidates >= 2);
+        assert_eq!(explain.dropped_count, explain.bm25_candidates - 1);
+        assert!(
+            explain
+                .dropped
+                .iter()
+                .all(|d| d.reason == DropReason::Limit)
+        );
+        assert_eq!(explain.dropped[0].explain.fused_rank, 2);
+    }
+
+    #[test]
+    fn bm25_only_without_explain_leaves_results_bare() {
+        let (idx, data_dir, config_db, _tmp) = setup_index_with_docs();
+        let mut model = ModelManager::new();
+        let args = make_search_args("programming");
+
+        let outcome =
+            run_explained(&args, &idx, &config_db, &data_dir, &mut model)
+                .unwrap();
+
+        assert!(outcome.explain.is_none());
+        assert!(outcome.results.iter().all(|r| r.explain.is_none()));
     }
 
     #[test]
-    fn bm25_only_returns_relevant_results() {
+    fn bm25_only_explain_attributes_min_score_drops() {
         let (idx, data_dir, config_db, _tmp) = setup_index_with_docs();
         let mut model = ModelManager::new();
-        let args = make_search_args("rust programming");
+        let mut args = make_search_args("programming");
+        args.min_score = f32::MAX;
+        args.explain = true;
 
-        let results =
-            run(&args, &idx, &config_db, &data_dir, &mut model).unwrap();
+        let outcome =
+            run_explained(&args, &idx, &config_db, &data_dir, &mut model)
+                .unwrap();
 
-        assert!(!results.is_empty(), "search should return results");
-        // The Rust guide should be the top result
+        assert!(outcome.results.is_empty());
+        let explain = outcome.explain.unwrap();
+        assert!(explain.dropped_count > 0);
+        assert!(
+            explain
+                .dropped
+                .iter()
+                .all(|d| d.reason == DropReason::MinScore)
+        );
+    }
+
+    #[test]
+    fn fused_candidates_record_each_legs_rrf_contribution() {
+        let bm25 = vec![
+            SearchResult {
+                score: 4.0,
+                doc_id: "a".to_string(),
+                doc_num_id: 1,
+                collection: "c".to_string(),
+                path: "a.md".to_string(),
+                title: "A".to_string(),
+                mtime: 1,
+            },
+            SearchResult {
+                score: 2.0,
+                doc_id: "b".to_string(),
+                doc_num_id: 2,
+                collection: "c".to_string(),
+                path: "b.md".to_string(),
+                title: "B".to_string(),
+                mtime: 1,
+            },
+        ];
+        let semantic = vec![RankedDocument {
+            doc_num_id: 2,
+            score: 12.5,
+            best_chunk_doc_id: None,
+        }];
+        let fused =
+            vec![passage_result(2, Vec::new()), passage_result(1, Vec::new())];
+
+        let candidates = fused_candidates(&fused, &bm25, &semantic);
+
+        let first = candidates[0].explain;
+        assert_eq!(first.fused_rank, 1);
         assert_eq!(
-            results[0].path, "rust-guide.md",
-            "Rust guide should rank first for 'rust programming'"
+            first.bm25,
+            Some(LegHit {
+                rank: 2,
+                score: 2.0,
+                rrf: 1.0 / (RRF_K + 2) as f32,
+            })
+        );
+        assert_eq!(
+            first.semantic,
+            Some(LegHit {
+                rank: 1,
+                score: 12.5,
+                rrf: 1.0 / (RRF_K + 1) as f32,
+            })
         );
+        let second = candidates[1].explain;
+        assert_eq!(second.bm25.map(|hit| hit.rank), Some(1));
+        assert!(second.semantic.is_none());
+    }
+
+    #[test]
+    fn attach_explanations_caps_the_dropped_list() {
+        let candidates: Vec<ExplainCandidate> =
+            (0..EXPLAIN_DROPPED_LIMIT as u64 + 10)
+                .map(|doc_num_id| ExplainCandidate {
+                    doc_num_id,
+                    collection: "c".to_string(),
+                    path: format!("{doc_num_id}.md"),
+                    score: 1.0,
+                    explain: ResultExplain {
+                        bm25: None,
+                        semantic: None,
+                        fused_rank: doc_num_id as usize + 1,
+                    },
+                })
+                .collect();
+        let mut results = vec![passage_result(0, Vec::new())];
+        let mut explain = SearchExplain::default();
+
+        attach_explanations(&mut results, candidates, None, &mut explain);
+
+        assert_eq!(results[0].explain.map(|e| e.fused_rank), Some(1));
+        assert_eq!(explain.dropped_count, EXPLAIN_DROPPED_LIMIT + 9);
+        assert_eq!(explain.dropped.len(), EXPLAIN_DROPPED_LIMIT);
+        assert_eq!(explain.dropped[0].doc_num_id, 1);
+    }
+
+    #[test]
+    fn search_json_includes_explain_objects() {
+        let mut result = passage_result(1, Vec::new());
+        result.explain = Some(ResultExplain {
+            bm25: Some(LegHit {
+                rank: 3,
+                score: 7.5,
+                rrf: 0.25,
+            }),
+            semantic: None,
+            fused_rank: 2,
+        });
+        let explain = SearchExplain {
+            timings: StageTimings {
+                tantivy: Duration::from_millis(2),
+                ..StageTimings::default()
+            },
+            bm25_candidates: 4,
+            dropped_count: 1,
+            ..SearchExplain::default()
+        };
+
+        let json =
+            format_json_string(&[result], "rust", Some(&explain), None, None);
+        assert!(json.contains(
+            "\"explain\":{\"bm25\":{\"rank\":3,\"score\":7.500000,\"rrf\":0.250000},\"semantic\":null,\"fused_rank\":2}"
+        ));
+        assert!(json.contains("\"tantivy\":2.000"));
+        assert!(json.contains("\"bm25_candidates\":4"));
+        assert!(json.contains("\"dropped_count\":1,\"dropped\":[]"));
+        assert!(json.ends_with("]}}"));
     }
 
     #[test]
@@ -1368,62 +4680,552 @@ mod tests {
         let results =
             run(&args, &idx, &config_db, &data_dir, &mut model).unwrap();
 
-        for window in results.windows(2) {
-            assert!(
-                window[0].score >= window[1].score,
-                "scores should be in descending order"
-            );
-        }
+        for window in results.windows(2) {
+            assert!(
+                window[0].score >= window[1].score,
+                "scores should be in descending order"
+            );
+        }
+    }
+
+    #[test]
+    fn bm25_only_fuzzy_matching_finds_typos() {
+        let (idx, data_dir, config_db, _tmp) = setup_index_with_docs();
+        let mut model = ModelManager::new();
+        // "programing" (one 'm') should fuzzy-match "programming"
+        let args = make_search_args("programing");
+
+        let results =
+            run(&args, &idx, &config_db, &data_dir, &mut model).unwrap();
+
+        assert!(
+            !results.is_empty(),
+            "fuzzy search should find results despite typo"
+        );
+    }
+
+    #[test]
+    fn misspelled_query_offers_a_suggestion_and_autocorrect_applies_it() {
+        let (idx, data_dir, config_db, _tmp) = setup_index_with_docs();
+        let mut model = ModelManager::new();
+        let mut args = make_search_args("gardning tips");
+        args.no_fuzzy = true;
+
+        let outcome =
+            run_explained(&args, &idx, &config_db, &data_dir, &mut model)
+                .unwrap();
+        // "tips" still matches, just not strongly enough.
+        assert_eq!(outcome.results[0].path, "gardening.md");
+        assert!(outcome.results[0].score < SUGGESTION_BM25_SCORE);
+        assert_eq!(
+            outcome.suggestion,
+            Some(QuerySuggestion {
+                query: "gardening tips".to_string(),
+                applied: false,
+            })
+        );
+
+        args.autocorrect = true;
+        let corrected =
+            run_explained(&args, &idx, &config_db, &data_dir, &mut model)
+                .unwrap();
+        assert!(corrected.suggestion.unwrap().applied);
+        assert_eq!(corrected.results[0].path, "gardening.md");
+
+        let known = make_search_args("gardening tips");
+        let outcome =
+            run_explained(&known, &idx, &config_db, &data_dir, &mut model)
+                .unwrap();
+        assert!(outcome.suggestion.is_none());
+    }
+
+    #[test]
+    fn strong_match_offers_no_suggestion_for_a_misspelled_term() {
+        let (idx, data_dir, config_db, _tmp) = setup_index_with_docs();
+        let mut model = ModelManager::new();
+        let mut args = make_search_args("rust programming langauge");
+        args.no_fuzzy = true;
+        args.autocorrect = true;
+
+        let outcome =
+            run_explained(&args, &idx, &config_db, &data_dir, &mut model)
+                .unwrap();
+        assert!(outcome.results[0].score >= SUGGESTION_BM25_SCORE);
+        assert_eq!(outcome.results[0].path, "rust-guide.md");
+        assert!(outcome.suggestion.is_none());
+    }
+
+    #[test]
+    fn bm25_only_no_fuzzy_flag() {
+        let (idx, data_dir, config_db, _tmp) = setup_index_with_docs();
+        let mut model = ModelManager::new();
+        let mut args = make_search_args("rust");
+        args.no_fuzzy = true;
+
+        let results =
+            run(&args, &idx, &config_db, &data_dir, &mut model).unwrap();
+
+        assert!(!results.is_empty(), "exact search should find 'rust'");
+        assert_eq!(results[0].path, "rust-guide.md");
+    }
+
+    #[test]
+    fn bm25_only_result_fields_populated() {
+        let (idx, data_dir, config_db, _tmp) = setup_index_with_docs();
+        let mut model = ModelManager::new();
+        let mut args = make_search_args("pasta");
+        args.no_fuzzy = true;
+
+        let results =
+            run(&args, &idx, &config_db, &data_dir, &mut model).unwrap();
+
+        assert_eq!(results.len(), 1);
+        let r = &results[0];
+        assert_eq!(r.collection, "docs");
+        assert_eq!(r.path, "cooking-pasta.md");
+        assert_eq!(r.title, "How to Cook Pasta");
+        assert!(r.score > 0.0);
+        assert!(!r.doc_id.is_empty());
+        assert!(r.doc_num_id > 0);
+    }
+
+    /// Five notes on disk and in the index: three about the borrow
+    /// checker (only two of which say "ownership") and two about pasta.
+    /// A batch over the [`setup_similar`] index whose queries are
+    /// already encoded as the given two-dimensional tokens.
+    fn prepared_batch(
+        config_db: &ConfigDb,
+        data_dir: &DataDir,
+        queries: &[(&str, [f32; 2])],
+    ) -> QueryBatch {
+        let (plaid_index, metadata) =
+            semantic_corpus(None, config_db, data_dir).unwrap();
+        QueryBatch {
+            plaid_index,
+            metadata,
+            tokens: queries
+                .iter()
+                .map(|(query, tokens)| (query.to_string(), tokens.to_vec()))
+                .collect(),
+            encoding_share: Duration::ZERO,
+            filters: plaid::PlaidFilterCache::new(),
+        }
+    }
+
+    #[test]
+    fn batched_searches_use_the_prepared_query_encodings() {
+        let (idx, data_dir, config_db, _tmp) = setup_similar();
+        let mut model = ModelManager::new();
+        let batch = prepared_batch(
+            &config_db,
+            &data_dir,
+            &[("ownership", [1.0, 0.0]), ("pasta", [-1.0, 0.0])],
+        );
+
+        let ownership = semantic_paged(
+            &make_semantic_args("ownership"),
+            None,
+            Some(&batch),
+            &config_db,
+            &data_dir,
+            &mut model,
+        )
+        .unwrap();
+        assert_eq!(ownership.total, 2);
+        assert!(ownership.results.iter().all(|r| r.path != "c.md"));
+
+        let mut pasta = make_search_args("pasta");
+        pasta.bm25_only = false;
+        pasta.no_fuzzy = true;
+        let fused = run_paged(
+            &pasta,
+            None,
+            Some(&batch),
+            &idx,
+            &config_db,
+            &data_dir,
+            &mut model,
+        )
+        .unwrap();
+        assert_eq!(fused.results[0].path, "c.md");
+
+        // Both searches ran on the batch's encodings alone.
+        assert!(!model.is_loaded());
+    }
+
+    #[test]
+    fn small_indexes_are_searched_exactly_unless_pinned_to_plaid() {
+        let (_idx, data_dir, config_db, _tmp) = setup_similar();
+        let mut model = ModelManager::new();
+        let batch =
+            prepared_batch(&config_db, &data_dir, &[("east", [1.0, 0.0])]);
+        let mut args = make_semantic_args("east");
+        args.explain = true;
+
+        let exact = semantic_paged(
+            &args,
+            None,
+            Some(&batch),
+            &config_db,
+            &data_dir,
+            &mut model,
+        )
+        .unwrap();
+        let explain = exact.explain.unwrap();
+        assert_eq!(explain.exact_scored_docs, 3);
+        assert_eq!(explain.plaid_probed_docs, 0);
+
+        args.plaid.exact = Some(false);
+        let pruned = semantic_paged(
+            &args,
+            None,
+            Some(&batch),
+            &config_db,
+            &data_dir,
+            &mut model,
+        )
+        .unwrap();
+        let explain = pruned.explain.unwrap();
+        assert_eq!(explain.exact_scored_docs, 0);
+        assert!(explain.plaid_probed_docs > 0);
+        assert!(result_paths(&exact.results).contains(&pruned.results[0].path));
+
+        // A collection scope scans only the collection's chunks.
+        args.plaid.exact = None;
+        args.collection = Some("notes".to_string());
+        let scoped = semantic_paged(
+            &args,
+            None,
+            Some(&batch),
+            &config_db,
+            &data_dir,
+            &mut model,
+        )
+        .unwrap();
+        assert_eq!(scoped.explain.unwrap().exact_scored_docs, 3);
+    }
+
+    #[test]
+    fn bm25_batch_matches_single_searches_without_a_plaid_index() {
+        let (idx, data_dir, config_db, _tmp) = setup_prf();
+        let mut model = ModelManager::new();
+        let args: Vec<SearchParams> =
+            ["borrow checker", "lifetimes", "nothing"]
+                .into_iter()
+                .map(make_search_args)
+                .collect();
+
+        let outcomes =
+            run_batch(&args, &idx, &config_db, &data_dir, &mut model).unwrap();
+
+        assert_eq!(outcomes.len(), args.len());
+        for (args, outcome) in args.iter().zip(&outcomes) {
+            let single =
+                run(args, &idx, &config_db, &data_dir, &mut model).unwrap();
+            assert_eq!(
+                result_paths(&outcome.results),
+                result_paths(&single),
+                "{}",
+                args.query
+            );
+        }
+        assert!(!outcomes[0].results.is_empty());
+        assert!(outcomes[2].results.is_empty());
+    }
+
+    fn setup_prf() -> (SearchIndex, DataDir, ConfigDb, tempfile::TempDir) {
+        let tmp = tempfile::tempdir().unwrap();
+        let data_dir = DataDir::new(tmp.path());
+        let config_db = ConfigDb::open(&data_dir.config_db()).unwrap();
+        let idx = SearchIndex::open_in_ram().unwrap();
+        let mut writer = idx.writer(15_000_000).unwrap();
+
+        let root = tmp.path().join("notes");
+        std::fs::create_dir_all(&root).unwrap();
+        config_db
+            .set_collection("notes", root.to_str().unwrap())
+            .unwrap();
+
+        let docs = [
+            (
+                "ownership.md",
+                "Ownership rules: the borrow checker tracks every owner.",
+            ),
+            (
+                "moves.md",
+                "Moves transfer ownership; the borrow checker rejects reuse.",
+            ),
+            (
+                "lifetimes.md",
+                "Lifetimes let the borrow checker reject dangling references.",
+            ),
+            ("sauce.md", "Simmer the pasta sauce slowly."),
+            ("boil.md", "Boil the pasta in salted water."),
+        ];
+        for (path, body) in docs {
+            std::fs::write(root.join(path), body).unwrap();
+            let did = DocumentId::new("notes", path);
+            idx.add_document(
+                &writer,
+                &did.full_hex(),
+                did.numeric,
+                "notes",
+                path,
+                path,
+                body,
+                1,
+            )
+            .unwrap();
+        }
+        writer.commit().unwrap();
+
+        (idx, data_dir, config_db, tmp)
+    }
+
+    #[test]
+    fn bm25_only_prf_surfaces_documents_sharing_feedback_terms() {
+        let (idx, data_dir, config_db, _tmp) = setup_prf();
+        let mut model = ModelManager::new();
+        let mut args = make_search_args("ownership");
+        args.no_fuzzy = true;
+
+        let results =
+            run(&args, &idx, &config_db, &data_dir, &mut model).unwrap();
+        let paths: Vec<&str> =
+            results.iter().map(|r| r.path.as_str()).collect();
+        assert!(!paths.contains(&"lifetimes.md"), "{paths:?}");
+
+        args.prf = Some(PrfParams {
+            feedback_docs: 2,
+            ..PrfParams::default()
+        });
+        let results =
+            run(&args, &idx, &config_db, &data_dir, &mut model).unwrap();
+        let paths: Vec<&str> =
+            results.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(paths.len(), 3, "{paths:?}");
+        assert_eq!(paths[2], "lifetimes.md");
+    }
+
+    #[test]
+    fn bm25_only_prf_without_feedback_keeps_first_pass_results() {
+        let (idx, data_dir, config_db, _tmp) = setup_prf();
+        let mut model = ModelManager::new();
+        let mut args = make_search_args("ownership");
+        args.no_fuzzy = true;
+        let baseline =
+            run(&args, &idx, &config_db, &data_dir, &mut model).unwrap();
+
+        args.prf = Some(PrfParams {
+            feedback_docs: 0,
+            ..PrfParams::default()
+        });
+        let results =
+            run(&args, &idx, &config_db, &data_dir, &mut model).unwrap();
+        let paths = |r: &[FinalResult]| {
+            r.iter().map(|r| r.path.clone()).collect::<Vec<_>>()
+        };
+        assert_eq!(paths(&results), paths(&baseline));
+    }
+
+    #[test]
+    fn bm25_only_facets_count_the_candidate_set() {
+        let tmp = tempfile::tempdir().unwrap();
+        let data_dir = DataDir::new(tmp.path());
+        let config_db = ConfigDb::open(&data_dir.config_db()).unwrap();
+        let idx = SearchIndex::open_in_ram().unwrap();
+        let mut writer = idx.writer(15_000_000).unwrap();
+
+        let docs = [
+            ("notes", "a.md", 951_782_400, vec!["rust", "memory"]),
+            ("notes", "b.MD", 1_704_067_200, vec!["rust"]),
+            ("docs", "c.txt", 1_704_067_199, vec![]),
+            ("docs", "d.md", 1_704_067_200, vec!["cooking"]),
+        ];
+        for (collection, path, mtime, tags) in docs {
+            let did = DocumentId::new(collection, path);
+            let tags: Vec<String> =
+                tags.into_iter().map(str::to_string).collect();
+            idx.add_tagged_document(
+                &writer,
+                &did.full_hex(),
+                did.numeric,
+                collection,
+                path,
+                path,
+                if path == "d.md" {
+                    "pasta sauce"
+                } else {
+                    "rust ownership"
+                },
+                mtime,
+                &tags,
+            )
+            .unwrap();
+        }
+        writer.commit().unwrap();
+
+        let mut model = ModelManager::new();
+        let mut args = make_search_args("rust");
+        args.no_fuzzy = true;
+        args.count = 1;
+        args.facets = true;
+        let outcome =
+            run_explained(&args, &idx, &config_db, &data_dir, &mut model)
+                .unwrap();
+        assert_eq!(outcome.results.len(), 1);
+
+        let count = |value: &str, n: usize| FacetCount {
+            value: value.to_string(),
+            count: n,
+        };
+        assert_eq!(
+            outcome.facets,
+            Some(Facets {
+                total: 3,
+                collections: vec![count("notes", 2), count("docs", 1)],
+                extensions: vec![count("md", 2), count("txt", 1)],
+                years: vec![
+                    count("2000", 1),
+                    count("2023", 1),
+                    count("2024", 1)
+                ],
+                tags: vec![count("rust", 2), count("memory", 1)],
+            })
+        );
+
+        args.facets = false;
+        let outcome =
+            run_explained(&args, &idx, &config_db, &data_dir, &mut model)
+                .unwrap();
+        assert!(outcome.facets.is_none());
+    }
+
+    fn result_paths(results: &[FinalResult]) -> Vec<String> {
+        results.iter().map(|r| r.path.clone()).collect()
     }
 
     #[test]
-    fn bm25_only_fuzzy_matching_finds_typos() {
-        let (idx, data_dir, config_db, _tmp) = setup_index_with_docs();
+    fn bm25_only_cursor_pages_through_one_ranking() {
+        let (idx, data_dir, config_db, _tmp) = setup_prf();
         let mut model = ModelManager::new();
-        // "programing" (one 'm') should fuzzy-match "programming"
-        let args = make_search_args("programing");
-
-        let results =
+        let cache = SearchCache::default();
+        let mut args = make_search_args("borrow checker");
+        args.no_fuzzy = true;
+        let everything =
             run(&args, &idx, &config_db, &data_dir, &mut model).unwrap();
+        assert_eq!(everything.len(), 3);
 
-        assert!(
-            !results.is_empty(),
-            "fuzzy search should find results despite typo"
+        args.count = 2;
+        let first =
+            run_cached(&args, &cache, &idx, &config_db, &data_dir, &mut model)
+                .unwrap();
+        assert_eq!(first.total, 3);
+        assert_eq!(
+            result_paths(&first.results),
+            result_paths(&everything[..2])
+        );
+        let cursor = first.next_cursor.expect("more results");
+        assert_eq!(cursor.offset(), 2);
+
+        args.cursor =
+            Some(SearchCursor::decode(&cursor.encode()).expect("round trip"));
+        let second =
+            run_cached(&args, &cache, &idx, &config_db, &data_dir, &mut model)
+                .unwrap();
+        assert_eq!(
+            result_paths(&second.results),
+            result_paths(&everything[2..])
+        );
+        assert_eq!(second.results[0].rank, 3);
+        assert!(second.next_cursor.is_none());
+
+        args.cursor = None;
+        args.offset = 2;
+        let by_offset =
+            run_cached(&args, &cache, &idx, &config_db, &data_dir, &mut model)
+                .unwrap();
+        assert_eq!(
+            result_paths(&by_offset.results),
+            result_paths(&second.results)
         );
     }
 
     #[test]
-    fn bm25_only_no_fuzzy_flag() {
-        let (idx, data_dir, config_db, _tmp) = setup_index_with_docs();
+    fn cursor_pages_come_from_the_cached_ranking() {
+        let (idx, data_dir, config_db, _tmp) = setup_prf();
         let mut model = ModelManager::new();
-        let mut args = make_search_args("rust");
+        let cache = SearchCache::default();
+        let mut args = make_search_args("borrow checker");
         args.no_fuzzy = true;
+        args.count = 1;
+        let first =
+            run_cached(&args, &cache, &idx, &config_db, &data_dir, &mut model)
+                .unwrap();
+        args.cursor = first.next_cursor;
+
+        // A new matching document changes the ranking the index would
+        // produce now, but not the one the cursor pages through.
+        let writer = idx.writer(15_000_000).unwrap();
+        let did = DocumentId::new("notes", "borrow.md");
+        idx.add_document(
+            &writer,
+            &did.full_hex(),
+            did.numeric,
+            "notes",
+            "borrow.md",
+            "borrow.md",
+            "borrow checker borrow checker borrow checker",
+            1,
+        )
+        .unwrap();
+        let mut writer = writer;
+        writer.commit().unwrap();
 
-        let results =
-            run(&args, &idx, &config_db, &data_dir, &mut model).unwrap();
+        let second =
+            run_cached(&args, &cache, &idx, &config_db, &data_dir, &mut model)
+                .unwrap();
+        assert_eq!(second.total, 3);
+        assert!(second.results.iter().all(|r| r.path != "borrow.md"));
 
-        assert!(!results.is_empty(), "exact search should find 'rust'");
-        assert_eq!(results[0].path, "rust-guide.md");
+        // Without the cached ranking the cursor no longer matches.
+        let err = run_explained(&args, &idx, &config_db, &data_dir, &mut model)
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidCursor(_)), "{err}");
+
+        args.query = "ownership".to_string();
+        let err =
+            run_cached(&args, &cache, &idx, &config_db, &data_dir, &mut model)
+                .unwrap_err();
+        assert!(matches!(err, Error::InvalidCursor(_)), "{err}");
     }
 
     #[test]
-    fn bm25_only_result_fields_populated() {
-        let (idx, data_dir, config_db, _tmp) = setup_index_with_docs();
-        let mut model = ModelManager::new();
-        let mut args = make_search_args("pasta");
+    fn cached_rankings_are_kept_apart_per_reranker_model() {
+        let (idx, data_dir, config_db, tmp) = setup_prf();
+        let cache = SearchCache::default();
+        let mut args = make_search_args("borrow checker");
         args.no_fuzzy = true;
+        let reranker = |name: &str| {
+            ModelManager::new().with_reranker_model(Some(
+                tmp.path().join(name).to_string_lossy().into_owned(),
+            ))
+        };
 
-        let results =
-            run(&args, &idx, &config_db, &data_dir, &mut model).unwrap();
+        for name in ["first", "first", "second"] {
+            let mut model = reranker(name);
+            run_cached(&args, &cache, &idx, &config_db, &data_dir, &mut model)
+                .unwrap();
+        }
+        assert_eq!(cache.len(), 2);
+    }
 
-        assert_eq!(results.len(), 1);
-        let r = &results[0];
-        assert_eq!(r.collection, "docs");
-        assert_eq!(r.path, "cooking-pasta.md");
-        assert_eq!(r.title, "How to Cook Pasta");
-        assert!(r.score > 0.0);
-        assert!(!r.doc_id.is_empty());
-        assert!(r.doc_num_id > 0);
+    #[test]
+    fn utc_year_handles_leap_days_and_year_boundaries() {
+        assert_eq!(utc_year(0), 1970);
+        assert_eq!(utc_year(951_782_400), 2000);
+        assert_eq!(utc_year(1_704_067_199), 2023);
+        assert_eq!(utc_year(1_704_067_200), 2024);
     }
 
     /// Helper to set up index + embeddings for end-to-end tests.
@@ -1550,93 +5352,469 @@ mod tests {
             embed_and_store(&mut model, &embedding_db, embed_docs).unwrap();
         assert_eq!(count, docs.len(), "all docs should be embedded");
 
-        // Build and persist a PLAID index so the search path can find it.
-        let plaid_index = crate::plaid::build_index_from_embedding_db(
-            &embedding_db,
-            crate::plaid::PlaidBuildParams {
-                k_centroids: 4,
-                nbits: 2,
-                max_kmeans_iters: 20,
-            },
+        // Build and persist a PLAID index so the search path can find it.
+        let plaid_index = crate::plaid::build_index_from_embedding_db(
+            &embedding_db,
+            crate::plaid::PlaidBuildParams {
+                k_centroids: 4,
+                nbits: 2,
+                max_kmeans_iters: 20,
+                ..crate::plaid::PlaidBuildParams::default()
+            },
+        )
+        .expect("failed to build PLAID index for e2e fixture");
+        crate::plaid::save_index(&plaid_index, &data_dir).unwrap();
+
+        (idx, data_dir, config_db, model, tmp)
+    }
+
+    fn setup_semantic_e2e()
+    -> (ConfigDb, DataDir, ModelManager, tempfile::TempDir) {
+        use crate::embedding::embed_and_store;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let data_dir = DataDir::new(tmp.path());
+        let config_db = ConfigDb::open(&data_dir.config_db()).unwrap();
+        let embedding_db =
+            EmbeddingDb::open(&data_dir.embeddings_db()).unwrap();
+        let mut model = ModelManager::new();
+
+        let docs: Vec<(&str, &str, &str, &str)> = vec![
+            (
+                "notes",
+                "rust-guide.md",
+                "The Rust Programming Language",
+                "Rust is a systems programming language focused on safety, \
+                 concurrency, and performance. It achieves memory safety \
+                 without garbage collection through its ownership system \
+                 and borrow checker.",
+            ),
+            (
+                "notes",
+                "python-intro.md",
+                "Introduction to Python",
+                "Python is a high-level interpreted programming language \
+                 known for its readability and simplicity. It supports \
+                 multiple programming paradigms including object-oriented \
+                 and functional programming.",
+            ),
+            (
+                "docs",
+                "cooking-pasta.md",
+                "How to Cook Pasta",
+                "Boil water in a large pot. Add salt generously. Cook the \
+                 pasta according to package directions until al dente. \
+                 Drain and serve with your favorite sauce.",
+            ),
+        ];
+
+        let mut embed_docs: Vec<(u64, String)> = Vec::new();
+
+        for (collection, path, title, body) in &docs {
+            let doc_id = DocumentId::new(collection, path);
+            let meta = DocumentMetadata {
+                collection: (*collection).to_string(),
+                relative_path: (*path).to_string(),
+                mtime: 1,
+            };
+            config_db
+                .set_document_metadata_typed(doc_id.numeric, &meta)
+                .unwrap();
+            embed_docs.push((doc_id.numeric, format!("{title}\n{body}")));
+        }
+
+        let count =
+            embed_and_store(&mut model, &embedding_db, embed_docs).unwrap();
+        assert_eq!(count, docs.len(), "all docs should be embedded");
+
+        // Build and persist the PLAID index so the semantic leg can find it.
+        let plaid_index = crate::plaid::build_index_from_embedding_db(
+            &embedding_db,
+            crate::plaid::PlaidBuildParams {
+                k_centroids: 2,
+                nbits: 2,
+                max_kmeans_iters: 20,
+                ..crate::plaid::PlaidBuildParams::default()
+            },
+        )
+        .expect("failed to build PLAID index for semantic e2e fixture");
+        crate::plaid::save_index(&plaid_index, &data_dir).unwrap();
+
+        (config_db, data_dir, model, tmp)
+    }
+
+    /// Three documents with hand-made two-token embeddings: `a.md` and
+    /// `b.md` point the same way and share vocabulary, `c.md` doesn't.
+    fn setup_similar() -> (SearchIndex, DataDir, ConfigDb, tempfile::TempDir) {
+        use crate::config_db::DocChunkEntry;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let data_dir = DataDir::new(tmp.path());
+        let config_db = ConfigDb::open(&data_dir.config_db()).unwrap();
+        let embedding_db =
+            EmbeddingDb::open(&data_dir.embeddings_db()).unwrap();
+        let idx = SearchIndex::open_in_ram().unwrap();
+        let mut writer = idx.writer(15_000_000).unwrap();
+
+        let root = tmp.path().join("notes");
+        std::fs::create_dir_all(&root).unwrap();
+        config_db
+            .set_collection("notes", root.to_str().unwrap())
+            .unwrap();
+
+        let docs: [(&str, &str, u64, [f32; 4]); 3] = [
+            (
+                "a.md",
+                "Rust borrowing and ownership rules",
+                101,
+                [1.0, 0.0, 0.9, 0.1],
+            ),
+            (
+                "b.md",
+                "Ownership and borrowing in Rust",
+                102,
+                [0.9, 0.1, 1.0, 0.0],
+            ),
+            (
+                "c.md",
+                "Simmer the pasta sauce slowly",
+                103,
+                [-1.0, 0.0, -0.9, 0.1],
+            ),
+        ];
+        for (path, body, chunk_id, tokens) in docs {
+            std::fs::write(root.join(path), body).unwrap();
+            let did = DocumentId::new("notes", path);
+            idx.add_document(
+                &writer,
+                &did.full_hex(),
+                did.numeric,
+                "notes",
+                path,
+                path,
+                body,
+                1,
+            )
+            .unwrap();
+            config_db
+                .set_document_metadata_typed(
+                    did.numeric,
+                    &DocumentMetadata {
+                        collection: "notes".to_string(),
+                        relative_path: path.to_string(),
+                        mtime: 1,
+                    },
+                )
+                .unwrap();
+            config_db
+                .set_doc_chunks(
+                    did.numeric,
+                    &[DocChunkEntry {
+                        chunk_doc_id: chunk_id,
+                        start_byte: 0,
+                        byte_len: body.len() as u64,
+                    }],
+                )
+                .unwrap();
+            embedding_db.store(chunk_id, 2, 2, &tokens).unwrap();
+        }
+        writer.commit().unwrap();
+
+        let plaid_index = crate::plaid::build_index_from_embedding_db(
+            &embedding_db,
+            crate::plaid::PlaidBuildParams {
+                k_centroids: 2,
+                nbits: 2,
+                max_kmeans_iters: 20,
+                ..crate::plaid::PlaidBuildParams::default()
+            },
+        )
+        .unwrap();
+        crate::plaid::save_index(&plaid_index, &data_dir).unwrap();
+
+        (idx, data_dir, config_db, tmp)
+    }
+
+    #[test]
+    fn collection_search_finds_a_small_collection_inside_a_large_corpus() {
+        use crate::config_db::DocChunkEntry;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let data_dir = DataDir::new(tmp.path());
+        let config_db = ConfigDb::open(&data_dir.config_db()).unwrap();
+        let embedding_db =
+            EmbeddingDb::open(&data_dir.embeddings_db()).unwrap();
+        // A hundred "big" documents match an east query better than the
+        // one "small" document does, more than PLAID's oversampled top-k
+        // can hold.
+        let mut docs: Vec<(&str, String, [f32; 4])> = (0..100)
+            .map(|i| {
+                let y = i as f32 / 1000.0;
+                ("big", format!("{i}.md"), [1.0, y, 1.0, -y])
+            })
+            .collect();
+        docs.push(("small", "only.md".to_string(), [0.6, 0.8, 0.6, 0.8]));
+        for (chunk_id, (collection, path, tokens)) in (1000..).zip(&docs) {
+            let did = DocumentId::new(collection, path);
+            config_db
+                .set_document_metadata_typed(
+                    did.numeric,
+                    &DocumentMetadata {
+                        collection: collection.to_string(),
+                        relative_path: path.clone(),
+                        mtime: 1,
+                    },
+                )
+                .unwrap();
+            config_db
+                .set_doc_chunks(
+                    did.numeric,
+                    &[DocChunkEntry {
+                        chunk_doc_id: chunk_id,
+                        start_byte: 0,
+                        byte_len: 1,
+                    }],
+                )
+                .unwrap();
+            embedding_db.store(chunk_id, 2, 2, tokens).unwrap();
+        }
+        let plaid_index = crate::plaid::build_index_from_embedding_db(
+            &embedding_db,
+            crate::plaid::PlaidBuildParams {
+                k_centroids: 2,
+                nbits: 2,
+                max_kmeans_iters: 20,
+                ..crate::plaid::PlaidBuildParams::default()
+            },
+        )
+        .unwrap();
+        crate::plaid::save_index(&plaid_index, &data_dir).unwrap();
+
+        let mut model = ModelManager::new();
+        let batch =
+            prepared_batch(&config_db, &data_dir, &[("east", [1.0, 0.0])]);
+        let mut args = make_semantic_args("east");
+        args.collection = Some("small".to_string());
+        // Small enough to be scanned exactly; the point is PLAID's filter.
+        args.plaid.exact = Some(false);
+        let outcome = semantic_paged(
+            &args,
+            None,
+            Some(&batch),
+            &config_db,
+            &data_dir,
+            &mut model,
+        )
+        .unwrap();
+        assert_eq!(result_paths(&outcome.results), vec!["only.md"]);
+    }
+
+    fn similar_args(reference: &str) -> SimilarParams {
+        SimilarParams {
+            reference: reference.to_string(),
+            count: 10,
+            collection: None,
+            passages: 0,
+        }
+    }
+
+    #[test]
+    fn similar_ranks_the_closest_document_first_and_skips_the_source() {
+        let (idx, data_dir, config_db, _tmp) = setup_similar();
+
+        let results =
+            similar(&similar_args("notes:a.md"), &idx, &config_db, &data_dir)
+                .unwrap();
+
+        assert_eq!(results[0].path, "b.md");
+        assert_eq!(results[0].rank, 1);
+        assert!(results[0].best_chunk_doc_id.is_some());
+        assert!(results.iter().all(|r| r.path != "a.md"));
+    }
+
+    #[test]
+    fn similar_falls_back_to_plaid_tokens_without_stored_embeddings() {
+        let (idx, data_dir, config_db, _tmp) = setup_similar();
+        EmbeddingDb::open(&data_dir.embeddings_db())
+            .unwrap()
+            .remove(101)
+            .unwrap();
+
+        let results =
+            similar(&similar_args("notes:a.md"), &idx, &config_db, &data_dir)
+                .unwrap();
+
+        assert_eq!(results[0].path, "b.md");
+        assert!(results[0].best_chunk_doc_id.is_some());
+    }
+
+    #[test]
+    fn similar_rejects_unknown_references() {
+        let (idx, data_dir, config_db, _tmp) = setup_similar();
+
+        let err = similar(
+            &similar_args("notes:missing.md"),
+            &idx,
+            &config_db,
+            &data_dir,
         )
-        .expect("failed to build PLAID index for e2e fixture");
-        crate::plaid::save_index(&plaid_index, &data_dir).unwrap();
-
-        (idx, data_dir, config_db, model, tmp)
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::NotFound {
+                kind: "document",
+                ..
+            }
+        ));
     }
 
-    fn setup_semantic_e2e()
-    -> (ConfigDb, DataDir, ModelManager, tempfile::TempDir) {
-        use crate::embedding::embed_and_store;
+    /// `notes:long.md` split into three one-token chunks pointing in
+    /// different directions of a 2-dim space.
+    fn setup_within() -> (DataDir, ConfigDb, tempfile::TempDir) {
+        use crate::config_db::DocChunkEntry;
 
         let tmp = tempfile::tempdir().unwrap();
         let data_dir = DataDir::new(tmp.path());
         let config_db = ConfigDb::open(&data_dir.config_db()).unwrap();
         let embedding_db =
             EmbeddingDb::open(&data_dir.embeddings_db()).unwrap();
-        let mut model = ModelManager::new();
+        let root = tmp.path().join("notes");
+        std::fs::create_dir_all(&root).unwrap();
+        config_db
+            .set_collection("notes", root.to_str().unwrap())
+            .unwrap();
 
-        let docs: Vec<(&str, &str, &str, &str)> = vec![
-            (
-                "notes",
-                "rust-guide.md",
-                "The Rust Programming Language",
-                "Rust is a systems programming language focused on safety, \
-                 concurrency, and performance. It achieves memory safety \
-                 without garbage collection through its ownership system \
-                 and borrow checker.",
-            ),
-            (
-                "notes",
-                "python-intro.md",
-                "Introduction to Python",
-                "Python is a high-level interpreted programming language \
-                 known for its readability and simplicity. It supports \
-                 multiple programming paradigms including object-oriented \
-                 and functional programming.",
-            ),
-            (
-                "docs",
-                "cooking-pasta.md",
-                "How to Cook Pasta",
-                "Boil water in a large pot. Add salt generously. Cook the \
-                 pasta according to package directions until al dente. \
-                 Drain and serve with your favorite sauce.",
-            ),
+        let chunks: [(&str, u64, [f32; 2]); 3] = [
+            ("intro line\n", 201, [0.0, 1.0]),
+            ("rust ownership\nborrowing\n", 202, [1.0, 0.0]),
+            ("pasta sauce\n", 203, [-1.0, 0.0]),
         ];
+        let mut manifest = Vec::new();
+        let mut body = String::new();
+        for (text, chunk_id, tokens) in chunks {
+            manifest.push(DocChunkEntry {
+                chunk_doc_id: chunk_id,
+                start_byte: body.len() as u64,
+                byte_len: text.len() as u64,
+            });
+            body.push_str(text);
+            embedding_db.store(chunk_id, 1, 2, &tokens).unwrap();
+        }
+        std::fs::write(root.join("long.md"), &body).unwrap();
+        let did = DocumentId::new("notes", "long.md");
+        config_db
+            .set_document_metadata_typed(
+                did.numeric,
+                &DocumentMetadata {
+                    collection: "notes".to_string(),
+                    relative_path: "long.md".to_string(),
+                    mtime: 1,
+                },
+            )
+            .unwrap();
+        config_db.set_doc_chunks(did.numeric, &manifest).unwrap();
 
-        let mut embed_docs: Vec<(u64, String)> = Vec::new();
+        (data_dir, config_db, tmp)
+    }
 
-        for (collection, path, title, body) in &docs {
-            let doc_id = DocumentId::new(collection, path);
-            let meta = DocumentMetadata {
-                collection: (*collection).to_string(),
-                relative_path: (*path).to_string(),
-                mtime: 1,
-            };
-            config_db
-                .set_document_metadata_typed(doc_id.numeric, &meta)
-                .unwrap();
-            embed_docs.push((doc_id.numeric, format!("{title}\n{body}")));
+    fn within_args(reference: &str) -> WithinParams {
+        WithinParams {
+            reference: reference.to_string(),
+            query: "ownership".to_string(),
+            count: DEFAULT_WITHIN_COUNT,
+            min_score: -10.0,
         }
+    }
 
-        let count =
-            embed_and_store(&mut model, &embedding_db, embed_docs).unwrap();
-        assert_eq!(count, docs.len(), "all docs should be embedded");
+    fn encode_along_x(_query: &str) -> Result<(Vec<f32>, usize)> {
+        Ok((vec![1.0, 0.0], 2))
+    }
 
-        // Build and persist the PLAID index so the semantic leg can find it.
-        let plaid_index = crate::plaid::build_index_from_embedding_db(
-            &embedding_db,
-            crate::plaid::PlaidBuildParams {
-                k_centroids: 2,
-                nbits: 2,
-                max_kmeans_iters: 20,
-            },
+    #[test]
+    fn within_ranks_every_chunk_with_byte_and_line_ranges() {
+        let (data_dir, config_db, _tmp) = setup_within();
+
+        let outcome = within_scored(
+            &within_args("notes:long.md"),
+            &config_db,
+            &data_dir,
+            encode_along_x,
         )
-        .expect("failed to build PLAID index for semantic e2e fixture");
-        crate::plaid::save_index(&plaid_index, &data_dir).unwrap();
+        .unwrap();
+
+        assert_eq!(outcome.collection, "notes");
+        assert_eq!(outcome.path, "long.md");
+        assert_eq!(outcome.chunks_scored, 3);
+        let order: Vec<u64> =
+            outcome.passages.iter().map(|p| p.chunk_doc_id).collect();
+        assert_eq!(order, vec![202, 201, 203]);
+        assert_eq!(
+            outcome.passages[0],
+            DocumentPassage {
+                rank: 1,
+                score: 1.0,
+                chunk_doc_id: 202,
+                start_byte: 11,
+                byte_len: 25,
+                start_line: 2,
+                end_line: 3,
+                text: "rust ownership\nborrowing\n".to_string(),
+            }
+        );
+        assert_eq!(outcome.passages[2].start_line, 4);
+        assert_eq!(outcome.passages[2].end_line, 4);
+    }
 
-        (config_db, data_dir, model, tmp)
+    #[test]
+    fn within_applies_count_and_min_score_and_skips_unembedded_chunks() {
+        let (data_dir, config_db, _tmp) = setup_within();
+        EmbeddingDb::open(&data_dir.embeddings_db())
+            .unwrap()
+            .remove(201)
+            .unwrap();
+
+        let mut args = within_args("notes:long.md");
+        args.min_score = -0.5;
+        let outcome =
+            within_scored(&args, &config_db, &data_dir, encode_along_x)
+                .unwrap();
+        assert_eq!(outcome.chunks_scored, 2);
+        assert_eq!(outcome.passages.len(), 1);
+        assert_eq!(outcome.passages[0].chunk_doc_id, 202);
+
+        args.min_score = -10.0;
+        args.count = 1;
+        let outcome =
+            within_scored(&args, &config_db, &data_dir, encode_along_x)
+                .unwrap();
+        assert_eq!(outcome.passages.len(), 1);
+    }
+
+    #[test]
+    fn within_rejects_unknown_references_without_encoding() {
+        let (data_dir, config_db, _tmp) = setup_within();
+
+        let err = within_scored(
+            &within_args("notes:missing.md"),
+            &config_db,
+            &data_dir,
+            |_| panic!("the query should not be encoded"),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::NotFound {
+                kind: "document",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn subsample_tokens_keeps_evenly_spaced_rows() {
+        let flat: Vec<f32> = (0..10).map(|i| i as f32).collect();
+        assert_eq!(subsample_tokens(flat.clone(), 2, 5), flat);
+        assert_eq!(subsample_tokens(flat, 2, 2), vec![0.0, 1.0, 4.0, 5.0]);
     }
 
     #[test]
@@ -1999,6 +6177,10 @@ mod tests {
                 path: "a.md".into(),
                 title: "A".into(),
                 best_chunk_doc_id: None,
+                rerank_score: None,
+                passages: Vec::new(),
+                token_matches: Vec::new(),
+                explain: None,
             },
             FinalResult {
                 rank: 2,
@@ -2009,6 +6191,10 @@ mod tests {
                 path: "b.md".into(),
                 title: "B".into(),
                 best_chunk_doc_id: None,
+                rerank_score: None,
+                passages: Vec::new(),
+                token_matches: Vec::new(),
+                explain: None,
             },
         ];
 
@@ -2057,6 +6243,10 @@ mod tests {
                 // BM25 already populated this from Tantivy.
                 title: "demo::greet".to_string(),
                 best_chunk_doc_id: Some(1),
+                rerank_score: None,
+                passages: Vec::new(),
+                token_matches: Vec::new(),
+                explain: None,
             },
             FinalResult {
                 rank: 2,
@@ -2068,6 +6258,10 @@ mod tests {
                 // Semantic-only hit: title is empty until populated.
                 title: String::new(),
                 best_chunk_doc_id: Some(2),
+                rerank_score: None,
+                passages: Vec::new(),
+                token_matches: Vec::new(),
+                explain: None,
             },
         ];
 
@@ -2153,9 +6347,14 @@ mod tests {
             path: "hello.md".to_string(),
             title: "Hello \"Rust\"".to_string(),
             best_chunk_doc_id: None,
+            rerank_score: None,
+            passages: Vec::new(),
+            token_matches: Vec::new(),
+            explain: None,
         }];
 
-        let json = format_json_string(&results, "rust\nquery");
+        let json =
+            format_json_string(&results, "rust\nquery", None, None, None);
 
         assert_eq!(
             json,
@@ -2174,12 +6373,422 @@ mod tests {
             path: "hello.md".to_string(),
             title: "Hello".to_string(),
             best_chunk_doc_id: None,
+            rerank_score: None,
+            passages: Vec::new(),
+            token_matches: Vec::new(),
+            explain: None,
         }];
 
-        let json = format_json_string(&results, "rust");
+        let json = format_json_string(&results, "rust", None, None, None);
         assert!(json.contains("\"score\":1.200000"));
     }
 
+    #[test]
+    fn search_json_includes_rerank_score_when_present() {
+        let results = vec![FinalResult {
+            rank: 1,
+            score: 0.03,
+            doc_id: "#abc123".to_string(),
+            doc_num_id: 42,
+            collection: "notes".to_string(),
+            path: "hello.md".to_string(),
+            title: "Hello".to_string(),
+            best_chunk_doc_id: None,
+            rerank_score: Some(4.5),
+            passages: Vec::new(),
+            token_matches: Vec::new(),
+            explain: None,
+        }];
+
+        let json = format_json_string(&results, "rust", None, None, None);
+        assert!(json.contains("\"score\":0.030000"));
+        assert!(json.contains("\"rerank_score\":4.500000"));
+    }
+
+    #[test]
+    fn search_json_appends_facets_object() {
+        let facets = Facets {
+            total: 2,
+            collections: vec![FacetCount {
+                value: "notes".to_string(),
+                count: 2,
+            }],
+            tags: vec![FacetCount {
+                value: "a\"b".to_string(),
+                count: 1,
+            }],
+            ..Facets::default()
+        };
+
+        let json = format_json_string(&[], "rust", None, Some(&facets), None);
+        assert!(json.ends_with(
+            ",\"facets\":{\"total\":2,\"collections\":[{\"value\":\"notes\",\"count\":2}],\"extensions\":[],\"years\":[],\"tags\":[{\"value\":\"a\\\"b\",\"count\":1}]}}"
+        ));
+    }
+
+    #[test]
+    fn search_json_appends_suggestion() {
+        let suggestion = QuerySuggestion {
+            query: "rust \"book\"".to_string(),
+            applied: true,
+        };
+
+        let json =
+            format_json_string(&[], "rsut", None, None, Some(&suggestion));
+        assert!(json.ends_with(
+            ",\"suggestion\":{\"query\":\"rust \\\"book\\\"\",\"applied\":true}}"
+        ));
+    }
+
+    #[test]
+    fn rerank_without_configured_model_keeps_fused_order() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config_db = ConfigDb::open(&tmp.path().join("config.db")).unwrap();
+        let mut model = ModelManager::new();
+        let mut results: Vec<FinalResult> = (1..=3)
+            .map(|i| FinalResult {
+                rank: i,
+                score: 1.0 / i as f32,
+                doc_id: format!("#doc{i}"),
+                doc_num_id: i as u64,
+                collection: "notes".to_string(),
+                path: format!("{i}.md"),
+                title: String::new(),
+                best_chunk_doc_id: None,
+                rerank_score: None,
+                passages: Vec::new(),
+                token_matches: Vec::new(),
+                explain: None,
+            })
+            .collect();
+
+        rerank_with_cross_encoder(&mut results, "q", &config_db, &mut model)
+            .unwrap();
+
+        let order: Vec<u64> = results.iter().map(|r| r.doc_num_id).collect();
+        assert_eq!(order, vec![1, 2, 3]);
+        assert!(results.iter().all(|r| r.rerank_score.is_none()));
+    }
+
+    #[test]
+    fn failing_reranker_keeps_fused_order_and_reports_the_error() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config_db = ConfigDb::open(&tmp.path().join("config.db")).unwrap();
+        let missing = tmp.path().join("no-such-reranker");
+        let mut model = ModelManager::new()
+            .with_reranker_model(Some(missing.to_str().unwrap().to_string()));
+        let mut results: Vec<FinalResult> = (1..=3)
+            .map(|i| FinalResult {
+                rank: i,
+                score: 1.0 / i as f32,
+                doc_id: format!("#doc{i}"),
+                doc_num_id: i as u64,
+                collection: "notes".to_string(),
+                path: format!("{i}.md"),
+                title: String::new(),
+                best_chunk_doc_id: None,
+                rerank_score: None,
+                passages: Vec::new(),
+                token_matches: Vec::new(),
+                explain: None,
+            })
+            .collect();
+
+        let mut explain = SearchExplain::default();
+
+        rerank_or_keep_order(
+            &mut results,
+            "q",
+            &config_db,
+            &mut model,
+            &mut explain,
+        );
+
+        let order: Vec<u64> = results.iter().map(|r| r.doc_num_id).collect();
+        assert_eq!(order, vec![1, 2, 3]);
+        assert!(results.iter().all(|r| r.rerank_score.is_none()));
+        assert!(explain.rerank_error.is_some());
+        assert!(
+            search_explain_json_string(&explain)
+                .contains("\"rerank_error\":\"")
+        );
+    }
+
+    #[test]
+    fn rerank_passage_prefers_best_chunk_range() {
+        use crate::config_db::DocChunkEntry;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let config_db = ConfigDb::open(&tmp.path().join("config.db")).unwrap();
+        let root = tmp.path().join("notes");
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("a.md"), "first chunk|second chunk").unwrap();
+        config_db
+            .set_collection("notes", root.to_str().unwrap())
+            .unwrap();
+        let did = DocumentId::new("notes", "a.md");
+        config_db
+            .set_doc_chunks(
+                did.numeric,
+                &[
+                    DocChunkEntry {
+                        chunk_doc_id: 7,
+                        start_byte: 0,
+                        byte_len: 11,
+                    },
+                    DocChunkEntry {
+                        chunk_doc_id: 8,
+                        start_byte: 12,
+                        byte_len: 12,
+                    },
+                ],
+            )
+            .unwrap();
+
+        let mut result = FinalResult {
+            rank: 1,
+            score: 1.0,
+            doc_id: did.short.clone(),
+            doc_num_id: did.numeric,
+            collection: "notes".to_string(),
+            path: "a.md".to_string(),
+            title: "A".to_string(),
+            best_chunk_doc_id: Some(8),
+            rerank_score: None,
+            passages: Vec::new(),
+            token_matches: Vec::new(),
+            explain: None,
+        };
+        let mut paths = HashMap::new();
+        assert_eq!(
+            rerank_passage(&result, &config_db, &mut paths),
+            "second chunk"
+        );
+
+        result.best_chunk_doc_id = None;
+        assert_eq!(
+            rerank_passage(&result, &config_db, &mut paths),
+            "first chunk"
+        );
+    }
+
+    fn passage_result(doc_num_id: u64, passages: Vec<Passage>) -> FinalResult {
+        FinalResult {
+            rank: 0,
+            score: 0.5,
+            doc_id: format!("#{doc_num_id:06x}"),
+            doc_num_id,
+            collection: "notes".to_string(),
+            path: format!("{doc_num_id}.md"),
+            title: String::new(),
+            best_chunk_doc_id: passages.first().map(|p| p.chunk_doc_id),
+            rerank_score: None,
+            passages,
+            token_matches: Vec::new(),
+            explain: None,
+        }
+    }
+
+    fn passage(chunk_doc_id: u64, score: f32, start_byte: u64) -> Passage {
+        Passage {
+            chunk_doc_id,
+            score,
+            start_byte,
+            byte_len: 10,
+        }
+    }
+
+    #[test]
+    fn attach_passages_keeps_top_chunks_with_manifest_ranges() {
+        use crate::config_db::DocChunkEntry;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let config_db = ConfigDb::open(&tmp.path().join("config.db")).unwrap();
+        config_db
+            .set_doc_chunks(
+                1,
+                &[
+                    DocChunkEntry {
+                        chunk_doc_id: 10,
+                        start_byte: 0,
+                        byte_len: 100,
+                    },
+                    DocChunkEntry {
+                        chunk_doc_id: 11,
+                        start_byte: 100,
+                        byte_len: 80,
+                    },
+                    DocChunkEntry {
+                        chunk_doc_id: 12,
+                        start_byte: 180,
+                        byte_len: 40,
+                    },
+                ],
+            )
+            .unwrap();
+        let mut hits = ChunkHits::new();
+        // 99 is not in the manifest and must be skipped; 11 repeats.
+        hits.insert(1, vec![(10, 0.2), (11, 0.9), (99, 0.95), (12, 0.5)]);
+        hits.get_mut(&1).unwrap().push((11, 0.9));
+
+        let mut results = vec![passage_result(1, Vec::new())];
+        attach_passages(&mut results, &hits, &config_db, 2);
+
+        assert_eq!(
+            results[0].passages,
+            vec![
+                Passage {
+                    chunk_doc_id: 11,
+                    score: 0.9,
+                    start_byte: 100,
+                    byte_len: 80,
+                },
+                Passage {
+                    chunk_doc_id: 12,
+                    score: 0.5,
+                    start_byte: 180,
+                    byte_len: 40,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn attach_passages_is_a_no_op_when_disabled() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config_db = ConfigDb::open(&tmp.path().join("config.db")).unwrap();
+        let mut hits = ChunkHits::new();
+        hits.insert(1, vec![(10, 0.2)]);
+
+        let mut results = vec![passage_result(1, Vec::new())];
+        attach_passages(&mut results, &hits, &config_db, 0);
+
+        assert!(results[0].passages.is_empty());
+    }
+
+    #[test]
+    fn group_passages_by_document_is_identity() {
+        let results = vec![passage_result(
+            1,
+            vec![passage(10, 0.9, 0), passage(11, 0.4, 50)],
+        )];
+        let grouped = group_passages(results, PassageGrouping::Document);
+        assert_eq!(grouped.len(), 1);
+        assert_eq!(grouped[0].passages.len(), 2);
+    }
+
+    #[test]
+    fn group_passages_flat_orders_by_passage_score_and_reranks() {
+        let results = vec![
+            passage_result(1, vec![passage(10, 0.9, 0), passage(11, 0.4, 50)]),
+            passage_result(2, vec![passage(20, 0.7, 0)]),
+            passage_result(3, Vec::new()),
+        ];
+
+        let flat = group_passages(results, PassageGrouping::Passage);
+
+        let summary: Vec<(usize, u64, Option<u64>, f32)> = flat
+            .iter()
+            .map(|r| (r.rank, r.doc_num_id, r.best_chunk_doc_id, r.score))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (1, 1, Some(10), 0.9),
+                (2, 2, Some(20), 0.7),
+                (3, 1, Some(11), 0.4),
+            ]
+        );
+        assert!(flat.iter().all(|r| r.passages.len() == 1));
+    }
+
+    #[test]
+    fn passage_grouping_parse_roundtrips() {
+        for grouping in [PassageGrouping::Document, PassageGrouping::Passage] {
+            assert_eq!(
+                PassageGrouping::parse(grouping.as_str()),
+                Some(grouping)
+            );
+        }
+        assert_eq!(PassageGrouping::parse("chunk"), None);
+    }
+
+    #[test]
+    fn search_json_includes_passages_when_present() {
+        let results = vec![passage_result(1, vec![passage(10, 0.25, 40)])];
+
+        let json = format_json_string(&results, "rust", None, None, None);
+        assert!(json.contains(
+            "\"passages\":[{\"score\":0.250000,\"start_byte\":40,\"end_byte\":49}]"
+        ));
+
+        let without = format_json_string(
+            &[passage_result(2, Vec::new())],
+            "q",
+            None,
+            None,
+            None,
+        );
+        assert!(!without.contains("passages"));
+    }
+
+    fn alignment(
+        query_token: &str,
+        score: f32,
+        span: Option<(usize, usize)>,
+    ) -> docbert_pylate::TokenAlignment {
+        docbert_pylate::TokenAlignment {
+            query_index: 0,
+            query_token: query_token.to_string(),
+            document_index: 0,
+            document_token: query_token.to_string(),
+            score,
+            span,
+        }
+    }
+
+    #[test]
+    fn token_matches_shift_spans_to_document_offsets() {
+        let alignments = vec![
+            alignment("[CLS]", 0.3, None),
+            alignment("rust", 0.9, Some((4, 8))),
+            alignment("borrow", 0.6, Some((10, 16))),
+        ];
+
+        let matches = token_matches_from_alignments(&alignments, 100);
+        let summary: Vec<_> = matches
+            .iter()
+            .map(|m| (m.query_token.as_str(), m.start_byte, m.byte_len))
+            .collect();
+        assert_eq!(summary, vec![("rust", 104, 4), ("borrow", 110, 6)]);
+    }
+
+    #[test]
+    fn search_json_includes_token_matches_when_present() {
+        let mut result = passage_result(1, Vec::new());
+        result.token_matches = vec![TokenMatch {
+            query_token: "rust".to_string(),
+            document_token: "rust".to_string(),
+            score: 0.5,
+            start_byte: 12,
+            byte_len: 4,
+        }];
+
+        let json = format_json_string(&[result], "rust", None, None, None);
+        assert!(json.contains(
+            "\"token_matches\":[{\"query_token\":\"rust\",\"document_token\":\"rust\",\"score\":0.500000,\"start_byte\":12,\"end_byte\":15}]"
+        ));
+
+        let without = format_json_string(
+            &[passage_result(2, Vec::new())],
+            "q",
+            None,
+            None,
+            None,
+        );
+        assert!(!without.contains("token_matches"));
+    }
+
     #[test]
     fn json_escape_basic() {
         assert_eq!(json_escape("hello"), "\"hello\"");
@@ -0,0 +1,332 @@
+//! Short-lived cache of ranked searches and the cursors that page
+//! through them.
+//!
+//! A search ranks every candidate once, then cuts the requested page
+//! out of that ranking. [`SearchCache`] keeps the ranking for a few
+//! minutes so later pages are cut from it without encoding the query or
+//! probing PLAID again. A [`SearchCursor`] names the ranking a page came
+//! from and where the next page starts; when the ranking has left the
+//! cache, the search runs again and the cursor only stays valid if it
+//! produces the same order.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    error::{Error, Result},
+    search::RankedSearch,
+};
+
+/// How long a [`SearchCache`] keeps a ranking after computing it.
+pub const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(300);
+
+/// Rankings a [`SearchCache`] holds before evicting the oldest.
+pub const DEFAULT_CACHE_CAPACITY: usize = 64;
+
+/// In-process cache of ranked searches, keyed by the query and every
+/// parameter that shapes the ranking.
+///
+/// Safe to share between threads. Entries expire after `ttl` and the
+/// oldest is evicted once `capacity` rankings are held. Rankings never
+/// go stale inside their lifetime on purpose: a first page always ranks
+/// afresh, so only follow-up pages read from the cache.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use docbert_core::search_cache::SearchCache;
+///
+/// let cache = SearchCache::new(Duration::from_secs(60), 16);
+/// assert!(cache.is_empty());
+/// ```
+pub struct SearchCache {
+    ttl: Duration,
+    capacity: usize,
+    entries: Mutex<HashMap<u64, CacheEntry>>,
+}
+
+struct CacheEntry {
+    stored_at: Instant,
+    ranking: Arc<RankedSearch>,
+}
+
+impl Default for SearchCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CACHE_TTL, DEFAULT_CACHE_CAPACITY)
+    }
+}
+
+impl SearchCache {
+    pub fn new(ttl: Duration, capacity: usize) -> Self {
+        Self {
+            ttl,
+            capacity,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Number of live rankings.
+    pub fn len(&self) -> usize {
+        let mut entries = self.entries();
+        self.evict_expired(&mut entries);
+        entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drop every cached ranking.
+    pub fn clear(&self) {
+        self.entries().clear();
+    }
+
+    pub(crate) fn get(&self, key: u64) -> Option<Arc<RankedSearch>> {
+        let mut entries = self.entries();
+        self.evict_expired(&mut entries);
+        entries.get(&key).map(|entry| Arc::clone(&entry.ranking))
+    }
+
+    pub(crate) fn insert(&self, key: u64, ranking: Arc<RankedSearch>) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.entries();
+        self.evict_expired(&mut entries);
+        if !entries.contains_key(&key) && entries.len() >= self.capacity {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.stored_at)
+                .map(|(key, _)| *key);
+            if let Some(oldest) = oldest {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(
+            key,
+            CacheEntry {
+                stored_at: Instant::now(),
+                ranking,
+            },
+        );
+    }
+
+    // A panic while holding the lock leaves the map itself intact, so a
+    // poisoned mutex is still safe to use.
+    fn entries(&self) -> MutexGuard<'_, HashMap<u64, CacheEntry>> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn evict_expired(&self, entries: &mut HashMap<u64, CacheEntry>) {
+        entries.retain(|_, entry| entry.stored_at.elapsed() < self.ttl);
+    }
+}
+
+/// Opaque position in a ranked search: which ranking a page was cut
+/// from and where the next page starts.
+///
+/// Cursors travel as strings (see [`SearchCursor::encode`]) and are only
+/// valid for the search that issued them.
+///
+/// # Examples
+///
+/// ```
+/// use docbert_core::search_cache::SearchCursor;
+///
+/// assert!(SearchCursor::decode("not-a-cursor").is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchCursor {
+    pub(crate) key: u64,
+    pub(crate) fingerprint: u64,
+    pub(crate) depth: usize,
+    pub(crate) offset: usize,
+}
+
+impl SearchCursor {
+    /// Index of the first result on the page this cursor points at.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Render the cursor for a client to send back.
+    pub fn encode(&self) -> String {
+        format!(
+            "{:016x}{:016x}-{:x}-{:x}",
+            self.key, self.fingerprint, self.depth, self.offset
+        )
+    }
+
+    /// Parse a cursor produced by [`SearchCursor::encode`].
+    pub fn decode(cursor: &str) -> Result<Self> {
+        let invalid = || Error::InvalidCursor("malformed cursor".to_string());
+        let mut parts = cursor.split('-');
+        let (Some(ids), Some(depth), Some(offset), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(invalid());
+        };
+        if ids.len() != 32 || !ids.is_ascii() {
+            return Err(invalid());
+        }
+        let hex_u64 =
+            |s: &str| u64::from_str_radix(s, 16).map_err(|_| invalid());
+        let hex_usize =
+            |s: &str| usize::from_str_radix(s, 16).map_err(|_| invalid());
+        Ok(Self {
+            key: hex_u64(&ids[..16])?,
+            fingerprint: hex_u64(&ids[16..])?,
+            depth: hex_usize(depth)?,
+            offset: hex_usize(offset)?,
+        })
+    }
+}
+
+/// Builds the cache key of a search from everything that shapes its
+/// ranking.
+pub(crate) struct CacheKey(blake3::Hasher);
+
+impl CacheKey {
+    pub(crate) fn new(kind: &str) -> Self {
+        let mut key = Self(blake3::Hasher::new());
+        key.str(kind);
+        key
+    }
+
+    pub(crate) fn str(&mut self, value: &str) -> &mut Self {
+        self.u64(value.len() as u64);
+        self.0.update(value.as_bytes());
+        self
+    }
+
+    pub(crate) fn opt_str(&mut self, value: Option<&str>) -> &mut Self {
+        match value {
+            Some(value) => self.flag(true).str(value),
+            None => self.flag(false),
+        }
+    }
+
+    pub(crate) fn opt_u64(&mut self, value: Option<u64>) -> &mut Self {
+        match value {
+            Some(value) => self.flag(true).u64(value),
+            None => self.flag(false),
+        }
+    }
+
+    pub(crate) fn u64(&mut self, value: u64) -> &mut Self {
+        self.0.update(&value.to_le_bytes());
+        self
+    }
+
+    pub(crate) fn flag(&mut self, value: bool) -> &mut Self {
+        self.0.update(&[u8::from(value)]);
+        self
+    }
+
+    pub(crate) fn finish(&self) -> u64 {
+        first_u64(self.0.finalize())
+    }
+}
+
+/// Hash of a ranking's document order, so a cursor can tell whether a
+/// recomputed ranking still matches the one it paged through.
+pub(crate) fn fingerprint(doc_num_ids: impl IntoIterator<Item = u64>) -> u64 {
+    let mut hasher = blake3::Hasher::new();
+    for id in doc_num_ids {
+        hasher.update(&id.to_le_bytes());
+    }
+    first_u64(hasher.finalize())
+}
+
+fn first_u64(hash: blake3::Hash) -> u64 {
+    let bytes = hash.as_bytes();
+    u64::from_le_bytes([
+        bytes[0], bytes[1], bytes[2], bytes[3], bytes[4], bytes[5], bytes[6],
+        bytes[7],
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranking(fingerprint: u64) -> Arc<RankedSearch> {
+        let mut ranking = RankedSearch::default();
+        ranking.fingerprint = fingerprint;
+        Arc::new(ranking)
+    }
+
+    #[test]
+    fn cursor_round_trips_through_its_encoding() {
+        let cursor = SearchCursor {
+            key: u64::MAX,
+            fingerprint: 7,
+            depth: 10,
+            offset: 30,
+        };
+        let encoded = cursor.encode();
+        assert_eq!(SearchCursor::decode(&encoded).unwrap(), cursor);
+        assert_eq!(cursor.offset(), 30);
+    }
+
+    #[test]
+    fn cursor_decode_rejects_malformed_input() {
+        for bad in [
+            "",
+            "abc-1-2",
+            "00000000000000000000000000000000-1",
+            "00000000000000000000000000000000-1-2-3",
+            "0000000000000000000000000000000g-1-2",
+            "00000000000000000000000000000000-x-2",
+        ] {
+            assert!(
+                matches!(
+                    SearchCursor::decode(bad),
+                    Err(Error::InvalidCursor(_))
+                ),
+                "{bad:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn cache_returns_inserted_rankings_until_they_expire() {
+        let cache = SearchCache::new(Duration::from_secs(60), 4);
+        cache.insert(1, ranking(11));
+        assert_eq!(cache.get(1).unwrap().fingerprint, 11);
+        assert!(cache.get(2).is_none());
+
+        let expired = SearchCache::new(Duration::ZERO, 4);
+        expired.insert(1, ranking(11));
+        assert!(expired.get(1).is_none());
+        assert!(expired.is_empty());
+    }
+
+    #[test]
+    fn cache_evicts_the_oldest_ranking_at_capacity() {
+        let cache = SearchCache::new(Duration::from_secs(60), 2);
+        cache.insert(1, ranking(1));
+        cache.insert(2, ranking(2));
+        cache.insert(3, ranking(3));
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(1).is_none());
+        assert!(cache.get(3).is_some());
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn cache_key_separates_missing_and_empty_strings() {
+        let missing = CacheKey::new("k").opt_str(None).finish();
+        let empty = CacheKey::new("k").opt_str(Some("")).finish();
+        assert_ne!(missing, empty);
+        assert_ne!(fingerprint([1, 2]), fingerprint([2, 1]));
+    }
+}
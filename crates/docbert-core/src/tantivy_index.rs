@@ -1,5 +1,6 @@
 use std::path::Path;
 
+use levenshtein_automata::{DFA, Distance, LevenshteinAutomatonBuilder};
 use tantivy::{
     Index,
     IndexReader,
@@ -14,12 +15,18 @@ use tantivy::{
         RemoveLongFilter,
         SimpleTokenizer,
         Stemmer,
+        StopWordFilter,
         TextAnalyzer,
     },
 };
+use tantivy_fst::Automaton;
 
 use crate::error::Result;
 
+/// Indexed completions each word of a [`SearchIndex::complete`] prefix
+/// expands to.
+const COMPLETION_EXPANSIONS: usize = 64;
+
 /// Field names used in docbert's Tantivy schema.
 ///
 /// These constants match the schema exactly and are reused when building
@@ -39,6 +46,15 @@ pub mod fields {
     pub const BODY: &str = "body";
     /// Last modification time as seconds since Unix epoch (u64, STORED, FAST).
     pub const MTIME: &str = "mtime";
+    /// Frontmatter tags, one value per tag (STRING, STORED, FAST).
+    pub const TAGS: &str = "tags";
+    /// Lowercased, unstemmed title and body terms of any length, feeding
+    /// spelling suggestions and the grep prefilter (TEXT, doc ids only,
+    /// not stored).
+    pub const SPELL: &str = "spell";
+    /// Lowercased, unstemmed title and path terms, feeding prefix
+    /// completion (TEXT, term frequencies, not stored).
+    pub const COMPLETE: &str = "complete";
 }
 
 /// Wrapper around docbert's Tantivy full-text index.
@@ -88,6 +104,12 @@ pub struct SchemaFields {
     pub body: Field,
     /// Last modification time (Unix timestamp).
     pub mtime: Field,
+    /// Frontmatter tags.
+    pub tags: Field,
+    /// Unstemmed terms for spelling suggestions.
+    pub spell: Field,
+    /// Unstemmed title and path terms for prefix completion.
+    pub complete: Field,
 }
 
 /// Result returned straight from the Tantivy index.
@@ -112,6 +134,18 @@ pub struct SearchResult {
     pub mtime: u64,
 }
 
+/// Fast-field values of one indexed document, read by
+/// [`SearchIndex::document_facets`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocumentFacets {
+    /// Collection name.
+    pub collection: String,
+    /// Last modification time as seconds since the Unix epoch.
+    pub mtime: u64,
+    /// Frontmatter tags.
+    pub tags: Vec<String>,
+}
+
 fn build_schema() -> (Schema, SchemaFields) {
     let mut builder = Schema::builder();
 
@@ -138,6 +172,23 @@ fn build_schema() -> (Schema, SchemaFields) {
     let body = builder.add_text_field(fields::BODY, body_opts);
 
     let mtime = builder.add_u64_field(fields::MTIME, STORED | FAST);
+    let tags = builder.add_text_field(fields::TAGS, STRING | STORED | FAST);
+    let spell = builder.add_text_field(
+        fields::SPELL,
+        TextOptions::default().set_indexing_options(
+            TextFieldIndexing::default()
+                .set_tokenizer(SPELL_TOKENIZER)
+                .set_index_option(IndexRecordOption::Basic),
+        ),
+    );
+    let complete = builder.add_text_field(
+        fields::COMPLETE,
+        TextOptions::default().set_indexing_options(
+            TextFieldIndexing::default()
+                .set_tokenizer("default")
+                .set_index_option(IndexRecordOption::WithFreqs),
+        ),
+    );
 
     let schema = builder.build();
     let fields = SchemaFields {
@@ -148,11 +199,47 @@ fn build_schema() -> (Schema, SchemaFields) {
         title,
         body,
         mtime,
+        tags,
+        spell,
+        complete,
     };
 
     (schema, fields)
 }
 
+/// Names of the fields of [`build_schema`] that `on_disk` lacks or
+/// indexes with a different tokenizer.
+fn missing_from(on_disk: &Schema) -> Vec<&'static str> {
+    // A spell field tokenized with `default` dropped words longer than 40
+    // bytes, which the grep prefilter cannot tell apart from absent ones.
+    let spell_outdated = on_disk.get_field(fields::SPELL).is_ok_and(|field| {
+        match on_disk.get_field_entry(field).field_type() {
+            FieldType::Str(options) => options
+                .get_indexing_options()
+                .is_none_or(|indexing| indexing.tokenizer() != SPELL_TOKENIZER),
+            _ => true,
+        }
+    });
+    [
+        fields::DOC_ID,
+        fields::DOC_NUM_ID,
+        fields::COLLECTION,
+        fields::PATH,
+        fields::TITLE,
+        fields::BODY,
+        fields::MTIME,
+        fields::TAGS,
+        fields::SPELL,
+        fields::COMPLETE,
+    ]
+    .into_iter()
+    .filter(|name| {
+        on_disk.get_field(name).is_err()
+            || (*name == fields::SPELL && spell_outdated)
+    })
+    .collect()
+}
+
 fn default_text_analyzer() -> TextAnalyzer {
     TextAnalyzer::builder(SimpleTokenizer::default())
         .filter(RemoveLongFilter::limit(40))
@@ -160,6 +247,16 @@ fn default_text_analyzer() -> TextAnalyzer {
         .build()
 }
 
+/// Tokenizer of the spell field. Unlike `default` it keeps words of any
+/// length, so a literal inside a long hash or identifier is still found.
+const SPELL_TOKENIZER: &str = "spell";
+
+fn spell_text_analyzer() -> TextAnalyzer {
+    TextAnalyzer::builder(SimpleTokenizer::default())
+        .filter(LowerCaser)
+        .build()
+}
+
 fn stemmed_text_analyzer() -> TextAnalyzer {
     TextAnalyzer::builder(SimpleTokenizer::default())
         .filter(RemoveLongFilter::limit(40))
@@ -168,10 +265,27 @@ fn stemmed_text_analyzer() -> TextAnalyzer {
         .build()
 }
 
+/// [`stemmed_text_analyzer`] with English stop words removed, for
+/// picking expansion terms.
+fn expansion_text_analyzer() -> TextAnalyzer {
+    TextAnalyzer::builder(SimpleTokenizer::default())
+        .filter(RemoveLongFilter::limit(40))
+        .filter(LowerCaser)
+        .filter(
+            StopWordFilter::new(tantivy::tokenizer::Language::English)
+                .expect("tantivy ships English stop words"),
+        )
+        .filter(Stemmer::new(tantivy::tokenizer::Language::English))
+        .build()
+}
+
 fn register_tokenizers(index: &Index) {
     index
         .tokenizers()
         .register("en_stem", stemmed_text_analyzer());
+    index
+        .tokenizers()
+        .register(SPELL_TOKENIZER, spell_text_analyzer());
 }
 
 fn normalize_query_tokens(
@@ -227,7 +341,16 @@ impl SearchIndex {
     /// Open or create a search index at the given directory.
     ///
     /// Creates the directory if it does not exist. If the index already
-    /// exists on disk, it is opened; otherwise a new one is created.
+    /// exists on disk, it is opened with the schema it was created with;
+    /// otherwise a new one is created.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::SearchIndexOutdated`] if the existing index
+    /// lacks fields of the current schema; see
+    /// [`crate::tantivy_migration`] for rebuilding it.
+    ///
+    /// [`Error::SearchIndexOutdated`]: crate::Error::SearchIndexOutdated
     ///
     /// # Examples
     ///
@@ -246,7 +369,15 @@ impl SearchIndex {
         let index = if Index::exists(&mmap_dir)
             .map_err(|e| tantivy::TantivyError::SystemError(e.to_string()))?
         {
-            Index::open(mmap_dir)?
+            let index = Index::open(mmap_dir)?;
+            let missing = missing_from(&index.schema());
+            if !missing.is_empty() {
+                return Err(crate::Error::SearchIndexOutdated {
+                    path: dir.to_path_buf(),
+                    missing: missing.into_iter().map(String::from).collect(),
+                });
+            }
+            index
         } else {
             Index::create(
                 mmap_dir,
@@ -257,6 +388,7 @@ impl SearchIndex {
 
         register_tokenizers(&index);
         let reader = index.reader()?;
+        let schema = index.schema();
 
         Ok(Self {
             index,
@@ -265,6 +397,30 @@ impl SearchIndex {
         })
     }
 
+    /// Names of the current schema's fields that the index at `dir`
+    /// lacks or tokenizes differently, in schema order. Empty when `dir`
+    /// holds no index yet.
+    pub fn missing_fields(dir: &Path) -> Result<Vec<&'static str>> {
+        let mmap_dir = match tantivy::directory::MmapDirectory::open(dir) {
+            Ok(mmap_dir) => mmap_dir,
+            Err(
+                tantivy::directory::error::OpenDirectoryError::DoesNotExist(_),
+            ) => return Ok(vec![]),
+            Err(e) => {
+                return Err(
+                    tantivy::TantivyError::SystemError(e.to_string()).into()
+                );
+            }
+        };
+        if !Index::exists(&mmap_dir)
+            .map_err(|e| tantivy::TantivyError::SystemError(e.to_string()))?
+        {
+            return Ok(vec![]);
+        }
+        let on_disk = Index::open(mmap_dir)?.schema();
+        Ok(missing_from(&on_disk))
+    }
+
     /// Create an in-memory search index.
     ///
     /// Useful for testing. Data is lost when the `SearchIndex` is dropped.
@@ -312,6 +468,9 @@ impl SearchIndex {
             title: self.schema.get_field(fields::TITLE)?,
             body: self.schema.get_field(fields::BODY)?,
             mtime: self.schema.get_field(fields::MTIME)?,
+            tags: self.schema.get_field(fields::TAGS)?,
+            spell: self.schema.get_field(fields::SPELL)?,
+            complete: self.schema.get_field(fields::COMPLETE)?,
         })
     }
 
@@ -338,6 +497,34 @@ impl SearchIndex {
         title: &str,
         body: &str,
         mtime: u64,
+    ) -> Result<()> {
+        self.add_tagged_document(
+            writer,
+            doc_id,
+            doc_num_id,
+            collection,
+            path,
+            title,
+            body,
+            mtime,
+            &[],
+        )
+    }
+
+    /// [`add_document`](Self::add_document) with frontmatter `tags`,
+    /// which feed the tag facet.
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_tagged_document(
+        &self,
+        writer: &IndexWriter,
+        doc_id: &str,
+        doc_num_id: u64,
+        collection: &str,
+        path: &str,
+        title: &str,
+        body: &str,
+        mtime: u64,
+        tags: &[String],
     ) -> Result<()> {
         let f = self.fields()?;
 
@@ -345,7 +532,7 @@ impl SearchIndex {
         let term = tantivy::Term::from_field_text(f.doc_id, doc_id);
         writer.delete_term(term);
 
-        writer.add_document(doc!(
+        let mut document = doc!(
             f.doc_id => doc_id,
             f.doc_num_id => doc_num_id,
             f.collection => collection,
@@ -353,7 +540,15 @@ impl SearchIndex {
             f.title => title,
             f.body => body,
             f.mtime => mtime,
-        ))?;
+        );
+        for tag in tags {
+            document.add_text(f.tags, tag);
+        }
+        document.add_text(f.spell, title);
+        document.add_text(f.spell, body);
+        document.add_text(f.complete, title);
+        document.add_text(f.complete, path);
+        writer.add_document(document)?;
 
         Ok(())
     }
@@ -553,6 +748,689 @@ impl SearchIndex {
             .collect())
     }
 
+    /// Suggest a corrected spelling of `query` from the indexed terms.
+    ///
+    /// Query terms of three or more characters that occur in no
+    /// document are replaced by the closest indexed term: within one
+    /// edit for terms of up to four characters and two edits for longer
+    /// ones, counting a swap of adjacent characters as one edit.
+    /// Candidates are weighed by `ln(1 + df) / distance²`, so a common
+    /// term two edits away can beat a rare one a single edit away.
+    /// Returns `None` when every term is indexed or nothing is close
+    /// enough.
+    ///
+    /// Candidates come from a Levenshtein automaton run over each
+    /// segment's term dictionary, so the cost grows with the number of
+    /// close terms rather than the vocabulary. The index is read as of
+    /// the last reload; [`search`](Self::search) reloads it, so
+    /// suggesting after a search sees the latest commit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use docbert_core::SearchIndex;
+    ///
+    /// let index = SearchIndex::open_in_ram().unwrap();
+    /// let mut writer = index.writer(15_000_000).unwrap();
+    /// index.add_document(&writer, "a", 1, "notes", "a.md",
+    ///     "Ownership", "Rust ownership and borrowing.", 1000).unwrap();
+    /// writer.commit().unwrap();
+    /// index.search("rust ownrship", 10).unwrap();
+    ///
+    /// let suggestion = index.suggest("rust ownrship").unwrap();
+    /// assert_eq!(suggestion.as_deref(), Some("rust ownership"));
+    /// assert_eq!(index.suggest("rust ownership").unwrap(), None);
+    /// ```
+    pub fn suggest(&self, query: &str) -> Result<Option<String>> {
+        let field = self.fields()?.spell;
+        let searcher = self.reader.searcher();
+
+        // Byte span of each unknown term in `query`, and its characters.
+        let mut unknown = Vec::new();
+        let mut analyzer = default_text_analyzer();
+        let mut stream = analyzer.token_stream(query);
+        while let Some(token) = stream.next() {
+            let chars: Vec<char> = token.text.chars().collect();
+            if chars.len() < 3 || chars.iter().all(|c| c.is_ascii_digit()) {
+                continue;
+            }
+            let term = tantivy::Term::from_field_text(field, &token.text);
+            if searcher.doc_freq(&term)? == 0 {
+                unknown.push((
+                    token.offset_from..token.offset_to,
+                    token.text.clone(),
+                    chars,
+                ));
+            }
+        }
+        if unknown.is_empty() {
+            return Ok(None);
+        }
+
+        // Indexed terms close to each unknown term, found by walking
+        // every segment's term dictionary with a Levenshtein automaton
+        // rather than scanning it, with their distance to the term.
+        let mut close: Vec<std::collections::HashMap<String, usize>> =
+            vec![std::collections::HashMap::new(); unknown.len()];
+        for (i, (_, text, chars)) in unknown.iter().enumerate() {
+            let max = if chars.len() <= 4 { 1 } else { 2 };
+            let dfa = LEVENSHTEIN[max - 1]
+                .get_or_init(|| {
+                    LevenshteinAutomatonBuilder::new(max as u8, true)
+                })
+                .build_dfa(text);
+            for segment in searcher.segment_readers() {
+                let inverted = segment.inverted_index(field)?;
+                let mut terms =
+                    inverted.terms().search(Levenshtein(&dfa)).into_stream()?;
+                while terms.advance() {
+                    let Ok(candidate) = std::str::from_utf8(terms.key()) else {
+                        continue;
+                    };
+                    if close[i].contains_key(candidate) {
+                        continue;
+                    }
+                    let candidate_chars: Vec<char> =
+                        candidate.chars().collect();
+                    if let Some(distance) =
+                        edit_distance(chars, &candidate_chars, max)
+                    {
+                        close[i].insert(candidate.to_string(), distance);
+                    }
+                }
+            }
+        }
+
+        let mut best: Vec<Option<(f64, usize, String)>> =
+            vec![None; unknown.len()];
+        for (i, candidates) in close.into_iter().enumerate() {
+            for (candidate, distance) in candidates {
+                let doc_freq = searcher.doc_freq(
+                    &tantivy::Term::from_field_text(field, &candidate),
+                )?;
+                let weight =
+                    (1.0 + doc_freq as f64).ln() / (distance * distance) as f64;
+                let better = match &best[i] {
+                    None => true,
+                    Some((best_weight, best_distance, best_term)) => weight
+                        .total_cmp(best_weight)
+                        .then_with(|| best_distance.cmp(&distance))
+                        .then_with(|| best_term.cmp(&candidate))
+                        .is_gt(),
+                };
+                if better {
+                    best[i] = Some((weight, distance, candidate));
+                }
+            }
+        }
+        if best.iter().all(Option::is_none) {
+            return Ok(None);
+        }
+
+        let mut corrected = String::with_capacity(query.len());
+        let mut end = 0;
+        for ((span, _, _), best) in unknown.iter().zip(best) {
+            if let Some((_, _, term)) = best {
+                corrected.push_str(&query[end..span.start]);
+                corrected.push_str(&term);
+                end = span.end;
+            }
+        }
+        corrected.push_str(&query[end..]);
+        Ok(Some(corrected))
+    }
+
+    /// Complete `prefix` to the titles and paths of indexed documents.
+    ///
+    /// Every word of `prefix` must start a word of a document's title or
+    /// relative path, so `rust own` finds both "Rust Ownership" and
+    /// `guides/rust-ownership.md`. Each word stands for its 64 most
+    /// frequent indexed completions, scored with BM25 so short titles
+    /// and paths rank first and an exact word counts double. Documents
+    /// whose title, path or file name starts with `prefix` itself come
+    /// before the rest. Only the term dictionary and stored fields are
+    /// read, so completing needs neither the model nor the source files.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use docbert_core::SearchIndex;
+    ///
+    /// let index = SearchIndex::open_in_ram().unwrap();
+    /// let mut writer = index.writer(15_000_000).unwrap();
+    /// index.add_document(&writer, "a", 1, "notes", "guides/rust-ownership.md",
+    ///     "Rust Ownership", "Moves and borrows.", 1000).unwrap();
+    /// index.add_document(&writer, "b", 2, "notes", "python.md",
+    ///     "Python Typing", "Gradual types.", 1000).unwrap();
+    /// writer.commit().unwrap();
+    ///
+    /// let completions = index.complete("rust own", None, 10).unwrap();
+    /// assert_eq!(completions.len(), 1);
+    /// assert_eq!(completions[0].path, "guides/rust-ownership.md");
+    /// ```
+    pub fn complete(
+        &self,
+        prefix: &str,
+        collection: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<SearchResult>> {
+        let fields = self.fields()?;
+        let field = fields.complete;
+        let words = normalize_query_tokens(prefix, default_text_analyzer());
+        if words.is_empty() || limit == 0 {
+            return Ok(vec![]);
+        }
+        self.reader.reload()?;
+        let searcher = self.reader.searcher();
+
+        let mut clauses: Vec<(
+            tantivy::query::Occur,
+            Box<dyn tantivy::query::Query>,
+        )> = Vec::with_capacity(words.len() + 1);
+        for word in &words {
+            // Document frequency of every indexed term `word` starts.
+            let mut completions: std::collections::HashMap<String, u32> =
+                std::collections::HashMap::new();
+            for segment in searcher.segment_readers() {
+                let inverted = segment.inverted_index(field)?;
+                let mut terms = inverted
+                    .terms()
+                    .range()
+                    .ge(word.as_bytes())
+                    .into_stream()?;
+                while terms.advance() {
+                    if !terms.key().starts_with(word.as_bytes()) {
+                        break;
+                    }
+                    let Ok(term) = std::str::from_utf8(terms.key()) else {
+                        continue;
+                    };
+                    *completions.entry(term.to_string()).or_default() +=
+                        terms.value().doc_freq;
+                }
+            }
+            if completions.is_empty() {
+                return Ok(vec![]);
+            }
+
+            let mut completions: Vec<(String, u32)> =
+                completions.into_iter().collect();
+            completions
+                .sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            completions.truncate(COMPLETION_EXPANSIONS);
+            let alternatives = completions
+                .into_iter()
+                .map(|(term, _)| {
+                    let boost = if term == *word { 2.0 } else { 1.0 };
+                    let query = tantivy::query::TermQuery::new(
+                        tantivy::Term::from_field_text(field, &term),
+                        IndexRecordOption::WithFreqs,
+                    );
+                    (
+                        tantivy::query::Occur::Should,
+                        Box::new(tantivy::query::BoostQuery::new(
+                            Box::new(query),
+                            boost,
+                        ))
+                            as Box<dyn tantivy::query::Query>,
+                    )
+                })
+                .collect();
+            clauses.push((
+                tantivy::query::Occur::Must,
+                Box::new(tantivy::query::BooleanQuery::new(alternatives)),
+            ));
+        }
+        if let Some(collection) = collection {
+            clauses.push((
+                tantivy::query::Occur::Must,
+                Box::new(tantivy::query::TermQuery::new(
+                    tantivy::Term::from_field_text(
+                        fields.collection,
+                        collection,
+                    ),
+                    IndexRecordOption::Basic,
+                )),
+            ));
+        }
+
+        let query = tantivy::query::BooleanQuery::new(clauses);
+        let candidates = self.execute_query(&query, limit.saturating_mul(4))?;
+
+        let prefix = prefix.trim().to_lowercase();
+        let mut ranked: Vec<(bool, SearchResult)> = candidates
+            .into_iter()
+            .map(|result| {
+                let file_name = Path::new(&result.path)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_lowercase())
+                    .unwrap_or_default();
+                let leading = result.title.to_lowercase().starts_with(&prefix)
+                    || result.path.to_lowercase().starts_with(&prefix)
+                    || file_name.starts_with(&prefix);
+                (leading, result)
+            })
+            .collect();
+        ranked.sort_by(|(a_leading, a), (b_leading, b)| {
+            b_leading
+                .cmp(a_leading)
+                .then_with(|| b.score.total_cmp(&a.score))
+                .then_with(|| a.collection.cmp(&b.collection))
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        Ok(ranked
+            .into_iter()
+            .take(limit)
+            .map(|(_, result)| result)
+            .collect())
+    }
+
+    /// Indexed documents whose title and body cannot contain `literal`,
+    /// mapped to the modification time they were indexed with.
+    ///
+    /// Every word of `literal` must occur in a matching document; the
+    /// first and last words may be the end and the start of longer
+    /// words. The spell field keeps words of any length and case is
+    /// ignored, so the complement is a safe superset of the
+    /// case-sensitive matches too. Returns `None` when `literal` has no
+    /// words.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use docbert_core::SearchIndex;
+    ///
+    /// let index = SearchIndex::open_in_ram().unwrap();
+    /// let mut writer = index.writer(15_000_000).unwrap();
+    /// index.add_document(&writer, "a", 1, "notes", "a.md",
+    ///     "A", "the borrow checker", 1000).unwrap();
+    /// index.add_document(&writer, "b", 2, "notes", "b.md",
+    ///     "B", "lifetimes", 2000).unwrap();
+    /// writer.commit().unwrap();
+    ///
+    /// let excluded = index.literal_exclusions("rrow check").unwrap().unwrap();
+    /// assert_eq!(excluded.len(), 1);
+    /// assert_eq!(excluded[&2], 2000);
+    /// assert_eq!(index.literal_exclusions("--").unwrap(), None);
+    /// ```
+    pub fn literal_exclusions(
+        &self,
+        literal: &str,
+    ) -> Result<Option<std::collections::HashMap<u64, u64>>> {
+        let field = self.fields()?.spell;
+
+        let mut clauses: Vec<(
+            tantivy::query::Occur,
+            Box<dyn tantivy::query::Query>,
+        )> = Vec::new();
+        let mut analyzer = spell_text_analyzer();
+        let mut stream = analyzer.token_stream(literal);
+        while let Some(token) = stream.next() {
+            // A word touching either end of the literal may continue
+            // beyond it in the document.
+            let open_start = token.offset_from == 0;
+            let open_end = token.offset_to == literal.len();
+            let query: Box<dyn tantivy::query::Query> =
+                if open_start || open_end {
+                    let pattern = format!(
+                        "{}{}{}",
+                        if open_start { ".*" } else { "" },
+                        regex::escape(&token.text),
+                        if open_end { ".*" } else { "" },
+                    );
+                    Box::new(tantivy::query::RegexQuery::from_pattern(
+                        &pattern, field,
+                    )?)
+                } else {
+                    Box::new(tantivy::query::TermQuery::new(
+                        tantivy::Term::from_field_text(field, &token.text),
+                        IndexRecordOption::Basic,
+                    ))
+                };
+            clauses.push((tantivy::query::Occur::Must, query));
+        }
+        if clauses.is_empty() {
+            return Ok(None);
+        }
+
+        self.reader.reload()?;
+        let searcher = self.reader.searcher();
+        let matching = searcher.search(
+            &tantivy::query::BooleanQuery::new(clauses),
+            &tantivy::collector::DocSetCollector,
+        )?;
+        let mut excluded = std::collections::HashMap::new();
+        for (ord, segment) in searcher.segment_readers().iter().enumerate() {
+            let fast = segment.fast_fields();
+            let num_ids = fast.u64(fields::DOC_NUM_ID)?;
+            let mtimes = fast.u64(fields::MTIME)?;
+            for doc in segment.doc_ids_alive() {
+                if matching.contains(&tantivy::DocAddress::new(ord as u32, doc))
+                {
+                    continue;
+                }
+                if let Some(doc_num_id) = num_ids.first(doc) {
+                    excluded.insert(doc_num_id, mtimes.first(doc).unwrap_or(0));
+                }
+            }
+        }
+        Ok(Some(excluded))
+    }
+
+    /// Find documents that share distinctive terms with `title` and
+    /// `body`, using Tantivy's MoreLikeThis query.
+    ///
+    /// The body isn't stored in the index, so callers pass the source
+    /// document's text. Terms are weighted by TF-IDF; terms that appear
+    /// in fewer than two documents can't match anything but the source
+    /// and are skipped. The document whose `doc_id` is `exclude_doc_id`
+    /// is left out of the results.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use docbert_core::SearchIndex;
+    ///
+    /// let index = SearchIndex::open_in_ram().unwrap();
+    /// let mut writer = index.writer(15_000_000).unwrap();
+    /// index.add_document(&writer, "a", 1, "notes", "a.md",
+    ///     "Borrowing", "Rust borrowing rules and lifetimes.", 1000).unwrap();
+    /// index.add_document(&writer, "b", 2, "notes", "b.md",
+    ///     "Lifetimes", "Lifetimes extend borrowing in Rust.", 1000).unwrap();
+    /// writer.commit().unwrap();
+    ///
+    /// let results = index.more_like_this(
+    ///     "Borrowing", "Rust borrowing rules and lifetimes.", "a", None, 10,
+    /// ).unwrap();
+    /// assert_eq!(results.len(), 1);
+    /// assert_eq!(results[0].path, "b.md");
+    /// ```
+    pub fn more_like_this(
+        &self,
+        title: &str,
+        body: &str,
+        exclude_doc_id: &str,
+        collection: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<SearchResult>> {
+        use tantivy::query::{
+            BooleanQuery,
+            MoreLikeThisQuery,
+            Occur,
+            Query,
+            TermQuery,
+        };
+
+        let fields = self.fields()?;
+        let similar = MoreLikeThisQuery::builder()
+            .with_min_doc_frequency(2)
+            .with_min_term_frequency(1)
+            .with_document_fields(vec![
+                (fields.title, vec![OwnedValue::from(title)]),
+                (fields.body, vec![OwnedValue::from(body)]),
+            ]);
+
+        let exclude =
+            tantivy::Term::from_field_text(fields.doc_id, exclude_doc_id);
+        let mut clauses: Vec<(Occur, Box<dyn Query>)> = vec![
+            (Occur::Must, Box::new(similar)),
+            (
+                Occur::MustNot,
+                Box::new(TermQuery::new(exclude, IndexRecordOption::Basic)),
+            ),
+        ];
+        if let Some(collection) = collection {
+            let term =
+                tantivy::Term::from_field_text(fields.collection, collection);
+            clauses.push((
+                Occur::Must,
+                Box::new(TermQuery::new(term, IndexRecordOption::Basic)),
+            ));
+        }
+
+        self.execute_query(&BooleanQuery::new(clauses), limit)
+    }
+
+    /// Pick the `limit` terms of `texts` that best separate them from
+    /// the rest of the corpus, for pseudo-relevance feedback.
+    ///
+    /// Terms are tokenized like the body field (lowercased and stemmed),
+    /// minus English stop words, and scored by their summed frequency
+    /// across `texts` times their BM25 IDF. Terms of `query`, terms
+    /// shorter than three characters, numbers, and terms indexed in
+    /// fewer than two documents are skipped. Weights are scaled so the
+    /// best term weighs `1.0`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use docbert_core::SearchIndex;
+    ///
+    /// let index = SearchIndex::open_in_ram().unwrap();
+    /// let mut writer = index.writer(15_000_000).unwrap();
+    /// index.add_document(&writer, "a", 1, "notes", "a.md",
+    ///     "Auth", "Auth tokens are signed with the session key.", 1000).unwrap();
+    /// index.add_document(&writer, "b", 2, "notes", "b.md",
+    ///     "Keys", "Rotate the session key monthly.", 1000).unwrap();
+    /// writer.commit().unwrap();
+    ///
+    /// let terms = index
+    ///     .expansion_terms(&["Auth tokens are signed with the session key."], "auth", 5)
+    ///     .unwrap();
+    /// assert_eq!(terms[0].1, 1.0);
+    /// assert!(terms.iter().any(|(term, _)| term == "session"));
+    /// assert!(terms.iter().all(|(term, _)| term != "auth"));
+    /// ```
+    pub fn expansion_terms(
+        &self,
+        texts: &[&str],
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<(String, f32)>> {
+        let query_terms: std::collections::HashSet<String> =
+            normalize_query_for_fuzzy(query).into_iter().collect();
+        let mut frequencies: std::collections::HashMap<String, usize> =
+            std::collections::HashMap::new();
+        for text in texts {
+            for term in normalize_query_tokens(text, expansion_text_analyzer())
+            {
+                if term.len() < 3
+                    || term.chars().all(|c| c.is_ascii_digit())
+                    || query_terms.contains(&term)
+                {
+                    continue;
+                }
+                *frequencies.entry(term).or_default() += 1;
+            }
+        }
+
+        let fields = self.fields()?;
+        self.reader.reload()?;
+        let searcher = self.reader.searcher();
+        let num_docs = searcher.num_docs() as f32;
+        let mut scored = Vec::with_capacity(frequencies.len());
+        for (term, tf) in frequencies {
+            let doc_freq = searcher
+                .doc_freq(&tantivy::Term::from_field_text(fields.body, &term))?
+                as f32;
+            if doc_freq < 2.0 {
+                continue;
+            }
+            let idf =
+                (1.0 + (num_docs - doc_freq + 0.5) / (doc_freq + 0.5)).ln();
+            scored.push((term, tf as f32 * idf));
+        }
+        scored.sort_by(|a, b| {
+            b.1.partial_cmp(&a.1)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.0.cmp(&b.0))
+        });
+        scored.truncate(limit);
+
+        if let Some(&(_, best)) = scored.first()
+            && best > 0.0
+        {
+            for (_, weight) in &mut scored {
+                *weight /= best;
+            }
+        }
+        Ok(scored)
+    }
+
+    /// BM25 search with expansion terms ORed into the query.
+    ///
+    /// Each `(term, weight)` pair from [`expansion_terms`] becomes a
+    /// body-field term query boosted by `weight * blend`, so `blend`
+    /// sets how much the expansion counts against the original query.
+    /// With `fuzzy`, the query also gets the fuzzy clauses of
+    /// [`search_fuzzy`].
+    ///
+    /// [`expansion_terms`]: Self::expansion_terms
+    /// [`search_fuzzy`]: Self::search_fuzzy
+    pub fn search_expanded(
+        &self,
+        query_str: &str,
+        expansion: &[(String, f32)],
+        blend: f32,
+        collection: Option<&str>,
+        fuzzy: bool,
+        limit: usize,
+    ) -> Result<Vec<SearchResult>> {
+        use tantivy::query::{
+            BooleanQuery,
+            BoostQuery,
+            FuzzyTermQuery,
+            Occur,
+            Query,
+            TermQuery,
+        };
+
+        let fields = self.fields()?;
+        let mut should: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+        if let Some(normalized_query) = normalize_query_for_parser(query_str) {
+            let parser = create_query_parser(&self.index, fields);
+            let (query, _errors) =
+                parser.parse_query_lenient(&normalized_query);
+            should.push((Occur::Should, query));
+        }
+        if fuzzy {
+            for term_str in normalize_query_for_fuzzy(query_str) {
+                if term_str.len() >= 3 {
+                    let term =
+                        tantivy::Term::from_field_text(fields.body, &term_str);
+                    should.push((
+                        Occur::Should,
+                        Box::new(FuzzyTermQuery::new(term, 1, true)),
+                    ));
+                }
+            }
+        }
+        for (term_str, weight) in expansion {
+            let term = tantivy::Term::from_field_text(fields.body, term_str);
+            should.push((
+                Occur::Should,
+                Box::new(BoostQuery::new(
+                    Box::new(TermQuery::new(
+                        term,
+                        IndexRecordOption::WithFreqs,
+                    )),
+                    weight * blend,
+                )),
+            ));
+        }
+        if should.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let mut clauses: Vec<(Occur, Box<dyn Query>)> =
+            vec![(Occur::Must, Box::new(BooleanQuery::new(should)))];
+        if let Some(collection) = collection {
+            let term =
+                tantivy::Term::from_field_text(fields.collection, collection);
+            clauses.push((
+                Occur::Must,
+                Box::new(TermQuery::new(term, IndexRecordOption::Basic)),
+            ));
+        }
+
+        self.execute_query(&BooleanQuery::new(clauses), limit)
+    }
+
+    /// Read the collection, mtime and tags of every live document whose
+    /// numeric id is in `doc_num_ids`, from the FAST columns only.
+    ///
+    /// Ids that aren't indexed are left out of the returned map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashSet;
+    /// use docbert_core::SearchIndex;
+    ///
+    /// let index = SearchIndex::open_in_ram().unwrap();
+    /// let mut writer = index.writer(15_000_000).unwrap();
+    /// index.add_tagged_document(&writer, "a", 1, "notes", "a.md", "A",
+    ///     "body", 1000, &["rust".to_string()]).unwrap();
+    /// writer.commit().unwrap();
+    ///
+    /// let facets = index.document_facets(&HashSet::from([1, 2])).unwrap();
+    /// assert_eq!(facets.len(), 1);
+    /// assert_eq!(facets[&1].collection, "notes");
+    /// assert_eq!(facets[&1].tags, ["rust"]);
+    /// ```
+    pub fn document_facets(
+        &self,
+        doc_num_ids: &std::collections::HashSet<u64>,
+    ) -> Result<std::collections::HashMap<u64, DocumentFacets>> {
+        let mut facets = std::collections::HashMap::new();
+        if doc_num_ids.is_empty() {
+            return Ok(facets);
+        }
+        self.reader.reload()?;
+        let searcher = self.reader.searcher();
+        let mut value = String::new();
+        for segment in searcher.segment_readers() {
+            let fast = segment.fast_fields();
+            let num_ids = fast.u64(fields::DOC_NUM_ID)?;
+            let mtimes = fast.u64(fields::MTIME)?;
+            let collections = fast.str(fields::COLLECTION)?;
+            let tags = fast.str(fields::TAGS)?;
+
+            for doc in segment.doc_ids_alive() {
+                let Some(doc_num_id) = num_ids.first(doc) else {
+                    continue;
+                };
+                if !doc_num_ids.contains(&doc_num_id) {
+                    continue;
+                }
+                let mut collection = String::new();
+                if let Some(column) = &collections
+                    && let Some(ord) = column.term_ords(doc).next()
+                {
+                    column.ord_to_str(ord, &mut collection)?;
+                }
+                let mut doc_tags = Vec::new();
+                if let Some(column) = &tags {
+                    for ord in column.term_ords(doc) {
+                        value.clear();
+                        if column.ord_to_str(ord, &mut value)? {
+                            doc_tags.push(value.clone());
+                        }
+                    }
+                }
+                facets.insert(
+                    doc_num_id,
+                    DocumentFacets {
+                        collection,
+                        mtime: mtimes.first(doc).unwrap_or(0),
+                        tags: doc_tags,
+                    },
+                );
+            }
+        }
+        Ok(facets)
+    }
+
     /// Returns a reference to the Tantivy schema.
     pub fn schema(&self) -> &Schema {
         &self.schema
@@ -565,6 +1443,65 @@ impl std::fmt::Debug for SearchIndex {
     }
 }
 
+/// Lazily built Levenshtein automaton builders for one and two edits,
+/// counting an adjacent swap as one. Building one is far more expensive
+/// than the DFAs it produces, so they are shared across calls.
+static LEVENSHTEIN: [std::sync::OnceLock<LevenshteinAutomatonBuilder>; 2] =
+    [std::sync::OnceLock::new(), std::sync::OnceLock::new()];
+
+/// A Levenshtein DFA as an automaton over the term dictionary.
+struct Levenshtein<'a>(&'a DFA);
+
+impl Automaton for Levenshtein<'_> {
+    type State = u32;
+
+    fn start(&self) -> u32 {
+        self.0.initial_state()
+    }
+
+    fn is_match(&self, state: &u32) -> bool {
+        matches!(self.0.distance(*state), Distance::Exact(_))
+    }
+
+    fn can_match(&self, state: &u32) -> bool {
+        *state != levenshtein_automata::SINK_STATE
+    }
+
+    fn accept(&self, state: &u32, byte: u8) -> u32 {
+        self.0.transition(*state, byte)
+    }
+}
+
+/// Optimal string alignment distance between `a` and `b` (edits with
+/// adjacent swaps counted once), or `None` when it exceeds `max`.
+fn edit_distance(a: &[char], b: &[char], max: usize) -> Option<usize> {
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (j, cell) in rows[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        rows[i][0] = i;
+        for j in 1..=b.len() {
+            let substitution = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + substitution);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = distance;
+        }
+        if rows[i].iter().all(|&distance| distance > max) {
+            return None;
+        }
+    }
+    let distance = rows[a.len()][b.len()];
+    (distance <= max).then_some(distance)
+}
+
 fn extract_text(doc: &TantivyDocument, field: Field) -> String {
     doc.get_first(field)
         .and_then(|v| v.as_str())
@@ -891,4 +1828,199 @@ mod tests {
             assert_eq!(results[0].doc_id, "abc");
         }
     }
+
+    #[test]
+    fn search_expanded_adds_expansion_matches_within_the_collection() {
+        let idx = SearchIndex::open_in_ram().unwrap();
+        let mut writer = idx.writer(15_000_000).unwrap();
+        let docs = [
+            ("a", 1, "notes", "the borrow checker enforces ownership"),
+            ("b", 2, "notes", "the borrow checker rejects dangling refs"),
+            ("c", 3, "docs", "the borrow checker in another collection"),
+        ];
+        for (doc_id, num_id, collection, body) in docs {
+            idx.add_document(
+                &writer,
+                doc_id,
+                num_id,
+                collection,
+                &format!("{doc_id}.md"),
+                "Note",
+                body,
+                1000,
+            )
+            .unwrap();
+        }
+        writer.commit().unwrap();
+
+        let plain = idx
+            .search_expanded("ownership", &[], 0.5, Some("notes"), false, 10)
+            .unwrap();
+        assert_eq!(plain.len(), 1);
+
+        let expansion = vec![("checker".to_string(), 1.0)];
+        let expanded = idx
+            .search_expanded(
+                "ownership",
+                &expansion,
+                0.5,
+                Some("notes"),
+                false,
+                10,
+            )
+            .unwrap();
+        let ids: Vec<&str> =
+            expanded.iter().map(|r| r.doc_id.as_str()).collect();
+        assert_eq!(ids, ["a", "b"]);
+    }
+
+    #[test]
+    fn edit_distance_counts_adjacent_swaps_once() {
+        let chars = |s: &str| s.chars().collect::<Vec<_>>();
+        assert_eq!(edit_distance(&chars("rust"), &chars("rsut"), 2), Some(1));
+        assert_eq!(edit_distance(&chars("rust"), &chars("rusty"), 2), Some(1));
+        assert_eq!(edit_distance(&chars("kitten"), &chars("sitting"), 2), None);
+        assert_eq!(edit_distance(&chars("café"), &chars("cafe"), 1), Some(1));
+    }
+
+    #[test]
+    fn suggest_prefers_frequent_terms_and_keeps_known_ones() {
+        let idx = SearchIndex::open_in_ram().unwrap();
+        let mut writer = idx.writer(15_000_000).unwrap();
+        let docs = [
+            ("a", 1, "Tokio runtime", "The async runtime schedules tasks"),
+            ("b", 2, "Runtime", "Each runtime owns a scheduler"),
+            ("c", 3, "Rare", "Runtmes is a typo that got indexed once"),
+        ];
+        for (doc_id, num_id, title, body) in docs {
+            idx.add_document(
+                &writer,
+                doc_id,
+                num_id,
+                "notes",
+                &format!("{doc_id}.md"),
+                title,
+                body,
+                1000,
+            )
+            .unwrap();
+        }
+        writer.commit().unwrap();
+        idx.search("runtime", 1).unwrap();
+
+        // "runtme" is one edit from both "runtime" (2 docs) and
+        // "runtmes" (1 doc); the frequent one wins.
+        assert_eq!(
+            idx.suggest("Async runtme, tasks").unwrap().as_deref(),
+            Some("Async runtime, tasks")
+        );
+        assert_eq!(idx.suggest("scheduler runtime").unwrap(), None);
+        assert_eq!(idx.suggest("zzzzzzzz").unwrap(), None);
+        assert_eq!(idx.suggest("").unwrap(), None);
+    }
+
+    #[test]
+    fn complete_ranks_leading_matches_first_and_filters_collection() {
+        let idx = SearchIndex::open_in_ram().unwrap();
+        let mut writer = idx.writer(15_000_000).unwrap();
+        let docs = [
+            ("a", 1, "notes", "archive/old-deploy-notes.md", "Old notes"),
+            ("b", 2, "notes", "deploy.md", "Deployment checklist"),
+            ("c", 3, "work", "ops/deploy.md", "Deploying services"),
+            ("d", 4, "notes", "cooking.md", "Bread"),
+        ];
+        for (doc_id, num_id, collection, path, title) in docs {
+            idx.add_document(
+                &writer, doc_id, num_id, collection, path, title, "body", 1000,
+            )
+            .unwrap();
+        }
+        writer.commit().unwrap();
+
+        let paths = |prefix: &str, collection: Option<&str>| -> Vec<String> {
+            idx.complete(prefix, collection, 10)
+                .unwrap()
+                .into_iter()
+                .map(|result| format!("{}:{}", result.collection, result.path))
+                .collect()
+        };
+
+        // Titles and file names starting with "depl" come before the
+        // archived note that only mentions it mid-path.
+        let all = paths("Depl", None);
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[2], "notes:archive/old-deploy-notes.md");
+        assert_eq!(paths("depl", Some("work")), vec!["work:ops/deploy.md"]);
+        assert_eq!(
+            paths("old dep", None),
+            vec!["notes:archive/old-deploy-notes.md"]
+        );
+        assert!(paths("zz", None).is_empty());
+        assert!(paths("  ", None).is_empty());
+        assert!(idx.complete("depl", None, 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn literal_exclusions_keep_a_literal_inside_a_long_word() {
+        let idx = SearchIndex::open_in_ram().unwrap();
+        let mut writer = idx.writer(15_000_000).unwrap();
+        let hash = format!("{}deadbeef{}", "0".repeat(28), "f".repeat(28));
+        assert_eq!(hash.len(), 64);
+        idx.add_document(
+            &writer,
+            "a",
+            1,
+            "notes",
+            "a.md",
+            "A",
+            &format!("commit {hash} landed"),
+            1000,
+        )
+        .unwrap();
+        idx.add_document(
+            &writer,
+            "b",
+            2,
+            "notes",
+            "b.md",
+            "B",
+            "no hashes",
+            2000,
+        )
+        .unwrap();
+        writer.commit().unwrap();
+
+        for literal in ["deadbeef", hash.as_str(), "ffff landed"] {
+            let excluded = idx.literal_exclusions(literal).unwrap().unwrap();
+            assert_eq!(excluded.keys().collect::<Vec<_>>(), [&2], "{literal}");
+        }
+    }
+
+    #[test]
+    fn missing_fields_reports_a_spell_field_that_drops_long_words() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut builder = Schema::builder();
+        for name in [
+            fields::DOC_ID,
+            fields::COLLECTION,
+            fields::PATH,
+            fields::TAGS,
+        ] {
+            builder.add_text_field(name, STRING | STORED);
+        }
+        for name in [fields::DOC_NUM_ID, fields::MTIME] {
+            builder.add_u64_field(name, STORED | FAST);
+        }
+        for name in
+            [fields::TITLE, fields::BODY, fields::SPELL, fields::COMPLETE]
+        {
+            builder.add_text_field(name, TEXT);
+        }
+        Index::create_in_dir(tmp.path(), builder.build()).unwrap();
+
+        assert_eq!(
+            SearchIndex::missing_fields(tmp.path()).unwrap(),
+            [fields::SPELL]
+        );
+    }
 }
@@ -0,0 +1,275 @@
+//! Rebuild of a Tantivy index whose schema predates the current one.
+//!
+//! Tantivy cannot add fields to an existing index, so an index created
+//! before tags, spelling suggestions or completion were indexed lacks
+//! those fields for good. [`SearchIndex::open`] refuses such an index
+//! with [`Error::SearchIndexOutdated`] rather than serving empty facets,
+//! suggestions and completions.
+//!
+//! [`ensure_search_index_migrated`] replaces it instead. `docbert sync`,
+//! `docbert rebuild` and the web and MCP servers call it before opening
+//! the index: every document recorded in `config.db` is re-read from
+//! its collection root, written to a fresh index with the current schema
+//! next to the old one, and the two directories are swapped. Embeddings, PLAID and metadata
+//! are untouched — only the lexical index is derived from the source
+//! files. On a fresh data dir or a current index the call is a no-op.
+//!
+//! [`Error::SearchIndexOutdated`]: crate::Error::SearchIndexOutdated
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use crate::{
+    config_db::ConfigDb,
+    data_dir::DataDir,
+    error::Result,
+    ingestion,
+    tantivy_index::SearchIndex,
+    walker::DiscoveredFile,
+};
+
+/// Writer memory budget for the rebuild, matching the indexing commands.
+const MIGRATION_WRITER_BUDGET: usize = 15_000_000;
+
+/// Rebuild the Tantivy index under `data_dir` from the documents
+/// recorded in `config_db` if its schema predates the current one.
+///
+/// Returns whether a rebuild ran. Documents whose collection or source
+/// file has disappeared are skipped with a warning; the next
+/// `docbert sync` reconciles them as it would after any deletion.
+///
+/// # Errors
+///
+/// Returns [`Error::Config`] if a previous migration left its backup
+/// behind, and any error from reading the sources or writing the new
+/// index. The old index stays in place on failure.
+///
+/// [`Error::Config`]: crate::Error::Config
+pub fn ensure_search_index_migrated(
+    data_dir: &DataDir,
+    config_db: &ConfigDb,
+) -> Result<bool> {
+    let dir = data_dir.tantivy_dir()?;
+    let missing = SearchIndex::missing_fields(&dir)?;
+    if missing.is_empty() {
+        return Ok(false);
+    }
+
+    let started = Instant::now();
+    tracing::info!(
+        path = %dir.display(),
+        missing = %missing.join(", "),
+        "tantivy migration: index predates the current schema, rebuilding",
+    );
+
+    let backup_dir = sibling(&dir, "pre-migration");
+    let tmp_dir = sibling(&dir, "migrating");
+    if backup_dir.exists() {
+        return Err(crate::Error::Config(format!(
+            "tantivy migration: a backup already exists at {}; \
+             move or remove it before retrying",
+            backup_dir.display()
+        )));
+    }
+    if tmp_dir.exists() {
+        // Left over from an interrupted run; the swap never happened.
+        std::fs::remove_dir_all(&tmp_dir)?;
+    }
+
+    let indexed = match write_current_index(&tmp_dir, config_db) {
+        Ok(indexed) => indexed,
+        Err(err) => {
+            let _ = std::fs::remove_dir_all(&tmp_dir);
+            return Err(err);
+        }
+    };
+
+    std::fs::rename(&dir, &backup_dir)?;
+    if let Err(err) = std::fs::rename(&tmp_dir, &dir) {
+        let _ = std::fs::rename(&backup_dir, &dir);
+        return Err(err.into());
+    }
+    // The old index is derived data and is now fully replaced.
+    std::fs::remove_dir_all(&backup_dir)?;
+
+    tracing::info!(
+        path = %dir.display(),
+        documents = indexed,
+        elapsed_ms = started.elapsed().as_millis() as u64,
+        "tantivy migration: completed",
+    );
+    Ok(true)
+}
+
+/// `<dir>.<suffix>` next to `dir`.
+fn sibling(dir: &Path, suffix: &str) -> PathBuf {
+    let mut name = dir.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(suffix);
+    dir.with_file_name(name)
+}
+
+/// Index every document in `config_db` into a new index at `dir`,
+/// returning how many were written.
+fn write_current_index(dir: &Path, config_db: &ConfigDb) -> Result<usize> {
+    let index = SearchIndex::open(dir)?;
+    let roots: HashMap<String, String> =
+        config_db.list_collections()?.into_iter().collect();
+
+    let mut files: HashMap<String, Vec<DiscoveredFile>> = HashMap::new();
+    for (_, meta) in config_db.list_all_document_metadata_typed()? {
+        let Some(root) = roots.get(&meta.collection) else {
+            tracing::warn!(
+                collection = %meta.collection,
+                path = %meta.relative_path,
+                "tantivy migration: skipped document of unknown collection",
+            );
+            continue;
+        };
+        files
+            .entry(meta.collection)
+            .or_default()
+            .push(DiscoveredFile {
+                absolute_path: Path::new(root).join(&meta.relative_path),
+                relative_path: PathBuf::from(meta.relative_path),
+                mtime: meta.mtime,
+            });
+    }
+
+    let mut writer = index.writer(MIGRATION_WRITER_BUDGET)?;
+    let mut indexed = 0;
+    for (collection, files) in &files {
+        let loaded = ingestion::load_documents(collection, files);
+        for failure in &loaded.failures {
+            tracing::warn!(
+                %collection,
+                path = %failure.file.relative_path.display(),
+                error = %failure.error,
+                "tantivy migration: skipped unreadable document",
+            );
+        }
+        indexed += ingestion::ingest_prepared_documents(
+            &index,
+            &mut writer,
+            collection,
+            &loaded.documents,
+        )?;
+    }
+    writer.commit()?;
+    Ok(indexed)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use tantivy::{
+        Index,
+        doc,
+        schema::{FAST, STORED, STRING, Schema, TEXT},
+    };
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::{incremental, walker};
+
+    /// Create the index `docbert` wrote before tags, spelling and
+    /// completion fields existed, holding one document.
+    fn write_legacy_index(dir: &Path) {
+        let mut builder = Schema::builder();
+        let doc_id = builder.add_text_field("doc_id", STRING | STORED);
+        let doc_num_id = builder.add_u64_field("doc_num_id", STORED | FAST);
+        let collection =
+            builder.add_text_field("collection", STRING | STORED | FAST);
+        let path = builder.add_text_field("path", STRING | STORED);
+        let title = builder.add_text_field("title", TEXT | STORED);
+        let body = builder.add_text_field("body", TEXT);
+        let mtime = builder.add_u64_field("mtime", STORED | FAST);
+        std::fs::create_dir_all(dir).unwrap();
+        let index = Index::create_in_dir(dir, builder.build()).unwrap();
+        let mut writer = index.writer(15_000_000).unwrap();
+        writer
+            .add_document(doc!(
+                doc_id => "stale",
+                doc_num_id => 99u64,
+                collection => "notes",
+                path => "stale.md",
+                title => "Stale",
+                body => "stale body",
+                mtime => 1u64,
+            ))
+            .unwrap();
+        writer.commit().unwrap();
+    }
+
+    #[test]
+    fn open_refuses_an_index_with_an_outdated_schema() {
+        let tmp = tempdir().unwrap();
+        let dir = tmp.path().join("tantivy");
+        write_legacy_index(&dir);
+
+        match SearchIndex::open(&dir) {
+            Err(crate::Error::SearchIndexOutdated { missing, .. }) => {
+                assert_eq!(missing, ["tags", "spell", "complete"]);
+            }
+            other => panic!("expected SearchIndexOutdated, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn migration_rebuilds_an_outdated_index_from_the_collection_sources() {
+        let tmp = tempdir().unwrap();
+        let data_dir = DataDir::new(tmp.path().join("data"));
+        let config_db = ConfigDb::open(&data_dir.config_db()).unwrap();
+        let root = tmp.path().join("notes");
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(
+            root.join("rust.md"),
+            "---\ntags: [lang]\n---\n# Rust Guide\n\nOwnership and borrowing.",
+        )
+        .unwrap();
+        config_db
+            .set_collection("notes", root.to_str().unwrap())
+            .unwrap();
+        let files = walker::discover_files(&root).unwrap();
+        incremental::batch_store_metadata(&config_db, "notes", &files).unwrap();
+        write_legacy_index(&data_dir.tantivy_dir().unwrap());
+
+        assert!(ensure_search_index_migrated(&data_dir, &config_db).unwrap());
+        assert!(!ensure_search_index_migrated(&data_dir, &config_db).unwrap());
+
+        let index =
+            SearchIndex::open(&data_dir.tantivy_dir().unwrap()).unwrap();
+        let results = index.search("ownership", 10).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].title, "Rust Guide");
+        // The stale entry had no source recorded in config.db.
+        assert!(index.search("stale", 10).unwrap().is_empty());
+        let facets = index
+            .document_facets(&HashSet::from([results[0].doc_num_id]))
+            .unwrap();
+        assert_eq!(facets[&results[0].doc_num_id].tags, ["lang"]);
+        assert_eq!(
+            index.complete("rus", None, 5).unwrap()[0].title,
+            "Rust Guide",
+        );
+        assert!(
+            !sibling(&data_dir.tantivy_dir().unwrap(), "pre-migration")
+                .exists()
+        );
+    }
+
+    #[test]
+    fn migration_is_a_no_op_for_a_current_or_missing_index() {
+        let tmp = tempdir().unwrap();
+        let data_dir = DataDir::new(tmp.path());
+        let config_db = ConfigDb::open(&data_dir.config_db()).unwrap();
+        assert!(!ensure_search_index_migrated(&data_dir, &config_db).unwrap());
+
+        SearchIndex::open(&data_dir.tantivy_dir().unwrap()).unwrap();
+        assert!(!ensure_search_index_migrated(&data_dir, &config_db).unwrap());
+    }
+}
@@ -41,6 +41,74 @@ pub fn strip_yaml_frontmatter(text: &str) -> &str {
     text
 }
 
+/// Read the `tags` key of a leading YAML frontmatter block.
+///
+/// Accepts a flow list (`tags: [a, b]`), a comma-separated scalar
+/// (`tags: a, b`) and a block list (`tags:` followed by `- a` lines).
+/// Tags are trimmed, unquoted, and deduplicated in order; documents
+/// without frontmatter or a `tags` key have none.
+///
+/// # Examples
+///
+/// ```
+/// use docbert_core::text::yaml_frontmatter_tags;
+///
+/// let doc = "---\ntitle: Notes\ntags: [rust, \"storage\"]\n---\nBody";
+/// assert_eq!(yaml_frontmatter_tags(doc), ["rust", "storage"]);
+///
+/// let doc = "---\ntags:\n  - rust\n  - cli\n---\nBody";
+/// assert_eq!(yaml_frontmatter_tags(doc), ["rust", "cli"]);
+/// ```
+pub fn yaml_frontmatter_tags(text: &str) -> Vec<String> {
+    let stripped = strip_yaml_frontmatter(text);
+    if stripped.len() == text.len() {
+        return Vec::new();
+    }
+    let block = &text[..text.len() - stripped.len()];
+
+    let mut raw = Vec::new();
+    let mut in_block_list = false;
+    for line in block.lines().skip(1) {
+        let trimmed = line.trim();
+        if trimmed == "---" || trimmed == "..." {
+            break;
+        }
+        if in_block_list {
+            if let Some(item) = trimmed.strip_prefix("- ") {
+                raw.push(item.to_string());
+                continue;
+            }
+            if trimmed.is_empty() {
+                continue;
+            }
+            break;
+        }
+        let Some(value) = line.strip_prefix("tags:") else {
+            continue;
+        };
+        let value = value.trim();
+        if value.is_empty() {
+            in_block_list = true;
+            continue;
+        }
+        let value = value
+            .strip_prefix('[')
+            .and_then(|v| v.strip_suffix(']'))
+            .unwrap_or(value);
+        raw.extend(value.split(',').map(str::to_string));
+        break;
+    }
+
+    let mut tags: Vec<String> = Vec::new();
+    for tag in raw {
+        let tag = tag.trim().trim_matches(['"', '\'']).trim();
+        if !tag.is_empty() && !tags.iter().any(|t| t == tag) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
+
 /// Add line numbers to each line of text.
 ///
 /// `start_line` is the number assigned to the first line. It is 1-indexed.
@@ -290,6 +358,55 @@ pub fn apply_byte_range(
     slice
 }
 
+/// Borrow the `[start_byte, start_byte + byte_len)` slice of `text`.
+///
+/// Unlike [`apply_byte_range`], this never appends a footer, which makes
+/// it suitable for feeding a stored chunk range back into a model. Both
+/// ends are clamped to the text and rounded down to a character
+/// boundary.
+///
+/// # Examples
+///
+/// ```
+/// use docbert_core::text::byte_slice;
+///
+/// assert_eq!(byte_slice("hello world", 6, 5), "world");
+/// assert_eq!(byte_slice("hello", 3, 100), "lo");
+/// assert_eq!(byte_slice("hello", 10, 2), "");
+/// ```
+pub fn byte_slice(text: &str, start_byte: u64, byte_len: u64) -> &str {
+    &text[byte_range(text, start_byte, byte_len)]
+}
+
+/// Resolve the byte range [`byte_slice`] borrows, after clamping and
+/// char-boundary rounding.
+///
+/// Useful when positions inside the slice have to be mapped back onto
+/// the full text.
+///
+/// # Examples
+///
+/// ```
+/// use docbert_core::text::byte_range;
+///
+/// assert_eq!(byte_range("hello world", 6, 5), 6..11);
+/// assert_eq!(byte_range("héllo", 2, 2), 1..4);
+/// assert_eq!(byte_range("hello", 10, 2), 5..5);
+/// ```
+pub fn byte_range(
+    text: &str,
+    start_byte: u64,
+    byte_len: u64,
+) -> std::ops::Range<usize> {
+    let total = text.len();
+    let start = floor_char_boundary(text, (start_byte as usize).min(total));
+    let end = floor_char_boundary(
+        text,
+        (start_byte.saturating_add(byte_len) as usize).min(total),
+    );
+    start..end.max(start)
+}
+
 fn floor_char_boundary(text: &str, mut byte: usize) -> usize {
     while byte > 0 && !text.is_char_boundary(byte) {
         byte -= 1;
@@ -322,6 +439,14 @@ mod tests {
         assert_eq!(strip_yaml_frontmatter(text), text);
     }
 
+    #[test]
+    fn yaml_frontmatter_tags_reads_scalar_and_ignores_other_keys() {
+        let text = "---\ntitle: Hi\ntags: rust, 'cli', rust\nauthor: me\n---\n";
+        assert_eq!(yaml_frontmatter_tags(text), ["rust", "cli"]);
+        assert!(yaml_frontmatter_tags("---\ntitle: Hi\n---\n").is_empty());
+        assert!(yaml_frontmatter_tags("tags: [rust]\n").is_empty());
+    }
+
     #[test]
     fn strip_yaml_frontmatter_ignores_unterminated_block() {
         let text = "---\ntitle: Hello\n# Heading";
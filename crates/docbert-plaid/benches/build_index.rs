@@ -23,7 +23,10 @@ use criterion::{
     criterion_group,
     criterion_main,
 };
-use docbert_plaid::index::{IndexParams, build_index};
+use docbert_plaid::{
+    index::{IndexParams, build_index},
+    kmeans::KMeansOptions,
+};
 
 const DIM: usize = 128;
 
@@ -56,6 +59,7 @@ fn bench_build_index(c: &mut Criterion) {
                             nbits: 2,
                             k_centroids: k,
                             max_kmeans_iters: iters,
+                            kmeans: KMeansOptions::default(),
                         },
                     )
                 });
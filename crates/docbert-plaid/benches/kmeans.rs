@@ -11,6 +11,10 @@
 //!   directly to the millions-of-tokens regime where production
 //!   indexes live.
 //!
+//! The `kmeans/init`, `kmeans/training` and `kmeans/sample_tokens`
+//! groups compare the [`KMeansOptions`] a build can select: seeding
+//! strategy, Lloyd vs mini-batch training, and training-sample size.
+//!
 //! Use `cargo bench -p docbert-plaid --bench kmeans` to run.
 
 #[path = "shared.rs"]
@@ -28,7 +32,14 @@ use criterion::{
 };
 use docbert_plaid::{
     device::default_device,
-    kmeans::{assign_points, fit, update_centroids},
+    kmeans::{
+        KMeansInit,
+        KMeansOptions,
+        assign_points,
+        fit,
+        fit_with_options,
+        update_centroids,
+    },
 };
 
 const DIM: usize = 128;
@@ -105,10 +116,95 @@ fn bench_fit(c: &mut Criterion) {
     group.finish();
 }
 
+fn bench_init(c: &mut Criterion) {
+    let mut group = c.benchmark_group("kmeans/init");
+    // Zero iterations: the sample and the seeds are all that's timed.
+    let n = 10_000usize;
+    let points = shared::random_unit_vectors(0x1A17, n, DIM);
+    group.throughput(Throughput::Elements(n as u64));
+    group.sample_size(10);
+    for init in [
+        KMeansInit::Random,
+        KMeansInit::PlusPlus,
+        KMeansInit::Parallel,
+    ] {
+        let options = KMeansOptions {
+            init,
+            ..KMeansOptions::default()
+        };
+        group.bench_function(format!("{init:?}"), |b| {
+            b.iter(|| fit_with_options(black_box(&points), K, DIM, 0, options));
+        });
+    }
+    group.finish();
+}
+
+fn bench_training(c: &mut Criterion) {
+    let mut group = c.benchmark_group("kmeans/training");
+    let (n, max_iters) = (10_000usize, 10usize);
+    let points = shared::random_unit_vectors(0x7EA1, n, DIM);
+    group.throughput(Throughput::Elements(n as u64 * max_iters as u64));
+    group.sample_size(10);
+    let variants = [
+        ("lloyd", None, None),
+        ("lloyd,tol=1e-3", None, Some(1e-3)),
+        ("mini_batch=1024", Some(1_024), None),
+        ("mini_batch=1024,tol=1e-3", Some(1_024), Some(1e-3)),
+    ];
+    for (name, mini_batch, tolerance) in variants {
+        let options = KMeansOptions {
+            mini_batch,
+            tolerance,
+            ..KMeansOptions::default()
+        };
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                fit_with_options(black_box(&points), K, DIM, max_iters, options)
+            });
+        });
+    }
+    group.finish();
+}
+
+fn bench_sample_tokens(c: &mut Criterion) {
+    let mut group = c.benchmark_group("kmeans/sample_tokens");
+    // Training cost follows the sample, not the 50k-token corpus.
+    let n = 50_000usize;
+    let max_iters = 5usize;
+    let points = shared::random_unit_vectors(0x5A3B, n, DIM);
+    group.sample_size(10);
+    for sample in [K * 8, K * 32, n] {
+        let options = KMeansOptions {
+            sample_tokens: Some(sample),
+            ..KMeansOptions::default()
+        };
+        group.throughput(Throughput::Elements(sample as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(sample),
+            &sample,
+            |b, _| {
+                b.iter(|| {
+                    fit_with_options(
+                        black_box(&points),
+                        K,
+                        DIM,
+                        max_iters,
+                        options,
+                    )
+                });
+            },
+        );
+    }
+    group.finish();
+}
+
 criterion_group!(
     benches,
     bench_assign_points,
     bench_update_centroids,
     bench_fit,
+    bench_init,
+    bench_training,
+    bench_sample_tokens,
 );
 criterion_main!(benches);
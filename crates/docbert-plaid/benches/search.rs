@@ -4,6 +4,10 @@
 //! types a query. The probe step (centroid distances + IVF gather)
 //! tends to be cheap; the dominant work is decoding candidate doc
 //! tokens and the per-doc MaxSim matmul.
+//!
+//! The `search/simd_*` groups run the CPU kernels behind that work on
+//! every instruction set this machine supports, scalar included, so
+//! the vectorised speed-up is measured against its own baseline.
 
 #[path = "shared.rs"]
 mod shared;
@@ -12,8 +16,11 @@ use std::hint::black_box;
 
 use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
 use docbert_plaid::{
+    codec::DecodeTable,
     index::{Index, IndexParams, build_index},
+    kmeans::KMeansOptions,
     search::{SearchParams, search, top_n_centroids},
+    simd::{Kernel, MaxSim},
 };
 
 const DIM: usize = 128;
@@ -30,6 +37,7 @@ fn make_index(seed: u64, n_docs: usize, tokens_per_doc: usize) -> Index {
             nbits: 2,
             k_centroids: k.max(2),
             max_kmeans_iters: 5,
+            kmeans: KMeansOptions::default(),
         },
     )
     .unwrap()
@@ -161,10 +169,93 @@ fn bench_plaid_stages(c: &mut Criterion) {
     group.finish();
 }
 
+fn bench_simd_dot(c: &mut Criterion) {
+    let mut group = c.benchmark_group("search/simd_dot");
+    let a = shared::random_unit_vectors(0xA, 1, DIM);
+    let b = shared::random_unit_vectors(0xB, 1, DIM);
+    for kernel in Kernel::available() {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(kernel.name()),
+            &kernel,
+            |bench, k| {
+                bench.iter(|| k.dot(black_box(&a), black_box(&b)));
+            },
+        );
+    }
+    group.finish();
+}
+
+fn bench_simd_decode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("search/simd_decode");
+    let index = make_index(0x1DEC, 100, 50);
+    let table = DecodeTable::new(&index.codec);
+    let centroid_ids = index.doc_centroid_ids(0);
+    let residuals = index.doc_residual_bytes(0);
+    let packed_bytes = index.codec.packed_bytes();
+    let mut out = vec![0.0f32; DIM];
+    for kernel in Kernel::available() {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(kernel.name()),
+            &kernel,
+            |bench, k| {
+                bench.iter(|| {
+                    for (&cid, codes) in centroid_ids
+                        .iter()
+                        .zip(residuals.chunks_exact(packed_bytes))
+                    {
+                        black_box(k.decode_into(
+                            &index.codec,
+                            &table,
+                            cid,
+                            codes,
+                            &mut out,
+                        ));
+                    }
+                });
+            },
+        );
+    }
+    group.finish();
+}
+
+/// Fused decode + MaxSim over 256 candidates of 100 tokens, the
+/// paper's `ndocs` for k=10 and the CPU cost of a typical query's
+/// final stage.
+fn bench_simd_maxsim(c: &mut Criterion) {
+    let mut group = c.benchmark_group("search/simd_maxsim");
+    let index = make_index(0x1DEC, 256, 100);
+    let table = DecodeTable::new(&index.codec);
+    let query = shared::random_unit_vectors(0x9, QUERY_TOKENS, DIM);
+    group.sample_size(20);
+    for kernel in Kernel::available() {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(kernel.name()),
+            &kernel,
+            |bench, &k| {
+                let mut maxsim = MaxSim::new(k, &index.codec, &table, &query);
+                bench.iter(|| {
+                    (0..index.num_documents())
+                        .map(|doc| {
+                            maxsim.score(
+                                index.doc_centroid_ids(doc),
+                                index.doc_residual_bytes(doc),
+                            )
+                        })
+                        .sum::<f32>()
+                });
+            },
+        );
+    }
+    group.finish();
+}
+
 criterion_group!(
     benches,
     bench_top_n_centroids,
     bench_search,
     bench_plaid_stages,
+    bench_simd_dot,
+    bench_simd_decode,
+    bench_simd_maxsim,
 );
 criterion_main!(benches);
@@ -8,6 +8,8 @@
 //! type so the primitives stay trivial to test, branch-predict, and
 //! vectorize. Higher layers bridge the gap to candle tensors.
 
+use crate::simd::Kernel;
+
 /// Dot product between two equal-length vectors.
 ///
 /// This is the per-token similarity ColBERT and PLAID use to build
@@ -16,6 +18,9 @@
 /// which is usually what you want after residual decoding can pull a
 /// token slightly off the unit sphere.
 ///
+/// Runs on the fastest [`Kernel`] this CPU supports, so the result can
+/// differ from a left-to-right scalar sum by float rounding.
+///
 /// # Panics
 ///
 /// Panics when `a.len() != b.len()`.
@@ -30,18 +35,7 @@
 /// assert_eq!(dot(&[1.0, 0.0], &[0.0, 1.0]), 0.0);
 /// ```
 pub fn dot(a: &[f32], b: &[f32]) -> f32 {
-    assert_eq!(
-        a.len(),
-        b.len(),
-        "dot requires equal-length vectors (got {} and {})",
-        a.len(),
-        b.len(),
-    );
-    let mut sum = 0.0f32;
-    for (x, y) in a.iter().zip(b.iter()) {
-        sum += x * y;
-    }
-    sum
+    Kernel::detect().dot(a, b)
 }
 
 /// Squared Euclidean distance between two equal-length vectors.
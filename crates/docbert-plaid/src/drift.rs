@@ -0,0 +1,177 @@
+//! Measuring how far an index's frozen codec has drifted from the corpus.
+//!
+//! Incremental updates ([`crate::update`], [`crate::segment`]) encode new
+//! documents against the codec trained when the index was built. That
+//! only works while new tokens look like the training tokens. Two cheap
+//! signals show when they stop doing so:
+//!
+//! - [`sample_reconstruction_error`]: the mean squared error of encoding
+//!   and decoding a sample of tokens. Compared between the training
+//!   tokens and tokens added later, it grows when new content lands far
+//!   from every centroid.
+//! - [`assignment_imbalance`]: how much more than its fair share of
+//!   tokens the fullest centroid holds. It grows when new content piles
+//!   into a few centroids, which makes those posting lists long and
+//!   their residuals coarse.
+//!
+//! Both are relative measures: callers compare the value after updates
+//! with the value right after the build.
+
+use crate::{Result, codec::ResidualCodec};
+
+/// Default number of tokens [`sample_reconstruction_error`] encodes.
+pub const DEFAULT_SAMPLE_TOKENS: usize = 2048;
+
+/// Mean reconstruction error over a sample of tokens.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ErrorSample {
+    /// Number of tokens the mean was taken over.
+    pub tokens: usize,
+    /// Mean squared L2 reconstruction error per token.
+    pub mean_error: f32,
+}
+
+impl ErrorSample {
+    /// Combine two samples into the mean over both, weighted by their
+    /// token counts.
+    pub fn merge(self, other: ErrorSample) -> ErrorSample {
+        let tokens = self.tokens + other.tokens;
+        if tokens == 0 {
+            return ErrorSample {
+                tokens: 0,
+                mean_error: 0.0,
+            };
+        }
+        let total = self.mean_error as f64 * self.tokens as f64
+            + other.mean_error as f64 * other.tokens as f64;
+        ErrorSample {
+            tokens,
+            mean_error: (total / tokens as f64) as f32,
+        }
+    }
+}
+
+/// Mean [`ResidualCodec::reconstruction_error`] over up to `max_tokens`
+/// tokens of the flat `[n_tokens * dim]` matrix `tokens`, picked evenly
+/// across it.
+///
+/// Returns `None` when there are no tokens to sample.
+///
+/// # Errors
+///
+/// Returns [`PlaidError::InvalidCodec`] if `codec` fails its shape
+/// invariants.
+///
+/// # Panics
+///
+/// Panics if `tokens.len()` is not a multiple of the codec's `dim`.
+///
+/// [`PlaidError::InvalidCodec`]: crate::PlaidError::InvalidCodec
+pub fn sample_reconstruction_error(
+    codec: &ResidualCodec,
+    tokens: &[f32],
+    max_tokens: usize,
+) -> Result<Option<ErrorSample>> {
+    let dim = codec.dim;
+    assert!(
+        dim > 0 && tokens.len().is_multiple_of(dim),
+        "sample_reconstruction_error: {} values is not a whole number of \
+         {dim}-dim tokens",
+        tokens.len(),
+    );
+    let n_tokens = tokens.len() / dim;
+    let take = n_tokens.min(max_tokens);
+    if take == 0 {
+        return Ok(None);
+    }
+    let mut total = 0.0f64;
+    for i in 0..take {
+        let row = i * n_tokens / take;
+        let token = &tokens[row * dim..(row + 1) * dim];
+        total += codec.reconstruction_error(token)? as f64;
+    }
+    Ok(Some(ErrorSample {
+        tokens: take,
+        mean_error: (total / take as f64) as f32,
+    }))
+}
+
+/// Ratio of the fullest centroid's token count to the mean count.
+///
+/// `1.0` means every centroid holds the same number of tokens; a value
+/// of `8.0` means one centroid holds eight times its fair share. Returns
+/// `0.0` when there are no tokens.
+pub fn assignment_imbalance(token_counts: &[usize]) -> f32 {
+    let total: usize = token_counts.iter().sum();
+    let max = token_counts.iter().copied().max().unwrap_or(0);
+    if total == 0 {
+        return 0.0;
+    }
+    max as f32 * token_counts.len() as f32 / total as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn codec() -> ResidualCodec {
+        ResidualCodec {
+            nbits: 1,
+            dim: 1,
+            centroids: vec![0.0, 10.0],
+            bucket_cutoffs: vec![0.0],
+            bucket_weights: vec![-0.5, 0.5],
+        }
+    }
+
+    #[test]
+    fn sample_error_grows_for_tokens_far_from_every_centroid() {
+        let codec = codec();
+        let near = sample_reconstruction_error(&codec, &[0.5, 9.5, -0.5], 8)
+            .unwrap()
+            .unwrap();
+        assert_eq!(near.tokens, 3);
+        assert_eq!(near.mean_error, 0.0);
+
+        let far = sample_reconstruction_error(&codec, &[30.0, -20.0], 8)
+            .unwrap()
+            .unwrap();
+        assert!(far.mean_error > 100.0, "{far:?}");
+        assert!(
+            sample_reconstruction_error(&codec, &[], 8)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn sample_error_caps_the_number_of_tokens() {
+        let tokens: Vec<f32> = (0..100).map(|i| i as f32 / 10.0).collect();
+        let sample = sample_reconstruction_error(&codec(), &tokens, 10)
+            .unwrap()
+            .unwrap();
+        assert_eq!(sample.tokens, 10);
+    }
+
+    #[test]
+    fn merge_weights_means_by_token_count() {
+        let a = ErrorSample {
+            tokens: 3,
+            mean_error: 1.0,
+        };
+        let b = ErrorSample {
+            tokens: 1,
+            mean_error: 5.0,
+        };
+        assert_eq!(a.merge(b).mean_error, 2.0);
+        assert_eq!(a.merge(b).tokens, 4);
+    }
+
+    #[test]
+    fn imbalance_is_one_when_balanced_and_grows_with_skew() {
+        assert_eq!(assignment_imbalance(&[5, 5, 5, 5]), 1.0);
+        assert_eq!(assignment_imbalance(&[8, 0, 0, 0]), 4.0);
+        assert_eq!(assignment_imbalance(&[0, 0]), 0.0);
+        assert_eq!(assignment_imbalance(&[]), 0.0);
+    }
+}
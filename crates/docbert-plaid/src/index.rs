@@ -14,10 +14,13 @@
 //! and the query-time search path will be added on top in later
 //! TDD cycles.
 
+use std::sync::Arc;
+
 use crate::{
     Result,
     codec::{EncodedVector, ResidualCodec, train_quantizer},
-    kmeans::{assign_points, fit},
+    kmeans::{KMeansOptions, assign_points, fit_with_options},
+    storage::Buffer,
 };
 
 /// Cap on tokens used to train the residual quantizer.
@@ -44,36 +47,60 @@ const MAX_QUANTIZER_TRAINING_TOKENS: usize = 65_536;
 /// document candidates: find the centroids with the highest dot-product
 /// against the query token, then gather every document listed under
 /// those centroids.
+///
+/// The lists are stored back to back in compressed-sparse-row form:
+/// centroid `c`'s postings are `postings[offsets[c]..offsets[c + 1]]`.
+/// Two flat buffers persist as two file sections and can be served
+/// straight from a memory map, where a `Vec<Vec<u32>>` would have to be
+/// rebuilt on every load.
 #[derive(Debug, Clone, Default)]
 pub struct InvertedFile {
-    /// `lists[c]` holds the sorted, deduplicated `doc_idx`s of every
-    /// document with at least one token assigned to centroid `c`.
-    pub lists: Vec<Vec<u32>>,
+    /// Cumulative posting counts; length `num_centroids + 1` (or empty
+    /// for an IVF with no centroids).
+    pub offsets: Buffer<usize>,
+    /// Every centroid's sorted, deduplicated `doc_idx`s, concatenated
+    /// in centroid order.
+    pub postings: Buffer<u32>,
 }
 
 impl InvertedFile {
+    /// Flatten one posting list per centroid into the CSR layout.
+    pub fn from_lists(lists: Vec<Vec<u32>>) -> Self {
+        let mut offsets = Vec::with_capacity(lists.len() + 1);
+        offsets.push(0usize);
+        let mut postings = Vec::with_capacity(lists.iter().map(Vec::len).sum());
+        for list in lists {
+            postings.extend_from_slice(&list);
+            offsets.push(postings.len());
+        }
+        Self {
+            offsets: offsets.into(),
+            postings: postings.into(),
+        }
+    }
+
     /// Total number of centroids the IVF spans.
     pub fn num_centroids(&self) -> usize {
-        self.lists.len()
+        self.offsets.len().saturating_sub(1)
     }
 
     /// Document indices currently associated with `centroid_id`, or an
     /// empty slice if the centroid is out of range. Entries are sorted
     /// ascending and contain no duplicates.
     pub fn docs_for_centroid(&self, centroid_id: usize) -> &[u32] {
-        self.lists
-            .get(centroid_id)
-            .map(Vec::as_slice)
-            .unwrap_or(&[])
+        if centroid_id >= self.num_centroids() {
+            return &[];
+        }
+        &self.postings[self.offsets[centroid_id]..self.offsets[centroid_id + 1]]
     }
 
     /// Total number of (centroid, doc) postings across every list.
     ///
-    /// This is the sum of `lists[c].len()` over all centroids `c`. It
-    /// is at most `num_centroids * num_documents` and at least equal
-    /// to the number of documents that contain any tokens at all.
+    /// This is the sum of every centroid's list length. It is at most
+    /// `num_centroids * num_documents` and at least equal to the number
+    /// of documents that contain any tokens at all.
     pub fn total_doc_postings(&self) -> usize {
-        self.lists.iter().map(Vec::len).sum()
+        self.postings.len()
     }
 }
 
@@ -107,6 +134,27 @@ pub struct IndexParams {
     pub k_centroids: usize,
     /// Maximum iterations for k-means clustering.
     pub max_kmeans_iters: usize,
+    /// Seeding, training and sampling choices for k-means.
+    /// [`KMeansOptions::default`] is the classic PLAID recipe. Only
+    /// the build reads these, so they aren't persisted and a loaded
+    /// index reports the default.
+    pub kmeans: KMeansOptions,
+}
+
+/// Number of coarse centroids to train for a corpus of `total_tokens`
+/// token embeddings, using fast-plaid's heuristic: the largest power of
+/// two at or below `16 * sqrt(total_tokens)`.
+///
+/// Capped at `total_tokens` so k-means never asks for more centroids
+/// than it has points, and at least `1`.
+pub fn auto_k_centroids(total_tokens: usize) -> usize {
+    let target = 16.0 * (total_tokens as f64).sqrt();
+    let k = if target < 1.0 {
+        1
+    } else {
+        1usize << target.log2().floor() as u32
+    };
+    k.min(total_tokens).max(1)
 }
 
 /// A fully-built PLAID index over a corpus of multi-vector embeddings.
@@ -130,20 +178,27 @@ pub struct IndexParams {
 /// Callers that want the per-token view use [`Index::doc_centroid_ids`]
 /// / [`Index::doc_residual_bytes`] — slices into the flat buffers with
 /// no allocation.
+///
+/// Each flat buffer is a [`Buffer`]: owned after a build or update,
+/// borrowed from a memory map after [`crate::persistence::load`] opens
+/// a version-3 file.
 #[derive(Debug, Clone)]
 pub struct Index {
     pub params: IndexParams,
-    pub codec: ResidualCodec,
-    pub doc_ids: Vec<u64>,
+    /// Shared so the segments of a
+    /// [`SegmentedIndex`](crate::segment::SegmentedIndex) can all point
+    /// at one copy.
+    pub codec: Arc<ResidualCodec>,
+    pub doc_ids: Buffer<u64>,
     /// Flat `[total_tokens]` vector of per-token centroid indices.
-    pub doc_centroid_ids: Vec<u32>,
+    pub doc_centroid_ids: Buffer<u32>,
     /// Flat `[total_tokens * packed_bytes_per_token]` residual bytes,
     /// row-major — each `packed_bytes_per_token`-long slice is one
     /// token's packed residual.
-    pub doc_residual_bytes: Vec<u8>,
+    pub doc_residual_bytes: Buffer<u8>,
     /// Cumulative per-document token counts; length `num_docs + 1`,
     /// `doc_offsets[i + 1] - doc_offsets[i]` = `n_tokens` for doc `i`.
-    pub doc_offsets: Vec<usize>,
+    pub doc_offsets: Buffer<usize>,
     /// Centroid → tokens inverted file used for candidate generation.
     pub ivf: InvertedFile,
 }
@@ -161,7 +216,7 @@ impl Index {
     /// per-token buffers.
     pub fn from_encoded_docs(
         params: IndexParams,
-        codec: ResidualCodec,
+        codec: Arc<ResidualCodec>,
         doc_ids: Vec<u64>,
         doc_tokens: Vec<Vec<EncodedVector>>,
         ivf: InvertedFile,
@@ -186,10 +241,10 @@ impl Index {
         Self {
             params,
             codec,
-            doc_ids,
-            doc_centroid_ids,
-            doc_residual_bytes,
-            doc_offsets,
+            doc_ids: doc_ids.into(),
+            doc_centroid_ids: doc_centroid_ids.into(),
+            doc_residual_bytes: doc_residual_bytes.into(),
+            doc_offsets: doc_offsets.into(),
             ivf,
         }
     }
@@ -395,15 +450,18 @@ pub fn build_index_from_pool(
         },
     );
 
-    // 1. Train coarse centroids with k-means. `fit` subsamples to
-    //    `k * MAX_POINTS_PER_CENTROID` rows and uploads only that
-    //    subsample, so peak VRAM here is bounded regardless of pool
-    //    size or `dim`.
-    let centroids = fit(
+    // 1. Train coarse centroids with k-means. `fit_with_options`
+    //    subsamples to `k * MAX_POINTS_PER_CENTROID` rows (or the
+    //    configured sample size) and uploads at most that subsample,
+    //    so peak VRAM here is bounded regardless of pool size or
+    //    `dim`. Tokens outside the sample are assigned once, when the
+    //    codec encodes the pool below.
+    let centroids = fit_with_options(
         &pool,
         params.k_centroids,
         params.dim,
         params.max_kmeans_iters.max(1),
+        params.kmeans,
     )?;
 
     // 2. Residuals for quantizer training. We only need enough samples
@@ -488,11 +546,11 @@ pub fn build_index_from_pool(
 
     Ok(Index {
         params,
-        codec,
-        doc_ids,
-        doc_centroid_ids,
-        doc_residual_bytes,
-        doc_offsets,
+        codec: Arc::new(codec),
+        doc_ids: doc_ids.into(),
+        doc_centroid_ids: doc_centroid_ids.into(),
+        doc_residual_bytes: doc_residual_bytes.into(),
+        doc_offsets: doc_offsets.into(),
         ivf,
     })
 }
@@ -521,7 +579,7 @@ pub(crate) fn build_inverted_file_from_flat(
             lists[cid as usize].push(doc_idx as u32);
         }
     }
-    InvertedFile { lists }
+    InvertedFile::from_lists(lists)
 }
 
 #[cfg(test)]
@@ -556,9 +614,22 @@ mod tests {
             nbits: 2,
             k_centroids: 2,
             max_kmeans_iters: 50,
+            kmeans: KMeansOptions::default(),
         }
     }
 
+    #[test]
+    fn auto_k_centroids_follows_the_sqrt_heuristic() {
+        assert_eq!(auto_k_centroids(0), 1);
+        assert_eq!(auto_k_centroids(1), 1);
+        // 16 * sqrt(100) = 160 -> 128, capped at the 100 tokens.
+        assert_eq!(auto_k_centroids(100), 100);
+        // 16 * sqrt(10_000) = 1_600 -> 1_024.
+        assert_eq!(auto_k_centroids(10_000), 1_024);
+        // 16 * sqrt(1_000_000) = 16_000 -> 8_192.
+        assert_eq!(auto_k_centroids(1_000_000), 8_192);
+    }
+
     #[test]
     fn build_index_encodes_every_token() {
         let docs = small_corpus();
@@ -738,9 +809,7 @@ mod tests {
 
     #[test]
     fn inverted_file_out_of_range_returns_empty_slice() {
-        let ivf = InvertedFile {
-            lists: vec![vec![0u32]],
-        };
+        let ivf = InvertedFile::from_lists(vec![vec![0u32]]);
         assert_eq!(ivf.docs_for_centroid(0).len(), 1);
         assert!(ivf.docs_for_centroid(999).is_empty());
     }
@@ -758,6 +827,7 @@ mod tests {
             nbits: 2,
             k_centroids: 4,
             max_kmeans_iters: 10,
+            kmeans: KMeansOptions::default(),
         };
         let _ = build_index(&docs, params).unwrap();
     }
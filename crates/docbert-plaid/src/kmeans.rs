@@ -24,6 +24,12 @@
 //! full-corpus assign happens later in [`crate::codec`] during
 //! encoding.
 //!
+//! [`fit_with_options`] makes each part of that recipe selectable
+//! through [`KMeansOptions`]: k-means++ or k-means|| seeding instead
+//! of a random pick, mini-batch updates with early stopping on
+//! centroid movement instead of full Lloyd iterations, and the size
+//! of the training sample.
+//!
 //! [`nearest_centroid`] (single point) is the only scalar primitive
 //! left — it's a pure-CPU helper used outside the training loop.
 //! [`update_centroids`] (the M-step) and the assignment step both run
@@ -31,7 +37,7 @@
 //! backend `p_tensor.device()` lives on.
 
 use candle_core::{DType, Tensor};
-use rand::{SeedableRng, rngs::StdRng, seq::SliceRandom};
+use rand::{RngExt, SeedableRng, rngs::StdRng, seq::SliceRandom};
 
 use crate::{Result, device::default_device, distance::squared_l2};
 
@@ -53,6 +59,64 @@ const MAX_POINTS_PER_CENTROID: usize = 256;
 /// shuffle `points` externally before calling [`fit`].
 const TRAINING_SHUFFLE_SEED: u64 = 0x7BE0_0CBE_7BE0_0CBE;
 
+/// Seed for the draws k-means++, k-means|| and mini-batch training
+/// make, fixed for the same reason as [`TRAINING_SHUFFLE_SEED`].
+const TRAINING_DRAW_SEED: u64 = 0x5EED_C0DE_5EED_C0DE;
+
+/// Oversampling rounds k-means|| runs before reducing its candidates
+/// to `k` seeds. Bahmani et al. find five enough in practice.
+pub const KMEANS_PARALLEL_ROUNDS: usize = 5;
+
+/// Candidates each k-means|| round draws in expectation, as a multiple
+/// of `k`. Bahmani et al. report seeds as good as k-means++ from `0.5`
+/// up; every candidate costs a distance per training point, so this
+/// stays at the low end.
+pub const KMEANS_PARALLEL_OVERSAMPLING: f64 = 0.5;
+
+/// How [`fit_with_options`] picks its initial centroids.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KMeansInit {
+    /// The first `k` rows of the shuffled training sample, a uniform
+    /// random pick. What fast-plaid and ColBERTv2 do.
+    #[default]
+    Random,
+    /// k-means++ (Arthur & Vassilvitskii, 2007): each further seed is
+    /// drawn with probability proportional to its squared distance
+    /// from the nearest seed so far. Takes `k` sequential passes over
+    /// the training sample.
+    PlusPlus,
+    /// k-means|| (Bahmani et al., 2012): each of
+    /// [`KMEANS_PARALLEL_ROUNDS`] rounds oversamples about `k / 2`
+    /// candidates in one batched pass, then weighted k-means++ over
+    /// the candidates picks the `k` seeds. Fewer passes than
+    /// k-means++ but more distances, so it pays off where each pass
+    /// is expensive (a GPU round-trip) rather than on the CPU.
+    Parallel,
+}
+
+/// Training choices for [`fit_with_options`] beyond `k` and the
+/// iteration cap. The default trains exactly like [`fit`].
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct KMeansOptions {
+    /// How the initial centroids are picked.
+    pub init: KMeansInit,
+    /// Train with mini-batch k-means (Sculley, 2010) on batches of
+    /// this many points instead of full Lloyd iterations. Each
+    /// iteration is then one shuffled pass over the training sample,
+    /// and only one batch is ever on the device. `None` runs Lloyd.
+    pub mini_batch: Option<usize>,
+    /// Stop once no centroid moves further than this (L2 distance) in
+    /// one update. `None` keeps Lloyd's own rule of stopping when no
+    /// point changes cluster, and runs mini-batch training for every
+    /// iteration.
+    pub tolerance: Option<f32>,
+    /// Number of tokens to train on, drawn at random from the corpus
+    /// and capped at its size. `None` uses `k · 256`. Tokens outside
+    /// the sample get their centroid afterwards, in the single
+    /// assignment pass the codec makes while encoding the corpus.
+    pub sample_tokens: Option<usize>,
+}
+
 /// Index of the centroid nearest to `point` under squared L2 distance.
 ///
 /// Ties are broken by preferring the earlier centroid, which keeps the
@@ -231,8 +295,9 @@ pub fn fit_with_init(
 /// the final centroids round-trip back to a host `Vec<f32>` for the
 /// caller.
 ///
-/// Factored out so [`fit`] can upload the points tensor once and share
-/// it with [`farthest_first_init_on_tensor`]. Duplicating the upload
+/// Factored out so [`fit_with_options`] can upload the points tensor
+/// once and share it with the k-means++ and k-means|| seeding passes.
+/// Duplicating the upload
 /// cost 3.47 GB of VRAM on the docbert production corpus, which
 /// pushed the build into CUDA OOM on 12 GB cards.
 fn fit_with_init_on_tensor(
@@ -240,6 +305,18 @@ fn fit_with_init_on_tensor(
     initial: &[f32],
     dim: usize,
     max_iters: usize,
+) -> Result<Vec<f32>> {
+    lloyd(p_tensor, initial, dim, max_iters, None)
+}
+
+/// [`fit_with_init_on_tensor`] that also stops once no centroid moves
+/// further than `tolerance` in an update.
+fn lloyd(
+    p_tensor: &Tensor,
+    initial: &[f32],
+    dim: usize,
+    max_iters: usize,
+    tolerance: Option<f32>,
 ) -> Result<Vec<f32>> {
     let k = initial.len() / dim;
     let device = p_tensor.device();
@@ -258,7 +335,22 @@ fn fit_with_init_on_tensor(
                 break;
             }
         }
-        centroids_t = update_centroids(p_tensor, &assignments, &centroids_t)?;
+        let updated = update_centroids(p_tensor, &assignments, &centroids_t)?;
+        let converged = match tolerance {
+            Some(tolerance) => {
+                let max_shift_sq = (&updated - &centroids_t)?
+                    .sqr()?
+                    .sum_keepdim(1)?
+                    .max_all()?
+                    .to_scalar::<f32>()?;
+                max_shift_sq.sqrt() <= tolerance
+            }
+            None => false,
+        };
+        centroids_t = updated;
+        if converged {
+            break;
+        }
         previous_assignments = Some(assignments);
     }
     Ok(centroids_t.flatten_all()?.to_vec1::<f32>()?)
@@ -279,6 +371,7 @@ fn fit_with_init_on_tensor(
 /// `256 · k` is the sample size the PLAID paper validates as having
 /// no measurable recall loss versus full-corpus training; this crate
 /// targets that point on the quality / build-time curve.
+/// [`fit_with_options`] trades along that curve differently.
 ///
 /// # Errors
 ///
@@ -295,6 +388,66 @@ pub fn fit(
     k: usize,
     dim: usize,
     max_iters: usize,
+) -> Result<Vec<f32>> {
+    assert!(k > 0, "fit: k must be positive");
+    assert!(dim > 0, "fit: dim must be positive");
+    assert!(
+        points.len().is_multiple_of(dim),
+        "fit: points length {} is not a multiple of dim {}",
+        points.len(),
+        dim,
+    );
+    fit_with_options(points, k, dim, max_iters, KMeansOptions::default())
+}
+
+/// Run k-means over a random sample of `points`, trained as `options`
+/// selects.
+///
+/// 1. Sample [`KMeansOptions::sample_tokens`] points (`k · 256` by
+///    default) with a seeded shuffle.
+/// 2. Pick `k` initial centroids from the sample by
+///    [`KMeansOptions::init`].
+/// 3. Refine them with Lloyd's algorithm, or with mini-batch updates
+///    when [`KMeansOptions::mini_batch`] is set, for up to
+///    `max_iters` iterations or until no centroid moves more than
+///    [`KMeansOptions::tolerance`].
+///
+/// Every draw is seeded, so the same inputs always produce the same
+/// centroids.
+///
+/// # Errors
+///
+/// Returns [`PlaidError::Tensor`] on underlying tensor failure.
+///
+/// # Panics
+///
+/// Panics if `k == 0`, if `dim == 0`, if `points` has fewer than `k`
+/// rows, or if `options` asks for empty mini-batches, an empty
+/// sample, or a negative or non-finite tolerance.
+///
+/// # Examples
+///
+/// ```
+/// use docbert_plaid::kmeans::{KMeansInit, KMeansOptions, fit_with_options};
+///
+/// let points = [0.0, 0.0, 0.1, 0.1, 10.0, 10.0, 10.1, 9.9];
+/// let options = KMeansOptions {
+///     init: KMeansInit::PlusPlus,
+///     mini_batch: Some(2),
+///     tolerance: Some(1e-4),
+///     sample_tokens: None,
+/// };
+/// let centroids = fit_with_options(&points, 2, 2, 20, options).unwrap();
+/// assert_eq!(centroids.len(), 4);
+/// ```
+///
+/// [`PlaidError::Tensor`]: crate::PlaidError::Tensor
+pub fn fit_with_options(
+    points: &[f32],
+    k: usize,
+    dim: usize,
+    max_iters: usize,
+    options: KMeansOptions,
 ) -> Result<Vec<f32>> {
     assert!(k > 0, "fit: k must be positive");
     assert!(dim > 0, "fit: dim must be positive");
@@ -309,15 +462,83 @@ pub fn fit(
         n_points >= k,
         "fit: need at least k={k} points, got {n_points}",
     );
+    assert!(
+        options.mini_batch != Some(0),
+        "fit: mini-batch size must be positive",
+    );
+    assert!(
+        options.sample_tokens != Some(0),
+        "fit: sample size must be positive",
+    );
+    if let Some(tolerance) = options.tolerance {
+        assert!(
+            tolerance.is_finite() && tolerance >= 0.0,
+            "fit: tolerance must be finite and non-negative, got {tolerance}",
+        );
+    }
 
-    let (training_points, _) = sample_training_points(points, n_points, k, dim);
+    let target = options
+        .sample_tokens
+        .unwrap_or(k * MAX_POINTS_PER_CENTROID)
+        .clamp(k, n_points);
+    let (training_points, _) =
+        sample_training_points(points, n_points, target, dim);
 
     let device = default_device();
     let n_train = training_points.len() / dim;
-    let training_tensor =
-        Tensor::from_slice(&training_points, (n_train, dim), device)?;
-    let initial = training_points[..k * dim].to_vec();
-    fit_with_init_on_tensor(&training_tensor, &initial, dim, max_iters)
+    let mut rng = StdRng::seed_from_u64(TRAINING_DRAW_SEED);
+    let (initial, training_tensor) = match options.init {
+        KMeansInit::Random => (training_points[..k * dim].to_vec(), None),
+        init => {
+            let training_tensor =
+                Tensor::from_slice(&training_points, (n_train, dim), device)?;
+            let initial = if init == KMeansInit::PlusPlus {
+                plus_plus_seeds(
+                    &training_tensor,
+                    &training_points,
+                    k,
+                    &mut rng,
+                )?
+            } else {
+                parallel_seeds(&training_tensor, &training_points, k, &mut rng)?
+            };
+            (initial, Some(training_tensor))
+        }
+    };
+
+    match options.mini_batch {
+        None => {
+            let training_tensor = match training_tensor {
+                Some(tensor) => tensor,
+                None => Tensor::from_slice(
+                    &training_points,
+                    (n_train, dim),
+                    device,
+                )?,
+            };
+            lloyd(
+                &training_tensor,
+                &initial,
+                dim,
+                max_iters,
+                options.tolerance,
+            )
+        }
+        Some(batch) => {
+            // Mini-batch training uploads one batch at a time; release
+            // the seeding tensor first.
+            drop(training_tensor);
+            mini_batch_fit(
+                &training_points,
+                initial,
+                dim,
+                max_iters,
+                batch,
+                options.tolerance,
+                &mut rng,
+            )
+        }
+    }
 }
 
 /// Same as [`fit`] but accepts an already-uploaded `p_tensor` covering
@@ -337,8 +558,9 @@ pub fn fit_on_tensor(
     max_iters: usize,
 ) -> Result<Vec<f32>> {
     let n = p_tensor.dim(0)?;
+    let target = (k * MAX_POINTS_PER_CENTROID).min(n);
     let (training_points, same_as_pool) =
-        sample_training_points(points, n, k, dim);
+        sample_training_points(points, n, target, dim);
     let initial = training_points[..k * dim].to_vec();
 
     if same_as_pool {
@@ -358,20 +580,19 @@ pub fn fit_on_tensor(
     fit_with_init_on_tensor(&training_tensor, &initial, dim, max_iters)
 }
 
-/// Sample up to `k · MAX_POINTS_PER_CENTROID` rows from `points` with
-/// a seeded Fisher–Yates shuffle.
+/// Sample `target` of the `n` rows in `points` with a seeded
+/// Fisher–Yates shuffle.
 ///
-/// Returns `(sample, same_as_pool)` — when the corpus already has
-/// `≤ k · MAX_POINTS_PER_CENTROID` rows, the caller's slice is cloned
-/// as-is and `same_as_pool = true`, letting [`fit_on_tensor`] reuse
-/// the pre-uploaded tensor without a second copy.
+/// Returns `(sample, same_as_pool)` — when `target == n`, the caller's
+/// slice is cloned as-is and `same_as_pool = true`, letting
+/// [`fit_on_tensor`] reuse the pre-uploaded tensor without a second
+/// copy.
 fn sample_training_points(
     points: &[f32],
     n: usize,
-    k: usize,
+    target: usize,
     dim: usize,
 ) -> (Vec<f32>, bool) {
-    let target = (k * MAX_POINTS_PER_CENTROID).min(n);
     if target == n {
         return (points.to_vec(), true);
     }
@@ -392,6 +613,284 @@ fn sample_training_points(
     (sample, false)
 }
 
+/// k-means++ seeds drawn from `points`, the host copy of `p_tensor`.
+///
+/// The first seed is row 0: the sample is already shuffled, so that is
+/// a uniform random pick.
+fn plus_plus_seeds(
+    p_tensor: &Tensor,
+    points: &[f32],
+    k: usize,
+    rng: &mut StdRng,
+) -> Result<Vec<f32>> {
+    let dim = p_tensor.dim(1)?;
+    let p_sq = row_sq_norms(p_tensor)?;
+    let mut seeds = points[..dim].to_vec();
+    let (mut nearest, _) = nearest_sq_distances(p_tensor, &p_sq, &seeds)?;
+    while seeds.len() < k * dim {
+        let i = sample_proportional(&nearest, rng);
+        let seed = &points[i * dim..(i + 1) * dim];
+        seeds.extend_from_slice(seed);
+        let (distances, _) = nearest_sq_distances(p_tensor, &p_sq, seed)?;
+        for (nearest, d) in nearest.iter_mut().zip(distances) {
+            *nearest = nearest.min(d);
+        }
+    }
+    Ok(seeds)
+}
+
+/// k-means|| seeds drawn from `points`, the host copy of `p_tensor`.
+///
+/// Starts from row 0 like [`plus_plus_seeds`]. Each round keeps every
+/// point independently with probability `ℓ · d² / Σd²`, where `d` is
+/// its distance to the nearest candidate so far and `ℓ` is
+/// [`KMEANS_PARALLEL_OVERSAMPLING`]` · k`. The candidates are then
+/// weighted by how many points they are nearest to, tracked along the
+/// way, and reduced to `k` seeds with weighted k-means++.
+fn parallel_seeds(
+    p_tensor: &Tensor,
+    points: &[f32],
+    k: usize,
+    rng: &mut StdRng,
+) -> Result<Vec<f32>> {
+    let dim = p_tensor.dim(1)?;
+    let n = points.len() / dim;
+    let p_sq = row_sq_norms(p_tensor)?;
+    let mut chosen = vec![false; n];
+    chosen[0] = true;
+    let mut candidates = points[..dim].to_vec();
+    let (mut nearest, _) = nearest_sq_distances(p_tensor, &p_sq, &candidates)?;
+    // Index of each point's nearest candidate.
+    let mut owner = vec![0usize; n];
+    let oversample = KMEANS_PARALLEL_OVERSAMPLING * k as f64;
+    for _ in 0..KMEANS_PARALLEL_ROUNDS {
+        let cost: f64 = nearest.iter().map(|&d| f64::from(d)).sum();
+        if cost <= 0.0 {
+            break;
+        }
+        let mut picked = Vec::new();
+        for (i, &d) in nearest.iter().enumerate() {
+            if rng.random::<f64>() < oversample * f64::from(d) / cost {
+                chosen[i] = true;
+                picked.extend_from_slice(&points[i * dim..(i + 1) * dim]);
+            }
+        }
+        if picked.is_empty() {
+            continue;
+        }
+        let offset = candidates.len() / dim;
+        let (distances, closest) =
+            nearest_sq_distances(p_tensor, &p_sq, &picked)?;
+        for ((nearest, owner), (d, c)) in nearest
+            .iter_mut()
+            .zip(owner.iter_mut())
+            .zip(distances.into_iter().zip(closest))
+        {
+            if d < *nearest {
+                *nearest = d;
+                *owner = offset + c;
+            }
+        }
+        candidates.extend_from_slice(&picked);
+    }
+
+    // Too few candidates (tiny or heavily duplicated samples): top up
+    // with the earliest unpicked rows, which are random as well. The
+    // result is exactly `k` candidates, used unweighted.
+    for (i, row) in points.chunks_exact(dim).enumerate() {
+        if candidates.len() >= k * dim {
+            break;
+        }
+        if !chosen[i] {
+            candidates.extend_from_slice(row);
+        }
+    }
+
+    let mut weights = vec![0.0f32; candidates.len() / dim];
+    for &candidate in &owner {
+        weights[candidate] += 1.0;
+    }
+    Ok(weighted_plus_plus(&candidates, &weights, k, dim, rng))
+}
+
+/// Weighted k-means++ over a small host-side candidate set: seeds are
+/// drawn with probability proportional to `weight · d²`, the first by
+/// weight alone.
+fn weighted_plus_plus(
+    candidates: &[f32],
+    weights: &[f32],
+    k: usize,
+    dim: usize,
+    rng: &mut StdRng,
+) -> Vec<f32> {
+    if candidates.len() == k * dim {
+        return candidates.to_vec();
+    }
+    let row = |i: usize| &candidates[i * dim..(i + 1) * dim];
+    let first = sample_proportional(weights, rng);
+    let mut seeds = row(first).to_vec();
+    let mut nearest: Vec<f32> = (0..weights.len())
+        .map(|i| squared_l2(row(i), row(first)))
+        .collect();
+    let mut scores = vec![0.0f32; weights.len()];
+    while seeds.len() < k * dim {
+        for ((score, w), d) in scores.iter_mut().zip(weights).zip(&nearest) {
+            *score = w * d;
+        }
+        let next = sample_proportional(&scores, rng);
+        seeds.extend_from_slice(row(next));
+        for (i, nearest) in nearest.iter_mut().enumerate() {
+            *nearest = nearest.min(squared_l2(row(i), row(next)));
+        }
+    }
+    seeds
+}
+
+/// Index drawn with probability proportional to `weights`, or uniformly
+/// when they are all zero.
+fn sample_proportional(weights: &[f32], rng: &mut StdRng) -> usize {
+    let total: f64 = weights.iter().map(|&w| f64::from(w)).sum();
+    if total <= 0.0 {
+        return rng.random_range(0..weights.len());
+    }
+    let mut target = rng.random::<f64>() * total;
+    for (i, &w) in weights.iter().enumerate() {
+        target -= f64::from(w);
+        if target < 0.0 {
+            return i;
+        }
+    }
+    // Rounding left a sliver of `target`; land on the last non-zero
+    // weight rather than a zero-probability row.
+    weights.iter().rposition(|&w| w > 0.0).unwrap_or(0)
+}
+
+/// Squared L2 norm of every row of `p_tensor`, on the host.
+fn row_sq_norms(p_tensor: &Tensor) -> Result<Vec<f32>> {
+    Ok(p_tensor.sqr()?.sum(1)?.to_vec1::<f32>()?)
+}
+
+/// Squared L2 distance from every row of `p_tensor` to its nearest row
+/// of the flat `centers`, and that row's index, given the rows'
+/// squared norms `p_sq`.
+///
+/// Chunked like [`assign_tensor`] so the `[rows, centers]` score matrix
+/// stays within [`ASSIGN_CHUNK_BYTES`].
+fn nearest_sq_distances(
+    p_tensor: &Tensor,
+    p_sq: &[f32],
+    centers: &[f32],
+) -> Result<(Vec<f32>, Vec<usize>)> {
+    let (n, dim) = p_tensor.dims2()?;
+    let m = centers.len() / dim;
+    let c = Tensor::from_slice(centers, (m, dim), p_tensor.device())?;
+    let c_t = c.t()?;
+    let c_sq = c.sqr()?.sum_keepdim(1)?.t()?; // [1, m]
+    let chunk_rows = (ASSIGN_CHUNK_BYTES / (m * std::mem::size_of::<f32>()))
+        .max(1)
+        .min(n.max(1));
+
+    let mut distances = Vec::with_capacity(n);
+    let mut closest = Vec::with_capacity(n);
+    let mut start = 0usize;
+    while start < n {
+        let len = chunk_rows.min(n - start);
+        // ||c||² − 2·p·c, minimised over c; ||p||² is added on the host.
+        let scores = p_tensor
+            .narrow(0, start, len)?
+            .matmul(&c_t)?
+            .affine(-2.0, 0.0)?
+            .broadcast_add(&c_sq)?;
+        let partial: Vec<f32> = scores.min(1)?.to_vec1()?;
+        // k-means++ measures one new seed at a time; skip the argmin.
+        let argmin: Vec<u32> = if m == 1 {
+            vec![0; len]
+        } else {
+            scores.argmin(1)?.to_vec1()?
+        };
+        distances.extend(
+            partial
+                .iter()
+                .zip(&p_sq[start..start + len])
+                .map(|(partial, p_sq)| (partial + p_sq).max(0.0)),
+        );
+        closest.extend(argmin.into_iter().map(|c| c as usize));
+        start += len;
+    }
+    Ok((distances, closest))
+}
+
+/// Mini-batch k-means (Sculley, 2010) over the host-side `points`.
+///
+/// Each iteration shuffles the points and walks them in batches of
+/// `batch`. Every batch is assigned to the current centroids, then
+/// each centroid moves towards the mean of its batch points by the
+/// share of all points it has absorbed so far, so updates shrink as
+/// training goes on. Stops early after any batch in which no centroid
+/// moves further than `tolerance`.
+fn mini_batch_fit(
+    points: &[f32],
+    initial: Vec<f32>,
+    dim: usize,
+    max_iters: usize,
+    batch: usize,
+    tolerance: Option<f32>,
+    rng: &mut StdRng,
+) -> Result<Vec<f32>> {
+    let n = points.len() / dim;
+    let k = initial.len() / dim;
+    let mut centroids = initial;
+    let mut seen = vec![0usize; k];
+    let mut order: Vec<usize> = (0..n).collect();
+    let mut rows = Vec::with_capacity(batch.min(n) * dim);
+    let mut sums = vec![0.0f32; k * dim];
+    let mut counts = vec![0usize; k];
+    for _ in 0..max_iters {
+        order.shuffle(rng);
+        for ids in order.chunks(batch) {
+            rows.clear();
+            for &i in ids {
+                rows.extend_from_slice(&points[i * dim..(i + 1) * dim]);
+            }
+            sums.fill(0.0);
+            counts.fill(0);
+            for (row, cluster) in rows
+                .chunks_exact(dim)
+                .zip(assign_points(&rows, &centroids, dim)?)
+            {
+                counts[cluster] += 1;
+                let sum = &mut sums[cluster * dim..(cluster + 1) * dim];
+                for (s, x) in sum.iter_mut().zip(row) {
+                    *s += x;
+                }
+            }
+
+            let mut max_shift_sq = 0.0f32;
+            for (cluster, &count) in counts.iter().enumerate() {
+                if count == 0 {
+                    continue;
+                }
+                seen[cluster] += count;
+                let rate = count as f32 / seen[cluster] as f32;
+                let span = cluster * dim..(cluster + 1) * dim;
+                let mut shift_sq = 0.0f32;
+                for (c, s) in
+                    centroids[span.clone()].iter_mut().zip(&sums[span])
+                {
+                    let step = rate * (s / count as f32 - *c);
+                    *c += step;
+                    shift_sq += step * step;
+                }
+                max_shift_sq = max_shift_sq.max(shift_sq);
+            }
+            if tolerance.is_some_and(|t| max_shift_sq.sqrt() <= t) {
+                return Ok(centroids);
+            }
+        }
+    }
+    Ok(centroids)
+}
+
 /// Assign every point in `points` to the index of its nearest centroid.
 ///
 /// `points` is a flat row-major `n_points × dim` buffer; `centroids` is a
@@ -842,4 +1341,179 @@ mod tests {
         let points = vec![0.0, 0.0];
         let _ = fit(&points, 5, 2, 1);
     }
+
+    // -- Training options --
+
+    /// Three tight clusters of four points around (0,0), (10,0) and
+    /// (0,10).
+    fn three_clusters() -> Vec<f32> {
+        let mut points = Vec::new();
+        for (cx, cy) in [(0.0, 0.0), (10.0, 0.0), (0.0, 10.0)] {
+            for (dx, dy) in [(0.1, 0.0), (-0.1, 0.0), (0.0, 0.1), (0.0, -0.1)] {
+                points.extend_from_slice(&[cx + dx, cy + dy]);
+            }
+        }
+        points
+    }
+
+    /// Index of the `three_clusters` cluster `point` belongs to.
+    fn cluster_of(point: &[f32; 2]) -> usize {
+        nearest_centroid(point, &[0.0, 0.0, 10.0, 0.0, 0.0, 10.0], 2)
+    }
+
+    fn seeds_cover_every_cluster(init: KMeansInit) {
+        let points = three_clusters();
+        let options = KMeansOptions {
+            init,
+            ..KMeansOptions::default()
+        };
+        // No iterations: these are the seeds themselves.
+        let seeds = fit_with_options(&points, 3, 2, 0, options).unwrap();
+        let mut clusters: Vec<usize> =
+            seeds.as_chunks::<2>().0.iter().map(cluster_of).collect();
+        clusters.sort_unstable();
+        assert_eq!(clusters, vec![0, 1, 2], "seeds {seeds:?}");
+    }
+
+    #[test]
+    fn plus_plus_seeds_cover_every_cluster() {
+        seeds_cover_every_cluster(KMeansInit::PlusPlus);
+    }
+
+    #[test]
+    fn parallel_seeds_cover_every_cluster() {
+        seeds_cover_every_cluster(KMeansInit::Parallel);
+    }
+
+    #[test]
+    fn parallel_seeds_top_up_when_the_sample_has_few_distinct_rows() {
+        // Every point identical: no round can pick a candidate, so the
+        // seeds come from the top-up.
+        let points = vec![1.0; 8];
+        let options = KMeansOptions {
+            init: KMeansInit::Parallel,
+            ..KMeansOptions::default()
+        };
+        let seeds = fit_with_options(&points, 3, 2, 0, options).unwrap();
+        assert_eq!(seeds, vec![1.0; 6]);
+    }
+
+    #[test]
+    fn mini_batch_fit_converges_on_three_clusters() {
+        let points = three_clusters();
+        for init in [KMeansInit::Random, KMeansInit::PlusPlus] {
+            let options = KMeansOptions {
+                init,
+                mini_batch: Some(4),
+                ..KMeansOptions::default()
+            };
+            let fitted = fit_with_options(&points, 3, 2, 20, options).unwrap();
+            let mut clusters: Vec<usize> =
+                fitted.as_chunks::<2>().0.iter().map(cluster_of).collect();
+            clusters.sort_unstable();
+            assert_eq!(clusters, vec![0, 1, 2], "{init:?}: {fitted:?}");
+            for centroid in fitted.as_chunks::<2>().0 {
+                let truth = [[0.0, 0.0], [10.0, 0.0], [0.0, 10.0]]
+                    [cluster_of(centroid)];
+                assert!(
+                    squared_l2(centroid, &truth) < 0.1,
+                    "{init:?}: {fitted:?}",
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn lloyd_stops_once_centroids_move_less_than_tolerance() {
+        let points = three_clusters();
+        let initial = [1.0, 1.0, 9.0, 1.0, 1.0, 9.0];
+        let tensor =
+            Tensor::from_slice(&points, (12, 2), default_device()).unwrap();
+        // Any tolerance the first update meets stops right after it.
+        let stopped = lloyd(&tensor, &initial, 2, 50, Some(f32::MAX)).unwrap();
+        let one_step = fit_with_init(&points, &initial, 2, 1).unwrap();
+        assert_eq!(stopped, one_step);
+    }
+
+    #[test]
+    fn mini_batch_fit_stops_once_centroids_move_less_than_tolerance() {
+        let points = three_clusters();
+        let initial = vec![1.0, 1.0, 9.0, 1.0, 1.0, 9.0];
+        let mut rng = StdRng::seed_from_u64(TRAINING_DRAW_SEED);
+        let stopped = mini_batch_fit(
+            &points,
+            initial.clone(),
+            2,
+            50,
+            4,
+            Some(f32::MAX),
+            &mut rng,
+        )
+        .unwrap();
+        let mut rng = StdRng::seed_from_u64(TRAINING_DRAW_SEED);
+        let full =
+            mini_batch_fit(&points, initial.clone(), 2, 50, 4, None, &mut rng)
+                .unwrap();
+        assert_ne!(stopped, initial, "the first batch should still apply");
+        assert_ne!(stopped, full);
+    }
+
+    #[test]
+    fn fit_with_options_seeds_from_a_sample_of_the_requested_size() {
+        let points = three_clusters();
+        // A sample of exactly k rows leaves nothing to choose between:
+        // every seeding strategy returns the sampled rows themselves,
+        // each a row of `points`.
+        for init in [
+            KMeansInit::Random,
+            KMeansInit::PlusPlus,
+            KMeansInit::Parallel,
+        ] {
+            let options = KMeansOptions {
+                init,
+                sample_tokens: Some(3),
+                ..KMeansOptions::default()
+            };
+            let seeds = fit_with_options(&points, 3, 2, 0, options).unwrap();
+            assert_eq!(seeds.len(), 6);
+            for seed in seeds.as_chunks::<2>().0 {
+                assert!(
+                    points.as_chunks::<2>().0.iter().any(|row| row == seed),
+                    "{init:?}: {seed:?} is not a corpus row",
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn fit_with_options_is_deterministic_for_every_option() {
+        let points = three_clusters();
+        for init in [
+            KMeansInit::Random,
+            KMeansInit::PlusPlus,
+            KMeansInit::Parallel,
+        ] {
+            for mini_batch in [None, Some(5)] {
+                let options = KMeansOptions {
+                    init,
+                    mini_batch,
+                    tolerance: Some(1e-3),
+                    sample_tokens: Some(9),
+                };
+                let a = fit_with_options(&points, 3, 2, 10, options).unwrap();
+                let b = fit_with_options(&points, 3, 2, 10, options).unwrap();
+                assert_eq!(a, b, "{options:?}");
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "mini-batch size must be positive")]
+    fn fit_with_options_panics_on_empty_mini_batches() {
+        let options = KMeansOptions {
+            mini_batch: Some(0),
+            ..KMeansOptions::default()
+        };
+        let _ = fit_with_options(&three_clusters(), 3, 2, 1, options);
+    }
 }
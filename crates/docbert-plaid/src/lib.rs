@@ -14,6 +14,8 @@
 //! - residual codec (coarse centroid + quantized residual).
 //! - inverted file (centroid → documents).
 //! - search path (query tokens → candidate docs → MaxSim).
+//! - [`simd`] — vectorised CPU kernels for dot products and fused
+//!   decode + MaxSim.
 //!
 //! At the moment the crate only exposes the lowest-level primitives; more
 //! layers will be added as subsequent TDD cycles come in.
@@ -21,11 +23,15 @@
 pub mod codec;
 pub mod device;
 pub mod distance;
+pub mod drift;
 pub mod error;
 pub mod index;
 pub mod kmeans;
 pub mod persistence;
 pub mod search;
+pub mod segment;
+pub mod simd;
+pub mod storage;
 pub mod update;
 
 pub use error::{PlaidError, Result};
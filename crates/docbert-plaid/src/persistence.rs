@@ -1,44 +1,75 @@
 //! Save and load a built [`Index`] to/from disk.
 //!
-//! The on-disk format is a single little-endian binary file with a small
-//! header followed by a sequence of plain f32/u32/u64 blobs. The layout
-//! is deliberately boring — there's no compression, no extra framing,
-//! and no schema evolution today; we can layer any of that on top later
-//! without changing the semantic API.
+//! The on-disk format is a single little-endian binary file: a fixed
+//! header, a section table, and one aligned blob per flat buffer of the
+//! [`Index`]. There's no compression and no extra framing. Every
+//! section starts on a 64-byte boundary, so [`load`] memory-maps the
+//! file and hands the blobs to the index as [`Buffer`]s without reading
+//! or copying them; the OS pages them in as search touches them.
 //!
-//! Layout, every field little-endian:
+//! Layout of format version 3, every field little-endian:
 //!
 //! ```text
 //! magic           : 8 bytes, b"PLAIDIDX"
-//! version         : u32     (currently 1)
+//! version         : u32     (currently 3)
+//! section_count   : u32
 //! dim             : u32
 //! nbits           : u32
-//! k_centroids    : u32
+//! k_centroids     : u32
 //! max_kmeans_iters: u32
 //! n_documents     : u64
-//! centroids       : dim * k_centroids     f32
-//! bucket_cutoffs  : 2^nbits - 1            f32
-//! bucket_weights  : 2^nbits                f32
-//! doc_ids         : n_documents            u64
-//! token_counts    : n_documents            u32  (tokens per document)
-//! encoded_tokens  : for each token, u32 centroid_id then `dim` u8 codes
+//! n_tokens        : u64
+//! section table   : section_count × { id: u32, reserved: u32,
+//!                                     offset: u64, byte_len: u64 }
+//! sections        : each at a 64-byte aligned `offset`, zero-padded
 //! ```
 //!
-//! The inverted file is *not* persisted — it's derivable from the
-//! encoded tokens in O(n_tokens) time on load and storing it would just
-//! duplicate state that's already on disk elsewhere.
+//! | id | section            | contents                                  |
+//! |----|--------------------|-------------------------------------------|
+//! | 1  | centroids          | `k_centroids × dim` f32                   |
+//! | 2  | bucket cutoffs     | `2^nbits - 1` f32                         |
+//! | 3  | bucket weights     | `2^nbits` f32                             |
+//! | 4  | doc ids            | `n_documents` u64                         |
+//! | 5  | doc offsets        | `n_documents + 1` u64 cumulative tokens   |
+//! | 6  | token centroid ids | `n_tokens` u32                            |
+//! | 7  | token residuals    | `n_tokens × packed_bytes` u8              |
+//! | 8  | IVF offsets        | `k_centroids + 1` u64                     |
+//! | 9  | IVF postings       | u32 doc indices, see [`InvertedFile`]     |
+//!
+//! Readers ignore section ids they don't know, so new sections can be
+//! added without a version bump. The inverted file is persisted rather
+//! than rebuilt on load: rebuilding walks every token, which is exactly
+//! the work a memory-mapped open avoids.
+//!
+//! Opening checks the header, the section table and that the sections
+//! agree on their lengths, which is constant work regardless of corpus
+//! size. The per-token contents are trusted as written by [`save`].
+//!
+//! Version 2 files (no section table, inverted file rebuilt on load)
+//! still load, into owned buffers; the next [`save`] rewrites them as
+//! version 3.
+//!
+//! [`Buffer`]: crate::storage::Buffer
+//! [`InvertedFile`]: crate::index::InvertedFile
 
 use std::{
+    ffi::OsString,
     fs::File,
     io::{BufReader, BufWriter, Read, Write},
-    path::Path,
+    path::{Path, PathBuf},
+    sync::Arc,
 };
 
+use bytemuck::Pod;
+use memmap2::Mmap;
+
 use crate::{
     PlaidError,
     Result,
     codec::{ResidualCodec, packed_bytes_per_vector},
-    index::{Index, IndexParams, build_inverted_file_from_flat},
+    index::{Index, IndexParams, InvertedFile, build_inverted_file_from_flat},
+    kmeans::KMeansOptions,
+    storage::Buffer,
 };
 
 const MAGIC: &[u8; 8] = b"PLAIDIDX";
@@ -47,66 +78,220 @@ const MAGIC: &[u8; 8] = b"PLAIDIDX";
 /// - `1`: unpacked residual codes, one byte per residual dimension.
 /// - `2`: LSB-first bit-packed codes at `nbits ∈ {1, 2, 4, 8}`;
 ///   `(dim * nbits) / 8` bytes per token.
-const FORMAT_VERSION: u32 = 2;
+/// - `3`: the same packed codes in aligned, memory-mappable sections
+///   behind a section table, with the inverted file persisted.
+const FORMAT_VERSION: u32 = 3;
+/// Oldest version [`load`] still reads.
+const LEGACY_PACKED_VERSION: u32 = 2;
+
+/// Bytes before the section table: magic, version, section count and
+/// the fixed header fields.
+const HEADER_LEN: usize = 48;
+/// Bytes per section table entry.
+const SECTION_ENTRY_LEN: usize = 24;
+/// Alignment of every section's first byte. A cache line, and more than
+/// any element type needs.
+const SECTION_ALIGN: usize = 64;
 
-/// Write `index` to `path`, creating or truncating the file as needed.
+const SECTION_CENTROIDS: u32 = 1;
+const SECTION_BUCKET_CUTOFFS: u32 = 2;
+const SECTION_BUCKET_WEIGHTS: u32 = 3;
+const SECTION_DOC_IDS: u32 = 4;
+const SECTION_DOC_OFFSETS: u32 = 5;
+const SECTION_CENTROID_IDS: u32 = 6;
+const SECTION_RESIDUALS: u32 = 7;
+const SECTION_IVF_OFFSETS: u32 = 8;
+const SECTION_IVF_POSTINGS: u32 = 9;
+
+/// Write `index` to `path` in the current format version.
+///
+/// The file is written next to `path` and renamed over it, so readers
+/// never see a partial file and an index still memory-mapped from the
+/// old file keeps reading the old contents.
 ///
 /// # Errors
 ///
 /// Returns [`PlaidError::Io`] for any underlying I/O failure, or
-/// [`PlaidError::InvalidIndex`] if an encoded token's `codes` buffer
-/// doesn't match the codec's advertised packed length (a caller bug
-/// worth flagging loudly on write rather than producing a file that
-/// would fail to load back).
+/// [`PlaidError::InvalidIndex`] if the residual buffer's length doesn't
+/// match the codec's advertised packed length (a caller bug worth
+/// flagging loudly on write rather than producing a file that would
+/// fail to load back).
 ///
 /// [`PlaidError::Io`]: crate::PlaidError::Io
 /// [`PlaidError::InvalidIndex`]: crate::PlaidError::InvalidIndex
 pub fn save(index: &Index, path: &Path) -> Result<()> {
-    let file = File::create(path)?;
-    let mut writer = BufWriter::new(file);
-    write_index(index, &mut writer)?;
-    writer.flush()?;
+    write_atomically(path, |w| write_index(index, true, w))
+}
+
+/// Write `index` without its codec sections, for a segment whose codec
+/// is stored once elsewhere. Read it back with [`load_segment`].
+pub(crate) fn save_segment(index: &Index, path: &Path) -> Result<()> {
+    write_atomically(path, |w| write_index(index, false, w))
+}
+
+/// Write only `codec` and the build parameters, as a version-3 file
+/// with no documents. Read it back with [`load_codec`].
+pub(crate) fn save_codec(
+    params: &IndexParams,
+    codec: &ResidualCodec,
+    path: &Path,
+) -> Result<()> {
+    write_atomically(path, |w| {
+        let sections = codec_sections(codec);
+        write_sections(w, params, 0, 0, &sections)
+    })
+}
+
+/// Write a file next to `path` with `write` and rename it over `path`.
+pub(crate) fn write_atomically(
+    path: &Path,
+    write: impl FnOnce(&mut BufWriter<File>) -> Result<()>,
+) -> Result<()> {
+    let staging = staging_path(path);
+    let written =
+        File::create(&staging)
+            .map_err(PlaidError::from)
+            .and_then(|file| {
+                let mut writer = BufWriter::new(file);
+                write(&mut writer)?;
+                writer.flush()?;
+                Ok(())
+            });
+    if let Err(err) = written {
+        let _ = std::fs::remove_file(&staging);
+        return Err(err);
+    }
+    std::fs::rename(&staging, path)?;
     Ok(())
 }
 
-/// Read an [`Index`] back from `path`.
+/// Open an [`Index`] stored at `path`.
+///
+/// Version 3 files are memory-mapped and opened in constant time; the
+/// index's flat buffers borrow from the mapping. Version 2 files are
+/// read into owned buffers.
 ///
 /// # Errors
 ///
 /// Returns [`PlaidError::Io`] for any read failure, or
-/// [`PlaidError::InvalidIndex`] if the file's magic, version, or
-/// header fields don't match this crate's format.
+/// [`PlaidError::InvalidIndex`] if the file's magic, version, header
+/// fields or section table don't match this crate's format.
 ///
 /// [`PlaidError::Io`]: crate::PlaidError::Io
 /// [`PlaidError::InvalidIndex`]: crate::PlaidError::InvalidIndex
 pub fn load(path: &Path) -> Result<Index> {
-    let file = File::open(path)?;
-    let mut reader = BufReader::new(file);
-    read_index(&mut reader)
+    let mut reader = BufReader::new(File::open(path)?);
+    match read_magic_and_version(&mut reader)? {
+        FORMAT_VERSION => {
+            let file = parse_mapped(map_file(reader.get_ref())?)?;
+            let codec = Arc::new(read_codec(&file)?);
+            read_documents(file, codec)
+        }
+        LEGACY_PACKED_VERSION => read_v2(&mut reader),
+        version => Err(unsupported_version(version)),
+    }
 }
 
-fn write_index<W: Write>(index: &Index, w: &mut W) -> Result<()> {
-    w.write_all(MAGIC)?;
-    write_u32(w, FORMAT_VERSION)?;
+/// Open a segment written by [`save_segment`], attaching the shared
+/// `codec` it was encoded with.
+pub(crate) fn load_segment(
+    path: &Path,
+    codec: &Arc<ResidualCodec>,
+) -> Result<Index> {
+    let file = open_v3(path)?;
+    let expected = (codec.dim, codec.nbits, codec.num_centroids());
+    let found = (file.params.dim, file.params.nbits, file.params.k_centroids);
+    if found != expected {
+        return Err(PlaidError::InvalidIndex(format!(
+            "segment has dim/nbits/k_centroids {found:?}, but its codec has {expected:?}",
+        )));
+    }
+    read_documents(file, Arc::clone(codec))
+}
 
-    let params = &index.params;
-    write_u32(w, params.dim as u32)?;
-    write_u32(w, params.nbits)?;
-    write_u32(w, params.k_centroids as u32)?;
-    write_u32(w, params.max_kmeans_iters as u32)?;
-    write_u64(w, index.doc_ids.len() as u64)?;
+/// Read the parameters and codec stored by [`save_codec`].
+pub(crate) fn load_codec(path: &Path) -> Result<(IndexParams, ResidualCodec)> {
+    let file = open_v3(path)?;
+    let codec = read_codec(&file)?;
+    Ok((file.params, codec))
+}
 
-    write_f32_slice(w, &index.codec.centroids)?;
-    write_f32_slice(w, &index.codec.bucket_cutoffs)?;
-    write_f32_slice(w, &index.codec.bucket_weights)?;
+/// Whether `path` starts with this format's magic bytes, whatever its
+/// version.
+pub(crate) fn has_index_magic(path: &Path) -> Result<bool> {
+    let mut magic = [0u8; 8];
+    match File::open(path)?.read_exact(&mut magic) {
+        Ok(()) => Ok(&magic == MAGIC),
+        Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => {
+            Ok(false)
+        }
+        Err(err) => Err(err.into()),
+    }
+}
 
-    write_u64_slice(w, &index.doc_ids)?;
+fn read_magic_and_version<R: Read>(r: &mut R) -> Result<u32> {
+    let mut magic = [0u8; 8];
+    r.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(PlaidError::InvalidIndex(
+            "not a docbert-plaid index (magic bytes mismatch)".into(),
+        ));
+    }
+    read_u32(r)
+}
 
-    let token_counts: Vec<u32> = (0..index.num_documents())
-        .map(|i| index.doc_token_count(i) as u32)
-        .collect();
-    write_u32_slice(w, &token_counts)?;
+fn unsupported_version(version: u32) -> PlaidError {
+    PlaidError::InvalidIndex(format!(
+        "unsupported plaid index version {version}, expected {FORMAT_VERSION} \
+         (or {LEGACY_PACKED_VERSION}, which is upgraded on save)",
+    ))
+}
 
+/// Open and parse a file that must be in the current version.
+fn open_v3(path: &Path) -> Result<MappedFile> {
+    let mut file = File::open(path)?;
+    match read_magic_and_version(&mut file)? {
+        FORMAT_VERSION => parse_mapped(map_file(&file)?),
+        version => Err(unsupported_version(version)),
+    }
+}
+
+fn map_file(file: &File) -> Result<Arc<Mmap>> {
+    // Safety: the map is only ever read. Every writer in this crate
+    // replaces files by renaming a fresh file over them instead of
+    // writing in place, so a mapped file is not modified while an
+    // index borrowing from it is alive.
+    let map = unsafe { Mmap::map(file)? };
+    Ok(Arc::new(map))
+}
+
+/// `path` with `.tmp` appended to its file name.
+fn staging_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().map(OsString::from).unwrap_or_default();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+fn codec_sections(codec: &ResidualCodec) -> [(u32, &[u8]); 3] {
+    [
+        (SECTION_CENTROIDS, bytemuck::cast_slice(&codec.centroids)),
+        (
+            SECTION_BUCKET_CUTOFFS,
+            bytemuck::cast_slice(&codec.bucket_cutoffs),
+        ),
+        (
+            SECTION_BUCKET_WEIGHTS,
+            bytemuck::cast_slice(&codec.bucket_weights),
+        ),
+    ]
+}
+
+fn write_index<W: Write>(
+    index: &Index,
+    with_codec: bool,
+    w: &mut W,
+) -> Result<()> {
+    let params = &index.params;
     let packed_bytes = packed_bytes_per_vector(params.dim, params.nbits);
     if index.doc_residual_bytes.len() != index.num_tokens() * packed_bytes {
         return Err(PlaidError::InvalidIndex(format!(
@@ -115,32 +300,311 @@ fn write_index<W: Write>(index: &Index, w: &mut W) -> Result<()> {
             index.num_tokens(),
         )));
     }
-    for i in 0..index.num_tokens() {
-        write_u32(w, index.doc_centroid_ids[i])?;
-        w.write_all(
-            &index.doc_residual_bytes[i * packed_bytes..(i + 1) * packed_bytes],
-        )?;
+
+    let doc_offsets: Vec<u64> =
+        index.doc_offsets.iter().map(|&o| o as u64).collect();
+    let ivf_offsets: Vec<u64> =
+        index.ivf.offsets.iter().map(|&o| o as u64).collect();
+    let mut sections: Vec<(u32, &[u8])> = Vec::with_capacity(9);
+    if with_codec {
+        sections.extend(codec_sections(&index.codec));
+    }
+    sections.extend([
+        (SECTION_DOC_IDS, bytemuck::cast_slice(&index.doc_ids)),
+        (SECTION_DOC_OFFSETS, bytemuck::cast_slice(&doc_offsets)),
+        (
+            SECTION_CENTROID_IDS,
+            bytemuck::cast_slice(&index.doc_centroid_ids),
+        ),
+        (SECTION_RESIDUALS, &index.doc_residual_bytes),
+        (SECTION_IVF_OFFSETS, bytemuck::cast_slice(&ivf_offsets)),
+        (
+            SECTION_IVF_POSTINGS,
+            bytemuck::cast_slice(&index.ivf.postings),
+        ),
+    ]);
+    write_sections(
+        w,
+        params,
+        index.num_documents(),
+        index.num_tokens(),
+        &sections,
+    )
+}
+
+fn write_sections<W: Write>(
+    w: &mut W,
+    params: &IndexParams,
+    n_documents: usize,
+    n_tokens: usize,
+    sections: &[(u32, &[u8])],
+) -> Result<()> {
+    w.write_all(MAGIC)?;
+    write_u32(w, FORMAT_VERSION)?;
+    write_u32(w, sections.len() as u32)?;
+    write_u32(w, params.dim as u32)?;
+    write_u32(w, params.nbits)?;
+    write_u32(w, params.k_centroids as u32)?;
+    write_u32(w, params.max_kmeans_iters as u32)?;
+    write_u64(w, n_documents as u64)?;
+    write_u64(w, n_tokens as u64)?;
+
+    let mut position = HEADER_LEN + sections.len() * SECTION_ENTRY_LEN;
+    let mut offsets = Vec::with_capacity(sections.len());
+    for (id, bytes) in sections {
+        let offset = position.next_multiple_of(SECTION_ALIGN);
+        write_u32(w, *id)?;
+        write_u32(w, 0)?;
+        write_u64(w, offset as u64)?;
+        write_u64(w, bytes.len() as u64)?;
+        offsets.push(offset);
+        position = offset + bytes.len();
+    }
+
+    let mut written = HEADER_LEN + sections.len() * SECTION_ENTRY_LEN;
+    for ((_, bytes), offset) in sections.iter().zip(offsets) {
+        w.write_all(&[0u8; SECTION_ALIGN][..offset - written])?;
+        w.write_all(bytes)?;
+        written = offset + bytes.len();
     }
 
     Ok(())
 }
 
-fn read_index<R: Read>(r: &mut R) -> Result<Index> {
-    let mut magic = [0u8; 8];
-    r.read_exact(&mut magic)?;
-    if &magic != MAGIC {
-        return Err(PlaidError::InvalidIndex(
-            "not a docbert-plaid index (magic bytes mismatch)".into(),
-        ));
+/// Where one section lives inside the mapped file.
+struct SectionEntry {
+    id: u32,
+    offset: usize,
+    byte_len: usize,
+}
+
+/// Sections of a mapped version-3 file.
+struct Sections {
+    map: Arc<Mmap>,
+    entries: Vec<SectionEntry>,
+}
+
+impl Sections {
+    /// Borrow section `id` as a buffer of `T`.
+    fn get<T: Pod>(&self, id: u32) -> Result<Buffer<T>> {
+        let entry =
+            self.entries.iter().find(|e| e.id == id).ok_or_else(|| {
+                PlaidError::InvalidIndex(format!("missing section {id}"))
+            })?;
+        if !entry.byte_len.is_multiple_of(size_of::<T>()) {
+            return Err(PlaidError::InvalidIndex(format!(
+                "section {id} is {} bytes, not a whole number of {}-byte elements",
+                entry.byte_len,
+                size_of::<T>(),
+            )));
+        }
+        Buffer::mapped(
+            Arc::clone(&self.map),
+            entry.offset,
+            entry.byte_len / size_of::<T>(),
+        )
+    }
+
+    /// Borrow a section of u64 offsets as `usize`s, copying only on
+    /// targets where the two differ in width.
+    fn get_offsets(&self, id: u32) -> Result<Buffer<usize>> {
+        if size_of::<usize>() == size_of::<u64>() {
+            self.get::<usize>(id)
+        } else {
+            Ok(self.get::<u64>(id)?.iter().map(|&o| o as usize).collect())
+        }
+    }
+
+    /// Borrow section `id`, which must hold exactly `len` elements.
+    fn get_exact<T: Pod>(&self, id: u32, len: usize) -> Result<Buffer<T>> {
+        let buffer = self.get::<T>(id)?;
+        expect_len(id, buffer.len(), len)?;
+        Ok(buffer)
+    }
+}
+
+fn expect_len(id: u32, got: usize, want: usize) -> Result<()> {
+    if got != want {
+        return Err(PlaidError::InvalidIndex(format!(
+            "section {id} holds {got} elements, expected {want}",
+        )));
     }
+    Ok(())
+}
 
-    let version = read_u32(r)?;
-    if version != FORMAT_VERSION {
+/// Reject offsets that go backwards, which would slice out of bounds.
+fn expect_ascending(id: u32, offsets: &[usize]) -> Result<()> {
+    if offsets.windows(2).any(|pair| pair[0] > pair[1]) {
         return Err(PlaidError::InvalidIndex(format!(
-            "unsupported plaid index version {version}, expected {FORMAT_VERSION}",
+            "section {id} offsets are not ascending",
         )));
     }
+    Ok(())
+}
+
+/// Reject indices of `limit` or more, which search would use unchecked.
+fn expect_below(id: u32, values: &[u32], limit: usize) -> Result<()> {
+    if let Some(&value) = values.iter().find(|&&v| v as usize >= limit) {
+        return Err(PlaidError::InvalidIndex(format!(
+            "section {id} holds {value}, out of range 0..{limit}",
+        )));
+    }
+    Ok(())
+}
+
+/// A mapped version-3 file with its header and section table parsed.
+struct MappedFile {
+    params: IndexParams,
+    n_documents: usize,
+    n_tokens: usize,
+    sections: Sections,
+}
+
+fn parse_mapped(map: Arc<Mmap>) -> Result<MappedFile> {
+    if map.len() < HEADER_LEN {
+        return Err(PlaidError::InvalidIndex(
+            "plaid index header is truncated".into(),
+        ));
+    }
+    let mut header = &map[12..HEADER_LEN];
+    let section_count = read_u32(&mut header)? as usize;
+    let dim = read_u32(&mut header)? as usize;
+    let nbits = read_u32(&mut header)?;
+    let k_centroids = read_u32(&mut header)? as usize;
+    let max_kmeans_iters = read_u32(&mut header)? as usize;
+    let n_documents = read_u64(&mut header)? as usize;
+    let n_tokens = read_u64(&mut header)? as usize;
+
+    if dim == 0 || k_centroids == 0 || !matches!(nbits, 1 | 2 | 4 | 8) {
+        return Err(PlaidError::InvalidIndex(
+            "plaid index header has invalid dim/k_centroids/nbits".into(),
+        ));
+    }
+
+    let table_end = section_count
+        .checked_mul(SECTION_ENTRY_LEN)
+        .and_then(|len| len.checked_add(HEADER_LEN))
+        .filter(|&end| end <= map.len())
+        .ok_or_else(|| {
+            PlaidError::InvalidIndex("plaid section table is truncated".into())
+        })?;
+    let mut table = &map[HEADER_LEN..table_end];
+    let mut entries = Vec::with_capacity(section_count);
+    for _ in 0..section_count {
+        let id = read_u32(&mut table)?;
+        let _reserved = read_u32(&mut table)?;
+        let offset = read_u64(&mut table)? as usize;
+        let byte_len = read_u64(&mut table)? as usize;
+        entries.push(SectionEntry {
+            id,
+            offset,
+            byte_len,
+        });
+    }
+
+    Ok(MappedFile {
+        params: IndexParams {
+            dim,
+            nbits,
+            k_centroids,
+            max_kmeans_iters,
+            kmeans: KMeansOptions::default(),
+        },
+        n_documents,
+        n_tokens,
+        sections: Sections { map, entries },
+    })
+}
+
+/// Copy the codec sections out of `file`; they're small next to the
+/// per-token sections and the codec is used on every query.
+fn read_codec(file: &MappedFile) -> Result<ResidualCodec> {
+    let IndexParams {
+        dim,
+        nbits,
+        k_centroids,
+        ..
+    } = file.params;
+    let num_buckets = 1usize << nbits;
+    let sections = &file.sections;
+    let codec = ResidualCodec {
+        nbits,
+        dim,
+        centroids: sections
+            .get_exact::<f32>(SECTION_CENTROIDS, k_centroids * dim)?
+            .to_vec(),
+        bucket_cutoffs: sections
+            .get_exact::<f32>(SECTION_BUCKET_CUTOFFS, num_buckets - 1)?
+            .to_vec(),
+        bucket_weights: sections
+            .get_exact::<f32>(SECTION_BUCKET_WEIGHTS, num_buckets)?
+            .to_vec(),
+    };
+    codec.validate()?;
+    Ok(codec)
+}
+
+/// Borrow the per-document sections of `file` into an [`Index`] that
+/// uses `codec`.
+fn read_documents(
+    file: MappedFile,
+    codec: Arc<ResidualCodec>,
+) -> Result<Index> {
+    let MappedFile {
+        params,
+        n_documents,
+        n_tokens,
+        sections,
+    } = file;
+    let packed_bytes = packed_bytes_per_vector(params.dim, params.nbits);
+
+    let doc_ids = sections.get_exact::<u64>(SECTION_DOC_IDS, n_documents)?;
+    let doc_offsets = sections.get_offsets(SECTION_DOC_OFFSETS)?;
+    expect_len(SECTION_DOC_OFFSETS, doc_offsets.len(), n_documents + 1)?;
+    if doc_offsets[0] != 0 || doc_offsets[n_documents] != n_tokens {
+        return Err(PlaidError::InvalidIndex(
+            "doc offsets do not span the encoded tokens".into(),
+        ));
+    }
+    expect_ascending(SECTION_DOC_OFFSETS, &doc_offsets)?;
+    let doc_centroid_ids =
+        sections.get_exact::<u32>(SECTION_CENTROID_IDS, n_tokens)?;
+    let doc_residual_bytes =
+        sections.get_exact::<u8>(SECTION_RESIDUALS, n_tokens * packed_bytes)?;
+
+    let k_centroids = params.k_centroids;
+    expect_below(SECTION_CENTROID_IDS, &doc_centroid_ids, k_centroids)?;
+    let ivf_offsets = sections.get_offsets(SECTION_IVF_OFFSETS)?;
+    expect_len(SECTION_IVF_OFFSETS, ivf_offsets.len(), k_centroids + 1)?;
+    let ivf_postings = sections.get::<u32>(SECTION_IVF_POSTINGS)?;
+    if ivf_offsets[0] != 0 || ivf_offsets[k_centroids] != ivf_postings.len() {
+        return Err(PlaidError::InvalidIndex(
+            "IVF offsets do not span the postings".into(),
+        ));
+    }
+    expect_ascending(SECTION_IVF_OFFSETS, &ivf_offsets)?;
+    expect_below(SECTION_IVF_POSTINGS, &ivf_postings, n_documents)?;
 
+    Ok(Index {
+        params,
+        codec,
+        doc_ids,
+        doc_centroid_ids,
+        doc_residual_bytes,
+        doc_offsets,
+        ivf: InvertedFile {
+            offsets: ivf_offsets,
+            postings: ivf_postings,
+        },
+    })
+}
+
+/// Read the body of a version-2 file, positioned just past the version.
+///
+/// Version 2 interleaves each token's centroid id with its codes and
+/// doesn't store the inverted file, so everything is copied into owned
+/// buffers and the IVF is rebuilt from the tokens.
+fn read_v2<R: Read>(r: &mut R) -> Result<Index> {
     let dim = read_u32(r)? as usize;
     let nbits = read_u32(r)?;
     let k_centroids = read_u32(r)? as usize;
@@ -158,6 +622,7 @@ fn read_index<R: Read>(r: &mut R) -> Result<Index> {
         nbits,
         k_centroids,
         max_kmeans_iters,
+        kmeans: KMeansOptions::default(),
     };
 
     let centroids = read_f32_vec(r, k_centroids * dim)?;
@@ -208,47 +673,32 @@ fn read_index<R: Read>(r: &mut R) -> Result<Index> {
 
     Ok(Index {
         params,
-        codec,
-        doc_ids,
-        doc_centroid_ids,
-        doc_residual_bytes,
-        doc_offsets,
+        codec: Arc::new(codec),
+        doc_ids: doc_ids.into(),
+        doc_centroid_ids: doc_centroid_ids.into(),
+        doc_residual_bytes: doc_residual_bytes.into(),
+        doc_offsets: doc_offsets.into(),
         ivf,
     })
 }
 
-fn write_u32<W: Write>(w: &mut W, v: u32) -> Result<()> {
+pub(crate) fn write_u32<W: Write>(w: &mut W, v: u32) -> Result<()> {
     w.write_all(&v.to_le_bytes())?;
     Ok(())
 }
 
-fn write_u64<W: Write>(w: &mut W, v: u64) -> Result<()> {
+pub(crate) fn write_u64<W: Write>(w: &mut W, v: u64) -> Result<()> {
     w.write_all(&v.to_le_bytes())?;
     Ok(())
 }
 
-fn write_f32_slice<W: Write>(w: &mut W, slice: &[f32]) -> Result<()> {
-    w.write_all(bytemuck::cast_slice(slice))?;
-    Ok(())
-}
-
-fn write_u32_slice<W: Write>(w: &mut W, slice: &[u32]) -> Result<()> {
-    w.write_all(bytemuck::cast_slice(slice))?;
-    Ok(())
-}
-
-fn write_u64_slice<W: Write>(w: &mut W, slice: &[u64]) -> Result<()> {
-    w.write_all(bytemuck::cast_slice(slice))?;
-    Ok(())
-}
-
-fn read_u32<R: Read>(r: &mut R) -> Result<u32> {
+pub(crate) fn read_u32<R: Read>(r: &mut R) -> Result<u32> {
     let mut buf = [0u8; 4];
     r.read_exact(&mut buf)?;
     Ok(u32::from_le_bytes(buf))
 }
 
-fn read_u64<R: Read>(r: &mut R) -> Result<u64> {
+pub(crate) fn read_u64<R: Read>(r: &mut R) -> Result<u64> {
     let mut buf = [0u8; 8];
     r.read_exact(&mut buf)?;
     Ok(u64::from_le_bytes(buf))
@@ -277,6 +727,53 @@ mod tests {
     use super::*;
     use crate::index::{DocumentTokens, build_index};
 
+    /// Write `index` in the version-2 layout that predates the section
+    /// table, exactly as older releases did.
+    fn write_v2(index: &Index, path: &Path) {
+        let mut w = BufWriter::new(File::create(path).unwrap());
+        let params = &index.params;
+        w.write_all(MAGIC).unwrap();
+        write_u32(&mut w, LEGACY_PACKED_VERSION).unwrap();
+        write_u32(&mut w, params.dim as u32).unwrap();
+        write_u32(&mut w, params.nbits).unwrap();
+        write_u32(&mut w, params.k_centroids as u32).unwrap();
+        write_u32(&mut w, params.max_kmeans_iters as u32).unwrap();
+        write_u64(&mut w, index.doc_ids.len() as u64).unwrap();
+        write_f32_slice(&mut w, &index.codec.centroids).unwrap();
+        write_f32_slice(&mut w, &index.codec.bucket_cutoffs).unwrap();
+        write_f32_slice(&mut w, &index.codec.bucket_weights).unwrap();
+        write_u64_slice(&mut w, &index.doc_ids).unwrap();
+        let token_counts: Vec<u32> = (0..index.num_documents())
+            .map(|i| index.doc_token_count(i) as u32)
+            .collect();
+        write_u32_slice(&mut w, &token_counts).unwrap();
+        let packed_bytes = index.codec.packed_bytes();
+        for i in 0..index.num_tokens() {
+            write_u32(&mut w, index.doc_centroid_ids[i]).unwrap();
+            w.write_all(
+                &index.doc_residual_bytes
+                    [i * packed_bytes..(i + 1) * packed_bytes],
+            )
+            .unwrap();
+        }
+        w.flush().unwrap();
+    }
+
+    fn write_f32_slice<W: Write>(w: &mut W, slice: &[f32]) -> Result<()> {
+        w.write_all(bytemuck::cast_slice(slice))?;
+        Ok(())
+    }
+
+    fn write_u32_slice<W: Write>(w: &mut W, slice: &[u32]) -> Result<()> {
+        w.write_all(bytemuck::cast_slice(slice))?;
+        Ok(())
+    }
+
+    fn write_u64_slice<W: Write>(w: &mut W, slice: &[u64]) -> Result<()> {
+        w.write_all(bytemuck::cast_slice(slice))?;
+        Ok(())
+    }
+
     fn small_corpus() -> Vec<DocumentTokens> {
         vec![
             DocumentTokens {
@@ -303,6 +800,7 @@ mod tests {
             nbits: 2,
             k_centroids: 2,
             max_kmeans_iters: 50,
+            kmeans: KMeansOptions::default(),
         }
     }
 
@@ -461,4 +959,145 @@ mod tests {
             "expected InvalidIndex with version message, got {err:?}",
         );
     }
+
+    fn version_of(path: &Path) -> u32 {
+        let bytes = std::fs::read(path).unwrap();
+        u32::from_le_bytes(bytes[8..12].try_into().unwrap())
+    }
+
+    #[test]
+    fn load_maps_version_three_files_without_copying() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("index.plaid");
+        let index = build_index(&small_corpus(), default_params()).unwrap();
+        assert!(!index.doc_centroid_ids.is_mapped());
+
+        save(&index, &path).unwrap();
+        assert_eq!(version_of(&path), FORMAT_VERSION);
+        let loaded = load(&path).unwrap();
+
+        assert!(loaded.doc_ids.is_mapped());
+        assert!(loaded.doc_centroid_ids.is_mapped());
+        assert!(loaded.doc_residual_bytes.is_mapped());
+        assert!(loaded.ivf.postings.is_mapped());
+        assert_eq!(loaded.doc_offsets, index.doc_offsets);
+        assert_eq!(loaded.doc_centroid_ids, index.doc_centroid_ids);
+        assert_eq!(loaded.doc_residual_bytes, index.doc_residual_bytes);
+        assert_eq!(loaded.ivf.offsets, index.ivf.offsets);
+        assert_eq!(loaded.ivf.postings, index.ivf.postings);
+    }
+
+    #[test]
+    fn load_reads_version_two_and_save_upgrades_it() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("index.plaid");
+        let index = build_index(&small_corpus(), default_params()).unwrap();
+        write_v2(&index, &path);
+
+        let legacy = load(&path).unwrap();
+        assert!(!legacy.doc_centroid_ids.is_mapped());
+        assert_eq!(legacy.doc_ids, index.doc_ids);
+        assert_eq!(legacy.doc_centroid_ids, index.doc_centroid_ids);
+        assert_eq!(legacy.doc_residual_bytes, index.doc_residual_bytes);
+        assert_eq!(legacy.ivf.postings, index.ivf.postings);
+
+        save(&legacy, &path).unwrap();
+        assert_eq!(version_of(&path), FORMAT_VERSION);
+        let upgraded = load(&path).unwrap();
+        assert!(upgraded.doc_centroid_ids.is_mapped());
+        assert_eq!(upgraded.doc_centroid_ids, index.doc_centroid_ids);
+        assert_eq!(upgraded.ivf.offsets, index.ivf.offsets);
+    }
+
+    #[test]
+    fn save_over_a_mapped_file_leaves_the_open_index_intact() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("index.plaid");
+        let index = build_index(&small_corpus(), default_params()).unwrap();
+        save(&index, &path).unwrap();
+        let mapped = load(&path).unwrap();
+
+        let mut docs = small_corpus();
+        docs.truncate(2);
+        save(&build_index(&docs, default_params()).unwrap(), &path).unwrap();
+
+        assert_eq!(mapped.doc_ids, index.doc_ids);
+        assert_eq!(mapped.doc_residual_bytes, index.doc_residual_bytes);
+        assert_eq!(load(&path).unwrap().num_documents(), 2);
+        assert!(!tmp.path().join("index.plaid.tmp").exists());
+    }
+
+    #[test]
+    fn load_rejects_a_truncated_version_three_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("index.plaid");
+        let index = build_index(&small_corpus(), default_params()).unwrap();
+        save(&index, &path).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &bytes[..bytes.len() - 4]).unwrap();
+
+        let err = load(&path).unwrap_err();
+        assert!(
+            matches!(err, PlaidError::InvalidIndex(ref m) if m.contains("past the end")),
+            "expected InvalidIndex for truncated section, got {err:?}",
+        );
+    }
+
+    #[test]
+    fn load_ignores_unknown_sections() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("index.plaid");
+        let index = build_index(&small_corpus(), default_params()).unwrap();
+        save(&index, &path).unwrap();
+        // Renumber the first table entry (centroids) to an unknown id
+        // and append a copy with the real id pointing at the same data.
+        let mut bytes = std::fs::read(&path).unwrap();
+        let entry = bytes[HEADER_LEN..HEADER_LEN + SECTION_ENTRY_LEN].to_vec();
+        bytes[HEADER_LEN..HEADER_LEN + 4].copy_from_slice(&99u32.to_le_bytes());
+        let count = u32::from_le_bytes(bytes[12..16].try_into().unwrap());
+        let table_end = HEADER_LEN + count as usize * SECTION_ENTRY_LEN;
+        // The first section starts past the table's padding, so there is
+        // room for one more entry without moving any data.
+        bytes[table_end..table_end + SECTION_ENTRY_LEN].copy_from_slice(&entry);
+        bytes[12..16].copy_from_slice(&(count + 1).to_le_bytes());
+        std::fs::write(&path, &bytes).unwrap();
+
+        let loaded = load(&path).unwrap();
+        assert_eq!(loaded.codec.centroids, index.codec.centroids);
+    }
+
+    /// Overwrite the first `u32` of section `id` in a saved file.
+    fn corrupt_section(path: &Path, id: u32, value: u32) {
+        let mut bytes = std::fs::read(path).unwrap();
+        let count = u32::from_le_bytes(bytes[12..16].try_into().unwrap());
+        let entry = (0..count as usize)
+            .map(|i| HEADER_LEN + i * SECTION_ENTRY_LEN)
+            .find(|&at| bytes[at..at + 4] == id.to_le_bytes())
+            .unwrap();
+        let offset = u64::from_le_bytes(
+            bytes[entry + 8..entry + 16].try_into().unwrap(),
+        ) as usize;
+        bytes[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+        std::fs::write(path, &bytes).unwrap();
+    }
+
+    #[test]
+    fn load_rejects_out_of_range_centroid_ids_and_postings() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("index.plaid");
+        let index = build_index(&small_corpus(), default_params()).unwrap();
+
+        for (id, value) in [
+            (SECTION_CENTROID_IDS, index.params.k_centroids as u32),
+            (SECTION_IVF_POSTINGS, 3),
+        ] {
+            save(&index, &path).unwrap();
+            corrupt_section(&path, id, value);
+            let err = load(&path).unwrap_err();
+            assert!(
+                matches!(err, PlaidError::InvalidIndex(ref m) if m.contains("out of range")),
+                "section {id}: expected InvalidIndex, got {err:?}",
+            );
+        }
+    }
 }
@@ -30,6 +30,13 @@
 //! decode in step 5. Callers that want the legacy behaviour (every
 //! probed candidate decodes, no pruning) can pass
 //! `n_candidate_docs = None` and `centroid_score_threshold = None`.
+//!
+//! [`search_filtered`] takes a [`DocFilter`] of the document positions a
+//! caller may see (one collection, say) and applies it in step 3, so
+//! centroid interaction and decode only ever spend their budget on
+//! allowed documents.
+
+use std::time::{Duration, Instant};
 
 use candle_core::{Device, Tensor};
 
@@ -39,6 +46,7 @@ use crate::{
     device::default_device,
     distance::dot,
     index::Index,
+    simd::{Kernel, MaxSim},
 };
 
 /// Tunable knobs for a single search call.
@@ -106,6 +114,102 @@ pub struct SearchResult {
     pub score: f32,
 }
 
+/// Per-stage counters and timings recorded by [`search_with_stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SearchStats {
+    /// Documents reachable through the probed (and unpruned) centroids.
+    pub probed_docs: usize,
+    /// Documents that survived centroid interaction and were decoded.
+    pub decoded_docs: usize,
+    /// Time spent scoring centroids, gathering postings and running
+    /// centroid interaction.
+    pub probe: Duration,
+    /// Time spent decoding survivors and computing exact MaxSim.
+    pub decode: Duration,
+}
+
+/// Bitmap over an [`Index`]'s document positions, restricting
+/// [`search_filtered`] to the documents it contains.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocFilter {
+    words: Vec<u64>,
+    len: usize,
+    count: usize,
+}
+
+impl DocFilter {
+    /// A filter over `len` documents that allows none of them.
+    pub fn none(len: usize) -> Self {
+        Self {
+            words: vec![0; len.div_ceil(64)],
+            len,
+            count: 0,
+        }
+    }
+
+    /// A filter over `len` documents allowing the positions `allowed`
+    /// yields.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a position is `len` or larger.
+    pub fn from_positions(
+        len: usize,
+        allowed: impl IntoIterator<Item = usize>,
+    ) -> Self {
+        let mut filter = Self::none(len);
+        for position in allowed {
+            filter.insert(position);
+        }
+        filter
+    }
+
+    /// Allow the document at `position`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `position` is out of range.
+    pub fn insert(&mut self, position: usize) {
+        assert!(
+            position < self.len,
+            "DocFilter: position {position} out of range 0..{}",
+            self.len,
+        );
+        let (word, bit) = (position / 64, 1u64 << (position % 64));
+        if self.words[word] & bit == 0 {
+            self.words[word] |= bit;
+            self.count += 1;
+        }
+    }
+
+    /// Whether the document at `position` is allowed. Positions past the
+    /// end are not.
+    pub fn contains(&self, position: usize) -> bool {
+        position < self.len
+            && self.words[position / 64] & (1u64 << (position % 64)) != 0
+    }
+
+    /// Number of documents the filter covers, allowed or not.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the filter covers no documents at all.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of allowed documents.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Allowed positions, ascending.
+    pub fn positions(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.len).filter(|&position| self.contains(position))
+    }
+}
+
 /// Run a PLAID-style search over `index` and return the top-`top_k`
 /// documents ranked by ColBERT MaxSim against `query_tokens`.
 ///
@@ -129,6 +233,67 @@ pub fn search(
     query_tokens: &[f32],
     params: SearchParams,
 ) -> Result<Vec<SearchResult>> {
+    search_with_stats(index, query_tokens, params).map(|(results, _)| results)
+}
+
+/// [`search`], also reporting how many candidates each stage kept and
+/// how long the probe and decode stages took.
+///
+/// # Errors
+///
+/// Same as [`search`].
+///
+/// # Panics
+///
+/// Same as [`search`].
+pub fn search_with_stats(
+    index: &Index,
+    query_tokens: &[f32],
+    params: SearchParams,
+) -> Result<(Vec<SearchResult>, SearchStats)> {
+    search_inner(index, query_tokens, params, None)
+}
+
+/// [`search_with_stats`], returning only documents whose position is in
+/// `allowed`.
+///
+/// The filter is applied while candidates are gathered, before
+/// centroid interaction shortlists them, so a small allow-list inside a
+/// large index still fills its top-k instead of losing its documents to
+/// better-scoring ones it can't return. When the probed centroids reach
+/// fewer than `top_k` allowed documents, every allowed document becomes
+/// a candidate.
+///
+/// # Errors
+///
+/// Same as [`search`].
+///
+/// # Panics
+///
+/// Same as [`search`], and if `allowed` doesn't cover exactly
+/// `index.num_documents()` documents.
+pub fn search_filtered(
+    index: &Index,
+    query_tokens: &[f32],
+    params: SearchParams,
+    allowed: &DocFilter,
+) -> Result<(Vec<SearchResult>, SearchStats)> {
+    assert_eq!(
+        allowed.len(),
+        index.num_documents(),
+        "search_filtered: filter covers {} documents but the index has {}",
+        allowed.len(),
+        index.num_documents(),
+    );
+    search_inner(index, query_tokens, params, Some(allowed))
+}
+
+fn search_inner(
+    index: &Index,
+    query_tokens: &[f32],
+    params: SearchParams,
+    allowed: Option<&DocFilter>,
+) -> Result<(Vec<SearchResult>, SearchStats)> {
     let dim = index.params.dim;
     assert!(params.top_k > 0, "search: top_k must be positive");
     assert!(params.n_probe > 0, "search: n_probe must be positive");
@@ -139,10 +304,15 @@ pub fn search(
         dim,
     );
 
-    if query_tokens.is_empty() || index.num_documents() == 0 {
-        return Ok(Vec::new());
+    let mut stats = SearchStats::default();
+    if query_tokens.is_empty()
+        || index.num_documents() == 0
+        || allowed.is_some_and(|allowed| allowed.count() == 0)
+    {
+        return Ok((Vec::new(), stats));
     }
 
+    let probe_started = Instant::now();
     let n_centroids = index.codec.num_centroids();
     let n_probe = params.n_probe.min(n_centroids);
 
@@ -178,7 +348,8 @@ pub fn search(
     //      Centroid pruning drops probed centroids whose best per-query
     //      score sits below the caller's threshold — skipping them
     //      both shrinks the candidate set and saves work later in
-    //      centroid interaction.
+    //      centroid interaction. Documents outside `allowed` are never
+    //      candidates.
     let mut candidate_docs: Vec<bool> = vec![false; index.num_documents()];
     for query_token in query_tokens.chunks_exact(dim) {
         for centroid_id in
@@ -195,13 +366,26 @@ pub fn search(
         }
     }
 
+    let is_allowed = |idx: usize| allowed.is_none_or(|a| a.contains(idx));
     let mut candidate_idxs: Vec<usize> = candidate_docs
         .iter()
         .enumerate()
         .filter_map(|(idx, &is_cand)| {
-            (is_cand && index.doc_token_count(idx) > 0).then_some(idx)
+            (is_cand && is_allowed(idx) && index.doc_token_count(idx) > 0)
+                .then_some(idx)
         })
         .collect();
+    // An allow-list the probe barely touches would come back short;
+    // hand all of it to centroid interaction instead.
+    if let Some(allowed) = allowed
+        && candidate_idxs.len() < params.top_k
+    {
+        candidate_idxs = allowed
+            .positions()
+            .filter(|&idx| index.doc_token_count(idx) > 0)
+            .collect();
+    }
+    stats.probed_docs = candidate_idxs.len();
 
     // 3. Centroid interaction: when the caller set `n_candidate_docs`,
     //    cheaply rank candidates via the precomputed query-centroid
@@ -247,8 +431,12 @@ pub fn search(
         );
     }
 
-    // 5. Score every surviving candidate with one batched MaxSim matmul
-    //    on decoded tokens.
+    stats.decoded_docs = candidate_idxs.len();
+    stats.probe = probe_started.elapsed();
+
+    // 5. Score every surviving candidate with exact MaxSim on decoded
+    //    tokens.
+    let decode_started = Instant::now();
     let mut scored: Vec<SearchResult> = if candidate_idxs.is_empty() {
         Vec::new()
     } else {
@@ -269,7 +457,8 @@ pub fn search(
             .then_with(|| a.doc_id.cmp(&b.doc_id))
     });
     scored.truncate(params.top_k);
-    Ok(scored)
+    stats.decode = decode_started.elapsed();
+    Ok((scored, stats))
 }
 
 /// Indices of the `n` centroids with the highest dot-product against
@@ -470,11 +659,13 @@ fn approx_centroid_interaction_score(
 /// Score a batch of candidate docs against `query_tokens` with a
 /// padding-free packed MaxSim, matching PLAID §4.5.
 ///
-/// Two decode strategies share the downstream MaxSim reduction:
+/// Two strategies, picked by device:
 ///
-/// - On CPU the fastest path is the hand-rolled LUT walk — a byte
-///   lookup per packed slot beats candle's generic `index_select`
-///   because there's no kernel-launch overhead to amortise.
+/// - On CPU, [`fused_maxsim`] decodes each token with a LUT walk —
+///   a byte lookup per packed slot beats candle's generic
+///   `index_select` because there's no kernel-launch overhead to
+///   amortise — and scores it against the query straight away with
+///   the vectorised kernels in [`crate::simd`].
 /// - On CUDA the right move is to keep the whole flow on-device:
 ///   upload the centroid bank and weights LUT once, gather token
 ///   residuals via two `index_select` ops, add, and feed straight
@@ -482,25 +673,62 @@ fn approx_centroid_interaction_score(
 ///   (one thread per packed byte, centroid-add, matmul) without
 ///   re-implementing it by hand.
 ///
-/// The per-doc max-then-sum reduction still runs on the host because
-/// candle doesn't expose a native segmented-max primitive and we want
-/// to avoid materialising the padded `[n_docs, max_len, n_q]` tensor
-/// the paper explicitly rejects.
+/// On CUDA the per-doc max-then-sum reduction still runs on the host
+/// because candle doesn't expose a native segmented-max primitive and
+/// we want to avoid materialising the padded `[n_docs, max_len, n_q]`
+/// tensor the paper explicitly rejects.
 fn batch_maxsim(
     query_tokens: &[f32],
     candidate_idxs: &[usize],
     index: &Index,
     dim: usize,
 ) -> Result<Vec<(usize, f32)>> {
+    let device = default_device();
+    if matches!(device, Device::Cpu) {
+        return fused_maxsim(
+            query_tokens,
+            candidate_idxs,
+            index,
+            Kernel::detect(),
+        );
+    }
     batch_maxsim_with_cap(
         query_tokens,
         candidate_idxs,
         index,
         dim,
         decode_chunk_capacity(dim),
+        device,
     )
 }
 
+/// CPU implementation of [`batch_maxsim`]: score every candidate with
+/// one fused decode + MaxSim pass on `kernel`.
+///
+/// Only one decoded token is ever live, so unlike the device path
+/// this needs no chunking to bound memory.
+fn fused_maxsim(
+    query_tokens: &[f32],
+    candidate_idxs: &[usize],
+    index: &Index,
+    kernel: Kernel,
+) -> Result<Vec<(usize, f32)>> {
+    index.codec.validate()?;
+    let decode_table = DecodeTable::new(&index.codec);
+    let mut maxsim =
+        MaxSim::new(kernel, &index.codec, &decode_table, query_tokens);
+    Ok(candidate_idxs
+        .iter()
+        .map(|&doc_idx| {
+            let score = maxsim.score(
+                index.doc_centroid_ids(doc_idx),
+                index.doc_residual_bytes(doc_idx),
+            );
+            (doc_idx, score)
+        })
+        .collect())
+}
+
 /// Target resident-tensor budget per chunk during on-device decode.
 ///
 /// The GPU decode + MaxSim pipeline holds roughly five
@@ -532,8 +760,8 @@ fn decode_chunk_capacity(dim: usize) -> usize {
     (DECODE_CHUNK_BUDGET_BYTES / per_token).max(64)
 }
 
-/// Chunked implementation of [`batch_maxsim`] that caps how many
-/// candidate tokens may be resident on the device at once.
+/// Chunked device implementation of [`batch_maxsim`] that caps how
+/// many candidate tokens may be resident on `device` at once.
 ///
 /// Splits `candidate_idxs` into contiguous runs whose cumulative
 /// token count stays under `max_tokens_per_chunk`, decodes each run
@@ -556,6 +784,7 @@ fn batch_maxsim_with_cap(
     index: &Index,
     dim: usize,
     max_tokens_per_chunk: usize,
+    device: &Device,
 ) -> Result<Vec<(usize, f32)>> {
     let n_q = query_tokens.len() / dim;
 
@@ -569,7 +798,6 @@ fn batch_maxsim_with_cap(
         return Ok(candidate_idxs.iter().map(|&i| (i, 0.0)).collect());
     }
 
-    let device = default_device();
     let q_t = Tensor::from_slice(query_tokens, (n_q, dim), device)?;
     let q_transposed = q_t.t()?.contiguous()?;
 
@@ -604,11 +832,7 @@ fn batch_maxsim_with_cap(
             decode_table: &decode_table,
             device,
         };
-        let decoded = if matches!(device, Device::Cpu) {
-            decode_on_cpu(&chunk_ctx, &mut chunk_offsets)?
-        } else {
-            decode_on_device(&chunk_ctx, &mut chunk_offsets)?
-        };
+        let decoded = decode_on_device(&chunk_ctx, &mut chunk_offsets)?;
 
         // `[chunk_tokens, dim] × [dim, n_q]` GEMM, same shape rule as
         // the pre-chunk implementation but bounded by the chunk cap.
@@ -649,7 +873,7 @@ fn batch_maxsim_with_cap(
     Ok(out)
 }
 
-/// Inputs the two decode strategies share. Grouping them keeps the
+/// Inputs to [`decode_on_device`]. Grouping them keeps the
 /// call-site readable and satisfies clippy's `too_many_arguments`
 /// without leaking implementation details into the public API.
 struct DecodeCtx<'a> {
@@ -661,55 +885,6 @@ struct DecodeCtx<'a> {
     device: &'a Device,
 }
 
-/// CPU decode path: walk every candidate token, look up each packed
-/// byte in the precomputed LUT, add the centroid dimensions, push
-/// into one big row-major buffer, then upload as a single tensor.
-///
-/// Fast on CPU because the whole loop is contiguous writes plus a
-/// 256-entry table of 4 KiB at `nbits=2` that lives in L1 the entire
-/// call — measurably faster than routing every token through candle
-/// `index_select`, which pays kernel-launch overhead per gather.
-fn decode_on_cpu(
-    ctx: &DecodeCtx<'_>,
-    offsets: &mut Vec<usize>,
-) -> Result<Tensor> {
-    let codec = &ctx.index.codec;
-    let packed_bytes = codec.packed_bytes();
-    let mut packed: Vec<f32> = Vec::with_capacity(ctx.total_tokens * ctx.dim);
-    let mut ev = crate::codec::EncodedVector {
-        centroid_id: 0,
-        codes: vec![0u8; packed_bytes],
-    };
-    for &doc_idx in ctx.candidate_idxs {
-        let cids = ctx.index.doc_centroid_ids(doc_idx);
-        let bytes = ctx.index.doc_residual_bytes(doc_idx);
-        for (i, &cid) in cids.iter().enumerate() {
-            ev.centroid_id = cid;
-            ev.codes.copy_from_slice(
-                &bytes[i * packed_bytes..(i + 1) * packed_bytes],
-            );
-            let decoded =
-                codec.decode_vector_with_table(&ev, ctx.decode_table)?;
-            // Row-wise L2 normalise, mirroring decode_on_device's tail
-            // so CPU and GPU paths return comparable scores. See
-            // `l2_normalize_rows` for why this matters.
-            let norm = decoded
-                .iter()
-                .map(|v| v * v)
-                .sum::<f32>()
-                .sqrt()
-                .max(1e-12_f32);
-            packed.extend(decoded.iter().map(|v| v / norm));
-        }
-        offsets.push(packed.len() / ctx.dim);
-    }
-    Ok(Tensor::from_vec(
-        packed,
-        (ctx.total_tokens, ctx.dim),
-        ctx.device,
-    )?)
-}
-
 /// GPU decode path: upload the centroid bank and the 256-entry LUT,
 /// then recover every candidate token via two `index_select` gathers
 /// and an add — all on-device. This keeps the decode inside the same
@@ -799,6 +974,7 @@ mod tests {
     use crate::{
         distance::dot,
         index::{DocumentTokens, IndexParams, build_index},
+        kmeans::KMeansOptions,
     };
 
     fn params() -> IndexParams {
@@ -807,6 +983,7 @@ mod tests {
             nbits: 2,
             k_centroids: 2,
             max_kmeans_iters: 50,
+            kmeans: KMeansOptions::default(),
         }
     }
 
@@ -959,6 +1136,82 @@ mod tests {
         assert_eq!(out.len(), 1);
     }
 
+    #[test]
+    fn search_with_stats_matches_search_and_counts_candidates() {
+        let index = build_index(&corpus(), params()).unwrap();
+        let params = SearchParams {
+            top_k: 3,
+            n_probe: 2,
+            n_candidate_docs: None,
+            centroid_score_threshold: None,
+        };
+        let plain = search(&index, &[1.0, 0.0], params).unwrap();
+        let (with_stats, stats) =
+            search_with_stats(&index, &[1.0, 0.0], params).unwrap();
+
+        assert_eq!(plain, with_stats);
+        // Both centroids are probed, so every document is reachable and,
+        // without centroid interaction, every one of them is decoded.
+        assert_eq!(stats.probed_docs, 3);
+        assert_eq!(stats.decoded_docs, 3);
+    }
+
+    #[test]
+    fn doc_filter_tracks_allowed_positions() {
+        let mut filter = DocFilter::from_positions(70, [1, 65, 65]);
+        assert_eq!(filter.count(), 2);
+        assert!(filter.contains(65));
+        assert!(!filter.contains(2));
+        assert!(!filter.contains(700));
+        filter.insert(2);
+        assert_eq!(filter.positions().collect::<Vec<_>>(), vec![1, 2, 65]);
+        assert_eq!(DocFilter::none(3).count(), 0);
+    }
+
+    #[test]
+    fn search_filtered_returns_only_allowed_documents() {
+        let index = build_index(&corpus(), params()).unwrap();
+        let params = SearchParams {
+            top_k: 3,
+            n_probe: 2,
+            n_candidate_docs: Some(8),
+            centroid_score_threshold: None,
+        };
+        // Positions 1 and 2 hold docs 2 and 3.
+        let allowed = DocFilter::from_positions(3, [1, 2]);
+        let (out, stats) =
+            search_filtered(&index, &[1.0, 0.0], params, &allowed).unwrap();
+        let ids: Vec<u64> = out.iter().map(|r| r.doc_id).collect();
+        assert_eq!(ids, vec![3, 2]);
+        assert_eq!(stats.probed_docs, 2);
+
+        let (out, _) =
+            search_filtered(&index, &[1.0, 0.0], params, &DocFilter::none(3))
+                .unwrap();
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn search_filtered_reaches_allowed_documents_the_probe_misses() {
+        // An east query probing one centroid never reaches doc 2, which
+        // is all north; allowing only doc 2 must still return it.
+        let index = build_index(&corpus(), params()).unwrap();
+        let params = SearchParams {
+            top_k: 1,
+            n_probe: 1,
+            n_candidate_docs: Some(4),
+            centroid_score_threshold: Some(0.5),
+        };
+        let unfiltered = search(&index, &[1.0, 0.0], params).unwrap();
+        assert_ne!(unfiltered[0].doc_id, 2);
+
+        let allowed = DocFilter::from_positions(3, [1]);
+        let (out, _) =
+            search_filtered(&index, &[1.0, 0.0], params, &allowed).unwrap();
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].doc_id, 2);
+    }
+
     #[test]
     fn search_scores_are_non_increasing() {
         let index = build_index(&corpus(), params()).unwrap();
@@ -1067,7 +1320,7 @@ mod tests {
 
         // Standard MaxSim: Σ max_j q_i · d_j.
         let mut expected = 0.0f32;
-        for q in query.chunks_exact(2) {
+        for q in query.as_chunks::<2>().0 {
             let best = decoded
                 .iter()
                 .map(|d| dot(q, d))
@@ -1092,6 +1345,7 @@ mod tests {
             nbits: 4,
             k_centroids: 2,
             max_kmeans_iters: 50,
+            kmeans: KMeansOptions::default(),
         };
         let index = build_index(&corpus(), params_4bit).unwrap();
         let out = search(
@@ -1497,7 +1751,9 @@ mod tests {
             })
             .collect();
         let expected: f32 = query
-            .chunks_exact(2)
+            .as_chunks::<2>()
+            .0
+            .iter()
             .map(|q| {
                 decoded
                     .iter()
@@ -1512,8 +1768,8 @@ mod tests {
 
     /// When run on CPU-backed candle, `decode_on_device` still lives on
     /// the code path the CUDA build takes. This test forces that path
-    /// and compares every decoded row to the byte-walking CPU decoder
-    /// at f32 precision. Any drift in the gather/reshape/narrow chain
+    /// and compares every decoded row to the scalar CPU kernel at f32
+    /// precision. Any drift in the gather/reshape/narrow chain
     /// would show up here without needing a real GPU.
     #[test]
     fn gpu_decode_path_matches_cpu_decode_element_wise() {
@@ -1544,14 +1800,30 @@ mod tests {
             .flatten()
             .collect();
 
+        let dim = index.params.dim;
+        let packed_bytes = index.codec.packed_bytes();
         let mut offsets_cpu = vec![0usize];
-        let decoded_cpu = decode_on_cpu(&ctx, &mut offsets_cpu).unwrap();
-        let cpu_rows: Vec<f32> = decoded_cpu
-            .to_vec2::<f32>()
-            .unwrap()
-            .into_iter()
-            .flatten()
-            .collect();
+        let mut cpu_rows = Vec::new();
+        let mut row = vec![0.0f32; dim];
+        for &doc_idx in &candidate_idxs {
+            let bytes = index.doc_residual_bytes(doc_idx);
+            for (&cid, codes) in index
+                .doc_centroid_ids(doc_idx)
+                .iter()
+                .zip(bytes.chunks_exact(packed_bytes))
+            {
+                let norm_sq = Kernel::scalar().decode_into(
+                    &index.codec,
+                    &decode_table,
+                    cid,
+                    codes,
+                    &mut row,
+                );
+                let norm = norm_sq.sqrt().max(1e-12);
+                cpu_rows.extend(row.iter().map(|v| v / norm));
+            }
+            offsets_cpu.push(cpu_rows.len() / dim);
+        }
 
         assert_eq!(offsets_gpu, offsets_cpu);
         assert_eq!(gpu_rows.len(), cpu_rows.len());
@@ -1650,6 +1922,7 @@ mod tests {
             &index,
             index.params.dim,
             usize::MAX,
+            &Device::Cpu,
         )
         .unwrap();
 
@@ -1664,6 +1937,7 @@ mod tests {
                 &index,
                 index.params.dim,
                 cap,
+                &Device::Cpu,
             )
             .unwrap();
             assert_eq!(
@@ -1688,6 +1962,41 @@ mod tests {
         }
     }
 
+    /// The fused CPU path must score like the candle decode + matmul
+    /// path the CUDA build takes, on every kernel this CPU supports.
+    #[test]
+    fn fused_maxsim_matches_device_path_on_every_kernel() {
+        let index = build_index(&corpus(), params()).unwrap();
+        let candidate_idxs: Vec<usize> = (0..index.num_documents()).collect();
+        let query = [0.8f32, 0.6, -0.6, 0.8, 1.0, 0.0];
+
+        let device = batch_maxsim_with_cap(
+            &query,
+            &candidate_idxs,
+            &index,
+            index.params.dim,
+            usize::MAX,
+            &Device::Cpu,
+        )
+        .unwrap();
+        for kernel in Kernel::available() {
+            let fused =
+                fused_maxsim(&query, &candidate_idxs, &index, kernel).unwrap();
+            assert_eq!(fused.len(), device.len());
+            for (got, want) in fused.iter().zip(&device) {
+                assert_eq!(got.0, want.0);
+                assert!(
+                    (got.1 - want.1).abs() < 1e-5,
+                    "{}: doc {} scored {} vs {}",
+                    kernel.name(),
+                    got.0,
+                    got.1,
+                    want.1,
+                );
+            }
+        }
+    }
+
     #[test]
     fn decode_chunk_capacity_scales_inversely_with_dim() {
         // Bigger models must produce smaller chunks so the resident
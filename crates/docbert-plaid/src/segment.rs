@@ -0,0 +1,1231 @@
+//! Segmented index: immutable segments that share one codec.
+//!
+//! [`crate::update::apply_update`] produces a whole new [`Index`], and
+//! saving it rewrites the whole file, even when a sync changed a single
+//! note. A [`SegmentedIndex`] instead keeps a list of immutable
+//! [`Segment`]s encoded against one shared codec:
+//!
+//! - [`SegmentedIndex::apply_update`] encodes new and changed documents
+//!   into one small delta segment and marks the copies they replace, and
+//!   deleted documents, in the older segments' [`DeletionBitmap`]s;
+//! - search runs on every segment and merges their top-k lists; an
+//!   [`IndexFilter`] restricts it to a subset of the documents;
+//! - [`SegmentedIndex::plan_merge`] picks segments to compact under a
+//!   [`MergePolicy`]. [`SegmentedIndex::merge`] does the copying against
+//!   a snapshot, so it can run on a background thread, and
+//!   [`SegmentedIndex::install_merge`] swaps the result in.
+//!
+//! On disk the index is a small manifest plus a directory next to it
+//! (see [`segments_dir`]) holding the codec and one file per segment, in
+//! the version-3 layout of [`crate::persistence`]. Codec and segment
+//! files are named by ids that are never reused and are never rewritten,
+//! so [`SegmentedIndex::save`] writes only the files that don't exist
+//! yet, replaces the manifest, and then removes files the manifest no
+//! longer lists.
+//!
+//! Manifest layout, every field little-endian:
+//!
+//! ```text
+//! magic           : 8 bytes, b"PLAIDSEG"
+//! version         : u32     (currently 1)
+//! dim             : u32
+//! nbits           : u32
+//! k_centroids     : u32
+//! max_kmeans_iters: u32
+//! codec_id        : u64
+//! segment_count   : u64
+//! segments        : segment_count × { id: u64, n_documents: u64,
+//!                                     ceil(n_documents / 64) u64
+//!                                     deletion bitmap words }
+//! ```
+//!
+//! A single-file index written by [`crate::persistence::save`] loads as
+//! one segment without deletions; the next save moves it into the
+//! segmented layout.
+
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{BufReader, Read, Write},
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    PlaidError,
+    Result,
+    codec::ResidualCodec,
+    index::{Index, IndexParams, build_inverted_file_from_flat},
+    kmeans::KMeansOptions,
+    persistence::{self, read_u32, read_u64, write_u32, write_u64},
+    search::{self, DocFilter, SearchParams, SearchResult, SearchStats},
+    update::{self, IndexUpdate},
+};
+
+const MANIFEST_MAGIC: &[u8; 8] = b"PLAIDSEG";
+const MANIFEST_VERSION: u32 = 1;
+const SEGMENT_EXTENSION: &str = "seg";
+const CODEC_PREFIX: &str = "codec-";
+const LOCK_FILE: &str = "lock";
+
+/// Sort merged per-segment hits like [`crate::search::search`] does and
+/// keep the best `top_k`.
+fn rank(results: &mut Vec<SearchResult>, top_k: usize) {
+    results.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.doc_id.cmp(&b.doc_id))
+    });
+    results.truncate(top_k);
+}
+
+/// Directory holding the codec and segment files of the index whose
+/// manifest is at `manifest`: `plaid.idx` keeps them in `plaid.segments`.
+pub fn segments_dir(manifest: &Path) -> PathBuf {
+    manifest.with_extension("segments")
+}
+
+fn segment_file_name(id: u64) -> String {
+    format!("{id:016x}.{SEGMENT_EXTENSION}")
+}
+
+fn codec_file_name(id: u64) -> String {
+    format!("{CODEC_PREFIX}{id:016x}.idx")
+}
+
+/// A new id for a codec or segment file.
+///
+/// Ids come from the wall clock in nanoseconds, bumped past the last id
+/// handed out in this process, so two writers practically never pick
+/// the same name and a rebuilt index never reuses an old one.
+fn fresh_id() -> u64 {
+    static LAST: AtomicU64 = AtomicU64::new(0);
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos() as u64);
+    let mut last = LAST.load(Ordering::Relaxed);
+    loop {
+        let id = now.max(last + 1);
+        match LAST.compare_exchange_weak(
+            last,
+            id,
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+        ) {
+            Ok(_) => return id,
+            Err(current) => last = current,
+        }
+    }
+}
+
+/// Which documents of a segment have been deleted or replaced.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeletionBitmap {
+    words: Vec<u64>,
+    len: usize,
+    deleted: usize,
+}
+
+impl DeletionBitmap {
+    /// A bitmap over `len` documents, none of them deleted.
+    pub fn new(len: usize) -> Self {
+        Self {
+            words: vec![0; len.div_ceil(64)],
+            len,
+            deleted: 0,
+        }
+    }
+
+    fn from_words(words: Vec<u64>, len: usize) -> Result<Self> {
+        let tail_bits = len % 64;
+        let tail_clear =
+            tail_bits == 0 || words.last().is_none_or(|w| w >> tail_bits == 0);
+        if words.len() != len.div_ceil(64) || !tail_clear {
+            return Err(PlaidError::InvalidIndex(format!(
+                "deletion bitmap of {} words does not fit {len} documents",
+                words.len(),
+            )));
+        }
+        let deleted = words.iter().map(|w| w.count_ones() as usize).sum();
+        Ok(Self {
+            words,
+            len,
+            deleted,
+        })
+    }
+
+    /// Number of documents the bitmap covers.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the bitmap covers no documents at all.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Whether document `idx` is deleted.
+    pub fn contains(&self, idx: usize) -> bool {
+        idx < self.len && (self.words[idx / 64] >> (idx % 64)) & 1 == 1
+    }
+
+    /// Mark document `idx` deleted; returns `false` if it already was.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is out of range.
+    pub fn insert(&mut self, idx: usize) -> bool {
+        assert!(idx < self.len, "DeletionBitmap::insert: {idx} out of range");
+        if self.contains(idx) {
+            return false;
+        }
+        self.words[idx / 64] |= 1 << (idx % 64);
+        self.deleted += 1;
+        true
+    }
+
+    /// Number of deleted documents.
+    pub fn count(&self) -> usize {
+        self.deleted
+    }
+}
+
+/// Which documents of a [`SegmentedIndex`] a filtered search may return,
+/// built by [`SegmentedIndex::filter`]: one [`DocFilter`] per segment,
+/// with deleted documents already left out.
+///
+/// Only valid for the index it was built from; build a new one after
+/// every update or merge.
+#[derive(Debug, Clone)]
+pub struct IndexFilter {
+    segments: Vec<(u64, DocFilter)>,
+}
+
+impl IndexFilter {
+    /// Number of live documents the filter allows.
+    pub fn count(&self) -> usize {
+        self.segments.iter().map(|(_, filter)| filter.count()).sum()
+    }
+}
+
+/// One immutable piece of a [`SegmentedIndex`].
+#[derive(Debug, Clone)]
+pub struct Segment {
+    id: u64,
+    index: Index,
+    deleted: DeletionBitmap,
+}
+
+impl Segment {
+    fn new(id: u64, index: Index) -> Self {
+        let deleted = DeletionBitmap::new(index.num_documents());
+        Self { id, index, deleted }
+    }
+
+    /// Id naming the segment's file.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// The segment's documents, deleted ones included.
+    pub fn index(&self) -> &Index {
+        &self.index
+    }
+
+    /// Which of [`Segment::index`]'s documents no longer count.
+    pub fn deleted(&self) -> &DeletionBitmap {
+        &self.deleted
+    }
+
+    /// Whether document `doc_idx` of this segment is still searchable.
+    pub fn is_live(&self, doc_idx: usize) -> bool {
+        !self.deleted.contains(doc_idx)
+    }
+
+    /// Number of documents that are not deleted.
+    pub fn num_live(&self) -> usize {
+        self.index.num_documents() - self.deleted.count()
+    }
+
+    fn live_positions(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.index.num_documents()).filter(|&idx| self.is_live(idx))
+    }
+
+    fn deleted_ratio(&self) -> f32 {
+        match self.index.num_documents() {
+            0 => 0.0,
+            n => self.deleted.count() as f32 / n as f32,
+        }
+    }
+}
+
+/// When [`SegmentedIndex::plan_merge`] asks for a compaction.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MergePolicy {
+    /// Most segments an index should have; past this the smallest ones
+    /// are merged together.
+    pub max_segments: usize,
+    /// Fraction of deleted documents above which a segment is rewritten
+    /// without them.
+    pub max_deleted_ratio: f32,
+}
+
+impl Default for MergePolicy {
+    fn default() -> Self {
+        Self {
+            max_segments: 8,
+            max_deleted_ratio: 0.25,
+        }
+    }
+}
+
+/// Segments [`SegmentedIndex::merge`] should combine, in index order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergePlan {
+    pub segment_ids: Vec<u64>,
+}
+
+/// The output of [`SegmentedIndex::merge`], waiting to be installed.
+#[derive(Debug, Clone)]
+pub struct MergedSegment {
+    codec_id: u64,
+    /// Each source segment's id and the positions of the documents that
+    /// were live when the merge ran, in merged order.
+    sources: Vec<(u64, Vec<usize>)>,
+    index: Index,
+}
+
+impl MergedSegment {
+    /// Number of documents carried over from the source segments.
+    pub fn num_documents(&self) -> usize {
+        self.index.num_documents()
+    }
+}
+
+/// Holds the index's write lock until dropped; see [`lock`].
+#[derive(Debug)]
+pub struct IndexLock {
+    _file: File,
+}
+
+/// Take the exclusive write lock of the index whose manifest is at
+/// `manifest`, waiting for any other holder.
+///
+/// Writers hold it from loading the index to saving it, so two
+/// processes can't both build on the same manifest and lose one of
+/// their changes. Readers never need it.
+pub fn lock(manifest: &Path) -> Result<IndexLock> {
+    let dir = segments_dir(manifest);
+    std::fs::create_dir_all(&dir)?;
+    let file = File::options()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(dir.join(LOCK_FILE))?;
+    file.lock()?;
+    Ok(IndexLock { _file: file })
+}
+
+/// A PLAID index split into immutable segments over one shared codec.
+///
+/// A document id is live in at most one segment: updating it deletes
+/// the old copy before the new one is added.
+#[derive(Debug, Clone)]
+pub struct SegmentedIndex {
+    pub params: IndexParams,
+    pub codec: Arc<ResidualCodec>,
+    codec_id: u64,
+    segments: Vec<Segment>,
+}
+
+impl SegmentedIndex {
+    /// Wrap a freshly built (or single-file) index as the only segment.
+    pub fn from_index(index: Index) -> Self {
+        Self {
+            params: index.params,
+            codec: Arc::clone(&index.codec),
+            codec_id: fresh_id(),
+            segments: vec![Segment::new(fresh_id(), index)],
+        }
+    }
+
+    /// Id naming the codec's file. A rebuilt index gets a new one, so it
+    /// also tells two trainings of the codec apart.
+    pub fn codec_id(&self) -> u64 {
+        self.codec_id
+    }
+
+    /// The segments, oldest first.
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// Number of live documents across every segment.
+    pub fn num_documents(&self) -> usize {
+        self.segments.iter().map(Segment::num_live).sum()
+    }
+
+    /// Every live document id, segment by segment.
+    pub fn live_doc_ids(&self) -> impl Iterator<Item = u64> + '_ {
+        self.segments.iter().flat_map(|segment| {
+            segment
+                .live_positions()
+                .map(|idx| segment.index.doc_ids[idx])
+        })
+    }
+
+    /// Whether `doc_id` is live in some segment.
+    pub fn contains(&self, doc_id: u64) -> bool {
+        self.locate(doc_id).is_some()
+    }
+
+    /// The segment holding the live copy of `doc_id` and its position
+    /// inside that segment.
+    pub fn locate(&self, doc_id: u64) -> Option<(&Segment, usize)> {
+        self.segments.iter().rev().find_map(|segment| {
+            segment
+                .index
+                .position_of(doc_id)
+                .filter(|&idx| segment.is_live(idx))
+                .map(|idx| (segment, idx))
+        })
+    }
+
+    /// Number of live documents with a token assigned to `centroid_id`.
+    pub fn doc_frequency(&self, centroid_id: usize) -> usize {
+        self.segments
+            .iter()
+            .map(|segment| {
+                segment
+                    .index
+                    .ivf
+                    .docs_for_centroid(centroid_id)
+                    .iter()
+                    .filter(|&&idx| segment.is_live(idx as usize))
+                    .count()
+            })
+            .sum()
+    }
+
+    /// Number of live tokens assigned to each centroid, the input of
+    /// [`crate::drift::assignment_imbalance`].
+    pub fn centroid_token_counts(&self) -> Vec<usize> {
+        let mut counts = vec![0usize; self.params.k_centroids];
+        for segment in &self.segments {
+            for idx in segment.live_positions() {
+                for &centroid in segment.index.doc_centroid_ids(idx) {
+                    counts[centroid as usize] += 1;
+                }
+            }
+        }
+        counts
+    }
+
+    /// Apply `update` without touching any existing segment's files.
+    ///
+    /// Deleted documents, and the old copies of upserted ones, are
+    /// marked in their segments' deletion bitmaps. Upserts are encoded
+    /// against the shared codec into one new segment, whose id is
+    /// returned (`None` when there were no upserts). Segments left
+    /// without live documents stay until the next merge removes them,
+    /// so a merge running meanwhile can still be installed.
+    ///
+    /// # Errors
+    ///
+    /// Propagates [`PlaidError::Tensor`] from encoding the upserts.
+    ///
+    /// # Panics
+    ///
+    /// Same as [`crate::update::apply_update`].
+    ///
+    /// [`PlaidError::Tensor`]: crate::PlaidError::Tensor
+    pub fn apply_update(
+        &mut self,
+        update: IndexUpdate<'_>,
+    ) -> Result<Option<u64>> {
+        let delta =
+            update::encode_documents(self.params, &self.codec, update.upserts)?;
+
+        let mut to_remove: HashSet<u64> =
+            update.deletions.iter().copied().collect();
+        to_remove.extend(update.upserts.iter().map(|doc| doc.doc_id));
+        if !to_remove.is_empty() {
+            for segment in &mut self.segments {
+                for (idx, doc_id) in segment.index.doc_ids.iter().enumerate() {
+                    if to_remove.contains(doc_id) {
+                        segment.deleted.insert(idx);
+                    }
+                }
+            }
+        }
+
+        if update.upserts.is_empty() {
+            return Ok(None);
+        }
+        let id = fresh_id();
+        self.segments.push(Segment::new(id, delta));
+        Ok(Some(id))
+    }
+
+    /// Search every segment and merge their results into one top-k
+    /// list, ranked like [`crate::search::search`].
+    ///
+    /// # Errors
+    ///
+    /// Same as [`crate::search::search`].
+    ///
+    /// # Panics
+    ///
+    /// Same as [`crate::search::search`].
+    pub fn search(
+        &self,
+        query_tokens: &[f32],
+        params: SearchParams,
+    ) -> Result<Vec<SearchResult>> {
+        self.search_with_stats(query_tokens, params)
+            .map(|(results, _)| results)
+    }
+
+    /// [`SegmentedIndex::search`], also reporting the stage counters and
+    /// timings summed over every segment.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`crate::search::search`].
+    ///
+    /// # Panics
+    ///
+    /// Same as [`crate::search::search`].
+    pub fn search_with_stats(
+        &self,
+        query_tokens: &[f32],
+        params: SearchParams,
+    ) -> Result<(Vec<SearchResult>, SearchStats)> {
+        let mut results = Vec::new();
+        let mut stats = SearchStats::default();
+        for segment in &self.segments {
+            if segment.num_live() == 0 {
+                continue;
+            }
+            // Deleted documents can still rank inside their segment; ask
+            // for enough extra hits that dropping them leaves a full
+            // top-k.
+            let top_k = params.top_k + segment.deleted.count();
+            let segment_params = SearchParams {
+                top_k,
+                n_candidate_docs: params
+                    .n_candidate_docs
+                    .map(|n| n.max(top_k.saturating_mul(4))),
+                ..params
+            };
+            let (hits, segment_stats) = search::search_with_stats(
+                &segment.index,
+                query_tokens,
+                segment_params,
+            )?;
+            stats.probed_docs += segment_stats.probed_docs;
+            stats.decoded_docs += segment_stats.decoded_docs;
+            stats.probe += segment_stats.probe;
+            stats.decode += segment_stats.decode;
+
+            if segment.deleted.count() == 0 {
+                results.extend(hits);
+            } else {
+                let deleted: HashSet<u64> = (0..segment.index.num_documents())
+                    .filter(|&idx| !segment.is_live(idx))
+                    .map(|idx| segment.index.doc_ids[idx])
+                    .collect();
+                results.extend(
+                    hits.into_iter()
+                        .filter(|hit| !deleted.contains(&hit.doc_id)),
+                );
+            }
+        }
+
+        rank(&mut results, params.top_k);
+        Ok((results, stats))
+    }
+
+    /// An [`IndexFilter`] allowing the live documents whose id passes
+    /// `allow`.
+    ///
+    /// Walks every document id once, so callers searching the same
+    /// subset repeatedly should build the filter once and keep it for
+    /// as long as the index doesn't change.
+    pub fn filter(&self, mut allow: impl FnMut(u64) -> bool) -> IndexFilter {
+        let segments = self
+            .segments
+            .iter()
+            .map(|segment| {
+                let positions =
+                    segment.index.doc_ids.iter().enumerate().filter_map(
+                        |(idx, &doc_id)| {
+                            (segment.is_live(idx) && allow(doc_id))
+                                .then_some(idx)
+                        },
+                    );
+                let filter = DocFilter::from_positions(
+                    segment.index.num_documents(),
+                    positions,
+                );
+                (segment.id, filter)
+            })
+            .collect();
+        IndexFilter { segments }
+    }
+
+    /// [`SegmentedIndex::search_with_stats`] restricted to the documents
+    /// `filter` allows, applied during candidate generation (see
+    /// [`crate::search::search_filtered`]).
+    ///
+    /// # Errors
+    ///
+    /// Same as [`crate::search::search`].
+    ///
+    /// # Panics
+    ///
+    /// Same as [`crate::search::search`], and if `filter` was built
+    /// from a different list of segments.
+    pub fn search_filtered(
+        &self,
+        query_tokens: &[f32],
+        params: SearchParams,
+        filter: &IndexFilter,
+    ) -> Result<(Vec<SearchResult>, SearchStats)> {
+        assert!(
+            self.segments.len() == filter.segments.len()
+                && self
+                    .segments
+                    .iter()
+                    .zip(&filter.segments)
+                    .all(|(segment, (id, _))| segment.id == *id),
+            "search_filtered: filter was built for another index",
+        );
+        let mut results = Vec::new();
+        let mut stats = SearchStats::default();
+        for (segment, (_, allowed)) in
+            self.segments.iter().zip(&filter.segments)
+        {
+            if allowed.count() == 0 {
+                continue;
+            }
+            // Deleted documents are outside the filter, so unlike an
+            // unfiltered search there is nothing to oversample for.
+            let (hits, segment_stats) = search::search_filtered(
+                &segment.index,
+                query_tokens,
+                params,
+                allowed,
+            )?;
+            stats.probed_docs += segment_stats.probed_docs;
+            stats.decoded_docs += segment_stats.decoded_docs;
+            stats.probe += segment_stats.probe;
+            stats.decode += segment_stats.decode;
+            results.extend(hits);
+        }
+        rank(&mut results, params.top_k);
+        Ok((results, stats))
+    }
+
+    /// Segments worth compacting under `policy`, or `None` when the
+    /// index is in shape.
+    ///
+    /// Segments whose deleted fraction exceeds
+    /// [`MergePolicy::max_deleted_ratio`] are always included. While
+    /// merging would still leave more than
+    /// [`MergePolicy::max_segments`] segments, the smallest remaining
+    /// ones are added too.
+    pub fn plan_merge(&self, policy: MergePolicy) -> Option<MergePlan> {
+        let mut chosen: Vec<bool> = self
+            .segments
+            .iter()
+            .map(|s| {
+                s.deleted.count() > 0
+                    && s.deleted_ratio() > policy.max_deleted_ratio
+            })
+            .collect();
+        let mut by_size: Vec<usize> = (0..self.segments.len()).collect();
+        by_size.sort_by_key(|&i| self.segments[i].num_live());
+
+        let max_segments = policy.max_segments.max(1);
+        let remaining = |chosen: &[bool]| {
+            let n = chosen.iter().filter(|&&c| c).count();
+            self.segments.len() - n + usize::from(n > 0)
+        };
+        for i in by_size {
+            if remaining(&chosen) <= max_segments {
+                break;
+            }
+            chosen[i] = true;
+        }
+
+        let segment_ids: Vec<u64> = self
+            .segments
+            .iter()
+            .zip(&chosen)
+            .filter(|(_, chosen)| **chosen)
+            .map(|(segment, _)| segment.id)
+            .collect();
+        let worthwhile = match segment_ids.as_slice() {
+            [] => false,
+            [only] => self
+                .segments
+                .iter()
+                .any(|s| s.id == *only && s.deleted.count() > 0),
+            _ => true,
+        };
+        worthwhile.then_some(MergePlan { segment_ids })
+    }
+
+    /// Copy the live documents of the planned segments into one new
+    /// segment. Nothing is re-encoded: the segments share a codec.
+    ///
+    /// Only reads `self`, so it can run on a snapshot while the index
+    /// keeps changing; [`SegmentedIndex::install_merge`] reconciles the
+    /// result with whatever happened meanwhile.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PlaidError::InvalidIndex`] if a planned segment isn't
+    /// part of this index.
+    ///
+    /// [`PlaidError::InvalidIndex`]: crate::PlaidError::InvalidIndex
+    pub fn merge(&self, plan: &MergePlan) -> Result<MergedSegment> {
+        let packed_bytes = self.codec.packed_bytes();
+        let mut sources = Vec::with_capacity(plan.segment_ids.len());
+        let mut doc_ids = Vec::new();
+        let mut doc_centroid_ids = Vec::new();
+        let mut doc_residual_bytes = Vec::new();
+        let mut doc_offsets = vec![0usize];
+        for &id in &plan.segment_ids {
+            let segment =
+                self.segments.iter().find(|s| s.id == id).ok_or_else(|| {
+                    PlaidError::InvalidIndex(format!(
+                        "segment {id:016x} is not part of this index"
+                    ))
+                })?;
+            let live: Vec<usize> = segment.live_positions().collect();
+            for &idx in &live {
+                let index = &segment.index;
+                doc_ids.push(index.doc_ids[idx]);
+                doc_centroid_ids.extend_from_slice(index.doc_centroid_ids(idx));
+                doc_residual_bytes
+                    .extend_from_slice(index.doc_residual_bytes(idx));
+                doc_offsets.push(doc_centroid_ids.len());
+            }
+            sources.push((id, live));
+        }
+        debug_assert_eq!(
+            doc_residual_bytes.len(),
+            doc_centroid_ids.len() * packed_bytes
+        );
+
+        let ivf = build_inverted_file_from_flat(
+            &doc_centroid_ids,
+            &doc_offsets,
+            self.params.k_centroids,
+        );
+        Ok(MergedSegment {
+            codec_id: self.codec_id,
+            sources,
+            index: Index {
+                params: self.params,
+                codec: Arc::clone(&self.codec),
+                doc_ids: doc_ids.into(),
+                doc_centroid_ids: doc_centroid_ids.into(),
+                doc_residual_bytes: doc_residual_bytes.into(),
+                doc_offsets: doc_offsets.into(),
+                ivf,
+            },
+        })
+    }
+
+    /// Replace the merged segments with `merged`.
+    ///
+    /// Documents deleted from a source segment after the merge ran stay
+    /// deleted in the merged one. Returns `false`, leaving the index
+    /// unchanged, when a source segment is gone (another merge or a
+    /// rebuild got there first).
+    pub fn install_merge(&mut self, merged: MergedSegment) -> bool {
+        if merged.codec_id != self.codec_id {
+            return false;
+        }
+        let Some(positions) = merged
+            .sources
+            .iter()
+            .map(|(id, _)| self.segments.iter().position(|s| s.id == *id))
+            .collect::<Option<Vec<usize>>>()
+        else {
+            return false;
+        };
+
+        let mut segment = Segment::new(fresh_id(), merged.index);
+        let mut merged_idx = 0;
+        for ((_, live), &position) in merged.sources.iter().zip(&positions) {
+            let source = &self.segments[position];
+            for &idx in live {
+                if !source.is_live(idx) {
+                    segment.deleted.insert(merged_idx);
+                }
+                merged_idx += 1;
+            }
+        }
+
+        let insert_at = positions.iter().copied().min().unwrap_or(0);
+        let old = std::mem::take(&mut self.segments);
+        let mut segment = (segment.num_live() > 0).then_some(segment);
+        for (position, existing) in old.into_iter().enumerate() {
+            if position == insert_at
+                && let Some(segment) = segment.take()
+            {
+                self.segments.push(segment);
+            }
+            if !positions.contains(&position) {
+                self.segments.push(existing);
+            }
+        }
+        true
+    }
+
+    /// Plan, merge and install in one go.
+    ///
+    /// Returns whether anything was merged.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`SegmentedIndex::merge`].
+    pub fn compact(&mut self, policy: MergePolicy) -> Result<bool> {
+        let Some(plan) = self.plan_merge(policy) else {
+            return Ok(false);
+        };
+        let merged = self.merge(&plan)?;
+        Ok(self.install_merge(merged))
+    }
+
+    /// Persist the index with its manifest at `manifest`.
+    ///
+    /// Only codec and segment files that don't exist yet are written, so
+    /// saving after an update writes the new delta segment and a fresh
+    /// manifest. The manifest is replaced atomically; afterwards, files
+    /// it no longer lists are removed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PlaidError::Io`] for any write failure.
+    ///
+    /// [`PlaidError::Io`]: crate::PlaidError::Io
+    pub fn save(&self, manifest: &Path) -> Result<()> {
+        let dir = segments_dir(manifest);
+        std::fs::create_dir_all(&dir)?;
+
+        let codec_path = dir.join(codec_file_name(self.codec_id));
+        if !codec_path.exists() {
+            persistence::save_codec(&self.params, &self.codec, &codec_path)?;
+        }
+        for segment in &self.segments {
+            let path = dir.join(segment_file_name(segment.id));
+            if !path.exists() {
+                persistence::save_segment(&segment.index, &path)?;
+            }
+        }
+        persistence::write_atomically(manifest, |w| self.write_manifest(w))?;
+        self.remove_unlisted_files(&dir);
+        Ok(())
+    }
+
+    fn write_manifest<W: Write>(&self, w: &mut W) -> Result<()> {
+        w.write_all(MANIFEST_MAGIC)?;
+        write_u32(w, MANIFEST_VERSION)?;
+        write_u32(w, self.params.dim as u32)?;
+        write_u32(w, self.params.nbits)?;
+        write_u32(w, self.params.k_centroids as u32)?;
+        write_u32(w, self.params.max_kmeans_iters as u32)?;
+        write_u64(w, self.codec_id)?;
+        write_u64(w, self.segments.len() as u64)?;
+        for segment in &self.segments {
+            write_u64(w, segment.id)?;
+            write_u64(w, segment.index.num_documents() as u64)?;
+            for &word in &segment.deleted.words {
+                write_u64(w, word)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Best-effort removal of codec and segment files this index doesn't
+    /// reference. Failures are ignored; the next save tries again.
+    fn remove_unlisted_files(&self, dir: &Path) {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+        let mut listed: HashSet<String> = self
+            .segments
+            .iter()
+            .map(|segment| segment_file_name(segment.id))
+            .collect();
+        listed.insert(codec_file_name(self.codec_id));
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let ours = name.ends_with(&format!(".{SEGMENT_EXTENSION}"))
+                || (name.starts_with(CODEC_PREFIX) && name.ends_with(".idx"));
+            if ours && !listed.contains(&name) {
+                let _ = std::fs::remove_file(entry.path());
+            }
+        }
+    }
+
+    /// Open the index whose manifest is at `manifest`.
+    ///
+    /// Segments are memory-mapped, so this costs a few syscalls per
+    /// segment whatever the corpus size. A single-file index loads as
+    /// one segment.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PlaidError::Io`] for any read failure, or
+    /// [`PlaidError::InvalidIndex`] if the manifest or a file it lists
+    /// is malformed or disagrees with it.
+    ///
+    /// [`PlaidError::Io`]: crate::PlaidError::Io
+    /// [`PlaidError::InvalidIndex`]: crate::PlaidError::InvalidIndex
+    pub fn load(manifest: &Path) -> Result<Self> {
+        // A writer can replace the manifest and remove the files it
+        // stopped listing between our reading the manifest and opening
+        // those files. Reading the new manifest fixes that.
+        let mut attempts = 0;
+        loop {
+            match Self::load_once(manifest) {
+                Err(PlaidError::Io(err))
+                    if err.kind() == std::io::ErrorKind::NotFound
+                        && attempts < 3
+                        && manifest.exists() =>
+                {
+                    attempts += 1;
+                }
+                other => return other,
+            }
+        }
+    }
+
+    fn load_once(manifest: &Path) -> Result<Self> {
+        if persistence::has_index_magic(manifest)? {
+            return Ok(Self::from_index(persistence::load(manifest)?));
+        }
+
+        let mut r = BufReader::new(File::open(manifest)?);
+        let mut magic = [0u8; 8];
+        r.read_exact(&mut magic)?;
+        if &magic != MANIFEST_MAGIC {
+            return Err(PlaidError::InvalidIndex(
+                "not a docbert-plaid index (magic bytes mismatch)".into(),
+            ));
+        }
+        let version = read_u32(&mut r)?;
+        if version != MANIFEST_VERSION {
+            return Err(PlaidError::InvalidIndex(format!(
+                "unsupported plaid segment manifest version {version}, \
+                 expected {MANIFEST_VERSION}",
+            )));
+        }
+        let params = IndexParams {
+            dim: read_u32(&mut r)? as usize,
+            nbits: read_u32(&mut r)?,
+            k_centroids: read_u32(&mut r)? as usize,
+            max_kmeans_iters: read_u32(&mut r)? as usize,
+            kmeans: KMeansOptions::default(),
+        };
+        let codec_id = read_u64(&mut r)?;
+        let segment_count = read_u64(&mut r)? as usize;
+
+        let dir = segments_dir(manifest);
+        let (codec_params, codec) =
+            persistence::load_codec(&dir.join(codec_file_name(codec_id)))?;
+        if (
+            codec_params.dim,
+            codec_params.nbits,
+            codec_params.k_centroids,
+        ) != (params.dim, params.nbits, params.k_centroids)
+        {
+            return Err(PlaidError::InvalidIndex(
+                "segment manifest and codec disagree on dim/nbits/k_centroids"
+                    .into(),
+            ));
+        }
+        let codec = Arc::new(codec);
+
+        let mut segments = Vec::with_capacity(segment_count.min(1024));
+        for _ in 0..segment_count {
+            let id = read_u64(&mut r)?;
+            let n_documents = read_u64(&mut r)? as usize;
+            let words = (0..n_documents.div_ceil(64))
+                .map(|_| read_u64(&mut r))
+                .collect::<Result<Vec<u64>>>()?;
+            let index = persistence::load_segment(
+                &dir.join(segment_file_name(id)),
+                &codec,
+            )?;
+            if index.num_documents() != n_documents {
+                return Err(PlaidError::InvalidIndex(format!(
+                    "segment {id:016x} holds {} documents, manifest says {n_documents}",
+                    index.num_documents(),
+                )));
+            }
+            let deleted = DeletionBitmap::from_words(words, n_documents)?;
+            segments.push(Segment { id, index, deleted });
+        }
+
+        Ok(Self {
+            params,
+            codec,
+            codec_id,
+            segments,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::{DocumentTokens, build_index};
+
+    fn doc(doc_id: u64, tokens: Vec<f32>) -> DocumentTokens {
+        let n_tokens = tokens.len() / 2;
+        DocumentTokens {
+            doc_id,
+            tokens,
+            n_tokens,
+        }
+    }
+
+    fn seed() -> SegmentedIndex {
+        let docs = vec![
+            doc(1, vec![0.0, 0.0, 0.1, 0.2]),
+            doc(2, vec![10.0, 10.0, 10.2, 9.9]),
+            doc(3, vec![0.3, -0.2, 9.7, 10.2]),
+        ];
+        let params = IndexParams {
+            dim: 2,
+            nbits: 2,
+            k_centroids: 2,
+            max_kmeans_iters: 50,
+            kmeans: KMeansOptions::default(),
+        };
+        SegmentedIndex::from_index(build_index(&docs, params).unwrap())
+    }
+
+    fn search_params(top_k: usize) -> SearchParams {
+        SearchParams {
+            top_k,
+            n_probe: 2,
+            n_candidate_docs: None,
+            centroid_score_threshold: None,
+        }
+    }
+
+    fn ids(results: &[SearchResult]) -> Vec<u64> {
+        let mut ids: Vec<u64> = results.iter().map(|r| r.doc_id).collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    #[test]
+    fn deletion_bitmap_tracks_deleted_documents() {
+        let mut bitmap = DeletionBitmap::new(70);
+        assert!(bitmap.insert(3));
+        assert!(bitmap.insert(69));
+        assert!(!bitmap.insert(3));
+        assert!(bitmap.contains(69) && !bitmap.contains(4));
+        assert_eq!(bitmap.count(), 2);
+
+        let words = bitmap.words.clone();
+        assert_eq!(DeletionBitmap::from_words(words, 70).unwrap(), bitmap);
+        assert!(DeletionBitmap::from_words(vec![1 << 10], 5).is_err());
+    }
+
+    #[test]
+    fn apply_update_adds_a_delta_segment_and_hides_replaced_copies() {
+        let mut index = seed();
+        let id = index
+            .apply_update(IndexUpdate {
+                deletions: &[3],
+                upserts: &[doc(2, vec![0.1, 0.1]), doc(4, vec![9.9, 9.9])],
+            })
+            .unwrap();
+
+        assert_eq!(index.segments().len(), 2);
+        assert_eq!(index.segments()[1].id(), id.unwrap());
+        assert_eq!(index.segments()[0].deleted().count(), 2);
+        assert_eq!(index.num_documents(), 3);
+        let mut live: Vec<u64> = index.live_doc_ids().collect();
+        live.sort_unstable();
+        assert_eq!(live, vec![1, 2, 4]);
+        assert_eq!(index.locate(2).unwrap().0.id(), id.unwrap());
+        assert!(!index.contains(3));
+        let tokens: usize = index.centroid_token_counts().iter().sum();
+        assert_eq!(tokens, 4, "doc 1's two tokens and one each for 2 and 4");
+
+        let hits = index.search(&[0.0, 0.0, 10.0, 10.0], search_params(10));
+        assert_eq!(ids(&hits.unwrap()), vec![1, 2, 4]);
+    }
+
+    #[test]
+    fn search_fills_top_k_past_deleted_documents() {
+        let mut index = seed();
+        index
+            .apply_update(IndexUpdate {
+                deletions: &[1],
+                upserts: &[],
+            })
+            .unwrap();
+        let hits = index.search(&[0.0, 0.0], search_params(2)).unwrap();
+        assert_eq!(ids(&hits), vec![2, 3]);
+    }
+
+    #[test]
+    fn search_filtered_spans_segments_and_skips_deleted_copies() {
+        let mut index = seed();
+        index
+            .apply_update(IndexUpdate {
+                deletions: &[],
+                upserts: &[doc(2, vec![0.1, 0.1]), doc(4, vec![9.9, 9.9])],
+            })
+            .unwrap();
+        // Doc 2's old copy in the first segment is deleted, so only the
+        // new one may come back.
+        let filter = index.filter(|doc_id| doc_id == 2 || doc_id == 3);
+        assert_eq!(filter.count(), 2);
+        let (hits, stats) = index
+            .search_filtered(&[10.0, 10.0], search_params(10), &filter)
+            .unwrap();
+        assert_eq!(ids(&hits), vec![2, 3]);
+        assert_eq!(stats.decoded_docs, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "filter was built for another index")]
+    fn search_filtered_rejects_a_stale_filter() {
+        let mut index = seed();
+        let filter = index.filter(|_| true);
+        index
+            .apply_update(IndexUpdate {
+                deletions: &[],
+                upserts: &[doc(4, vec![9.9, 9.9])],
+            })
+            .unwrap();
+        let _ = index.search_filtered(&[0.0, 0.0], search_params(1), &filter);
+    }
+
+    #[test]
+    fn merge_compacts_segments_and_keeps_later_deletions() {
+        let mut index = seed();
+        for id in 10..13 {
+            index
+                .apply_update(IndexUpdate {
+                    deletions: &[],
+                    upserts: &[doc(id, vec![id as f32, 0.0])],
+                })
+                .unwrap();
+        }
+        let policy = MergePolicy {
+            max_segments: 2,
+            max_deleted_ratio: 0.5,
+        };
+        let plan = index.plan_merge(policy).unwrap();
+        assert_eq!(plan.segment_ids.len(), 3);
+        let merged = index.merge(&plan).unwrap();
+        assert_eq!(merged.num_documents(), 3);
+
+        // A delete that lands while the merge runs must survive it.
+        index
+            .apply_update(IndexUpdate {
+                deletions: &[11],
+                upserts: &[],
+            })
+            .unwrap();
+        assert!(index.install_merge(merged));
+        assert_eq!(index.segments().len(), 2);
+        assert!(!index.contains(11));
+        assert!(index.contains(10) && index.contains(12));
+        assert!(index.plan_merge(policy).is_none());
+    }
+
+    #[test]
+    fn plan_merge_rewrites_a_segment_with_many_deletions() {
+        let mut index = seed();
+        index
+            .apply_update(IndexUpdate {
+                deletions: &[1, 2],
+                upserts: &[],
+            })
+            .unwrap();
+        assert!(index.compact(MergePolicy::default()).unwrap());
+        assert_eq!(index.segments()[0].index().num_documents(), 1);
+        assert_eq!(index.segments()[0].deleted().count(), 0);
+    }
+
+    #[test]
+    fn save_writes_only_new_segments_and_round_trips() {
+        let tmp = tempfile::tempdir().unwrap();
+        let manifest = tmp.path().join("plaid.idx");
+        let mut index = seed();
+        index.save(&manifest).unwrap();
+        let dir = segments_dir(&manifest);
+        let base = dir.join(segment_file_name(index.segments()[0].id()));
+        let base_modified = base.metadata().unwrap().modified().unwrap();
+
+        index
+            .apply_update(IndexUpdate {
+                deletions: &[3],
+                upserts: &[doc(5, vec![0.2, 0.2])],
+            })
+            .unwrap();
+        index.save(&manifest).unwrap();
+        assert_eq!(base.metadata().unwrap().modified().unwrap(), base_modified);
+
+        let loaded = SegmentedIndex::load(&manifest).unwrap();
+        assert_eq!(loaded.segments().len(), 2);
+        assert!(loaded.segments()[0].index().doc_ids.is_mapped());
+        assert_eq!(
+            loaded.segments()[0].deleted(),
+            index.segments()[0].deleted()
+        );
+        let query = [0.0, 0.0, 10.0, 10.0];
+        assert_eq!(
+            loaded.search(&query, search_params(10)).unwrap(),
+            index.search(&query, search_params(10)).unwrap(),
+        );
+
+        // Compaction drops the old files on the next save.
+        index
+            .compact(MergePolicy {
+                max_segments: 1,
+                max_deleted_ratio: 0.5,
+            })
+            .unwrap();
+        index.save(&manifest).unwrap();
+        assert!(!base.exists());
+        let files = std::fs::read_dir(&dir).unwrap().count();
+        assert_eq!(files, 2, "the codec and the merged segment");
+    }
+
+    #[test]
+    fn load_reads_a_single_file_index_as_one_segment() {
+        let tmp = tempfile::tempdir().unwrap();
+        let manifest = tmp.path().join("plaid.idx");
+        let single = seed().segments()[0].index().clone();
+        persistence::save(&single, &manifest).unwrap();
+
+        let loaded = SegmentedIndex::load(&manifest).unwrap();
+        assert_eq!(loaded.segments().len(), 1);
+        assert_eq!(loaded.num_documents(), 3);
+
+        loaded.save(&manifest).unwrap();
+        assert!(!persistence::has_index_magic(&manifest).unwrap());
+        assert_eq!(SegmentedIndex::load(&manifest).unwrap().num_documents(), 3);
+    }
+}
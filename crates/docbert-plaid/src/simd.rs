@@ -0,0 +1,961 @@
+//! Vectorised CPU kernels for the search hot path.
+//!
+//! Without CUDA, query time is dominated by two loops: the dot
+//! products behind [`distance::dot`] and the residual decode feeding
+//! MaxSim. This module provides explicitly vectorised versions of
+//! both for AVX2 (+FMA) and AVX-512 on x86-64 and NEON on aarch64,
+//! alongside a scalar fallback that is the reference every other
+//! kernel is checked against.
+//!
+//! The instruction set is picked at runtime: [`Kernel::detect`] asks
+//! the CPU what it supports on first call and caches the fastest
+//! match for the rest of the process, the same way
+//! [`default_device`] caches the candle device. A [`Kernel`] can only
+//! be obtained for an instruction set the running CPU supports, so
+//! all of its methods are safe to call.
+//!
+//! [`MaxSim`] fuses decode and scoring: each candidate token is
+//! decoded into one `dim`-wide scratch row, scored against every
+//! query token, and overwritten by the next, so the decoded candidate
+//! set is never materialised. The same scorer takes full-precision
+//! documents through [`MaxSim::dense`], so exact search and
+//! within-document scoring run on these kernels too.
+//!
+//! Vectorised kernels sum in a different order from the scalar one,
+//! so their dot products agree with it within float rounding rather
+//! than bit-for-bit. Decoded values are bit-identical across kernels.
+//!
+//! [`distance::dot`]: crate::distance::dot
+//! [`default_device`]: crate::device::default_device
+
+use std::sync::OnceLock;
+
+use crate::codec::{DecodeTable, ResidualCodec};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Isa {
+    Scalar,
+    #[cfg(target_arch = "x86_64")]
+    Avx2,
+    #[cfg(target_arch = "x86_64")]
+    Avx512,
+    #[cfg(target_arch = "aarch64")]
+    Neon,
+}
+
+/// An instruction set the kernels run on.
+///
+/// # Examples
+///
+/// ```
+/// use docbert_plaid::simd::Kernel;
+///
+/// let a = [1.0, 2.0, 3.0];
+/// let b = [4.0, 5.0, 6.0];
+/// for kernel in Kernel::available() {
+///     assert_eq!(kernel.dot(&a, &b), 32.0);
+/// }
+/// assert_eq!(Kernel::available()[0], Kernel::scalar());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Kernel(Isa);
+
+static DETECTED: OnceLock<Kernel> = OnceLock::new();
+
+impl Kernel {
+    /// The portable scalar kernel, available everywhere.
+    pub const fn scalar() -> Self {
+        Self(Isa::Scalar)
+    }
+
+    /// The fastest kernel this CPU supports. Cached on first call.
+    pub fn detect() -> Self {
+        *DETECTED.get_or_init(|| {
+            *Self::available()
+                .last()
+                .expect("the scalar kernel is always available")
+        })
+    }
+
+    /// Every kernel this CPU supports, slowest first. Always starts
+    /// with [`Kernel::scalar`].
+    pub fn available() -> Vec<Self> {
+        let mut kernels = vec![Self::scalar()];
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("avx2")
+                && is_x86_feature_detected!("fma")
+            {
+                kernels.push(Self(Isa::Avx2));
+            }
+            if is_x86_feature_detected!("avx512f") {
+                kernels.push(Self(Isa::Avx512));
+            }
+        }
+        #[cfg(target_arch = "aarch64")]
+        {
+            if std::arch::is_aarch64_feature_detected!("neon") {
+                kernels.push(Self(Isa::Neon));
+            }
+        }
+        kernels
+    }
+
+    /// Short name of the instruction set, e.g. for bench labels.
+    pub fn name(self) -> &'static str {
+        match self.0 {
+            Isa::Scalar => "scalar",
+            #[cfg(target_arch = "x86_64")]
+            Isa::Avx2 => "avx2",
+            #[cfg(target_arch = "x86_64")]
+            Isa::Avx512 => "avx512",
+            #[cfg(target_arch = "aarch64")]
+            Isa::Neon => "neon",
+        }
+    }
+
+    /// Dot product of two equal-length vectors.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `a.len() != b.len()`.
+    pub fn dot(self, a: &[f32], b: &[f32]) -> f32 {
+        assert_eq!(
+            a.len(),
+            b.len(),
+            "dot requires equal-length vectors (got {} and {})",
+            a.len(),
+            b.len(),
+        );
+        // SAFETY: a `Kernel` only ever holds an instruction set that
+        // `available` saw the CPU support.
+        unsafe {
+            match self.0 {
+                Isa::Scalar => Scalar::dot(a, b),
+                #[cfg(target_arch = "x86_64")]
+                Isa::Avx2 => Avx2::dot(a, b),
+                #[cfg(target_arch = "x86_64")]
+                Isa::Avx512 => Avx512::dot(a, b),
+                #[cfg(target_arch = "aarch64")]
+                Isa::Neon => Neon::dot(a, b),
+            }
+        }
+    }
+
+    /// Decode one encoded token into `out` and return its squared L2
+    /// norm.
+    ///
+    /// `out` holds exactly what
+    /// [`ResidualCodec::decode_vector_with_table`] returns for the
+    /// same token, without allocating.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `table` was built for another `nbits`, if `codes` or
+    /// `out` don't match the codec's packed width and `dim`, or if
+    /// `centroid_id` is out of range.
+    pub fn decode_into(
+        self,
+        codec: &ResidualCodec,
+        table: &DecodeTable,
+        centroid_id: u32,
+        codes: &[u8],
+        out: &mut [f32],
+    ) -> f32 {
+        assert_eq!(
+            table.nbits(),
+            codec.nbits,
+            "decode_into: table nbits {} != codec nbits {}",
+            table.nbits(),
+            codec.nbits,
+        );
+        assert_eq!(
+            codes.len(),
+            codec.packed_bytes(),
+            "decode_into: expected {} packed bytes, got {}",
+            codec.packed_bytes(),
+            codes.len(),
+        );
+        assert_eq!(
+            out.len(),
+            codec.dim,
+            "decode_into: output length {} does not match dim {}",
+            out.len(),
+            codec.dim,
+        );
+        let centroid = centroid_row(codec, centroid_id);
+        gather_residual(codes, table, out);
+        // SAFETY: as in `dot`.
+        unsafe {
+            match self.0 {
+                Isa::Scalar => Scalar::add_norm(out, centroid),
+                #[cfg(target_arch = "x86_64")]
+                Isa::Avx2 => Avx2::add_norm(out, centroid),
+                #[cfg(target_arch = "x86_64")]
+                Isa::Avx512 => Avx512::add_norm(out, centroid),
+                #[cfg(target_arch = "aarch64")]
+                Isa::Neon => Neon::add_norm(out, centroid),
+            }
+        }
+    }
+}
+
+/// Fused decode + MaxSim scorer for one query against packed
+/// documents.
+///
+/// Each document token is decoded, L2-normalised and dotted with
+/// every query token, keeping the per-query maximum; the score is the
+/// sum of those maxima, as in the candle path `search` takes on CUDA.
+/// Scratch buffers are allocated once in [`MaxSim::new`] and reused
+/// for every [`MaxSim::score`] call.
+///
+/// A scorer built with [`MaxSim::dense`] instead takes documents as
+/// full-precision token rows through [`MaxSim::score_dense`], which
+/// dots them as stored, without normalising.
+///
+/// # Examples
+///
+/// ```
+/// use docbert_plaid::codec::{DecodeTable, ResidualCodec};
+/// use docbert_plaid::simd::{Kernel, MaxSim};
+///
+/// let codec = ResidualCodec {
+///     nbits: 8,
+///     dim: 2,
+///     centroids: vec![1.0, 0.0, 0.0, 1.0],
+///     bucket_cutoffs: (1..256).map(|i| i as f32 - 0.5).collect(),
+///     bucket_weights: vec![0.0; 256],
+/// };
+/// let table = DecodeTable::new(&codec);
+/// let query = [1.0, 0.0];
+///
+/// let mut maxsim = MaxSim::new(Kernel::detect(), &codec, &table, &query);
+/// // Two tokens: one on each centroid, with zero residuals.
+/// assert_eq!(maxsim.score(&[0, 1], &[0, 0, 0, 0]), 1.0);
+/// assert_eq!(maxsim.score(&[1], &[0, 0]), 0.0);
+///
+/// let mut dense = MaxSim::dense(Kernel::detect(), &query, 2);
+/// assert_eq!(dense.score_dense(&[0.5, 0.5, 0.0, 1.0]), 0.5);
+/// ```
+pub struct MaxSim<'a> {
+    kernel: Kernel,
+    /// Codec and decode table of packed documents; `None` for a
+    /// scorer built with [`MaxSim::dense`].
+    codec: Option<(&'a ResidualCodec, &'a DecodeTable)>,
+    query: &'a [f32],
+    dim: usize,
+    token: Vec<f32>,
+    best: Vec<f32>,
+}
+
+impl<'a> MaxSim<'a> {
+    /// Prepare to score the row-major `[n_q, dim]` `query` against
+    /// documents encoded with `codec`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `table` was built for another `nbits` or `query`
+    /// isn't a whole number of `dim`-wide tokens.
+    pub fn new(
+        kernel: Kernel,
+        codec: &'a ResidualCodec,
+        table: &'a DecodeTable,
+        query: &'a [f32],
+    ) -> Self {
+        assert_eq!(
+            table.nbits(),
+            codec.nbits,
+            "MaxSim: table nbits {} != codec nbits {}",
+            table.nbits(),
+            codec.nbits,
+        );
+        assert!(
+            codec.dim > 0 && query.len().is_multiple_of(codec.dim),
+            "MaxSim: query length {} is not a multiple of dim {}",
+            query.len(),
+            codec.dim,
+        );
+        Self {
+            kernel,
+            codec: Some((codec, table)),
+            query,
+            dim: codec.dim,
+            token: vec![0.0; codec.dim],
+            best: vec![0.0; query.len() / codec.dim],
+        }
+    }
+
+    /// Prepare to score the row-major `[n_q, dim]` `query` against
+    /// full-precision documents with [`score_dense`](Self::score_dense).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `dim` is 0 or `query` isn't a whole number of
+    /// `dim`-wide tokens.
+    pub fn dense(kernel: Kernel, query: &'a [f32], dim: usize) -> Self {
+        assert!(
+            dim > 0 && query.len().is_multiple_of(dim),
+            "MaxSim: query length {} is not a multiple of dim {dim}",
+            query.len(),
+        );
+        Self {
+            kernel,
+            codec: None,
+            query,
+            dim,
+            token: Vec::new(),
+            best: vec![0.0; query.len() / dim],
+        }
+    }
+
+    /// MaxSim score of the document whose tokens are `centroid_ids`
+    /// with `residuals` packed back to back. A document without
+    /// tokens scores 0.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the scorer was built with [`MaxSim::dense`], if
+    /// `residuals` doesn't hold one packed residual per centroid id, or
+    /// if a centroid id is out of range.
+    pub fn score(&mut self, centroid_ids: &[u32], residuals: &[u8]) -> f32 {
+        let (codec, table) = self
+            .codec
+            .expect("MaxSim::score needs a codec; use score_dense instead");
+        assert_eq!(
+            residuals.len(),
+            centroid_ids.len() * codec.packed_bytes(),
+            "MaxSim: {} residual bytes for {} tokens",
+            residuals.len(),
+            centroid_ids.len(),
+        );
+        if centroid_ids.is_empty() {
+            return 0.0;
+        }
+        self.dispatch(Packed {
+            codec,
+            table,
+            centroid_ids,
+            residuals,
+        })
+    }
+
+    /// MaxSim score of the document whose full-precision tokens are
+    /// the `dim`-wide rows of `document`: the sum over query tokens of
+    /// each one's best dot product with a document token. A document
+    /// without tokens scores 0.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `document` isn't a whole number of `dim`-wide tokens.
+    pub fn score_dense(&mut self, document: &[f32]) -> f32 {
+        assert!(
+            document.len().is_multiple_of(self.dim),
+            "MaxSim: document length {} is not a multiple of dim {}",
+            document.len(),
+            self.dim,
+        );
+        if document.is_empty() {
+            return 0.0;
+        }
+        self.dispatch(Dense {
+            tokens: document,
+            dim: self.dim,
+        })
+    }
+
+    /// Score `document` with the kernel's instruction set.
+    fn dispatch(&mut self, document: impl Tokens) -> f32 {
+        // SAFETY: as in `Kernel::dot`.
+        unsafe {
+            match self.kernel.0 {
+                Isa::Scalar => self.score_with::<Scalar>(document),
+                #[cfg(target_arch = "x86_64")]
+                Isa::Avx2 => self.score_avx2(document),
+                #[cfg(target_arch = "x86_64")]
+                Isa::Avx512 => self.score_avx512(document),
+                #[cfg(target_arch = "aarch64")]
+                Isa::Neon => self.score_neon(document),
+            }
+        }
+    }
+
+    /// Shared body of every `score_*` entry point. Always inlined so
+    /// the `L` kernels compile with the caller's target features.
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support `L`'s instruction set.
+    #[inline(always)]
+    unsafe fn score_with<L: Lanes>(&mut self, document: impl Tokens) -> f32 {
+        let dim = self.dim;
+        let query = self.query;
+        let best = &mut self.best;
+        best.fill(f32::NEG_INFINITY);
+        // SAFETY: forwarded from the caller.
+        unsafe {
+            document.for_each::<L>(&mut self.token, |token, norm| {
+                for (best, q) in best.iter_mut().zip(query.chunks_exact(dim)) {
+                    let s = L::dot(q, token) / norm;
+                    if s > *best {
+                        *best = s;
+                    }
+                }
+            });
+        }
+        self.best
+            .iter()
+            .filter(|best| best.is_finite())
+            .fold(0.0, |total, best| total + best)
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "avx2,fma")]
+    unsafe fn score_avx2(&mut self, document: impl Tokens) -> f32 {
+        // SAFETY: forwarded from the caller.
+        unsafe { self.score_with::<Avx2>(document) }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "avx512f")]
+    unsafe fn score_avx512(&mut self, document: impl Tokens) -> f32 {
+        // SAFETY: forwarded from the caller.
+        unsafe { self.score_with::<Avx512>(document) }
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    #[target_feature(enable = "neon")]
+    unsafe fn score_neon(&mut self, document: impl Tokens) -> f32 {
+        // SAFETY: forwarded from the caller.
+        unsafe { self.score_with::<Neon>(document) }
+    }
+}
+
+/// A document's tokens as [`MaxSim`] reads them.
+trait Tokens {
+    /// Call `score` with every token and the norm to divide its dot
+    /// products by, building tokens in `scratch` where needed.
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support `L`'s instruction set.
+    unsafe fn for_each<L: Lanes>(
+        self,
+        scratch: &mut [f32],
+        score: impl FnMut(&[f32], f32),
+    );
+}
+
+/// Packed tokens, decoded one at a time into the scratch row and
+/// L2-normalised.
+struct Packed<'a> {
+    codec: &'a ResidualCodec,
+    table: &'a DecodeTable,
+    centroid_ids: &'a [u32],
+    residuals: &'a [u8],
+}
+
+impl Tokens for Packed<'_> {
+    #[inline(always)]
+    unsafe fn for_each<L: Lanes>(
+        self,
+        scratch: &mut [f32],
+        mut score: impl FnMut(&[f32], f32),
+    ) {
+        for (&centroid_id, codes) in self
+            .centroid_ids
+            .iter()
+            .zip(self.residuals.chunks_exact(self.codec.packed_bytes()))
+        {
+            let centroid = centroid_row(self.codec, centroid_id);
+            gather_residual(codes, self.table, scratch);
+            // SAFETY: forwarded from the caller.
+            let norm_sq = unsafe { L::add_norm(scratch, centroid) };
+            // Same clamp as `l2_normalize_rows` in the search path.
+            score(scratch, norm_sq.sqrt().max(1e-12));
+        }
+    }
+}
+
+/// Full-precision tokens, scored as stored.
+struct Dense<'a> {
+    tokens: &'a [f32],
+    dim: usize,
+}
+
+impl Tokens for Dense<'_> {
+    #[inline(always)]
+    unsafe fn for_each<L: Lanes>(
+        self,
+        _scratch: &mut [f32],
+        mut score: impl FnMut(&[f32], f32),
+    ) {
+        for token in self.tokens.chunks_exact(self.dim) {
+            score(token, 1.0);
+        }
+    }
+}
+
+/// The `dim`-wide row of centroid `centroid_id`.
+fn centroid_row(codec: &ResidualCodec, centroid_id: u32) -> &[f32] {
+    let start = centroid_id as usize * codec.dim;
+    assert!(
+        start < codec.centroids.len(),
+        "centroid_id {centroid_id} out of range 0..{}",
+        codec.num_centroids(),
+    );
+    &codec.centroids[start..start + codec.dim]
+}
+
+/// Write each packed byte's residual weights into `out`, one LUT row
+/// per byte. The last row is truncated when `dim` isn't a multiple of
+/// `codes_per_byte`.
+fn gather_residual(codes: &[u8], table: &DecodeTable, out: &mut [f32]) {
+    let weights = table.weights_flat();
+    match table.codes_per_byte() {
+        1 => gather_rows::<1>(codes, weights, out),
+        2 => gather_rows::<2>(codes, weights, out),
+        4 => gather_rows::<4>(codes, weights, out),
+        8 => gather_rows::<8>(codes, weights, out),
+        n => unreachable!("{n} codes per byte"),
+    }
+}
+
+/// [`gather_residual`] for `N` codes per byte. A constant row width
+/// turns each row copy into a single register move instead of a
+/// `memcpy` call.
+#[inline(always)]
+fn gather_rows<const N: usize>(codes: &[u8], weights: &[f32], out: &mut [f32]) {
+    let (rows, tail) = out.as_chunks_mut::<N>();
+    for (slot, &byte) in rows.iter_mut().zip(codes) {
+        let row = byte as usize * N;
+        slot.copy_from_slice(&weights[row..row + N]);
+    }
+    if let Some(&byte) = codes.get(rows.len())
+        && !tail.is_empty()
+    {
+        let row = byte as usize * N;
+        tail.copy_from_slice(&weights[row..row + tail.len()]);
+    }
+}
+
+/// The per-instruction-set primitives [`MaxSim`] and [`Kernel`] are
+/// built from. Implementations tolerate mismatched lengths by only
+/// touching the common prefix; the public wrappers assert equality.
+trait Lanes {
+    /// `Σ a[i] * b[i]`.
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support the implementing instruction set.
+    unsafe fn dot(a: &[f32], b: &[f32]) -> f32;
+
+    /// `out[i] += centroid[i]`, returning `Σ out[i]²` afterwards.
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support the implementing instruction set.
+    unsafe fn add_norm(out: &mut [f32], centroid: &[f32]) -> f32;
+}
+
+struct Scalar;
+
+impl Lanes for Scalar {
+    #[inline(always)]
+    unsafe fn dot(a: &[f32], b: &[f32]) -> f32 {
+        let mut sum = 0.0f32;
+        for (x, y) in a.iter().zip(b) {
+            sum += x * y;
+        }
+        sum
+    }
+
+    #[inline(always)]
+    unsafe fn add_norm(out: &mut [f32], centroid: &[f32]) -> f32 {
+        let mut sum = 0.0f32;
+        for (x, c) in out.iter_mut().zip(centroid) {
+            *x += c;
+            sum += *x * *x;
+        }
+        sum
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+struct Avx2;
+
+#[cfg(target_arch = "x86_64")]
+impl Lanes for Avx2 {
+    #[inline(always)]
+    unsafe fn dot(a: &[f32], b: &[f32]) -> f32 {
+        // SAFETY: forwarded from the caller.
+        unsafe { x86::dot_avx2(a, b) }
+    }
+
+    #[inline(always)]
+    unsafe fn add_norm(out: &mut [f32], centroid: &[f32]) -> f32 {
+        // SAFETY: forwarded from the caller.
+        unsafe { x86::add_norm_avx2(out, centroid) }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+struct Avx512;
+
+#[cfg(target_arch = "x86_64")]
+impl Lanes for Avx512 {
+    #[inline(always)]
+    unsafe fn dot(a: &[f32], b: &[f32]) -> f32 {
+        // SAFETY: forwarded from the caller.
+        unsafe { x86::dot_avx512(a, b) }
+    }
+
+    #[inline(always)]
+    unsafe fn add_norm(out: &mut [f32], centroid: &[f32]) -> f32 {
+        // SAFETY: forwarded from the caller.
+        unsafe { x86::add_norm_avx512(out, centroid) }
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+struct Neon;
+
+#[cfg(target_arch = "aarch64")]
+impl Lanes for Neon {
+    #[inline(always)]
+    unsafe fn dot(a: &[f32], b: &[f32]) -> f32 {
+        // SAFETY: forwarded from the caller.
+        unsafe { arm::dot_neon(a, b) }
+    }
+
+    #[inline(always)]
+    unsafe fn add_norm(out: &mut [f32], centroid: &[f32]) -> f32 {
+        // SAFETY: forwarded from the caller.
+        unsafe { arm::add_norm_neon(out, centroid) }
+    }
+}
+
+/// AVX2 and AVX-512 kernels. Each unrolls over several accumulators
+/// to hide FMA latency, then finishes the tail that doesn't fill a
+/// register with scalar code.
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use std::arch::x86_64::*;
+
+    #[inline]
+    #[target_feature(enable = "avx2,fma")]
+    pub(super) fn dot_avx2(a: &[f32], b: &[f32]) -> f32 {
+        let n = a.len().min(b.len());
+        let (pa, pb) = (a.as_ptr(), b.as_ptr());
+        let mut acc = [_mm256_setzero_ps(); 4];
+        let mut i = 0;
+        // SAFETY: every load reads 8 floats starting at `i`, and the
+        // loop bounds keep `i + 8 <= n`.
+        unsafe {
+            while i + 32 <= n {
+                for (lane, acc) in acc.iter_mut().enumerate() {
+                    let at = i + lane * 8;
+                    *acc = _mm256_fmadd_ps(
+                        _mm256_loadu_ps(pa.add(at)),
+                        _mm256_loadu_ps(pb.add(at)),
+                        *acc,
+                    );
+                }
+                i += 32;
+            }
+            while i + 8 <= n {
+                acc[0] = _mm256_fmadd_ps(
+                    _mm256_loadu_ps(pa.add(i)),
+                    _mm256_loadu_ps(pb.add(i)),
+                    acc[0],
+                );
+                i += 8;
+            }
+        }
+        let sum = _mm256_add_ps(
+            _mm256_add_ps(acc[0], acc[1]),
+            _mm256_add_ps(acc[2], acc[3]),
+        );
+        let mut total = hsum_avx2(sum);
+        for (x, y) in a[i..n].iter().zip(&b[i..n]) {
+            total += x * y;
+        }
+        total
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2,fma")]
+    pub(super) fn add_norm_avx2(out: &mut [f32], centroid: &[f32]) -> f32 {
+        let n = out.len().min(centroid.len());
+        let (po, pc) = (out.as_mut_ptr(), centroid.as_ptr());
+        let mut acc = _mm256_setzero_ps();
+        let mut i = 0;
+        // SAFETY: as in `dot_avx2`; `po` is the only live reference
+        // to `out` inside this block.
+        unsafe {
+            while i + 8 <= n {
+                let v = _mm256_add_ps(
+                    _mm256_loadu_ps(po.add(i)),
+                    _mm256_loadu_ps(pc.add(i)),
+                );
+                _mm256_storeu_ps(po.add(i), v);
+                acc = _mm256_fmadd_ps(v, v, acc);
+                i += 8;
+            }
+        }
+        let mut total = hsum_avx2(acc);
+        for (x, c) in out[i..n].iter_mut().zip(&centroid[i..n]) {
+            *x += c;
+            total += *x * *x;
+        }
+        total
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2,fma")]
+    fn hsum_avx2(v: __m256) -> f32 {
+        let s = _mm_add_ps(
+            _mm256_castps256_ps128(v),
+            _mm256_extractf128_ps::<1>(v),
+        );
+        let s = _mm_add_ps(s, _mm_movehl_ps(s, s));
+        let s = _mm_add_ss(s, _mm_shuffle_ps::<1>(s, s));
+        _mm_cvtss_f32(s)
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx512f")]
+    pub(super) fn dot_avx512(a: &[f32], b: &[f32]) -> f32 {
+        let n = a.len().min(b.len());
+        let (pa, pb) = (a.as_ptr(), b.as_ptr());
+        let mut acc = [_mm512_setzero_ps(); 2];
+        let mut i = 0;
+        // SAFETY: every load reads 16 floats starting at `i`, and the
+        // loop bounds keep `i + 16 <= n`.
+        unsafe {
+            while i + 32 <= n {
+                for (lane, acc) in acc.iter_mut().enumerate() {
+                    let at = i + lane * 16;
+                    *acc = _mm512_fmadd_ps(
+                        _mm512_loadu_ps(pa.add(at)),
+                        _mm512_loadu_ps(pb.add(at)),
+                        *acc,
+                    );
+                }
+                i += 32;
+            }
+            while i + 16 <= n {
+                acc[0] = _mm512_fmadd_ps(
+                    _mm512_loadu_ps(pa.add(i)),
+                    _mm512_loadu_ps(pb.add(i)),
+                    acc[0],
+                );
+                i += 16;
+            }
+        }
+        let mut total = _mm512_reduce_add_ps(_mm512_add_ps(acc[0], acc[1]));
+        for (x, y) in a[i..n].iter().zip(&b[i..n]) {
+            total += x * y;
+        }
+        total
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx512f")]
+    pub(super) fn add_norm_avx512(out: &mut [f32], centroid: &[f32]) -> f32 {
+        let n = out.len().min(centroid.len());
+        let (po, pc) = (out.as_mut_ptr(), centroid.as_ptr());
+        let mut acc = _mm512_setzero_ps();
+        let mut i = 0;
+        // SAFETY: as in `add_norm_avx2`, 16 floats at a time.
+        unsafe {
+            while i + 16 <= n {
+                let v = _mm512_add_ps(
+                    _mm512_loadu_ps(po.add(i)),
+                    _mm512_loadu_ps(pc.add(i)),
+                );
+                _mm512_storeu_ps(po.add(i), v);
+                acc = _mm512_fmadd_ps(v, v, acc);
+                i += 16;
+            }
+        }
+        let mut total = _mm512_reduce_add_ps(acc);
+        for (x, c) in out[i..n].iter_mut().zip(&centroid[i..n]) {
+            *x += c;
+            total += *x * *x;
+        }
+        total
+    }
+}
+
+/// NEON kernels, structured like the x86 ones with 4-lane registers.
+#[cfg(target_arch = "aarch64")]
+mod arm {
+    use std::arch::aarch64::*;
+
+    #[inline]
+    #[target_feature(enable = "neon")]
+    pub(super) fn dot_neon(a: &[f32], b: &[f32]) -> f32 {
+        let n = a.len().min(b.len());
+        let (pa, pb) = (a.as_ptr(), b.as_ptr());
+        let mut acc = [vdupq_n_f32(0.0); 4];
+        let mut i = 0;
+        // SAFETY: every load reads 4 floats starting at `i`, and the
+        // loop bounds keep `i + 4 <= n`.
+        unsafe {
+            while i + 16 <= n {
+                for (lane, acc) in acc.iter_mut().enumerate() {
+                    let at = i + lane * 4;
+                    *acc = vfmaq_f32(
+                        *acc,
+                        vld1q_f32(pa.add(at)),
+                        vld1q_f32(pb.add(at)),
+                    );
+                }
+                i += 16;
+            }
+            while i + 4 <= n {
+                acc[0] = vfmaq_f32(
+                    acc[0],
+                    vld1q_f32(pa.add(i)),
+                    vld1q_f32(pb.add(i)),
+                );
+                i += 4;
+            }
+        }
+        let mut total = vaddvq_f32(vaddq_f32(
+            vaddq_f32(acc[0], acc[1]),
+            vaddq_f32(acc[2], acc[3]),
+        ));
+        for (x, y) in a[i..n].iter().zip(&b[i..n]) {
+            total += x * y;
+        }
+        total
+    }
+
+    #[inline]
+    #[target_feature(enable = "neon")]
+    pub(super) fn add_norm_neon(out: &mut [f32], centroid: &[f32]) -> f32 {
+        let n = out.len().min(centroid.len());
+        let (po, pc) = (out.as_mut_ptr(), centroid.as_ptr());
+        let mut acc = vdupq_n_f32(0.0);
+        let mut i = 0;
+        // SAFETY: as in `dot_neon`; `po` is the only live reference
+        // to `out` inside this block.
+        unsafe {
+            while i + 4 <= n {
+                let v = vaddq_f32(vld1q_f32(po.add(i)), vld1q_f32(pc.add(i)));
+                vst1q_f32(po.add(i), v);
+                acc = vfmaq_f32(acc, v, v);
+                i += 4;
+            }
+        }
+        let mut total = vaddvq_f32(acc);
+        for (x, c) in out[i..n].iter_mut().zip(&centroid[i..n]) {
+            *x += c;
+            total += *x * *x;
+        }
+        total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn codec(nbits: u32, dim: usize) -> ResidualCodec {
+        let num_buckets = 1u32 << nbits;
+        ResidualCodec {
+            nbits,
+            dim,
+            centroids: (0..3 * dim)
+                .map(|i| (i % 7) as f32 / 7.0 - 0.4)
+                .collect(),
+            bucket_cutoffs: (1..num_buckets)
+                .map(|i| i as f32 / num_buckets as f32 - 0.5)
+                .collect(),
+            bucket_weights: (0..num_buckets)
+                .map(|i| (i as f32 + 0.5) / num_buckets as f32 - 0.5)
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn detect_is_the_fastest_available_kernel() {
+        let available = Kernel::available();
+        assert_eq!(available[0], Kernel::scalar());
+        assert_eq!(Some(&Kernel::detect()), available.last());
+    }
+
+    #[test]
+    fn dot_covers_unrolled_body_and_tail() {
+        // 71 = two 32-wide unrolled steps, no full 8/16-lane step,
+        // and a 7-float scalar tail.
+        let a: Vec<f32> = (0..71).map(|i| (i % 5) as f32 - 2.0).collect();
+        let b: Vec<f32> = (0..71).map(|i| (i % 3) as f32).collect();
+        let expected = Kernel::scalar().dot(&a, &b);
+        for kernel in Kernel::available() {
+            // Small integers sum exactly in any order.
+            assert_eq!(kernel.dot(&a, &b), expected, "{}", kernel.name());
+        }
+    }
+
+    #[test]
+    fn decode_into_matches_decode_vector_for_every_kernel() {
+        for nbits in [1, 2, 4, 8] {
+            let dim = 40;
+            let codec = codec(nbits, dim);
+            let table = DecodeTable::new(&codec);
+            let input: Vec<f32> =
+                (0..dim).map(|i| (i as f32 * 0.37).sin()).collect();
+            let mut encoded = codec.encode_vector(&input).unwrap();
+            encoded.centroid_id = 2;
+            let expected = codec.decode_vector(&encoded).unwrap();
+            for kernel in Kernel::available() {
+                let mut out = vec![0.0; dim];
+                let norm_sq = kernel.decode_into(
+                    &codec,
+                    &table,
+                    encoded.centroid_id,
+                    &encoded.codes,
+                    &mut out,
+                );
+                assert_eq!(out, expected, "{} at nbits={nbits}", kernel.name());
+                let expected_sq: f32 = expected.iter().map(|x| x * x).sum();
+                assert!((norm_sq - expected_sq).abs() < 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn maxsim_scores_empty_documents_as_zero() {
+        let codec = codec(2, 8);
+        let table = DecodeTable::new(&codec);
+        let query = vec![0.5; 16];
+        let mut maxsim = MaxSim::new(Kernel::detect(), &codec, &table, &query);
+        assert_eq!(maxsim.score(&[], &[]), 0.0);
+    }
+
+    #[test]
+    fn dense_maxsim_matches_the_reference_for_every_kernel() {
+        let dim = 40;
+        let query: Vec<f32> =
+            (0..3 * dim).map(|i| (i as f32 * 0.11).cos()).collect();
+        let document: Vec<f32> =
+            (0..5 * dim).map(|i| (i as f32 * 0.37).sin()).collect();
+        let expected: f32 = query
+            .chunks_exact(dim)
+            .map(|q| {
+                document
+                    .chunks_exact(dim)
+                    .map(|d| q.iter().zip(d).map(|(a, b)| a * b).sum::<f32>())
+                    .fold(f32::NEG_INFINITY, f32::max)
+            })
+            .sum();
+        for kernel in Kernel::available() {
+            let mut maxsim = MaxSim::dense(kernel, &query, dim);
+            let score = maxsim.score_dense(&document);
+            assert!((score - expected).abs() < 1e-4, "{}", kernel.name());
+            assert_eq!(maxsim.score_dense(&[]), 0.0);
+        }
+    }
+}
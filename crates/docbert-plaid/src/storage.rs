@@ -0,0 +1,182 @@
+//! Backing storage for the flat [`Index`] buffers.
+//!
+//! A freshly built or updated index owns its buffers as plain `Vec`s.
+//! An index opened from a version-3 file instead borrows them straight
+//! out of a read-only memory map of the file, so opening it costs a
+//! handful of syscalls rather than a full read. [`Buffer`] hides the
+//! difference: both variants deref to `&[T]`.
+//!
+//! [`Index`]: crate::index::Index
+
+use std::{fmt, ops::Deref, sync::Arc};
+
+use bytemuck::Pod;
+use memmap2::Mmap;
+
+use crate::{PlaidError, Result};
+
+/// A read-only slice of `T` that is either owned or borrowed from a
+/// shared memory map.
+///
+/// Cloning a mapped buffer only bumps the map's reference count; the
+/// mapping stays alive until the last buffer pointing into it drops.
+#[derive(Clone)]
+pub struct Buffer<T: Pod> {
+    repr: Repr<T>,
+}
+
+#[derive(Clone)]
+enum Repr<T> {
+    Owned(Vec<T>),
+    Mapped {
+        map: Arc<Mmap>,
+        /// Byte offset of the first element inside `map`.
+        offset: usize,
+        /// Number of `T` elements.
+        len: usize,
+    },
+}
+
+impl<T: Pod> Buffer<T> {
+    /// Borrow `len` elements of `T` starting `offset` bytes into `map`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PlaidError::InvalidIndex`] if the range runs past the
+    /// end of the map or `offset` is not aligned for `T`.
+    pub(crate) fn mapped(
+        map: Arc<Mmap>,
+        offset: usize,
+        len: usize,
+    ) -> Result<Self> {
+        let end = len
+            .checked_mul(size_of::<T>())
+            .and_then(|bytes| bytes.checked_add(offset));
+        if end.is_none_or(|end| end > map.len()) {
+            return Err(PlaidError::InvalidIndex(format!(
+                "section at byte {offset} with {len} elements runs past the end of the file",
+            )));
+        }
+        if !(map.as_ptr() as usize + offset).is_multiple_of(align_of::<T>()) {
+            return Err(PlaidError::InvalidIndex(format!(
+                "section at byte {offset} is not aligned to {} bytes",
+                align_of::<T>(),
+            )));
+        }
+        Ok(Self {
+            repr: Repr::Mapped { map, offset, len },
+        })
+    }
+
+    /// Whether the elements live in a memory map rather than on the
+    /// heap.
+    pub fn is_mapped(&self) -> bool {
+        matches!(self.repr, Repr::Mapped { .. })
+    }
+
+    /// Take the elements as an owned `Vec`, copying them out of the
+    /// map if necessary.
+    pub fn into_vec(self) -> Vec<T> {
+        match self.repr {
+            Repr::Owned(vec) => vec,
+            Repr::Mapped { .. } => self.to_vec(),
+        }
+    }
+}
+
+impl<T: Pod> Deref for Buffer<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        match &self.repr {
+            Repr::Owned(vec) => vec,
+            Repr::Mapped { map, offset, len } => bytemuck::cast_slice(
+                &map[*offset..*offset + *len * size_of::<T>()],
+            ),
+        }
+    }
+}
+
+impl<T: Pod> Default for Buffer<T> {
+    fn default() -> Self {
+        Vec::new().into()
+    }
+}
+
+impl<T: Pod> From<Vec<T>> for Buffer<T> {
+    fn from(vec: Vec<T>) -> Self {
+        Self {
+            repr: Repr::Owned(vec),
+        }
+    }
+}
+
+impl<T: Pod> FromIterator<T> for Buffer<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        iter.into_iter().collect::<Vec<T>>().into()
+    }
+}
+
+impl<'a, T: Pod> IntoIterator for &'a Buffer<T> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: Pod + PartialEq> PartialEq for Buffer<T> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<T: Pod + PartialEq> PartialEq<Vec<T>> for Buffer<T> {
+    fn eq(&self, other: &Vec<T>) -> bool {
+        **self == **other
+    }
+}
+
+impl<T: Pod + fmt::Debug> fmt::Debug for Buffer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    fn map_bytes(bytes: &[u8]) -> Arc<Mmap> {
+        let mut file = tempfile::tempfile().unwrap();
+        file.write_all(bytes).unwrap();
+        // Safety: the temporary file is private to this test and is
+        // never written again while mapped.
+        Arc::new(unsafe { Mmap::map(&file) }.unwrap())
+    }
+
+    #[test]
+    fn mapped_buffer_reads_the_same_elements_as_an_owned_one() {
+        let values = [7u32, 8, 9];
+        let mut bytes = vec![0u8; 8];
+        bytes.extend_from_slice(bytemuck::cast_slice(&values));
+        let map = map_bytes(&bytes);
+
+        let mapped = Buffer::<u32>::mapped(map, 8, 3).unwrap();
+        assert!(mapped.is_mapped());
+        assert_eq!(mapped, values.to_vec());
+        assert_eq!(mapped, Buffer::from(values.to_vec()));
+        assert_eq!(mapped.clone().into_vec(), values.to_vec());
+    }
+
+    #[test]
+    fn mapped_rejects_out_of_bounds_and_misaligned_ranges() {
+        let map = map_bytes(&[0u8; 16]);
+        assert!(Buffer::<u64>::mapped(Arc::clone(&map), 8, 2).is_err());
+        assert!(Buffer::<u32>::mapped(Arc::clone(&map), 2, 1).is_err());
+        assert!(Buffer::<u32>::mapped(map, 16, 0).unwrap().is_empty());
+    }
+}
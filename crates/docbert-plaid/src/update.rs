@@ -17,17 +17,20 @@
 //! [`build_index`]) periodically to combat codec drift as the corpus
 //! evolves — the existing codec only stays well-calibrated while the
 //! underlying token distribution doesn't change dramatically.
+//! [`crate::drift`] measures that drift so the rebuild can happen when
+//! it is needed rather than on a schedule.
 //!
 //! [`build_index`]: crate::index::build_index
 
-use std::collections::HashSet;
+use std::{collections::HashSet, sync::Arc};
 
 use crate::{
     Result,
-    codec::EncodedVector,
+    codec::{EncodedVector, ResidualCodec},
     index::{
         DocumentTokens,
         Index,
+        IndexParams,
         InvertedFile,
         build_inverted_file_from_flat,
     },
@@ -67,28 +70,7 @@ pub struct IndexUpdate<'a> {
 /// [`PlaidError::Tensor`]: crate::PlaidError::Tensor
 pub fn apply_update(index: Index, update: IndexUpdate<'_>) -> Result<Index> {
     let params = index.params;
-
-    for doc in update.upserts {
-        assert!(
-            doc.tokens.len() == doc.n_tokens * params.dim,
-            "apply_update: doc {} declared {} tokens but carries {} f32s (dim={})",
-            doc.doc_id,
-            doc.n_tokens,
-            doc.tokens.len(),
-            params.dim,
-        );
-    }
-
-    // Guard against ambiguous duplicate upserts early so callers get a
-    // loud failure rather than silently losing one of their writes.
-    let mut seen: HashSet<u64> = HashSet::with_capacity(update.upserts.len());
-    for doc in update.upserts {
-        assert!(
-            seen.insert(doc.doc_id),
-            "apply_update: duplicate doc_id {} in upserts",
-            doc.doc_id,
-        );
-    }
+    check_upserts(params.dim, update.upserts);
 
     // Everything in `deletions` leaves the index. Anything in
     // `upserts` also leaves first (so the upsert replaces the old
@@ -104,7 +86,7 @@ pub fn apply_update(index: Index, update: IndexUpdate<'_>) -> Result<Index> {
     // think in per-document terms; we'll reflatten at the end.
     let codec = index.codec.clone();
     let packed_bytes = codec.packed_bytes();
-    let existing_doc_ids = index.doc_ids.clone();
+    let existing_doc_ids = index.doc_ids.to_vec();
     let existing_doc_tokens: Vec<Vec<EncodedVector>> = (0..existing_doc_ids
         .len())
         .map(|i| index.doc_tokens_vec(i))
@@ -190,10 +172,91 @@ pub fn apply_update(index: Index, update: IndexUpdate<'_>) -> Result<Index> {
     Ok(new_index)
 }
 
+/// Encode `docs` against an existing `codec` into a standalone [`Index`]
+/// that shares it.
+///
+/// This is how a [`SegmentedIndex`] builds its delta segments: nothing is
+/// trained and no existing document is touched, so the cost is the
+/// encoding of `docs` alone.
+///
+/// # Panics
+///
+/// Same as [`apply_update`] for the upserted documents.
+///
+/// [`SegmentedIndex`]: crate::segment::SegmentedIndex
+pub(crate) fn encode_documents(
+    params: IndexParams,
+    codec: &Arc<ResidualCodec>,
+    docs: &[DocumentTokens],
+) -> Result<Index> {
+    check_upserts(params.dim, docs);
+
+    let total_tokens: usize = docs.iter().map(|d| d.n_tokens).sum();
+    let (doc_centroid_ids, doc_residual_bytes) = if total_tokens > 0 {
+        let mut pool: Vec<f32> = Vec::with_capacity(total_tokens * params.dim);
+        for doc in docs {
+            pool.extend_from_slice(&doc.tokens);
+        }
+        codec.batch_encode_tokens(&pool)?
+    } else {
+        (Vec::new(), Vec::new())
+    };
+
+    let mut doc_offsets = Vec::with_capacity(docs.len() + 1);
+    doc_offsets.push(0usize);
+    for doc in docs {
+        doc_offsets.push(doc_offsets[doc_offsets.len() - 1] + doc.n_tokens);
+    }
+    let ivf = build_inverted_file_from_flat(
+        &doc_centroid_ids,
+        &doc_offsets,
+        params.k_centroids,
+    );
+
+    Ok(Index {
+        params,
+        codec: Arc::clone(codec),
+        doc_ids: docs.iter().map(|d| d.doc_id).collect(),
+        doc_centroid_ids: doc_centroid_ids.into(),
+        doc_residual_bytes: doc_residual_bytes.into(),
+        doc_offsets: doc_offsets.into(),
+        ivf,
+    })
+}
+
+/// Panic on upserts whose token buffers disagree with `dim` or that
+/// repeat a doc_id.
+fn check_upserts(dim: usize, upserts: &[DocumentTokens]) {
+    for doc in upserts {
+        assert!(
+            doc.tokens.len() == doc.n_tokens * dim,
+            "apply_update: doc {} declared {} tokens but carries {} f32s (dim={})",
+            doc.doc_id,
+            doc.n_tokens,
+            doc.tokens.len(),
+            dim,
+        );
+    }
+
+    // Guard against ambiguous duplicate upserts early so callers get a
+    // loud failure rather than silently losing one of their writes.
+    let mut seen: HashSet<u64> = HashSet::with_capacity(upserts.len());
+    for doc in upserts {
+        assert!(
+            seen.insert(doc.doc_id),
+            "apply_update: duplicate doc_id {} in upserts",
+            doc.doc_id,
+        );
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::index::{IndexParams, build_index};
+    use crate::{
+        index::{IndexParams, build_index},
+        kmeans::KMeansOptions,
+    };
 
     fn seed_corpus() -> Vec<DocumentTokens> {
         // Two well-separated clusters so k-means produces stable
@@ -224,6 +287,7 @@ mod tests {
             nbits: 2,
             k_centroids: 2,
             max_kmeans_iters: 50,
+            kmeans: KMeansOptions::default(),
         }
     }
 
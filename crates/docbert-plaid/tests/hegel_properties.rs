@@ -15,7 +15,10 @@
 
 #![allow(dead_code)]
 
-use docbert_plaid::index::{DocumentTokens, IndexParams};
+use docbert_plaid::{
+    index::{DocumentTokens, IndexParams},
+    kmeans::KMeansOptions,
+};
 use hegel::{HealthCheck, TestCase, generators as gs};
 
 // ---------------------------------------------------------------------------
@@ -156,6 +159,7 @@ fn index_params(
         nbits,
         k_centroids,
         max_kmeans_iters,
+        kmeans: KMeansOptions::default(),
     }
 }
 
@@ -211,6 +215,31 @@ fn prop_dot_commutative(tc: TestCase) {
     assert_eq!(dot(&a, &b), dot(&b, &a));
 }
 
+/// Differential: every vectorised kernel's dot product matches the
+/// scalar kernel within float rounding. Sizes cross the 4/8/16-lane
+/// and 32-wide unrolled boundaries, so register bodies and scalar
+/// tails are both exercised. The bound is the usual reordered-sum
+/// error, `2 · n · ε · Σ|aᵢbᵢ|`.
+#[hegel::test(test_cases = 200)]
+fn prop_simd_dot_matches_scalar(tc: TestCase) {
+    use docbert_plaid::simd::Kernel;
+    let n = tc.draw(gs::integers::<usize>().min_value(0).max_value(300));
+    let a = tc.draw(finite_floats(n));
+    let b = tc.draw(finite_floats(n));
+
+    let scalar = Kernel::scalar().dot(&a, &b);
+    let magnitude: f32 = a.iter().zip(&b).map(|(x, y)| (x * y).abs()).sum();
+    let tolerance = 2.0 * n as f32 * f32::EPSILON * magnitude + 1e-6;
+    for kernel in Kernel::available() {
+        let got = kernel.dot(&a, &b);
+        assert!(
+            (got - scalar).abs() <= tolerance,
+            "{}: {got} vs scalar {scalar} (tolerance {tolerance})",
+            kernel.name(),
+        );
+    }
+}
+
 /// Algebraic: `squared_l2(a, b) == squared_l2(b, a)`. Each squared
 /// difference is symmetric in its operands, and the summation iterates
 /// in the same left-to-right order in both directions, so equality is
@@ -546,6 +575,73 @@ fn prop_fit_zero_iters_returns_input_rows(tc: TestCase) {
     }
 }
 
+/// Draw [`KMeansOptions`] covering every seeding strategy, both
+/// training loops, early stopping and sample sizes from `k` up past
+/// the corpus size.
+#[hegel::composite]
+fn kmeans_options(tc: TestCase, k: usize) -> KMeansOptions {
+    use docbert_plaid::kmeans::KMeansInit;
+    let init = match tc.draw(gs::integers::<u8>().min_value(0).max_value(2)) {
+        0 => KMeansInit::Random,
+        1 => KMeansInit::PlusPlus,
+        _ => KMeansInit::Parallel,
+    };
+    // 0 stands for "unset" in each of the optional draws.
+    let mini_batch =
+        tc.draw(gs::integers::<usize>().min_value(0).max_value(16));
+    let tolerance = tc.draw(gs::sampled_from(vec![0.0f32, 1e-4, 1e-2, 1.0]));
+    let sample_tokens =
+        tc.draw(gs::integers::<usize>().min_value(0).max_value(k * 16));
+    KMeansOptions {
+        init,
+        mini_batch: (mini_batch > 0).then_some(mini_batch),
+        tolerance: (tolerance > 0.0).then_some(tolerance),
+        sample_tokens: (sample_tokens > 0).then_some(sample_tokens),
+    }
+}
+
+/// Shape and safety for every training option: `fit_with_options`
+/// returns exactly `k · dim` finite floats whichever seeding, training
+/// loop and sample size it is given.
+#[hegel::test(test_cases = 100)]
+fn prop_fit_with_options_returns_finite_centroids(tc: TestCase) {
+    use docbert_plaid::kmeans::fit_with_options;
+    let dim = tc.draw(codec_dim());
+    let k = tc.draw(gs::integers::<usize>().min_value(1).max_value(8));
+    let n = tc.draw(gs::integers::<usize>().min_value(k).max_value(64));
+    let iters = tc.draw(gs::integers::<usize>().min_value(0).max_value(5));
+    let points = tc.draw(unit_rows(dim, n));
+    let options = tc.draw(kmeans_options(k));
+
+    let centroids = fit_with_options(&points, k, dim, iters, options).unwrap();
+    assert_eq!(centroids.len(), k * dim, "{options:?}");
+    for v in &centroids {
+        assert!(v.is_finite(), "non-finite centroid value {v} ({options:?})");
+    }
+}
+
+/// Seeding: like [`prop_fit_zero_iters_returns_input_rows`], but for
+/// k-means++ and k-means|| too. Every strategy picks its seeds among
+/// the sampled rows and never synthesises one.
+#[hegel::test(test_cases = 100)]
+fn prop_fit_with_options_seeds_are_input_rows(tc: TestCase) {
+    use docbert_plaid::kmeans::fit_with_options;
+    let dim = tc.draw(codec_dim());
+    let k = tc.draw(gs::integers::<usize>().min_value(1).max_value(8));
+    let n = tc.draw(gs::integers::<usize>().min_value(k).max_value(64));
+    let points = tc.draw(unit_rows(dim, n));
+    let options = tc.draw(kmeans_options(k));
+
+    let seeds = fit_with_options(&points, k, dim, 0, options).unwrap();
+    let input_rows: Vec<&[f32]> = points.chunks_exact(dim).collect();
+    for seed in seeds.chunks_exact(dim) {
+        assert!(
+            input_rows.contains(&seed),
+            "seed row {seed:?} is not any input row ({options:?})",
+        );
+    }
+}
+
 /// Algebraic / monotonicity: across Lloyd iterations (`assign →
 /// update → repeat`) the within-cluster sum-of-squares is
 /// non-increasing within a small f32 slack. Generalises the
@@ -803,7 +899,7 @@ fn prop_train_quantizer_cutoffs_monotonic(tc: TestCase) {
     let residuals = tc.draw(finite_floats(n));
     let (cutoffs, _) = train_quantizer(residuals, nbits);
     for pair in cutoffs.windows(2) {
-        assert!(pair[0] <= pair[1], "cutoffs not monotone: {:?}", &cutoffs,);
+        assert!(pair[0] <= pair[1], "cutoffs not monotone: {:?}", cutoffs,);
     }
 }
 
@@ -1010,6 +1106,52 @@ fn prop_decode_table_matches_scalar(tc: TestCase) {
     assert_eq!(scalar, via_table);
 }
 
+/// Differential: `Kernel::decode_into` writes bit-for-bit what
+/// `decode_vector_with_table` returns on every kernel, including dims
+/// that leave a partially used last byte, and its squared norm matches
+/// the decoded vector's within float rounding.
+#[hegel::test(test_cases = 100)]
+fn prop_simd_decode_matches_decode_table(tc: TestCase) {
+    use docbert_plaid::{
+        codec::{DecodeTable, ResidualCodec},
+        simd::Kernel,
+    };
+    let nbits: u32 = tc.draw(gs::sampled_from(vec![1u32, 2, 4, 8]));
+    let dim = tc.draw(gs::integers::<usize>().min_value(1).max_value(70));
+
+    let num_buckets = 1u32 << nbits;
+    let codec = ResidualCodec {
+        nbits,
+        dim,
+        centroids: tc.draw(unit_rows(dim, 2)),
+        bucket_cutoffs: (1..num_buckets).map(|i| i as f32 - 0.5).collect(),
+        bucket_weights: (0..num_buckets).map(|i| i as f32).collect(),
+    };
+    let input = tc.draw(finite_floats(dim));
+    let encoded = codec.encode_vector(&input).unwrap();
+    let table = DecodeTable::new(&codec);
+    let expected = codec.decode_vector_with_table(&encoded, &table).unwrap();
+    let expected_sq: f32 = expected.iter().map(|x| x * x).sum();
+
+    for kernel in Kernel::available() {
+        let mut out = vec![f32::NAN; dim];
+        let norm_sq = kernel.decode_into(
+            &codec,
+            &table,
+            encoded.centroid_id,
+            &encoded.codes,
+            &mut out,
+        );
+        assert_eq!(out, expected, "{}", kernel.name());
+        assert!(
+            (norm_sq - expected_sq).abs()
+                <= 2.0 * dim as f32 * f32::EPSILON * expected_sq + 1e-6,
+            "{}: norm² {norm_sq} vs {expected_sq}",
+            kernel.name(),
+        );
+    }
+}
+
 /// Partition invariance: encoding the same token stream with any two
 /// chunk sizes must produce byte-identical output. This is the
 /// correctness guard on the "upload tile → encode → drop" pipeline
@@ -1341,6 +1483,68 @@ fn prop_build_inverted_file_invariants(tc: TestCase) {
 // search.rs
 // ---------------------------------------------------------------------------
 
+/// Differential: fused decode + MaxSim on every kernel matches the
+/// reference it replaces — decode each token, L2-normalise it, take
+/// each query token's best scalar dot, and sum — within tolerance.
+#[hegel::test(test_cases = 50)]
+fn prop_simd_maxsim_matches_scalar_reference(tc: TestCase) {
+    use docbert_plaid::{
+        codec::{DecodeTable, ResidualCodec},
+        simd::{Kernel, MaxSim},
+    };
+    let nbits: u32 = tc.draw(gs::sampled_from(vec![1u32, 2, 4, 8]));
+    let dim = tc.draw(gs::integers::<usize>().min_value(1).max_value(48));
+    let n_q = tc.draw(gs::integers::<usize>().min_value(1).max_value(6));
+    let n_tokens = tc.draw(gs::integers::<usize>().min_value(1).max_value(8));
+
+    let num_buckets = 1u32 << nbits;
+    let codec = ResidualCodec {
+        nbits,
+        dim,
+        centroids: tc.draw(unit_rows(dim, 3)),
+        bucket_cutoffs: (1..num_buckets)
+            .map(|i| i as f32 / num_buckets as f32 - 0.5)
+            .collect(),
+        bucket_weights: (0..num_buckets)
+            .map(|i| (i as f32 + 0.5) / num_buckets as f32 - 0.5)
+            .collect(),
+    };
+    let table = DecodeTable::new(&codec);
+    let query = tc.draw(unit_rows(dim, n_q));
+    let tokens = tc.draw(unit_rows(dim, n_tokens));
+
+    let mut centroid_ids = Vec::new();
+    let mut residuals = Vec::new();
+    let mut decoded = Vec::new();
+    for token in tokens.chunks_exact(dim) {
+        let encoded = codec.encode_vector(token).unwrap();
+        let raw = codec.decode_vector(&encoded).unwrap();
+        let norm = raw.iter().map(|x| x * x).sum::<f32>().sqrt().max(1e-12);
+        decoded.push(raw.iter().map(|x| x / norm).collect::<Vec<f32>>());
+        centroid_ids.push(encoded.centroid_id);
+        residuals.extend_from_slice(&encoded.codes);
+    }
+    let expected: f32 = query
+        .chunks_exact(dim)
+        .map(|q| {
+            decoded
+                .iter()
+                .map(|d| Kernel::scalar().dot(q, d))
+                .fold(f32::NEG_INFINITY, f32::max)
+        })
+        .sum();
+
+    for kernel in Kernel::available() {
+        let mut maxsim = MaxSim::new(kernel, &codec, &table, &query);
+        let got = maxsim.score(&centroid_ids, &residuals);
+        assert!(
+            (got - expected).abs() <= 1e-5 * n_q as f32,
+            "{}: fused MaxSim {got} vs reference {expected}",
+            kernel.name(),
+        );
+    }
+}
+
 /// Algebraic + shape: `search` returns at most `top_k` results, its
 /// scores are non-increasing, and equal-scoring entries are ordered
 /// by ascending `doc_id`. All three are load-bearing for downstream
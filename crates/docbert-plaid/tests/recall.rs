@@ -25,6 +25,7 @@
 use docbert_plaid::{
     distance::dot,
     index::{DocumentTokens, IndexParams, build_index},
+    kmeans::KMeansOptions,
     search::{SearchParams, search},
 };
 use rand::{RngExt, SeedableRng, rngs::StdRng};
@@ -127,6 +128,7 @@ fn plaid_search_recall_at_10_against_decoded_brute_force_is_high() {
             nbits: 4,
             k_centroids: 32,
             max_kmeans_iters: 20,
+            kmeans: KMeansOptions::default(),
         },
     )
     .unwrap();
@@ -202,6 +204,7 @@ fn plaid_search_recall_at_10_against_original_brute_force_is_meaningful() {
             nbits: 4,
             k_centroids: 32,
             max_kmeans_iters: 20,
+            kmeans: KMeansOptions::default(),
         },
     )
     .unwrap();
@@ -262,6 +265,7 @@ fn plaid_search_score_matches_recomputed_maxsim_on_decoded_tokens() {
             nbits: 4,
             k_centroids: 16,
             max_kmeans_iters: 20,
+            kmeans: KMeansOptions::default(),
         },
     )
     .unwrap();
@@ -292,9 +296,11 @@ fn plaid_search_score_matches_recomputed_maxsim_on_decoded_tokens() {
         }
         let recomputed = {
             let mut score = 0.0f32;
-            for q in query.chunks_exact(DIM) {
+            for q in query.as_chunks::<DIM>().0 {
                 let best = decoded_tokens
-                    .chunks_exact(DIM)
+                    .as_chunks::<DIM>()
+                    .0
+                    .iter()
                     .map(|d| dot(q, d))
                     .fold(f32::NEG_INFINITY, f32::max);
                 if best.is_finite() {
@@ -327,6 +333,7 @@ fn plaid_search_returns_results_sorted_descending_by_score() {
             nbits: 2,
             k_centroids: 16,
             max_kmeans_iters: 10,
+            kmeans: KMeansOptions::default(),
         },
     )
     .unwrap();
@@ -381,6 +388,7 @@ fn centroid_interaction_shortlist_keeps_recall_high_against_no_shortlist() {
             nbits: 4,
             k_centroids: 64,
             max_kmeans_iters: 20,
+            kmeans: KMeansOptions::default(),
         },
     )
     .unwrap();
@@ -440,6 +448,7 @@ fn maxsim_score_for_unit_norm_query_is_at_most_q_times_one() {
             nbits: 4,
             k_centroids: 8,
             max_kmeans_iters: 20,
+            kmeans: KMeansOptions::default(),
         },
     )
     .unwrap();
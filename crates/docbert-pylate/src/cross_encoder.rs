@@ -0,0 +1,251 @@
+use std::{fs, path::Path};
+
+use candle_core::{DType, Device, Tensor};
+use candle_nn::VarBuilder;
+use tokenizers::{
+    EncodeInput,
+    PaddingParams,
+    PaddingStrategy,
+    Tokenizer,
+    TruncationParams,
+};
+
+use crate::{
+    error::ColbertError,
+    modernbert::{
+        Config as ModernBertConfig,
+        ModernBertForSequenceClassification,
+    },
+};
+
+/// Longest `(query, passage)` pair fed to the classifier when neither
+/// the caller nor the checkpoint says otherwise. Matches the
+/// `max_length` most sentence-transformers cross-encoders ship with.
+const DEFAULT_MAX_LENGTH: usize = 512;
+
+/// Default number of pairs scored per forward pass.
+const DEFAULT_BATCH_SIZE: usize = 16;
+
+/// A ModernBERT cross-encoder that scores `(query, passage)` pairs.
+///
+/// Unlike [`ColBERT`](crate::ColBERT), which encodes queries and
+/// documents independently, a cross-encoder reads both texts in one
+/// sequence and emits a single relevance logit per pair. That makes it
+/// far more accurate and far more expensive, so it is only suitable as
+/// a final rerank over a handful of candidates.
+pub struct CrossEncoder {
+    model: ModernBertForSequenceClassification,
+    tokenizer: Tokenizer,
+    max_length: usize,
+    batch_size: usize,
+    device: Device,
+}
+
+impl CrossEncoder {
+    /// Creates a cross-encoder from raw checkpoint bytes.
+    ///
+    /// `config_bytes` must describe a `ModernBertForSequenceClassification`
+    /// architecture. `max_length` falls back to the smaller of the model's
+    /// `max_position_embeddings` and 512 when unset.
+    pub fn new(
+        weights: Vec<u8>,
+        tokenizer_bytes: Vec<u8>,
+        config_bytes: Vec<u8>,
+        max_length: Option<usize>,
+        device: &Device,
+    ) -> Result<Self, ColbertError> {
+        let config_value: serde_json::Value =
+            serde_json::from_slice(&config_bytes)?;
+        let architecture = config_value["architectures"]
+            .as_array()
+            .and_then(|arr| arr.first())
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| {
+                ColbertError::Operation(
+                    "Missing or invalid 'architectures' in config.json".into(),
+                )
+            })?;
+        if architecture != "ModernBertForSequenceClassification" {
+            return Err(ColbertError::Operation(format!(
+                "Unsupported cross-encoder architecture: {architecture}"
+            )));
+        }
+
+        let config: ModernBertConfig = serde_json::from_slice(&config_bytes)?;
+        let vb =
+            VarBuilder::from_buffered_safetensors(weights, DType::F32, device)?;
+        let model = ModernBertForSequenceClassification::load(vb, &config)?;
+
+        let mut tokenizer = Tokenizer::from_bytes(&tokenizer_bytes)?;
+        let max_length = max_length.unwrap_or_else(|| {
+            config.max_position_embeddings.min(DEFAULT_MAX_LENGTH)
+        });
+        tokenizer.with_truncation(Some(TruncationParams {
+            max_length,
+            ..Default::default()
+        }))?;
+        tokenizer.with_padding(Some(PaddingParams {
+            strategy: PaddingStrategy::BatchLongest,
+            pad_id: config.pad_token_id,
+            ..Default::default()
+        }));
+
+        Ok(Self {
+            model,
+            tokenizer,
+            max_length,
+            batch_size: DEFAULT_BATCH_SIZE,
+            device: device.clone(),
+        })
+    }
+
+    /// Loads a cross-encoder from a local checkpoint directory.
+    ///
+    /// The directory must contain `config.json`, `model.safetensors`,
+    /// and `tokenizer.json`, which is the layout `save_pretrained`
+    /// produces.
+    pub fn from_dir(
+        path: &Path,
+        device: &Device,
+    ) -> Result<Self, ColbertError> {
+        let config_path = path.join("config.json");
+        let weights_path = path.join("model.safetensors");
+        let tokenizer_path = path.join("tokenizer.json");
+        for file in [&config_path, &weights_path, &tokenizer_path] {
+            if !file.exists() {
+                return Err(ColbertError::Io(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!(
+                        "File not found in cross-encoder directory: {}",
+                        file.display()
+                    ),
+                )));
+            }
+        }
+
+        Self::new(
+            fs::read(weights_path)?,
+            fs::read(tokenizer_path)?,
+            fs::read(config_path)?,
+            None,
+            device,
+        )
+    }
+
+    /// Sets the number of pairs scored per forward pass.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Maximum token length of one `(query, passage)` pair.
+    pub fn max_length(&self) -> usize {
+        self.max_length
+    }
+
+    /// Scores every passage against `query`, returning one relevance
+    /// score per passage in input order. Higher is more relevant.
+    pub fn score(
+        &self,
+        query: &str,
+        passages: &[String],
+    ) -> Result<Vec<f32>, ColbertError> {
+        let mut scores = Vec::with_capacity(passages.len());
+        for batch in passages.chunks(self.batch_size) {
+            let inputs: Vec<EncodeInput> = batch
+                .iter()
+                .map(|passage| (query, passage.as_str()).into())
+                .collect();
+            let encodings = self.tokenizer.encode_batch(inputs, true)?;
+
+            let batch_len = encodings.len();
+            let seq_len = encodings.first().map_or(0, |e| e.get_ids().len());
+            let mut ids = Vec::with_capacity(batch_len * seq_len);
+            let mut mask = Vec::with_capacity(batch_len * seq_len);
+            for encoding in &encodings {
+                ids.extend_from_slice(encoding.get_ids());
+                mask.extend_from_slice(encoding.get_attention_mask());
+            }
+            let ids =
+                Tensor::from_vec(ids, (batch_len, seq_len), &self.device)?;
+            let mask =
+                Tensor::from_vec(mask, (batch_len, seq_len), &self.device)?;
+
+            let logits = self
+                .model
+                .forward_logits(&ids, &mask)?
+                .to_dtype(DType::F32)?
+                .to_vec2::<f32>()?;
+            scores.extend(logits.iter().map(|row| relevance_from_logits(row)));
+        }
+        Ok(scores)
+    }
+}
+
+/// Collapses one row of classifier logits into a relevance score.
+///
+/// Single-label checkpoints (the common reranker layout) return the raw
+/// logit. Multi-label checkpoints are treated as `[irrelevant, ...,
+/// relevant]` and return the softmax probability of the last label.
+pub(crate) fn relevance_from_logits(logits: &[f32]) -> f32 {
+    match logits {
+        [] => 0.0,
+        [single] => *single,
+        _ => {
+            let max = logits.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+            let denom: f32 = logits.iter().map(|l| (l - max).exp()).sum();
+            let last = logits[logits.len() - 1];
+            (last - max).exp() / denom
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_label_logits_are_returned_unchanged() {
+        assert_eq!(relevance_from_logits(&[3.25]), 3.25);
+        assert_eq!(relevance_from_logits(&[-1.5]), -1.5);
+    }
+
+    #[test]
+    fn multi_label_logits_return_last_label_probability() {
+        let score = relevance_from_logits(&[0.0, 0.0]);
+        assert!((score - 0.5).abs() < 1e-6);
+
+        let confident = relevance_from_logits(&[-4.0, 4.0]);
+        assert!(confident > 0.99);
+    }
+
+    #[test]
+    fn empty_logits_score_zero() {
+        assert_eq!(relevance_from_logits(&[]), 0.0);
+    }
+
+    #[test]
+    fn new_rejects_non_classification_architectures() {
+        let config = br#"{"architectures":["ModernBertModel"]}"#.to_vec();
+        let err = CrossEncoder::new(
+            Vec::new(),
+            Vec::new(),
+            config,
+            None,
+            &Device::Cpu,
+        )
+        .err()
+        .expect("expected architecture error");
+        assert!(err.to_string().contains("ModernBertModel"));
+    }
+
+    #[test]
+    fn from_dir_reports_missing_files() {
+        let tmp = std::env::temp_dir().join("docbert-cross-encoder-missing");
+        let _ = fs::create_dir_all(&tmp);
+        let err = CrossEncoder::from_dir(&tmp, &Device::Cpu)
+            .err()
+            .expect("expected missing-file error");
+        assert!(matches!(err, ColbertError::Io(_)));
+    }
+}
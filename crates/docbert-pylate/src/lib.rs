@@ -1,4 +1,5 @@
 pub mod builder;
+pub mod cross_encoder;
 pub mod error;
 pub mod model;
 pub mod modernbert;
@@ -7,14 +8,17 @@ pub mod types;
 pub mod utils;
 
 pub use builder::ColbertBuilder;
+pub use cross_encoder::CrossEncoder;
 pub use error::ColbertError;
 pub use model::{BaseModel, ColBERT};
 pub use pooling::hierarchical_pooling;
 pub use types::{
     EncodeInput,
     EncodeOutput,
+    MatchExplanation,
     RawSimilarityOutput,
     Similarities,
     SimilarityInput,
+    TokenAlignment,
 };
 pub use utils::normalize_l2;
@@ -16,7 +16,7 @@ use crate::{
     builder::{ColbertBuilder, DenseModuleData},
     error::ColbertError,
     modernbert::{Config as ModernBertConfig, ModernBert},
-    types::Similarities,
+    types::{MatchExplanation, Similarities, TokenAlignment},
     utils::normalize_l2,
 };
 
@@ -223,6 +223,62 @@ pub(crate) fn compute_raw_similarity(
         .map_err(ColbertError::from)
 }
 
+/// Byte range `(start, end)` of a token in its source text, `None` for
+/// tokens that have no source text.
+type TokenSpan = Option<(usize, usize)>;
+
+/// Picks the best document token for every query token of a
+/// `(q_tokens, d_tokens)` similarity matrix, returning `(index, score)`.
+///
+/// Ties keep the earliest document token. Rows over an empty document
+/// come back as `(0, 0.0)`.
+pub(crate) fn best_document_tokens(
+    similarity: &[Vec<f32>],
+) -> Vec<(usize, f32)> {
+    similarity
+        .iter()
+        .map(|row| {
+            row.iter().enumerate().fold(
+                (0, f32::NEG_INFINITY),
+                |best, (idx, &score)| {
+                    if score > best.1 { (idx, score) } else { best }
+                },
+            )
+        })
+        .map(|(idx, score)| {
+            if score.is_finite() {
+                (idx, score)
+            } else {
+                (0, 0.0)
+            }
+        })
+        .collect()
+}
+
+/// Maps tokenizer offsets over `{prefix}{prompt}{text}` back to byte
+/// spans in `text`.
+///
+/// Special tokens and tokens that start inside the `header_len` bytes of
+/// prefix and prompt have no source text and map to `None`.
+pub(crate) fn text_spans(
+    offsets: &[(usize, usize)],
+    special_tokens_mask: &[u32],
+    header_len: usize,
+) -> Vec<TokenSpan> {
+    offsets
+        .iter()
+        .enumerate()
+        .map(|(idx, &(start, end))| {
+            let special = special_tokens_mask.get(idx).copied().unwrap_or(0);
+            if special != 0 || start < header_len || end <= start {
+                None
+            } else {
+                Some((start - header_len, end - header_len))
+            }
+        })
+        .collect()
+}
+
 /// Builds the Dense layer chain from raw module bytes.
 ///
 /// Each entry's `config.json` must be `{ in_features, out_features, bias,
@@ -819,6 +875,100 @@ impl ColBERT {
         compute_raw_similarity(queries_embeddings, documents_embeddings)
     }
 
+    /// Explains a single query/document MaxSim score token by token.
+    ///
+    /// Encodes both texts, takes the [`raw_similarity`](Self::raw_similarity)
+    /// matrix and, for every query token, reports the document token with
+    /// the highest dot product together with that token's byte span in
+    /// `document`. The alignment scores sum to the MaxSim similarity.
+    pub fn explain(
+        &mut self,
+        query: &str,
+        document: &str,
+    ) -> Result<MatchExplanation, ColbertError> {
+        let query_embeddings = self.encode(&[query.to_string()], true)?;
+        let document_embeddings =
+            self.encode(&[document.to_string()], false)?;
+        let similarity = self
+            .raw_similarity(&query_embeddings, &document_embeddings)?
+            .squeeze(0)?
+            .squeeze(0)?
+            .to_vec2::<f32>()?;
+
+        let query_tokens = self.token_surface_forms(query, true)?;
+        let document_tokens = self.token_surface_forms(document, false)?;
+
+        let alignments: Vec<TokenAlignment> = best_document_tokens(&similarity)
+            .into_iter()
+            .enumerate()
+            .map(|(query_index, (document_index, score))| {
+                let (document_token, span) = document_tokens
+                    .get(document_index)
+                    .cloned()
+                    .unwrap_or_default();
+                TokenAlignment {
+                    query_index,
+                    query_token: query_tokens
+                        .get(query_index)
+                        .map(|(token, _)| token.clone())
+                        .unwrap_or_default(),
+                    document_index,
+                    document_token,
+                    score,
+                    span,
+                }
+            })
+            .collect();
+        Ok(MatchExplanation {
+            score: alignments.iter().map(|a| a.score).sum(),
+            alignments,
+        })
+    }
+
+    /// Tokenizes `text` the way [`encode`](Self::encode) does and returns
+    /// each token with its byte span in `text`.
+    ///
+    /// Uses the same prompt, prefix, truncation and padding as the
+    /// encoder, so entry `i` lines up with row `i` of the encoded tensor.
+    fn token_surface_forms(
+        &mut self,
+        text: &str,
+        is_query: bool,
+    ) -> Result<Vec<(String, TokenSpan)>, ColbertError> {
+        let (prefix, prompt, max_length) = if is_query {
+            (&self.query_prefix, &self.query_prompt, self.query_length)
+        } else {
+            (
+                &self.document_prefix,
+                &self.document_prompt,
+                self.document_length,
+            )
+        };
+        let header = format!("{prefix}{prompt}");
+        let _ = self.tokenizer.with_truncation(Some(
+            tokenizers::TruncationParams {
+                max_length,
+                ..Default::default()
+            },
+        ));
+        let padding = is_query.then(|| PaddingParams {
+            strategy: PaddingStrategy::Fixed(max_length),
+            pad_id: self.mask_token_id,
+            pad_token: self.mask_token.clone(),
+            ..Default::default()
+        });
+        self.tokenizer.with_padding(padding);
+
+        let encoding =
+            self.tokenizer.encode(format!("{header}{text}"), true)?;
+        let spans = text_spans(
+            encoding.get_offsets(),
+            encoding.get_special_tokens_mask(),
+            header.len(),
+        );
+        Ok(encoding.get_tokens().iter().cloned().zip(spans).collect())
+    }
+
     fn tensorize_encodings(
         &self,
         encodings: &[Encoding],
@@ -962,10 +1112,12 @@ mod tests {
     use candle_core::{DType, Tensor};
 
     use super::{
+        best_document_tokens,
         concatenate_embedding_batches,
         filter_normalize_and_pad_compact,
         normalize_and_mask_padded,
         normalize_mask_and_truncate_right_padded,
+        text_spans,
     };
 
     #[test]
@@ -1085,6 +1237,31 @@ mod tests {
             concatenate_embedding_batches(vec![first, second]).unwrap();
         assert_eq!(combined.dims3().unwrap(), (128, 519, 128));
     }
+
+    #[test]
+    fn best_document_tokens_picks_row_maximum() {
+        let similarity = vec![
+            vec![0.1, 0.9, 0.3],
+            vec![0.5, 0.5, 0.2],
+            vec![-0.4, -0.1, -0.7],
+        ];
+        assert_eq!(
+            best_document_tokens(&similarity),
+            vec![(1, 0.9), (0, 0.5), (1, -0.1)]
+        );
+        assert_eq!(best_document_tokens(&[vec![]]), vec![(0, 0.0)]);
+    }
+
+    #[test]
+    fn text_spans_drop_special_and_prefix_tokens() {
+        // "[D] hello world" tokenized as [CLS] [D] hello world [SEP].
+        let offsets = [(0, 0), (0, 3), (4, 9), (10, 15), (0, 0)];
+        let special = [1, 0, 0, 0, 1];
+        assert_eq!(
+            text_spans(&offsets, &special, 4),
+            vec![None, None, Some((0, 5)), Some((6, 11)), None]
+        );
+    }
 }
 
 #[cfg(test)]
@@ -1,4 +1,3 @@
-use core::f32;
 use std::{
     collections::HashMap,
     sync::{Arc, Mutex},
@@ -1011,8 +1010,12 @@ pub struct ModernBertForSequenceClassification {
 }
 
 impl ModernBertForSequenceClassification {
+    /// Loads a HuggingFace `ModernBertForSequenceClassification`
+    /// checkpoint. Unlike the PyLate ColBERT exports, these keep the
+    /// encoder weights under a `model.` prefix next to the `head.` and
+    /// `classifier.` tensors.
     pub fn load(vb: VarBuilder, config: &Config) -> Result<Self> {
-        let model = ModernBert::load(vb.clone(), config)?;
+        let model = ModernBert::load(vb.pp("model"), config)?;
         let classifier = ModernBertClassifier::load(vb.clone(), config)?;
         let head = ModernBertHead::load(vb.pp("head"), config)?;
         Ok(Self {
@@ -1027,10 +1030,20 @@ impl ModernBertForSequenceClassification {
         })
     }
 
+    /// Class probabilities, `[batch, num_labels]`.
     pub fn forward(&self, xs: &Tensor, mask: &Tensor) -> Result<Tensor> {
+        softmax(&self.forward_logits(xs, mask)?, D::Minus1)
+    }
+
+    /// Raw classifier logits, `[batch, num_labels]`.
+    ///
+    /// Single-label cross-encoders are trained on the logit itself, and
+    /// a softmax over one column is always `1.0`, so rerankers must read
+    /// this instead of [`forward`](Self::forward).
+    pub fn forward_logits(&self, xs: &Tensor, mask: &Tensor) -> Result<Tensor> {
         let output = self.model.forward(xs, mask)?;
         let last_hidden_state = match self.classifier_pooling {
-            ClassifierPooling::CLS => output.i((.., .., 0))?,
+            ClassifierPooling::CLS => output.i((.., 0, ..))?.contiguous()?,
             ClassifierPooling::MEAN => {
                 let unsqueezed_mask =
                     &mask.unsqueeze(D::Minus1)?.to_dtype(DType::F32)?;
@@ -1041,10 +1054,13 @@ impl ModernBertForSequenceClassification {
                 )?
             }
         };
-        let xs = self
-            .head
+        self.head
             .forward(&last_hidden_state)?
-            .apply(&self.classifier)?;
-        Ok(xs)
+            .apply(&self.classifier.classifier)
+    }
+
+    /// Number of output labels the classifier was trained with.
+    pub fn num_labels(&self) -> usize {
+        self.classifier.classifier.weight().dim(0).unwrap_or(0)
     }
 }
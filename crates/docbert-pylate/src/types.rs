@@ -57,3 +57,34 @@ pub struct RawSimilarityOutput {
     /// The tokens corresponding to each document.
     pub document_tokens: Vec<Vec<String>>,
 }
+
+/// The document token a single query token matched under MaxSim.
+///
+/// Produced by [`ColBERT::explain`](crate::ColBERT::explain). The `score`
+/// is the query token's contribution to the overall MaxSim score, i.e.
+/// the maximum dot product over every document token.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TokenAlignment {
+    /// Position of the query token in the encoded query.
+    pub query_index: usize,
+    /// Surface form of the query token as produced by the tokenizer.
+    pub query_token: String,
+    /// Position of the best-matching token in the encoded document.
+    pub document_index: usize,
+    /// Surface form of the best-matching document token.
+    pub document_token: String,
+    /// Dot product between the two token embeddings.
+    pub score: f32,
+    /// Byte range `(start, end)` of the document token in the input text.
+    /// `None` for special and prefix tokens that have no source text.
+    pub span: Option<(usize, usize)>,
+}
+
+/// Token-level breakdown of one query/document MaxSim score.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MatchExplanation {
+    /// Sum of every alignment's score; equal to the MaxSim similarity.
+    pub score: f32,
+    /// One entry per encoded query token, in query order.
+    pub alignments: Vec<TokenAlignment>,
+}
@@ -37,19 +37,32 @@ pub enum Command {
         #[command(subcommand)]
         action: ContextAction,
     },
+    /// Manage saved searches and the documents that newly match them
+    Saved {
+        #[command(subcommand)]
+        action: SavedAction,
+    },
     /// Search indexed documents across collections
     Search(SearchArgs),
     /// Semantic-only search across all collections
     #[command(name = "ssearch")]
     Ssearch(SemanticSearchArgs),
+    /// Find documents similar to an indexed document
+    Similar(SimilarArgs),
+    /// Score search quality against relevance judgments
+    Eval(EvalArgs),
     /// Retrieve a document by reference
     Get(GetArgs),
     /// Retrieve multiple documents matching a glob pattern
     MultiGet(MultiGetArgs),
+    /// Complete a prefix to indexed document titles and paths
+    Complete(CompleteArgs),
+    /// List every line matching a regular expression or literal string
+    Grep(GrepArgs),
     /// Rebuild indexes from source files (full rebuild)
     Rebuild(RebuildArgs),
     /// Rebuild the PLAID semantic index from existing embeddings
-    Reindex,
+    Reindex(ReindexArgs),
     /// Sync collections with source files (incremental)
     Sync(SyncArgs),
     /// Show system status and statistics
@@ -65,6 +78,11 @@ pub enum Command {
         #[command(subcommand)]
         action: ModelAction,
     },
+    /// Manage the persisted default PLAID search parameters
+    Plaid {
+        #[command(subcommand)]
+        action: PlaidAction,
+    },
     /// Generate shell completions
     #[command(hide = true)]
     Completions(CompletionsArgs),
@@ -119,6 +137,68 @@ pub enum ContextAction {
     },
 }
 
+// -- Saved search subcommands --
+
+#[derive(Debug, Subcommand)]
+pub enum SavedAction {
+    /// Save a query and take a baseline of its current results
+    Add(SavedAddArgs),
+    /// List saved searches
+    List {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Run a saved search and print its current results
+    Run {
+        /// Name of the saved search
+        name: String,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+        /// Output file paths only
+        #[arg(long, conflicts_with = "json")]
+        files: bool,
+    },
+    /// Remove a saved search
+    Remove {
+        /// Name of the saved search
+        name: String,
+    },
+    /// Show documents that entered saved searches since the last clear
+    New {
+        /// Only show this saved search
+        name: Option<String>,
+        /// Forget the shown hits afterwards
+        #[arg(long)]
+        clear: bool,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Debug, Parser)]
+pub struct SavedAddArgs {
+    /// Unique name for the saved search
+    pub name: String,
+
+    /// Search query
+    pub query: String,
+
+    /// Search only within this named collection
+    #[arg(short = 'c', long)]
+    pub collection: Option<String>,
+
+    /// Number of top results to watch for newcomers
+    #[arg(short = 'n', long, default_value = "10")]
+    pub count: usize,
+
+    /// Retrieval mode
+    #[arg(long, default_value = "hybrid", value_parser = ["hybrid", "semantic", "bm25"])]
+    pub mode: String,
+}
+
 // -- Model --
 
 #[derive(Debug, Subcommand)]
@@ -136,6 +216,95 @@ pub enum ModelAction {
     },
     /// Clear the stored model setting (revert to default)
     Clear,
+    /// Persist a local cross-encoder checkpoint used to rerank results
+    SetReranker {
+        /// Directory with config.json, model.safetensors and tokenizer.json
+        path: String,
+    },
+    /// Clear the stored reranker setting (disables reranking)
+    ClearReranker,
+}
+
+// -- PLAID search parameters --
+
+#[derive(Debug, Subcommand)]
+pub enum PlaidAction {
+    /// Show the stored PLAID search parameters
+    Show {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Store PLAID search parameters used by every search
+    Set(PlaidQueryArgs),
+    /// Clear the stored parameters (revert to the paper defaults)
+    Clear,
+}
+
+/// PLAID query-time parameters. Unset ones keep the stored default, or
+/// the PLAID paper's value for the result count.
+#[derive(Debug, Clone, Default, Parser)]
+pub struct PlaidQueryArgs {
+    /// Centroids each query token probes
+    #[arg(long, value_name = "N", value_parser = parse_positive)]
+    pub n_probe: Option<usize>,
+
+    /// Candidates kept by centroid interaction for exact scoring
+    #[arg(long, value_name = "N", value_parser = parse_positive)]
+    pub n_candidate_docs: Option<usize>,
+
+    /// Minimum centroid score for a centroid to produce candidates
+    #[arg(long, value_name = "SCORE", value_parser = parse_finite)]
+    pub centroid_score_threshold: Option<f32>,
+
+    /// Skip PLAID pruning: probe every centroid and score every candidate
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "n_probe", "n_candidate_docs", "centroid_score_threshold",
+        ]
+    )]
+    pub exhaustive: bool,
+
+    /// Score every stored embedding at full precision instead of using
+    /// PLAID
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "n_probe", "n_candidate_docs", "centroid_score_threshold",
+            "exhaustive", "no_exact",
+        ]
+    )]
+    pub exact: bool,
+
+    /// Use PLAID even on an index small enough to be scanned exactly
+    #[arg(long)]
+    pub no_exact: bool,
+}
+
+impl PlaidQueryArgs {
+    pub fn params(&self) -> docbert_core::plaid::PlaidQueryParams {
+        docbert_core::plaid::PlaidQueryParams {
+            n_probe: self.n_probe,
+            n_candidate_docs: self.n_candidate_docs,
+            centroid_score_threshold: self.centroid_score_threshold,
+            exhaustive: self.exhaustive,
+            exact: if self.exact {
+                Some(true)
+            } else if self.no_exact {
+                Some(false)
+            } else {
+                None
+            },
+        }
+    }
+}
+
+fn parse_finite(value: &str) -> Result<f32, String> {
+    match value.parse::<f32>() {
+        Ok(score) if score.is_finite() => Ok(score),
+        _ => Err(format!("expected a number, got `{value}`")),
+    }
 }
 
 // -- Search --
@@ -143,7 +312,12 @@ pub enum ModelAction {
 #[derive(Debug, Parser)]
 pub struct SearchArgs {
     /// The search query
-    pub query: String,
+    #[arg(required_unless_present = "queries_file")]
+    pub query: Option<String>,
+
+    /// Run every query in this file, one per line, as one batch
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["query", "offset"])]
+    pub queries_file: Option<PathBuf>,
 
     /// Number of results to return
     #[arg(short = 'n', long, default_value = "10")]
@@ -176,6 +350,117 @@ pub struct SearchArgs {
     /// Disable fuzzy matching in the first stage
     #[arg(long)]
     pub no_fuzzy: bool,
+
+    /// Local cross-encoder checkpoint used to rerank the top results
+    #[arg(long)]
+    pub rerank_model: Option<String>,
+
+    /// Show up to N matching passages per document (default N: 3)
+    #[arg(long, value_name = "N", num_args = 0..=1, default_missing_value = "3")]
+    pub passages: Option<usize>,
+
+    /// Group passages per document or list them flat
+    #[arg(long, default_value = "document", value_parser = ["document", "passage"])]
+    pub group: String,
+
+    /// Show which document tokens each query token matched
+    #[arg(long)]
+    pub explain: bool,
+
+    /// Expand the query from the top results and search again
+    #[arg(long)]
+    pub prf: bool,
+
+    /// Number of top results used as feedback by --prf
+    #[arg(long, value_name = "N", default_value = "3", requires = "prf")]
+    pub prf_docs: usize,
+
+    /// Number of expansion terms and embeddings added by --prf
+    #[arg(long, value_name = "N", default_value = "10", requires = "prf")]
+    pub prf_terms: usize,
+
+    /// Weight of the --prf expansion against the original query
+    #[arg(long, value_name = "W", default_value = "0.5", requires = "prf")]
+    pub prf_weight: f32,
+
+    /// Count candidates per collection, extension, year and tag
+    #[arg(long)]
+    pub facets: bool,
+
+    /// Skip this many ranked results before the first one shown
+    #[arg(long, default_value = "0")]
+    pub offset: usize,
+
+    /// Search with the "did you mean" suggestion when one is offered
+    #[arg(long)]
+    pub autocorrect: bool,
+
+    #[command(flatten)]
+    pub plaid: PlaidQueryArgs,
+
+    /// Rank the passages of this one document: path, #doc_id, or
+    /// collection:path
+    #[arg(
+        long = "in",
+        value_name = "REF",
+        conflicts_with_all = [
+            "queries_file", "collection", "all", "files", "bm25_only",
+            "no_fuzzy", "rerank_model", "passages", "group", "explain",
+            "prf", "facets", "offset", "autocorrect", "n_probe",
+            "n_candidate_docs", "centroid_score_threshold", "exhaustive",
+            "exact", "no_exact",
+        ]
+    )]
+    pub within: Option<String>,
+}
+
+// -- Evaluation --
+
+#[derive(Debug, Parser)]
+pub struct EvalArgs {
+    /// JSONL file of queries, one `{"id": ..., "query": ...}` per line
+    #[arg(long, value_name = "PATH")]
+    pub queries: PathBuf,
+
+    /// Relevance judgments (BEIR TSV or TREC qrels)
+    #[arg(long, value_name = "PATH")]
+    pub qrels: PathBuf,
+
+    /// Retrieval mode to evaluate
+    #[arg(long, default_value = "hybrid", value_parser = ["hybrid", "semantic", "bm25"])]
+    pub mode: String,
+
+    /// Rank cutoff for nDCG, MRR and recall
+    #[arg(short = 'k', long, default_value = "10")]
+    pub k: usize,
+
+    /// Search only within this named collection
+    #[arg(short = 'c', long)]
+    pub collection: Option<String>,
+
+    /// Disable fuzzy matching in the first stage
+    #[arg(long)]
+    pub no_fuzzy: bool,
+
+    /// Local cross-encoder checkpoint used to rerank the top results
+    #[arg(long)]
+    pub rerank_model: Option<String>,
+
+    /// Expand each query from its top results and search again
+    #[arg(long)]
+    pub prf: bool,
+
+    #[command(flatten)]
+    pub plaid: PlaidQueryArgs,
+
+    /// Also rank every query with PLAID and with exact MaxSim, and
+    /// report how much of the exact top k PLAID misses
+    #[arg(long)]
+    pub plaid_recall: bool,
+
+    /// Output the report as JSON
+    #[arg(long)]
+    pub json: bool,
 }
 
 // -- Semantic-only Search --
@@ -204,6 +489,65 @@ pub struct SemanticSearchArgs {
     /// Minimum score threshold
     #[arg(long, default_value = "0.0")]
     pub min_score: f32,
+
+    /// Local cross-encoder checkpoint used to rerank the top results
+    #[arg(long)]
+    pub rerank_model: Option<String>,
+
+    /// Show up to N matching passages per document (default N: 3)
+    #[arg(long, value_name = "N", num_args = 0..=1, default_missing_value = "3")]
+    pub passages: Option<usize>,
+
+    /// Group passages per document or list them flat
+    #[arg(long, default_value = "document", value_parser = ["document", "passage"])]
+    pub group: String,
+
+    /// Show which document tokens each query token matched
+    #[arg(long)]
+    pub explain: bool,
+
+    /// Count candidates per collection, extension, year and tag
+    #[arg(long)]
+    pub facets: bool,
+
+    /// Skip this many ranked results before the first one shown
+    #[arg(long, default_value = "0")]
+    pub offset: usize,
+
+    #[command(flatten)]
+    pub plaid: PlaidQueryArgs,
+}
+
+// -- Similar --
+
+#[derive(Debug, Parser)]
+pub struct SimilarArgs {
+    /// Source document reference: path, #doc_id, or collection:path
+    pub reference: String,
+
+    /// Number of results to return
+    #[arg(short = 'n', long, default_value = "10")]
+    pub count: usize,
+
+    /// Return only documents from this named collection
+    #[arg(short = 'c', long)]
+    pub collection: Option<String>,
+
+    /// Output results as JSON
+    #[arg(long)]
+    pub json: bool,
+
+    /// Output only file paths (one per line)
+    #[arg(long)]
+    pub files: bool,
+
+    /// Show up to N matching passages per document (default N: 3)
+    #[arg(long, value_name = "N", num_args = 0..=1, default_missing_value = "3")]
+    pub passages: Option<usize>,
+
+    /// Group passages per document or list them flat
+    #[arg(long, default_value = "document", value_parser = ["document", "passage"])]
+    pub group: String,
 }
 
 // -- Get --
@@ -250,6 +594,66 @@ pub struct MultiGetArgs {
     pub full: bool,
 }
 
+// -- Complete --
+
+#[derive(Debug, Parser)]
+pub struct CompleteArgs {
+    /// Start of a title or relative path
+    pub prefix: String,
+
+    /// Number of suggestions to return
+    #[arg(short = 'n', long, default_value = "10")]
+    pub count: usize,
+
+    /// Restrict to a specific collection
+    #[arg(short = 'c', long)]
+    pub collection: Option<String>,
+
+    /// Output as JSON array
+    #[arg(long)]
+    pub json: bool,
+}
+
+// -- Grep --
+
+#[derive(Debug, Parser)]
+pub struct GrepArgs {
+    /// Regular expression to match against each line
+    pub pattern: String,
+
+    /// Treat the pattern as a literal string
+    #[arg(short = 'F', long)]
+    pub fixed_strings: bool,
+
+    /// Match regardless of case
+    #[arg(short = 'i', long)]
+    pub ignore_case: bool,
+
+    /// Search only within this named collection
+    #[arg(short = 'c', long)]
+    pub collection: Option<String>,
+
+    /// Lines of context to show around each match
+    #[arg(short = 'C', long, default_value_t = docbert_core::grep::DEFAULT_CONTEXT_LINES)]
+    pub context: usize,
+
+    /// Stop after this many matching lines
+    #[arg(short = 'm', long, default_value_t = docbert_core::grep::DEFAULT_MAX_MATCHES)]
+    pub max_count: usize,
+
+    /// Skip unchanged indexed files the index rules out (literal patterns)
+    #[arg(long)]
+    pub prefilter: bool,
+
+    /// Print only the `collection:path` of files with matches
+    #[arg(short = 'l', long)]
+    pub files_with_matches: bool,
+
+    /// Output matches as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
 // -- Rebuild --
 
 #[derive(Debug, Parser)]
@@ -267,6 +671,43 @@ pub struct RebuildArgs {
     pub index_only: bool,
 }
 
+// -- Reindex --
+
+#[derive(Debug, Parser)]
+pub struct ReindexArgs {
+    /// Train this many coarse centroids instead of picking the count from
+    /// the corpus size; remembered for later rebuilds
+    #[arg(long, value_name = "N", value_parser = parse_positive)]
+    pub centroids: Option<usize>,
+
+    /// Residual bits per dimension; remembered for later rebuilds
+    #[arg(long, value_parser = parse_nbits)]
+    pub nbits: Option<u32>,
+
+    /// Upper bound on k-means iterations; remembered for later rebuilds
+    #[arg(long, value_name = "N", value_parser = parse_positive)]
+    pub kmeans_iters: Option<usize>,
+
+    /// Forget previously remembered build parameters before applying the
+    /// flags above
+    #[arg(long)]
+    pub reset_params: bool,
+}
+
+fn parse_positive(value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(format!("expected a positive integer, got `{value}`")),
+    }
+}
+
+fn parse_nbits(value: &str) -> Result<u32, String> {
+    match value.parse::<u32>() {
+        Ok(nbits @ (1 | 2 | 4 | 8)) => Ok(nbits),
+        _ => Err(format!("expected 1, 2, 4 or 8, got `{value}`")),
+    }
+}
+
 // -- Sync --
 
 #[derive(Debug, Parser)]
@@ -274,6 +715,21 @@ pub struct SyncArgs {
     /// Sync only this collection
     #[arg(short = 'c', long)]
     pub collection: Option<String>,
+
+    /// Retrain the PLAID index once updated chunks reconstruct this many
+    /// times worse than the corpus did at the last build
+    #[arg(long, value_name = "RATIO", default_value = "1.5")]
+    pub max_codec_drift: f32,
+
+    /// Retrain the PLAID index once the fullest centroid is this many
+    /// times more overloaded than at the last build
+    #[arg(long, value_name = "RATIO", default_value = "2.0")]
+    pub max_centroid_imbalance: f32,
+
+    /// Only recommend `docbert reindex` when drift crosses a threshold
+    /// instead of retraining during the sync
+    #[arg(long)]
+    pub no_retrain: bool,
 }
 
 // -- Status --
@@ -340,7 +796,8 @@ mod tests {
         let cli = Cli::parse_from(["docbert", "search", "query"]);
         match cli.command {
             Command::Search(args) => {
-                assert_eq!(args.query, "query");
+                assert_eq!(args.query.as_deref(), Some("query"));
+                assert!(args.queries_file.is_none());
                 assert_eq!(args.count, 10);
                 assert!(!args.json);
                 assert!(!args.all);
@@ -349,6 +806,10 @@ mod tests {
                 assert!(!args.bm25_only);
                 assert!(!args.no_fuzzy);
                 assert!(args.collection.is_none());
+                assert!(args.rerank_model.is_none());
+                assert!(args.passages.is_none());
+                assert_eq!(args.group, "document");
+                assert!(!args.explain);
             }
             _ => panic!("expected search command"),
         }
@@ -371,10 +832,13 @@ mod tests {
             "--no-fuzzy",
             "-c",
             "notes",
+            "--rerank-model",
+            "/models/reranker",
+            "--explain",
         ]);
         match cli.command {
             Command::Search(args) => {
-                assert_eq!(args.query, "q");
+                assert_eq!(args.query.as_deref(), Some("q"));
                 assert_eq!(args.count, 5);
                 assert!(args.json);
                 assert!(args.all);
@@ -383,9 +847,296 @@ mod tests {
                 assert!(args.bm25_only);
                 assert!(args.no_fuzzy);
                 assert_eq!(args.collection.as_deref(), Some("notes"));
+                assert_eq!(
+                    args.rerank_model.as_deref(),
+                    Some("/models/reranker")
+                );
+                assert!(args.explain);
+            }
+            _ => panic!("expected search command"),
+        }
+    }
+
+    #[test]
+    fn parse_search_passages_flags() {
+        let cli = Cli::parse_from([
+            "docbert",
+            "search",
+            "q",
+            "--passages",
+            "5",
+            "--group",
+            "passage",
+        ]);
+        match cli.command {
+            Command::Search(args) => {
+                assert_eq!(args.passages, Some(5));
+                assert_eq!(args.group, "passage");
+            }
+            _ => panic!("expected search command"),
+        }
+
+        let cli = Cli::parse_from(["docbert", "ssearch", "q", "--passages"]);
+        match cli.command {
+            Command::Ssearch(args) => assert_eq!(args.passages, Some(3)),
+            _ => panic!("expected ssearch command"),
+        }
+
+        assert!(
+            Cli::try_parse_from(["docbert", "search", "q", "--group", "chunk"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn parse_search_prf_flags() {
+        let cli = Cli::parse_from(["docbert", "search", "q"]);
+        match cli.command {
+            Command::Search(args) => {
+                assert!(!args.prf);
+                assert_eq!(args.prf_docs, 3);
+                assert_eq!(args.prf_terms, 10);
+                assert_eq!(args.prf_weight, 0.5);
+            }
+            _ => panic!("expected search command"),
+        }
+
+        let cli = Cli::parse_from([
+            "docbert",
+            "search",
+            "q",
+            "--prf",
+            "--prf-docs",
+            "5",
+            "--prf-terms",
+            "20",
+            "--prf-weight",
+            "0.3",
+        ]);
+        match cli.command {
+            Command::Search(args) => {
+                assert!(args.prf);
+                assert_eq!(args.prf_docs, 5);
+                assert_eq!(args.prf_terms, 20);
+                assert_eq!(args.prf_weight, 0.3);
+            }
+            _ => panic!("expected search command"),
+        }
+
+        assert!(
+            Cli::try_parse_from(["docbert", "search", "q", "--prf-docs", "5"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn parse_search_offset_flag() {
+        let cli = Cli::parse_from(["docbert", "search", "q"]);
+        match cli.command {
+            Command::Search(args) => assert_eq!(args.offset, 0),
+            _ => panic!("expected search command"),
+        }
+
+        let cli = Cli::parse_from(["docbert", "search", "q", "--offset", "20"]);
+        match cli.command {
+            Command::Search(args) => assert_eq!(args.offset, 20),
+            _ => panic!("expected search command"),
+        }
+
+        let cli = Cli::parse_from(["docbert", "ssearch", "q", "--offset", "5"]);
+        match cli.command {
+            Command::Ssearch(args) => assert_eq!(args.offset, 5),
+            _ => panic!("expected ssearch command"),
+        }
+    }
+
+    #[test]
+    fn parse_search_queries_file_replaces_the_query() {
+        let cli = Cli::parse_from([
+            "docbert",
+            "search",
+            "--queries-file",
+            "queries.txt",
+            "--json",
+        ]);
+        match cli.command {
+            Command::Search(args) => {
+                assert!(args.query.is_none());
+                assert_eq!(
+                    args.queries_file,
+                    Some(PathBuf::from("queries.txt"))
+                );
+            }
+            _ => panic!("expected search command"),
+        }
+
+        assert!(Cli::try_parse_from(["docbert", "search"]).is_err());
+        assert!(
+            Cli::try_parse_from([
+                "docbert",
+                "search",
+                "q",
+                "--queries-file",
+                "queries.txt",
+            ])
+            .is_err()
+        );
+        assert!(
+            Cli::try_parse_from([
+                "docbert",
+                "search",
+                "--queries-file",
+                "queries.txt",
+                "--offset",
+                "10",
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn parse_search_autocorrect_flag() {
+        let cli = Cli::parse_from(["docbert", "search", "q"]);
+        match cli.command {
+            Command::Search(args) => assert!(!args.autocorrect),
+            _ => panic!("expected search command"),
+        }
+
+        let cli = Cli::parse_from(["docbert", "search", "q", "--autocorrect"]);
+        match cli.command {
+            Command::Search(args) => assert!(args.autocorrect),
+            _ => panic!("expected search command"),
+        }
+    }
+
+    #[test]
+    fn parse_search_in_document() {
+        let cli = Cli::parse_from([
+            "docbert",
+            "search",
+            "cache invalidation",
+            "--in",
+            "specs:http.pdf",
+            "-n",
+            "3",
+        ]);
+        match cli.command {
+            Command::Search(args) => {
+                assert_eq!(args.within.as_deref(), Some("specs:http.pdf"));
+                assert_eq!(args.query.as_deref(), Some("cache invalidation"));
+                assert_eq!(args.count, 3);
+            }
+            _ => panic!("expected search command"),
+        }
+    }
+
+    #[test]
+    fn parse_search_in_document_rejects_corpus_flags() {
+        for flag in [["-c", "notes"], ["--offset", "5"]] {
+            let mut argv = vec!["docbert", "search", "q", "--in", "a.md"];
+            argv.extend(flag);
+            assert!(Cli::try_parse_from(argv).is_err());
+        }
+        assert!(
+            Cli::try_parse_from([
+                "docbert",
+                "search",
+                "q",
+                "--in",
+                "a.md",
+                "--bm25-only",
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn parse_eval_defaults_to_hybrid_at_ten() {
+        let cli = Cli::parse_from([
+            "docbert",
+            "eval",
+            "--queries",
+            "q.jsonl",
+            "--qrels",
+            "qrels.tsv",
+        ]);
+        match cli.command {
+            Command::Eval(args) => {
+                assert_eq!(args.queries, PathBuf::from("q.jsonl"));
+                assert_eq!(args.qrels, PathBuf::from("qrels.tsv"));
+                assert_eq!(args.mode, "hybrid");
+                assert_eq!(args.k, 10);
+                assert!(!args.json);
+                assert!(!args.plaid_recall);
+            }
+            _ => panic!("expected eval command"),
+        }
+    }
+
+    #[test]
+    fn parse_eval_mode_and_cutoff() {
+        let cli = Cli::parse_from([
+            "docbert",
+            "eval",
+            "--queries",
+            "q.jsonl",
+            "--qrels",
+            "qrels.tsv",
+            "--mode",
+            "semantic",
+            "-k",
+            "5",
+            "--json",
+            "--plaid-recall",
+        ]);
+        match cli.command {
+            Command::Eval(args) => {
+                assert_eq!(args.mode, "semantic");
+                assert_eq!(args.k, 5);
+                assert!(args.json);
+                assert!(args.plaid_recall);
             }
+            _ => panic!("expected eval command"),
+        }
+
+        assert!(
+            Cli::try_parse_from([
+                "docbert",
+                "eval",
+                "--queries",
+                "q.jsonl",
+                "--qrels",
+                "r.tsv",
+                "--mode",
+                "fuzzy",
+            ])
+            .is_err()
+        );
+        assert!(
+            Cli::try_parse_from(["docbert", "eval", "--queries", "q.jsonl"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn parse_search_facets_flag() {
+        let cli = Cli::parse_from(["docbert", "search", "q"]);
+        match cli.command {
+            Command::Search(args) => assert!(!args.facets),
+            _ => panic!("expected search command"),
+        }
+
+        let cli = Cli::parse_from(["docbert", "search", "q", "--facets"]);
+        match cli.command {
+            Command::Search(args) => assert!(args.facets),
             _ => panic!("expected search command"),
         }
+
+        let cli = Cli::parse_from(["docbert", "ssearch", "q", "--facets"]);
+        match cli.command {
+            Command::Ssearch(args) => assert!(args.facets),
+            _ => panic!("expected ssearch command"),
+        }
     }
 
     #[test]
@@ -431,6 +1182,44 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_similar_flags() {
+        let cli = Cli::parse_from(["docbert", "similar", "#abc123"]);
+        match cli.command {
+            Command::Similar(args) => {
+                assert_eq!(args.reference, "#abc123");
+                assert_eq!(args.count, 10);
+                assert!(args.collection.is_none());
+                assert!(!args.json);
+                assert!(!args.files);
+                assert!(args.passages.is_none());
+                assert_eq!(args.group, "document");
+            }
+            _ => panic!("expected similar command"),
+        }
+
+        let cli = Cli::parse_from([
+            "docbert",
+            "similar",
+            "notes:rust.md",
+            "-n",
+            "3",
+            "-c",
+            "notes",
+            "--json",
+            "--passages",
+        ]);
+        match cli.command {
+            Command::Similar(args) => {
+                assert_eq!(args.count, 3);
+                assert_eq!(args.collection.as_deref(), Some("notes"));
+                assert!(args.json);
+                assert_eq!(args.passages, Some(3));
+            }
+            _ => panic!("expected similar command"),
+        }
+    }
+
     #[test]
     fn parse_get_defaults() {
         let cli = Cli::parse_from(["docbert", "get", "notes:file.md"]);
@@ -500,6 +1289,88 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_complete_defaults() {
+        let cli = Cli::parse_from(["docbert", "complete", "rust own"]);
+        match cli.command {
+            Command::Complete(args) => {
+                assert_eq!(args.prefix, "rust own");
+                assert_eq!(args.count, 10);
+                assert!(args.collection.is_none());
+                assert!(!args.json);
+            }
+            _ => panic!("expected complete command"),
+        }
+    }
+
+    #[test]
+    fn parse_complete_with_flags() {
+        let cli = Cli::parse_from([
+            "docbert", "complete", "dep", "-n", "5", "-c", "notes", "--json",
+        ]);
+        match cli.command {
+            Command::Complete(args) => {
+                assert_eq!(args.prefix, "dep");
+                assert_eq!(args.count, 5);
+                assert_eq!(args.collection.as_deref(), Some("notes"));
+                assert!(args.json);
+            }
+            _ => panic!("expected complete command"),
+        }
+    }
+
+    #[test]
+    fn parse_grep_defaults() {
+        let cli = Cli::parse_from(["docbert", "grep", "TODO|FIXME"]);
+        match cli.command {
+            Command::Grep(args) => {
+                assert_eq!(args.pattern, "TODO|FIXME");
+                assert!(!args.fixed_strings);
+                assert!(!args.ignore_case);
+                assert!(args.collection.is_none());
+                assert_eq!(args.context, 2);
+                assert_eq!(args.max_count, 200);
+                assert!(!args.prefilter);
+                assert!(!args.files_with_matches);
+                assert!(!args.json);
+            }
+            _ => panic!("expected grep command"),
+        }
+    }
+
+    #[test]
+    fn parse_grep_with_flags() {
+        let cli = Cli::parse_from([
+            "docbert",
+            "grep",
+            "a.b",
+            "-F",
+            "-i",
+            "-c",
+            "notes",
+            "-C",
+            "0",
+            "-m",
+            "5",
+            "--prefilter",
+            "-l",
+            "--json",
+        ]);
+        match cli.command {
+            Command::Grep(args) => {
+                assert!(args.fixed_strings);
+                assert!(args.ignore_case);
+                assert_eq!(args.collection.as_deref(), Some("notes"));
+                assert_eq!(args.context, 0);
+                assert_eq!(args.max_count, 5);
+                assert!(args.prefilter);
+                assert!(args.files_with_matches);
+                assert!(args.json);
+            }
+            _ => panic!("expected grep command"),
+        }
+    }
+
     #[test]
     fn parse_collection_add() {
         let cli = Cli::parse_from([
@@ -581,6 +1452,60 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_saved_add_with_options() {
+        let cli = Cli::parse_from([
+            "docbert",
+            "saved",
+            "add",
+            "plaid",
+            "plaid compression",
+            "-c",
+            "notes",
+            "-n",
+            "5",
+            "--mode",
+            "bm25",
+        ]);
+        match cli.command {
+            Command::Saved {
+                action: SavedAction::Add(args),
+            } => {
+                assert_eq!(args.name, "plaid");
+                assert_eq!(args.query, "plaid compression");
+                assert_eq!(args.collection.as_deref(), Some("notes"));
+                assert_eq!(args.count, 5);
+                assert_eq!(args.mode, "bm25");
+            }
+            _ => panic!("expected saved add command"),
+        }
+    }
+
+    #[test]
+    fn parse_saved_new_defaults_to_all_searches() {
+        let cli = Cli::parse_from(["docbert", "saved", "new", "--clear"]);
+        match cli.command {
+            Command::Saved {
+                action: SavedAction::New { name, clear, json },
+            } => {
+                assert!(name.is_none());
+                assert!(clear);
+                assert!(!json);
+            }
+            _ => panic!("expected saved new command"),
+        }
+    }
+
+    #[test]
+    fn parse_saved_add_rejects_unknown_mode() {
+        assert!(
+            Cli::try_parse_from([
+                "docbert", "saved", "add", "n", "q", "--mode", "fuzzy",
+            ])
+            .is_err()
+        );
+    }
+
     #[test]
     fn parse_context_list() {
         let cli = Cli::parse_from(["docbert", "context", "list", "--json"]);
@@ -629,7 +1554,151 @@ mod tests {
     #[test]
     fn parse_reindex_command() {
         let cli = Cli::parse_from(["docbert", "reindex"]);
-        assert!(matches!(cli.command, Command::Reindex));
+        match cli.command {
+            Command::Reindex(args) => {
+                assert!(args.centroids.is_none());
+                assert!(args.nbits.is_none());
+                assert!(args.kmeans_iters.is_none());
+                assert!(!args.reset_params);
+            }
+            _ => panic!("expected reindex command"),
+        }
+    }
+
+    #[test]
+    fn parse_reindex_build_params() {
+        let cli = Cli::parse_from([
+            "docbert",
+            "reindex",
+            "--centroids",
+            "4096",
+            "--nbits",
+            "4",
+            "--kmeans-iters",
+            "10",
+            "--reset-params",
+        ]);
+        match cli.command {
+            Command::Reindex(args) => {
+                assert_eq!(args.centroids, Some(4096));
+                assert_eq!(args.nbits, Some(4));
+                assert_eq!(args.kmeans_iters, Some(10));
+                assert!(args.reset_params);
+            }
+            _ => panic!("expected reindex command"),
+        }
+    }
+
+    #[test]
+    fn parse_search_plaid_params() {
+        let cli = Cli::parse_from([
+            "docbert",
+            "search",
+            "ownership",
+            "--n-probe",
+            "4",
+            "--n-candidate-docs",
+            "2048",
+            "--centroid-score-threshold",
+            "0.3",
+        ]);
+        match cli.command {
+            Command::Search(args) => {
+                let params = args.plaid.params();
+                assert_eq!(params.n_probe, Some(4));
+                assert_eq!(params.n_candidate_docs, Some(2048));
+                assert_eq!(params.centroid_score_threshold, Some(0.3));
+                assert!(!params.exhaustive);
+            }
+            _ => panic!("expected search command"),
+        }
+
+        let cli = Cli::parse_from(["docbert", "ssearch", "q", "--exhaustive"]);
+        match cli.command {
+            Command::Ssearch(args) => assert!(args.plaid.exhaustive),
+            _ => panic!("expected ssearch command"),
+        }
+    }
+
+    #[test]
+    fn parse_exhaustive_conflicts_with_pruning_params() {
+        assert!(
+            Cli::try_parse_from([
+                "docbert",
+                "search",
+                "q",
+                "--exhaustive",
+                "--n-probe",
+                "2",
+            ])
+            .is_err()
+        );
+        assert!(
+            Cli::try_parse_from(["docbert", "search", "q", "--n-probe", "0"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn parse_exact_and_no_exact() {
+        let cli = Cli::parse_from(["docbert", "ssearch", "q", "--exact"]);
+        match cli.command {
+            Command::Ssearch(args) => {
+                assert_eq!(args.plaid.params().exact, Some(true));
+            }
+            _ => panic!("expected ssearch command"),
+        }
+        let cli = Cli::parse_from(["docbert", "search", "q", "--no-exact"]);
+        match cli.command {
+            Command::Search(args) => {
+                assert_eq!(args.plaid.params().exact, Some(false));
+            }
+            _ => panic!("expected search command"),
+        }
+        for conflicting in ["--no-exact", "--exhaustive"] {
+            assert!(
+                Cli::try_parse_from([
+                    "docbert",
+                    "search",
+                    "q",
+                    "--exact",
+                    conflicting,
+                ])
+                .is_err()
+            );
+        }
+    }
+
+    #[test]
+    fn parse_plaid_set_and_clear() {
+        let cli =
+            Cli::parse_from(["docbert", "plaid", "set", "--n-probe", "8"]);
+        match cli.command {
+            Command::Plaid {
+                action: PlaidAction::Set(args),
+            } => assert_eq!(args.n_probe, Some(8)),
+            _ => panic!("expected plaid set command"),
+        }
+
+        let cli = Cli::parse_from(["docbert", "plaid", "clear"]);
+        assert!(matches!(
+            cli.command,
+            Command::Plaid {
+                action: PlaidAction::Clear
+            }
+        ));
+    }
+
+    #[test]
+    fn parse_reindex_rejects_unsupported_nbits_and_zero_centroids() {
+        assert!(
+            Cli::try_parse_from(["docbert", "reindex", "--nbits", "3"])
+                .is_err()
+        );
+        assert!(
+            Cli::try_parse_from(["docbert", "reindex", "--centroids", "0"])
+                .is_err()
+        );
     }
 
     #[test]
@@ -638,6 +1707,9 @@ mod tests {
         match cli.command {
             Command::Sync(args) => {
                 assert!(args.collection.is_none());
+                assert_eq!(args.max_codec_drift, 1.5);
+                assert_eq!(args.max_centroid_imbalance, 2.0);
+                assert!(!args.no_retrain);
             }
             _ => panic!("expected sync command"),
         }
@@ -654,6 +1726,27 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_sync_drift_thresholds() {
+        let cli = Cli::parse_from([
+            "docbert",
+            "sync",
+            "--max-codec-drift",
+            "3",
+            "--max-centroid-imbalance",
+            "4.5",
+            "--no-retrain",
+        ]);
+        match cli.command {
+            Command::Sync(args) => {
+                assert_eq!(args.max_codec_drift, 3.0);
+                assert_eq!(args.max_centroid_imbalance, 4.5);
+                assert!(args.no_retrain);
+            }
+            _ => panic!("expected sync command"),
+        }
+    }
+
     #[test]
     fn parse_status_defaults() {
         let cli = Cli::parse_from(["docbert", "status"]);
@@ -735,6 +1828,35 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_model_set_reranker() {
+        let cli = Cli::parse_from([
+            "docbert",
+            "model",
+            "set-reranker",
+            "/models/reranker",
+        ]);
+        match cli.command {
+            Command::Model {
+                action: ModelAction::SetReranker { path },
+            } => {
+                assert_eq!(path, "/models/reranker");
+            }
+            _ => panic!("expected model set-reranker command"),
+        }
+    }
+
+    #[test]
+    fn parse_model_clear_reranker() {
+        let cli = Cli::parse_from(["docbert", "model", "clear-reranker"]);
+        match cli.command {
+            Command::Model {
+                action: ModelAction::ClearReranker,
+            } => {}
+            _ => panic!("expected model clear-reranker command"),
+        }
+    }
+
     #[test]
     fn parse_global_flags() {
         let cli = Cli::parse_from([
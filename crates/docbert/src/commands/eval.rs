@@ -0,0 +1,219 @@
+use docbert_core::{
+    ConfigDb,
+    DataDir,
+    ModelManager,
+    SearchIndex,
+    error,
+    eval::{self, EvalReport},
+    model_manager::{ModelResolution, resolve_reranker_model},
+    plaid,
+    search::{self, SearchMode},
+};
+
+use super::{
+    json_output::{EvalRunConfig, eval_json_string},
+    model::log_model_runtime,
+    plaid::describe,
+    style,
+};
+use crate::cli;
+
+pub(crate) fn run(
+    config_db: &ConfigDb,
+    data_dir: &DataDir,
+    model_resolution: &ModelResolution,
+    args: &cli::EvalArgs,
+) -> error::Result<()> {
+    let queries =
+        eval::parse_queries(&std::fs::read_to_string(&args.queries)?)?;
+    let qrels = eval::parse_qrels(&std::fs::read_to_string(&args.qrels)?)?;
+    if queries.is_empty() {
+        return Err(error::Error::Config(format!(
+            "no queries in {}",
+            args.queries.display()
+        )));
+    }
+
+    // `--mode` is restricted to valid values by clap.
+    let mode = SearchMode::parse(&args.mode).unwrap_or(SearchMode::Hybrid);
+    let reranker =
+        resolve_reranker_model(config_db, args.rerank_model.as_deref())?;
+    let search_index = SearchIndex::open(&data_dir.tantivy_dir()?)?;
+    let mut model =
+        ModelManager::with_model_id(model_resolution.model_id.clone())
+            .with_reranker_model(reranker.clone());
+    if mode != SearchMode::Bm25 {
+        log_model_runtime(&mut model)?;
+    }
+
+    let plaid = (mode != SearchMode::Bm25)
+        .then(|| {
+            plaid::load_query_params(config_db)
+                .map(|stored| stored.with(args.plaid.params()))
+        })
+        .transpose()?;
+
+    let mut report = eval::evaluate(&queries, &qrels, args.k, |query| {
+        let mut results = search_once(
+            mode,
+            &query.query,
+            args,
+            &search_index,
+            config_db,
+            data_dir,
+            &mut model,
+        )?;
+        search::disambiguate_doc_ids(&mut results, config_db);
+        Ok(results)
+    })?;
+    if args.plaid_recall {
+        // Compare the semantic rankings themselves; a reranker would
+        // reorder both and blur what PLAID lost.
+        let mut model = model.with_reranker_model(None);
+        if mode == SearchMode::Bm25 {
+            log_model_runtime(&mut model)?;
+        }
+        let loss = eval::recall_loss(&queries, args.k, |query, exact| {
+            let plaid = plaid::PlaidQueryParams {
+                exact: Some(exact),
+                ..args.plaid.params()
+            };
+            search::semantic(
+                &semantic_params(&query.query, args, plaid),
+                config_db,
+                data_dir,
+                &mut model,
+            )
+        })?;
+        report.plaid_recall_loss = Some(loss);
+    }
+
+    let config = EvalRunConfig {
+        mode: mode.as_str(),
+        k: args.k,
+        model: &model_resolution.model_id,
+        reranker: reranker.as_deref(),
+        collection: args.collection.as_deref(),
+        no_fuzzy: args.no_fuzzy,
+        prf: args.prf,
+        plaid,
+    };
+    if args.json {
+        println!("{}", eval_json_string(&config, &report)?);
+    } else {
+        print_report(&config, &report);
+    }
+    Ok(())
+}
+
+/// Run one evaluation query, asking for exactly the `k` results the
+/// metrics look at.
+fn search_once(
+    mode: SearchMode,
+    query: &str,
+    args: &cli::EvalArgs,
+    search_index: &SearchIndex,
+    config_db: &ConfigDb,
+    data_dir: &DataDir,
+    model: &mut ModelManager,
+) -> error::Result<Vec<search::FinalResult>> {
+    if mode == SearchMode::Semantic {
+        return search::semantic(
+            &semantic_params(query, args, args.plaid.params()),
+            config_db,
+            data_dir,
+            model,
+        );
+    }
+    search::run(
+        &search::SearchParams {
+            query: query.to_string(),
+            count: args.k,
+            collection: args.collection.clone(),
+            min_score: 0.0,
+            bm25_only: mode == SearchMode::Bm25,
+            no_fuzzy: args.no_fuzzy,
+            all: false,
+            passages: 0,
+            explain: false,
+            prf: args.prf.then(search::PrfParams::default),
+            facets: false,
+            offset: 0,
+            cursor: None,
+            autocorrect: false,
+            plaid: args.plaid.params(),
+        },
+        search_index,
+        config_db,
+        data_dir,
+        model,
+    )
+}
+
+/// A semantic search for the `k` results the metrics look at, run with
+/// the `plaid` parameters.
+fn semantic_params(
+    query: &str,
+    args: &cli::EvalArgs,
+    plaid: plaid::PlaidQueryParams,
+) -> search::SemanticSearchParams {
+    search::SemanticSearchParams {
+        query: query.to_string(),
+        collection: args.collection.clone(),
+        count: args.k,
+        min_score: 0.0,
+        all: false,
+        passages: 0,
+        explain: false,
+        facets: false,
+        offset: 0,
+        cursor: None,
+        plaid,
+    }
+}
+
+fn print_report(config: &EvalRunConfig<'_>, report: &EvalReport) {
+    let k = report.k;
+    println!(
+        "{} {} mode, model {}",
+        style::header(&"Evaluated"),
+        config.mode,
+        config.model
+    );
+    if let Some(reranker) = config.reranker {
+        println!("Reranker: {reranker}");
+    }
+    if let Some(params) = config.plaid.filter(|params| !params.is_empty()) {
+        println!("PLAID search: {}", describe(&params));
+    }
+    println!(
+        "Queries: {} evaluated, {} skipped without relevant judgments",
+        report.evaluated,
+        report.skipped.len()
+    );
+    println!();
+    for (metric, value) in [
+        (format!("nDCG@{k}"), report.ndcg),
+        (format!("MRR@{k}"), report.mrr),
+        (format!("Recall@{k}"), report.recall),
+    ] {
+        println!("{metric:<12} {value:.4}");
+    }
+    println!();
+    let latency = &report.latency;
+    println!(
+        "Latency (ms): mean {:.1}, p50 {:.1}, p90 {:.1}, p95 {:.1}, p99 {:.1}, max {:.1}",
+        latency.mean_ms,
+        latency.p50_ms,
+        latency.p90_ms,
+        latency.p95_ms,
+        latency.p99_ms,
+        latency.max_ms
+    );
+    if let Some(loss) = &report.plaid_recall_loss {
+        println!(
+            "PLAID recall loss@{k}: mean {:.4}, max {:.4} over {} queries",
+            loss.mean, loss.max, loss.queries
+        );
+    }
+}
@@ -0,0 +1,54 @@
+use docbert_core::{ConfigDb, DataDir, SearchIndex, error, grep};
+
+use super::json_output::grep_json_string;
+use crate::cli;
+
+pub(crate) fn run(
+    config_db: &ConfigDb,
+    data_dir: &DataDir,
+    args: &cli::GrepArgs,
+) -> error::Result<()> {
+    let search_index = SearchIndex::open(&data_dir.tantivy_dir()?)?;
+    let params = grep::GrepParams {
+        pattern: args.pattern.clone(),
+        fixed_strings: args.fixed_strings,
+        ignore_case: args.ignore_case,
+        collection: args.collection.clone(),
+        context: args.context,
+        max_matches: args.max_count,
+        prefilter: args.prefilter,
+    };
+    let outcome = grep::run(&params, config_db, &search_index)?;
+
+    if args.json {
+        println!("{}", grep_json_string(&args.pattern, &outcome)?);
+        return Ok(());
+    }
+    if args.files_with_matches {
+        let mut last = None;
+        for found in &outcome.matches {
+            let reference = format!("{}:{}", found.collection, found.path);
+            if last.as_ref() != Some(&reference) {
+                println!("{reference}");
+                last = Some(reference);
+            }
+        }
+        return Ok(());
+    }
+    if outcome.matches.is_empty() {
+        println!("No matches for '{}'", args.pattern);
+        return Ok(());
+    }
+
+    for line in grep::format_lines(&outcome.matches) {
+        println!("{line}");
+    }
+    println!("\n{} match(es)", outcome.matches.len());
+    if outcome.truncated {
+        println!(
+            "Stopped after {} matches; raise --max-count to see more.",
+            args.max_count
+        );
+    }
+    Ok(())
+}
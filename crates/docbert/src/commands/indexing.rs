@@ -1,4 +1,4 @@
-use std::time::Instant;
+use std::{thread::JoinHandle, time::Instant};
 
 use docbert_core::{
     ConfigDb,
@@ -11,6 +11,8 @@ use docbert_core::{
     error,
     incremental,
     ingestion,
+    plaid::PlaidBuildOverrides,
+    plaid_drift::{self, DriftThresholds, PlaidDrift},
     walker,
 };
 use kdam::{BarExt, Spinner, tqdm};
@@ -230,21 +232,6 @@ fn process_document_batch(
     Ok(())
 }
 
-/// Rebuild the PLAID semantic index from whatever is currently in
-/// `embedding_db` and persist it under `data_dir`.
-///
-/// Called at the end of `sync` and `rebuild` once the embedding
-/// database is in its post-sync state. When the embedding db is empty
-/// (no collections have been indexed yet, or every doc was deleted), we
-/// skip with a short message — there's nothing to train centroids on.
-///
-/// Centroid count is adapted to the corpus size: we want enough
-/// centroids for good recall but cannot ask for more centroids than we
-/// have training tokens. The ceiling of 256 matches
-/// `PlaidBuildParams::default()` and covers small-to-medium personal
-/// corpora; the ⌈√n⌉ heuristic keeps tiny corpora (e.g. a fresh user
-/// with one collection) from tripping the k > tokens guard inside
-/// `build_index_from_embedding_db`.
 /// Incrementally sync the PLAID index after `docbert sync` has written
 /// the new/changed embeddings. Falls back to [`rebuild_plaid_index`]
 /// when no prior index exists on disk.
@@ -257,19 +244,33 @@ fn process_document_batch(
 /// This replaces the full rebuild-every-sync behaviour: for a corpus
 /// of a few million tokens, retraining k-means + quantizer + encoding
 /// every token dominates sync time even when only a handful of files
-/// changed. With this path, cost scales with the size of the delta.
+/// changed. With this path, cost scales with the size of the delta:
+/// the update lands in one new segment, which is all that gets written.
+///
+/// Because the codec is never retrained here, every update also
+/// measures codec drift (see [`plaid_drift`]). Once it crosses
+/// `drift_policy`'s thresholds the index is rebuilt from scratch, or
+/// `docbert reindex` is recommended when retraining is turned off.
+///
+/// Returns the background merge started after saving, if any, so the
+/// caller can report it with [`finish_plaid_merge`].
 fn sync_plaid_index(
     data_dir: &DataDir,
     embedding_db: &EmbeddingDb,
     config_db: &ConfigDb,
     touched_bases: &[u64],
     deleted_bases: &[u64],
-) -> error::Result<()> {
+    drift_policy: DriftPolicy,
+) -> error::Result<Option<PlaidMerge>> {
+    let lock = docbert_core::plaid::lock_index(data_dir)?;
     let Some(existing) = docbert_core::plaid::load_index(data_dir)? else {
         // First-time sync — we have no codec to reuse. Fall back to
         // the full build path, which also handles the empty-db case.
-        return rebuild_plaid_index(data_dir, embedding_db);
+        drop(lock);
+        rebuild_plaid_index(data_dir, embedding_db, config_db)?;
+        return Ok(None);
     };
+    let previous_drift = plaid_drift::load(config_db, &existing)?;
 
     // Expand each touched document into its current chunk_doc_ids by
     // reading the manifest. Deduplicate across documents — content-based
@@ -291,7 +292,7 @@ fn sync_plaid_index(
     // unknown ids fall through harmlessly.
     let mut deletion_candidates: std::collections::HashSet<u64> =
         std::collections::HashSet::new();
-    for &chunk_doc_id in &existing.doc_ids {
+    for chunk_doc_id in existing.live_doc_ids() {
         if upsert_chunks.contains(&chunk_doc_id) {
             continue;
         }
@@ -315,56 +316,160 @@ fn sync_plaid_index(
         &deletions,
     )?;
     docbert_core::plaid::save_index(&updated, data_dir)?;
+    drop(lock);
     eprintln!(
-        "  Indexed {} documents in {}.",
+        "  Indexed {} documents in {} ({} segment(s)).",
         updated.num_documents(),
         style::accent(&style::format_duration(start.elapsed())),
+        updated.segments().len(),
     );
-    Ok(())
+
+    let drift = plaid_drift::measure_after_update(
+        embedding_db,
+        &updated,
+        previous_drift,
+        &upserts,
+    )?;
+    plaid_drift::save(config_db, &drift)?;
+    if drift.exceeds(drift_policy.thresholds) {
+        if drift_policy.retrain {
+            eprintln!(
+                "  PLAID codec has drifted ({}); retraining.",
+                describe_drift(&drift),
+            );
+            drop(updated);
+            rebuild_plaid_index(data_dir, embedding_db, config_db)?;
+            return Ok(None);
+        }
+        eprintln!(
+            "{} the PLAID codec has drifted ({}); run `docbert reindex` \
+             to retrain it.",
+            style::warn(&"Warning:"),
+            describe_drift(&drift),
+        );
+    }
+
+    let policy = docbert_core::plaid::PlaidMergePolicy::default();
+    Ok(updated.plan_merge(policy).is_some().then(|| {
+        docbert_core::plaid::spawn_background_merge(data_dir.clone(), policy)
+    }))
+}
+
+/// When [`sync_plaid_index`] retrains a drifted PLAID codec.
+#[derive(Debug, Clone, Copy)]
+struct DriftPolicy {
+    thresholds: DriftThresholds,
+    /// Rebuild during the sync rather than only recommending it.
+    retrain: bool,
+}
+
+impl Default for DriftPolicy {
+    fn default() -> Self {
+        Self {
+            thresholds: DriftThresholds::default(),
+            retrain: true,
+        }
+    }
+}
+
+impl From<&cli::SyncArgs> for DriftPolicy {
+    fn from(args: &cli::SyncArgs) -> Self {
+        Self {
+            thresholds: DriftThresholds {
+                max_error_ratio: args.max_codec_drift,
+                max_imbalance_ratio: args.max_centroid_imbalance,
+            },
+            retrain: !args.no_retrain,
+        }
+    }
+}
+
+fn describe_drift(drift: &PlaidDrift) -> String {
+    let error = drift
+        .error_ratio()
+        .map_or("n/a".to_string(), |ratio| format!("{ratio:.2}x"));
+    format!(
+        "reconstruction error {error}, centroid imbalance {:.2}x",
+        drift.imbalance_ratio(),
+    )
+}
+
+/// A PLAID segment merge running on a background thread.
+type PlaidMerge = JoinHandle<error::Result<bool>>;
+
+/// Wait for a background merge started by [`sync_plaid_index`] and
+/// report how it went.
+///
+/// A failed merge only leaves more segments than necessary, so it is
+/// reported as a warning; the next sync tries again.
+fn finish_plaid_merge(merge: Option<PlaidMerge>) {
+    let Some(merge) = merge else {
+        return;
+    };
+    let start = Instant::now();
+    match merge.join() {
+        Ok(Ok(true)) => eprintln!(
+            "  Compacted PLAID index segments (waited {}).",
+            style::accent(&style::format_duration(start.elapsed())),
+        ),
+        Ok(Ok(false)) => {}
+        Ok(Err(err)) => eprintln!(
+            "{} could not compact the PLAID index: {err}",
+            style::warn(&"Warning:"),
+        ),
+        Err(_) => eprintln!(
+            "{} the PLAID index merge panicked",
+            style::warn(&"Warning:"),
+        ),
+    }
 }
 
+/// Rebuild the PLAID semantic index from whatever is currently in
+/// `embedding_db`, persist it under `data_dir` and take its codec drift
+/// baseline.
+///
+/// Called at the end of `sync` and `rebuild` once the embedding
+/// database is in its post-sync state, and by `reindex`. When the
+/// embedding db is empty (no collections have been indexed yet, or
+/// every doc was deleted), we skip with a short message — there's
+/// nothing to train centroids on.
+///
+/// Build parameters pinned with `docbert reindex` are read from
+/// `config_db`; the rest are adapted to the corpus size by
+/// [`PlaidBuildOverrides::resolve`], which never asks for more
+/// centroids than there are training tokens.
 fn rebuild_plaid_index(
     data_dir: &DataDir,
     embedding_db: &EmbeddingDb,
+    config_db: &ConfigDb,
 ) -> error::Result<()> {
-    // `list_shapes` reads only the 8-byte header per entry and is
-    // enough to pick `k_centroids`; the heavy loading is deferred to
-    // `build_index_from_embedding_db`, which streams matrices into a
-    // pre-sized pool.
-    let shapes = embedding_db.list_shapes()?;
-    if shapes.is_empty() {
-        eprintln!("No embeddings yet; skipping PLAID index rebuild.");
-        return Ok(());
-    }
-    let total_tokens: usize = shapes.iter().map(|&(_, n, _)| n as usize).sum();
+    // The token count comes from the 8-byte entry headers alone; the
+    // heavy loading is deferred to `build_index_from_embedding_db`,
+    // which streams matrices into a pre-sized pool.
+    let total_tokens = docbert_core::plaid::corpus_token_count(embedding_db)?;
     if total_tokens == 0 {
-        eprintln!(
-            "No tokens in the embedding database; skipping PLAID index rebuild."
-        );
+        eprintln!("No embeddings yet; skipping PLAID index rebuild.");
         return Ok(());
     }
-
-    let default_params = docbert_core::plaid::PlaidBuildParams::default();
-    let sqrt_tokens = (total_tokens as f32).sqrt().ceil() as usize;
-    let k_centroids = default_params
-        .k_centroids
-        .min(total_tokens)
-        .min(sqrt_tokens.max(1));
-    let params = docbert_core::plaid::PlaidBuildParams {
-        k_centroids,
-        ..default_params
-    };
+    let params = docbert_core::plaid::load_build_overrides(config_db)?
+        .resolve(total_tokens);
 
     let start = Instant::now();
     eprintln!(
-        "{} ({total_tokens} tokens, {k_centroids} centroids)...",
+        "{} ({total_tokens} tokens, {} centroids, {}-bit residuals)...",
         style::subheader(&"Rebuilding PLAID semantic index"),
+        params.k_centroids,
+        params.nbits,
     );
     let index = docbert_core::plaid::build_index_from_embedding_db(
         embedding_db,
         params,
     )?;
+    let lock = docbert_core::plaid::lock_index(data_dir)?;
     docbert_core::plaid::save_index(&index, data_dir)?;
+    drop(lock);
+    let drift = plaid_drift::measure_after_build(embedding_db, &index)?;
+    plaid_drift::save(config_db, &drift)?;
     eprintln!(
         "  Indexed {} documents in {}.",
         index.num_documents(),
@@ -462,7 +567,7 @@ pub(crate) fn rebuild(
     config_db.set_setting(EMBEDDING_MODEL_KEY, model_id)?;
 
     let embedding_db = release_encoder_before_plaid(runtime)?;
-    rebuild_plaid_index(data_dir, &embedding_db)?;
+    rebuild_plaid_index(data_dir, &embedding_db, config_db)?;
 
     eprintln!(
         "{} in {}.",
@@ -512,10 +617,25 @@ fn release_encoder_before_plaid(
 /// every document — a four-minute walk over the model for the current
 /// docbert corpus — before it even starts training centroids. This
 /// command skips straight to the train + encode step.
-pub(crate) fn reindex(data_dir: &DataDir) -> error::Result<()> {
+pub(crate) fn reindex(
+    config_db: &ConfigDb,
+    data_dir: &DataDir,
+    args: &cli::ReindexArgs,
+) -> error::Result<()> {
     let total_start = Instant::now();
+    let stored = if args.reset_params {
+        PlaidBuildOverrides::default()
+    } else {
+        docbert_core::plaid::load_build_overrides(config_db)?
+    };
+    let overrides = stored.with(PlaidBuildOverrides {
+        k_centroids: args.centroids,
+        nbits: args.nbits,
+        max_kmeans_iters: args.kmeans_iters,
+    });
+    docbert_core::plaid::save_build_overrides(config_db, &overrides)?;
     let embedding_db = EmbeddingDb::open(&data_dir.embeddings_db())?;
-    rebuild_plaid_index(data_dir, &embedding_db)?;
+    rebuild_plaid_index(data_dir, &embedding_db, config_db)?;
     eprintln!(
         "{} in {}.",
         style::header(&"Reindex complete"),
@@ -698,14 +818,20 @@ pub(crate) fn sync(
     config_db.set_setting(EMBEDDING_MODEL_KEY, model_id)?;
 
     let embedding_db = release_encoder_before_plaid(runtime)?;
-    sync_plaid_index(
+    let merge = sync_plaid_index(
         data_dir,
         &embedding_db,
         config_db,
         &touched_bases,
         &deleted_bases,
+        DriftPolicy::from(args),
     )?;
 
+    if !touched_bases.is_empty() || !deleted_bases.is_empty() {
+        super::saved::refresh_after_sync(config_db, data_dir, model_id);
+    }
+    finish_plaid_merge(merge);
+
     eprintln!(
         "{} in {}.",
         style::header(&"Sync complete"),
@@ -716,6 +842,7 @@ pub(crate) fn sync(
 
 #[cfg(test)]
 mod tests {
+    use clap::Parser;
     use docbert_core::{ConfigDb, DocumentId, incremental};
 
     use super::*;
@@ -989,11 +1116,12 @@ mod tests {
     fn rebuild_plaid_index_skips_when_embedding_db_is_empty() {
         let tmp = tempfile::tempdir().unwrap();
         let data_dir = DataDir::new(tmp.path());
+        let config_db = ConfigDb::open(&data_dir.config_db()).unwrap();
         let embedding_db =
             EmbeddingDb::open(&data_dir.embeddings_db()).unwrap();
 
         // Should return Ok and not write a PLAID index file.
-        rebuild_plaid_index(&data_dir, &embedding_db).unwrap();
+        rebuild_plaid_index(&data_dir, &embedding_db, &config_db).unwrap();
         assert!(
             !data_dir.plaid_index().exists(),
             "no PLAID file should be written for an empty db"
@@ -1030,13 +1158,20 @@ mod tests {
         seed_single_chunk_manifest(&config_db, 1);
         seed_single_chunk_manifest(&config_db, 2);
 
-        sync_plaid_index(&data_dir, &embedding_db, &config_db, &[], &[])
-            .unwrap();
+        sync_plaid_index(
+            &data_dir,
+            &embedding_db,
+            &config_db,
+            &[],
+            &[],
+            DriftPolicy::default(),
+        )
+        .unwrap();
 
         let loaded = docbert_core::plaid::load_index(&data_dir)
             .unwrap()
             .expect("sync must produce an index on the no-existing-index path");
-        let mut doc_ids = loaded.doc_ids.clone();
+        let mut doc_ids: Vec<u64> = loaded.live_doc_ids().collect();
         doc_ids.sort();
         assert_eq!(doc_ids, vec![1, 2]);
     }
@@ -1055,13 +1190,27 @@ mod tests {
         seed_single_chunk_manifest(&config_db, 1);
         seed_single_chunk_manifest(&config_db, 2);
 
-        sync_plaid_index(&data_dir, &embedding_db, &config_db, &[], &[])
-            .unwrap();
+        sync_plaid_index(
+            &data_dir,
+            &embedding_db,
+            &config_db,
+            &[],
+            &[],
+            DriftPolicy::default(),
+        )
+        .unwrap();
         let first =
             docbert_core::plaid::load_index(&data_dir).unwrap().unwrap();
 
-        sync_plaid_index(&data_dir, &embedding_db, &config_db, &[], &[])
-            .unwrap();
+        sync_plaid_index(
+            &data_dir,
+            &embedding_db,
+            &config_db,
+            &[],
+            &[],
+            DriftPolicy::default(),
+        )
+        .unwrap();
         let second =
             docbert_core::plaid::load_index(&data_dir).unwrap().unwrap();
 
@@ -1084,22 +1233,42 @@ mod tests {
         seed_single_chunk_manifest(&config_db, 1);
         seed_single_chunk_manifest(&config_db, 2);
 
-        sync_plaid_index(&data_dir, &embedding_db, &config_db, &[], &[])
-            .unwrap();
+        sync_plaid_index(
+            &data_dir,
+            &embedding_db,
+            &config_db,
+            &[],
+            &[],
+            DriftPolicy::default(),
+        )
+        .unwrap();
         let before =
             docbert_core::plaid::load_index(&data_dir).unwrap().unwrap();
-        let before_tokens_for_1 =
-            before.doc_tokens_vec(before.position_of(1).unwrap());
+        let (segment, position) = before.locate(1).unwrap();
+        let before_tokens_for_1 = segment.index().doc_tokens_vec(position);
         let before_codec = before.codec.clone();
 
+        // Moving every token of doc 1 into the other cluster is drift
+        // enough to retrain a two-document index; keep the codec so the
+        // incremental path is what gets tested.
         embedding_db.store(1, 2, 2, &[9.5, 9.5, 10.1, 9.9]).unwrap();
-        sync_plaid_index(&data_dir, &embedding_db, &config_db, &[1], &[])
-            .unwrap();
+        sync_plaid_index(
+            &data_dir,
+            &embedding_db,
+            &config_db,
+            &[1],
+            &[],
+            DriftPolicy {
+                retrain: false,
+                ..DriftPolicy::default()
+            },
+        )
+        .unwrap();
 
         let after =
             docbert_core::plaid::load_index(&data_dir).unwrap().unwrap();
-        let after_tokens_for_1 =
-            after.doc_tokens_vec(after.position_of(1).unwrap());
+        let (segment, position) = after.locate(1).unwrap();
+        let after_tokens_for_1 = segment.index().doc_tokens_vec(position);
 
         assert_eq!(after.codec.centroids, before_codec.centroids);
         assert_ne!(
@@ -1122,50 +1291,185 @@ mod tests {
         seed_single_chunk_manifest(&config_db, 1);
         seed_single_chunk_manifest(&config_db, 2);
 
-        sync_plaid_index(&data_dir, &embedding_db, &config_db, &[], &[])
-            .unwrap();
+        sync_plaid_index(
+            &data_dir,
+            &embedding_db,
+            &config_db,
+            &[],
+            &[],
+            DriftPolicy::default(),
+        )
+        .unwrap();
         assert!(
             docbert_core::plaid::load_index(&data_dir)
                 .unwrap()
                 .unwrap()
-                .doc_ids
-                .contains(&2),
+                .contains(2),
         );
 
         // Drop doc 2's manifest (chunk 2 now has no owners) and tell
         // the sync that doc 2 was deleted. The embedding row stays put,
         // but PLAID drops the orphan chunk so search won't surface it.
         config_db.remove_doc_chunks(2).unwrap();
-        sync_plaid_index(&data_dir, &embedding_db, &config_db, &[], &[2])
-            .unwrap();
+        sync_plaid_index(
+            &data_dir,
+            &embedding_db,
+            &config_db,
+            &[],
+            &[2],
+            DriftPolicy::default(),
+        )
+        .unwrap();
 
         let after =
             docbert_core::plaid::load_index(&data_dir).unwrap().unwrap();
-        assert!(!after.doc_ids.contains(&2));
-        assert!(after.doc_ids.contains(&1));
+        assert!(!after.contains(2));
+        assert!(after.contains(1));
+    }
+
+    #[test]
+    fn sync_plaid_index_retrains_once_the_codec_drifts_too_far() {
+        let tmp = tempfile::tempdir().unwrap();
+        let data_dir = DataDir::new(tmp.path());
+        let config_db = ConfigDb::open(&data_dir.config_db()).unwrap();
+        let embedding_db =
+            EmbeddingDb::open(&data_dir.embeddings_db()).unwrap();
+        embedding_db.store(1, 2, 2, &[0.0, 0.0, 0.1, 0.1]).unwrap();
+        embedding_db
+            .store(2, 2, 2, &[9.0, 9.0, 10.0, 10.0])
+            .unwrap();
+        seed_single_chunk_manifest(&config_db, 1);
+        seed_single_chunk_manifest(&config_db, 2);
+        sync_plaid_index(
+            &data_dir,
+            &embedding_db,
+            &config_db,
+            &[],
+            &[],
+            DriftPolicy::default(),
+        )
+        .unwrap();
+        let built =
+            docbert_core::plaid::load_index(&data_dir).unwrap().unwrap();
+        let baseline = plaid_drift::load(&config_db, &built).unwrap().unwrap();
+        assert!(baseline.error_ratio().is_none());
+
+        // Tokens far from both trained centroids reconstruct badly.
+        embedding_db
+            .store(1, 2, 2, &[-60.0, 80.0, 75.0, -50.0])
+            .unwrap();
+        let recommend_only = DriftPolicy {
+            retrain: false,
+            ..DriftPolicy::default()
+        };
+        sync_plaid_index(
+            &data_dir,
+            &embedding_db,
+            &config_db,
+            &[1],
+            &[],
+            recommend_only,
+        )
+        .unwrap();
+        let updated =
+            docbert_core::plaid::load_index(&data_dir).unwrap().unwrap();
+        assert_eq!(updated.codec_id(), built.codec_id());
+        let drift = plaid_drift::load(&config_db, &updated).unwrap().unwrap();
+        assert!(drift.exceeds(DriftThresholds::default()), "{drift:?}");
+
+        sync_plaid_index(
+            &data_dir,
+            &embedding_db,
+            &config_db,
+            &[1],
+            &[],
+            DriftPolicy::default(),
+        )
+        .unwrap();
+        let retrained =
+            docbert_core::plaid::load_index(&data_dir).unwrap().unwrap();
+        assert_ne!(retrained.codec_id(), built.codec_id());
+        assert_eq!(retrained.segments().len(), 1);
+        let fresh = plaid_drift::load(&config_db, &retrained).unwrap().unwrap();
+        assert!(fresh.error_ratio().is_none());
     }
 
     #[test]
     fn rebuild_plaid_index_writes_loadable_file_for_non_empty_db() {
         let tmp = tempfile::tempdir().unwrap();
         let data_dir = DataDir::new(tmp.path());
+        let config_db = ConfigDb::open(&data_dir.config_db()).unwrap();
         let embedding_db =
             EmbeddingDb::open(&data_dir.embeddings_db()).unwrap();
 
-        // Two tiny 2-D documents — enough tokens for the sqrt-based
-        // centroid heuristic to pick k >= 1.
+        // Two tiny 2-D documents; the centroid heuristic caps k at
+        // their four tokens.
         embedding_db.store(1, 2, 2, &[0.0, 0.0, 0.1, 0.1]).unwrap();
         embedding_db
             .store(2, 2, 2, &[9.0, 9.0, 10.0, 10.0])
             .unwrap();
 
-        rebuild_plaid_index(&data_dir, &embedding_db).unwrap();
+        rebuild_plaid_index(&data_dir, &embedding_db, &config_db).unwrap();
 
         let loaded = docbert_core::plaid::load_index(&data_dir)
             .unwrap()
             .expect("PLAID index should be persisted after rebuild");
-        let mut doc_ids = loaded.doc_ids.clone();
+        let mut doc_ids: Vec<u64> = loaded.live_doc_ids().collect();
         doc_ids.sort();
         assert_eq!(doc_ids, vec![1, 2]);
     }
+
+    #[test]
+    fn reindex_remembers_pinned_build_params_until_reset() {
+        let tmp = tempfile::tempdir().unwrap();
+        let data_dir = DataDir::new(tmp.path());
+        let config_db = ConfigDb::open(&data_dir.config_db()).unwrap();
+        let embedding_db =
+            EmbeddingDb::open(&data_dir.embeddings_db()).unwrap();
+        embedding_db.store(1, 2, 2, &[0.0, 0.0, 0.1, 0.1]).unwrap();
+        embedding_db
+            .store(2, 2, 2, &[9.0, 9.0, 10.0, 10.0])
+            .unwrap();
+        drop(embedding_db);
+        let args = |flags: &[&str]| {
+            let cli = cli::Cli::parse_from(
+                ["docbert", "reindex"].iter().chain(flags),
+            );
+            match cli.command {
+                cli::Command::Reindex(args) => args,
+                _ => unreachable!(),
+            }
+        };
+        let built = || {
+            docbert_core::plaid::load_index(&data_dir)
+                .unwrap()
+                .unwrap()
+                .params
+        };
+
+        reindex(
+            &config_db,
+            &data_dir,
+            &args(&["--centroids", "2", "--nbits", "4"]),
+        )
+        .unwrap();
+        assert_eq!((built().k_centroids, built().nbits), (2, 4));
+
+        // Flags given later are merged into the remembered ones.
+        reindex(&config_db, &data_dir, &args(&["--kmeans-iters", "7"]))
+            .unwrap();
+        let params = built();
+        assert_eq!(
+            (params.k_centroids, params.nbits, params.max_kmeans_iters),
+            (2, 4, 7)
+        );
+
+        reindex(&config_db, &data_dir, &args(&["--reset-params"])).unwrap();
+        assert!(
+            docbert_core::plaid::load_build_overrides(&config_db)
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!((built().k_centroids, built().nbits), (4, 2));
+    }
 }
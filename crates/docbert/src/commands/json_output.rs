@@ -1,6 +1,16 @@
 use std::path::Path;
 
-use docbert_core::{DataDir, error, model_manager::ModelResolution};
+use docbert_core::{
+    DataDir,
+    error,
+    eval::EvalReport,
+    grep::{GrepMatch, GrepOutcome},
+    model_manager::ModelResolution,
+    plaid::{PlaidBuildOverrides, PlaidQueryParams},
+    saved_search::{SavedSearch, SavedSearchHit},
+    search::WithinOutcome,
+    tantivy_index::SearchResult,
+};
 use serde::Serialize;
 
 fn serialize_json<T: Serialize + ?Sized>(
@@ -43,6 +53,57 @@ pub(super) fn context_list_json_string(
     serialize_json(&items, "failed to serialize context list")
 }
 
+#[derive(Serialize)]
+struct SavedListItem<'a> {
+    name: &'a str,
+    query: &'a str,
+    mode: &'a str,
+    collection: Option<&'a str>,
+    count: usize,
+    created_at: u64,
+    last_run_at: Option<u64>,
+    new_hits: usize,
+}
+
+pub(super) fn saved_list_json_string(
+    searches: &[SavedSearch],
+) -> error::Result<String> {
+    let items: Vec<_> = searches
+        .iter()
+        .map(|saved| SavedListItem {
+            name: &saved.name,
+            query: &saved.query,
+            mode: &saved.mode,
+            collection: saved.collection.as_deref(),
+            count: saved.count,
+            created_at: saved.created_at,
+            last_run_at: saved.last_run_at,
+            new_hits: saved.new_hits.len(),
+        })
+        .collect();
+    serialize_json(&items, "failed to serialize saved searches")
+}
+
+#[derive(Serialize)]
+struct SavedHitJsonItem<'a> {
+    reference: String,
+    #[serde(flatten)]
+    hit: &'a SavedSearchHit,
+}
+
+pub(super) fn saved_new_json_string(
+    hits: &[SavedSearchHit],
+) -> error::Result<String> {
+    let items: Vec<_> = hits
+        .iter()
+        .map(|hit| SavedHitJsonItem {
+            reference: format!("{}:{}", hit.collection, hit.path),
+            hit,
+        })
+        .collect();
+    serialize_json(&items, "failed to serialize saved search hits")
+}
+
 #[derive(Serialize)]
 struct GetJsonOutput<'a> {
     collection: &'a str,
@@ -85,6 +146,122 @@ pub(super) fn multi_get_json_string(
     serialize_json(items, "failed to serialize multi-get response")
 }
 
+#[derive(Serialize)]
+struct CompletionJsonItem<'a> {
+    reference: String,
+    collection: &'a str,
+    path: &'a str,
+    title: &'a str,
+}
+
+#[derive(Serialize)]
+struct GrepJsonItem<'a> {
+    reference: String,
+    #[serde(flatten)]
+    found: &'a GrepMatch,
+}
+
+#[derive(Serialize)]
+struct GrepJsonOutput<'a> {
+    pattern: &'a str,
+    match_count: usize,
+    files_searched: usize,
+    files_skipped: usize,
+    truncated: bool,
+    matches: Vec<GrepJsonItem<'a>>,
+}
+
+pub(super) fn grep_json_string(
+    pattern: &str,
+    outcome: &GrepOutcome,
+) -> error::Result<String> {
+    serialize_json(
+        &GrepJsonOutput {
+            pattern,
+            match_count: outcome.matches.len(),
+            files_searched: outcome.files_searched,
+            files_skipped: outcome.files_skipped,
+            truncated: outcome.truncated,
+            matches: outcome
+                .matches
+                .iter()
+                .map(|found| GrepJsonItem {
+                    reference: format!("{}:{}", found.collection, found.path),
+                    found,
+                })
+                .collect(),
+        },
+        "failed to serialize grep matches",
+    )
+}
+
+#[derive(Serialize)]
+struct WithinJsonPassage<'a> {
+    rank: usize,
+    score: f32,
+    start_byte: u64,
+    end_byte: u64,
+    start_line: usize,
+    end_line: usize,
+    text: &'a str,
+}
+
+#[derive(Serialize)]
+struct WithinJsonOutput<'a> {
+    query: &'a str,
+    reference: String,
+    collection: &'a str,
+    path: &'a str,
+    chunks_scored: usize,
+    passages: Vec<WithinJsonPassage<'a>>,
+}
+
+pub(super) fn within_json_string(
+    query: &str,
+    outcome: &WithinOutcome,
+) -> error::Result<String> {
+    serialize_json(
+        &WithinJsonOutput {
+            query,
+            reference: format!("{}:{}", outcome.collection, outcome.path),
+            collection: &outcome.collection,
+            path: &outcome.path,
+            chunks_scored: outcome.chunks_scored,
+            passages: outcome
+                .passages
+                .iter()
+                .filter_map(|passage| {
+                    Some(WithinJsonPassage {
+                        rank: passage.rank,
+                        score: passage.score,
+                        start_byte: passage.start_byte,
+                        end_byte: passage.byte_offset().inclusive_end()?,
+                        start_line: passage.start_line,
+                        end_line: passage.end_line,
+                        text: &passage.text,
+                    })
+                })
+                .collect(),
+        },
+        "failed to serialize document passages",
+    )
+}
+
+pub(super) fn completion_json_string(
+    completions: &[SearchResult],
+) -> error::Result<String> {
+    let items: Vec<_> = completions
+        .iter()
+        .map(|result| CompletionJsonItem {
+            reference: format!("{}:{}", result.collection, result.path),
+            collection: &result.collection,
+            path: &result.path,
+            title: &result.title,
+        })
+        .collect();
+    serialize_json(&items, "failed to serialize completions")
+}
+
 #[derive(Serialize)]
 struct StatusJsonOutput<'a> {
     data_dir: String,
@@ -93,6 +270,36 @@ struct StatusJsonOutput<'a> {
     embedding_model: Option<&'a str>,
     collections: usize,
     documents: usize,
+    plaid: Option<&'a PlaidStatus>,
+}
+
+/// The PLAID part of `docbert status`.
+#[derive(Debug, Serialize)]
+pub(super) struct PlaidStatus {
+    pub(super) documents: usize,
+    pub(super) segments: usize,
+    pub(super) build: PlaidBuildStatus,
+    pub(super) drift: Option<PlaidDriftStatus>,
+}
+
+/// Parameters the PLAID index was built with, read from its header,
+/// and the ones pinned for the next build.
+#[derive(Debug, Serialize)]
+pub(super) struct PlaidBuildStatus {
+    pub(super) k_centroids: usize,
+    pub(super) nbits: u32,
+    pub(super) max_kmeans_iters: usize,
+    pub(super) pinned: PlaidBuildOverrides,
+}
+
+/// Codec drift of the PLAID index as `docbert status` reports it.
+#[derive(Debug, Serialize)]
+pub(super) struct PlaidDriftStatus {
+    pub(super) error_ratio: Option<f32>,
+    pub(super) imbalance_ratio: f32,
+    pub(super) update_sampled_tokens: usize,
+    pub(super) measured_at: u64,
+    pub(super) retrain_recommended: bool,
 }
 
 pub(super) fn status_json_string(
@@ -101,6 +308,7 @@ pub(super) fn status_json_string(
     embedding_model: Option<&str>,
     collection_count: usize,
     doc_count: usize,
+    plaid: Option<&PlaidStatus>,
 ) -> error::Result<String> {
     serialize_json(
         &StatusJsonOutput {
@@ -110,6 +318,7 @@ pub(super) fn status_json_string(
             embedding_model,
             collections: collection_count,
             documents: doc_count,
+            plaid,
         },
         "failed to serialize status response",
     )
@@ -138,3 +347,42 @@ pub(super) fn model_show_json_string(
         "failed to serialize model resolution",
     )
 }
+
+pub(super) fn plaid_show_json_string(
+    params: &PlaidQueryParams,
+) -> error::Result<String> {
+    serialize_json(params, "failed to serialize PLAID search parameters")
+}
+
+/// The search configuration an evaluation ran with, recorded next to
+/// its scores so runs can be compared.
+#[derive(Serialize)]
+pub(super) struct EvalRunConfig<'a> {
+    pub(super) mode: &'a str,
+    pub(super) k: usize,
+    pub(super) model: &'a str,
+    pub(super) reranker: Option<&'a str>,
+    pub(super) collection: Option<&'a str>,
+    pub(super) no_fuzzy: bool,
+    pub(super) prf: bool,
+    /// PLAID parameters the semantic leg ran with; `None` in `bm25`
+    /// mode.
+    pub(super) plaid: Option<PlaidQueryParams>,
+}
+
+#[derive(Serialize)]
+struct EvalJsonOutput<'a> {
+    config: &'a EvalRunConfig<'a>,
+    #[serde(flatten)]
+    report: &'a EvalReport,
+}
+
+pub(super) fn eval_json_string(
+    config: &EvalRunConfig<'_>,
+    report: &EvalReport,
+) -> error::Result<String> {
+    serialize_json(
+        &EvalJsonOutput { config, report },
+        "failed to serialize evaluation report",
+    )
+}
@@ -1,8 +1,12 @@
 pub(crate) mod collections;
 pub(crate) mod contexts;
+pub(crate) mod eval;
+pub(crate) mod grep;
 pub(crate) mod indexing;
 mod json_output;
 pub(crate) mod model;
+pub(crate) mod plaid;
+pub(crate) mod saved;
 pub(crate) mod search;
 mod style;
 
@@ -16,8 +20,12 @@ mod tests {
         DocChunkEntry,
         DocumentId,
         EmbeddingDb,
+        grep::{GrepMatch, GrepOutcome},
         incremental,
         model_manager::{ModelResolution, ModelSource},
+        saved_search::{SavedSearch, SavedSearchHit},
+        search::{DocumentPassage, SearchMode, WithinOutcome},
+        tantivy_index::SearchResult,
     };
 
     use super::{
@@ -27,13 +35,23 @@ mod tests {
             remove_document_artifacts_for_ids,
         },
         json_output::{
+            EvalRunConfig,
             MultiGetJsonItem,
+            PlaidBuildStatus,
+            PlaidDriftStatus,
+            PlaidStatus,
             collection_list_json_string,
+            completion_json_string,
             context_list_json_string,
+            eval_json_string,
             get_json_string,
+            grep_json_string,
             model_show_json_string,
             multi_get_json_string,
+            saved_list_json_string,
+            saved_new_json_string,
             status_json_string,
+            within_json_string,
         },
         model::EMBEDDING_MODEL_KEY,
     };
@@ -171,6 +189,115 @@ mod tests {
         );
     }
 
+    #[test]
+    fn grep_json_snapshot() {
+        let found = GrepMatch {
+            collection: "notes".to_string(),
+            path: "a.md".to_string(),
+            line_number: 2,
+            column: 1,
+            line: "TODO: fix".to_string(),
+            before: vec!["# Title".to_string()],
+            after: Vec::new(),
+        };
+        let json = grep_json_string(
+            "TODO",
+            &GrepOutcome {
+                matches: vec![found],
+                files_searched: 3,
+                files_skipped: 1,
+                truncated: false,
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            json,
+            "{\"pattern\":\"TODO\",\"match_count\":1,\"files_searched\":3,\"files_skipped\":1,\"truncated\":false,\"matches\":[{\"reference\":\"notes:a.md\",\"collection\":\"notes\",\"path\":\"a.md\",\"line_number\":2,\"column\":1,\"line\":\"TODO: fix\",\"before\":[\"# Title\"],\"after\":[]}]}"
+        );
+    }
+
+    #[test]
+    fn within_json_snapshot() {
+        let json = within_json_string(
+            "ownership",
+            &WithinOutcome {
+                collection: "notes".to_string(),
+                path: "rust.md".to_string(),
+                chunks_scored: 4,
+                passages: vec![DocumentPassage {
+                    rank: 1,
+                    score: 1.5,
+                    chunk_doc_id: 7,
+                    start_byte: 10,
+                    byte_len: 6,
+                    start_line: 2,
+                    end_line: 2,
+                    text: "owner\n".to_string(),
+                }],
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            json,
+            "{\"query\":\"ownership\",\"reference\":\"notes:rust.md\",\"collection\":\"notes\",\"path\":\"rust.md\",\"chunks_scored\":4,\"passages\":[{\"rank\":1,\"score\":1.5,\"start_byte\":10,\"end_byte\":15,\"start_line\":2,\"end_line\":2,\"text\":\"owner\\n\"}]}"
+        );
+    }
+
+    #[test]
+    fn saved_search_json_snapshots() {
+        let hit = SavedSearchHit {
+            search: "plaid".to_string(),
+            doc_num_id: 7,
+            collection: "notes".to_string(),
+            path: "plaid.md".to_string(),
+            title: "PLAID".to_string(),
+            rank: 2,
+            score: 1.5,
+            found_at: 1_700_000_100,
+        };
+        let mut saved = SavedSearch::new(
+            "plaid",
+            "plaid index",
+            SearchMode::Bm25,
+            Some("notes".to_string()),
+            5,
+        )
+        .unwrap();
+        saved.created_at = 1_700_000_000;
+        saved.last_run_at = Some(1_700_000_100);
+        saved.new_hits.push(hit.clone());
+
+        assert_eq!(
+            saved_list_json_string(&[saved]).unwrap(),
+            "[{\"name\":\"plaid\",\"query\":\"plaid index\",\"mode\":\"bm25\",\"collection\":\"notes\",\"count\":5,\"created_at\":1700000000,\"last_run_at\":1700000100,\"new_hits\":1}]"
+        );
+        assert_eq!(
+            saved_new_json_string(&[hit]).unwrap(),
+            "[{\"reference\":\"notes:plaid.md\",\"search\":\"plaid\",\"doc_num_id\":7,\"collection\":\"notes\",\"path\":\"plaid.md\",\"title\":\"PLAID\",\"rank\":2,\"score\":1.5,\"found_at\":1700000100}]"
+        );
+    }
+
+    #[test]
+    fn completion_json_snapshot() {
+        let json = completion_json_string(&[SearchResult {
+            score: 1.5,
+            doc_id: "abc".to_string(),
+            doc_num_id: 1,
+            collection: "notes".to_string(),
+            path: "guides/rust.md".to_string(),
+            title: "Rust".to_string(),
+            mtime: 1000,
+        }])
+        .unwrap();
+
+        assert_eq!(
+            json,
+            "[{\"reference\":\"notes:guides/rust.md\",\"collection\":\"notes\",\"path\":\"guides/rust.md\",\"title\":\"Rust\"}]"
+        );
+    }
+
     #[test]
     fn cli_collection_remove_drops_metadata_and_manifests_but_keeps_cache() {
         let (_tmp, data_dir, config_db) = test_data_dir();
@@ -314,23 +441,50 @@ mod tests {
             Some("lightonai/ColBERT-Zero"),
             2,
             15,
+            None,
         )
         .unwrap();
         assert_eq!(
             with_embedding,
             format!(
-                "{{\"data_dir\":\"{}\",\"model\":\"lightonai/ColBERT-Zero\",\"model_source\":\"config\",\"embedding_model\":\"lightonai/ColBERT-Zero\",\"collections\":2,\"documents\":15}}",
+                "{{\"data_dir\":\"{}\",\"model\":\"lightonai/ColBERT-Zero\",\"model_source\":\"config\",\"embedding_model\":\"lightonai/ColBERT-Zero\",\"collections\":2,\"documents\":15,\"plaid\":null}}",
                 data_dir.root().display()
             )
         );
 
-        let without_embedding =
-            status_json_string(&data_dir, &model_resolution, None, 2, 15)
-                .unwrap();
+        let plaid = PlaidStatus {
+            documents: 40,
+            segments: 3,
+            build: PlaidBuildStatus {
+                k_centroids: 1024,
+                nbits: 2,
+                max_kmeans_iters: 20,
+                pinned: docbert_core::plaid::PlaidBuildOverrides {
+                    nbits: Some(2),
+                    ..Default::default()
+                },
+            },
+            drift: Some(PlaidDriftStatus {
+                error_ratio: Some(1.75),
+                imbalance_ratio: 1.25,
+                update_sampled_tokens: 512,
+                measured_at: 1700000000,
+                retrain_recommended: true,
+            }),
+        };
+        let without_embedding = status_json_string(
+            &data_dir,
+            &model_resolution,
+            None,
+            2,
+            15,
+            Some(&plaid),
+        )
+        .unwrap();
         assert_eq!(
             without_embedding,
             format!(
-                "{{\"data_dir\":\"{}\",\"model\":\"lightonai/ColBERT-Zero\",\"model_source\":\"config\",\"embedding_model\":null,\"collections\":2,\"documents\":15}}",
+                "{{\"data_dir\":\"{}\",\"model\":\"lightonai/ColBERT-Zero\",\"model_source\":\"config\",\"embedding_model\":null,\"collections\":2,\"documents\":15,\"plaid\":{{\"documents\":40,\"segments\":3,\"build\":{{\"k_centroids\":1024,\"nbits\":2,\"max_kmeans_iters\":20,\"pinned\":{{\"k_centroids\":null,\"nbits\":2,\"max_kmeans_iters\":null}}}},\"drift\":{{\"error_ratio\":1.75,\"imbalance_ratio\":1.25,\"update_sampled_tokens\":512,\"measured_at\":1700000000,\"retrain_recommended\":true}}}}}}",
                 data_dir.root().display()
             )
         );
@@ -353,6 +507,96 @@ mod tests {
         );
     }
 
+    #[test]
+    fn eval_json_snapshot() {
+        let mut report = docbert_core::eval::evaluate(
+            &[docbert_core::eval::EvalQuery {
+                id: "q1".to_string(),
+                query: "rust".to_string(),
+            }],
+            &docbert_core::eval::parse_qrels("q1 notes:rust.md 1\n").unwrap(),
+            10,
+            |_| Ok(Vec::new()),
+        )
+        .unwrap();
+        let config = EvalRunConfig {
+            mode: "bm25",
+            k: 10,
+            model: "lightonai/ColBERT-Zero",
+            reranker: None,
+            collection: Some("notes"),
+            no_fuzzy: false,
+            prf: false,
+            plaid: Some(docbert_core::plaid::PlaidQueryParams {
+                n_probe: Some(4),
+                ..Default::default()
+            }),
+        };
+
+        let json = eval_json_string(&config, &report).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert!(value.get("plaid_recall_loss").is_none());
+
+        report.plaid_recall_loss = Some(docbert_core::eval::RecallLoss {
+            queries: 1,
+            mean: 0.5,
+            max: 0.5,
+        });
+        let json = eval_json_string(&config, &report).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            value["config"],
+            serde_json::json!({
+                "mode": "bm25",
+                "k": 10,
+                "model": "lightonai/ColBERT-Zero",
+                "reranker": null,
+                "collection": "notes",
+                "no_fuzzy": false,
+                "prf": false,
+                "plaid": {
+                    "n_probe": 4,
+                    "n_candidate_docs": null,
+                    "centroid_score_threshold": null,
+                    "exhaustive": false,
+                    "exact": null,
+                },
+            })
+        );
+        assert_eq!(
+            value["plaid_recall_loss"],
+            serde_json::json!({"queries": 1, "mean": 0.5, "max": 0.5})
+        );
+        assert_eq!(value["evaluated"], 1);
+        assert_eq!(value["ndcg"], 0.0);
+        assert_eq!(value["queries"][0]["id"], "q1");
+        assert!(value["latency"]["p95_ms"].is_number());
+    }
+
+    #[test]
+    fn plaid_set_replaces_the_stored_params_and_clear_removes_them() {
+        let (_tmp, _data_dir, config_db) = test_data_dir();
+        let args = |args: &[&str]| {
+            let mut argv = vec!["plaid-set"];
+            argv.extend_from_slice(args);
+            <crate::cli::PlaidQueryArgs as clap::Parser>::parse_from(argv)
+        };
+
+        super::plaid::set(&config_db, &args(&["--n-probe", "4"])).unwrap();
+        super::plaid::set(&config_db, &args(&["--exhaustive"])).unwrap();
+        let stored =
+            docbert_core::plaid::load_query_params(&config_db).unwrap();
+        assert!(stored.exhaustive);
+        assert_eq!(stored.n_probe, None);
+
+        super::plaid::clear(&config_db).unwrap();
+        assert!(
+            docbert_core::plaid::load_query_params(&config_db)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
     #[test]
     fn embedding_model_setting_key_is_stable() {
         assert_eq!(EMBEDDING_MODEL_KEY, "embedding_model");
@@ -5,10 +5,17 @@ use docbert_core::{
     DataDir,
     ModelManager,
     error,
-    model_manager::{MODEL_ENV_VAR, ModelResolution},
+    model_manager::{MODEL_ENV_VAR, ModelResolution, RERANKER_MODEL_SETTING},
+    plaid_drift,
 };
 
-use super::json_output::{model_show_json_string, status_json_string};
+use super::json_output::{
+    PlaidBuildStatus,
+    PlaidDriftStatus,
+    PlaidStatus,
+    model_show_json_string,
+    status_json_string,
+};
 
 pub(super) const EMBEDDING_MODEL_KEY: &str = "embedding_model";
 
@@ -91,6 +98,7 @@ pub(crate) fn status(
     let doc_count = config_db.list_document_ids()?.len();
     let model_name = &model_resolution.model_id;
     let embedding_model = config_db.get_setting(EMBEDDING_MODEL_KEY)?;
+    let plaid = plaid_status(config_db, data_dir)?;
 
     if json {
         println!(
@@ -101,6 +109,7 @@ pub(crate) fn status(
                 embedding_model.as_deref(),
                 collections.len(),
                 doc_count,
+                plaid.as_ref(),
             )?
         );
     } else {
@@ -123,11 +132,86 @@ pub(crate) fn status(
             println!("  {name}: {path}");
         }
         println!("Documents: {doc_count}");
+        print_plaid_status(plaid.as_ref());
     }
     Ok(())
 }
 
+/// Size and codec drift of the PLAID index, or `None` before the
+/// first build.
+fn plaid_status(
+    config_db: &ConfigDb,
+    data_dir: &DataDir,
+) -> error::Result<Option<PlaidStatus>> {
+    let Some(index) = docbert_core::plaid::load_index(data_dir)? else {
+        return Ok(None);
+    };
+    let thresholds = plaid_drift::DriftThresholds::default();
+    let drift =
+        plaid_drift::load(config_db, &index)?.map(|drift| PlaidDriftStatus {
+            error_ratio: drift.error_ratio(),
+            imbalance_ratio: drift.imbalance_ratio(),
+            update_sampled_tokens: drift.update_sampled_tokens,
+            measured_at: drift.measured_at,
+            retrain_recommended: drift.exceeds(thresholds),
+        });
+    Ok(Some(PlaidStatus {
+        documents: index.num_documents(),
+        segments: index.segments().len(),
+        build: PlaidBuildStatus {
+            k_centroids: index.params.k_centroids,
+            nbits: index.params.nbits,
+            max_kmeans_iters: index.params.max_kmeans_iters,
+            pinned: docbert_core::plaid::load_build_overrides(config_db)?,
+        },
+        drift,
+    }))
+}
+
+fn print_plaid_status(plaid: Option<&PlaidStatus>) {
+    let Some(plaid) = plaid else {
+        println!("PLAID index: (not built -- run `docbert sync`)");
+        return;
+    };
+    println!(
+        "PLAID index: {} documents in {} segment(s)",
+        plaid.documents, plaid.segments,
+    );
+    let build = &plaid.build;
+    let pinned = |is_pinned: bool| if is_pinned { " (pinned)" } else { "" };
+    println!(
+        "PLAID build: {} centroids{}, {}-bit residuals{}, {} k-means iterations{}",
+        build.k_centroids,
+        pinned(build.pinned.k_centroids.is_some()),
+        build.nbits,
+        pinned(build.pinned.nbits.is_some()),
+        build.max_kmeans_iters,
+        pinned(build.pinned.max_kmeans_iters.is_some()),
+    );
+    let Some(drift) = &plaid.drift else {
+        println!("Codec drift: (not measured yet)");
+        return;
+    };
+    let error = match drift.error_ratio {
+        Some(ratio) => format!(
+            "{ratio:.2}x over {} sampled token(s)",
+            drift.update_sampled_tokens
+        ),
+        None => "no updates since the last build".to_string(),
+    };
+    println!(
+        "Codec drift: reconstruction error {error}, centroid imbalance {:.2}x{}",
+        drift.imbalance_ratio,
+        if drift.retrain_recommended {
+            " (RETRAIN RECOMMENDED -- run `docbert reindex`)"
+        } else {
+            ""
+        },
+    );
+}
+
 pub(crate) fn show(
+    config_db: &ConfigDb,
     model_resolution: &ModelResolution,
     json: bool,
 ) -> error::Result<()> {
@@ -147,6 +231,10 @@ pub(crate) fn show(
         } else {
             println!("Config setting: (unset)");
         }
+        match config_db.get_setting(RERANKER_MODEL_SETTING)? {
+            Some(reranker) => println!("Reranker: {reranker}"),
+            None => println!("Reranker: (unset)"),
+        }
     }
     Ok(())
 }
@@ -177,3 +265,30 @@ pub(crate) fn clear(config_db: &ConfigDb) -> error::Result<()> {
     }
     Ok(())
 }
+
+pub(crate) fn set_reranker(
+    config_db: &ConfigDb,
+    path: &str,
+) -> error::Result<()> {
+    config_db.set_setting(RERANKER_MODEL_SETTING, path)?;
+
+    let model_path = Path::new(path);
+    if !model_path.join("config.json").exists() {
+        eprintln!(
+            "Warning: {} has no config.json; the reranker will fail to load.",
+            model_path.display()
+        );
+    }
+
+    println!("Stored {RERANKER_MODEL_SETTING}: {path}");
+    Ok(())
+}
+
+pub(crate) fn clear_reranker(config_db: &ConfigDb) -> error::Result<()> {
+    if config_db.remove_setting(RERANKER_MODEL_SETTING)? {
+        println!("Cleared {RERANKER_MODEL_SETTING} setting.");
+    } else {
+        println!("{RERANKER_MODEL_SETTING} setting was already unset.");
+    }
+    Ok(())
+}
@@ -0,0 +1,69 @@
+use docbert_core::{
+    ConfigDb,
+    error,
+    plaid::{self, PLAID_QUERY_SETTING, PlaidQueryParams},
+};
+
+use super::json_output::plaid_show_json_string;
+use crate::cli;
+
+pub(crate) fn show(config_db: &ConfigDb, json: bool) -> error::Result<()> {
+    let params = plaid::load_query_params(config_db)?;
+    if json {
+        println!("{}", plaid_show_json_string(&params)?);
+    } else if params.is_empty() {
+        println!("PLAID search: paper defaults");
+    } else {
+        println!("PLAID search: {}", describe(&params));
+    }
+    Ok(())
+}
+
+pub(crate) fn set(
+    config_db: &ConfigDb,
+    args: &cli::PlaidQueryArgs,
+) -> error::Result<()> {
+    let params = args.params();
+    plaid::save_query_params(config_db, &params)?;
+    if params.is_empty() {
+        println!("Cleared {PLAID_QUERY_SETTING} setting.");
+    } else {
+        println!("Stored {PLAID_QUERY_SETTING}: {}", describe(&params));
+    }
+    Ok(())
+}
+
+pub(crate) fn clear(config_db: &ConfigDb) -> error::Result<()> {
+    if config_db.remove_setting(PLAID_QUERY_SETTING)? {
+        println!("Cleared {PLAID_QUERY_SETTING} setting.");
+    } else {
+        println!("{PLAID_QUERY_SETTING} setting was already unset.");
+    }
+    Ok(())
+}
+
+/// One-line summary of the parameters that differ from the paper
+/// defaults, e.g. `n_probe=4, n_candidate_docs=64`.
+pub(super) fn describe(params: &PlaidQueryParams) -> String {
+    if params.exact == Some(true) {
+        return "exact".to_string();
+    }
+    let mut parts = Vec::new();
+    if params.exact == Some(false) {
+        parts.push("no exact".to_string());
+    }
+    if params.exhaustive {
+        parts.push("exhaustive".to_string());
+        return parts.join(", ");
+    }
+    if let Some(n_probe) = params.n_probe {
+        parts.push(format!("n_probe={n_probe}"));
+    }
+    if let Some(n_candidate_docs) = params.n_candidate_docs {
+        parts.push(format!("n_candidate_docs={n_candidate_docs}"));
+    }
+    if let Some(threshold) = params.centroid_score_threshold {
+        parts.push(format!("centroid_score_threshold={threshold}"));
+    }
+    parts.join(", ")
+}
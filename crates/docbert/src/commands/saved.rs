@@ -0,0 +1,223 @@
+use docbert_core::{
+    ConfigDb,
+    DataDir,
+    ModelManager,
+    SearchIndex,
+    error,
+    model_manager::ModelResolution,
+    saved_search::{self, SavedSearch},
+    search::{self, SearchMode},
+};
+
+use super::{
+    json_output::{saved_list_json_string, saved_new_json_string},
+    model::log_model_runtime,
+    style,
+};
+use crate::cli;
+
+fn missing(name: &str) -> error::Error {
+    error::Error::NotFound {
+        kind: "saved search",
+        name: name.to_string(),
+    }
+}
+
+pub(crate) fn add(
+    config_db: &ConfigDb,
+    data_dir: &DataDir,
+    model_resolution: &ModelResolution,
+    args: &cli::SavedAddArgs,
+) -> error::Result<()> {
+    let name = args.name.as_str();
+    if config_db.get_saved_search(name)?.is_some() {
+        return Err(error::Error::Config(format!(
+            "saved search '{name}' already exists; remove it first"
+        )));
+    }
+    if let Some(collection) = &args.collection
+        && config_db.get_collection(collection)?.is_none()
+    {
+        return Err(error::Error::NotFound {
+            kind: "collection",
+            name: collection.clone(),
+        });
+    }
+
+    // `--mode` is restricted to valid values by clap.
+    let mode = SearchMode::parse(&args.mode).unwrap_or(SearchMode::Hybrid);
+    let mut saved = SavedSearch::new(
+        name,
+        &args.query,
+        mode,
+        args.collection.clone(),
+        args.count,
+    )?;
+
+    // Take the baseline now so the next sync only reports newcomers. A
+    // search that can't run yet (no index, no PLAID) is still saved and
+    // takes its baseline on the first sync that can run it.
+    let baseline =
+        SearchIndex::open(&data_dir.tantivy_dir()?).and_then(|search_index| {
+            let mut model =
+                ModelManager::with_model_id(model_resolution.model_id.clone());
+            saved_search::run(
+                &saved,
+                &search_index,
+                config_db,
+                data_dir,
+                &mut model,
+            )
+        });
+    match baseline {
+        Ok(results) => {
+            let created_at = saved.created_at;
+            saved_search::record(&mut saved, &results, created_at);
+            println!(
+                "Saved search '{name}' ({} current result(s))",
+                results.len()
+            );
+        }
+        Err(err) => {
+            eprintln!(
+                "{} could not run '{name}' yet ({err}); it will take its \
+                 baseline on the next sync.",
+                style::warn(&"Warning:"),
+            );
+            println!("Saved search '{name}'");
+        }
+    }
+    config_db.set_saved_search(&saved)
+}
+
+pub(crate) fn list(config_db: &ConfigDb, json: bool) -> error::Result<()> {
+    let searches = config_db.list_saved_searches()?;
+
+    if json {
+        println!("{}", saved_list_json_string(&searches)?);
+    } else if searches.is_empty() {
+        println!("No saved searches.");
+    } else {
+        for saved in &searches {
+            let scope = saved.collection.as_deref().unwrap_or("all");
+            println!(
+                "{}\t{}\t{} top {} in {scope}\t{} new",
+                saved.name,
+                saved.query,
+                saved.mode,
+                saved.count,
+                saved.new_hits.len(),
+            );
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn run(
+    config_db: &ConfigDb,
+    data_dir: &DataDir,
+    model_resolution: &ModelResolution,
+    name: &str,
+    json: bool,
+    files: bool,
+) -> error::Result<()> {
+    let saved = config_db
+        .get_saved_search(name)?
+        .ok_or_else(|| missing(name))?;
+    let search_index = SearchIndex::open(&data_dir.tantivy_dir()?)?;
+    let mut model =
+        ModelManager::with_model_id(model_resolution.model_id.clone());
+    if saved.mode() != SearchMode::Bm25 && !json && !files {
+        log_model_runtime(&mut model)?;
+    }
+
+    let mut results = saved_search::run(
+        &saved,
+        &search_index,
+        config_db,
+        data_dir,
+        &mut model,
+    )?;
+    search::disambiguate_doc_ids(&mut results, config_db);
+
+    if json {
+        search::format_json(&results, &saved.query);
+    } else if files {
+        search::format_files(&results, config_db);
+    } else {
+        search::format_human(&results);
+    }
+    Ok(())
+}
+
+pub(crate) fn remove(config_db: &ConfigDb, name: &str) -> error::Result<()> {
+    if !config_db.remove_saved_search(name)? {
+        return Err(missing(name));
+    }
+    println!("Removed saved search '{name}'");
+    Ok(())
+}
+
+pub(crate) fn new_hits(
+    config_db: &ConfigDb,
+    name: Option<&str>,
+    clear: bool,
+    json: bool,
+) -> error::Result<()> {
+    let hits = saved_search::new_hits(config_db, name)?;
+
+    if json {
+        println!("{}", saved_new_json_string(&hits)?);
+    } else if hits.is_empty() {
+        println!("No new results.");
+    } else {
+        let mut last = None;
+        for hit in &hits {
+            if last != Some(hit.search.as_str()) {
+                println!("{}", style::subheader(&hit.search));
+                last = Some(hit.search.as_str());
+            }
+            println!(
+                "  {}:{}  {}  (rank {}, score {:.3})",
+                hit.collection, hit.path, hit.title, hit.rank, hit.score,
+            );
+        }
+    }
+
+    if clear {
+        saved_search::clear_new_hits(config_db, name)?;
+    }
+    Ok(())
+}
+
+/// Re-run every saved search after a sync changed the indexed documents
+/// and report how many new results they picked up.
+///
+/// Failures are reported as warnings: the sync itself already succeeded.
+pub(crate) fn refresh_after_sync(
+    config_db: &ConfigDb,
+    data_dir: &DataDir,
+    model_id: &str,
+) {
+    let refreshed = (|| {
+        if config_db.list_saved_searches()?.is_empty() {
+            return Ok(Vec::new());
+        }
+        let search_index = SearchIndex::open(&data_dir.tantivy_dir()?)?;
+        let mut model = ModelManager::with_model_id(model_id.to_string());
+        saved_search::refresh(&search_index, config_db, data_dir, &mut model)
+    })();
+
+    match refreshed {
+        Ok(hits) if hits.is_empty() => {}
+        Ok(hits) => eprintln!(
+            "{} new result(s) for saved searches; run `docbert saved new` \
+             to see them.",
+            style::accent(&hits.len()),
+        ),
+        Err(err) => eprintln!(
+            "{} could not refresh saved searches: {err}",
+            style::warn(&"Warning:"),
+        ),
+    }
+}
@@ -1,70 +1,262 @@
+use std::path::Path;
+
 use docbert_core::{
     ConfigDb,
     DataDir,
     ModelManager,
     SearchIndex,
     error,
-    model_manager::ModelResolution,
+    model_manager::{ModelResolution, resolve_reranker_model},
     search,
 };
 
 use super::{
-    json_output::{MultiGetJsonItem, get_json_string, multi_get_json_string},
+    json_output::{
+        MultiGetJsonItem,
+        completion_json_string,
+        get_json_string,
+        multi_get_json_string,
+        within_json_string,
+    },
     model::log_model_runtime,
 };
 use crate::cli;
 
+/// `--group` is restricted to valid values by clap.
+fn passage_grouping(group: &str) -> search::PassageGrouping {
+    search::PassageGrouping::parse(group).unwrap_or_default()
+}
+
+/// Print search results in the output mode the flags selected, with the
+/// query-level breakdown when the search was explained, the facet
+/// counts when they were requested, and any spelling suggestion.
+#[allow(clippy::too_many_arguments)]
+fn print_results(
+    results: &[search::FinalResult],
+    explain: Option<&search::SearchExplain>,
+    facets: Option<&search::Facets>,
+    suggestion: Option<&search::QuerySuggestion>,
+    query: &str,
+    config_db: &ConfigDb,
+    json: bool,
+    files: bool,
+) {
+    if json {
+        search::format_json_outcome(
+            results, query, explain, facets, suggestion,
+        );
+    } else if files {
+        search::format_files(results, config_db);
+    } else {
+        if let Some(suggestion) = suggestion {
+            search::format_suggestion_human(suggestion);
+        }
+        search::format_human(results);
+        if let Some(explain) = explain {
+            search::format_explain_human(explain);
+        }
+        if let Some(facets) = facets {
+            search::format_facets_human(facets);
+        }
+    }
+}
+
 pub(crate) fn run(
     config_db: &ConfigDb,
     data_dir: &DataDir,
     model_resolution: &ModelResolution,
     args: &cli::SearchArgs,
 ) -> error::Result<()> {
+    if let Some(reference) = &args.within {
+        return within(config_db, data_dir, model_resolution, reference, args);
+    }
+
     let search_index = SearchIndex::open(&data_dir.tantivy_dir()?)?;
     let mut model =
-        ModelManager::with_model_id(model_resolution.model_id.clone());
+        ModelManager::with_model_id(model_resolution.model_id.clone())
+            .with_reranker_model(resolve_reranker_model(
+                config_db,
+                args.rerank_model.as_deref(),
+            )?);
     if !args.bm25_only {
         log_model_runtime(&mut model)?;
     }
 
-    let mut results = if args.bm25_only || args.no_fuzzy || args.all {
-        let params = search::SearchParams {
-            query: args.query.clone(),
-            count: args.count,
-            collection: args.collection.clone(),
-            min_score: args.min_score,
-            bm25_only: args.bm25_only,
-            no_fuzzy: args.no_fuzzy,
-            all: args.all,
-        };
-
-        search::run(&params, &search_index, config_db, data_dir, &mut model)?
-    } else {
-        let request = search::SearchQuery {
-            query: args.query.clone(),
-            collection: args.collection.clone(),
-            count: args.count,
-            min_score: args.min_score,
-        };
-        search::by_mode(
-            search::SearchMode::Hybrid,
-            &request,
+    let grouping = passage_grouping(&args.group);
+
+    if let Some(path) = &args.queries_file {
+        let queries = read_queries_file(path)?;
+        let params: Vec<search::SearchParams> = queries
+            .iter()
+            .map(|query| search_params(args, query, grouping))
+            .collect();
+        let outcomes = search::run_batch(
+            &params,
             &search_index,
             config_db,
             data_dir,
             &mut model,
-        )?
-    };
+        )?;
+        for (i, (query, outcome)) in queries.iter().zip(outcomes).enumerate() {
+            if !args.json && !args.files {
+                if i > 0 {
+                    println!();
+                }
+                println!("Query: {query}");
+            }
+            print_outcome(
+                outcome, query, args, grouping, config_db, &mut model,
+            )?;
+        }
+        return Ok(());
+    }
 
-    search::disambiguate_doc_ids(&mut results, config_db);
+    // clap requires a query whenever --queries-file is absent.
+    let query = args.query.as_deref().unwrap_or_default();
+    let params = search_params(args, query, grouping);
+    let outcome = search::run_explained(
+        &params,
+        &search_index,
+        config_db,
+        data_dir,
+        &mut model,
+    )?;
+    print_outcome(outcome, query, args, grouping, config_db, &mut model)
+}
+
+/// `docbert search --in <ref>`: rank the passages of one document.
+fn within(
+    config_db: &ConfigDb,
+    data_dir: &DataDir,
+    model_resolution: &ModelResolution,
+    reference: &str,
+    args: &cli::SearchArgs,
+) -> error::Result<()> {
+    let mut model =
+        ModelManager::with_model_id(model_resolution.model_id.clone());
+    log_model_runtime(&mut model)?;
+
+    // clap requires a query whenever --queries-file is absent, and --in
+    // conflicts with it.
+    let query = args.query.as_deref().unwrap_or_default();
+    let params = search::WithinParams {
+        reference: reference.to_string(),
+        query: query.to_string(),
+        count: args.count,
+        min_score: args.min_score,
+    };
+    let outcome = search::within(&params, config_db, data_dir, &mut model)?;
 
     if args.json {
-        search::format_json(&results, &args.query);
-    } else if args.files {
-        search::format_files(&results, config_db);
-    } else {
-        search::format_human(&results);
+        println!("{}", within_json_string(query, &outcome)?);
+        return Ok(());
+    }
+    if outcome.passages.is_empty() {
+        println!("No passages found.");
+        return Ok(());
+    }
+    for passage in &outcome.passages {
+        let start = passage.start_byte;
+        let end = passage.byte_offset().inclusive_end().unwrap_or(start);
+        println!(
+            "{:>3}. [{:.3}] {}:{} lines {}-{} @ bytes {start}-{end}",
+            passage.rank,
+            passage.score,
+            outcome.collection,
+            outcome.path,
+            passage.start_line,
+            passage.end_line,
+        );
+        for line in passage.text.lines() {
+            println!("     {line}");
+        }
+    }
+    println!(
+        "\n{} passage(s) from {} chunk(s)",
+        outcome.passages.len(),
+        outcome.chunks_scored
+    );
+    Ok(())
+}
+
+/// The core search parameters `docbert search` runs `query` with.
+fn search_params(
+    args: &cli::SearchArgs,
+    query: &str,
+    grouping: search::PassageGrouping,
+) -> search::SearchParams {
+    search::SearchParams {
+        query: query.to_string(),
+        count: args.count,
+        collection: args.collection.clone(),
+        min_score: args.min_score,
+        bm25_only: args.bm25_only,
+        no_fuzzy: args.no_fuzzy,
+        all: args.all,
+        passages: search::passage_count(args.passages, grouping),
+        explain: args.explain,
+        prf: args.prf.then_some(search::PrfParams {
+            feedback_docs: args.prf_docs,
+            expansion_terms: args.prf_terms,
+            weight: args.prf_weight,
+        }),
+        facets: args.facets,
+        offset: args.offset,
+        cursor: None,
+        autocorrect: args.autocorrect,
+        plaid: args.plaid.params(),
+    }
+}
+
+/// Read a `--queries-file`: one query per line, blank lines skipped.
+fn read_queries_file(path: &Path) -> error::Result<Vec<String>> {
+    let content = std::fs::read_to_string(path)?;
+    let queries: Vec<String> = content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect();
+    if queries.is_empty() {
+        return Err(error::Error::Config(format!(
+            "no queries in {}",
+            path.display()
+        )));
+    }
+    Ok(queries)
+}
+
+/// Lay out, explain and print the outcome of one `docbert search` query.
+fn print_outcome(
+    outcome: search::SearchOutcome,
+    query: &str,
+    args: &cli::SearchArgs,
+    grouping: search::PassageGrouping,
+    config_db: &ConfigDb,
+    model: &mut ModelManager,
+) -> error::Result<()> {
+    let mut results = search::group_passages(outcome.results, grouping);
+    if args.explain {
+        // Autocorrected results match the corrected query.
+        let searched = outcome
+            .suggestion
+            .as_ref()
+            .filter(|suggestion| suggestion.applied)
+            .map_or(query, |suggestion| suggestion.query.as_str());
+        search::explain_matches(&mut results, searched, config_db, model)?;
     }
+    search::disambiguate_doc_ids(&mut results, config_db);
+
+    print_results(
+        &results,
+        outcome.explain.as_ref(),
+        outcome.facets.as_ref(),
+        outcome.suggestion.as_ref(),
+        query,
+        config_db,
+        args.json,
+        args.files,
+    );
     Ok(())
 }
 
@@ -75,29 +267,85 @@ pub(crate) fn semantic(
     args: &cli::SemanticSearchArgs,
 ) -> error::Result<()> {
     let mut model =
-        ModelManager::with_model_id(model_resolution.model_id.clone());
+        ModelManager::with_model_id(model_resolution.model_id.clone())
+            .with_reranker_model(resolve_reranker_model(
+                config_db,
+                args.rerank_model.as_deref(),
+            )?);
     log_model_runtime(&mut model)?;
 
+    let grouping = passage_grouping(&args.group);
     let params = search::SemanticSearchParams {
         query: args.query.clone(),
         collection: None,
         count: args.count,
         min_score: args.min_score,
         all: args.all,
+        passages: search::passage_count(args.passages, grouping),
+        explain: args.explain,
+        facets: args.facets,
+        offset: args.offset,
+        cursor: None,
+        plaid: args.plaid.params(),
     };
 
-    let mut results =
-        search::semantic(&params, config_db, data_dir, &mut model)?;
+    let outcome =
+        search::semantic_explained(&params, config_db, data_dir, &mut model)?;
 
+    let mut results = search::group_passages(outcome.results, grouping);
+    if args.explain {
+        search::explain_matches(
+            &mut results,
+            &args.query,
+            config_db,
+            &mut model,
+        )?;
+    }
     search::disambiguate_doc_ids(&mut results, config_db);
 
-    if args.json {
-        search::format_json(&results, &args.query);
-    } else if args.files {
-        search::format_files(&results, config_db);
-    } else {
-        search::format_human(&results);
-    }
+    print_results(
+        &results,
+        outcome.explain.as_ref(),
+        outcome.facets.as_ref(),
+        None,
+        &args.query,
+        config_db,
+        args.json,
+        args.files,
+    );
+    Ok(())
+}
+
+pub(crate) fn similar(
+    config_db: &ConfigDb,
+    data_dir: &DataDir,
+    args: &cli::SimilarArgs,
+) -> error::Result<()> {
+    let search_index = SearchIndex::open(&data_dir.tantivy_dir()?)?;
+
+    let grouping = passage_grouping(&args.group);
+    let params = search::SimilarParams {
+        reference: args.reference.clone(),
+        count: args.count,
+        collection: args.collection.clone(),
+        passages: search::passage_count(args.passages, grouping),
+    };
+
+    let results = search::similar(&params, &search_index, config_db, data_dir)?;
+
+    let mut results = search::group_passages(results, grouping);
+    search::disambiguate_doc_ids(&mut results, config_db);
+
+    print_results(
+        &results,
+        None,
+        None,
+        None,
+        &args.reference,
+        config_db,
+        args.json,
+        args.files,
+    );
     Ok(())
 }
 
@@ -247,3 +495,24 @@ pub(crate) fn multi_get(
 
     Ok(())
 }
+
+pub(crate) fn complete(
+    data_dir: &DataDir,
+    args: &cli::CompleteArgs,
+) -> error::Result<()> {
+    let search_index = SearchIndex::open(&data_dir.tantivy_dir()?)?;
+    let completions = search_index.complete(
+        &args.prefix,
+        args.collection.as_deref(),
+        args.count,
+    )?;
+
+    if args.json {
+        println!("{}", completion_json_string(&completions)?);
+    } else {
+        for result in &completions {
+            println!("{}:{}", result.collection, result.path);
+        }
+    }
+    Ok(())
+}
@@ -344,6 +344,7 @@ mod tests {
             raw_content: None,
             metadata: None,
             mtime: 1,
+            tags: Vec::new(),
         }];
 
         let chunking_config = docbert_core::chunking::Config {
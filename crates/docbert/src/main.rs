@@ -1,7 +1,13 @@
 use std::path::{Path, PathBuf};
 
 use clap::Parser;
-use docbert_core::{ConfigDb, DataDir, error, model_manager::resolve_model};
+use docbert_core::{
+    ConfigDb,
+    DataDir,
+    error,
+    model_manager::{resolve_model, resolve_reranker_model},
+    tantivy_migration,
+};
 use tracing_subscriber::EnvFilter;
 
 mod cli;
@@ -12,7 +18,7 @@ mod runtime;
 mod snapshots;
 mod web;
 
-use cli::{Cli, CollectionAction, Command, ContextAction};
+use cli::{Cli, CollectionAction, Command, ContextAction, SavedAction};
 
 /// Resolve the data directory using this priority order:
 /// 1. an explicit path, such as `--data-dir`
@@ -59,6 +65,22 @@ fn init_tracing(verbose: u8) {
         .init();
 }
 
+/// Rebuild a Tantivy index whose schema predates this version from the
+/// documents `config_db` records.
+///
+/// Only `sync`, `rebuild` and the servers run this; every other command
+/// leaves the index alone and fails with `SearchIndexOutdated`, which
+/// points at `docbert sync`.
+fn migrate_search_index(
+    data_dir: &DataDir,
+    config_db: &ConfigDb,
+) -> error::Result<()> {
+    if tantivy_migration::ensure_search_index_migrated(data_dir, config_db)? {
+        tracing::info!("rebuilt the lexical index for this version of docbert");
+    }
+    Ok(())
+}
+
 fn main() -> error::Result<()> {
     let cli = Cli::parse();
 
@@ -77,17 +99,22 @@ fn main() -> error::Result<()> {
     let data_dir = resolve_data_dir(cli.data_dir.as_deref())?;
 
     if let Command::Mcp = &cli.command {
-        let model_id = {
+        let (model_id, reranker_model) = {
             let config_db = ConfigDb::open(&data_dir.config_db())?;
-            resolve_model(&config_db, cli.model.as_deref())?.model_id
+            migrate_search_index(&data_dir, &config_db)?;
+            (
+                resolve_model(&config_db, cli.model.as_deref())?.model_id,
+                resolve_reranker_model(&config_db, None)?,
+            )
         };
-        mcp::run_mcp(data_dir, model_id)?;
+        mcp::run_mcp(data_dir, model_id, reranker_model)?;
         return Ok(());
     }
 
     if let Command::Web(args) = &cli.command {
         let model_id = {
             let config_db = ConfigDb::open(&data_dir.config_db())?;
+            migrate_search_index(&data_dir, &config_db)?;
             resolve_model(&config_db, cli.model.as_deref())?.model_id
         };
         web::run(args, data_dir, model_id)?;
@@ -95,6 +122,9 @@ fn main() -> error::Result<()> {
     }
 
     let config_db = ConfigDb::open(&data_dir.config_db())?;
+    if matches!(cli.command, Command::Sync(_) | Command::Rebuild(_)) {
+        migrate_search_index(&data_dir, &config_db)?;
+    }
     let model_resolution = resolve_model(&config_db, cli.model.as_deref())?;
 
     match cli.command {
@@ -122,6 +152,40 @@ fn main() -> error::Result<()> {
                 commands::contexts::list(&config_db, json)?;
             }
         },
+        Command::Saved { action } => match action {
+            SavedAction::Add(args) => {
+                commands::saved::add(
+                    &config_db,
+                    &data_dir,
+                    &model_resolution,
+                    &args,
+                )?;
+            }
+            SavedAction::List { json } => {
+                commands::saved::list(&config_db, json)?;
+            }
+            SavedAction::Run { name, json, files } => {
+                commands::saved::run(
+                    &config_db,
+                    &data_dir,
+                    &model_resolution,
+                    &name,
+                    json,
+                    files,
+                )?;
+            }
+            SavedAction::Remove { name } => {
+                commands::saved::remove(&config_db, &name)?;
+            }
+            SavedAction::New { name, clear, json } => {
+                commands::saved::new_hits(
+                    &config_db,
+                    name.as_deref(),
+                    clear,
+                    json,
+                )?;
+            }
+        },
         Command::Search(args) => {
             commands::search::run(
                 &config_db,
@@ -138,12 +202,29 @@ fn main() -> error::Result<()> {
                 &args,
             )?;
         }
+        Command::Eval(args) => {
+            commands::eval::run(
+                &config_db,
+                &data_dir,
+                &model_resolution,
+                &args,
+            )?;
+        }
+        Command::Similar(args) => {
+            commands::search::similar(&config_db, &data_dir, &args)?;
+        }
         Command::Get(args) => {
             commands::search::get(&config_db, &args)?;
         }
         Command::MultiGet(args) => {
             commands::search::multi_get(&config_db, &args)?;
         }
+        Command::Complete(args) => {
+            commands::search::complete(&data_dir, &args)?;
+        }
+        Command::Grep(args) => {
+            commands::grep::run(&config_db, &data_dir, &args)?;
+        }
         Command::Rebuild(args) => {
             commands::indexing::rebuild(
                 &config_db,
@@ -152,8 +233,8 @@ fn main() -> error::Result<()> {
                 &model_resolution.model_id,
             )?;
         }
-        Command::Reindex => {
-            commands::indexing::reindex(&data_dir)?;
+        Command::Reindex(args) => {
+            commands::indexing::reindex(&config_db, &data_dir, &args)?;
         }
         Command::Sync(args) => {
             commands::indexing::sync(
@@ -175,7 +256,7 @@ fn main() -> error::Result<()> {
         Command::Web(_) => unreachable!(), // Handled above
         Command::Model { action } => match action {
             cli::ModelAction::Show { json } => {
-                commands::model::show(&model_resolution, json)?;
+                commands::model::show(&config_db, &model_resolution, json)?;
             }
             cli::ModelAction::Set { model } => {
                 commands::model::set(&config_db, &model)?;
@@ -183,6 +264,23 @@ fn main() -> error::Result<()> {
             cli::ModelAction::Clear => {
                 commands::model::clear(&config_db)?;
             }
+            cli::ModelAction::SetReranker { path } => {
+                commands::model::set_reranker(&config_db, &path)?;
+            }
+            cli::ModelAction::ClearReranker => {
+                commands::model::clear_reranker(&config_db)?;
+            }
+        },
+        Command::Plaid { action } => match action {
+            cli::PlaidAction::Show { json } => {
+                commands::plaid::show(&config_db, json)?;
+            }
+            cli::PlaidAction::Set(args) => {
+                commands::plaid::set(&config_db, &args)?;
+            }
+            cli::PlaidAction::Clear => {
+                commands::plaid::clear(&config_db)?;
+            }
         },
     }
 
@@ -8,8 +8,12 @@ use docbert_core::{
     data_dir::DataDir,
     doc_id::format_document_ref,
     error,
+    grep,
     model_manager::{DEFAULT_MODEL_ID, ModelManager},
+    plaid,
+    saved_search,
     search,
+    search_cache::{SearchCache, SearchCursor},
     tantivy_index::SearchIndex,
     text,
 };
@@ -63,9 +67,13 @@ use crate::runtime;
 const DEFAULT_SEARCH_LIMIT: usize = 10;
 
 struct DocbertState {
+    /// Carries the server's resident PLAID index, so searches reuse one
+    /// in-memory copy until `plaid.idx` changes on disk.
     data_dir: DataDir,
     search_index: SearchIndex,
     model: Mutex<ModelManager>,
+    /// Rankings kept between pages of the search tools.
+    search_cache: SearchCache,
 }
 
 impl DocbertState {
@@ -163,6 +171,18 @@ fn build_search_result_item(
         None
     };
 
+    let passages = result
+        .passages
+        .iter()
+        .filter_map(|passage| {
+            Some(SearchPassage {
+                score: passage.score,
+                start_byte: passage.start_byte,
+                end_byte: passage.byte_offset().inclusive_end()?,
+            })
+        })
+        .collect();
+
     SearchResultItem {
         doc_id: format_document_ref(&result.doc_id),
         collection: result.collection,
@@ -170,35 +190,142 @@ fn build_search_result_item(
         file,
         title: result.title,
         score: result.score,
+        rerank_score: result.rerank_score,
         context,
         snippet,
         line_count,
         byte_count,
+        passages,
+        explain: result.explain.map(ResultExplainItem::from),
+    }
+}
+
+/// Parse the optional `group` tool parameter.
+fn passage_grouping(
+    group: Option<&str>,
+) -> Result<search::PassageGrouping, rmcp::ErrorData> {
+    match group {
+        None => Ok(search::PassageGrouping::default()),
+        Some(value) => search::PassageGrouping::parse(value).ok_or_else(|| {
+            rmcp::ErrorData::invalid_params(
+                format!(
+                    "invalid group {value:?}: expected \"document\" or \"passage\""
+                ),
+                None,
+            )
+        }),
     }
 }
 
+/// Parse the `cursor` parameter of a search tool.
+fn search_cursor(
+    cursor: Option<&str>,
+) -> Result<Option<SearchCursor>, rmcp::ErrorData> {
+    cursor
+        .map(|cursor| {
+            SearchCursor::decode(cursor).map_err(|err| {
+                rmcp::ErrorData::invalid_params(err.to_string(), None)
+            })
+        })
+        .transpose()
+}
+
+/// Collect and check the PLAID parameters of a search tool.
+fn plaid_params(
+    n_probe: Option<usize>,
+    n_candidate_docs: Option<usize>,
+    centroid_score_threshold: Option<f32>,
+    exhaustive: Option<bool>,
+    exact: Option<bool>,
+) -> Result<plaid::PlaidQueryParams, rmcp::ErrorData> {
+    let params = plaid::PlaidQueryParams {
+        n_probe,
+        n_candidate_docs,
+        centroid_score_threshold,
+        exhaustive: exhaustive.unwrap_or(false),
+        exact,
+    };
+    params.validate().map_err(|err| {
+        rmcp::ErrorData::invalid_params(err.to_string(), None)
+    })?;
+    Ok(params)
+}
+
+/// Lay out a search outcome's results per `grouping` and give them
+/// disambiguated doc ids.
+fn finish_outcome(
+    mut outcome: search::SearchOutcome,
+    grouping: search::PassageGrouping,
+    config_db: &ConfigDb,
+) -> search::SearchOutcome {
+    outcome.results =
+        search::group_passages(std::mem::take(&mut outcome.results), grouping);
+    search::disambiguate_doc_ids(&mut outcome.results, config_db);
+    outcome
+}
+
 fn build_search_tool_result(
     config_db: &ConfigDb,
-    results: Vec<search::FinalResult>,
+    outcome: search::SearchOutcome,
     query: String,
     include_snippet: bool,
 ) -> Result<CallToolResult, rmcp::ErrorData> {
-    let items: Vec<_> = results
+    let (summary, response) =
+        build_search_response(config_db, outcome, query, include_snippet);
+    let structured = serde_json::to_value(response)
+        .map_err(|e| mcp_error("failed to serialize search results", e))?;
+
+    Ok(structured_tool_result(summary, structured))
+}
+
+/// The text summary and structured response of one search.
+fn build_search_response(
+    config_db: &ConfigDb,
+    outcome: search::SearchOutcome,
+    query: String,
+    include_snippet: bool,
+) -> (String, SearchResponse) {
+    // Autocorrected results match the corrected query, so snippets are
+    // cut around its terms.
+    let searched = outcome
+        .suggestion
+        .as_ref()
+        .filter(|suggestion| suggestion.applied)
+        .map_or(query.as_str(), |suggestion| suggestion.query.as_str());
+    let items: Vec<_> = outcome
+        .results
         .into_iter()
         .map(|result| {
-            build_search_result_item(config_db, result, &query, include_snippet)
+            build_search_result_item(
+                config_db,
+                result,
+                searched,
+                include_snippet,
+            )
         })
         .collect();
-
-    let summary = format_search_summary(&items, &query);
-    let structured = serde_json::to_value(SearchResponse {
+    let next_cursor = outcome.next_cursor.map(|cursor| cursor.encode());
+
+    let summary = format_search_summary(
+        &items,
+        &query,
+        next_cursor.as_deref(),
+        outcome.suggestion.as_ref(),
+    );
+    let response = SearchResponse {
         query,
         result_count: items.len(),
+        total_count: outcome.total,
         results: items,
-    })
-    .map_err(|e| mcp_error("failed to serialize search results", e))?;
-
-    Ok(structured_tool_result(summary, structured))
+        next_cursor,
+        explain: outcome.explain.map(SearchExplainItem::from),
+        facets: outcome.facets.map(FacetsItem::from),
+        suggestion: outcome.suggestion.map(|suggestion| SuggestionItem {
+            query: suggestion.query,
+            applied: suggestion.applied,
+        }),
+    };
+    (summary, response)
 }
 
 #[tool_router(router = tool_router)]
@@ -214,6 +341,14 @@ impl DocbertMcpServer {
     ) -> Result<CallToolResult, rmcp::ErrorData> {
         let params = params.0;
         let query = params.query.clone();
+        let grouping = passage_grouping(params.group.as_deref())?;
+        let plaid = plaid_params(
+            params.n_probe,
+            params.n_candidate_docs,
+            params.centroid_score_threshold,
+            params.exhaustive,
+            params.exact,
+        )?;
 
         let args = search::SearchParams {
             query: params.query,
@@ -223,6 +358,25 @@ impl DocbertMcpServer {
             min_score: params.min_score.unwrap_or(0.0),
             bm25_only: params.bm25_only.unwrap_or(false),
             no_fuzzy: params.no_fuzzy.unwrap_or(false),
+            passages: search::passage_count(params.passages, grouping),
+            explain: params.explain.unwrap_or(false),
+            prf: params.prf.unwrap_or(false).then(|| {
+                let defaults = search::PrfParams::default();
+                search::PrfParams {
+                    feedback_docs: params
+                        .prf_docs
+                        .unwrap_or(defaults.feedback_docs),
+                    expansion_terms: params
+                        .prf_terms
+                        .unwrap_or(defaults.expansion_terms),
+                    weight: params.prf_weight.unwrap_or(defaults.weight),
+                }
+            }),
+            facets: params.facets.unwrap_or(false),
+            offset: params.offset.unwrap_or(0),
+            cursor: search_cursor(params.cursor.as_deref())?,
+            autocorrect: params.autocorrect.unwrap_or(false),
+            plaid,
         };
 
         let config_db = self
@@ -233,19 +387,102 @@ impl DocbertMcpServer {
             rmcp::ErrorData::internal_error("model lock poisoned", None)
         })?;
 
-        let mut results = search::run(
+        let outcome = search::run_cached(
             &args,
+            &self.state.search_cache,
             &self.state.search_index,
             &config_db,
             &self.state.data_dir,
             &mut model,
         )
         .map_err(search_error)?;
+        let outcome = finish_outcome(outcome, grouping, &config_db);
+
+        let include_snippet = params.include_snippet.unwrap_or(true);
+        build_search_tool_result(&config_db, outcome, query, include_snippet)
+    }
+
+    /// Run many searches with the same settings in one call.
+    #[tool(
+        name = "docbert_search_batch",
+        description = "Run several search queries with the same settings in one call. Faster than calling docbert_search repeatedly: the index is loaded once and all queries are encoded together."
+    )]
+    pub async fn docbert_search_batch(
+        &self,
+        params: Parameters<BatchSearchParams>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let params = params.0;
+        let grouping = passage_grouping(params.group.as_deref())?;
+        let plaid = plaid_params(
+            params.n_probe,
+            params.n_candidate_docs,
+            params.centroid_score_threshold,
+            params.exhaustive,
+            params.exact,
+        )?;
+
+        let args: Vec<search::SearchParams> = params
+            .queries
+            .iter()
+            .map(|query| search::SearchParams {
+                query: query.clone(),
+                count: params.limit.unwrap_or(DEFAULT_SEARCH_LIMIT),
+                collection: params.collection.clone(),
+                all: false,
+                min_score: params.min_score.unwrap_or(0.0),
+                bm25_only: params.bm25_only.unwrap_or(false),
+                no_fuzzy: params.no_fuzzy.unwrap_or(false),
+                passages: search::passage_count(params.passages, grouping),
+                explain: params.explain.unwrap_or(false),
+                prf: None,
+                facets: params.facets.unwrap_or(false),
+                offset: 0,
+                cursor: None,
+                autocorrect: params.autocorrect.unwrap_or(false),
+                plaid,
+            })
+            .collect();
+
+        let config_db = self
+            .state
+            .open_config_db()
+            .map_err(|e| mcp_error("failed to open config db", e))?;
+        let mut model = self.state.model.lock().map_err(|_| {
+            rmcp::ErrorData::internal_error("model lock poisoned", None)
+        })?;
 
-        search::disambiguate_doc_ids(&mut results, &config_db);
+        let outcomes = search::run_batch(
+            &args,
+            &self.state.search_index,
+            &config_db,
+            &self.state.data_dir,
+            &mut model,
+        )
+        .map_err(search_error)?;
+        drop(model);
 
         let include_snippet = params.include_snippet.unwrap_or(true);
-        build_search_tool_result(&config_db, results, query, include_snippet)
+        let (summaries, searches): (Vec<_>, Vec<_>) = params
+            .queries
+            .into_iter()
+            .zip(outcomes)
+            .map(|(query, outcome)| {
+                let outcome = finish_outcome(outcome, grouping, &config_db);
+                build_search_response(
+                    &config_db,
+                    outcome,
+                    query,
+                    include_snippet,
+                )
+            })
+            .unzip();
+
+        let structured = serde_json::to_value(BatchSearchResponse {
+            query_count: searches.len(),
+            searches,
+        })
+        .map_err(|e| mcp_error("failed to serialize search results", e))?;
+        Ok(structured_tool_result(summaries.join("\n\n"), structured))
     }
 
     /// Semantic-only search across all indexed documents.
@@ -259,6 +496,14 @@ impl DocbertMcpServer {
     ) -> Result<CallToolResult, rmcp::ErrorData> {
         let params = params.0;
         let query = params.query.clone();
+        let grouping = passage_grouping(params.group.as_deref())?;
+        let plaid = plaid_params(
+            params.n_probe,
+            params.n_candidate_docs,
+            params.centroid_score_threshold,
+            params.exhaustive,
+            params.exact,
+        )?;
 
         let args = search::SemanticSearchParams {
             query: params.query,
@@ -266,6 +511,12 @@ impl DocbertMcpServer {
             count: params.limit.unwrap_or(DEFAULT_SEARCH_LIMIT),
             all: params.all.unwrap_or(false),
             min_score: params.min_score.unwrap_or(0.0),
+            passages: search::passage_count(params.passages, grouping),
+            explain: params.explain.unwrap_or(false),
+            facets: params.facets.unwrap_or(false),
+            offset: params.offset.unwrap_or(0),
+            cursor: search_cursor(params.cursor.as_deref())?,
+            plaid,
         };
 
         let config_db = self
@@ -276,18 +527,291 @@ impl DocbertMcpServer {
             rmcp::ErrorData::internal_error("model lock poisoned", None)
         })?;
 
-        let mut results = search::semantic(
+        let outcome = search::semantic_cached(
             &args,
+            &self.state.search_cache,
             &config_db,
             &self.state.data_dir,
             &mut model,
         )
         .map_err(search_error)?;
+        let outcome = finish_outcome(outcome, grouping, &config_db);
+
+        let include_snippet = params.include_snippet.unwrap_or(true);
+        build_search_tool_result(&config_db, outcome, query, include_snippet)
+    }
+
+    /// Find documents similar to an indexed document.
+    #[tool(
+        name = "docbert_similar",
+        description = "Find documents similar to an indexed document (collection:path, #doc_id, or path), using its ColBERT token embeddings fused with a keyword MoreLikeThis query."
+    )]
+    pub async fn docbert_similar(
+        &self,
+        params: Parameters<SimilarParams>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let params = params.0;
+        let grouping = passage_grouping(params.group.as_deref())?;
+
+        let args = search::SimilarParams {
+            reference: params.reference.clone(),
+            count: params.limit.unwrap_or(DEFAULT_SEARCH_LIMIT),
+            collection: params.collection,
+            passages: search::passage_count(params.passages, grouping),
+        };
+
+        let config_db = self
+            .state
+            .open_config_db()
+            .map_err(|e| mcp_error("failed to open config db", e))?;
 
-        search::disambiguate_doc_ids(&mut results, &config_db);
+        let results = search::similar(
+            &args,
+            &self.state.search_index,
+            &config_db,
+            &self.state.data_dir,
+        )
+        .map_err(|err| match err {
+            docbert_core::Error::NotFound { .. } => {
+                rmcp::ErrorData::resource_not_found(err.to_string(), None)
+            }
+            other => search_error(other),
+        })?;
+
+        let outcome = search::SearchOutcome {
+            total: results.len(),
+            results,
+            explain: None,
+            facets: None,
+            next_cursor: None,
+            suggestion: None,
+        };
+        let outcome = finish_outcome(outcome, grouping, &config_db);
 
         let include_snippet = params.include_snippet.unwrap_or(true);
-        build_search_tool_result(&config_db, results, query, include_snippet)
+        build_search_tool_result(
+            &config_db,
+            outcome,
+            params.reference,
+            include_snippet,
+        )
+    }
+
+    /// List every line matching a regular expression or literal string.
+    #[tool(
+        name = "docbert_grep",
+        description = "List every line matching a regular expression (or a literal string with fixedStrings) across the collections, with line numbers and surrounding lines. Use it for exact occurrences; use docbert_search for ranked, meaning-based results."
+    )]
+    pub async fn docbert_grep(
+        &self,
+        params: Parameters<GrepParams>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let params = params.0;
+        let args = grep::GrepParams {
+            pattern: params.pattern.clone(),
+            fixed_strings: params.fixed_strings.unwrap_or(false),
+            ignore_case: params.ignore_case.unwrap_or(false),
+            collection: params.collection,
+            context: params.context.unwrap_or(grep::DEFAULT_CONTEXT_LINES),
+            max_matches: params
+                .max_matches
+                .unwrap_or(grep::DEFAULT_MAX_MATCHES),
+            prefilter: params.prefilter.unwrap_or(false),
+        };
+
+        let config_db = self
+            .state
+            .open_config_db()
+            .map_err(|e| mcp_error("failed to open config db", e))?;
+        let outcome = grep::run(&args, &config_db, &self.state.search_index)
+            .map_err(|err| match err {
+                docbert_core::Error::InvalidPattern(_) => {
+                    rmcp::ErrorData::invalid_params(err.to_string(), None)
+                }
+                docbert_core::Error::NotFound { .. } => {
+                    rmcp::ErrorData::resource_not_found(err.to_string(), None)
+                }
+                other => mcp_error("grep failed", other),
+            })?;
+
+        let mut lines = vec![format!(
+            "Found {} matching line(s) for {:?} in {} file(s) searched",
+            outcome.matches.len(),
+            params.pattern,
+            outcome.files_searched
+        )];
+        if outcome.truncated {
+            lines.push(format!(
+                "Stopped after {} matches; raise maxMatches to see more",
+                args.max_matches
+            ));
+        }
+        lines.extend(grep::format_lines(&outcome.matches));
+
+        let response = GrepResponse {
+            pattern: params.pattern,
+            match_count: outcome.matches.len(),
+            files_searched: outcome.files_searched,
+            files_skipped: outcome.files_skipped,
+            truncated: outcome.truncated,
+            matches: outcome
+                .matches
+                .into_iter()
+                .map(|found| GrepMatchItem {
+                    reference: format!("{}:{}", found.collection, found.path),
+                    collection: found.collection,
+                    path: found.path,
+                    line_number: found.line_number,
+                    column: found.column,
+                    line: found.line,
+                    before: found.before,
+                    after: found.after,
+                })
+                .collect(),
+        };
+        let structured = serde_json::to_value(response)
+            .map_err(|e| mcp_error("failed to serialize grep matches", e))?;
+        Ok(structured_tool_result(lines.join("\n"), structured))
+    }
+
+    /// Rank the passages of one document against a query.
+    #[tool(
+        name = "docbert_search_document",
+        description = "Find the parts of one document (collection:path, #doc_id, or path) about a query. Scores every chunk of the document with ColBERT and returns the best passages with their text and line and byte ranges, so long documents don't have to be fetched whole with docbert_get."
+    )]
+    pub async fn docbert_search_document(
+        &self,
+        params: Parameters<SearchDocumentParams>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let params = params.0;
+        let args = search::WithinParams {
+            reference: params.reference,
+            query: params.query.clone(),
+            count: params.limit.unwrap_or(search::DEFAULT_WITHIN_COUNT),
+            min_score: params.min_score.unwrap_or(0.0),
+        };
+
+        let config_db = self
+            .state
+            .open_config_db()
+            .map_err(|e| mcp_error("failed to open config db", e))?;
+        let mut model = self.state.model.lock().map_err(|_| {
+            rmcp::ErrorData::internal_error("model lock poisoned", None)
+        })?;
+        let outcome =
+            search::within(&args, &config_db, &self.state.data_dir, &mut model)
+                .map_err(|err| match err {
+                    docbert_core::Error::NotFound { .. } => {
+                        rmcp::ErrorData::resource_not_found(
+                            err.to_string(),
+                            None,
+                        )
+                    }
+                    other => search_error(other),
+                })?;
+
+        let reference = format!("{}:{}", outcome.collection, outcome.path);
+        let mut lines = vec![format!(
+            "Found {} passage(s) for {:?} in {reference} ({} chunk(s) scored)",
+            outcome.passages.len(),
+            params.query,
+            outcome.chunks_scored
+        )];
+        let mut passages = Vec::with_capacity(outcome.passages.len());
+        for passage in outcome.passages {
+            let Some(end_byte) = passage.byte_offset().inclusive_end() else {
+                continue;
+            };
+            lines.push(format!(
+                "\n{}. [{:.3}] lines {}-{}, bytes {}-{end_byte}\n{}",
+                passage.rank,
+                passage.score,
+                passage.start_line,
+                passage.end_line,
+                passage.start_byte,
+                text::add_line_numbers(&passage.text, passage.start_line)
+            ));
+            passages.push(DocumentPassageItem {
+                rank: passage.rank,
+                score: passage.score,
+                start_line: passage.start_line,
+                end_line: passage.end_line,
+                start_byte: passage.start_byte,
+                end_byte,
+                text: passage.text,
+            });
+        }
+
+        let response = SearchDocumentResponse {
+            query: params.query,
+            reference,
+            collection: outcome.collection,
+            path: outcome.path,
+            chunks_scored: outcome.chunks_scored,
+            passages,
+        };
+        let structured = serde_json::to_value(response).map_err(|e| {
+            mcp_error("failed to serialize document passages", e)
+        })?;
+        Ok(structured_tool_result(lines.join("\n"), structured))
+    }
+
+    /// Report documents that newly entered saved searches.
+    #[tool(
+        name = "docbert_saved_new",
+        description = "List documents that entered the top results of the user's saved searches (managed with `docbert saved`) since the hits were last cleared. Saved searches are re-run after every sync and ingest. Set clear to forget the returned hits."
+    )]
+    pub async fn docbert_saved_new(
+        &self,
+        params: Parameters<SavedNewParams>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let params = params.0;
+        let config_db = self
+            .state
+            .open_config_db()
+            .map_err(|e| mcp_error("failed to open config db", e))?;
+        let not_found = |err: docbert_core::Error| match err {
+            docbert_core::Error::NotFound { .. } => {
+                rmcp::ErrorData::resource_not_found(err.to_string(), None)
+            }
+            other => mcp_error("failed to read saved searches", other),
+        };
+        let hits = saved_search::new_hits(&config_db, params.search.as_deref())
+            .map_err(not_found)?;
+        if params.clear.unwrap_or(false) {
+            saved_search::clear_new_hits(&config_db, params.search.as_deref())
+                .map_err(not_found)?;
+        }
+
+        let mut lines =
+            vec![format!("Found {} new saved search result(s)", hits.len())];
+        for hit in &hits {
+            lines.push(format!(
+                "{}: {}:{} ({})",
+                hit.search, hit.collection, hit.path, hit.title
+            ));
+        }
+
+        let response = SavedNewResponse {
+            hit_count: hits.len(),
+            hits: hits
+                .into_iter()
+                .map(|hit| SavedHitItem {
+                    search: hit.search,
+                    reference: format!("{}:{}", hit.collection, hit.path),
+                    collection: hit.collection,
+                    path: hit.path,
+                    title: hit.title,
+                    rank: hit.rank,
+                    score: hit.score,
+                    found_at: hit.found_at,
+                })
+                .collect(),
+        };
+        let structured = serde_json::to_value(response).map_err(|e| {
+            mcp_error("failed to serialize saved search hits", e)
+        })?;
+        Ok(structured_tool_result(lines.join("\n"), structured))
     }
 
     /// Retrieve a document by reference (collection:path, #doc_id, or path).
@@ -548,7 +1072,12 @@ docbert indexes local document collections and provides MCP tools for search and
 ## Tools
 
 - docbert_search: keyword + semantic search (use collection filters when possible)
+- docbert_search_batch: run several docbert_search queries in one call
 - semantic_search: ColBERT-only search across all documents
+- docbert_similar: documents similar to one you already have
+- docbert_grep: every line matching a regex or literal, with context
+- docbert_search_document: the passages of one document that match a query
+- docbert_saved_new: documents that newly match the user's saved searches
 - docbert_get: fetch a single document by path or #doc_id
 - docbert_multi_get: fetch multiple documents by glob pattern
 - docbert_status: index health and collection summary
@@ -557,6 +1086,8 @@ docbert indexes local document collections and provides MCP tools for search and
 
 - Use min_score to filter low-confidence results
 - Use bm25_only for fast keyword-only search
+- If the summary says "Did you mean ...", retry with that query or set autocorrect
+- In a long document, find the relevant parts with docbert_search_document before reading it with docbert_get
 - docbert_get supports startLine/endLine or startByte/endByte (inclusive) and optional line numbers
 "#,
         )]
@@ -642,6 +1173,101 @@ pub struct SearchParams {
     pub all: Option<bool>,
     /// Include a snippet preview (default: true).
     pub include_snippet: Option<bool>,
+    /// Attach up to this many matching passages per document (default: 0,
+    /// or 3 when `group` is "passage").
+    pub passages: Option<usize>,
+    /// "document" (default) for one result per document with its
+    /// passages, or "passage" for a flat list of passages.
+    pub group: Option<String>,
+    /// Report each result's per-leg rank, score and RRF contribution,
+    /// plus stage timings and dropped candidates (default: false).
+    pub explain: Option<bool>,
+    /// Expand the query from the top results of a first pass and search
+    /// again (pseudo-relevance feedback, default: false).
+    pub prf: Option<bool>,
+    /// Number of first-pass results used as feedback (default: 3).
+    pub prf_docs: Option<usize>,
+    /// Number of expansion terms and embeddings (default: 10).
+    pub prf_terms: Option<usize>,
+    /// Weight of the expansion against the query (default: 0.5).
+    pub prf_weight: Option<f32>,
+    /// Count the candidate set per collection, extension, year and tag
+    /// (default: false).
+    pub facets: Option<bool>,
+    /// Ranked results to skip before the page starts (default: 0).
+    pub offset: Option<usize>,
+    /// `nextCursor` of an earlier page, to fetch the page after it
+    /// without re-running the search; overrides `offset`.
+    pub cursor: Option<String>,
+    /// Search with the spelling suggestion instead of the query when
+    /// one is offered (default: false).
+    pub autocorrect: Option<bool>,
+    /// PLAID centroids each query token probes (default: picked from
+    /// the result count, or the stored `docbert plaid` setting).
+    pub n_probe: Option<usize>,
+    /// PLAID candidates kept for exact scoring after centroid
+    /// interaction.
+    pub n_candidate_docs: Option<usize>,
+    /// Minimum PLAID centroid score for a centroid to produce
+    /// candidates.
+    pub centroid_score_threshold: Option<f32>,
+    /// Skip PLAID pruning and score every candidate: slower, best
+    /// recall (default: false).
+    pub exhaustive: Option<bool>,
+    /// Score every stored embedding at full precision instead of using
+    /// PLAID (`false` always uses PLAID; default: exact on small
+    /// indexes).
+    pub exact: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchSearchParams {
+    /// Search query strings, each searched with the settings below.
+    pub queries: Vec<String>,
+    /// Maximum number of results per query (default: 10).
+    pub limit: Option<usize>,
+    /// Minimum score threshold.
+    pub min_score: Option<f32>,
+    /// Restrict to a specific collection name.
+    pub collection: Option<String>,
+    /// Skip ColBERT reranking, return BM25 results directly.
+    pub bm25_only: Option<bool>,
+    /// Disable fuzzy matching in the first stage.
+    pub no_fuzzy: Option<bool>,
+    /// Include a snippet preview (default: true).
+    pub include_snippet: Option<bool>,
+    /// Attach up to this many matching passages per document (default: 0,
+    /// or 3 when `group` is "passage").
+    pub passages: Option<usize>,
+    /// "document" (default) for one result per document with its
+    /// passages, or "passage" for a flat list of passages.
+    pub group: Option<String>,
+    /// Report each result's per-leg rank, score and RRF contribution,
+    /// plus stage timings and dropped candidates (default: false).
+    pub explain: Option<bool>,
+    /// Count each query's candidate set per collection, extension, year
+    /// and tag (default: false).
+    pub facets: Option<bool>,
+    /// Search each query with its spelling suggestion instead, when one
+    /// is offered (default: false).
+    pub autocorrect: Option<bool>,
+    /// PLAID centroids each query token probes (default: picked from
+    /// the result count, or the stored `docbert plaid` setting).
+    pub n_probe: Option<usize>,
+    /// PLAID candidates kept for exact scoring after centroid
+    /// interaction.
+    pub n_candidate_docs: Option<usize>,
+    /// Minimum PLAID centroid score for a centroid to produce
+    /// candidates.
+    pub centroid_score_threshold: Option<f32>,
+    /// Skip PLAID pruning and score every candidate: slower, best
+    /// recall (default: false).
+    pub exhaustive: Option<bool>,
+    /// Score every stored embedding at full precision instead of using
+    /// PLAID (`false` always uses PLAID; default: exact on small
+    /// indexes).
+    pub exact: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -657,6 +1283,102 @@ pub struct SemanticSearchParams {
     pub all: Option<bool>,
     /// Include a snippet preview (default: true).
     pub include_snippet: Option<bool>,
+    /// Attach up to this many matching passages per document (default: 0,
+    /// or 3 when `group` is "passage").
+    pub passages: Option<usize>,
+    /// "document" (default) for one result per document with its
+    /// passages, or "passage" for a flat list of passages.
+    pub group: Option<String>,
+    /// Report each result's per-leg rank, score and RRF contribution,
+    /// plus stage timings and dropped candidates (default: false).
+    pub explain: Option<bool>,
+    /// Count the candidate set per collection, extension, year and tag
+    /// (default: false).
+    pub facets: Option<bool>,
+    /// Ranked results to skip before the page starts (default: 0).
+    pub offset: Option<usize>,
+    /// `nextCursor` of an earlier page, to fetch the page after it
+    /// without re-running the search; overrides `offset`.
+    pub cursor: Option<String>,
+    /// PLAID centroids each query token probes (default: picked from
+    /// the result count, or the stored `docbert plaid` setting).
+    pub n_probe: Option<usize>,
+    /// PLAID candidates kept for exact scoring after centroid
+    /// interaction.
+    pub n_candidate_docs: Option<usize>,
+    /// Minimum PLAID centroid score for a centroid to produce
+    /// candidates.
+    pub centroid_score_threshold: Option<f32>,
+    /// Skip PLAID pruning and score every candidate: slower, best
+    /// recall (default: false).
+    pub exhaustive: Option<bool>,
+    /// Score every stored embedding at full precision instead of using
+    /// PLAID (`false` always uses PLAID; default: exact on small
+    /// indexes).
+    pub exact: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SimilarParams {
+    /// Source document reference: collection:path, #doc_id, or path.
+    pub reference: String,
+    /// Maximum number of results (default: 10).
+    pub limit: Option<usize>,
+    /// Only return documents from this collection.
+    pub collection: Option<String>,
+    /// Include a snippet preview (default: true).
+    pub include_snippet: Option<bool>,
+    /// Attach up to this many matching passages per document (default: 0,
+    /// or 3 when `group` is "passage").
+    pub passages: Option<usize>,
+    /// "document" (default) for one result per document with its
+    /// passages, or "passage" for a flat list of passages.
+    pub group: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GrepParams {
+    /// Regular expression matched against each line.
+    pub pattern: String,
+    /// Match the pattern as a literal string (default: false).
+    pub fixed_strings: Option<bool>,
+    /// Match regardless of case (default: false).
+    pub ignore_case: Option<bool>,
+    /// Only search this collection.
+    pub collection: Option<String>,
+    /// Lines of context before and after each match (default: 2).
+    pub context: Option<usize>,
+    /// Stop after this many matching lines (default: 200).
+    pub max_matches: Option<usize>,
+    /// Skip unchanged indexed files the index rules out; literal patterns
+    /// only, and matches inside YAML frontmatter are missed (default:
+    /// false).
+    pub prefilter: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchDocumentParams {
+    /// Document reference: collection:path, #doc_id, or path.
+    pub reference: String,
+    /// What to look for inside the document.
+    pub query: String,
+    /// Maximum number of passages to return (default: 5).
+    pub limit: Option<usize>,
+    /// Drop passages scoring below this (default: 0.0).
+    pub min_score: Option<f32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedNewParams {
+    /// Only this saved search (default: all saved searches).
+    pub search: Option<String>,
+    /// Forget the returned hits so later calls only report newer ones
+    /// (default: false).
+    pub clear: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -772,7 +1494,256 @@ impl RangeSelection {
 struct SearchResponse {
     query: String,
     result_count: usize,
+    /// Ranked results across all pages.
+    total_count: usize,
     results: Vec<SearchResultItem>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_cursor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    explain: Option<SearchExplainItem>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    facets: Option<FacetsItem>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    suggestion: Option<SuggestionItem>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GrepResponse {
+    pattern: String,
+    match_count: usize,
+    files_searched: usize,
+    files_skipped: usize,
+    truncated: bool,
+    matches: Vec<GrepMatchItem>,
+}
+
+/// One matching line with its context.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GrepMatchItem {
+    /// `collection:path`, accepted by `docbert_get`.
+    reference: String,
+    collection: String,
+    path: String,
+    line_number: usize,
+    column: usize,
+    line: String,
+    before: Vec<String>,
+    after: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchDocumentResponse {
+    query: String,
+    /// `collection:path`, accepted by `docbert_get`.
+    reference: String,
+    collection: String,
+    path: String,
+    chunks_scored: usize,
+    passages: Vec<DocumentPassageItem>,
+}
+
+/// One ranked chunk of the searched document. Line and byte ranges are
+/// inclusive and can be passed straight to `docbert_get`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DocumentPassageItem {
+    rank: usize,
+    score: f32,
+    start_line: usize,
+    end_line: usize,
+    start_byte: u64,
+    end_byte: u64,
+    text: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SavedNewResponse {
+    hit_count: usize,
+    hits: Vec<SavedHitItem>,
+}
+
+/// A document that entered a saved search's top results.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SavedHitItem {
+    search: String,
+    /// `collection:path`, accepted by `docbert_get`.
+    reference: String,
+    collection: String,
+    path: String,
+    title: String,
+    rank: usize,
+    score: f32,
+    /// Unix seconds of the refresh that first returned the document.
+    found_at: u64,
+}
+
+/// Corrected spelling of a query with terms the index has never seen.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SuggestionItem {
+    query: String,
+    /// Whether the results are for `query` because `autocorrect` was set.
+    applied: bool,
+}
+
+/// One [`SearchResponse`] per query of a batch, in request order.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BatchSearchResponse {
+    query_count: usize,
+    searches: Vec<SearchResponse>,
+}
+
+/// Candidate counts per facet, each list sorted by count descending.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FacetsItem {
+    total: usize,
+    collections: Vec<FacetCountItem>,
+    extensions: Vec<FacetCountItem>,
+    years: Vec<FacetCountItem>,
+    tags: Vec<FacetCountItem>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FacetCountItem {
+    value: String,
+    count: usize,
+}
+
+impl From<search::Facets> for FacetsItem {
+    fn from(facets: search::Facets) -> Self {
+        let list = |counts: Vec<search::FacetCount>| {
+            counts
+                .into_iter()
+                .map(|c| FacetCountItem {
+                    value: c.value,
+                    count: c.count,
+                })
+                .collect()
+        };
+        Self {
+            total: facets.total,
+            collections: list(facets.collections),
+            extensions: list(facets.extensions),
+            years: list(facets.years),
+            tags: list(facets.tags),
+        }
+    }
+}
+
+/// Query-level breakdown of an explained search.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchExplainItem {
+    timings_ms: StageTimingsItem,
+    bm25_candidates: usize,
+    semantic_candidates: usize,
+    plaid_probed_docs: usize,
+    plaid_decoded_docs: usize,
+    exact_scored_docs: usize,
+    min_score_ignored: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rerank_error: Option<String>,
+    dropped_count: usize,
+    dropped: Vec<DroppedCandidateItem>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StageTimingsItem {
+    query_encoding: f64,
+    plaid_probe: f64,
+    plaid_decode: f64,
+    exact: f64,
+    tantivy: f64,
+    rerank: f64,
+    total: f64,
+}
+
+/// A scored candidate the result limit or `minScore` filtered out.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DroppedCandidateItem {
+    file: String,
+    score: f32,
+    /// `"limit"` or `"min_score"`.
+    reason: &'static str,
+    explain: ResultExplainItem,
+}
+
+/// Where one result sat in each retrieval leg.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ResultExplainItem {
+    bm25: Option<LegHitItem>,
+    semantic: Option<LegHitItem>,
+    fused_rank: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LegHitItem {
+    rank: usize,
+    score: f32,
+    rrf: f32,
+}
+
+impl From<search::ResultExplain> for ResultExplainItem {
+    fn from(explain: search::ResultExplain) -> Self {
+        let leg = |hit: search::LegHit| LegHitItem {
+            rank: hit.rank,
+            score: hit.score,
+            rrf: hit.rrf,
+        };
+        Self {
+            bm25: explain.bm25.map(leg),
+            semantic: explain.semantic.map(leg),
+            fused_rank: explain.fused_rank,
+        }
+    }
+}
+
+impl From<search::SearchExplain> for SearchExplainItem {
+    fn from(explain: search::SearchExplain) -> Self {
+        let millis = |d: std::time::Duration| d.as_secs_f64() * 1000.0;
+        let t = explain.timings;
+        Self {
+            timings_ms: StageTimingsItem {
+                query_encoding: millis(t.query_encoding),
+                plaid_probe: millis(t.plaid_probe),
+                plaid_decode: millis(t.plaid_decode),
+                exact: millis(t.exact),
+                tantivy: millis(t.tantivy),
+                rerank: millis(t.rerank),
+                total: millis(t.total),
+            },
+            bm25_candidates: explain.bm25_candidates,
+            semantic_candidates: explain.semantic_candidates,
+            plaid_probed_docs: explain.plaid_probed_docs,
+            plaid_decoded_docs: explain.plaid_decoded_docs,
+            exact_scored_docs: explain.exact_scored_docs,
+            min_score_ignored: explain.min_score_ignored,
+            rerank_error: explain.rerank_error,
+            dropped_count: explain.dropped_count,
+            dropped: explain
+                .dropped
+                .into_iter()
+                .map(|d| DroppedCandidateItem {
+                    file: format!("{}/{}", d.collection, d.path),
+                    score: d.score,
+                    reason: d.reason.as_str(),
+                    explain: d.explain.into(),
+                })
+                .collect(),
+        }
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -784,10 +1755,26 @@ struct SearchResultItem {
     file: String,
     title: String,
     score: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rerank_score: Option<f32>,
     context: Option<String>,
     snippet: Option<String>,
     line_count: Option<usize>,
     byte_count: Option<u64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    passages: Vec<SearchPassage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    explain: Option<ResultExplainItem>,
+}
+
+/// One matching chunk of a search hit. `startByte`/`endByte` are
+/// inclusive and can be passed straight to `docbert_get`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchPassage {
+    score: f32,
+    start_byte: u64,
+    end_byte: u64,
 }
 
 #[derive(Debug, Serialize)]
@@ -807,12 +1794,29 @@ struct StatusCollection {
     documents: usize,
 }
 
-fn format_search_summary(results: &[SearchResultItem], query: &str) -> String {
+fn format_search_summary(
+    results: &[SearchResultItem],
+    query: &str,
+    next_cursor: Option<&str>,
+    suggestion: Option<&search::QuerySuggestion>,
+) -> String {
+    let hint = suggestion.map(|suggestion| {
+        if suggestion.applied {
+            format!("Showing results for \"{}\"", suggestion.query)
+        } else {
+            format!("Did you mean \"{}\"?", suggestion.query)
+        }
+    });
     if results.is_empty() {
-        return format!("No results found for \"{query}\"");
+        let none = format!("No results found for \"{query}\"");
+        return match hint {
+            Some(hint) => format!("{none}\n{hint}"),
+            None => none,
+        };
     }
 
-    let mut lines = Vec::with_capacity(results.len() + 1);
+    let mut lines = Vec::with_capacity(results.len() + 2);
+    lines.extend(hint);
     let suffix = if results.len() == 1 { "" } else { "s" };
     lines.push(format!(
         "Found {} result{} for \"{query}\":",
@@ -823,6 +1827,9 @@ fn format_search_summary(results: &[SearchResultItem], query: &str) -> String {
     for item in results {
         lines.push(format!("{} {:.3} {}", item.doc_id, item.score, item.file));
     }
+    if let Some(cursor) = next_cursor {
+        lines.push(format!("More results: pass cursor \"{cursor}\""));
+    }
 
     lines.join("\n")
 }
@@ -974,6 +1981,9 @@ fn search_error(err: docbert_core::Error) -> rmcp::ErrorData {
         docbert_core::Error::PlaidIndexMissing => {
             rmcp::ErrorData::internal_error(err.to_string(), None)
         }
+        docbert_core::Error::InvalidCursor(_) => {
+            rmcp::ErrorData::invalid_params(err.to_string(), None)
+        }
         other => mcp_error("search failed", other),
     }
 }
@@ -982,13 +1992,21 @@ fn search_error(err: docbert_core::Error) -> rmcp::ErrorData {
 ///
 /// This blocks until the client disconnects. In practice, it is what
 /// `docbert mcp` calls.
-pub fn run_mcp(data_dir: DataDir, model_id: String) -> error::Result<()> {
+pub fn run_mcp(
+    data_dir: DataDir,
+    model_id: String,
+    reranker_model: Option<String>,
+) -> error::Result<()> {
     let search_index = SearchIndex::open(&data_dir.tantivy_dir()?)?;
 
     let state = DocbertState {
-        data_dir,
+        data_dir: runtime::with_resident_plaid(data_dir),
         search_index,
-        model: Mutex::new(ModelManager::with_model_id(model_id)),
+        model: Mutex::new(
+            ModelManager::with_model_id(model_id)
+                .with_reranker_model(reranker_model),
+        ),
+        search_cache: SearchCache::default(),
     };
 
     let server = DocbertMcpServer::new(state);
@@ -1086,11 +2104,70 @@ mod tests {
             data_dir,
             search_index,
             model: Mutex::new(ModelManager::new()),
+            search_cache: SearchCache::default(),
         });
 
         (server, tmp, doc_ids)
     }
 
+    fn page(results: Vec<search::FinalResult>) -> search::SearchOutcome {
+        search::SearchOutcome {
+            total: results.len(),
+            results,
+            explain: None,
+            facets: None,
+            next_cursor: None,
+            suggestion: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn search_tool_suggests_and_autocorrects_misspelled_queries() {
+        let (server, _tmp, _doc_ids) = build_server(&[(
+            "rust.md",
+            "Rust is fast.\nOwnership keeps memory safe.\n",
+        )]);
+        let params = |autocorrect| SearchParams {
+            query: "ownrship".to_string(),
+            limit: Some(5),
+            min_score: None,
+            collection: None,
+            bm25_only: Some(true),
+            no_fuzzy: Some(true),
+            all: None,
+            include_snippet: Some(false),
+            passages: None,
+            group: None,
+            explain: None,
+            prf: None,
+            prf_docs: None,
+            prf_terms: None,
+            prf_weight: None,
+            facets: None,
+            offset: None,
+            cursor: None,
+            autocorrect: Some(autocorrect),
+            n_probe: None,
+            n_candidate_docs: None,
+            centroid_score_threshold: None,
+            exhaustive: None,
+            exact: None,
+        };
+
+        let plain = server.docbert_search(Parameters(params(false))).await;
+        let structured = plain.unwrap().structured_content.unwrap();
+        assert_eq!(structured["resultCount"], 0);
+        assert_eq!(
+            structured["suggestion"],
+            serde_json::json!({ "query": "ownership", "applied": false })
+        );
+
+        let corrected = server.docbert_search(Parameters(params(true))).await;
+        let structured = corrected.unwrap().structured_content.unwrap();
+        assert_eq!(structured["resultCount"], 1);
+        assert_eq!(structured["suggestion"]["applied"], true);
+    }
+
     #[tokio::test]
     async fn search_tool_returns_structured_results() {
         let (server, _tmp, _doc_ids) = build_server(&[(
@@ -1107,6 +2184,22 @@ mod tests {
             no_fuzzy: Some(true),
             all: Some(false),
             include_snippet: Some(true),
+            passages: None,
+            group: None,
+            explain: None,
+            prf: None,
+            prf_docs: None,
+            prf_terms: None,
+            prf_weight: None,
+            facets: None,
+            offset: None,
+            cursor: None,
+            autocorrect: None,
+            n_probe: None,
+            n_candidate_docs: None,
+            centroid_score_threshold: None,
+            exhaustive: None,
+            exact: None,
         };
 
         let result = server.docbert_search(Parameters(params)).await.unwrap();
@@ -1142,6 +2235,97 @@ mod tests {
         assert!(summary.contains("Found 1 result"));
     }
 
+    #[tokio::test]
+    async fn batch_search_tool_answers_each_query_in_order() {
+        let (server, _tmp, _doc_ids) = build_server(&[
+            ("rust.md", "Rust is fast.\nOwnership keeps memory safe.\n"),
+            ("go.md", "Go has goroutines.\n"),
+        ]);
+
+        let params = BatchSearchParams {
+            queries: vec!["goroutines".to_string(), "ownership".to_string()],
+            limit: Some(5),
+            min_score: None,
+            collection: None,
+            bm25_only: Some(true),
+            no_fuzzy: Some(true),
+            include_snippet: Some(false),
+            passages: None,
+            group: None,
+            explain: None,
+            facets: None,
+            autocorrect: None,
+            n_probe: None,
+            n_candidate_docs: None,
+            centroid_score_threshold: None,
+            exhaustive: None,
+            exact: None,
+        };
+
+        let result = server
+            .docbert_search_batch(Parameters(params))
+            .await
+            .unwrap();
+
+        let structured = result.structured_content.expect("structured");
+        assert_eq!(structured["queryCount"], 2);
+        let searches = structured["searches"].as_array().expect("searches");
+        assert_eq!(searches[0]["query"], "goroutines");
+        assert_eq!(searches[0]["results"][0]["path"], "go.md");
+        assert_eq!(searches[1]["query"], "ownership");
+        assert_eq!(searches[1]["results"][0]["path"], "rust.md");
+    }
+
+    #[tokio::test]
+    async fn search_tool_explain_reports_leg_ranks_and_timings() {
+        let (server, _tmp, _doc_ids) = build_server(&[
+            ("rust.md", "Rust is fast.\n"),
+            ("more.md", "Rust Rust ownership.\n"),
+        ]);
+
+        let params = SearchParams {
+            query: "Rust".to_string(),
+            limit: Some(1),
+            min_score: None,
+            collection: None,
+            bm25_only: Some(true),
+            no_fuzzy: Some(true),
+            all: None,
+            include_snippet: Some(false),
+            passages: None,
+            group: None,
+            explain: Some(true),
+            prf: None,
+            prf_docs: None,
+            prf_terms: None,
+            prf_weight: None,
+            facets: None,
+            offset: None,
+            cursor: None,
+            autocorrect: None,
+            n_probe: None,
+            n_candidate_docs: None,
+            centroid_score_threshold: None,
+            exhaustive: None,
+            exact: None,
+        };
+
+        let result = server.docbert_search(Parameters(params)).await.unwrap();
+        let structured = result.structured_content.expect("structured");
+
+        let first = &structured["results"][0];
+        assert_eq!(first["explain"]["bm25"]["rank"], 1);
+        assert!(first["explain"]["semantic"].is_null());
+        assert_eq!(first["explain"]["fusedRank"], 1);
+
+        let explain = &structured["explain"];
+        assert!(explain["timingsMs"]["total"].is_number());
+        assert_eq!(explain["bm25Candidates"], 2);
+        assert_eq!(explain["droppedCount"], 1);
+        assert_eq!(explain["dropped"][0]["reason"], "limit");
+        assert_eq!(explain["dropped"][0]["explain"]["bm25"]["rank"], 2);
+    }
+
     #[tokio::test]
     async fn semantic_search_without_plaid_index_returns_actionable_error() {
         // Without a prebuilt PLAID index (fresh data dir), semantic_search
@@ -1155,6 +2339,17 @@ mod tests {
             min_score: Some(0.0),
             all: Some(false),
             include_snippet: Some(false),
+            passages: None,
+            group: None,
+            explain: None,
+            facets: None,
+            offset: None,
+            cursor: None,
+            n_probe: None,
+            n_candidate_docs: None,
+            centroid_score_threshold: None,
+            exhaustive: None,
+            exact: None,
         };
 
         let err = server
@@ -1168,6 +2363,217 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn semantic_search_rejects_a_zero_n_probe() {
+        let (server, _tmp, _doc_ids) = build_server(&[]);
+
+        let params = SemanticSearchParams {
+            query: "anything".to_string(),
+            limit: None,
+            min_score: None,
+            all: None,
+            include_snippet: None,
+            passages: None,
+            group: None,
+            explain: None,
+            facets: None,
+            offset: None,
+            cursor: None,
+            n_probe: Some(0),
+            n_candidate_docs: None,
+            centroid_score_threshold: None,
+            exhaustive: None,
+            exact: None,
+        };
+
+        let err = server
+            .semantic_search(Parameters(params))
+            .await
+            .expect_err("expected invalid_params");
+        assert_eq!(err.code, rmcp::model::ErrorCode::INVALID_PARAMS);
+        assert!(err.message.contains("n_probe"), "{}", err.message);
+    }
+
+    #[tokio::test]
+    async fn grep_tool_returns_numbered_lines() {
+        let (server, _tmp, _doc_ids) = build_server(&[
+            ("rust.md", "Intro\nunsafe blocks\nOutro\n"),
+            ("go.md", "No match here.\n"),
+        ]);
+        let params = |pattern: &str| GrepParams {
+            pattern: pattern.to_string(),
+            fixed_strings: None,
+            ignore_case: Some(true),
+            collection: None,
+            context: Some(1),
+            max_matches: None,
+            prefilter: None,
+        };
+
+        let result = server
+            .docbert_grep(Parameters(params("UNSAFE")))
+            .await
+            .unwrap();
+        let structured = result.structured_content.unwrap();
+        assert_eq!(structured["matchCount"], 1);
+        assert_eq!(structured["filesSearched"], 2);
+        assert_eq!(structured["matches"][0]["reference"], "notes:rust.md");
+        assert_eq!(structured["matches"][0]["lineNumber"], 2);
+        assert_eq!(structured["matches"][0]["before"][0], "Intro");
+        let summary = format!("{:?}", result.content[0]);
+        assert!(
+            summary.contains("notes:rust.md:2:unsafe blocks"),
+            "{summary}"
+        );
+
+        let err = server
+            .docbert_grep(Parameters(params("(")))
+            .await
+            .expect_err("expected invalid_params");
+        assert_eq!(err.code, rmcp::model::ErrorCode::INVALID_PARAMS);
+    }
+
+    #[tokio::test]
+    async fn search_document_tool_reports_passages_and_unknown_references() {
+        let (server, _tmp, doc_ids) = build_server(&[("rust.md", "Rust.\n")]);
+        let params = |reference: &str| SearchDocumentParams {
+            reference: reference.to_string(),
+            query: "ownership".to_string(),
+            limit: None,
+            min_score: None,
+        };
+
+        // Without a chunk manifest there is nothing to score, so the
+        // model is never loaded.
+        let result = server
+            .docbert_search_document(Parameters(params(
+                &doc_ids[0].to_string(),
+            )))
+            .await
+            .unwrap();
+        let structured = result.structured_content.unwrap();
+        assert_eq!(structured["reference"], "notes:rust.md");
+        assert_eq!(structured["chunksScored"], 0);
+        assert_eq!(structured["passages"], serde_json::json!([]));
+
+        let err = server
+            .docbert_search_document(Parameters(params("notes:missing.md")))
+            .await
+            .expect_err("expected resource_not_found");
+        assert_eq!(err.code, rmcp::model::ErrorCode::RESOURCE_NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn saved_new_tool_lists_and_clears_hits() {
+        let (server, _tmp, _doc_ids) =
+            build_server(&[("borrow.md", "The borrow checker.\n")]);
+        let config_db = server.state.open_config_db().unwrap();
+        let mut saved = saved_search::SavedSearch::new(
+            "borrow",
+            "borrow",
+            search::SearchMode::Bm25,
+            None,
+            10,
+        )
+        .unwrap();
+        // An empty baseline makes every current result a new hit.
+        saved_search::record(&mut saved, &[], 1);
+        config_db.set_saved_search(&saved).unwrap();
+        {
+            let mut model = server.state.model.lock().unwrap();
+            saved_search::refresh(
+                &server.state.search_index,
+                &config_db,
+                &server.state.data_dir,
+                &mut model,
+            )
+            .unwrap();
+        }
+
+        let params = |search: Option<&str>, clear| SavedNewParams {
+            search: search.map(str::to_string),
+            clear: Some(clear),
+        };
+        let result = server
+            .docbert_saved_new(Parameters(params(None, true)))
+            .await
+            .unwrap();
+        let structured = result.structured_content.unwrap();
+        assert_eq!(structured["hitCount"], 1);
+        assert_eq!(structured["hits"][0]["reference"], "notes:borrow.md");
+
+        let result = server
+            .docbert_saved_new(Parameters(params(Some("borrow"), false)))
+            .await
+            .unwrap();
+        assert_eq!(result.structured_content.unwrap()["hitCount"], 0);
+
+        let err = server
+            .docbert_saved_new(Parameters(params(Some("missing"), false)))
+            .await
+            .expect_err("expected resource_not_found");
+        assert_eq!(err.code, rmcp::model::ErrorCode::RESOURCE_NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn similar_tool_reports_unknown_reference_as_not_found() {
+        let (server, _tmp, _doc_ids) = build_server(&[("rust.md", "Rust.\n")]);
+
+        let params = SimilarParams {
+            reference: "notes:missing.md".to_string(),
+            limit: None,
+            collection: None,
+            include_snippet: None,
+            passages: None,
+            group: None,
+        };
+
+        let err = server
+            .docbert_similar(Parameters(params))
+            .await
+            .expect_err("expected resource_not_found");
+        assert_eq!(err.code, rmcp::model::ErrorCode::RESOURCE_NOT_FOUND);
+        assert!(err.message.contains("missing.md"), "{}", err.message);
+    }
+
+    #[tokio::test]
+    async fn search_tool_rejects_unknown_group() {
+        let (server, _tmp, _doc_ids) = build_server(&[]);
+
+        let params = SearchParams {
+            query: "Rust".to_string(),
+            limit: None,
+            min_score: None,
+            collection: None,
+            bm25_only: Some(true),
+            no_fuzzy: None,
+            all: None,
+            include_snippet: None,
+            passages: None,
+            group: Some("chunk".to_string()),
+            explain: None,
+            prf: None,
+            prf_docs: None,
+            prf_terms: None,
+            prf_weight: None,
+            facets: None,
+            offset: None,
+            cursor: None,
+            autocorrect: None,
+            n_probe: None,
+            n_candidate_docs: None,
+            centroid_score_threshold: None,
+            exhaustive: None,
+            exact: None,
+        };
+
+        let err = server
+            .docbert_search(Parameters(params))
+            .await
+            .expect_err("expected invalid_params error");
+        assert!(err.message.contains("invalid group"), "{}", err.message);
+    }
+
     #[test]
     fn semantic_search_tool_payload_snapshot() {
         let (server, _tmp, doc_ids) = build_server(&[(
@@ -1178,7 +2584,7 @@ mod tests {
 
         let result = build_search_tool_result(
             &server.state.open_config_db().unwrap(),
-            vec![search::FinalResult {
+            page(vec![search::FinalResult {
                 rank: 1,
                 score: 1.25,
                 doc_id: doc_id.short.clone(),
@@ -1187,7 +2593,11 @@ mod tests {
                 path: "rust.md".to_string(),
                 title: "rust.md".to_string(),
                 best_chunk_doc_id: None,
-            }],
+                rerank_score: None,
+                passages: Vec::new(),
+                token_matches: Vec::new(),
+                explain: None,
+            }]),
             "Rust".to_string(),
             true,
         )
@@ -1213,6 +2623,7 @@ mod tests {
             json!({
                 "query": "Rust",
                 "resultCount": 1,
+                "totalCount": 1,
                 "results": [{
                     "docId": format!("#{}", doc_id.short),
                     "collection": "notes",
@@ -1242,7 +2653,7 @@ mod tests {
 
         let result = build_search_tool_result(
             &server.state.open_config_db().unwrap(),
-            vec![search::FinalResult {
+            page(vec![search::FinalResult {
                 rank: 1,
                 score: 1.0,
                 doc_id: doc_id.short.clone(),
@@ -1251,7 +2662,11 @@ mod tests {
                 path: "rust.md".to_string(),
                 title: "rust.md".to_string(),
                 best_chunk_doc_id: None,
-            }],
+                rerank_score: None,
+                passages: Vec::new(),
+                token_matches: Vec::new(),
+                explain: None,
+            }]),
             "Rust".to_string(),
             false,
         )
@@ -1274,7 +2689,7 @@ mod tests {
 
         let unprefixed = build_search_tool_result(
             &server.state.open_config_db().unwrap(),
-            vec![search::FinalResult {
+            page(vec![search::FinalResult {
                 rank: 1,
                 score: 1.0,
                 doc_id: doc_id.short.clone(),
@@ -1283,14 +2698,18 @@ mod tests {
                 path: "rust.md".to_string(),
                 title: "rust.md".to_string(),
                 best_chunk_doc_id: None,
-            }],
+                rerank_score: None,
+                passages: Vec::new(),
+                token_matches: Vec::new(),
+                explain: None,
+            }]),
             "Rust".to_string(),
             false,
         )
         .unwrap();
         let prefixed = build_search_tool_result(
             &server.state.open_config_db().unwrap(),
-            vec![search::FinalResult {
+            page(vec![search::FinalResult {
                 rank: 1,
                 score: 1.0,
                 doc_id: format!("#{}", doc_id.short),
@@ -1299,7 +2718,11 @@ mod tests {
                 path: "rust.md".to_string(),
                 title: "rust.md".to_string(),
                 best_chunk_doc_id: None,
-            }],
+                rerank_score: None,
+                passages: Vec::new(),
+                token_matches: Vec::new(),
+                explain: None,
+            }]),
             "Rust".to_string(),
             false,
         )
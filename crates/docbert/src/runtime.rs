@@ -1,6 +1,13 @@
-use std::{thread, time::Duration};
+use std::{sync::Arc, thread, time::Duration};
 
-use docbert_core::{ConfigDb, DataDir, EmbeddingDb, SearchIndex, error};
+use docbert_core::{
+    ConfigDb,
+    DataDir,
+    EmbeddingDb,
+    SearchIndex,
+    error,
+    resident_plaid::ResidentPlaid,
+};
 use tantivy::{IndexWriter, TantivyError};
 
 const OPEN_RETRY_DELAY: Duration = Duration::from_millis(50);
@@ -23,6 +30,18 @@ pub(crate) fn open_embedding_db_blocking(
     EmbeddingDb::open(&data_dir.embeddings_db())
 }
 
+/// Attach a [`ResidentPlaid`] to `data_dir` for a long-lived server and
+/// load the current index up front, so the first query doesn't pay for
+/// it. A missing or unreadable index is not fatal here: searches report
+/// it when they need PLAID.
+pub(crate) fn with_resident_plaid(data_dir: DataDir) -> DataDir {
+    let resident = Arc::new(ResidentPlaid::new());
+    if let Err(err) = resident.get(&data_dir) {
+        tracing::warn!(%err, "could not preload the PLAID index");
+    }
+    data_dir.with_resident_plaid(resident)
+}
+
 pub(crate) fn open_index_writer_blocking(
     search_index: &SearchIndex,
     memory_budget: usize,
@@ -64,7 +64,7 @@ pub(crate) fn ingest_prepared_document(
 
     let mut writer = state.open_index_writer_blocking(50_000_000)?;
 
-    state.search_index.add_document(
+    state.search_index.add_tagged_document(
         &writer,
         &document.did.full_hex(),
         document.did.numeric,
@@ -73,6 +73,7 @@ pub(crate) fn ingest_prepared_document(
         &document.title,
         &document.searchable_body,
         document.mtime,
+        &document.tags,
     )?;
 
     // The embedding store is treated as a content-addressed cache: we
@@ -280,7 +281,7 @@ pub(crate) fn rollback_document(
                 } else {
                     String::new()
                 };
-                state.search_index.add_document(
+                state.search_index.add_tagged_document(
                     &writer,
                     &prev.did.full_hex(),
                     prev.did.numeric,
@@ -289,6 +290,9 @@ pub(crate) fn rollback_document(
                     &title,
                     &searchable_body,
                     meta.mtime,
+                    &docbert_core::text::yaml_frontmatter_tags(
+                        &searchable_body,
+                    ),
                 )?;
             }
             writer.commit()?;
@@ -460,7 +464,12 @@ mod tests {
         sync::{Arc, Mutex},
     };
 
-    use docbert_core::{ConfigDb, ModelManager, SearchIndex};
+    use docbert_core::{
+        ConfigDb,
+        ModelManager,
+        SearchIndex,
+        search_cache::SearchCache,
+    };
 
     use super::*;
     use crate::web::state::Inner;
@@ -472,6 +481,8 @@ mod tests {
             search_index: SearchIndex::open_in_ram().unwrap(),
             model: Mutex::new(ModelManager::new()),
             model_id: "test-model".to_string(),
+            search_cache: SearchCache::default(),
+            saved_refresh: Default::default(),
         });
         (tmp, state)
     }
@@ -34,7 +34,12 @@ mod tests {
         http::{Request, StatusCode},
         routing,
     };
-    use docbert_core::{ConfigDb, ModelManager, SearchIndex};
+    use docbert_core::{
+        ConfigDb,
+        ModelManager,
+        SearchIndex,
+        search_cache::SearchCache,
+    };
     use tower::util::ServiceExt;
 
     use super::*;
@@ -47,6 +52,8 @@ mod tests {
             search_index: SearchIndex::open_in_ram().unwrap(),
             model: Mutex::new(ModelManager::new()),
             model_id: "test-model".to_string(),
+            search_cache: SearchCache::default(),
+            saved_refresh: Default::default(),
         });
 
         (tmp, state)
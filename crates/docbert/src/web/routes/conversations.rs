@@ -153,6 +153,7 @@ mod tests {
         ModelManager,
         SearchIndex,
         conversation::{ChatActor, ChatPart, ChatRole},
+        search_cache::SearchCache,
     };
 
     use super::*;
@@ -165,6 +166,8 @@ mod tests {
             search_index: SearchIndex::open_in_ram().unwrap(),
             model: Mutex::new(ModelManager::new()),
             model_id: "test-model".to_string(),
+            search_cache: SearchCache::default(),
+            saved_refresh: Default::default(),
         });
 
         (tmp, state)
@@ -16,6 +16,7 @@ use docbert_core::{
     embedding,
     ingestion,
     preparation::{self, SearchDocument},
+    search,
     text,
 };
 use serde::{Deserialize, Serialize};
@@ -387,6 +388,8 @@ pub(crate) async fn ingest(
         return Err(status);
     }
 
+    super::saved::refresh_after_ingest(&state);
+
     Ok(Json(IngestResponse {
         ingested: ingested.len(),
         documents: ingested,
@@ -504,6 +507,95 @@ pub(crate) async fn get(
     }))
 }
 
+/// Body of `POST /v1/search/document`.
+#[derive(Debug, Deserialize)]
+pub(crate) struct DocumentSearchRequest {
+    pub(crate) collection: String,
+    pub(crate) path: String,
+    pub(crate) query: String,
+    #[serde(default = "default_passage_count")]
+    pub(crate) count: usize,
+    #[serde(default)]
+    pub(crate) min_score: f32,
+}
+
+fn default_passage_count() -> usize {
+    search::DEFAULT_WITHIN_COUNT
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub(crate) struct DocumentSearchResponse {
+    pub(crate) query: String,
+    /// `collection:path`, accepted wherever a document reference is.
+    pub(crate) reference: String,
+    pub(crate) collection: String,
+    pub(crate) path: String,
+    pub(crate) chunks_scored: usize,
+    pub(crate) passages: Vec<DocumentPassageItem>,
+}
+
+/// One ranked chunk of the searched document. Both byte and line
+/// ranges are inclusive, ready for a ranged `GET` of the document.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub(crate) struct DocumentPassageItem {
+    pub(crate) rank: usize,
+    pub(crate) score: f32,
+    pub(crate) start_byte: u64,
+    pub(crate) end_byte: u64,
+    pub(crate) start_line: usize,
+    pub(crate) end_line: usize,
+    pub(crate) text: String,
+}
+
+/// Rank the chunks of one document against a query.
+pub(crate) async fn search(
+    State(state): State<AppState>,
+    Json(body): Json<DocumentSearchRequest>,
+) -> Result<Json<DocumentSearchResponse>, StatusCode> {
+    let config_db = state.open_config_db().map_err(map_error)?;
+    let params = search::WithinParams {
+        reference: format!("{}:{}", body.collection, body.path),
+        query: body.query.clone(),
+        count: body.count,
+        min_score: body.min_score,
+    };
+    // Recover from a poisoned mutex — a prior panic left the lock in
+    // a bad state, but the model data is still intact enough to use.
+    let mut model = state.model.lock().unwrap_or_else(|poisoned| {
+        tracing::warn!("documents::search recovered from poisoned model mutex");
+        poisoned.into_inner()
+    });
+    let outcome =
+        search::within(&params, &config_db, &state.data_dir, &mut model)
+            .map_err(|err| match err {
+                docbert_core::Error::NotFound { .. } => StatusCode::NOT_FOUND,
+                other => log_internal_error(other, "documents::search"),
+            })?;
+
+    Ok(Json(DocumentSearchResponse {
+        query: body.query,
+        reference: params.reference,
+        collection: outcome.collection,
+        path: outcome.path,
+        chunks_scored: outcome.chunks_scored,
+        passages: outcome
+            .passages
+            .into_iter()
+            .filter_map(|passage| {
+                Some(DocumentPassageItem {
+                    rank: passage.rank,
+                    score: passage.score,
+                    start_byte: passage.start_byte,
+                    end_byte: passage.byte_offset().inclusive_end()?,
+                    start_line: passage.start_line,
+                    end_line: passage.end_line,
+                    text: passage.text,
+                })
+            })
+            .collect(),
+    }))
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::{Arc, Mutex as StdMutex, OnceLock};
@@ -514,7 +606,13 @@ mod tests {
         http::{Request, StatusCode},
         routing,
     };
-    use docbert_core::{ConfigDb, ModelManager, SearchIndex, incremental};
+    use docbert_core::{
+        ConfigDb,
+        ModelManager,
+        SearchIndex,
+        incremental,
+        search_cache::SearchCache,
+    };
     use tokio::sync::{Mutex, MutexGuard};
     use tower::util::ServiceExt;
 
@@ -528,6 +626,8 @@ mod tests {
             search_index: SearchIndex::open_in_ram().unwrap(),
             model: StdMutex::new(ModelManager::new()),
             model_id: "test-model".to_string(),
+            search_cache: SearchCache::default(),
+            saved_refresh: Default::default(),
         });
 
         (tmp, state)
@@ -558,6 +658,7 @@ mod tests {
                 "/v1/documents/{collection}/{*path}",
                 routing::get(get).delete(delete),
             )
+            .route("/v1/search/document", routing::post(search))
             .with_state(state)
     }
 
@@ -1045,6 +1146,105 @@ mod tests {
         assert_eq!(item.byte_count, Some(23));
     }
 
+    #[tokio::test]
+    async fn web_documents_search_ranks_passages_with_ranges() {
+        let _guard = env_lock().await;
+        let (tmp, state) = test_state();
+        let root = tmp.path().join("notes");
+        let did = seed_filesystem_document(
+            &state,
+            &root,
+            "notes",
+            "spec.md",
+            "# Spec\n\nCaching\nrules\n",
+        );
+        test_config_db(&state)
+            .set_doc_chunks(
+                did.numeric,
+                &[
+                    DocChunkEntry {
+                        chunk_doc_id: 1,
+                        start_byte: 0,
+                        byte_len: 8,
+                    },
+                    DocChunkEntry {
+                        chunk_doc_id: 2,
+                        start_byte: 8,
+                        byte_len: 14,
+                    },
+                ],
+            )
+            .unwrap();
+        let embedding_db = test_embedding_db(&state);
+        embedding_db.store(1, 1, 2, &[0.0, 1.0]).unwrap();
+        embedding_db.store(2, 1, 2, &[1.0, 0.0]).unwrap();
+
+        let search_request = |path: &str| {
+            Request::builder()
+                .uri("/v1/search/document")
+                .method("POST")
+                .header("content-type", "application/json")
+                .body(Body::from(format!(
+                    r#"{{"collection":"notes","path":"{path}","query":"caching","count":1}}"#
+                )))
+                .unwrap()
+        };
+        unsafe {
+            std::env::set_var(TEST_FAKE_EMBEDDINGS_ENV, "1");
+        }
+        let response = documents_router(state.clone())
+            .oneshot(search_request("spec.md"))
+            .await
+            .unwrap();
+        unsafe {
+            std::env::remove_var(TEST_FAKE_EMBEDDINGS_ENV);
+        }
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let item: DocumentSearchResponse =
+            serde_json::from_slice(&body).unwrap();
+        assert_eq!(item.reference, "notes:spec.md");
+        assert_eq!(item.chunks_scored, 2);
+        assert_eq!(
+            item.passages,
+            vec![DocumentPassageItem {
+                rank: 1,
+                score: 1.0,
+                start_byte: 8,
+                end_byte: 21,
+                start_line: 3,
+                end_line: 4,
+                text: "Caching\nrules\n".to_string(),
+            }]
+        );
+
+        let response = documents_router(state.clone())
+            .oneshot(search_request("missing.md"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        // A document named `search` is read, not searched.
+        seed_filesystem_document(
+            &state,
+            &root,
+            "notes",
+            "spec/search",
+            "# Search\n",
+        );
+        let response = documents_router(state)
+            .oneshot(
+                Request::builder()
+                    .uri("/v1/documents/notes/spec/search")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
     #[tokio::test]
     async fn web_documents_get_slices_by_line_range() {
         // Mirrors the MCP `docbert_get` partial-read contract: passing
@@ -0,0 +1,193 @@
+use axum::{Json, extract::State, http::StatusCode};
+use docbert_core::grep::{self, GrepParams};
+use serde::{Deserialize, Serialize};
+
+use crate::web::{routes::log_internal_error, state::AppState};
+
+/// Body of `POST /v1/grep`.
+#[derive(Debug, Deserialize)]
+pub(crate) struct GrepRequest {
+    pub(crate) pattern: String,
+    /// Match `pattern` literally instead of as a regular expression.
+    #[serde(default)]
+    pub(crate) fixed_strings: bool,
+    #[serde(default)]
+    pub(crate) ignore_case: bool,
+    pub(crate) collection: Option<String>,
+    #[serde(default = "default_context")]
+    pub(crate) context: usize,
+    #[serde(default = "default_max_matches")]
+    pub(crate) max_matches: usize,
+    /// Skip unchanged indexed files the index rules out.
+    #[serde(default)]
+    pub(crate) prefilter: bool,
+}
+
+fn default_context() -> usize {
+    grep::DEFAULT_CONTEXT_LINES
+}
+
+fn default_max_matches() -> usize {
+    grep::DEFAULT_MAX_MATCHES
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub(crate) struct GrepResponse {
+    pub(crate) pattern: String,
+    pub(crate) match_count: usize,
+    pub(crate) files_searched: usize,
+    pub(crate) files_skipped: usize,
+    pub(crate) truncated: bool,
+    pub(crate) matches: Vec<GrepMatchItem>,
+}
+
+/// One matching line with its context.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub(crate) struct GrepMatchItem {
+    /// `collection:path`, accepted wherever a document reference is.
+    pub(crate) reference: String,
+    pub(crate) collection: String,
+    pub(crate) path: String,
+    pub(crate) line_number: usize,
+    pub(crate) column: usize,
+    pub(crate) line: String,
+    pub(crate) before: Vec<String>,
+    pub(crate) after: Vec<String>,
+}
+
+impl From<grep::GrepMatch> for GrepMatchItem {
+    fn from(found: grep::GrepMatch) -> Self {
+        Self {
+            reference: format!("{}:{}", found.collection, found.path),
+            collection: found.collection,
+            path: found.path,
+            line_number: found.line_number,
+            column: found.column,
+            line: found.line,
+            before: found.before,
+            after: found.after,
+        }
+    }
+}
+
+/// Every line of the registered collections matching a pattern, in
+/// collection, path and line order.
+pub(crate) async fn grep(
+    State(state): State<AppState>,
+    Json(body): Json<GrepRequest>,
+) -> Result<Json<GrepResponse>, StatusCode> {
+    let params = GrepParams {
+        pattern: body.pattern.clone(),
+        fixed_strings: body.fixed_strings,
+        ignore_case: body.ignore_case,
+        collection: body.collection,
+        context: body.context,
+        max_matches: body.max_matches,
+        prefilter: body.prefilter,
+    };
+    let config_db = state
+        .open_config_db()
+        .map_err(|err| log_internal_error(err, "grep::grep open config db"))?;
+    let outcome =
+        grep::run(&params, &config_db, &state.search_index).map_err(|err| {
+            match err {
+                docbert_core::Error::InvalidPattern(_) => {
+                    StatusCode::BAD_REQUEST
+                }
+                docbert_core::Error::NotFound { .. } => StatusCode::NOT_FOUND,
+                other => log_internal_error(other, "grep::grep"),
+            }
+        })?;
+
+    Ok(Json(GrepResponse {
+        pattern: body.pattern,
+        match_count: outcome.matches.len(),
+        files_searched: outcome.files_searched,
+        files_skipped: outcome.files_skipped,
+        truncated: outcome.truncated,
+        matches: outcome
+            .matches
+            .into_iter()
+            .map(GrepMatchItem::from)
+            .collect(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use docbert_core::{
+        ConfigDb,
+        ModelManager,
+        SearchIndex,
+        search_cache::SearchCache,
+    };
+
+    use super::*;
+    use crate::web::state::Inner;
+
+    fn request(pattern: &str) -> GrepRequest {
+        GrepRequest {
+            pattern: pattern.to_string(),
+            fixed_strings: false,
+            ignore_case: false,
+            collection: None,
+            context: 1,
+            max_matches: 10,
+            prefilter: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn web_grep_returns_references_and_maps_errors() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().join("notes");
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("a.md"), "intro\nuse unsafe here\nend\n")
+            .unwrap();
+        let state: AppState = Arc::new(Inner {
+            data_dir: docbert_core::DataDir::new(tmp.path()),
+            search_index: SearchIndex::open_in_ram().unwrap(),
+            model: Mutex::new(ModelManager::new()),
+            model_id: "test-model".to_string(),
+            search_cache: SearchCache::default(),
+            saved_refresh: Default::default(),
+        });
+        ConfigDb::open(&state.data_dir.config_db())
+            .unwrap()
+            .set_collection("notes", root.to_str().unwrap())
+            .unwrap();
+
+        let response = grep(State(state.clone()), Json(request("uns.fe")))
+            .await
+            .unwrap();
+        assert_eq!(response.0.match_count, 1);
+        assert_eq!(response.0.files_searched, 1);
+        assert_eq!(
+            response.0.matches,
+            vec![GrepMatchItem {
+                reference: "notes:a.md".to_string(),
+                collection: "notes".to_string(),
+                path: "a.md".to_string(),
+                line_number: 2,
+                column: 5,
+                line: "use unsafe here".to_string(),
+                before: vec!["intro".to_string()],
+                after: vec!["end".to_string()],
+            }]
+        );
+
+        let status = grep(State(state.clone()), Json(request("[")))
+            .await
+            .expect_err("expected 400 for an invalid pattern");
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let mut missing = request("x");
+        missing.collection = Some("nope".to_string());
+        let status = grep(State(state), Json(missing))
+            .await
+            .expect_err("expected 404 for an unknown collection");
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
@@ -5,6 +5,8 @@ use super::{state::AppState, ui};
 pub(crate) mod collections;
 pub(crate) mod conversations;
 pub(crate) mod documents;
+pub(crate) mod grep;
+pub(crate) mod saved;
 pub(crate) mod search;
 pub(crate) mod settings;
 
@@ -57,6 +59,15 @@ pub(crate) fn router() -> Router<AppState> {
             routing::get(documents::get).delete(documents::delete),
         )
         .route("/v1/search", routing::post(search::search))
+        .route("/v1/search/batch", routing::post(search::batch))
+        .route("/v1/search/similar", routing::post(search::similar))
+        .route("/v1/search/document", routing::post(documents::search))
+        .route("/v1/complete", routing::get(search::complete))
+        .route("/v1/grep", routing::post(grep::grep))
+        .route(
+            "/v1/saved/new",
+            routing::get(saved::new_hits).delete(saved::clear_new_hits),
+        )
         .route("/v1/settings/llm", routing::get(settings::get))
         .route("/v1/settings/llm", routing::put(settings::update))
         .route(
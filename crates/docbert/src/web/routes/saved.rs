@@ -0,0 +1,333 @@
+use std::sync::{Mutex, MutexGuard};
+
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::StatusCode,
+};
+use docbert_core::saved_search::{self, SavedSearchHit};
+use serde::{Deserialize, Serialize};
+
+use crate::web::{routes::log_internal_error, state::AppState};
+
+/// Query string of `GET` and `DELETE /v1/saved/new`.
+#[derive(Debug, Deserialize)]
+pub(crate) struct SavedNewQuery {
+    /// Only this saved search; every saved search when absent.
+    pub(crate) search: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub(crate) struct SavedNewResponse {
+    pub(crate) hit_count: usize,
+    pub(crate) hits: Vec<SavedHitItem>,
+}
+
+/// A document that entered a saved search's results.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub(crate) struct SavedHitItem {
+    pub(crate) search: String,
+    /// `collection:path`, accepted wherever a document reference is.
+    pub(crate) reference: String,
+    pub(crate) collection: String,
+    pub(crate) path: String,
+    pub(crate) title: String,
+    pub(crate) rank: usize,
+    pub(crate) score: f32,
+    /// Unix seconds of the refresh that first returned the document.
+    pub(crate) found_at: u64,
+}
+
+impl From<SavedSearchHit> for SavedHitItem {
+    fn from(hit: SavedSearchHit) -> Self {
+        Self {
+            search: hit.search,
+            reference: format!("{}:{}", hit.collection, hit.path),
+            collection: hit.collection,
+            path: hit.path,
+            title: hit.title,
+            rank: hit.rank,
+            score: hit.score,
+            found_at: hit.found_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub(crate) struct SavedClearResponse {
+    pub(crate) cleared: usize,
+}
+
+fn map_error(err: docbert_core::Error) -> StatusCode {
+    match err {
+        docbert_core::Error::NotFound { .. } => StatusCode::NOT_FOUND,
+        other => log_internal_error(other, "saved handler"),
+    }
+}
+
+/// Documents that entered saved searches since their hits were last
+/// cleared.
+pub(crate) async fn new_hits(
+    State(state): State<AppState>,
+    Query(query): Query<SavedNewQuery>,
+) -> Result<Json<SavedNewResponse>, StatusCode> {
+    let config_db = state.open_config_db().map_err(map_error)?;
+    let hits = saved_search::new_hits(&config_db, query.search.as_deref())
+        .map_err(map_error)?;
+
+    Ok(Json(SavedNewResponse {
+        hit_count: hits.len(),
+        hits: hits.into_iter().map(SavedHitItem::from).collect(),
+    }))
+}
+
+/// Forget the waiting hits so the next `GET` only shows later ones.
+pub(crate) async fn clear_new_hits(
+    State(state): State<AppState>,
+    Query(query): Query<SavedNewQuery>,
+) -> Result<Json<SavedClearResponse>, StatusCode> {
+    let config_db = state.open_config_db().map_err(map_error)?;
+    let cleared =
+        saved_search::clear_new_hits(&config_db, query.search.as_deref())
+            .map_err(map_error)?;
+    Ok(Json(SavedClearResponse { cleared }))
+}
+
+/// Coalesces the saved-search refreshes ingests ask for: one runs at a
+/// time, and every request made while it runs folds into a single
+/// follow-up run, since that one already sees all of their changes.
+#[derive(Debug, Default)]
+pub(crate) struct SavedRefresh {
+    state: Mutex<RefreshState>,
+}
+
+#[derive(Debug, Default)]
+struct RefreshState {
+    /// A refresh is under way.
+    running: bool,
+    /// Another refresh was asked for while one was running.
+    pending: bool,
+}
+
+impl SavedRefresh {
+    fn lock(&self) -> MutexGuard<'_, RefreshState> {
+        self.state.lock().unwrap_or_else(|p| p.into_inner())
+    }
+
+    /// Ask for a refresh; `true` when the caller should start one.
+    fn begin(&self) -> bool {
+        let mut state = self.lock();
+        if state.running {
+            state.pending = true;
+            false
+        } else {
+            state.running = true;
+            true
+        }
+    }
+
+    /// Finish a refresh; `true` when another was asked for meanwhile and
+    /// the caller should run again.
+    fn finish(&self) -> bool {
+        let mut state = self.lock();
+        let again = state.pending;
+        *state = RefreshState {
+            running: again,
+            pending: false,
+        };
+        again
+    }
+
+    /// Call `refresh` until no request is left, after a `begin` that
+    /// returned `true`. A panicking `refresh` leaves the state idle, so
+    /// the next ingest starts a refresh again.
+    fn run(&self, mut refresh: impl FnMut()) {
+        let _reset = ResetOnPanic(self);
+        loop {
+            refresh();
+            if !self.finish() {
+                break;
+            }
+        }
+    }
+}
+
+/// Marks a [`SavedRefresh`] idle when dropped during a panic.
+struct ResetOnPanic<'a>(&'a SavedRefresh);
+
+impl Drop for ResetOnPanic<'_> {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            tracing::error!("saved search refresh panicked");
+            *self.0.lock() = RefreshState::default();
+        }
+    }
+}
+
+/// Re-run the saved searches after an ingest changed the index.
+///
+/// The searches may load the model, so they run on the blocking pool
+/// rather than in the handler, and a refresh already under way absorbs
+/// this request (see [`SavedRefresh`]). Returns the task when one was
+/// started. The ingest already succeeded, so a failure is only logged.
+pub(crate) fn refresh_after_ingest(
+    state: &AppState,
+) -> Option<tokio::task::JoinHandle<()>> {
+    if !state.saved_refresh.begin() {
+        return None;
+    }
+    let state = state.clone();
+    Some(tokio::task::spawn_blocking(move || {
+        state.saved_refresh.run(|| refresh_saved_searches(&state));
+    }))
+}
+
+fn refresh_saved_searches(state: &AppState) {
+    let refreshed = state.open_config_db_blocking().and_then(|config_db| {
+        if config_db.list_saved_searches()?.is_empty() {
+            return Ok(Vec::new());
+        }
+        // Recover from a poisoned mutex — a prior panic left the lock in
+        // a bad state, but the model data is still intact enough to use.
+        let mut model = state.model.lock().unwrap_or_else(|poisoned| {
+            tracing::warn!(
+                "saved::refresh_after_ingest recovered from poisoned model mutex"
+            );
+            poisoned.into_inner()
+        });
+        saved_search::refresh(
+            &state.search_index,
+            &config_db,
+            &state.data_dir,
+            &mut model,
+        )
+    });
+
+    match refreshed {
+        Ok(hits) if !hits.is_empty() => {
+            tracing::info!(new_hits = hits.len(), "saved searches refreshed");
+        }
+        Ok(_) => {}
+        Err(err) => {
+            tracing::warn!(%err, "could not refresh saved searches");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use docbert_core::{
+        ConfigDb,
+        DocumentId,
+        ModelManager,
+        SearchIndex,
+        saved_search::SavedSearch,
+        search::SearchMode,
+        search_cache::SearchCache,
+    };
+
+    use super::*;
+    use crate::web::state::Inner;
+
+    fn index(state: &AppState, path: &str, body: &str) {
+        let mut writer = state.search_index.writer(15_000_000).unwrap();
+        let did = DocumentId::new("notes", path);
+        state
+            .search_index
+            .add_document(
+                &writer,
+                &did.full_hex(),
+                did.numeric,
+                "notes",
+                path,
+                path,
+                body,
+                1,
+            )
+            .unwrap();
+        writer.commit().unwrap();
+    }
+
+    fn query(search: Option<&str>) -> Query<SavedNewQuery> {
+        Query(SavedNewQuery {
+            search: search.map(str::to_string),
+        })
+    }
+
+    #[tokio::test]
+    async fn web_saved_new_reports_hits_after_refresh_and_clears_them() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state: AppState = Arc::new(Inner {
+            data_dir: docbert_core::DataDir::new(tmp.path()),
+            search_index: SearchIndex::open_in_ram().unwrap(),
+            model: Mutex::new(ModelManager::new()),
+            model_id: "test-model".to_string(),
+            search_cache: SearchCache::default(),
+            saved_refresh: SavedRefresh::default(),
+        });
+        let config_db = ConfigDb::open(&state.data_dir.config_db()).unwrap();
+        config_db
+            .set_collection("notes", tmp.path().to_str().unwrap())
+            .unwrap();
+        index(&state, "a.md", "Ownership and the borrow checker.");
+        let saved =
+            SavedSearch::new("borrow", "borrow", SearchMode::Bm25, None, 10)
+                .unwrap();
+        config_db.set_saved_search(&saved).unwrap();
+        drop(config_db);
+
+        refresh_after_ingest(&state).unwrap().await.unwrap();
+        index(&state, "b.md", "Borrow rules for slices.");
+        refresh_after_ingest(&state).unwrap().await.unwrap();
+
+        let response =
+            new_hits(State(state.clone()), query(None)).await.unwrap();
+        assert_eq!(response.0.hit_count, 1);
+        assert_eq!(response.0.hits[0].search, "borrow");
+        assert_eq!(response.0.hits[0].reference, "notes:b.md");
+
+        let missing = new_hits(State(state.clone()), query(Some("nope")))
+            .await
+            .unwrap_err();
+        assert_eq!(missing, StatusCode::NOT_FOUND);
+
+        let cleared =
+            clear_new_hits(State(state.clone()), query(Some("borrow")))
+                .await
+                .unwrap();
+        assert_eq!(cleared.0.cleared, 1);
+        let response = new_hits(State(state), query(None)).await.unwrap();
+        assert!(response.0.hits.is_empty());
+    }
+
+    #[test]
+    fn saved_refresh_folds_requests_made_while_running_into_one_rerun() {
+        let refresh = SavedRefresh::default();
+        assert!(refresh.begin());
+        assert!(!refresh.begin());
+        assert!(!refresh.begin());
+        // Both requests are served by a single further run.
+        assert!(refresh.finish());
+        assert!(!refresh.finish());
+        assert!(refresh.begin());
+    }
+
+    #[test]
+    fn saved_refresh_is_idle_again_after_a_panicking_run() {
+        let refresh = SavedRefresh::default();
+        assert!(refresh.begin());
+        assert!(!refresh.begin());
+        let panicked = std::panic::catch_unwind(|| {
+            refresh.run(|| panic!("search failed"));
+        });
+        assert!(panicked.is_err());
+
+        assert!(refresh.begin());
+        let mut runs = 0;
+        refresh.run(|| runs += 1);
+        assert_eq!(runs, 1);
+        assert!(refresh.begin());
+    }
+}
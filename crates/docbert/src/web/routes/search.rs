@@ -1,8 +1,15 @@
 use std::path::Path;
 
-use axum::{Json, extract::State, http::StatusCode};
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::StatusCode,
+};
 use docbert_core::{
-    search::{self, SearchMode, SearchQuery},
+    ChunkByteOffset,
+    plaid::PlaidQueryParams,
+    search::{self, PassageGrouping, SearchMode, SearchQuery},
+    search_cache::SearchCursor,
     text,
 };
 use serde::{Deserialize, Serialize};
@@ -19,6 +26,105 @@ pub(crate) struct SearchRequest {
     pub(crate) count: usize,
     #[serde(default)]
     pub(crate) min_score: f32,
+    /// Matching chunks to attach per document. Defaults to none, or
+    /// [`search::DEFAULT_PASSAGE_COUNT`] when `group` is `"passage"`.
+    #[serde(default)]
+    pub(crate) passages: Option<usize>,
+    /// `"document"` (default) or `"passage"`.
+    #[serde(default)]
+    pub(crate) group: Option<String>,
+    /// Attach per-token MaxSim alignments and the fusion breakdown to
+    /// every result, plus query-level stage timings and drops.
+    #[serde(default)]
+    pub(crate) explain: bool,
+    /// Pseudo-relevance feedback; omitted or `null` disables it.
+    #[serde(default)]
+    pub(crate) prf: Option<PrfRequest>,
+    /// Count the candidate set per collection, extension, year and tag.
+    #[serde(default)]
+    pub(crate) facets: bool,
+    /// Ranked results to skip before the page starts.
+    #[serde(default)]
+    pub(crate) offset: usize,
+    /// `next_cursor` of an earlier page; overrides `offset`.
+    #[serde(default)]
+    pub(crate) cursor: Option<String>,
+    /// Search with the spelling suggestion instead of the query, when
+    /// there is one.
+    #[serde(default)]
+    pub(crate) autocorrect: bool,
+    /// PLAID query-time parameters; omitted fields keep the stored
+    /// default or the paper value.
+    #[serde(default)]
+    pub(crate) plaid: PlaidQueryParams,
+}
+
+/// Body of `POST /v1/search/batch`: several queries searched with the
+/// same settings.
+#[derive(Debug, Deserialize)]
+pub(crate) struct BatchSearchRequest {
+    pub(crate) queries: Vec<String>,
+    #[serde(default = "default_mode")]
+    pub(crate) mode: String,
+    pub(crate) collection: Option<String>,
+    #[serde(default = "default_count")]
+    pub(crate) count: usize,
+    #[serde(default)]
+    pub(crate) min_score: f32,
+    #[serde(default)]
+    pub(crate) passages: Option<usize>,
+    /// `"document"` (default) or `"passage"`.
+    #[serde(default)]
+    pub(crate) group: Option<String>,
+    #[serde(default)]
+    pub(crate) explain: bool,
+    #[serde(default)]
+    pub(crate) prf: Option<PrfRequest>,
+    #[serde(default)]
+    pub(crate) facets: bool,
+    #[serde(default)]
+    pub(crate) autocorrect: bool,
+    #[serde(default)]
+    pub(crate) plaid: PlaidQueryParams,
+}
+
+/// Pseudo-relevance feedback settings of a [`SearchRequest`]. Missing
+/// fields take the [`search::PrfParams`] defaults.
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct PrfRequest {
+    pub(crate) feedback_docs: Option<usize>,
+    pub(crate) expansion_terms: Option<usize>,
+    pub(crate) weight: Option<f32>,
+}
+
+impl From<PrfRequest> for search::PrfParams {
+    fn from(request: PrfRequest) -> Self {
+        let defaults = search::PrfParams::default();
+        Self {
+            feedback_docs: request
+                .feedback_docs
+                .unwrap_or(defaults.feedback_docs),
+            expansion_terms: request
+                .expansion_terms
+                .unwrap_or(defaults.expansion_terms),
+            weight: request.weight.unwrap_or(defaults.weight),
+        }
+    }
+}
+
+/// Body of `POST /v1/search/similar`.
+#[derive(Debug, Deserialize)]
+pub(crate) struct SimilarRequest {
+    /// Source document: `#short_id`, `collection:path`, or a bare path.
+    pub(crate) reference: String,
+    pub(crate) collection: Option<String>,
+    #[serde(default = "default_count")]
+    pub(crate) count: usize,
+    #[serde(default)]
+    pub(crate) passages: Option<usize>,
+    /// `"document"` (default) or `"passage"`.
+    #[serde(default)]
+    pub(crate) group: Option<String>,
 }
 
 fn default_mode() -> String {
@@ -34,9 +140,234 @@ pub(crate) struct SearchResponse {
     pub(crate) query: String,
     pub(crate) mode: String,
     pub(crate) result_count: usize,
+    /// Ranked results across all pages.
+    pub(crate) total_count: usize,
+    pub(crate) results: Vec<SearchResultItem>,
+    /// Pass back as `cursor` to fetch the next page; absent on the last.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) next_cursor: Option<String>,
+    /// Stage timings, candidate counts and dropped candidates, when
+    /// `explain` was requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) explain: Option<SearchExplainBody>,
+    /// Candidate counts per facet, when `facets` was requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) facets: Option<FacetsBody>,
+    /// Corrected spelling of a query with terms the index has never
+    /// seen, in `hybrid` and `bm25` mode.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) suggestion: Option<SuggestionBody>,
+}
+
+/// A spelling correction offered for the query.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub(crate) struct SuggestionBody {
+    pub(crate) query: String,
+    /// Whether the results are for `query` because the request set
+    /// `autocorrect`.
+    pub(crate) applied: bool,
+}
+
+impl From<search::QuerySuggestion> for SuggestionBody {
+    fn from(suggestion: search::QuerySuggestion) -> Self {
+        Self {
+            query: suggestion.query,
+            applied: suggestion.applied,
+        }
+    }
+}
+
+/// One [`SearchResponse`] per query of a [`BatchSearchRequest`], in
+/// request order.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub(crate) struct BatchSearchResponse {
+    pub(crate) mode: String,
+    pub(crate) query_count: usize,
+    pub(crate) searches: Vec<SearchResponse>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub(crate) struct SimilarResponse {
+    pub(crate) reference: String,
+    pub(crate) result_count: usize,
     pub(crate) results: Vec<SearchResultItem>,
 }
 
+/// Query string of `GET /v1/complete`.
+#[derive(Debug, Deserialize)]
+pub(crate) struct CompleteQuery {
+    pub(crate) q: String,
+    #[serde(default = "default_count")]
+    pub(crate) limit: usize,
+    pub(crate) collection: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub(crate) struct CompleteResponse {
+    pub(crate) query: String,
+    pub(crate) suggestions: Vec<CompletionItem>,
+}
+
+/// One completed document, best first.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub(crate) struct CompletionItem {
+    /// `collection:path`, accepted wherever a document reference is.
+    pub(crate) reference: String,
+    pub(crate) collection: String,
+    pub(crate) path: String,
+    pub(crate) title: String,
+}
+
+/// Query-level breakdown of an explained search.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub(crate) struct SearchExplainBody {
+    pub(crate) timings_ms: StageTimingsBody,
+    pub(crate) bm25_candidates: usize,
+    pub(crate) semantic_candidates: usize,
+    pub(crate) plaid_probed_docs: usize,
+    pub(crate) plaid_decoded_docs: usize,
+    pub(crate) exact_scored_docs: usize,
+    pub(crate) min_score_ignored: bool,
+    /// Why the cross-encoder failed; the fused order was kept.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) rerank_error: Option<String>,
+    pub(crate) dropped_count: usize,
+    pub(crate) dropped: Vec<DroppedCandidateBody>,
+}
+
+/// Wall-clock milliseconds per search stage.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub(crate) struct StageTimingsBody {
+    pub(crate) query_encoding: f64,
+    pub(crate) plaid_probe: f64,
+    pub(crate) plaid_decode: f64,
+    pub(crate) exact: f64,
+    pub(crate) tantivy: f64,
+    pub(crate) rerank: f64,
+    pub(crate) total: f64,
+}
+
+/// A scored candidate the result limit or `min_score` filtered out.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub(crate) struct DroppedCandidateBody {
+    pub(crate) collection: String,
+    pub(crate) path: String,
+    pub(crate) score: f32,
+    /// `"limit"` or `"min_score"`.
+    pub(crate) reason: String,
+    pub(crate) explain: ResultExplainBody,
+}
+
+/// Counts of the candidate set, each list sorted by count descending.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub(crate) struct FacetsBody {
+    pub(crate) total: usize,
+    pub(crate) collections: Vec<FacetCountBody>,
+    pub(crate) extensions: Vec<FacetCountBody>,
+    pub(crate) years: Vec<FacetCountBody>,
+    pub(crate) tags: Vec<FacetCountBody>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub(crate) struct FacetCountBody {
+    pub(crate) value: String,
+    pub(crate) count: usize,
+}
+
+impl From<search::Facets> for FacetsBody {
+    fn from(facets: search::Facets) -> Self {
+        let list = |counts: Vec<search::FacetCount>| {
+            counts
+                .into_iter()
+                .map(|c| FacetCountBody {
+                    value: c.value,
+                    count: c.count,
+                })
+                .collect()
+        };
+        Self {
+            total: facets.total,
+            collections: list(facets.collections),
+            extensions: list(facets.extensions),
+            years: list(facets.years),
+            tags: list(facets.tags),
+        }
+    }
+}
+
+/// Where one result sat in each retrieval leg.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+pub(crate) struct ResultExplainBody {
+    pub(crate) bm25: Option<LegHitBody>,
+    pub(crate) semantic: Option<LegHitBody>,
+    pub(crate) fused_rank: usize,
+}
+
+/// Rank, score and RRF contribution of a result inside one leg.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+pub(crate) struct LegHitBody {
+    pub(crate) rank: usize,
+    pub(crate) score: f32,
+    pub(crate) rrf: f32,
+}
+
+impl From<search::LegHit> for LegHitBody {
+    fn from(hit: search::LegHit) -> Self {
+        Self {
+            rank: hit.rank,
+            score: hit.score,
+            rrf: hit.rrf,
+        }
+    }
+}
+
+impl From<search::ResultExplain> for ResultExplainBody {
+    fn from(explain: search::ResultExplain) -> Self {
+        Self {
+            bm25: explain.bm25.map(LegHitBody::from),
+            semantic: explain.semantic.map(LegHitBody::from),
+            fused_rank: explain.fused_rank,
+        }
+    }
+}
+
+impl From<search::SearchExplain> for SearchExplainBody {
+    fn from(explain: search::SearchExplain) -> Self {
+        let millis = |d: std::time::Duration| d.as_secs_f64() * 1000.0;
+        let t = explain.timings;
+        Self {
+            timings_ms: StageTimingsBody {
+                query_encoding: millis(t.query_encoding),
+                plaid_probe: millis(t.plaid_probe),
+                plaid_decode: millis(t.plaid_decode),
+                exact: millis(t.exact),
+                tantivy: millis(t.tantivy),
+                rerank: millis(t.rerank),
+                total: millis(t.total),
+            },
+            bm25_candidates: explain.bm25_candidates,
+            semantic_candidates: explain.semantic_candidates,
+            plaid_probed_docs: explain.plaid_probed_docs,
+            plaid_decoded_docs: explain.plaid_decoded_docs,
+            exact_scored_docs: explain.exact_scored_docs,
+            min_score_ignored: explain.min_score_ignored,
+            rerank_error: explain.rerank_error,
+            dropped_count: explain.dropped_count,
+            dropped: explain
+                .dropped
+                .into_iter()
+                .map(|d| DroppedCandidateBody {
+                    collection: d.collection,
+                    path: d.path,
+                    score: d.score,
+                    reason: d.reason.as_str().to_string(),
+                    explain: d.explain.into(),
+                })
+                .collect(),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub(crate) struct SearchExcerpt {
     pub(crate) text: String,
@@ -61,6 +392,11 @@ pub(crate) struct ChunkMatch {
 pub(crate) struct SearchResultItem {
     pub(crate) rank: usize,
     pub(crate) score: f32,
+    /// Cross-encoder score, when a reranker model is configured and
+    /// this hit was among the rescored candidates. `score` keeps the
+    /// fused (or MaxSim) value.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) rerank_score: Option<f32>,
     pub(crate) doc_id: String,
     pub(crate) collection: String,
     pub(crate) path: String,
@@ -84,6 +420,37 @@ pub(crate) struct SearchResultItem {
     /// to `analyze_document` to read only the matching region.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) match_chunk: Option<ChunkMatch>,
+    /// Top matching chunks in passage mode, best first. Each range is
+    /// clamped to the current file size like `match_chunk`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) passages: Vec<PassageMatch>,
+    /// Query tokens aligned to their best-matching document token when
+    /// `explain` was requested, for highlighting. Ranges are clamped
+    /// like `match_chunk`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) token_matches: Vec<TokenMatchItem>,
+    /// BM25/PLAID rank, score and RRF contribution when `explain` was
+    /// requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) explain: Option<ResultExplainBody>,
+}
+
+/// One query token's MaxSim alignment inside a search hit.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub(crate) struct TokenMatchItem {
+    pub(crate) query_token: String,
+    pub(crate) document_token: String,
+    pub(crate) score: f32,
+    pub(crate) start_byte: u64,
+    pub(crate) end_byte: u64,
+}
+
+/// One scored chunk of a search hit in passage mode.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+pub(crate) struct PassageMatch {
+    pub(crate) score: f32,
+    pub(crate) start_byte: u64,
+    pub(crate) end_byte: u64,
 }
 
 pub(crate) async fn search(
@@ -91,11 +458,32 @@ pub(crate) async fn search(
     Json(body): Json<SearchRequest>,
 ) -> Result<Json<SearchResponse>, StatusCode> {
     let mode = SearchMode::parse(&body.mode).ok_or(StatusCode::BAD_REQUEST)?;
+    let grouping = match body.group.as_deref() {
+        None => PassageGrouping::default(),
+        Some(group) => {
+            PassageGrouping::parse(group).ok_or(StatusCode::BAD_REQUEST)?
+        }
+    };
+    let cursor = body
+        .cursor
+        .as_deref()
+        .map(SearchCursor::decode)
+        .transpose()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    body.plaid.validate().map_err(|_| StatusCode::BAD_REQUEST)?;
     let request = SearchQuery {
         query: body.query.clone(),
         collection: body.collection.clone(),
         count: body.count,
         min_score: body.min_score,
+        passages: search::passage_count(body.passages, grouping),
+        explain: body.explain,
+        prf: body.prf.map(search::PrfParams::from),
+        facets: body.facets,
+        offset: body.offset,
+        cursor,
+        autocorrect: body.autocorrect,
+        plaid: body.plaid,
     };
 
     let config_db = state.open_config_db().map_err(|err| {
@@ -112,9 +500,10 @@ pub(crate) async fn search(
         );
         poisoned.into_inner()
     });
-    let mut results = search::by_mode(
+    let outcome = search::by_mode_cached(
         mode,
         &request,
+        &state.search_cache,
         &state.search_index,
         &config_db,
         &state.data_dir,
@@ -132,6 +521,12 @@ pub(crate) async fn search(
             );
             StatusCode::SERVICE_UNAVAILABLE
         }
+        // A cursor from another search, or one whose ranking changed
+        // since it was issued: the client should start over.
+        docbert_core::Error::InvalidCursor(reason) => {
+            tracing::info!(query = %body.query, %reason, "search cursor rejected");
+            StatusCode::BAD_REQUEST
+        }
         other => {
             tracing::error!(
                 error = %other,
@@ -144,22 +539,234 @@ pub(crate) async fn search(
             StatusCode::INTERNAL_SERVER_ERROR
         }
     })?;
-    drop(model);
 
-    search::disambiguate_doc_ids(&mut results, &config_db);
+    search_response(
+        &state,
+        &config_db,
+        &mut model,
+        outcome,
+        body.query,
+        mode,
+        grouping,
+        body.explain,
+    )
+    .map(Json)
+}
+
+/// Run every query of the batch with one PLAID load and one query
+/// encoding pass, answering with a [`SearchResponse`] per query.
+pub(crate) async fn batch(
+    State(state): State<AppState>,
+    Json(body): Json<BatchSearchRequest>,
+) -> Result<Json<BatchSearchResponse>, StatusCode> {
+    let mode = SearchMode::parse(&body.mode).ok_or(StatusCode::BAD_REQUEST)?;
+    let grouping = match body.group.as_deref() {
+        None => PassageGrouping::default(),
+        Some(group) => {
+            PassageGrouping::parse(group).ok_or(StatusCode::BAD_REQUEST)?
+        }
+    };
+    let prf = body.prf.map(search::PrfParams::from);
+    body.plaid.validate().map_err(|_| StatusCode::BAD_REQUEST)?;
+    let requests: Vec<SearchQuery> = body
+        .queries
+        .iter()
+        .map(|query| SearchQuery {
+            query: query.clone(),
+            collection: body.collection.clone(),
+            count: body.count,
+            min_score: body.min_score,
+            passages: search::passage_count(body.passages, grouping),
+            explain: body.explain,
+            prf,
+            facets: body.facets,
+            offset: 0,
+            cursor: None,
+            autocorrect: body.autocorrect,
+            plaid: body.plaid,
+        })
+        .collect();
+
+    let config_db = state.open_config_db().map_err(|err| {
+        log_internal_error(err, "search::batch open config db")
+    })?;
+    let mut model = state.model.lock().unwrap_or_else(|poisoned| {
+        tracing::warn!(
+            "batch search recovered from poisoned model mutex (a prior search panicked)"
+        );
+        poisoned.into_inner()
+    });
+    let outcomes = search::by_mode_batch(
+        mode,
+        &requests,
+        &state.search_index,
+        &config_db,
+        &state.data_dir,
+        &mut model,
+    )
+    .map_err(|err| match err {
+        docbert_core::Error::PlaidIndexMissing => {
+            tracing::info!(
+                queries = body.queries.len(),
+                mode = %body.mode,
+                "batch search rejected: PLAID index missing (run `docbert sync`)"
+            );
+            StatusCode::SERVICE_UNAVAILABLE
+        }
+        other => log_internal_error(other, "search::batch"),
+    })?;
+
+    let searches = body
+        .queries
+        .into_iter()
+        .zip(outcomes)
+        .map(|(query, outcome)| {
+            search_response(
+                &state,
+                &config_db,
+                &mut model,
+                outcome,
+                query,
+                mode,
+                grouping,
+                body.explain,
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Json(BatchSearchResponse {
+        mode: mode.as_str().to_string(),
+        query_count: searches.len(),
+        searches,
+    }))
+}
+
+/// Lay out a search outcome per `grouping`, attach token matches when
+/// `explain` is set, and build the response items.
+#[allow(clippy::too_many_arguments)]
+fn search_response(
+    state: &AppState,
+    config_db: &docbert_core::ConfigDb,
+    model: &mut docbert_core::ModelManager,
+    outcome: search::SearchOutcome,
+    query: String,
+    mode: SearchMode,
+    grouping: PassageGrouping,
+    explain: bool,
+) -> Result<SearchResponse, StatusCode> {
+    // Autocorrected results match the corrected query, so that is what
+    // excerpts and token matches highlight.
+    let searched = outcome
+        .suggestion
+        .as_ref()
+        .filter(|suggestion| suggestion.applied)
+        .map_or(query.as_str(), |suggestion| suggestion.query.as_str());
+    let mut results = search::group_passages(outcome.results, grouping);
+    if explain {
+        search::explain_matches(&mut results, searched, config_db, model)
+            .map_err(|err| log_internal_error(err, "search::search explain"))?;
+    }
+    search::disambiguate_doc_ids(&mut results, config_db);
 
     let items: Vec<SearchResultItem> = results
         .into_iter()
         .map(|result| {
-            build_search_result_item(&state, &config_db, result, &body.query)
+            build_search_result_item(state, config_db, result, Some(searched))
         })
         .collect();
 
-    Ok(Json(SearchResponse {
-        query: body.query,
+    Ok(SearchResponse {
+        query,
         mode: mode.as_str().to_string(),
         result_count: items.len(),
+        total_count: outcome.total,
         results: items,
+        next_cursor: outcome.next_cursor.map(|cursor| cursor.encode()),
+        explain: outcome.explain.map(SearchExplainBody::from),
+        facets: outcome.facets.map(FacetsBody::from),
+        suggestion: outcome.suggestion.map(SuggestionBody::from),
+    })
+}
+
+/// Documents most similar to an indexed one, in the same item shape as
+/// [`search`]. Items carry no excerpts since there is no query text.
+pub(crate) async fn similar(
+    State(state): State<AppState>,
+    Json(body): Json<SimilarRequest>,
+) -> Result<Json<SimilarResponse>, StatusCode> {
+    let grouping = match body.group.as_deref() {
+        None => PassageGrouping::default(),
+        Some(group) => {
+            PassageGrouping::parse(group).ok_or(StatusCode::BAD_REQUEST)?
+        }
+    };
+    let params = search::SimilarParams {
+        reference: body.reference.clone(),
+        count: body.count,
+        collection: body.collection.clone(),
+        passages: search::passage_count(body.passages, grouping),
+    };
+
+    let config_db = state.open_config_db().map_err(|err| {
+        log_internal_error(err, "search::similar open config db")
+    })?;
+    let results = search::similar(
+        &params,
+        &state.search_index,
+        &config_db,
+        &state.data_dir,
+    )
+    .map_err(|err| match err {
+        docbert_core::Error::NotFound { .. } => StatusCode::NOT_FOUND,
+        docbert_core::Error::PlaidIndexMissing => {
+            tracing::info!(
+                reference = %body.reference,
+                "similar rejected: PLAID index missing (run `docbert sync`)"
+            );
+            StatusCode::SERVICE_UNAVAILABLE
+        }
+        other => log_internal_error(other, "search::similar"),
+    })?;
+
+    let mut results = search::group_passages(results, grouping);
+    search::disambiguate_doc_ids(&mut results, &config_db);
+
+    let items: Vec<SearchResultItem> = results
+        .into_iter()
+        .map(|result| {
+            build_search_result_item(&state, &config_db, result, None)
+        })
+        .collect();
+
+    Ok(Json(SimilarResponse {
+        reference: body.reference,
+        result_count: items.len(),
+        results: items,
+    }))
+}
+
+/// Titles and paths starting with the typed prefix, straight from the
+/// Tantivy index: no model, no PLAID and no reads of the source files.
+pub(crate) async fn complete(
+    State(state): State<AppState>,
+    Query(query): Query<CompleteQuery>,
+) -> Result<Json<CompleteResponse>, StatusCode> {
+    let completions = state
+        .search_index
+        .complete(&query.q, query.collection.as_deref(), query.limit)
+        .map_err(|err| log_internal_error(err, "search::complete"))?;
+
+    Ok(Json(CompleteResponse {
+        query: query.q,
+        suggestions: completions
+            .into_iter()
+            .map(|result| CompletionItem {
+                reference: format!("{}:{}", result.collection, result.path),
+                collection: result.collection,
+                path: result.path,
+                title: result.title,
+            })
+            .collect(),
     }))
 }
 
@@ -167,7 +774,7 @@ fn build_search_result_item(
     _state: &AppState,
     config_db: &docbert_core::ConfigDb,
     result: search::FinalResult,
-    query: &str,
+    query: Option<&str>,
 ) -> SearchResultItem {
     let metadata = load_user_metadata(config_db, result.doc_num_id);
     let preview = load_preview(config_db, &result.collection, &result.path);
@@ -177,7 +784,9 @@ fn build_search_result_item(
                 &content,
                 Path::new(&result.path),
             );
-            let excerpts = text::extract_excerpts(&content, query, 3)
+            let excerpts = query
+                .map(|query| text::extract_excerpts(&content, query, 3))
+                .unwrap_or_default()
                 .into_iter()
                 .map(|excerpt| SearchExcerpt {
                     text: excerpt.text,
@@ -198,10 +807,37 @@ fn build_search_result_item(
         result.best_chunk_doc_id,
         byte_count,
     );
+    let passages = result
+        .passages
+        .iter()
+        .filter_map(|passage| {
+            let range = clamp_chunk_range(passage.byte_offset(), byte_count)?;
+            Some(PassageMatch {
+                score: passage.score,
+                start_byte: range.start_byte,
+                end_byte: range.end_byte,
+            })
+        })
+        .collect();
+    let token_matches = result
+        .token_matches
+        .iter()
+        .filter_map(|token| {
+            let range = clamp_chunk_range(token.byte_offset(), byte_count)?;
+            Some(TokenMatchItem {
+                query_token: token.query_token.clone(),
+                document_token: token.document_token.clone(),
+                score: token.score,
+                start_byte: range.start_byte,
+                end_byte: range.end_byte,
+            })
+        })
+        .collect();
 
     SearchResultItem {
         rank: result.rank,
         score: result.score,
+        rerank_score: result.rerank_score,
         doc_id: result.doc_id,
         collection: result.collection,
         path: result.path,
@@ -211,6 +847,9 @@ fn build_search_result_item(
         line_count,
         byte_count,
         match_chunk,
+        passages,
+        token_matches,
+        explain: result.explain.map(ResultExplainBody::from),
     }
 }
 
@@ -237,6 +876,16 @@ fn load_match_chunk(
         .get_chunk_offset_for_doc(doc_num_id, chunk_doc_id)
         .ok()
         .flatten()?;
+    clamp_chunk_range(offset, document_byte_count)
+}
+
+/// Turn a chunk's byte range into an inclusive [`ChunkMatch`] that stays
+/// inside the document's current size. `None` for empty chunks and for
+/// ranges that start past the end of the file.
+fn clamp_chunk_range(
+    offset: ChunkByteOffset,
+    document_byte_count: Option<u64>,
+) -> Option<ChunkMatch> {
     let end_byte = offset.inclusive_end()?;
 
     if let Some(total_bytes) = document_byte_count
@@ -290,6 +939,7 @@ mod tests {
         ModelManager,
         SearchIndex,
         incremental,
+        search_cache::SearchCache,
     };
 
     use super::*;
@@ -305,6 +955,8 @@ mod tests {
                 search_index: SearchIndex::open_in_ram().unwrap(),
                 model: Mutex::new(ModelManager::new()),
                 model_id: "test-model".to_string(),
+                search_cache: SearchCache::default(),
+                saved_refresh: Default::default(),
             }),
         )
     }
@@ -364,6 +1016,10 @@ mod tests {
             path: path.to_string(),
             title: title.to_string(),
             best_chunk_doc_id: None,
+            rerank_score: None,
+            passages: Vec::new(),
+            token_matches: Vec::new(),
+            explain: None,
         }
     }
 
@@ -372,6 +1028,26 @@ mod tests {
         assert_eq!(default_mode(), "semantic");
     }
 
+    #[test]
+    fn web_search_prf_fills_missing_fields_with_defaults() {
+        let body: SearchRequest = serde_json::from_str(
+            r#"{"query": "rust", "prf": {"expansion_terms": 4}}"#,
+        )
+        .unwrap();
+        let prf = search::PrfParams::from(body.prf.unwrap());
+        assert_eq!(
+            prf,
+            search::PrfParams {
+                expansion_terms: 4,
+                ..search::PrfParams::default()
+            }
+        );
+
+        let body: SearchRequest =
+            serde_json::from_str(r#"{"query": "rust"}"#).unwrap();
+        assert!(body.prf.is_none());
+    }
+
     #[test]
     fn web_search_result_item_reads_title_and_excerpts_from_disk() {
         let (_tmp, state) = test_state();
@@ -388,7 +1064,7 @@ mod tests {
             &state,
             &config_db,
             final_result(&did, "Index Rust", "rust.md"),
-            "ownership",
+            Some("ownership"),
         );
 
         assert_eq!(item.title, "Disk Rust");
@@ -436,7 +1112,7 @@ mod tests {
 
         let mut result = final_result(&did, "Long", "long.md");
         result.best_chunk_doc_id = Some(chunk_id);
-        let item = build_search_result_item(&state, &config_db, result, "");
+        let item = build_search_result_item(&state, &config_db, result, None);
 
         assert_eq!(
             item.match_chunk,
@@ -477,13 +1153,100 @@ mod tests {
 
         let mut result = final_result(&did, "Shrunk", "shrunk.md");
         result.best_chunk_doc_id = Some(chunk_id);
-        let item = build_search_result_item(&state, &config_db, result, "");
+        let item = build_search_result_item(&state, &config_db, result, None);
 
         let m = item.match_chunk.expect("clamped match");
         assert_eq!(m.start_byte, 0);
         assert_eq!(m.end_byte, "now only ten".len() as u64 - 1);
     }
 
+    #[test]
+    fn web_search_result_item_lists_passages_clamped_to_document_size() {
+        let (_tmp, state) = test_state();
+        let content = "first passage\nsecond passage";
+        let did =
+            seed_filesystem_document(&state, "notes", "p.md", content, None);
+        let config_db = state.open_config_db().unwrap();
+
+        let mut result = final_result(&did, "P", "p.md");
+        result.passages = vec![
+            search::Passage {
+                chunk_doc_id: 2,
+                score: 0.8,
+                start_byte: 14,
+                byte_len: 100,
+            },
+            search::Passage {
+                chunk_doc_id: 1,
+                score: 0.5,
+                start_byte: 0,
+                byte_len: 13,
+            },
+            search::Passage {
+                chunk_doc_id: 3,
+                score: 0.1,
+                start_byte: 500,
+                byte_len: 10,
+            },
+        ];
+        let item = build_search_result_item(&state, &config_db, result, None);
+
+        assert_eq!(
+            item.passages,
+            vec![
+                PassageMatch {
+                    score: 0.8,
+                    start_byte: 14,
+                    end_byte: content.len() as u64 - 1,
+                },
+                PassageMatch {
+                    score: 0.5,
+                    start_byte: 0,
+                    end_byte: 12,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn web_search_result_item_lists_token_matches_as_inclusive_ranges() {
+        let (_tmp, state) = test_state();
+        let content = "rust ownership rules";
+        let did =
+            seed_filesystem_document(&state, "notes", "t.md", content, None);
+        let config_db = state.open_config_db().unwrap();
+
+        let mut result = final_result(&did, "T", "t.md");
+        result.token_matches = vec![
+            search::TokenMatch {
+                query_token: "ownership".to_string(),
+                document_token: "ownership".to_string(),
+                score: 0.9,
+                start_byte: 5,
+                byte_len: 9,
+            },
+            search::TokenMatch {
+                query_token: "stale".to_string(),
+                document_token: "gone".to_string(),
+                score: 0.2,
+                start_byte: 400,
+                byte_len: 4,
+            },
+        ];
+        let item = build_search_result_item(&state, &config_db, result, None);
+
+        assert_eq!(
+            item.token_matches,
+            vec![TokenMatchItem {
+                query_token: "ownership".to_string(),
+                document_token: "ownership".to_string(),
+                score: 0.9,
+                start_byte: 5,
+                end_byte: 13,
+            }]
+        );
+    }
+
     #[test]
     fn web_search_result_item_omits_match_chunk_for_bm25_only_hits() {
         // BM25-only hits don't carry a `best_chunk_doc_id`, so the
@@ -503,7 +1266,7 @@ mod tests {
             &state,
             &config_db,
             final_result(&did, "Plain", "plain.md"),
-            "alpha",
+            Some("alpha"),
         );
 
         assert!(item.match_chunk.is_none());
@@ -540,7 +1303,7 @@ mod tests {
             &state,
             &config_db,
             final_result(&did, "Ghost", "ghost.md"),
-            "anything",
+            Some("anything"),
         );
 
         assert_eq!(item.line_count, None);
@@ -565,7 +1328,7 @@ mod tests {
             &state,
             &config_db,
             final_result(&did, "Semantic result", "rust.md"),
-            "memory management",
+            Some("memory management"),
         );
 
         assert_eq!(item.excerpts.len(), 1);
@@ -619,7 +1382,7 @@ mod tests {
             &state,
             &config_db,
             final_result(&did, "Rust", "rust.md"),
-            "ownership",
+            Some("ownership"),
         );
 
         assert_eq!(item.excerpts.len(), 3);
@@ -643,6 +1406,87 @@ mod tests {
                 collection: None,
                 count: 10,
                 min_score: 0.0,
+                passages: None,
+                group: None,
+                explain: false,
+                prf: None,
+                facets: false,
+                offset: 0,
+                cursor: None,
+                autocorrect: false,
+                plaid: PlaidQueryParams::default(),
+            }),
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(error, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn web_search_rejects_unknown_group() {
+        let (_tmp, state) = test_state();
+
+        let error = search(
+            State(state),
+            Json(SearchRequest {
+                query: "rust".to_string(),
+                mode: "bm25".to_string(),
+                collection: None,
+                count: 10,
+                min_score: 0.0,
+                passages: Some(2),
+                group: Some("chunk".to_string()),
+                explain: false,
+                prf: None,
+                facets: false,
+                offset: 0,
+                cursor: None,
+                autocorrect: false,
+                plaid: PlaidQueryParams::default(),
+            }),
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(error, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn web_search_rejects_invalid_plaid_params() {
+        let (_tmp, state) = test_state();
+        let body: SearchRequest = serde_json::from_value(serde_json::json!({
+            "query": "rust",
+            "plaid": { "n_candidate_docs": 0 },
+        }))
+        .unwrap();
+
+        let error = search(State(state), Json(body)).await.unwrap_err();
+
+        assert_eq!(error, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn web_search_rejects_malformed_cursor() {
+        let (_tmp, state) = test_state();
+
+        let error = search(
+            State(state),
+            Json(SearchRequest {
+                query: "rust".to_string(),
+                mode: "bm25".to_string(),
+                collection: None,
+                count: 10,
+                min_score: 0.0,
+                passages: None,
+                group: None,
+                explain: false,
+                prf: None,
+                facets: false,
+                offset: 0,
+                cursor: Some("page-2".to_string()),
+                autocorrect: false,
+                plaid: PlaidQueryParams::default(),
             }),
         )
         .await
@@ -667,6 +1511,15 @@ mod tests {
                 collection: None,
                 count: 10,
                 min_score: 0.0,
+                passages: None,
+                group: None,
+                explain: false,
+                prf: None,
+                facets: false,
+                offset: 0,
+                cursor: None,
+                autocorrect: false,
+                plaid: PlaidQueryParams::default(),
             }),
         )
         .await
@@ -676,6 +1529,295 @@ mod tests {
         assert_eq!(response.0.result_count, 0);
     }
 
+    #[tokio::test]
+    async fn web_complete_returns_ranked_references() {
+        let (_tmp, state) = test_state();
+        let mut writer = state.search_index.writer(15_000_000).unwrap();
+        for (num_id, collection, path, title) in [
+            (1, "notes", "deploy.md", "Deployment checklist"),
+            (2, "notes", "archive/old-deploy.md", "Old notes"),
+            (3, "work", "deploy.md", "Deploying services"),
+        ] {
+            state
+                .search_index
+                .add_document(
+                    &writer,
+                    &num_id.to_string(),
+                    num_id,
+                    collection,
+                    path,
+                    title,
+                    "body",
+                    1,
+                )
+                .unwrap();
+        }
+        writer.commit().unwrap();
+
+        let response = complete(
+            State(state.clone()),
+            Query(CompleteQuery {
+                q: "Depl".to_string(),
+                limit: 2,
+                collection: None,
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.0.query, "Depl");
+        let references: Vec<&str> = response
+            .0
+            .suggestions
+            .iter()
+            .map(|item| item.reference.as_str())
+            .collect();
+        assert_eq!(references.len(), 2);
+        assert!(!references.contains(&"notes:archive/old-deploy.md"));
+
+        let response = complete(
+            State(state),
+            Query(CompleteQuery {
+                q: "depl".to_string(),
+                limit: 10,
+                collection: Some("work".to_string()),
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            response.0.suggestions,
+            vec![CompletionItem {
+                reference: "work:deploy.md".to_string(),
+                collection: "work".to_string(),
+                path: "deploy.md".to_string(),
+                title: "Deploying services".to_string(),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn web_search_suggests_and_autocorrects_misspelled_queries() {
+        let (_tmp, state) = test_state();
+        let did = seed_filesystem_document(
+            &state,
+            "notes",
+            "garden.md",
+            "Gardening tips for spring.\n",
+            None,
+        );
+        let mut writer = state.search_index.writer(15_000_000).unwrap();
+        state
+            .search_index
+            .add_document(
+                &writer,
+                &did.full_hex(),
+                did.numeric,
+                "notes",
+                "garden.md",
+                "Garden",
+                "Gardening tips for spring.",
+                1,
+            )
+            .unwrap();
+        writer.commit().unwrap();
+        let request = |autocorrect| SearchRequest {
+            query: "gardning".to_string(),
+            mode: "bm25".to_string(),
+            collection: None,
+            count: 10,
+            min_score: 0.0,
+            passages: None,
+            group: None,
+            explain: false,
+            prf: None,
+            facets: false,
+            offset: 0,
+            cursor: None,
+            autocorrect,
+            plaid: PlaidQueryParams::default(),
+        };
+
+        let response = search(State(state.clone()), Json(request(false)))
+            .await
+            .unwrap();
+        assert_eq!(
+            response.0.suggestion,
+            Some(SuggestionBody {
+                query: "gardening".to_string(),
+                applied: false,
+            })
+        );
+
+        let response = search(State(state), Json(request(true))).await.unwrap();
+        assert!(response.0.suggestion.unwrap().applied);
+        assert_eq!(response.0.result_count, 1);
+        assert_eq!(response.0.results[0].path, "garden.md");
+    }
+
+    fn batch_request(mode: &str, queries: &[&str]) -> BatchSearchRequest {
+        BatchSearchRequest {
+            queries: queries.iter().map(|q| q.to_string()).collect(),
+            mode: mode.to_string(),
+            collection: None,
+            count: 10,
+            min_score: 0.0,
+            passages: None,
+            group: None,
+            explain: false,
+            prf: None,
+            facets: false,
+            autocorrect: false,
+            plaid: PlaidQueryParams::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn web_batch_search_answers_every_query_in_order() {
+        let (_tmp, state) = test_state();
+
+        let response =
+            batch(State(state), Json(batch_request("bm25", &["rust", "go"])))
+                .await
+                .expect("bm25 batch should be accepted");
+
+        assert_eq!(response.0.mode, "bm25");
+        assert_eq!(response.0.query_count, 2);
+        let queries: Vec<&str> = response
+            .0
+            .searches
+            .iter()
+            .map(|search| search.query.as_str())
+            .collect();
+        assert_eq!(queries, ["rust", "go"]);
+    }
+
+    #[tokio::test]
+    async fn web_batch_search_rejects_unknown_mode() {
+        let (_tmp, state) = test_state();
+
+        let error =
+            batch(State(state), Json(batch_request("fuzzy", &["rust"])))
+                .await
+                .unwrap_err();
+
+        assert_eq!(error, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn web_batch_search_without_plaid_index_returns_service_unavailable()
+    {
+        let (_tmp, state) = test_state();
+
+        let error =
+            batch(State(state), Json(batch_request("hybrid", &["rust"])))
+                .await
+                .unwrap_err();
+
+        assert_eq!(error, StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn web_search_explain_returns_query_level_breakdown() {
+        let (_tmp, state) = test_state();
+
+        let response = search(
+            State(state),
+            Json(SearchRequest {
+                query: "rust".to_string(),
+                mode: "bm25".to_string(),
+                collection: None,
+                count: 10,
+                min_score: 0.0,
+                passages: None,
+                group: None,
+                explain: true,
+                prf: None,
+                facets: false,
+                offset: 0,
+                cursor: None,
+                autocorrect: false,
+                plaid: PlaidQueryParams::default(),
+            }),
+        )
+        .await
+        .expect("bm25 mode should be accepted");
+
+        let explain = response.0.explain.expect("explain body");
+        assert_eq!(explain.bm25_candidates, 0);
+        assert_eq!(explain.dropped_count, 0);
+        assert_eq!(explain.timings_ms.query_encoding, 0.0);
+        assert!(explain.timings_ms.total >= explain.timings_ms.tantivy);
+    }
+
+    #[test]
+    fn web_search_result_item_carries_fusion_explain() {
+        let (_tmp, state) = test_state();
+        let did =
+            seed_filesystem_document(&state, "notes", "f.md", "fused", None);
+        let config_db = state.open_config_db().unwrap();
+
+        let mut result = final_result(&did, "F", "f.md");
+        result.explain = Some(search::ResultExplain {
+            bm25: None,
+            semantic: Some(search::LegHit {
+                rank: 2,
+                score: 14.0,
+                rrf: 0.5,
+            }),
+            fused_rank: 3,
+        });
+        let item = build_search_result_item(&state, &config_db, result, None);
+
+        let value = serde_json::to_value(&item).unwrap();
+        assert_eq!(
+            value["explain"],
+            serde_json::json!({
+                "bm25": null,
+                "semantic": { "rank": 2, "score": 14.0, "rrf": 0.5 },
+                "fused_rank": 3,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn web_similar_unknown_reference_returns_not_found() {
+        let (_tmp, state) = test_state();
+
+        let status = similar(
+            State(state),
+            Json(SimilarRequest {
+                reference: "notes:missing.md".to_string(),
+                collection: None,
+                count: 10,
+                passages: None,
+                group: None,
+            }),
+        )
+        .await
+        .expect_err("unknown reference");
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn web_similar_without_plaid_index_returns_service_unavailable() {
+        let (_tmp, state) = test_state();
+        seed_filesystem_document(&state, "notes", "a.md", "alpha", None);
+
+        let status = similar(
+            State(state),
+            Json(SimilarRequest {
+                reference: "notes:a.md".to_string(),
+                collection: None,
+                count: 10,
+                passages: None,
+                group: None,
+            }),
+        )
+        .await
+        .expect_err("no PLAID index");
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    }
+
     #[tokio::test]
     async fn web_search_without_plaid_index_returns_service_unavailable() {
         // Fresh data dir → no PLAID index → semantic leg cannot run.
@@ -692,6 +1834,15 @@ mod tests {
                 collection: None,
                 count: 10,
                 min_score: 0.0,
+                passages: None,
+                group: None,
+                explain: false,
+                prf: None,
+                facets: false,
+                offset: 0,
+                cursor: None,
+                autocorrect: false,
+                plaid: PlaidQueryParams::default(),
             }),
         )
         .await
@@ -633,7 +633,7 @@ pub(crate) async fn logout_openai_codex_oauth(
 mod tests {
     use std::sync::{Arc, Mutex, OnceLock};
 
-    use docbert_core::{ModelManager, SearchIndex};
+    use docbert_core::{ModelManager, SearchIndex, search_cache::SearchCache};
 
     use super::*;
     use crate::web::state::Inner;
@@ -650,6 +650,8 @@ mod tests {
             search_index: SearchIndex::open_in_ram().unwrap(),
             model: Mutex::new(ModelManager::new()),
             model_id: "test-model".to_string(),
+            search_cache: SearchCache::default(),
+            saved_refresh: Default::default(),
         });
 
         (tmp, state)
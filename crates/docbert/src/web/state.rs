@@ -7,16 +7,24 @@ use docbert_core::{
     ModelManager,
     SearchIndex,
     error,
+    model_manager::resolve_reranker_model,
+    search_cache::SearchCache,
 };
 use tantivy::IndexWriter;
 
-use crate::runtime;
+use crate::{runtime, web::routes::saved::SavedRefresh};
 
 pub(crate) struct Inner {
+    /// Carries the server's resident PLAID index, so searches reuse one
+    /// in-memory copy until `plaid.idx` changes on disk.
     pub(crate) data_dir: DataDir,
     pub(crate) search_index: SearchIndex,
     pub(crate) model: Mutex<ModelManager>,
     pub(crate) model_id: String,
+    /// Rankings kept between pages of `POST /v1/search`.
+    pub(crate) search_cache: SearchCache,
+    /// Saved-search refreshes started by ingests.
+    pub(crate) saved_refresh: SavedRefresh,
 }
 
 pub(crate) type AppState = Arc<Inner>;
@@ -45,17 +53,21 @@ impl Inner {
 }
 
 pub(crate) fn init(
-    _config_db: ConfigDb,
+    config_db: ConfigDb,
     data_dir: DataDir,
     model_id: String,
 ) -> error::Result<AppState> {
     let search_index = SearchIndex::open(&data_dir.tantivy_dir()?)?;
-    let model = ModelManager::with_model_id(model_id.clone());
+    let reranker_model = resolve_reranker_model(&config_db, None)?;
+    let model = ModelManager::with_model_id(model_id.clone())
+        .with_reranker_model(reranker_model);
 
     Ok(Arc::new(Inner {
-        data_dir,
+        data_dir: runtime::with_resident_plaid(data_dir),
         search_index,
         model: Mutex::new(model),
         model_id,
+        search_cache: SearchCache::default(),
+        saved_refresh: SavedRefresh::default(),
     }))
 }
@@ -92,6 +92,19 @@ export interface SearchResponse {
   results: SearchResult[];
 }
 
+/// One `GET /v1/complete` suggestion; `reference` is `collection:path`.
+export interface CompletionItem {
+  reference: string;
+  collection: string;
+  path: string;
+  title: string;
+}
+
+export interface CompleteResponse {
+  query: string;
+  suggestions: CompletionItem[];
+}
+
 export interface DocumentResponse {
   doc_id: string;
   collection: string;
@@ -304,4 +317,11 @@ export const api = {
       method: "POST",
       body: JSON.stringify(params),
     }),
+
+  complete: (q: string, params: { limit?: number; collection?: string } = {}) => {
+    const query = new URLSearchParams({ q });
+    if (params.limit !== undefined) query.set("limit", String(params.limit));
+    if (params.collection) query.set("collection", params.collection);
+    return request<CompleteResponse>(`/complete?${query.toString()}`);
+  },
 };
@@ -20,12 +20,13 @@ use docbert_core::{
     chunking,
     embedding,
     incremental::DocumentMetadata,
-    plaid::{self, PlaidBuildParams},
+    plaid,
     preparation::{self, SearchDocument},
     search::{self, SearchParams},
 };
 
 use crate::{
+    cache::{CacheStatus, CrateCache},
     collection::SyntheticCollection,
     error::{Error, Result},
     item::RustItem,
@@ -51,6 +52,20 @@ impl Indexer {
             docbert_data_dir.tantivy_dir().map_err(map_core_err)?;
         std::fs::create_dir_all(&tantivy_path)?;
 
+        // The lexical index is derived from the JSON cache, so one
+        // created before the current schema is dropped and re-lexed
+        // rather than refused.
+        let missing =
+            SearchIndex::missing_fields(&tantivy_path).map_err(map_core_err)?;
+        if !missing.is_empty() {
+            tracing::info!(
+                missing = %missing.join(", "),
+                "lexical index predates the current schema, rebuilding",
+            );
+            std::fs::remove_dir_all(&tantivy_path)?;
+            std::fs::create_dir_all(&tantivy_path)?;
+        }
+
         let config_db = ConfigDb::open(&docbert_data_dir.config_db())
             .map_err(map_core_err)?;
         let search_index =
@@ -59,13 +74,35 @@ impl Indexer {
             .map_err(map_core_err)?;
         let model = ModelManager::new();
 
-        Ok(Self {
+        let indexer = Self {
             data_dir: docbert_data_dir,
             config_db,
             search_index,
             embedding_db,
             model,
-        })
+        };
+        if !missing.is_empty() {
+            indexer.relex_cache(&CrateCache::new(data_dir)?)?;
+        }
+        Ok(indexer)
+    }
+
+    /// Re-run the lexical write for every ready crate in `cache`.
+    /// Embeddings and PLAID are keyed by document id and stay valid.
+    fn relex_cache(&self, cache: &CrateCache) -> Result<usize> {
+        let mut count = 0;
+        for entry in cache.entries()? {
+            let collection = SyntheticCollection {
+                crate_name: entry.crate_name,
+                version: entry.version,
+            };
+            if entry.status != CacheStatus::Ready || !cache.has(&collection) {
+                continue;
+            }
+            count +=
+                self.index_lexical(&collection, &cache.load(&collection)?)?;
+        }
+        Ok(count)
     }
 
     /// Borrow the inner [`ConfigDb`] for direct queries.
@@ -191,14 +228,20 @@ impl Indexer {
     /// startup, expensive on large corpora — call after a sync, not
     /// after every single ingest.
     ///
+    /// The centroid count scales with the corpus, and any parameters
+    /// pinned with `docbert reindex` are honoured.
+    ///
     /// Fail-soft: PLAID is a search *optimization* over the
-    /// embeddings already in `EmbeddingDb`. When the corpus is too
-    /// small for the configured k-means (the common "first crate
-    /// indexed" case yields fewer than 256 tokens), this logs a
-    /// warning and returns Ok — `search::run` falls back to a linear
-    /// MaxSim scan when no PLAID index exists.
+    /// embeddings already in `EmbeddingDb`. When there is nothing to
+    /// train on yet, this logs a warning and returns Ok —
+    /// `search::run` falls back to a linear MaxSim scan when no PLAID
+    /// index exists.
     pub fn rebuild_plaid(&self) -> Result<()> {
-        let params = PlaidBuildParams::default();
+        let total_tokens = plaid::corpus_token_count(&self.embedding_db)
+            .map_err(map_core_err)?;
+        let params = plaid::load_build_overrides(&self.config_db)
+            .map_err(map_core_err)?
+            .resolve(total_tokens);
         match plaid::build_index_from_embedding_db(&self.embedding_db, params) {
             Ok(index) => {
                 plaid::save_index(&index, &self.data_dir)
@@ -348,6 +391,14 @@ mod tests {
             bm25_only: true,
             no_fuzzy: false,
             all: false,
+            passages: 0,
+            explain: false,
+            prf: None,
+            facets: false,
+            offset: 0,
+            cursor: None,
+            autocorrect: false,
+            plaid: Default::default(),
         };
         let results = indexer.search(params).unwrap();
         assert!(!results.is_empty());
@@ -375,6 +426,14 @@ mod tests {
             bm25_only: true,
             no_fuzzy: false,
             all: false,
+            passages: 0,
+            explain: false,
+            prf: None,
+            facets: false,
+            offset: 0,
+            cursor: None,
+            autocorrect: false,
+            plaid: Default::default(),
         };
         let results = indexer.search(params).unwrap();
         assert!(
@@ -401,8 +460,39 @@ mod tests {
             bm25_only: true,
             no_fuzzy: false,
             all: false,
+            passages: 0,
+            explain: false,
+            prf: None,
+            facets: false,
+            offset: 0,
+            cursor: None,
+            autocorrect: false,
+            plaid: Default::default(),
         };
         let results = indexer.search(params).unwrap();
         assert!(results.is_empty());
     }
+
+    #[test]
+    fn open_relexes_the_cache_into_an_outdated_index() {
+        use tantivy::schema::{STRING, Schema};
+
+        let tmp = TempDir::new().unwrap();
+        let coll = collection();
+        CrateCache::new(tmp.path())
+            .unwrap()
+            .store(&coll, &[sample("demo::greet", "say hello")])
+            .unwrap();
+        // An index from before the tags/spell/complete fields.
+        let mut builder = Schema::builder();
+        builder.add_text_field("doc_id", STRING);
+        let dir = tmp.path().join("tantivy");
+        std::fs::create_dir_all(&dir).unwrap();
+        tantivy::Index::create_in_dir(&dir, builder.build()).unwrap();
+
+        let indexer = Indexer::open(tmp.path()).unwrap();
+        let results = indexer.search_index.search("hello", 10).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].collection, coll.to_string());
+    }
 }
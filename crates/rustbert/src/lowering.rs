@@ -38,6 +38,7 @@ pub fn lower(
         raw_content: None,
         metadata: Some(build_metadata(item)),
         mtime: 0,
+        tags: Vec::new(),
     }
 }
 
@@ -349,6 +349,14 @@ async fn cmd_search(
         bm25_only: false,
         no_fuzzy: false,
         all: false,
+        passages: 0,
+        explain: false,
+        prf: None,
+        facets: false,
+        offset: 0,
+        cursor: None,
+        autocorrect: false,
+        plaid: Default::default(),
     };
     let results = indexer.search(params)?;
     let items = cache.load(&coll)?;
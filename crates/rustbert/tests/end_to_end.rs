@@ -160,6 +160,14 @@ async fn fetch_parse_index_and_search_roundtrip() {
         bm25_only: false,
         no_fuzzy: false,
         all: false,
+        passages: 0,
+        explain: false,
+        prf: None,
+        facets: false,
+        offset: 0,
+        cursor: None,
+        autocorrect: false,
+        plaid: Default::default(),
     };
     let hits = indexer.search(params).unwrap();
     assert!(